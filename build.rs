@@ -0,0 +1,19 @@
+//! writes the C header for the ffi module to bfint.h in OUT_DIR when the ffi feature is on, using
+//! the settings in cbindgen.toml. tests/ffi.rs checks include/bfint.h is the same as it
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("cargo sets this");
+        let out_dir = std::env::var("OUT_DIR").expect("cargo sets this");
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+            .expect("cbindgen.toml is readable");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{}/src/ffi.rs", crate_dir))
+            .generate()
+            .expect("the ffi module can be turned into a header")
+            .write_to_file(format!("{}/bfint.h", out_dir));
+    }
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
@@ -0,0 +1,37 @@
+//! runs a generated program of a few megabytes, to see how much memory its instructions take
+//! packed into ops next to how much they'd take as Instructions with a table of jumps, and how
+//! fast it runs. "cargo run --release --example big_program"
+use bfint::optimize::Level;
+use bfint::program::Op;
+use bfint::{lang::Lang, Instruction, Program, TuringMachine};
+use std::sync::Arc;
+use std::time::Instant;
+
+fn main() {
+    //a small loop, two hundred thousand times over. -O0 keeps every instruction there is
+    let source = "++++++++[>++++<-]>[-]<".repeat(200_000);
+    let program = Arc::new(
+        Program::compile(&source, &Lang::Brainfuck, Level::O0).expect("the program parses"),
+    );
+    let unpacked = program.len() * (size_of::<Instruction>() + size_of::<usize>());
+    println!(
+        "{} bytes of source, {} instructions",
+        source.len(),
+        program.len()
+    );
+    println!(
+        "{} bytes as ops of {} bytes, {} bytes as instructions and jumps",
+        program.code_size(),
+        size_of::<Op>(),
+        unpacked
+    );
+    for _ in 0..3 {
+        let mut tm = TuringMachine::with_program(Arc::clone(&program));
+        let started = Instant::now();
+        if let Err(e) = tm.run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        println!("{} steps in {:?}", tm.steps, started.elapsed());
+    }
+}
@@ -0,0 +1,45 @@
+//! times how long it takes to get a machine ready to run, when the program is parsed for every
+//! run and when it's compiled once and shared, then runs the shared one over a few inputs.
+//! "cargo run --release --example compile_once"
+use bfint::optimize::Level;
+use bfint::{lang::Lang, Program, TuringMachine};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const RUNS: u32 = 10_000;
+
+fn main() {
+    //writes its input back with every byte one higher, after thousands of instructions that
+    //cancel out, so there's something to parse and optimize
+    let program = format!("{},[+.,]", "><+-".repeat(2_000));
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        TuringMachine::new(&program, &Lang::Brainfuck).expect("the program parses");
+    }
+    let parsing = started.elapsed() / RUNS;
+    let compiled = Arc::new(
+        Program::compile(&program, &Lang::Brainfuck, Level::O2).expect("the program parses"),
+    );
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        TuringMachine::with_program(Arc::clone(&compiled));
+    }
+    let sharing = started.elapsed() / RUNS;
+    println!("getting a machine ready, parsing every time: {:?}", parsing);
+    println!("getting a machine ready, compiled once: {:?}", sharing);
+    for input in ["HAL", "IBM"] {
+        let mut tm = TuringMachine::with_program(Arc::clone(&compiled));
+        let output = Arc::new(Mutex::new(vec![]));
+        tm.input = Arc::new(Mutex::new(std::io::Cursor::new(input.as_bytes().to_vec())));
+        tm.output = output.clone();
+        if let Err(e) = tm.run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        println!(
+            "{} -> {}",
+            input,
+            String::from_utf8_lossy(&bfint::shared::lock(&output))
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! looks at hello world as it runs: its first ten instructions, where the pointer is every
+//! hundredth step, and the bytes it wrote. "cargo run --example observe_steps"
+use bfint::observe::Observation;
+use bfint::shared::shared;
+use bfint::{lang::Lang, TuringMachine};
+
+const HELLO: &str =
+    "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.\
+    +++.------.--------.>>+.>++.";
+
+///a machine running hello world, with its output kept out of the way of what's printed here
+fn machine() -> TuringMachine {
+    let mut tm = TuringMachine::new(HELLO, &Lang::Brainfuck).expect("the program parses");
+    tm.output = shared(vec![]);
+    tm
+}
+
+fn main() {
+    for observation in machine().iter_steps().take(10) {
+        println!("{:?}", observation);
+    }
+    for observation in machine().iter_steps().step_by(100) {
+        if let Observation::Step(step) = observation {
+            println!("step {}: the pointer is at {}", step.step, step.pointer);
+        }
+    }
+    let written: Vec<u8> = machine()
+        .iter_steps()
+        .filter_map(|observation| match observation {
+            Observation::Step(step) => step.output,
+            Observation::Finished(outcome) => {
+                println!("finished: {:?}", outcome);
+                None
+            }
+        })
+        .collect();
+    print!("{}", String::from_utf8_lossy(&written));
+}
@@ -0,0 +1,86 @@
+//! watches hello world run with a few observers, each doing what one of the interpreter's own
+//! features does: a trace of every step, counts of how often every kind of instruction ran, and
+//! a report every so many steps. "cargo run --example observers"
+use bfint::control::RunOutcome;
+use bfint::observe::{Observer, StepCtx};
+use bfint::shared::shared;
+use bfint::{lang::Lang, RuntimeError, TuringMachine};
+use std::collections::BTreeMap;
+
+const HELLO: &str =
+    "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.\
+    +++.------.--------.>>+.>++.";
+
+///prints the first few steps, like -vv does
+struct Trace {
+    left: usize,
+}
+
+impl Observer for Trace {
+    fn before_step(&mut self, ctx: &StepCtx) {
+        if self.left > 0 {
+            self.left -= 1;
+            println!(
+                "step {}: {:?} at {}, pointer {}",
+                ctx.steps(),
+                ctx.op(),
+                ctx.pc(),
+                ctx.pointer()
+            );
+        }
+    }
+}
+
+///counts how often every kind of instruction ran, and prints them at the end
+#[derive(Default)]
+struct Profile {
+    counts: BTreeMap<String, u64>,
+}
+
+impl Observer for Profile {
+    fn after_step(&mut self, ctx: &StepCtx) {
+        *self.counts.entry(format!("{:?}", ctx.op())).or_default() += 1;
+    }
+    fn on_halt(&mut self, outcome: Result<RunOutcome, &RuntimeError>) {
+        println!("{:?}, after running", outcome);
+        for (instruction, count) in &self.counts {
+            println!("{:>6} {}", count, instruction);
+        }
+    }
+}
+
+///says how far the run has got every so many steps, like --progress does
+struct Progress {
+    every: u64,
+}
+
+impl Observer for Progress {
+    fn after_step(&mut self, ctx: &StepCtx) {
+        if ctx.steps().is_multiple_of(self.every) {
+            println!(
+                "{} steps, the cell at {} holds {:?}",
+                ctx.steps(),
+                ctx.pointer(),
+                ctx.cell(ctx.pointer())
+            );
+        }
+    }
+}
+
+fn main() {
+    let observers: Vec<Box<dyn Observer + Send>> = vec![
+        Box::new(Trace { left: 5 }),
+        Box::new(Profile::default()),
+        Box::new(Progress { every: 250 }),
+    ];
+    for observer in observers {
+        let mut tm = TuringMachine::new(HELLO, &Lang::Brainfuck).expect("the program parses");
+        tm.output = shared(vec![]);
+        tm.set_observer(observer);
+        if let Err(e) = tm.run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        println!();
+    }
+}
@@ -0,0 +1,22 @@
+//! adds a "~" instruction that prints the current cell as a decimal number, so
+//! "++++++++++[>++++<-]>++~" prints 42
+use bfint::custom::CustomInstructions;
+use bfint::{lang::Lang, TuringMachine};
+
+fn main() {
+    let mut custom = CustomInstructions::new();
+    custom.custom_instruction('~', |ctx| {
+        for byte in ctx.cell().to_string().bytes() {
+            ctx.output(byte)?;
+        }
+        Ok(())
+    });
+    let program = "++++++++++[>++++<-]>++~";
+    let mut tm = TuringMachine::with_custom_instructions(program, &Lang::Brainfuck, custom)
+        .expect("the program parses");
+    if let Err(e) = tm.run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    println!();
+}
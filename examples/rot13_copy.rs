@@ -0,0 +1,23 @@
+//! puts a file through rot13.bf with io::copy, the way any other filter in a chain of readers
+//! and writers would be, and checks it came out the way the fixture says it should.
+//! "cargo run --example rot13_copy"
+use bfint::transform::BfTransform;
+use bfint::{lang::Lang, TuringMachine};
+use std::fs::File;
+use std::io::{self, Read};
+
+fn main() -> io::Result<()> {
+    let source = std::fs::read_to_string("tests/fixtures/rot13.bf")?;
+    let machine = TuringMachine::new(&source, &Lang::Brainfuck).expect("the program parses");
+    let mut rot13 = BfTransform::new(machine);
+    io::copy(&mut File::open("tests/fixtures/rot13.in")?, &mut rot13)?;
+    let mut output = vec![];
+    rot13.read_to_end(&mut output)?;
+    let expected = std::fs::read("tests/fixtures/rot13.out")?;
+    print!("{}", String::from_utf8_lossy(&output));
+    if output != expected {
+        eprintln!("that isn't what tests/fixtures/rot13.out says it should be");
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! runs programs with whatever settings the input asks for. everything before the first NUL is
+//! settings, one "name value" per line the way InterpreterConfig::set takes them, and the rest
+//! is split at the first "!" into the program and its input like in execute. a setting that
+//! doesn't make sense has to be an error, and so does anything the program does with the ones
+//! that do, like running out of steps straight away with "max-steps 0"
+#![no_main]
+use bfint::config::InterpreterConfig;
+use bfint::lang::Lang;
+use bfint::TuringMachine;
+use libfuzzer_sys::fuzz_target;
+use std::sync::{Arc, Mutex};
+
+const STEP_LIMIT: u64 = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let (settings, rest) = match data.iter().position(|&byte| byte == 0) {
+        Some(split) => (&data[..split], &data[split + 1..]),
+        None => (&[][..], data),
+    };
+    let mut config = InterpreterConfig::default();
+    for line in String::from_utf8_lossy(settings).lines() {
+        let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+        let _ = config.set(name, value);
+    }
+    //brainfork is left out because it starts threads, and bf-file because it writes files
+    if matches!(config.lang, Some(Lang::Brainfork) | Some(Lang::BfFile)) {
+        return;
+    }
+    let limit = config.step_limit.unwrap_or(STEP_LIMIT).min(STEP_LIMIT);
+    config.step_limit = Some(limit);
+    let (program, input) = match rest.iter().position(|&byte| byte == b'!') {
+        Some(split) => (&rest[..split], &rest[split + 1..]),
+        None => (rest, &[][..]),
+    };
+    let source = String::from_utf8_lossy(program);
+    let Ok(mut tm) = TuringMachine::with_config(&source, &config) else {
+        return;
+    };
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(input.to_vec())));
+    tm.output = Arc::new(Mutex::new(std::io::sink()));
+    let _ = tm.run();
+    assert!(tm.steps <= limit, "ran {} steps", tm.steps);
+    //what's left of the machine can still be looked at, and saved and read back
+    let _ = tm.describe_state();
+    let _ = tm.show_cells(0..=usize::MAX);
+    let checkpoint = tm.checkpoint(&source);
+    let _ = tm.resume(&source, &checkpoint);
+});
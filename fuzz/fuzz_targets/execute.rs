@@ -0,0 +1,43 @@
+//! runs whatever programs parse, with a hard limit on the number of steps and the output thrown
+//! away. the first byte picks the language, the second what happens at the end of the input, and
+//! the rest is split at the first "!" into the program and its input
+#![no_main]
+use bfint::lang::Lang;
+use bfint::{EofBehavior, TuringMachine};
+use libfuzzer_sys::fuzz_target;
+use std::sync::{Arc, Mutex};
+
+const STEP_LIMIT: u64 = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let [language, eof, rest @ ..] = data else {
+        return;
+    };
+    //brainfork is left out because it starts threads, and bf-file because it writes files
+    let lang = match language % 6 {
+        0 => Lang::Brainfuck,
+        1 => Lang::Pbrain,
+        2 => Lang::Ebf1,
+        3 => Lang::Boolfuck,
+        4 => Lang::BfEval,
+        _ => Lang::BfRand,
+    };
+    let (program, input) = match rest.iter().position(|&byte| byte == b'!') {
+        Some(split) => (&rest[..split], &rest[split + 1..]),
+        None => (rest, &[][..]),
+    };
+    let Ok(mut tm) = TuringMachine::new(&String::from_utf8_lossy(program), &lang) else {
+        return;
+    };
+    tm.eof_behavior = match eof % 3 {
+        0 => EofBehavior::Zero,
+        1 => EofBehavior::Max,
+        _ => EofBehavior::Unchanged,
+    };
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(input.to_vec())));
+    tm.output = Arc::new(Mutex::new(std::io::sink()));
+    tm.step_limit = Some(STEP_LIMIT);
+    //running into the step limit or off the tape is fine, as long as it's an error and not a panic
+    let _ = tm.run();
+    assert!(tm.steps <= STEP_LIMIT, "ran {} steps", tm.steps);
+});
@@ -0,0 +1,23 @@
+//! reads the input as every kind of text bfint reads besides programs: checkpoints, core dumps,
+//! conditions, specs, transcripts, the configs of crosscheck, profiles for listings and
+//! baselines. whatever it is, reading it has to either work or be an error
+#![no_main]
+use bfint::lang::Lang;
+use bfint::TuringMachine;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let _ = bfint::checkpoint::CoreDump::parse(&text);
+    let _ = bfint::condition::Condition::parse(&text);
+    let _ = bfint::spec::Spec::from_toml(&text);
+    let _ = bfint::spec::Spec::from_json(&text);
+    let _ = bfint::transcript::Transcript::from_json(&text);
+    let _ = bfint::crosscheck::Configs::parse(&text);
+    let _ = bfint::listing::Profile::parse(&text);
+    let _ = bfint::fixtures::Baselines::parse(&text);
+    let _ = bfint::provenance::Provenance::find(&text);
+    if let Ok(mut tm) = TuringMachine::new("+[>,.<-]", &Lang::Brainfuck) {
+        let _ = tm.resume("+[>,.<-]", &text);
+    }
+});
@@ -0,0 +1,25 @@
+//! feeds whatever bytes the fuzzer comes up with to the parser. the first byte picks the
+//! language, the rest is the program. a broken program should only ever give back a ParseError
+#![no_main]
+use bfint::lang::Lang;
+use bfint::TuringMachine;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&choice, program)) = data.split_first() else {
+        return;
+    };
+    //bf-file is left out, the fuzzer shouldn't get to write files
+    let lang = match choice % 9 {
+        0 => Lang::Brainfuck,
+        1 => Lang::Ook,
+        2 => Lang::Spoon,
+        3 => Lang::Pbrain,
+        4 => Lang::Ebf1,
+        5 => Lang::Brainfork,
+        6 => Lang::Boolfuck,
+        7 => Lang::BfEval,
+        _ => Lang::BfRand,
+    };
+    let _ = TuringMachine::new(&String::from_utf8_lossy(program), &lang);
+});
@@ -0,0 +1,132 @@
+#ifndef BFINT_H
+#define BFINT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ *what went wrong in a call, `Ok` when nothing did
+ */
+typedef enum BfError {
+  BF_ERROR_OK = 0,
+  /**
+   *a pointer that may not be null was null
+   */
+  BF_ERROR_NULL_POINTER,
+  /**
+   *the program text isn't valid UTF-8. programs are read whatever their bytes are now, see
+   *bf_machine_new, so this never happens, it's kept so the codes after it stay the same
+   */
+  BF_ERROR_INVALID_UTF8,
+  /**
+   *the program couldn't be parsed
+   */
+  BF_ERROR_PARSE_ERROR,
+  /**
+   *the program failed while running
+   */
+  BF_ERROR_RUNTIME_ERROR,
+  /**
+   *a cell index past the end of the tape
+   */
+  BF_ERROR_OUT_OF_RANGE,
+  /**
+   *something inside the interpreter panicked
+   */
+  BF_ERROR_PANIC,
+} BfError;
+
+/**
+ *a machine with its input and output kept in memory, since C can't hand over a reader or writer
+ */
+typedef struct BfMachine BfMachine;
+
+/**
+ *parses a NUL-terminated brainfuck program and makes a machine for it, with no input. gives back
+ *null if the program can't be parsed. text that isn't UTF-8 is read as latin-1, the way files
+ *are
+ *
+ * # Safety
+ *
+ *"program" has to be null or point to a NUL-terminated string, and "err_out" has to be null or
+ *point to writable memory
+ */
+struct BfMachine *bf_machine_new(const char *program, enum BfError *err_out);
+
+/**
+ *runs the program until it ends or fails
+ *
+ * # Safety
+ *
+ *"machine" has to come from bf_machine_new and not have been freed, and "err_out" has to be null
+ *or point to writable memory
+ */
+void bf_machine_run(struct BfMachine *machine, enum BfError *err_out);
+
+/**
+ *runs a single instruction. gives back 1 if the program has instructions left after it, and 0
+ *once it has ended or failed
+ *
+ * # Safety
+ *
+ *"machine" has to come from bf_machine_new and not have been freed, and "err_out" has to be null
+ *or point to writable memory
+ */
+int32_t bf_machine_step(struct BfMachine *machine, enum BfError *err_out);
+
+/**
+ *reads the cell at "index" on the tape
+ *
+ * # Safety
+ *
+ *"machine" has to come from bf_machine_new and not have been freed, and "err_out" has to be null
+ *or point to writable memory
+ */
+uint8_t bf_machine_read_cell(struct BfMachine *machine, uintptr_t index, enum BfError *err_out);
+
+/**
+ *gives the program "length" bytes of input starting at "data", replacing whatever input it
+ *hadn't read yet
+ *
+ * # Safety
+ *
+ *"machine" has to come from bf_machine_new and not have been freed, "data" has to point to at
+ *least "length" readable bytes, and "err_out" has to be null or point to writable memory
+ */
+void bf_machine_set_input(struct BfMachine *machine,
+                          const uint8_t *data,
+                          uintptr_t length,
+                          enum BfError *err_out);
+
+/**
+ *copies up to "length" bytes of the output the program has written into "buffer", and forgets
+ *about them. gives back how many bytes were copied, any that didn't fit are left for next time
+ *
+ * # Safety
+ *
+ *"machine" has to come from bf_machine_new and not have been freed, "buffer" has to point to at
+ *least "length" writable bytes, and "err_out" has to be null or point to writable memory
+ */
+uintptr_t bf_machine_take_output(struct BfMachine *machine,
+                                 uint8_t *buffer,
+                                 uintptr_t length,
+                                 enum BfError *err_out);
+
+/**
+ *frees a machine made by bf_machine_new. freeing null does nothing
+ *
+ * # Safety
+ *
+ *"machine" has to be null or come from bf_machine_new and not have been freed already
+ */
+void bf_machine_free(struct BfMachine *machine);
+
+/**
+ *the message that goes with the last error on this thread. the string belongs to the library
+ *and stays valid until the next call that fails
+ */
+const char *bf_last_error_message(void);
+
+#endif  /* BFINT_H */
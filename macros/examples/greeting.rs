@@ -0,0 +1,12 @@
+//! prints a greeting that was worked out while the example compiled, by running a brainfuck
+//! program with bf!. "cargo run -p bfint-macros --example greeting"
+use bfint_macros::bf;
+
+const GREETING: &[u8] = bf!(
+    "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.
+     --------.>>+.>++."
+);
+
+fn main() {
+    print!("{}", String::from_utf8_lossy(GREETING));
+}
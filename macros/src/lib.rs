@@ -0,0 +1,78 @@
+//! bf!, which runs a brainfuck program while the crate using it compiles and gives its output as
+//! a byte string, so `const GREETING: &[u8] = bf!("++++++++[>++++++++<-]>+.");` is `b"A"`.
+//!
+//! the program runs on bfint's interpreter with the usual tape, for at most STEP_LIMIT steps,
+//! so a program that never halts is an error and not a build that never ends. there's no input
+//! at compile time, so a program with a "," is an error too. every error points at the string
+//! the program is in, and says where in the program it is
+use bfint::program::{self, ParseOptions};
+use bfint::{lang::Lang, Instruction, TuringMachine};
+use proc_macro::TokenStream;
+use std::sync::{Arc, Mutex};
+use syn::{parse_macro_input, LitStr};
+
+///the most steps a program gets before it's taken to never halt
+const STEP_LIMIT: u64 = 10_000_000;
+
+///runs the brainfuck program in the string literal and expands to what it wrote, as a byte
+///string literal
+#[proc_macro]
+pub fn bf(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    match run(&literal.value()) {
+        Ok(output) => {
+            let output = proc_macro2::Literal::byte_string(&output);
+            quote::quote_spanned!(literal.span()=> #output).into()
+        }
+        Err(problems) => {
+            let mut problems = problems
+                .into_iter()
+                .map(|problem| syn::Error::new(literal.span(), problem));
+            let mut error = problems.next().expect("a failed run has a problem");
+            for problem in problems {
+                error.combine(problem);
+            }
+            //a block, so more than one compile_error! still fits where an expression goes
+            let errors = error.to_compile_error();
+            quote::quote!({ #errors }).into()
+        }
+    }
+}
+
+///the output of the program, or everything that kept it from running to the end
+fn run(source: &str) -> Result<Vec<u8>, Vec<String>> {
+    let options = ParseOptions {
+        lang: Lang::Brainfuck,
+        ..ParseOptions::default()
+    };
+    let program = program::parse(source, &options).map_err(|errors| {
+        errors
+            .iter()
+            .map(|e| format!("the program doesn't parse: {}", e))
+            .collect::<Vec<_>>()
+    })?;
+    let mut tm = TuringMachine::with_program(Arc::new(program));
+    let reads = tm.find(|instruction| instruction == Instruction::Replace);
+    if !reads.is_empty() {
+        return Err(reads
+            .iter()
+            .map(|position| {
+                format!(
+                    "the \",\" at {} reads input, which there isn't at compile time",
+                    position
+                )
+            })
+            .collect());
+    }
+    tm.step_limit = Some(STEP_LIMIT);
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    match tm.run() {
+        Ok(_) => Ok(std::mem::take(&mut *bfint::shared::lock(&output))),
+        Err(bfint::RuntimeError::StepLimitReached(steps)) => Err(vec![format!(
+            "the program didn't halt in {} steps, the most bf! runs a program for",
+            steps
+        )]),
+        Err(e) => Err(vec![format!("the program failed: {}", e)]),
+    }
+}
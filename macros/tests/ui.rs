@@ -0,0 +1,7 @@
+//! bf! on programs that can't be run at compile time, each of which has to fail to compile with
+//! the error in the .stderr file next to it in tests/ui. "TRYBUILD=overwrite cargo test -p
+//! bfint-macros" writes them again after the messages change on purpose
+#[test]
+fn errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
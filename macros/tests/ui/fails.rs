@@ -0,0 +1,7 @@
+use bfint_macros::bf;
+
+const OUTPUT: &[u8] = bf!("+.<");
+
+fn main() {
+    print!("{:?}", OUTPUT);
+}
@@ -0,0 +1,7 @@
+use bfint_macros::bf;
+
+const OUTPUT: &[u8] = bf!(42);
+
+fn main() {
+    print!("{:?}", OUTPUT);
+}
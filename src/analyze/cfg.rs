@@ -0,0 +1,126 @@
+//! the control flow graph of a program, written out in graphviz's dot language. the program is
+//! cut into blocks of instructions that always run one after the other, and every "[" and "]"
+//! gets a block of its own since those are where the program can go two ways
+use crate::{Instruction, Position};
+use std::fmt::{self, Write};
+
+///gives back the graph of a program that parses, as dot. the same program always gives the
+///same text, so it can be compared from one version to the next
+pub fn to_dot(source: &str, tokens: &[(Instruction, Position)]) -> String {
+    let matches = matching_brackets(tokens);
+    //the instructions that start a block
+    let mut leaders = vec![0];
+    for (index, (instruction, _)) in tokens.iter().enumerate() {
+        if matches!(
+            instruction,
+            Instruction::JumpToClose | Instruction::JumpToOpen
+        ) {
+            leaders.push(index);
+        }
+        if matches!(
+            instruction,
+            Instruction::JumpToClose | Instruction::JumpToOpen | Instruction::Halt
+        ) {
+            leaders.push(index + 1);
+        }
+    }
+    leaders.retain(|leader| *leader < tokens.len());
+    leaders.sort();
+    leaders.dedup();
+    let node = |index: usize| match index < tokens.len() {
+        true => format!("n{}", index),
+        false => "end".to_string(),
+    };
+    let lines: Vec<Vec<char>> = source.lines().map(|line| line.chars().collect()).collect();
+    let mut out = String::new();
+    let write = |out: &mut String| -> fmt::Result {
+        writeln!(out, "digraph program {{")?;
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+        writeln!(out, "    start [shape=circle];")?;
+        writeln!(out, "    end [shape=doublecircle];")?;
+        writeln!(out, "    start -> {};", node(0))?;
+        for (block, start) in leaders.iter().enumerate() {
+            let end = leaders.get(block + 1).copied().unwrap_or(tokens.len());
+            let range = match end - start {
+                1 => start.to_string(),
+                _ => format!("{}-{}", start, end - 1),
+            };
+            let label = format!("{}: {}", range, excerpt(&lines, &tokens[*start..end]));
+            let last = end - 1;
+            match tokens[last].0 {
+                Instruction::JumpToClose => {
+                    let close = matches[last];
+                    writeln!(out, "    n{} [shape=diamond, label=\"{}\"];", start, label)?;
+                    writeln!(out, "    n{} -> {} [label=\"nonzero\"];", start, node(end))?;
+                    writeln!(
+                        out,
+                        "    n{} -> {} [label=\"zero\"];",
+                        start,
+                        node(close + 1)
+                    )?;
+                }
+                Instruction::JumpToOpen => {
+                    let open = matches[last];
+                    //the edge back to the start of the loop body is what makes the loop, so it's
+                    //drawn so it stands out
+                    writeln!(out, "    n{} [shape=diamond, label=\"{}\"];", start, label)?;
+                    writeln!(
+                        out,
+                        "    n{} -> {} [label=\"nonzero\", style=bold, color=blue];",
+                        start,
+                        node(open + 1)
+                    )?;
+                    writeln!(out, "    n{} -> {} [label=\"zero\"];", start, node(end))?;
+                }
+                Instruction::Halt => {
+                    writeln!(out, "    n{} [label=\"{}\"];", start, label)?;
+                    writeln!(out, "    n{} -> end;", start)?;
+                }
+                _ => {
+                    writeln!(out, "    n{} [label=\"{}\"];", start, label)?;
+                    writeln!(out, "    n{} -> {};", start, node(end))?;
+                }
+            }
+        }
+        writeln!(out, "}}")
+    };
+    write(&mut out).expect("writing to a String can't fail");
+    out
+}
+
+///for every "[" the index of its "]" and the other way around, and 0 for everything else
+pub(crate) fn matching_brackets(tokens: &[(Instruction, Position)]) -> Vec<usize> {
+    let mut matches = vec![0; tokens.len()];
+    let mut open = vec![];
+    for (index, (instruction, _)) in tokens.iter().enumerate() {
+        match instruction {
+            Instruction::JumpToClose => open.push(index),
+            Instruction::JumpToOpen => {
+                if let Some(start) = open.pop() {
+                    matches[start] = index;
+                    matches[index] = start;
+                }
+            }
+            _ => {}
+        }
+    }
+    matches
+}
+
+///the source characters of the instructions, shortened when there are a lot of them, and made
+///safe to put between quotes in dot
+fn excerpt(lines: &[Vec<char>], tokens: &[(Instruction, Position)]) -> String {
+    let chars: String = tokens
+        .iter()
+        .filter_map(|(_, position)| {
+            lines
+                .get(position.line - 1)
+                .and_then(|line| line.get(position.column - 1))
+        })
+        .collect();
+    let mut excerpt: String = chars.chars().take(24).collect();
+    if chars.chars().count() > 24 {
+        excerpt.push_str("...");
+    }
+    excerpt.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,150 @@
+//! numbers about the shape of a program, for comparing programs and spotting ones that nest
+//! very deep before running them
+use crate::{Instruction, Position};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{self, Write};
+
+///the numbers for a single program
+#[derive(PartialEq, Debug, Clone)]
+pub struct Metrics {
+    ///how many instructions the program has
+    pub length: usize,
+    ///how many characters the source has
+    pub source_length: usize,
+    ///how many times every kind of instruction is used, by name
+    pub counts: BTreeMap<String, usize>,
+    ///the longest run of the same instruction one after the other, for every kind, by name
+    pub longest_runs: BTreeMap<String, usize>,
+    ///how many loops the program has
+    pub loops: usize,
+    ///how deep the deepest loop is nested, 0 when there are no loops
+    pub max_depth: usize,
+    ///how many instructions a loop has between its brackets on average, counting the ones in
+    ///loops inside it
+    pub average_loop_body: f64,
+    ///how many characters of comment there are for every instruction, not counting whitespace
+    pub comment_ratio: f64,
+}
+
+impl Metrics {
+    ///works out the numbers for a program that parses
+    pub fn new(source: &str, tokens: &[(Instruction, Position)]) -> Self {
+        let mut counts = BTreeMap::new();
+        let mut longest_runs = BTreeMap::new();
+        let mut run = 0;
+        let mut open = vec![];
+        let mut loops = 0;
+        let mut max_depth = 0;
+        let mut loop_bodies = 0;
+        for (index, (instruction, _)) in tokens.iter().enumerate() {
+            let name = name(instruction);
+            *counts.entry(name.clone()).or_insert(0) += 1;
+            run = match index > 0 && tokens[index - 1].0 == *instruction {
+                true => run + 1,
+                false => 1,
+            };
+            let longest = longest_runs.entry(name).or_insert(0);
+            *longest = run.max(*longest);
+            match instruction {
+                Instruction::JumpToClose => {
+                    open.push(index);
+                    max_depth = max_depth.max(open.len());
+                }
+                Instruction::JumpToOpen => {
+                    if let Some(start) = open.pop() {
+                        loops += 1;
+                        loop_bodies += index - start - 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        //the characters the instructions were written with, everything else that isn't
+        //whitespace is comment
+        let code: HashSet<(usize, usize)> = tokens
+            .iter()
+            .map(|(_, position)| (position.line, position.column))
+            .collect();
+        let mut comment = 0;
+        for (line_index, line) in source.lines().enumerate() {
+            for (column_index, c) in line.chars().enumerate() {
+                if !c.is_whitespace() && !code.contains(&(line_index + 1, column_index + 1)) {
+                    comment += 1;
+                }
+            }
+        }
+        Metrics {
+            length: tokens.len(),
+            source_length: source.chars().count(),
+            counts,
+            longest_runs,
+            loops,
+            max_depth,
+            average_loop_body: match loops {
+                0 => 0.0,
+                _ => loop_bodies as f64 / loops as f64,
+            },
+            comment_ratio: match tokens.len() {
+                0 => 0.0,
+                length => comment as f64 / length as f64,
+            },
+        }
+    }
+    ///the numbers as a table for people to read
+    pub fn table(&self) -> String {
+        let mut out = String::new();
+        let write = |out: &mut String| -> fmt::Result {
+            writeln!(out, "instructions       {}", self.length)?;
+            writeln!(out, "source length      {}", self.source_length)?;
+            writeln!(out, "loops              {}", self.loops)?;
+            writeln!(out, "max nesting        {}", self.max_depth)?;
+            writeln!(out, "average loop body  {:.2}", self.average_loop_body)?;
+            writeln!(out, "comment ratio      {:.2}", self.comment_ratio)?;
+            writeln!(out)?;
+            writeln!(
+                out,
+                "{:<18} {:>8} {:>12}",
+                "instruction", "count", "longest run"
+            )?;
+            for (name, count) in &self.counts {
+                writeln!(
+                    out,
+                    "{:<18} {:>8} {:>12}",
+                    name, count, self.longest_runs[name]
+                )?;
+            }
+            Ok(())
+        };
+        write(&mut out).expect("writing to a String can't fail");
+        out
+    }
+    ///the numbers as json. the keys stay the same from one version to the next, and the
+    ///instruction names are always in alphabetical order
+    pub fn json(&self) -> String {
+        let map = |map: &BTreeMap<String, usize>| {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(name, value)| format!("\"{}\": {}", name, value))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        };
+        format!(
+            "{{\"length\": {}, \"source_length\": {}, \"loops\": {}, \"max_depth\": {}, \
+             \"average_loop_body\": {:.2}, \"comment_ratio\": {:.2}, \"counts\": {}, \
+             \"longest_runs\": {}}}",
+            self.length,
+            self.source_length,
+            self.loops,
+            self.max_depth,
+            self.average_loop_body,
+            self.comment_ratio,
+            map(&self.counts),
+            map(&self.longest_runs)
+        )
+    }
+}
+
+///the name an instruction is listed under, like "Increment"
+fn name(instruction: &Instruction) -> String {
+    format!("{:?}", instruction)
+}
@@ -0,0 +1,4 @@
+//! looking at the shape of a program without running it
+pub mod cfg;
+pub mod metrics;
+pub mod writes;
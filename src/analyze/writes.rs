@@ -0,0 +1,111 @@
+//! finding cells that are written and then written over before anything reads them, like the
+//! leftover copy a copy loop makes that's only ever cleared again. that's work thrown away, and
+//! usually a sign the program doesn't do what its author thinks.
+//!
+//! this is a guess, not a proof. the program is split into stretches the analysis can follow:
+//! moves, "+", "-", "," and "." and loops like "[->+<]" that come back to where they started,
+//! which are summed up the way the optimizer does (see optimize::AddLoop). cells are told apart
+//! by how far they are from where the pointer was when the stretch started. any other loop, like
+//! one that moves the pointer or reads or writes, is looked at as a stretch of its own, and counts
+//! as reading every cell for the stretch around it. so does anything a language adds to
+//! brainfuck. what's left written at the end of a loop's body or of the program isn't counted,
+//! since the loop going around again or a dump of the tape might look at it
+use crate::optimize::AddLoop;
+use crate::{Instruction, Position};
+use std::collections::BTreeMap;
+use std::fmt;
+
+///a write nothing reads before it's written over
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct DeadWrite {
+    ///the instruction that wrote the value first, when more "+" and "-" or loops added to it
+    ///after
+    pub position: Position,
+    ///which cell it is, by how far it is from where the pointer was at start
+    pub offset: isize,
+    ///where the stretch the offset counts from starts
+    pub start: Position,
+    ///the instruction that wrote over the value
+    pub overwritten_at: Position,
+}
+
+impl fmt::Display for DeadWrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: probably never read, the cell {:+} from where the pointer was at {} is written \
+             over at {} first",
+            self.position, self.offset, self.start, self.overwritten_at
+        )
+    }
+}
+
+///looks through the tokens of a program that parses, and gives back the writes it thinks are
+///never read, in the order they appear in the program
+pub fn dead_writes(tokens: &[(Instruction, Position)]) -> Vec<DeadWrite> {
+    let brackets = super::cfg::matching_brackets(tokens);
+    let mut found = vec![];
+    //the stretches are gone through one after the other: a loop that can't be summed up ends
+    //the stretch around it, its body is a stretch of its own, and its "]" ends that one. so
+    //there's nothing to come back to however deep the loops go. this is the stretch being gone
+    //through, every cell that was written and not read yet, with what wrote it first
+    let mut written: BTreeMap<isize, Position> = BTreeMap::new();
+    let mut offset = 0;
+    let mut start = None;
+    let mut index = 0;
+    while index < tokens.len() {
+        let (instruction, position) = tokens[index];
+        let origin = *start.get_or_insert(position);
+        let mut overwrite = |written: &mut BTreeMap<isize, Position>, offset| {
+            if let Some(first) = written.remove(&offset) {
+                found.push(DeadWrite {
+                    position: first,
+                    offset,
+                    start: origin,
+                    overwritten_at: position,
+                });
+            }
+        };
+        match instruction {
+            Instruction::MoveRight => offset += 1,
+            Instruction::MoveLeft => offset -= 1,
+            Instruction::Increment | Instruction::Decrement => {
+                written.entry(offset).or_insert(position);
+            }
+            Instruction::Output => {
+                written.remove(&offset);
+            }
+            //what "," reads isn't counted as a write, throwing input away is how it's skipped
+            Instruction::Replace => overwrite(&mut written, offset),
+            Instruction::JumpToClose => {
+                let end = brackets[index];
+                let body = tokens[index + 1..end].iter().map(|(i, _)| *i);
+                match AddLoop::new(body) {
+                    //"[-]" only ever sets the cell to 0, whatever was in it
+                    Some(add) if add.adds.is_empty() => overwrite(&mut written, offset),
+                    //the cell says how many times the others are added to. the 0 the loop leaves
+                    //behind isn't counted as a write either
+                    Some(add) => {
+                        written.remove(&offset);
+                        for (distance, _) in add.adds {
+                            written.entry(offset + distance).or_insert(position);
+                        }
+                    }
+                    //the body is gone through next, as a stretch of its own
+                    None => {
+                        (written, offset, start) = (BTreeMap::new(), 0, None);
+                        index += 1;
+                        continue;
+                    }
+                }
+                index = end;
+            }
+            //anything else could read any cell, and a new stretch starts after it. that's the
+            //"]" of a loop that couldn't be summed up too, which ends its body's stretch
+            _ => (written, offset, start) = (BTreeMap::new(), 0, None),
+        }
+        index += 1;
+    }
+    found.sort_by_key(|write| (write.position.line, write.position.column));
+    found
+}
@@ -0,0 +1,94 @@
+//! names for cells, given in the program's comments so the debugger can show and take them
+//! instead of bare cell numbers. a name is given with `;@` followed by the cell and the name:
+//!
+//! ```text
+//! ;@3 counter
+//! >>>++++++ ;@ptr total
+//! ```
+//!
+//! the first names cell 3, the second names whichever cell the pointer is on at that place in the
+//! program, going by the ">" and "<" before it. that's only right when every loop before it ends
+//! where it started, which is how loops in hand written programs usually are
+use crate::Position;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+///an annotation that couldn't be read, and where it is
+#[derive(PartialEq, Debug, Clone)]
+pub struct AnnotationError {
+    pub message: String,
+    pub position: Position,
+}
+
+impl fmt::Display for AnnotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+///these mean something of their own in the debugger, so they can't be names
+const RESERVED: [&str; 3] = ["ptr", "steps", "cell"];
+
+///the names given to cells in the source, by the index of the cell
+pub fn cell_names(source: &str) -> Result<BTreeMap<usize, String>, AnnotationError> {
+    let mut names: BTreeMap<usize, String> = BTreeMap::new();
+    for (offset, _) in source.match_indices(";@") {
+        let error = |message: String| AnnotationError {
+            message,
+            position: position_of(source, offset),
+        };
+        let rest = &source[offset + 2..];
+        let (cell, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let index = match cell {
+            "ptr" => {
+                let before = &source[..offset];
+                let right = before.matches('>').count();
+                let left = before.matches('<').count();
+                right.checked_sub(left).ok_or_else(|| {
+                    error("the pointer is left of the first cell here".to_string())
+                })?
+            }
+            cell => cell
+                .parse()
+                .map_err(|_| error(format!("'{}' isn't a cell, that's a number or ptr", cell)))?,
+        };
+        let name: String = rest
+            .trim_start_matches([' ', '\t'])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(error(format!("cell {} is given no name", index)));
+        }
+        if RESERVED.contains(&name.as_str()) {
+            return Err(error(format!("'{}' can't be a name for a cell", name)));
+        }
+        if let Some((other, _)) = names
+            .iter()
+            .find(|(other, known)| **known == name && **other != index)
+        {
+            return Err(error(format!(
+                "'{}' is already the name of cell {}",
+                name, other
+            )));
+        }
+        if let Some(known) = names.get(&index).filter(|known| **known != name) {
+            return Err(error(format!(
+                "cell {} is already named '{}'",
+                index, known
+            )));
+        }
+        names.insert(index, name);
+    }
+    Ok(names)
+}
+
+///the line and column of the character this many bytes into the source
+fn position_of(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    Position { line, column }
+}
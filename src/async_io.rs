@@ -0,0 +1,92 @@
+//! running a program with async input and output, for servers where the input and output are
+//! network streams. this sits on top of the events in events.rs, so the machine itself does the
+//! same work it always does, and only the reading and writing around it is async
+use crate::control::RunOutcome;
+use crate::events::Event;
+use crate::{RuntimeError, TuringMachine};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+///a turing machine along with the async streams it reads from and writes to
+pub struct AsyncTuringMachine<R, W> {
+    pub machine: TuringMachine,
+    pub input: R,
+    pub output: W,
+    //how many instructions run before giving the executor a turn, so a long loop can't keep the
+    //other tasks from running
+    pub yield_every: u64,
+    //the byte being written when the run was dropped, which goes out before anything else when
+    //it's run again
+    unwritten: Option<u8>,
+}
+
+impl<R: AsyncRead + Unpin, W: AsyncWrite + Unpin> AsyncTuringMachine<R, W> {
+    pub fn new(machine: TuringMachine, input: R, output: W) -> Self {
+        AsyncTuringMachine {
+            machine,
+            input,
+            output,
+            yield_every: 10_000,
+            unwritten: None,
+        }
+    }
+    ///runs the program to the end. the future can be dropped halfway to cancel the run, the
+    ///machine keeps the state it was in and a new call to run carries on from there, without
+    ///losing a byte it was writing. the future can be spawned on another thread when the streams
+    ///can be sent there too
+    pub async fn run(&mut self) -> Result<RunOutcome, RuntimeError> {
+        let io_error = |e: std::io::Error| RuntimeError::IoError(e.to_string());
+        loop {
+            //the byte is only let go of once it's been written, so a run dropped while it waits
+            //to write still has it next time
+            if let Some(byte) = self.unwritten {
+                self.output.write_all(&[byte]).await.map_err(io_error)?;
+                self.unwritten = None;
+            }
+            match self.machine.run_for(self.yield_every) {
+                Some(Event::Output(byte)) => self.unwritten = Some(byte),
+                Some(Event::InputNeeded) => {
+                    //anything written so far should have gone out before we wait for an answer
+                    self.output.flush().await.map_err(io_error)?;
+                    let mut input = [0u8; 1];
+                    match self.input.read(&mut input).await.map_err(io_error)? {
+                        0 => self.machine.provide_input(None),
+                        _ => self.machine.provide_input(Some(input[0])),
+                    }
+                }
+                Some(Event::Breakpoint(_)) => {}
+                Some(Event::Halted) => {
+                    self.output.flush().await.map_err(io_error)?;
+                    return Ok(RunOutcome::Halted);
+                }
+                Some(Event::Cancelled) => {
+                    self.output.flush().await.map_err(io_error)?;
+                    return Ok(RunOutcome::Cancelled);
+                }
+                Some(Event::Error(e)) => {
+                    self.output.flush().await.map_err(io_error)?;
+                    return crate::control::settle(Err(e));
+                }
+                None => YieldNow(false).await,
+            }
+        }
+    }
+}
+
+///a future that is pending the first time it's polled, which hands the executor back its turn
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,93 @@
+//! running one program on many inputs at once, for "bfint run --batch". the program is compiled
+//! once and shared, and every input gets a machine of its own on one of a few threads, so an
+//! input that makes the program fail or run into its step limit doesn't get in the way of the
+//! others
+use crate::{Program, RuntimeError, TuringMachine};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///one input to run the program on
+#[derive(PartialEq, Debug, Clone)]
+pub struct InputSpec {
+    ///what the input is called, like the name of the file it came from
+    pub name: String,
+    pub input: Vec<u8>,
+    ///a step limit for this input alone, in place of the one setup gives
+    pub step_limit: Option<u64>,
+}
+
+///how running the program on one input went
+#[derive(PartialEq, Debug)]
+pub struct BatchResult {
+    ///the name of the input
+    pub name: String,
+    ///everything the program wrote, up to where it stopped
+    pub output: Vec<u8>,
+    pub result: Result<(), RuntimeError>,
+    pub steps: u64,
+    pub time: Duration,
+}
+
+///runs the program on every input, on up to `jobs` threads, and gives back how every run went
+///in the order the inputs came in. setup is called on every machine before it starts, to give it
+///the settings it should run with, like its step limit. machines that brainfork's "Y" splits off
+///aren't run
+pub fn run_batch(
+    program: &Arc<Program>,
+    inputs: impl IntoIterator<Item = InputSpec>,
+    jobs: usize,
+    setup: impl Fn(&mut TuringMachine) + Sync,
+) -> Vec<BatchResult> {
+    let inputs: Vec<InputSpec> = inputs.into_iter().collect();
+    //the index of the next input a thread should take
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<BatchResult>>> =
+        Mutex::new(inputs.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, inputs.len().max(1)) {
+            scope.spawn(|| {
+                let mut index = next.fetch_add(1, Ordering::Relaxed);
+                while let Some(spec) = inputs.get(index) {
+                    let result = run_one(program, spec, &setup);
+                    results.lock().expect("no thread panics holding the lock")[index] =
+                        Some(result);
+                    index = next.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("no thread panics holding the lock")
+        .into_iter()
+        .map(|result| result.expect("every input was run"))
+        .collect()
+}
+
+///runs the program on one input, on a machine of its own
+fn run_one(
+    program: &Arc<Program>,
+    spec: &InputSpec,
+    setup: &impl Fn(&mut TuringMachine),
+) -> BatchResult {
+    let mut tm = TuringMachine::with_program(Arc::clone(program));
+    setup(&mut tm);
+    if let Some(step_limit) = spec.step_limit {
+        tm.step_limit = Some(step_limit);
+    }
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(spec.input.clone())));
+    tm.output = output.clone();
+    let started = Instant::now();
+    let result = tm.run().map(|_| ());
+    let time = started.elapsed();
+    let output = std::mem::take(&mut *crate::shared::lock(&output));
+    BatchResult {
+        name: spec.name.clone(),
+        output,
+        result,
+        steps: tm.steps,
+        time,
+    }
+}
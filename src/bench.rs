@@ -0,0 +1,198 @@
+//! timing how fast the interpreter runs a program, for "bfint bench". the program is compiled
+//! once for every optimization level being timed, and then run over and over on fresh machines
+//! with its output thrown away, so all that's timed is running it. a few runs first aren't
+//! counted, to give the caches a chance to fill up
+use crate::optimize::Level;
+use crate::{lang, ParseError, Program, RuntimeError, TuringMachine};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///how long one run took, and how many instructions it ran
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Run {
+    pub time: Duration,
+    pub steps: u64,
+}
+
+///the runs that were timed at one optimization level
+#[derive(PartialEq, Debug, Clone)]
+pub struct Timing {
+    pub level: Level,
+    pub runs: Vec<Run>,
+}
+
+impl Timing {
+    pub fn min(&self) -> Duration {
+        self.times().next().unwrap_or_default()
+    }
+    ///the time of the run in the middle, or halfway between the two in the middle
+    pub fn median(&self) -> Duration {
+        let times: Vec<Duration> = self.times().collect();
+        match times.len() {
+            0 => Duration::ZERO,
+            length if length % 2 == 1 => times[length / 2],
+            length => (times[length / 2 - 1] + times[length / 2]) / 2,
+        }
+    }
+    pub fn max(&self) -> Duration {
+        self.times().last().unwrap_or_default()
+    }
+    ///how many instructions a run ran. the same program runs the same instructions every time,
+    ///unless it uses random numbers
+    pub fn steps(&self) -> u64 {
+        self.runs.first().map_or(0, |run| run.steps)
+    }
+    ///how many instructions ran a second, in the median run
+    pub fn steps_per_second(&self) -> f64 {
+        self.steps() as f64 / self.median().as_secs_f64()
+    }
+    ///the times of the runs, from the fastest to the slowest
+    fn times(&self) -> impl Iterator<Item = Duration> {
+        let mut times: Vec<Duration> = self.runs.iter().map(|run| run.time).collect();
+        times.sort();
+        times.into_iter()
+    }
+}
+
+///what can keep a program from being timed
+#[derive(PartialEq, Debug)]
+pub enum BenchError {
+    ParseError(ParseError),
+    ///a run at the level failed, which would make its time mean nothing
+    RuntimeError(Level, RuntimeError),
+    ///brainfork runs its machines on threads of their own, which isn't timing the interpreter
+    UnsupportedLang,
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BenchError::ParseError(e) => write!(f, "{}", e),
+            BenchError::RuntimeError(level, e) => write!(f, "at {}: {}", level, e),
+            BenchError::UnsupportedLang => write!(f, "brainfork programs can't be timed"),
+        }
+    }
+}
+
+///times the program at every level, with warmup runs that aren't counted first and then runs
+///that are. every run gets the same input, and step_limit stops runs that go on too long.
+///after_run is called with every run that's counted, as soon as it's done
+pub fn time(
+    source: &str,
+    lang: &lang::Lang,
+    levels: &[Level],
+    (warmup, runs): (usize, usize),
+    input: &[u8],
+    step_limit: Option<u64>,
+    mut after_run: impl FnMut(Level, usize, &Run),
+) -> Result<Vec<Timing>, BenchError> {
+    if *lang == lang::Lang::Brainfork {
+        return Err(BenchError::UnsupportedLang);
+    }
+    let mut timings = vec![];
+    for &level in levels {
+        let program =
+            Arc::new(Program::compile(source, lang, level).map_err(BenchError::ParseError)?);
+        let mut timing = Timing {
+            level,
+            runs: vec![],
+        };
+        for index in 0..warmup + runs {
+            let run = run_once(&program, input, step_limit)
+                .map_err(|e| BenchError::RuntimeError(level, e))?;
+            if index >= warmup {
+                after_run(level, index - warmup + 1, &run);
+                timing.runs.push(run);
+            }
+        }
+        timings.push(timing);
+    }
+    Ok(timings)
+}
+
+///runs the program once on a fresh machine, with its output thrown away
+pub fn run_once(
+    program: &Arc<Program>,
+    input: &[u8],
+    step_limit: Option<u64>,
+) -> Result<Run, RuntimeError> {
+    let mut tm = TuringMachine::with_program(Arc::clone(program));
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(input.to_vec())));
+    tm.output = Arc::new(Mutex::new(std::io::sink()));
+    tm.step_limit = step_limit;
+    let started = Instant::now();
+    tm.run()?;
+    Ok(Run {
+        time: started.elapsed(),
+        steps: tm.steps,
+    })
+}
+
+///the timings as a table, with how much faster every level is than the first one
+pub fn table(timings: &[Timing]) -> String {
+    let mut table = format!(
+        "{:<6} {:>10} {:>10} {:>10} {:>14} {:>14} {:>8}\n",
+        "level", "min", "median", "max", "instructions", "instructions/s", "speedup"
+    );
+    for timing in timings {
+        table.push_str(&format!(
+            "{:<6} {:>10} {:>10} {:>10} {:>14} {:>14} {:>8}\n",
+            timing.level.to_string(),
+            format!("{:.2?}", timing.min()),
+            format!("{:.2?}", timing.median()),
+            format!("{:.2?}", timing.max()),
+            timing.steps(),
+            per_second(timing.steps_per_second()),
+            format!("{:.2}x", speedup(&timings[0], timing))
+        ));
+    }
+    table
+}
+
+///the timings as json, with the time of every run in seconds
+pub fn json(path: &str, warmup: usize, timings: &[Timing]) -> String {
+    let results: Vec<String> = timings
+        .iter()
+        .map(|timing| {
+            let times: Vec<String> = timing
+                .runs
+                .iter()
+                .map(|run| run.time.as_secs_f64().to_string())
+                .collect();
+            format!(
+                "{{\"level\": \"{}\", \"times\": [{}], \"min\": {}, \"median\": {}, \"max\": {}, \
+                 \"instructions\": {}, \"instructions_per_second\": {:.0}, \"speedup\": {:.3}}}",
+                timing.level,
+                times.join(", "),
+                timing.min().as_secs_f64(),
+                timing.median().as_secs_f64(),
+                timing.max().as_secs_f64(),
+                timing.steps(),
+                timing.steps_per_second(),
+                speedup(&timings[0], timing)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"program\": \"{}\", \"warmup\": {}, \"results\": [{}]}}",
+        path.replace('\\', "\\\\").replace('"', "\\\""),
+        warmup,
+        results.join(", ")
+    )
+}
+
+///how many times faster the timing's median run is than the baseline's
+fn speedup(baseline: &Timing, timing: &Timing) -> f64 {
+    baseline.median().as_secs_f64() / timing.median().as_secs_f64()
+}
+
+///a number of instructions a second, like "1.25G"
+pub fn per_second(rate: f64) -> String {
+    match rate {
+        rate if rate >= 1e9 => format!("{:.2}G", rate / 1e9),
+        rate if rate >= 1e6 => format!("{:.2}M", rate / 1e6),
+        rate if rate >= 1e3 => format!("{:.2}k", rate / 1e3),
+        rate => format!("{:.0}", rate),
+    }
+}
@@ -0,0 +1,103 @@
+//! cells with no upper limit, for using brainfuck as a way of writing down arithmetic. the tape
+//! keeps every cell's lowest byte as usual, so "." and "," and everything else that looks at a
+//! cell's byte works on its value mod 256 without knowing about any of this. what's above that
+//! byte is kept here, only for the few cells that ever go past 255
+use alloc::collections::BTreeMap;
+use num_bigint::BigUint;
+
+///what "-" does to a cell that is already 0, now that it can't wrap around to 255
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Underflow {
+    ///leave it at 0
+    Saturate,
+    ///stop the program with an error
+    Error,
+}
+
+impl Underflow {
+    pub const ALL: [Underflow; 2] = [Underflow::Saturate, Underflow::Error];
+    ///the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Underflow::Saturate => "saturate",
+            Underflow::Error => "error",
+        }
+    }
+    ///turns the name used on the command line into the matching behavior
+    pub fn from_name(name: &str) -> Option<Self> {
+        Underflow::ALL
+            .into_iter()
+            .find(|underflow| underflow.name() == name)
+    }
+}
+
+///everything above the lowest byte of the cells that have gone past 255
+#[derive(PartialEq, Debug, Clone)]
+pub struct BigCells {
+    //every cell's value divided by 256, by the index of the cell. cells where that's 0 aren't in
+    //here at all
+    high: BTreeMap<usize, BigUint>,
+    pub underflow: Underflow,
+    //the largest value any cell has held so far
+    largest: BigUint,
+}
+
+impl BigCells {
+    pub fn new(underflow: Underflow) -> Self {
+        BigCells {
+            high: BTreeMap::new(),
+            underflow,
+            largest: BigUint::ZERO,
+        }
+    }
+    ///the whole value of the cell, given its lowest byte from the tape
+    pub fn value(&self, index: usize, low: u8) -> BigUint {
+        match self.high.get(&index) {
+            Some(high) => (high << 8u32) + low,
+            None => BigUint::from(low),
+        }
+    }
+    ///whether the cell is 0, given its lowest byte from the tape
+    pub fn is_zero(&self, index: usize, low: u8) -> bool {
+        low == 0 && !self.high.contains_key(&index)
+    }
+    ///the largest value any cell has held so far
+    pub fn largest(&self) -> &BigUint {
+        &self.largest
+    }
+    ///carries into the part above the lowest byte after "+" took the byte from 255 to `low`
+    pub(crate) fn incremented(&mut self, index: usize, low: u8) {
+        if low == 0 {
+            *self.high.entry(index).or_default() += 1u32;
+        }
+        let value = self.value(index, low);
+        if value > self.largest {
+            self.largest = value;
+        }
+    }
+    ///borrows from the part above the lowest byte before "-" takes the byte from 0 to 255. gives
+    ///back false when there's nothing to borrow, because the cell is 0 already
+    pub(crate) fn decrementing(&mut self, index: usize, low: u8) -> bool {
+        if low != 0 {
+            return true;
+        }
+        match self.high.get_mut(&index) {
+            Some(high) => {
+                *high -= 1u32;
+                if *high == BigUint::ZERO {
+                    self.high.remove(&index);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+    ///drops what was above the lowest byte, for when a cell is given a new byte as a whole
+    pub(crate) fn forget(&mut self, index: usize) {
+        self.high.remove(&index);
+    }
+    pub(crate) fn clear(&mut self) {
+        self.high.clear();
+        self.largest = BigUint::ZERO;
+    }
+}
@@ -0,0 +1,110 @@
+//! running the straight runs of moves, "+" and "-" in an optimized program with one bounds check
+//! for the whole run instead of one for every move. when a program is optimized with
+//! offset-fusion, every run of two or more of them between anything else is made into a block,
+//! and with only rle every run of the same one of them over and over. a block knows how far the
+//! pointer gets to either side while it runs, what it adds to every cell it touches and where
+//! the pointer ends up. the op the run starts with says which block it starts, see Op::block.
+//!
+//! when the machine gets to the start of a block, it checks once that the pointer stays on the
+//! tape the whole way, and then does what the run does in one go without checking any further.
+//! a block that would go off the tape runs an instruction at a time as usual instead, so the
+//! move that goes off fails with its own position, like it always has. a run can only be
+//! started from its first instruction, since nothing jumps into the middle of it: brackets land
+//! just past the bracket they match, and procedures start past their "(".
+//!
+//! programs that weren't optimized with either of those, like with -O0, don't get any blocks, so
+//! they're always run an instruction at a time
+use crate::optimize::{self, movement, Runs};
+use crate::program::Program;
+use crate::{Instruction, TuringMachine};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+///a run of moves, "+" and "-" in a program, and what it does in all
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct Block {
+    //the index of the instruction just after the run
+    end: usize,
+    //what the run adds to every cell it touches, by how far it is from where the pointer starts.
+    //the ones it adds nothing to in all are left out
+    adds: Vec<(isize, u8)>,
+    //how far the pointer gets to the left and to the right of where it starts, and where it ends
+    reach: (isize, isize),
+    shift: isize,
+}
+
+impl Program {
+    ///makes every run of moves, "+" and "-" that runs says runs in one go into a block, marking
+    ///the op it starts with
+    pub(crate) fn find_blocks(&mut self, instructions: &[Instruction], runs: Runs) {
+        self.blocks.clear();
+        for run in optimize::runs(instructions, runs) {
+            let mut adds: BTreeMap<isize, u8> = BTreeMap::new();
+            let (mut offset, mut reach) = (0isize, (0isize, 0isize));
+            for &instruction in &instructions[run.clone()] {
+                let (moved, added) = movement(instruction).expect("runs only hold moves, + and -");
+                offset += moved as isize;
+                reach = (reach.0.min(offset), reach.1.max(offset));
+                let add = adds.entry(offset).or_insert(0);
+                *add = add.wrapping_add(added as u8);
+            }
+            self.ops[run.start] = self.ops[run.start].starting_block(self.blocks.len());
+            self.blocks.push(Block {
+                end: run.end,
+                adds: adds.into_iter().filter(|(_, add)| *add != 0).collect(),
+                reach,
+                shift: offset,
+            });
+        }
+    }
+}
+
+impl TuringMachine {
+    ///runs the block that starts at the next instruction in one go, if nothing about the
+    ///machine needs to see it run an instruction at a time, like an observer, and it stays on
+    ///the tape. steps count the same as running every instruction would have, and it isn't run
+    ///when it would go past the step limit or the next time the control handles or the progress
+    ///callback would be checked in with. gives back whether it did anything
+    pub(crate) fn run_block(&mut self, index: usize) -> bool {
+        if self.history.is_some()
+            || self.writers.is_some()
+            || self.observer.is_some()
+            || log::log_enabled!(log::Level::Trace)
+        {
+            return false;
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_cells.is_some() {
+            return false;
+        }
+        let start = self.program_counter;
+        let block = &self.program.blocks[index];
+        let length = (block.end - start) as u64;
+        if self
+            .breakpoints
+            .range(start + 1..block.end)
+            .next()
+            .is_some()
+            || self.steps_before_check_in() < length
+        {
+            return false;
+        }
+        //the one bounds check for the whole block
+        let pointer = self.pointer as isize;
+        if pointer + block.reach.0 < 0 || pointer + block.reach.1 >= self.tape.len() as isize {
+            return false;
+        }
+        for &(offset, add) in &block.adds {
+            //SAFETY: every offset in adds is one the pointer got to while the block ran, so it's
+            //between reach.0 and reach.1, and the check above makes sure that the pointer plus
+            //either of those is on the tape
+            let cell = unsafe { self.tape.get_unchecked_mut((pointer + offset) as usize) };
+            *cell = cell.wrapping_add(add);
+        }
+        self.pointer = (pointer + block.shift) as usize;
+        self.steps += length;
+        self.burn_fuel(length);
+        self.program_counter = block.end;
+        true
+    }
+}
@@ -0,0 +1,259 @@
+//! a compiled program written out as bytes and read back, so a program that takes a while to
+//! parse and optimize only has to be once, see cache.rs. it's the instructions the machine runs
+//! and where they came from in the source, along with the input and cell names that came with
+//! the program, but not the source itself: whoever reads it back has that already, and the
+//! hash of it that's kept makes sure it's the same one.
+//!
+//! the bytes start with "bfbc", the version of the format and the version of bfint that wrote
+//! them, since the instructions are numbered the way that version numbers them. numbers are
+//! leb128, which takes a byte for most of them, and the last 8 bytes are a hash of the rest, so a
+//! file that got broken on the way is noticed. reading it back checks the brackets again, so
+//! even a file that was tampered with gives a program that can run, or an error. the blocks and
+//! loops that optimized programs run in one go (see blocks.rs and fused.rs) aren't written out,
+//! only which passes found them, and they're found again, they're quick to find
+use crate::optimize::{Fusions, Runs};
+use crate::program::{self, Program, CUSTOM, INSTRUCTIONS};
+use crate::{Instruction, ParseError, Position};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+///what every program written out starts with
+const MAGIC: &[u8] = b"bfbc";
+///the version of the format, which goes up whenever it changes
+pub const FORMAT: u8 = 2;
+
+//the flags after the hash of the source
+const HAS_BLOCKS: u8 = 1;
+const HAS_INPUT: u8 = 2;
+const HAS_REPEATS: u8 = 4;
+const HAS_CLEAR_LOOPS: u8 = 8;
+const HAS_COPY_LOOPS: u8 = 16;
+const HAS_SCANS: u8 = 32;
+
+///everything that can be wrong with a program read back from bytes
+#[derive(PartialEq, Debug)]
+pub enum BytecodeError {
+    ///the bytes aren't a program, or one in another version of the format
+    NotBytecode,
+    ///the program was written by another version of bfint, this one
+    OtherVersion(String),
+    ///the program was compiled from a different source
+    OtherSource,
+    ///the bytes end too soon, or have something in them that can't be there
+    Malformed,
+    ParseError(ParseError),
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BytecodeError::NotBytecode => write!(f, "not a compiled program"),
+            BytecodeError::OtherVersion(version) => write!(
+                f,
+                "the program was compiled by bfint {}, this is {}",
+                version,
+                env!("CARGO_PKG_VERSION")
+            ),
+            BytecodeError::OtherSource => {
+                write!(f, "the program was compiled from a different source")
+            }
+            BytecodeError::Malformed => write!(f, "the compiled program is malformed"),
+            BytecodeError::ParseError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Program {
+    ///the program written out as bytes, see the top of this file
+    pub fn to_bytecode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT);
+        write_bytes(&mut bytes, env!("CARGO_PKG_VERSION").as_bytes());
+        bytes.extend_from_slice(&self.hash().to_le_bytes());
+        let flags = fusion_flags(self.fusions)
+            | match self.input.is_some() {
+                true => HAS_INPUT,
+                false => 0,
+            };
+        bytes.push(flags);
+        write_number(&mut bytes, self.ops.len() as u64);
+        for (op, position) in self.ops.iter().zip(&self.positions) {
+            bytes.push(op.code());
+            if let Instruction::Custom(index) = op.instruction() {
+                write_number(&mut bytes, index as u64);
+            }
+            write_number(&mut bytes, position.line as u64);
+            write_number(&mut bytes, position.column as u64);
+        }
+        if let Some(input) = &self.input {
+            write_bytes(&mut bytes, input);
+        }
+        write_number(&mut bytes, self.names.len() as u64);
+        for (index, name) in &self.names {
+            write_number(&mut bytes, *index as u64);
+            write_bytes(&mut bytes, name.as_bytes());
+        }
+        let hash = program::fnv1a_bytes(bytes.iter().copied());
+        bytes.extend_from_slice(&hash.to_le_bytes());
+        bytes
+    }
+    ///reads back a program written out with to_bytecode. the source is the text it was
+    ///compiled from, up to the "!" if it came with its input
+    pub fn from_bytecode(bytes: &[u8], source: &str) -> Result<Self, BytecodeError> {
+        if !bytes.starts_with(MAGIC) || bytes.get(MAGIC.len()) != Some(&FORMAT) {
+            return Err(BytecodeError::NotBytecode);
+        }
+        let (bytes, hash) = bytes
+            .split_at_checked(bytes.len().saturating_sub(8))
+            .ok_or(BytecodeError::Malformed)?;
+        if hash != program::fnv1a_bytes(bytes.iter().copied()).to_le_bytes() {
+            return Err(BytecodeError::Malformed);
+        }
+        let mut reader = Reader {
+            bytes: bytes
+                .get(MAGIC.len() + 1..)
+                .ok_or(BytecodeError::Malformed)?,
+        };
+        let version = reader.bytes()?;
+        if version != env!("CARGO_PKG_VERSION").as_bytes() {
+            return Err(BytecodeError::OtherVersion(
+                String::from_utf8_lossy(version).into_owned(),
+            ));
+        }
+        let hash = reader.take(8)?;
+        if hash != program::fnv1a(source).to_le_bytes() {
+            return Err(BytecodeError::OtherSource);
+        }
+        let flags = reader.byte()?;
+        let count = reader.number()?;
+        //every instruction takes at least 3 bytes, so a count that doesn't fit is a broken file
+        //and not a reason to run out of memory
+        if count > reader.bytes.len() as u64 / 3 {
+            return Err(BytecodeError::Malformed);
+        }
+        let mut tokens = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let instruction = match reader.byte()? {
+                CUSTOM => Instruction::Custom(reader.index()?),
+                code => *INSTRUCTIONS
+                    .get(code as usize)
+                    .ok_or(BytecodeError::Malformed)?,
+            };
+            let position = Position {
+                line: reader.index()?,
+                column: reader.index()?,
+            };
+            tokens.push((instruction, position));
+        }
+        let input = match flags & HAS_INPUT != 0 {
+            true => Some(reader.bytes()?.to_vec()),
+            false => None,
+        };
+        let mut names = BTreeMap::new();
+        for _ in 0..reader.number()? {
+            let index = reader.index()?;
+            let name =
+                core::str::from_utf8(reader.bytes()?).map_err(|_| BytecodeError::Malformed)?;
+            names.insert(index, name.to_string());
+        }
+        if !reader.bytes.is_empty() {
+            return Err(BytecodeError::Malformed);
+        }
+        let mut program = Program {
+            input,
+            names,
+            source: Arc::from(source),
+            ..Program::check(tokens).map_err(BytecodeError::ParseError)?
+        };
+        program.fuse(fusions(flags));
+        Ok(program)
+    }
+}
+
+///the flags for what the program runs in one go
+fn fusion_flags(fusions: Fusions) -> u8 {
+    let runs = match fusions.runs {
+        Runs::None => 0,
+        Runs::Repeats => HAS_REPEATS,
+        Runs::Mixed => HAS_BLOCKS,
+    };
+    [
+        (fusions.clear_loops, HAS_CLEAR_LOOPS),
+        (fusions.copy_loops, HAS_COPY_LOOPS),
+        (fusions.scans, HAS_SCANS),
+    ]
+    .into_iter()
+    .filter(|(on, _)| *on)
+    .fold(runs, |flags, (_, flag)| flags | flag)
+}
+
+///what the flags say the program runs in one go
+fn fusions(flags: u8) -> Fusions {
+    Fusions {
+        runs: match (flags & HAS_BLOCKS != 0, flags & HAS_REPEATS != 0) {
+            (true, _) => Runs::Mixed,
+            (false, true) => Runs::Repeats,
+            (false, false) => Runs::None,
+        },
+        clear_loops: flags & HAS_CLEAR_LOOPS != 0,
+        copy_loops: flags & HAS_COPY_LOOPS != 0,
+        scans: flags & HAS_SCANS != 0,
+    }
+}
+
+///a number as leb128: 7 bits a byte, lowest first, with the top bit set on all but the last
+fn write_number(bytes: &mut Vec<u8>, mut number: u64) {
+    while number >= 0x80 {
+        bytes.push(number as u8 | 0x80);
+        number >>= 7;
+    }
+    bytes.push(number as u8);
+}
+
+///how many bytes there are, then the bytes
+fn write_bytes(bytes: &mut Vec<u8>, written: &[u8]) {
+    write_number(bytes, written.len() as u64);
+    bytes.extend_from_slice(written);
+}
+
+///the bytes of a program that haven't been read yet
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], BytecodeError> {
+        if length > self.bytes.len() {
+            return Err(BytecodeError::Malformed);
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+    fn byte(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take(1)?[0])
+    }
+    fn number(&mut self) -> Result<u64, BytecodeError> {
+        let mut number = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            number |= ((byte & 0x7f) as u64)
+                .checked_shl(shift)
+                .ok_or(BytecodeError::Malformed)?;
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+        }
+        Err(BytecodeError::Malformed)
+    }
+    fn index(&mut self) -> Result<usize, BytecodeError> {
+        usize::try_from(self.number()?).map_err(|_| BytecodeError::Malformed)
+    }
+    fn bytes(&mut self) -> Result<&'a [u8], BytecodeError> {
+        let length = self.index()?;
+        self.take(length)
+    }
+}
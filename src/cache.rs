@@ -0,0 +1,148 @@
+//! compiled programs kept on disk from one run to the next, so running a huge program again
+//! doesn't parse and optimize it again. every program is kept in a file of its own in the cache
+//! directory, named after a hash of everything that goes into compiling it: its source, its
+//! language, the optimizer passes and the version of bfint. the file is the program as
+//! bytecode.rs writes it, followed by how many instructions it had before it was optimized.
+//! there's no directory it's in unless one is given, nothing is kept without it.
+//!
+//! a file that can't be read back, because it's broken or was written by another version, is
+//! the same as one that isn't there: the program is compiled as usual and the file written
+//! again
+use crate::bytecode::BytecodeError;
+use crate::lang::Lang;
+use crate::program::{self, Program};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+///what cached programs are called, after the key
+const EXTENSION: &str = "bfbc";
+
+///a program from the cache
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cached {
+    pub program: Program,
+    ///how many instructions the program had before it was optimized
+    pub parsed: usize,
+}
+
+///a program in the cache, by the name of its file
+#[derive(PartialEq, Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    ///how big the file is, in bytes
+    pub size: u64,
+}
+
+///why a program couldn't be had from the cache
+#[derive(PartialEq, Debug)]
+pub enum CacheError {
+    ///there's nothing in the cache for it
+    Missing,
+    Io(String),
+    Bytecode(BytecodeError),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CacheError::Missing => write!(f, "it isn't in the cache"),
+            CacheError::Io(e) => write!(f, "{}", e),
+            CacheError::Bytecode(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+///the cache directory
+#[derive(PartialEq, Debug, Clone)]
+pub struct Cache {
+    directory: PathBuf,
+}
+
+impl Cache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Cache {
+            directory: directory.into(),
+        }
+    }
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+    ///the name the program compiled from the source with the optimizer passes is kept under
+    pub fn key(source: &str, lang: &Lang, passes: &[&str]) -> String {
+        let compiled = format!(
+            "{}\0{}\0{:?}\0{}\0{}",
+            env!("CARGO_PKG_VERSION"),
+            crate::bytecode::FORMAT,
+            lang,
+            passes.join(","),
+            source
+        );
+        format!("{:016x}", program::fnv1a(&compiled))
+    }
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.{}", key, EXTENSION))
+    }
+    ///the program kept under the key, which has to have been compiled from the source
+    pub fn load(&self, key: &str, source: &str) -> Result<Cached, CacheError> {
+        let bytes = match std::fs::read(self.path(key)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(CacheError::Missing),
+            Err(e) => return Err(CacheError::Io(e.to_string())),
+        };
+        let Some(split) = bytes.len().checked_sub(8) else {
+            return Err(CacheError::Bytecode(BytecodeError::Malformed));
+        };
+        let (bytecode, parsed) = bytes.split_at(split);
+        let parsed = u64::from_le_bytes(parsed.try_into().expect("the split leaves 8 bytes"));
+        Ok(Cached {
+            program: Program::from_bytecode(bytecode, source).map_err(CacheError::Bytecode)?,
+            parsed: parsed as usize,
+        })
+    }
+    ///keeps the program under the key, along with how many instructions it had before it was
+    ///optimized. it's written to another file first and then moved into place, so a run reading
+    ///it at the same time never sees half of it
+    pub fn store(&self, key: &str, program: &Program, parsed: usize) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        let mut bytes = program.to_bytecode();
+        bytes.extend_from_slice(&(parsed as u64).to_le_bytes());
+        let path = self.path(key);
+        let partial = path.with_extension(format!("{}.{}", EXTENSION, std::process::id()));
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(&partial, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&partial);
+        })
+    }
+    ///every program in the cache, by name. a directory that isn't there has none
+    pub fn entries(&self) -> std::io::Result<Vec<Entry>> {
+        let read = match std::fs::read_dir(&self.directory) {
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut entries = vec![];
+        for entry in read {
+            let entry = entry?;
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == EXTENSION)
+            {
+                entries.push(Entry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+    ///takes every program out of the cache, and gives back the ones it took out
+    pub fn clear(&self) -> std::io::Result<Vec<Entry>> {
+        let entries = self.entries()?;
+        for entry in &entries {
+            std::fs::remove_file(self.directory.join(&entry.name))?;
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,308 @@
+//! what this build of bfint can do, for "bfint capabilities", GET /capabilities on the playground
+//! and the language server's bfint/capabilities request, so a tool that drives bfint can find out
+//! instead of guessing from the version. nothing in it is written down twice: the languages,
+//! widths, eof behaviors and levels are the lists their from_name goes by, the settings are
+//! config::SETTINGS with what config::expected says they take and what InterpreterConfig::get
+//! says they default to, and the passes are the ones optimize::passes runs. so whatever the
+//! config takes is in the report, and the other way around.
+//!
+//! the json looks like
+//!
+//! ```json
+//! {"schema": 1, "version": "0.1.0", "languages": ["bf", "ook", ...],
+//!  "extensions": [{"setting": "ext-halt", "lang": "bf-halt"}, ...],
+//!  "cell_widths": [{"name": "8", "available": true}, {"name": "big", "available": false}],
+//!  "cell_underflow": [], "cell_signedness": ["unsigned", "signed"],
+//!  "eof": ["zero", "max", "unchanged"],
+//!  "engines": ["interpreter", "tiered interpreter", "brainfork scheduler"],
+//!  "targets": ["python"], "passes": ["cancel", "dead-code", ...],
+//!  "levels": [{"name": "0", "passes": []}, ...], "runtime_warnings": ["oscillation", ...],
+//!  "tape": {"cells": 30000, "backends": ["8"]},
+//!  "limits": {"tier_threshold": 100, "snippet_steps": 100000, "warning_sample_every": 10007},
+//!  "settings": [{"name": "lang", "takes": "bf, ook, ...", "default": null}, ...],
+//!  "features": ["cli", "std"]}
+//! ```
+//!
+//! where a default is a string the way the setting is written in a profile, or null when it isn't
+//! set to anything. cell_underflow is empty without the "bigint" feature, like big is missing from
+//! the tape's backends. schema goes up when a field is taken out or starts meaning something
+//! else, not when one is added, so a tool only has to check it's the one it knows
+use crate::config::{self, CellWidth, InterpreterConfig};
+use crate::lang::Lang;
+use crate::optimize::{self, Level};
+use crate::report::json_string;
+use crate::transpile::Target;
+use crate::warnings::Detector;
+use crate::{provenance, snippet, tier, warnings, EofBehavior, Signedness, TAPE_LENGTH};
+use std::fmt;
+
+///the version of the report's json, see the top of this file
+pub const SCHEMA_VERSION: u32 = 1;
+
+///every feature bfint can be built with, and whether this build was
+const FEATURES: [(&str, bool); 17] = [
+    ("cli", cfg!(feature = "cli")),
+    ("std", cfg!(feature = "std")),
+    ("wasm", cfg!(feature = "wasm")),
+    ("ffi", cfg!(feature = "ffi")),
+    ("python", cfg!(feature = "python")),
+    ("async", cfg!(feature = "async")),
+    ("bigint", cfg!(feature = "bigint")),
+    ("tui", cfg!(feature = "tui")),
+    ("serde", cfg!(feature = "serde")),
+    ("decompress", cfg!(feature = "decompress")),
+    ("png", cfg!(feature = "png")),
+    ("net", cfg!(feature = "net")),
+    ("server", cfg!(feature = "server")),
+    ("rpc", cfg!(feature = "rpc")),
+    ("sandbox", cfg!(feature = "sandbox")),
+    ("dap", cfg!(feature = "dap")),
+    ("lsp", cfg!(feature = "lsp")),
+];
+
+///a setting, what it takes and what it is when it isn't set
+#[derive(PartialEq, Debug, Clone)]
+pub struct Setting {
+    pub name: &'static str,
+    pub takes: String,
+    pub default: Option<String>,
+}
+
+///what this build can do, see the top of this file
+#[derive(PartialEq, Debug, Clone)]
+pub struct Capabilities {
+    pub languages: Vec<&'static str>,
+    ///the settings that turn on an extension, with the language they turn it on in
+    pub extensions: Vec<(&'static str, &'static str)>,
+    ///every width, with whether this build has it
+    pub cell_widths: Vec<(&'static str, bool)>,
+    pub cell_underflow: Vec<&'static str>,
+    pub cell_signedness: Vec<&'static str>,
+    pub eof: Vec<&'static str>,
+    pub engines: Vec<&'static str>,
+    ///the languages --target translates into
+    pub targets: Vec<&'static str>,
+    pub passes: Vec<&'static str>,
+    ///every -O level, with the passes it runs
+    pub levels: Vec<(&'static str, Vec<&'static str>)>,
+    pub runtime_warnings: Vec<&'static str>,
+    pub tape_cells: usize,
+    ///the cell widths the tape can hold, which are the ones this build has
+    pub tape_backends: Vec<&'static str>,
+    ///the limits that aren't settings, by name
+    pub limits: Vec<(&'static str, u64)>,
+    pub settings: Vec<Setting>,
+    ///the features this build has
+    pub features: Vec<&'static str>,
+}
+
+impl Capabilities {
+    ///what this build can do
+    pub fn new() -> Self {
+        let defaults = InterpreterConfig::default();
+        let widths = CellWidth::ALL;
+        Capabilities {
+            languages: Lang::ALL.iter().filter_map(Lang::name).collect(),
+            extensions: config::EXTENSIONS
+                .iter()
+                .filter_map(|(setting, lang)| Some((*setting, lang.name()?)))
+                .collect(),
+            cell_widths: widths
+                .map(|width| (width.name(), width.available()))
+                .to_vec(),
+            cell_underflow: underflows(),
+            cell_signedness: Signedness::ALL.map(Signedness::name).to_vec(),
+            eof: EofBehavior::ALL.map(EofBehavior::name).to_vec(),
+            engines: vec![
+                provenance::engine(&Lang::Brainfuck, false),
+                provenance::engine(&Lang::Brainfuck, true),
+                provenance::engine(&Lang::Brainfork, false),
+            ],
+            targets: Target::ALL.map(Target::name).to_vec(),
+            passes: optimize::passes().iter().map(|pass| pass.name()).collect(),
+            levels: Level::ALL
+                .map(|level| (level.name(), level.pipeline().names()))
+                .to_vec(),
+            runtime_warnings: Detector::ALL.map(|detector| detector.name()).to_vec(),
+            tape_cells: TAPE_LENGTH,
+            tape_backends: widths
+                .into_iter()
+                .filter(|width| width.available())
+                .map(CellWidth::name)
+                .collect(),
+            limits: vec![
+                ("tier_threshold", tier::DEFAULT_THRESHOLD),
+                ("snippet_steps", snippet::SNIPPET_STEPS),
+                ("warning_sample_every", warnings::SAMPLE_EVERY),
+            ],
+            settings: config::SETTINGS
+                .iter()
+                .map(|name| Setting {
+                    name,
+                    takes: config::expected(name),
+                    default: defaults.get(name).ok().flatten(),
+                })
+                .collect(),
+            features: FEATURES
+                .iter()
+                .filter(|(_, on)| *on)
+                .map(|(name, _)| *name)
+                .collect(),
+        }
+    }
+    ///the report as json, see the top of this file
+    pub fn to_json(&self) -> String {
+        let extensions = array(self.extensions.iter().map(|(setting, lang)| {
+            format!(
+                "{{\"setting\":{},\"lang\":{}}}",
+                json_string(setting),
+                json_string(lang)
+            )
+        }));
+        let widths = array(self.cell_widths.iter().map(|(name, available)| {
+            format!(
+                "{{\"name\":{},\"available\":{}}}",
+                json_string(name),
+                available
+            )
+        }));
+        let levels = array(self.levels.iter().map(|(name, passes)| {
+            format!(
+                "{{\"name\":{},\"passes\":{}}}",
+                json_string(name),
+                strings(passes)
+            )
+        }));
+        let limits: Vec<String> = self
+            .limits
+            .iter()
+            .map(|(name, limit)| format!("{}:{}", json_string(name), limit))
+            .collect();
+        let settings = array(self.settings.iter().map(|setting| {
+            format!(
+                "{{\"name\":{},\"takes\":{},\"default\":{}}}",
+                json_string(setting.name),
+                json_string(&setting.takes),
+                setting
+                    .default
+                    .as_deref()
+                    .map_or("null".to_string(), json_string)
+            )
+        }));
+        format!(
+            "{{\"schema\":{},\"version\":{},\"languages\":{},\"extensions\":{},\"cell_widths\":{},\
+             \"cell_underflow\":{},\"cell_signedness\":{},\"eof\":{},\"engines\":{},\"targets\":{},\
+             \"passes\":{},\"levels\":{},\"runtime_warnings\":{},\
+             \"tape\":{{\"cells\":{},\"backends\":{}}},\"limits\":{{{}}},\"settings\":{},\
+             \"features\":{}}}",
+            SCHEMA_VERSION,
+            json_string(env!("CARGO_PKG_VERSION")),
+            strings(&self.languages),
+            extensions,
+            widths,
+            strings(&self.cell_underflow),
+            strings(&self.cell_signedness),
+            strings(&self.eof),
+            strings(&self.engines),
+            strings(&self.targets),
+            strings(&self.passes),
+            levels,
+            strings(&self.runtime_warnings),
+            self.tape_cells,
+            strings(&self.tape_backends),
+            limits.join(","),
+            settings,
+            strings(&self.features)
+        )
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::new()
+    }
+}
+
+impl fmt::Display for Capabilities {
+    ///the report as text, a line for each thing and one for each setting
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |names: &[&str]| match names.is_empty() {
+            true => "none".to_string(),
+            false => names.join(", "),
+        };
+        writeln!(
+            f,
+            "bfint {}, capabilities schema {}",
+            env!("CARGO_PKG_VERSION"),
+            SCHEMA_VERSION
+        )?;
+        writeln!(f, "languages: {}", list(&self.languages))?;
+        let extensions: Vec<String> = self
+            .extensions
+            .iter()
+            .map(|(setting, lang)| format!("{} ({})", setting, lang))
+            .collect();
+        writeln!(f, "extensions: {}", extensions.join(", "))?;
+        let widths: Vec<String> = self
+            .cell_widths
+            .iter()
+            .map(|(name, available)| match available {
+                true => name.to_string(),
+                false => format!("{} (not in this build)", name),
+            })
+            .collect();
+        writeln!(f, "cell widths: {}", widths.join(", "))?;
+        writeln!(f, "cell underflow: {}", list(&self.cell_underflow))?;
+        writeln!(f, "cell signedness: {}", list(&self.cell_signedness))?;
+        writeln!(f, "eof: {}", list(&self.eof))?;
+        writeln!(f, "engines: {}", list(&self.engines))?;
+        writeln!(f, "targets: {}", list(&self.targets))?;
+        writeln!(f, "passes: {}", list(&self.passes))?;
+        for (name, passes) in &self.levels {
+            writeln!(f, "-O{}: {}", name, list(passes))?;
+        }
+        writeln!(f, "runtime warnings: {}", list(&self.runtime_warnings))?;
+        writeln!(
+            f,
+            "tape: {} cells, of width {}",
+            self.tape_cells,
+            list(&self.tape_backends)
+        )?;
+        for (name, limit) in &self.limits {
+            writeln!(f, "{}: {}", name.replace('_', " "), limit)?;
+        }
+        writeln!(f, "settings:")?;
+        let width = self.settings.iter().map(|setting| setting.name.len()).max();
+        for setting in &self.settings {
+            writeln!(
+                f,
+                "  {:width$}  {} (default: {})",
+                setting.name,
+                setting.takes,
+                setting.default.as_deref().unwrap_or("none"),
+                width = width.unwrap_or(0)
+            )?;
+        }
+        writeln!(f, "features: {}", list(&self.features))
+    }
+}
+
+///the names cell-underflow takes, none without big cells
+fn underflows() -> Vec<&'static str> {
+    #[cfg(feature = "bigint")]
+    return crate::big::Underflow::ALL
+        .map(crate::big::Underflow::name)
+        .to_vec();
+    #[cfg(not(feature = "bigint"))]
+    return vec![];
+}
+
+///the names as a json array of strings
+fn strings(names: &[&str]) -> String {
+    array(names.iter().map(|name| json_string(name)))
+}
+
+///the json values as a json array
+fn array(values: impl Iterator<Item = String>) -> String {
+    format!("[{}]", values.collect::<Vec<String>>().join(","))
+}
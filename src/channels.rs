@@ -0,0 +1,285 @@
+//! bf-channels' input channels, for programs that read from more than one place, like a
+//! simulation reading sensor data from one and commands from another. "'" switches to the
+//! channel numbered by the current cell, and from then on "," reads from that one. every
+//! channel has an input of its own, or reads the machine's usual input, and runs out on its
+//! own: once a channel has run out it stays that way, whatever the others do. a channel can
+//! have its own eof behavior too, for what "," does when it has run out.
+//!
+//! channel 0 is the usual input unless it's given another one, and the program starts out on
+//! it, so a program that never switches reads like any other. reading from any other channel
+//! that wasn't given anything is an error. how much has been read from every channel is kept,
+//! and goes into checkpoints, so a channel given to a resumed machine skips what was already
+//! read from it before
+use crate::io::InputByte;
+use crate::shared::{lock, Shared};
+use crate::{EofBehavior, RuntimeError, TuringMachine};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+///how much has been read from a channel
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct ChannelUse {
+    ///the bytes read from it
+    pub read: u64,
+    ///whether it has run out
+    pub ended: bool,
+}
+
+///an input a channel was given
+#[derive(Clone)]
+struct ChannelInput {
+    //what it reads from, or nothing for the machine's usual input
+    input: Option<Shared<dyn InputByte + Send>>,
+    eof: Option<EofBehavior>,
+    //how many bytes of it were read before a checkpoint it was resumed from, which are skipped
+    //before it's first read
+    skip: u64,
+}
+
+///the channels a machine has, and which one "," reads from
+#[derive(Clone, Default)]
+pub(crate) struct Channels {
+    selected: u8,
+    inputs: BTreeMap<u8, ChannelInput>,
+    used: BTreeMap<u8, ChannelUse>,
+}
+
+impl TuringMachine {
+    ///gives the channel an input of its own, or the machine's usual input with None, and an eof
+    ///behavior of its own if it has one. whatever was read from the channel before a checkpoint
+    ///the machine was resumed from is skipped
+    pub fn add_channel(
+        &mut self,
+        number: u8,
+        input: Option<Shared<dyn InputByte + Send>>,
+        eof: Option<EofBehavior>,
+    ) {
+        let channels = self.channels.get_or_insert_with(Box::default);
+        let skip = channels.used.get(&number).map_or(0, |used| used.read);
+        channels
+            .inputs
+            .insert(number, ChannelInput { input, eof, skip });
+    }
+    ///the channel "," reads from
+    pub fn channel(&self) -> u8 {
+        self.channels
+            .as_ref()
+            .map_or(0, |channels| channels.selected)
+    }
+    ///how much has been read from every channel that has been read from, by number
+    pub fn channel_use(&self) -> Vec<(u8, ChannelUse)> {
+        self.channels.as_ref().map_or(vec![], |channels| {
+            channels.used.iter().map(|(n, used)| (*n, *used)).collect()
+        })
+    }
+    ///executes the "SwitchChannel" instruction, switching to the channel numbered by the
+    ///current cell
+    pub(crate) fn switch_channel(&mut self) {
+        let cell = self.tape[self.pointer];
+        self.channels.get_or_insert_with(Box::default).selected = cell;
+        self.program_counter += 1;
+    }
+    ///the eof behavior of the channel that's switched to, if it has one of its own
+    pub(crate) fn channel_eof(&self) -> Option<EofBehavior> {
+        let channels = self.channels.as_ref()?;
+        channels.inputs.get(&channels.selected)?.eof
+    }
+    ///reads a byte from the channel that's switched to
+    pub(crate) fn read_channel(&mut self) -> Result<Option<u8>, RuntimeError> {
+        let Some(channels) = self.channels.as_mut() else {
+            return self.read_input();
+        };
+        let number = channels.selected;
+        if channels.used.get(&number).is_some_and(|used| used.ended) {
+            self.forget_states();
+            return Ok(None);
+        }
+        let byte = match channels.inputs.get_mut(&number) {
+            Some(ChannelInput {
+                input: Some(input),
+                skip,
+                ..
+            }) => {
+                let input = Shared::clone(input);
+                let skip = core::mem::take(skip);
+                self.forget_states();
+                let mut input = lock(&input);
+                let mut read = || {
+                    input
+                        .read_byte()
+                        .map_err(|e| RuntimeError::IoError(format!("could not read input: {}", e)))
+                };
+                for _ in 0..skip {
+                    read()?;
+                }
+                read()?
+            }
+            Some(ChannelInput { input: None, .. }) => self.read_input()?,
+            None if number == 0 => self.read_input()?,
+            None => {
+                return Err(RuntimeError::NoSuchChannel(
+                    number,
+                    self.program.positions[self.program_counter],
+                ))
+            }
+        };
+        let used = self
+            .channels
+            .as_mut()
+            .expect("checked above")
+            .used
+            .entry(number)
+            .or_default();
+        match byte {
+            Some(_) => used.read += 1,
+            None => used.ended = true,
+        }
+        Ok(byte)
+    }
+    ///the channels as a line of a checkpoint, if there are any: the one that's switched to, and
+    ///how much has been read from every channel as number:read:ended
+    #[cfg(feature = "std")]
+    pub(crate) fn channels_checkpoint(&self) -> Option<String> {
+        let channels = self.channels.as_ref()?;
+        let used: Vec<String> = channels
+            .used
+            .iter()
+            .map(|(number, used)| format!("{}:{}:{}", number, used.read, u8::from(used.ended)))
+            .collect();
+        Some(format!("channels {} {}", channels.selected, used.join(" ")))
+    }
+    ///puts the channels back the way the words after "channels" in a checkpoint say, or the
+    ///way they start out without them. gives back nothing if they can't be read
+    #[cfg(feature = "std")]
+    pub(crate) fn resume_channels(&mut self, words: Option<&[&str]>) -> Option<()> {
+        let (selected, used) = match words {
+            Some(words) => {
+                let (selected, used) = words.split_first()?;
+                let used = used
+                    .iter()
+                    .map(|word| {
+                        let mut parts = word.split(':');
+                        let number = parts.next()?.parse().ok()?;
+                        let read = parts.next()?.parse().ok()?;
+                        let ended = match parts.next()? {
+                            "0" => false,
+                            "1" => true,
+                            _ => return None,
+                        };
+                        Some((number, ChannelUse { read, ended }))
+                    })
+                    .collect::<Option<BTreeMap<u8, ChannelUse>>>()?;
+                (selected.parse().ok()?, used)
+            }
+            None => (0, BTreeMap::new()),
+        };
+        if words.is_none() && self.channels.is_none() {
+            return Some(());
+        }
+        let channels = self.channels.get_or_insert_with(Box::default);
+        channels.selected = selected;
+        for (number, input) in channels.inputs.iter_mut() {
+            input.skip = used.get(number).map_or(0, |used| used.read);
+        }
+        channels.used = used;
+        Some(())
+    }
+}
+
+///where a channel given on the command line reads from
+#[derive(PartialEq, Debug, Clone)]
+pub enum Source {
+    ///the file at the path
+    File(String),
+    ///the text itself
+    Text(String),
+    ///the machine's usual input, stdin unless --input says otherwise
+    Stdin,
+}
+
+///a channel as it's given on the command line, like "0=file:a.bin", "1=str:hello" or
+///"2=stdin", with ",eof=max" or another eof behavior after it for one of its own
+#[derive(PartialEq, Debug, Clone)]
+pub struct ChannelSpec {
+    pub number: u8,
+    pub source: Source,
+    pub eof: Option<EofBehavior>,
+}
+
+///everything that can be wrong with a channel given on the command line
+#[derive(PartialEq, Debug, Clone)]
+pub enum ChannelError {
+    ///there's no "=" between the number and the source
+    Malformed(String),
+    ///the number isn't one from 0 to 255
+    Number(String),
+    ///the source isn't "file:", "str:" or "stdin"
+    Source(String),
+    ///what comes after ",eof=" isn't an eof behavior
+    Eof(String),
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChannelError::Malformed(spec) => write!(
+                f,
+                "'{}' isn't a channel, they're written like 0=file:a.bin, 1=str:hello or 2=stdin",
+                spec
+            ),
+            ChannelError::Number(number) => write!(
+                f,
+                "'{}' isn't a channel number, they go from 0 to 255",
+                number
+            ),
+            ChannelError::Source(source) => write!(
+                f,
+                "'{}' isn't something a channel can read from, it takes file:<path>, str:<text> \
+                 or stdin",
+                source
+            ),
+            ChannelError::Eof(eof) => write!(
+                f,
+                "'{}' isn't an eof behavior, it takes zero, max or unchanged",
+                eof
+            ),
+        }
+    }
+}
+
+impl ChannelSpec {
+    pub fn parse(spec: &str) -> Result<Self, ChannelError> {
+        let (number, rest) = spec
+            .split_once('=')
+            .ok_or_else(|| ChannelError::Malformed(spec.to_string()))?;
+        let number = number
+            .parse()
+            .map_err(|_| ChannelError::Number(number.to_string()))?;
+        let (source, eof) = match rest.rsplit_once(",eof=") {
+            Some((source, eof)) => (
+                source,
+                Some(
+                    EofBehavior::from_name(eof)
+                        .ok_or_else(|| ChannelError::Eof(eof.to_string()))?,
+                ),
+            ),
+            None => (rest, None),
+        };
+        let source = match source.split_once(':') {
+            Some(("file", path)) => Source::File(path.to_string()),
+            Some(("str", text)) => Source::Text(text.to_string()),
+            _ if source == "stdin" => Source::Stdin,
+            _ => return Err(ChannelError::Source(source.to_string())),
+        };
+        Ok(ChannelSpec {
+            number,
+            source,
+            eof,
+        })
+    }
+}
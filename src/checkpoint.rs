@@ -0,0 +1,299 @@
+//! saving a running machine to text and picking it back up later, so a program that runs for
+//! hours doesn't have to start over when the computer does. a checkpoint holds everything that
+//! changes while a program runs: the tape, the pointer, where in the program it is, the step
+//! count, the random numbers and so on. it doesn't hold the program itself, only a hash of its
+//! source, so it can't be resumed with a different program by accident. brainfork's machines and
+//! bf-file's open file aren't saved. a core dump is a checkpoint made when a run fails, kept
+//! along with the error so it can be looked at afterwards. whatever comes after the cells, like
+//! the provenance bfint writes there (see provenance.rs), is left alone when resuming, apart
+//! from a line saying how much was read from every input channel, which comes right after the
+//! cells when a program has channels (see channels.rs)
+use crate::provenance::Provenance;
+use crate::{Call, TuringMachine};
+use std::fmt;
+
+///the first line of every checkpoint, with the version of the format in it
+const HEADER: &str = "bfstate 1";
+
+///everything that can be wrong with a checkpoint
+#[derive(PartialEq, Debug, Clone)]
+pub enum CheckpointError {
+    ///the text isn't a checkpoint, or one written by another version
+    NotACheckpoint,
+    ///the line, counting from 1, isn't what it should be
+    Malformed(usize),
+    ///the checkpoint was made while running another program
+    WrongProgram,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::NotACheckpoint => write!(f, "not a checkpoint"),
+            CheckpointError::Malformed(line) => write!(f, "line {} is malformed", line),
+            CheckpointError::WrongProgram => {
+                write!(
+                    f,
+                    "the checkpoint was made for a different program, resume it with the \
+                     program it was made with, unchanged"
+                )
+            }
+        }
+    }
+}
+
+///a hash of the program's source, the same as Program::hash
+pub fn program_hash(source: &str) -> u64 {
+    crate::program::fnv1a(source)
+}
+
+///the numbers as text, separated by spaces
+fn list<T: ToString>(numbers: impl IntoIterator<Item = T>) -> String {
+    numbers
+        .into_iter()
+        .map(|number| number.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl TuringMachine {
+    ///the state of the machine as text, one thing per line. the source is only used for its
+    ///hash
+    pub fn checkpoint(&self, source: &str) -> String {
+        let last_used = self
+            .tape
+            .iter()
+            .rposition(|cell| *cell != 0)
+            .map_or(0, |last| last + 1);
+        let procedures = self
+            .procedures
+            .iter()
+            .enumerate()
+            .filter_map(|(number, start)| start.map(|start| format!("{}:{}", number, start)));
+        [
+            HEADER.to_string(),
+            format!("program {:016x}", program_hash(source)),
+            format!("pointer {}", self.pointer),
+            format!("pc {}", self.program_counter),
+            format!("steps {}", self.steps),
+            format!("rng {}", self.rng.state),
+            format!("storage {}", self.storage),
+            format!(
+                "bits {} {} {} {}",
+                self.input_bits.0, self.input_bits.1, self.output_bits.0, self.output_bits.1
+            ),
+            format!("output-bytes {}", self.output_bytes),
+            format!(
+                "calls {}",
+                list(self.calls.iter().filter_map(|call| call.return_to))
+            ),
+            format!("procedures {}", list(procedures)),
+            format!("pending {}", list(&self.pending_output)),
+            format!("cells {}", list(&self.tape[..last_used])),
+        ]
+        .into_iter()
+        .chain(self.channels_checkpoint())
+        .map(|line| line.trim_end().to_string() + "\n")
+        .collect()
+    }
+    ///puts the machine back in the state the checkpoint was made in. the machine has to be
+    ///running the same source as when the checkpoint was made. nothing changes if the checkpoint
+    ///can't be read
+    pub fn resume(&mut self, source: &str, checkpoint: &str) -> Result<(), CheckpointError> {
+        let mut lines = checkpoint.lines().enumerate();
+        //where a line that's missing from the end would have been
+        let end = checkpoint.lines().count() + 1;
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(CheckpointError::NotACheckpoint);
+        }
+        //the words after the key on the next line, and the line's number for errors
+        let mut field = |key: &str| {
+            let (index, line) = lines.next().ok_or(CheckpointError::Malformed(end))?;
+            let values = line
+                .strip_prefix(key)
+                .filter(|values| values.is_empty() || values.starts_with(' '))
+                .ok_or(CheckpointError::Malformed(index + 1))?;
+            Ok::<_, CheckpointError>((index + 1, values.split_whitespace().collect::<Vec<_>>()))
+        };
+        fn number<T: std::str::FromStr>(
+            (line, values): &(usize, Vec<&str>),
+            index: usize,
+        ) -> Result<T, CheckpointError> {
+            values
+                .get(index)
+                .and_then(|value| value.parse().ok())
+                .ok_or(CheckpointError::Malformed(*line))
+        }
+        fn numbers<T: std::str::FromStr>(
+            (line, values): &(usize, Vec<&str>),
+        ) -> Result<Vec<T>, CheckpointError> {
+            values
+                .iter()
+                .map(|value| value.parse().map_err(|_| CheckpointError::Malformed(*line)))
+                .collect()
+        }
+        let program = field("program")?;
+        let hash = program
+            .1
+            .first()
+            .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+            .ok_or(CheckpointError::Malformed(program.0))?;
+        if hash != program_hash(source) {
+            return Err(CheckpointError::WrongProgram);
+        }
+        let mut resumed = self.clone();
+        let pointer = field("pointer")?;
+        resumed.pointer = number(&pointer, 0)?;
+        let program_counter = field("pc")?;
+        resumed.program_counter = number(&program_counter, 0)?;
+        resumed.steps = number(&field("steps")?, 0)?;
+        resumed.rng.state = number(&field("rng")?, 0)?;
+        resumed.storage = number(&field("storage")?, 0)?;
+        let bits = field("bits")?;
+        resumed.input_bits = (number(&bits, 0)?, number(&bits, 1)?);
+        resumed.output_bits = (number(&bits, 2)?, number(&bits, 3)?);
+        resumed.output_bytes = number(&field("output-bytes")?, 0)?;
+        let calls = field("calls")?;
+        let returns: Vec<usize> = numbers(&calls)?;
+        //a call returns to just after its ":", which is where it's said to be
+        if returns
+            .iter()
+            .any(|&call| call == 0 || call > resumed.program.len())
+        {
+            return Err(CheckpointError::Malformed(calls.0));
+        }
+        resumed.calls = returns
+            .into_iter()
+            .map(|return_to| Call {
+                site: resumed.program.positions[return_to - 1],
+                return_to: Some(return_to),
+            })
+            .collect();
+        let procedures = field("procedures")?;
+        resumed.procedures = [None; 256];
+        for procedure in &procedures.1 {
+            let (number, start) = procedure
+                .split_once(':')
+                .and_then(|(number, start)| Some((number.parse::<u8>().ok()?, start.parse().ok()?)))
+                .ok_or(CheckpointError::Malformed(procedures.0))?;
+            resumed.procedures[number as usize] = Some(start);
+        }
+        resumed.pending_output = numbers::<u8>(&field("pending")?)?.into();
+        let cells_line = field("cells")?;
+        let cells: Vec<u8> = numbers(&cells_line)?;
+        if cells.len() > resumed.tape.len() {
+            return Err(CheckpointError::Malformed(cells_line.0));
+        }
+        resumed.tape = [0; crate::TAPE_LENGTH];
+        resumed.tape[..cells.len()].copy_from_slice(&cells);
+        //checkpoints of programs without channels, and ones from before there were any, don't
+        //have the line
+        let channels = lines.next().and_then(|(index, line)| {
+            let words = line.strip_prefix("channels ")?;
+            Some((index + 1, words.split_whitespace().collect::<Vec<_>>()))
+        });
+        resumed
+            .resume_channels(channels.as_ref().map(|(_, words)| words.as_slice()))
+            .ok_or(CheckpointError::Malformed(
+                channels.map_or(0, |(line, _)| line),
+            ))?;
+        //a checkpoint that points outside the tape or the program would only fail later on
+        if resumed.pointer >= resumed.tape.len() {
+            return Err(CheckpointError::Malformed(pointer.0));
+        }
+        if resumed.program_counter > resumed.program.len() {
+            return Err(CheckpointError::Malformed(program_counter.0));
+        }
+        resumed.paused_at = None;
+        *self = resumed;
+        Ok(())
+    }
+}
+
+///the first line of every core dump
+const DUMP_HEADER: &str = "bfdump 1";
+
+///what's saved when a run fails: a checkpoint of the machine when it stopped, with the error and
+///the whole source of the program, so it can be looked at later without the program file
+#[derive(PartialEq, Debug, Clone)]
+pub struct CoreDump {
+    ///the path the program was run from, which tells which language it's in
+    pub path: String,
+    pub error: String,
+    pub source: String,
+    ///the checkpoint of the machine, which can be given to resume
+    pub state: String,
+    ///what the run was, written after the state when it's known
+    pub provenance: Option<Provenance>,
+}
+
+impl CoreDump {
+    pub fn new(path: &str, error: &str, source: &str, tm: &TuringMachine) -> Self {
+        CoreDump {
+            path: path.to_string(),
+            error: error.to_string(),
+            source: source.to_string(),
+            state: tm.checkpoint(source),
+            provenance: None,
+        }
+    }
+    ///the dump as text. the source is written with its length in front, since it can have any
+    ///lines in it, and the provenance goes last
+    pub fn to_text(&self) -> String {
+        format!(
+            "{}\npath {}\nerror {}\nsource {}\n{}\n{}{}",
+            DUMP_HEADER,
+            self.path.replace('\n', " "),
+            self.error.replace('\n', " "),
+            self.source.len(),
+            self.source,
+            self.state,
+            self.provenance
+                .as_ref()
+                .map_or(String::new(), Provenance::to_text)
+        )
+    }
+    ///reads back what to_text wrote
+    pub fn parse(text: &str) -> Result<Self, CheckpointError> {
+        let rest = text
+            .strip_prefix(DUMP_HEADER)
+            .and_then(|rest| rest.strip_prefix('\n'))
+            .ok_or(CheckpointError::NotACheckpoint)?;
+        let line = |rest: &mut &str, key: &str, number: usize| {
+            let (line, after) = rest
+                .split_once('\n')
+                .ok_or(CheckpointError::Malformed(number))?;
+            *rest = after;
+            line.strip_prefix(key)
+                .and_then(|value| value.strip_prefix(' '))
+                .map(str::to_string)
+                .ok_or(CheckpointError::Malformed(number))
+        };
+        let mut rest = rest;
+        let path = line(&mut rest, "path", 2)?;
+        let error = line(&mut rest, "error", 3)?;
+        let length: usize = line(&mut rest, "source", 4)?
+            .parse()
+            .map_err(|_| CheckpointError::Malformed(4))?;
+        let source = rest
+            .get(..length)
+            .ok_or(CheckpointError::Malformed(5))?
+            .to_string();
+        let state = rest[length..]
+            .strip_prefix('\n')
+            .ok_or(CheckpointError::Malformed(5 + source.lines().count()))?;
+        //the provenance starts at its first line, if there is one
+        let provenance = Provenance::find(state);
+        let state = match state.find("\nprovenance ") {
+            Some(end) => &state[..end + 1],
+            None => state,
+        };
+        Ok(CoreDump {
+            path,
+            error,
+            source,
+            state: state.to_string(),
+            provenance,
+        })
+    }
+}
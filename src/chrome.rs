@@ -0,0 +1,203 @@
+//! a trace of a run in the trace event format, for --trace-chrome, which chrome's about:tracing
+//! and perfetto show as a timeline. every time the program goes into a loop there's a begin
+//! event named after where its "[" is, like "loop@3:1", and an end event when it comes out, so
+//! loops inside loops nest the way they ran. reading and writing are instant events with the
+//! byte, and the stats of the run go in the trace's metadata.
+//!
+//! reading the clock every step would slow the run down more than the loops being timed, so
+//! it's only read every SAMPLE_EVERY steps. the time of an event in between is worked out from
+//! how long a step took over the last stretch of steps. a trace stops taking new events after
+//! MAX_EVENTS, but the loops it has begun are always ended
+use crate::control::RunOutcome;
+use crate::observe::{Observer, StepCtx};
+use crate::report::json_string;
+use crate::{Instruction, RuntimeError, TuringMachine};
+use std::fmt::Write;
+use std::time::Instant;
+
+///how many steps there are between reading the clock
+pub const SAMPLE_EVERY: u64 = 1024;
+///the most events a trace takes, past which it would be too big for the viewers to open
+pub const MAX_EVENTS: usize = 1_000_000;
+
+///the events of a run, as it goes
+pub struct ChromeTrace {
+    started: Instant,
+    //the step the clock was last read at, what it said in microseconds since started, and how
+    //many microseconds a step took over the stretch before that
+    sampled_step: u64,
+    sampled_at: f64,
+    per_step: f64,
+    //the time of the last event, which the next one can't be before
+    last_time: f64,
+    //the events so far, a json object each
+    events: Vec<String>,
+    //the loops the program is in, innermost last, and whether their begin event was taken,
+    //which it isn't once there are too many events
+    open: Vec<bool>,
+    //the events that weren't taken
+    dropped: u64,
+    loops_entered: u64,
+    inputs: u64,
+    outputs: u64,
+}
+
+impl Default for ChromeTrace {
+    fn default() -> Self {
+        ChromeTrace::new()
+    }
+}
+
+impl ChromeTrace {
+    ///a trace whose times count from now
+    pub fn new() -> Self {
+        ChromeTrace {
+            started: Instant::now(),
+            sampled_step: 0,
+            sampled_at: 0.0,
+            per_step: 0.0,
+            last_time: 0.0,
+            events: vec![],
+            open: vec![],
+            dropped: 0,
+            loops_entered: 0,
+            inputs: 0,
+            outputs: 0,
+        }
+    }
+    ///the time of the step in microseconds, reading the clock when it's time to
+    fn time(&mut self, step: u64) -> f64 {
+        if step >= self.sampled_step + SAMPLE_EVERY {
+            let now = self.started.elapsed().as_secs_f64() * 1e6;
+            self.per_step = (now - self.sampled_at) / (step - self.sampled_step) as f64;
+            self.sampled_step = step;
+            self.sampled_at = now;
+        }
+        let time = self.sampled_at + (step - self.sampled_step) as f64 * self.per_step;
+        self.last_time = time.max(self.last_time);
+        self.last_time
+    }
+    ///adds the event, unless there are too many already. gives back whether it was added
+    fn event(&mut self, event: String) -> bool {
+        let taken = self.events.len() < MAX_EVENTS;
+        match taken {
+            true => self.events.push(event),
+            false => self.dropped += 1,
+        }
+        taken
+    }
+    fn end(&mut self, time: f64) {
+        if let Some(true) = self.open.pop() {
+            //an end always goes in, a begin without one would run to the end of the trace
+            self.events.push(format!(
+                "{{\"ph\":\"E\",\"ts\":{:.3},\"pid\":1,\"tid\":1}}",
+                time
+            ));
+        }
+    }
+    ///the trace as json, with the name of the program and the stats of the machine that ran it
+    pub fn to_json(&self, name: &str, tm: &TuringMachine) -> String {
+        let name = json_string(name);
+        let mut json = String::from("{\"traceEvents\":[\n");
+        let _ = writeln!(
+            json,
+            "{{\"name\":\"process_name\",\"ph\":\"M\",\"pid\":1,\"args\":{{\"name\":{}}}}},",
+            name
+        );
+        json.push_str(
+            "{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":1,\"args\":{\"name\":\"main\"}},\n",
+        );
+        for event in &self.events {
+            json.push_str(event);
+            json.push_str(",\n");
+        }
+        let stats = format!(
+            "\"steps\":{},\"instructions\":{},\"loops_entered\":{},\"inputs\":{},\"outputs\":{}",
+            tm.steps,
+            tm.program().len(),
+            self.loops_entered,
+            self.inputs,
+            self.outputs
+        );
+        //the stats once more as a counter, which the viewers draw as a track of its own
+        let _ = writeln!(
+            json,
+            "{{\"name\":\"stats\",\"ph\":\"C\",\"ts\":{:.3},\"pid\":1,\"tid\":1,\"args\":{{{}}}}}",
+            self.last_time, stats
+        );
+        let _ = write!(
+            json,
+            "],\n\"displayTimeUnit\":\"ns\",\n\"otherData\":{{\"program\":{},{},\
+             \"events_dropped\":{},\"clock_read_every\":{}}}}}\n",
+            name, stats, self.dropped, SAMPLE_EVERY
+        );
+        json
+    }
+}
+
+impl Observer for ChromeTrace {
+    fn after_step(&mut self, ctx: &StepCtx) {
+        let tm = ctx.machine();
+        let pc = ctx.pc();
+        let step = ctx.steps();
+        let position = tm.program.positions[pc];
+        match ctx.op() {
+            //a "[" that jumps past its "]" never goes into the loop, and a "]" that jumps back
+            //past its "[" stays in it
+            Instruction::JumpToClose | Instruction::JumpToOpen if tm.program_counter != pc + 1 => {}
+            //a "[" that's carried on after goes into its loop, and a "]" that's carried on after
+            //comes out of it
+            Instruction::JumpToClose => {
+                self.loops_entered += 1;
+                let time = self.time(step);
+                let end = tm
+                    .program
+                    .matching_bracket(pc)
+                    .map(|close| tm.program.positions[close]);
+                let mut event = format!(
+                    "{{\"name\":\"loop@{}\",\"cat\":\"loop\",\"ph\":\"B\",\"ts\":{:.3},\"pid\":1,\
+                     \"tid\":1",
+                    position, time
+                );
+                if let Some(end) = end {
+                    let _ = write!(event, ",\"args\":{{\"end\":\"{}\"}}", end);
+                }
+                event.push('}');
+                let taken = self.event(event);
+                self.open.push(taken);
+            }
+            Instruction::JumpToOpen => {
+                let time = self.time(step);
+                self.end(time);
+            }
+            op @ (Instruction::Output | Instruction::Replace) => {
+                let (name, count) = match op {
+                    Instruction::Output => ("output", &mut self.outputs),
+                    _ => ("input", &mut self.inputs),
+                };
+                *count += 1;
+                let byte = ctx.cell(ctx.pointer()).unwrap_or(0);
+                let time = self.time(step);
+                self.event(format!(
+                    "{{\"name\":\"{}\",\"cat\":\"io\",\"ph\":\"i\",\"s\":\"t\",\"ts\":{:.3},\
+                     \"pid\":1,\"tid\":1,\"args\":{{\"byte\":{},\"loc\":\"{}\"}}}}",
+                    name, time, byte, position
+                ));
+            }
+            _ => {
+                if step >= self.sampled_step + SAMPLE_EVERY {
+                    self.time(step);
+                }
+            }
+        }
+    }
+    ///a run that ends inside loops, by halting or failing, comes out of all of them then
+    fn on_halt(&mut self, _outcome: Result<RunOutcome, &RuntimeError>) {
+        let time = self.started.elapsed().as_secs_f64() * 1e6;
+        let time = time.max(self.last_time);
+        self.last_time = time;
+        while !self.open.is_empty() {
+            self.end(time);
+        }
+    }
+}
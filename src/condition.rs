@@ -0,0 +1,353 @@
+//! the conditions on conditional breakpoints, like `cell[42] == 7 && ptr > 100`. a condition is
+//! only looked at when the machine gets to its breakpoint, and the breakpoint is passed by when
+//! it doesn't hold, so a breakpoint in a loop that goes around a million times only stops on the
+//! time round that matters.
+//!
+//! a condition compares numbers with `==`, `!=`, `<`, `<=`, `>` and `>=`, and comparisons are
+//! put together with `&&`, `||`, `!` and brackets. the numbers can be written out, or be `ptr`
+//! for where the pointer is, `cell` for the current cell, `cell[n]` for cell n, or `steps` for
+//! how many instructions have run, and they can be added and taken away with `+` and `-`, so
+//! `cell[ptr+1]` is the cell to the right of the pointer. cells that were given names in the
+//! program's comments (see annotations.rs) can be called by them, `counter` and `cell[counter]`
+//! are both the cell named counter.
+//!
+//! a condition can have at most MAX_OPERATORS operators and brackets in it, which is also as
+//! deep as its pieces can be nested, so that reading it and working it out never run out of
+//! stack
+use crate::TuringMachine;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+///how many "!", "&&", "||", "+", "-", "(" and "cell[" a condition can have
+pub const MAX_OPERATORS: usize = 256;
+
+///a number in a condition, worked out from the machine whenever the condition is looked at
+#[derive(PartialEq, Debug, Clone)]
+pub enum Value {
+    Number(i64),
+    ///where the pointer is
+    Pointer,
+    ///how many instructions have run
+    Steps,
+    ///the cell at the index, cells that aren't on the tape count as 0
+    Cell(Box<Value>),
+    Add(Box<Value>, Box<Value>),
+    Subtract(Box<Value>, Box<Value>),
+}
+
+///the ways two numbers can be compared
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+///something that's either true or false about the machine
+#[derive(PartialEq, Debug, Clone)]
+pub enum Condition {
+    Compare(Value, Comparison, Value),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+///a condition that couldn't be read, with what was wrong and how many characters in it was
+#[derive(PartialEq, Debug, Clone)]
+pub struct ConditionError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at character {}", self.message, self.offset + 1)
+    }
+}
+
+impl Value {
+    ///reads a number from its text, like `cell[ptr+1]`
+    pub fn parse(text: &str) -> Result<Self, ConditionError> {
+        Value::parse_named(text, &BTreeMap::new())
+    }
+    ///the same as parse, with the names of cells by their index
+    pub fn parse_named(
+        text: &str,
+        names: &BTreeMap<usize, String>,
+    ) -> Result<Self, ConditionError> {
+        let mut parser = Parser {
+            text,
+            offset: 0,
+            names,
+            operators: 0,
+        };
+        let value = parser.value()?;
+        match parser.rest().is_empty() {
+            true => Ok(value),
+            false => Err(parser.error("expected \"+\", \"-\" or the end of the value")),
+        }
+    }
+    ///the number as it is for the machine right now, with cells read as signed when the
+    ///machine's are
+    pub fn evaluate(&self, tm: &TuringMachine) -> i64 {
+        match self {
+            Value::Number(number) => *number,
+            Value::Pointer => tm.pointer() as i64,
+            Value::Steps => tm.steps as i64,
+            Value::Cell(index) => usize::try_from(index.evaluate(tm))
+                .ok()
+                .and_then(|index| tm.tape().get(index))
+                .map_or(0, |cell| tm.cell_signedness.value(*cell) as i64),
+            Value::Add(left, right) => left.evaluate(tm).wrapping_add(right.evaluate(tm)),
+            Value::Subtract(left, right) => left.evaluate(tm).wrapping_sub(right.evaluate(tm)),
+        }
+    }
+}
+
+impl Condition {
+    ///the condition that cell number `index` compares to `value` like this
+    pub fn cell(index: usize, comparison: Comparison, value: i64) -> Self {
+        Condition::Compare(
+            Value::Cell(Box::new(Value::Number(index as i64))),
+            comparison,
+            Value::Number(value),
+        )
+    }
+    ///reads a condition from its text, like `cell[42] == 7 && ptr > 100`
+    pub fn parse(text: &str) -> Result<Self, ConditionError> {
+        Condition::parse_named(text, &BTreeMap::new())
+    }
+    ///the same as parse, with the names of cells by their index
+    pub fn parse_named(
+        text: &str,
+        names: &BTreeMap<usize, String>,
+    ) -> Result<Self, ConditionError> {
+        let mut parser = Parser {
+            text,
+            offset: 0,
+            names,
+            operators: 0,
+        };
+        let condition = parser.or()?;
+        match parser.rest().is_empty() {
+            true => Ok(condition),
+            false => Err(parser.error("expected \"&&\", \"||\" or the end of the condition")),
+        }
+    }
+    ///whether the condition holds for the machine as it is right now
+    pub fn holds(&self, tm: &TuringMachine) -> bool {
+        match self {
+            Condition::Compare(left, comparison, right) => {
+                let (left, right) = (left.evaluate(tm), right.evaluate(tm));
+                match comparison {
+                    Comparison::Equal => left == right,
+                    Comparison::NotEqual => left != right,
+                    Comparison::Less => left < right,
+                    Comparison::LessOrEqual => left <= right,
+                    Comparison::Greater => left > right,
+                    Comparison::GreaterOrEqual => left >= right,
+                }
+            }
+            Condition::And(left, right) => left.holds(tm) && right.holds(tm),
+            Condition::Or(left, right) => left.holds(tm) || right.holds(tm),
+            Condition::Not(condition) => !condition.holds(tm),
+        }
+    }
+}
+
+///reads a condition from left to right, skipping whitespace before everything it reads
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    names: &'a BTreeMap<usize, String>,
+    ///how many operators and brackets have been read
+    operators: usize,
+}
+
+impl Parser<'_> {
+    ///what's left after the whitespace at the current place
+    fn rest(&mut self) -> &str {
+        let rest = &self.text[self.offset..];
+        self.offset += rest.len() - rest.trim_start().len();
+        &self.text[self.offset..]
+    }
+    ///moves past the text if it comes next
+    fn eat(&mut self, text: &str) -> bool {
+        match self.rest().starts_with(text) {
+            true => {
+                self.offset += text.len();
+                true
+            }
+            false => false,
+        }
+    }
+    ///moves past the operator or bracket if it comes next, as long as there haven't been too
+    ///many already
+    fn operator(&mut self, text: &str) -> Result<bool, ConditionError> {
+        let start = self.offset;
+        if !self.eat(text) {
+            return Ok(false);
+        }
+        self.operators += 1;
+        if self.operators > MAX_OPERATORS {
+            self.offset = start;
+            return Err(self.error(&format!(
+                "there can only be {} operators and brackets in a condition",
+                MAX_OPERATORS
+            )));
+        }
+        Ok(true)
+    }
+    fn error(&mut self, message: &str) -> ConditionError {
+        self.rest();
+        ConditionError {
+            message: message.to_string(),
+            offset: self.offset,
+        }
+    }
+    fn or(&mut self) -> Result<Condition, ConditionError> {
+        let mut condition = self.and()?;
+        while self.operator("||")? {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+    fn and(&mut self) -> Result<Condition, ConditionError> {
+        let mut condition = self.not()?;
+        while self.operator("&&")? {
+            condition = Condition::And(Box::new(condition), Box::new(self.not()?));
+        }
+        Ok(condition)
+    }
+    fn not(&mut self) -> Result<Condition, ConditionError> {
+        //"!=" only ever comes after a value, so a "!" here is always a not
+        if self.operator("!")? {
+            return Ok(Condition::Not(Box::new(self.not()?)));
+        }
+        if self.operator("(")? {
+            let condition = self.or()?;
+            if !self.eat(")") {
+                return Err(self.error("expected \")\""));
+            }
+            return Ok(condition);
+        }
+        let left = self.value()?;
+        //the two character comparisons go first, so "<=" isn't read as "<"
+        let comparisons = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        let comparison = comparisons
+            .iter()
+            .find(|(text, _)| self.eat(text))
+            .map(|(_, comparison)| *comparison)
+            .ok_or_else(|| self.error("expected a comparison like \"==\" or \"<\""))?;
+        Ok(Condition::Compare(left, comparison, self.value()?))
+    }
+    fn value(&mut self) -> Result<Value, ConditionError> {
+        let mut value = self.term()?;
+        loop {
+            if self.operator("+")? {
+                value = Value::Add(Box::new(value), Box::new(self.term()?));
+            } else if self.operator("-")? {
+                value = Value::Subtract(Box::new(value), Box::new(self.term()?));
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+    fn term(&mut self) -> Result<Value, ConditionError> {
+        let rest = self.rest();
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 {
+            let number = rest[..digits]
+                .parse()
+                .map_err(|_| self.error("this number is too big"))?;
+            self.offset += digits;
+            return Ok(Value::Number(number));
+        }
+        let start = self.offset;
+        match self.word() {
+            "ptr" => Ok(Value::Pointer),
+            "steps" => Ok(Value::Steps),
+            "cell" => {
+                if !self.operator("[")? {
+                    return Ok(Value::Cell(Box::new(Value::Pointer)));
+                }
+                //a name in the brackets is the cell itself, not the number in it
+                let before = self.offset;
+                let index = match self.named() {
+                    Some(index) if self.next_is("]") => Value::Number(index as i64),
+                    _ => {
+                        self.offset = before;
+                        self.value()?
+                    }
+                };
+                if !self.eat("]") {
+                    return Err(self.error("expected \"]\""));
+                }
+                Ok(Value::Cell(Box::new(index)))
+            }
+            "" => Err(self.error("expected a number, \"ptr\", \"steps\" or \"cell\"")),
+            _ => {
+                self.offset = start;
+                let index = self.named().ok_or_else(|| self.unknown_name(start))?;
+                Ok(Value::Cell(Box::new(Value::Number(index as i64))))
+            }
+        }
+    }
+    ///moves past the next word, made of letters, digits and underscores, and gives it back
+    fn word(&mut self) -> &str {
+        let rest = self.rest();
+        let length = rest.len()
+            - rest
+                .trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_')
+                .len();
+        let start = self.offset;
+        self.offset += length;
+        &self.text[start..start + length]
+    }
+    fn next_is(&mut self, text: &str) -> bool {
+        self.rest().starts_with(text)
+    }
+    ///the index of the cell named by the next word, moving past it if it is a name
+    fn named(&mut self) -> Option<usize> {
+        let start = self.offset;
+        let word = self.word().to_string();
+        let index = self
+            .names
+            .iter()
+            .find(|(_, name)| **name == word)
+            .map(|(index, _)| *index);
+        if index.is_none() {
+            self.offset = start;
+        }
+        index
+    }
+    fn unknown_name(&mut self, start: usize) -> ConditionError {
+        self.offset = start;
+        let word = self.word().to_string();
+        self.offset = start;
+        let known: Vec<&str> = self.names.values().map(String::as_str).collect();
+        let message = match known.is_empty() {
+            true => format!("there is no cell named '{}', no cells have names", word),
+            false => format!(
+                "there is no cell named '{}', the names are {}",
+                word,
+                known.join(", ")
+            ),
+        };
+        self.error(&message)
+    }
+}
@@ -0,0 +1,478 @@
+//! every setting a machine runs with, in one place. the command line's options for running a
+//! program and the keys of a profile file are the same names, and both go through
+//! InterpreterConfig::set, so "--max-steps 1000" and "max-steps = 1000" in a profile mean exactly
+//! the same thing. the default is how a machine runs when nothing is said about it.
+//!
+//! with the "serde" feature a config can be deserialized from a map of those names to values,
+//! like a toml profile loaded with "--config profile.toml":
+//!
+//! ```toml
+//! eof = "max"
+//! max-steps = 1000000
+//! optimize = 2
+//! tiered = true
+//! ```
+use crate::optimize::Level;
+use crate::TuringMachine;
+use crate::{lang, program, random, tier, EofBehavior, ParseError, Position, Signedness};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+///what a cell holds
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CellWidth {
+    ///a byte that wraps around
+    Eight,
+    ///a number with no upper limit, see big.rs
+    Big,
+}
+
+impl CellWidth {
+    ///every width there's a name for, even big when this build doesn't have it, see available
+    pub const ALL: [CellWidth; 2] = [CellWidth::Eight, CellWidth::Big];
+    ///the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            CellWidth::Eight => "8",
+            CellWidth::Big => "big",
+        }
+    }
+    ///turns the name used on the command line into the matching width
+    pub fn from_name(name: &str) -> Option<Self> {
+        CellWidth::ALL
+            .into_iter()
+            .find(|width| width.name() == name)
+    }
+    ///whether this build can run with the width, which for big cells takes the "bigint" feature
+    pub fn available(self) -> bool {
+        match self {
+            CellWidth::Eight => true,
+            CellWidth::Big => cfg!(feature = "bigint"),
+        }
+    }
+}
+
+///everything that can be wrong with a config
+#[derive(PartialEq, Debug)]
+pub enum ConfigError {
+    ///the program the machine was being made for didn't parse
+    ParseError(ParseError),
+    ///there's no setting with the name
+    UnknownSetting(String),
+    ///the value can't be given to the setting, by the setting's name and the value
+    InvalidValue(String, String),
+    ///big cells were asked for, but this build doesn't have them
+    BigCellsUnavailable,
+    ///tiered along with big cells, whose loops can't be run in one go
+    TieredBigCells,
+    ///tiered along with track-writers, whose writes would be skipped in the loops run in one go
+    TieredWriters,
+    ///cell-underflow without big cells, the only cells that don't wrap around
+    UnderflowWithoutBigCells,
+    ///big cells for boolfuck, whose cells are bits
+    BigBits,
+    ///big cells were asked for, but the program has an instruction at the position that doesn't
+    ///work with them
+    NotPlain(Position),
+    ///a profile file that couldn't be read as one
+    #[cfg(feature = "serde")]
+    Profile(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::ParseError(e) => write!(f, "{}", e),
+            ConfigError::UnknownSetting(name) => write!(
+                f,
+                "there is no setting called '{}', the settings are {}",
+                name,
+                SETTINGS.join(", ")
+            ),
+            ConfigError::InvalidValue(name, value) => write!(
+                f,
+                "'{}' isn't something {} can be, it takes {}",
+                value,
+                name,
+                expected(name)
+            ),
+            ConfigError::BigCellsUnavailable => write!(
+                f,
+                "this bfint was built without big cells, build it with the \"bigint\" feature to \
+                 use them"
+            ),
+            ConfigError::TieredBigCells => write!(
+                f,
+                "tiered can't be used with cell-width big, a loop over cells that don't wrap \
+                 around can't be run in one go. leave one of them out"
+            ),
+            ConfigError::TieredWriters => write!(
+                f,
+                "tiered can't be used with track-writers, the loops it runs in one go don't say \
+                 which cells they write. leave one of them out"
+            ),
+            ConfigError::UnderflowWithoutBigCells => write!(
+                f,
+                "cell-underflow only means something with cell-width big, bytes wrap around \
+                 instead. set cell-width big too, or leave cell-underflow out"
+            ),
+            ConfigError::BigBits => write!(
+                f,
+                "boolfuck's cells hold a single bit, so they can't be big. leave cell-width out"
+            ),
+            ConfigError::NotPlain(position) => write!(
+                f,
+                "only plain brainfuck instructions work with big cells, there's another one at {}",
+                position
+            ),
+            #[cfg(feature = "serde")]
+            ConfigError::Profile(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+///how a machine is set up to run a program
+#[derive(PartialEq, Debug, Clone)]
+pub struct InterpreterConfig {
+    ///the language the program is in, or nothing to go by the extension of its file, and plain
+    ///brainfuck when there's no file
+    pub lang: Option<lang::Lang>,
+    pub eof: EofBehavior,
+    pub cell_width: CellWidth,
+    ///what "-" does to a big cell that's 0. only big cells can be set to anything but
+    ///saturating, which is what nothing means
+    #[cfg(feature = "bigint")]
+    pub cell_underflow: Option<crate::big::Underflow>,
+    pub cell_signedness: Signedness,
+    pub step_limit: Option<u64>,
+    pub output_limit: Option<u64>,
+    pub loop_iteration_limit: Option<u64>,
+    ///how many steps apart the machine looks for a state it's been in before, see cycles.rs
+    pub cycle_check_interval: Option<u64>,
+    pub call_depth_limit: usize,
+    pub eval_depth_limit: usize,
+    ///what makes "?" give the same bytes every run, or nothing for different ones every run
+    pub seed: Option<u64>,
+    ///how much the program is optimized before it runs
+    pub level: Level,
+    ///whether hot loops that only add are run in one go, see tier.rs
+    pub tiered: bool,
+    ///whether the machine keeps track of who wrote every cell last, see writers.rs
+    pub track_writers: bool,
+    ///how deep loops and procedures can be nested, see program::MAX_NESTING
+    pub max_nesting: usize,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        InterpreterConfig {
+            lang: None,
+            eof: EofBehavior::Zero,
+            cell_width: CellWidth::Eight,
+            #[cfg(feature = "bigint")]
+            cell_underflow: None,
+            cell_signedness: Signedness::Unsigned,
+            step_limit: None,
+            output_limit: None,
+            loop_iteration_limit: None,
+            cycle_check_interval: None,
+            call_depth_limit: 1024,
+            eval_depth_limit: 64,
+            seed: None,
+            level: Level::O0,
+            tiered: false,
+            track_writers: false,
+            max_nesting: program::MAX_NESTING,
+        }
+    }
+}
+
+impl InterpreterConfig {
+    ///sets the setting with the name to the value, both written the way they are on the
+    ///command line, like "eof" and "max" for "--eof max". settings that are only turned on, like
+    ///"tiered", take "true" or "false"
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = || ConfigError::InvalidValue(name.to_string(), value.to_string());
+        let number = || value.parse().map_err(|_| invalid());
+        if let Some((_, extension)) = EXTENSIONS.into_iter().find(|(setting, _)| *setting == name) {
+            self.set_extension(extension, flag(value).ok_or_else(invalid)?);
+            return Ok(());
+        }
+        //every name here is in SETTINGS, and every one of them that only works along with some
+        //others is checked in validate
+        match name {
+            "lang" => self.lang = Some(lang::Lang::from_name(value).ok_or_else(invalid)?),
+            "eof" => self.eof = EofBehavior::from_name(value).ok_or_else(invalid)?,
+            "cell-width" => self.cell_width = CellWidth::from_name(value).ok_or_else(invalid)?,
+            "cell-underflow" => self.set_underflow(value).ok_or_else(invalid)??,
+            "cell-signedness" => {
+                self.cell_signedness = Signedness::from_name(value).ok_or_else(invalid)?
+            }
+            "max-steps" => self.step_limit = Some(number()?),
+            "max-output" => self.output_limit = Some(number()?),
+            "max-loop-iterations" => self.loop_iteration_limit = Some(number()?),
+            "detect-cycles" => self.cycle_check_interval = Some(number()?),
+            //call-depth is what it used to be called
+            "max-call-depth" | "call-depth" => self.call_depth_limit = number()? as usize,
+            "eval-depth" => self.eval_depth_limit = number()? as usize,
+            "seed" => self.seed = Some(number()?),
+            "optimize" => self.level = Level::from_name(value).ok_or_else(invalid)?,
+            "tiered" => self.tiered = flag(value).ok_or_else(invalid)?,
+            "track-writers" => self.track_writers = flag(value).ok_or_else(invalid)?,
+            "max-nesting" => self.max_nesting = number()? as usize,
+            _ => return Err(ConfigError::UnknownSetting(name.to_string())),
+        }
+        Ok(())
+    }
+    ///what the setting is set to, the way set would take it, or nothing when it isn't set to
+    ///anything, like max-steps without a limit. gives back UnknownSetting for a name that isn't
+    ///in SETTINGS
+    pub fn get(&self, name: &str) -> Result<Option<String>, ConfigError> {
+        let number = |value: Option<u64>| value.map(|value| value.to_string());
+        if let Some((_, extension)) = EXTENSIONS.into_iter().find(|(setting, _)| *setting == name) {
+            return Ok(Some((self.lang == Some(extension)).to_string()));
+        }
+        Ok(match name {
+            "lang" => self
+                .lang
+                .as_ref()
+                .and_then(lang::Lang::name)
+                .map(String::from),
+            "eof" => Some(self.eof.name().to_string()),
+            "cell-width" => Some(self.cell_width.name().to_string()),
+            #[cfg(feature = "bigint")]
+            "cell-underflow" => self
+                .cell_underflow
+                .map(|underflow| underflow.name().to_string()),
+            #[cfg(not(feature = "bigint"))]
+            "cell-underflow" => None,
+            "cell-signedness" => Some(self.cell_signedness.name().to_string()),
+            "max-steps" => number(self.step_limit),
+            "max-output" => number(self.output_limit),
+            "max-loop-iterations" => number(self.loop_iteration_limit),
+            "detect-cycles" => number(self.cycle_check_interval),
+            "max-call-depth" | "call-depth" => Some(self.call_depth_limit.to_string()),
+            "eval-depth" => Some(self.eval_depth_limit.to_string()),
+            "seed" => number(self.seed),
+            "optimize" => Some(self.level.name().to_string()),
+            "tiered" => Some(self.tiered.to_string()),
+            "track-writers" => Some(self.track_writers.to_string()),
+            "max-nesting" => Some(self.max_nesting.to_string()),
+            _ => return Err(ConfigError::UnknownSetting(name.to_string())),
+        })
+    }
+    ///"ext-halt" and "ext-channels" are other ways of saying the language is bf-halt or
+    ///bf-channels
+    fn set_extension(&mut self, extension: lang::Lang, on: bool) {
+        if on {
+            self.lang = Some(extension);
+        } else if self.lang == Some(extension) {
+            self.lang = None;
+        }
+    }
+    ///how a program in the language is parsed to run with this config
+    pub fn parse_options(&self, lang: &lang::Lang) -> program::ParseOptions {
+        program::ParseOptions {
+            lang: lang.clone(),
+            max_nesting: self.max_nesting,
+            ..program::ParseOptions::default()
+        }
+    }
+    ///sets cell_underflow, or gives back nothing if the value isn't one
+    #[cfg(feature = "bigint")]
+    fn set_underflow(&mut self, value: &str) -> Option<Result<(), ConfigError>> {
+        self.cell_underflow = Some(crate::big::Underflow::from_name(value)?);
+        Some(Ok(()))
+    }
+    ///there's no cell_underflow without big cells
+    #[cfg(not(feature = "bigint"))]
+    fn set_underflow(&mut self, _: &str) -> Option<Result<(), ConfigError>> {
+        Some(Err(ConfigError::BigCellsUnavailable))
+    }
+    ///the language of the program in the file at the path: the one asked for, or the one
+    ///Lang::detect guesses when none was
+    #[cfg(feature = "std")]
+    pub fn lang_for(&self, path: &str, source: &str) -> lang::Lang {
+        self.lang
+            .clone()
+            .unwrap_or_else(|| lang::Lang::detect(path, source))
+    }
+    ///makes sure the settings make sense together. every setting that only works along with
+    ///some others, or not with them, says so here, so a machine never starts out doing something
+    ///other than what it was asked to
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.cell_width == CellWidth::Big {
+            if cfg!(not(feature = "bigint")) {
+                return Err(ConfigError::BigCellsUnavailable);
+            }
+            if self.tiered {
+                return Err(ConfigError::TieredBigCells);
+            }
+            if self.lang == Some(lang::Lang::Boolfuck) {
+                return Err(ConfigError::BigBits);
+            }
+        }
+        if self.tiered && self.track_writers {
+            return Err(ConfigError::TieredWriters);
+        }
+        #[cfg(feature = "bigint")]
+        if self.cell_underflow.is_some() && self.cell_width != CellWidth::Big {
+            return Err(ConfigError::UnderflowWithoutBigCells);
+        }
+        Ok(())
+    }
+    ///sets the machine up to run the way the config says. the program isn't optimized here,
+    ///that's up to whoever made the machine, since a machine that has started running can't
+    ///count on its tape being all zeroes
+    pub fn apply(&self, tm: &mut TuringMachine) -> Result<(), ConfigError> {
+        self.validate()?;
+        //the program was parsed before the machine got here, maybe with another limit
+        if let Some(position) = tm.program.nested_past(self.max_nesting) {
+            return Err(ConfigError::ParseError(ParseError::TooDeep(
+                position,
+                self.max_nesting,
+            )));
+        }
+        tm.eof_behavior = self.eof;
+        tm.cell_signedness = self.cell_signedness;
+        tm.step_limit = self.step_limit;
+        tm.output_limit = self.output_limit;
+        tm.loop_iteration_limit = self.loop_iteration_limit;
+        tm.detect_cycles(self.cycle_check_interval);
+        tm.call_depth_limit = self.call_depth_limit;
+        tm.eval_depth_limit = self.eval_depth_limit;
+        if let Some(seed) = self.seed {
+            tm.rng = random::Rng::from_seed(seed);
+        }
+        tm.set_tiering(self.tiered.then_some(tier::DEFAULT_THRESHOLD));
+        if self.track_writers {
+            tm.track_writers();
+        }
+        #[cfg(feature = "bigint")]
+        if self.cell_width == CellWidth::Big {
+            let underflow = self
+                .cell_underflow
+                .unwrap_or(crate::big::Underflow::Saturate);
+            tm.use_big_cells(underflow).map_err(ConfigError::NotPlain)?;
+        }
+        Ok(())
+    }
+    ///reads a profile, see the top of this file
+    #[cfg(feature = "serde")]
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(|e| ConfigError::Profile(e.to_string().trim_end().to_string()))
+    }
+}
+
+///the names set knows
+pub const SETTINGS: [&str; 18] = [
+    "lang",
+    "ext-halt",
+    "ext-channels",
+    "eof",
+    "cell-width",
+    "cell-underflow",
+    "cell-signedness",
+    "max-steps",
+    "max-output",
+    "max-loop-iterations",
+    "detect-cycles",
+    "max-call-depth",
+    "eval-depth",
+    "seed",
+    "optimize",
+    "tiered",
+    "track-writers",
+    "max-nesting",
+];
+
+///the settings that turn on an extension of brainfuck, which are other ways of setting lang, by
+///the language they set it to
+pub const EXTENSIONS: [(&str, lang::Lang); 2] = [
+    ("ext-halt", lang::Lang::BfHalt),
+    ("ext-channels", lang::Lang::BfChannels),
+];
+
+///what the setting can be set to, for saying so when it's set to something else. the names come
+///from the same lists set goes by
+pub fn expected(name: &str) -> String {
+    match name {
+        "lang" => either(lang::Lang::ALL.iter().filter_map(lang::Lang::name)),
+        "eof" => either(EofBehavior::ALL.map(EofBehavior::name)),
+        "cell-width" => either(CellWidth::ALL.map(CellWidth::name)),
+        #[cfg(feature = "bigint")]
+        "cell-underflow" => either(crate::big::Underflow::ALL.map(crate::big::Underflow::name)),
+        #[cfg(not(feature = "bigint"))]
+        "cell-underflow" => "nothing, this bfint was built without big cells".to_string(),
+        "cell-signedness" => either(Signedness::ALL.map(Signedness::name)),
+        "optimize" => either(Level::ALL.map(Level::name)),
+        "ext-halt" | "ext-channels" | "tiered" | "track-writers" => "true or false".to_string(),
+        _ => "a number".to_string(),
+    }
+}
+
+///the names as "a, b or c"
+fn either<'a>(names: impl IntoIterator<Item = &'a str>) -> String {
+    let names: Vec<&str> = names.into_iter().collect();
+    match names.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+///"true" or "false"
+fn flag(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+impl TuringMachine {
+    ///a new machine for the program, in the config's language or plain brainfuck, optimized
+    ///and set up the way the config says
+    pub fn with_config(source: &str, config: &InterpreterConfig) -> Result<Self, ConfigError> {
+        let lang = config.lang.clone().unwrap_or(lang::Lang::Brainfuck);
+        let program = program::parse(source, &config.parse_options(&lang))
+            .map_err(|mut errors| ConfigError::ParseError(errors.swap_remove(0)))?;
+        let program = program
+            .optimized(&config.level.pipeline(), true, |_, _| {})
+            .0;
+        let mut tm = TuringMachine::with_program(Arc::new(program));
+        config.apply(&mut tm)?;
+        Ok(tm)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InterpreterConfig {
+    ///a map of setting names to their values, every one of them given to set in turn on top of
+    ///the default. the values can be strings, numbers or booleans
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Text(String),
+            Number(u64),
+            Flag(bool),
+        }
+        let settings = alloc::collections::BTreeMap::<String, Value>::deserialize(deserializer)?;
+        let mut config = InterpreterConfig::default();
+        for (name, value) in settings {
+            let value = match value {
+                Value::Text(text) => text,
+                Value::Number(number) => number.to_string(),
+                Value::Flag(on) => on.to_string(),
+            };
+            config.set(&name, &value).map_err(D::Error::custom)?;
+        }
+        config.validate().map_err(D::Error::custom)?;
+        Ok(config)
+    }
+}
@@ -0,0 +1,147 @@
+//! pausing, resuming and cancelling a run from another thread, for front ends that run the
+//! program on a worker thread and keep their own thread free for the user. the machine can be
+//! made on one thread, moved to the worker to run, and joined back to be looked at once it
+//! stops, while the handle stays with whoever is in charge of it
+#[cfg(feature = "std")]
+use crate::TuringMachine;
+use crate::{Position, RuntimeError};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Condvar, Mutex};
+
+#[cfg(feature = "std")]
+const RUNNING: u8 = 0;
+#[cfg(feature = "std")]
+const PAUSED: u8 = 1;
+#[cfg(feature = "std")]
+const CANCELLED: u8 = 2;
+
+///what running a single instruction with TuringMachine::step did
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum StepResult {
+    ///the instruction ran
+    Ran,
+    ///the program counter was past the last instruction, so there was nothing to run. that's
+    ///the only way a program ends, and it isn't counted as a step
+    Halted,
+}
+
+///how a run ended when it didn't fail
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RunOutcome {
+    ///the program ran out of instructions
+    Halted,
+    ///the run was stopped with ControlHandle::cancel. the machine is left exactly where it
+    ///stopped, so it can be looked at or run some more
+    Cancelled,
+    ///the machine's fuel ran out before the instruction at the position, with the stage the
+    ///fuel was given to if it was given one. the machine can carry on once there's more
+    FuelExhausted {
+        stage: Option<usize>,
+        position: Position,
+    },
+    ///whatever was reading the output went away, like "head" does once it has all it wants, so
+    ///there was no point in going on. nothing went wrong with the program itself
+    OutputClosed,
+}
+
+///a run that failed because it ran out of fuel or because its output was closed, as the outcome
+///it is instead
+pub(crate) fn settle(
+    outcome: Result<RunOutcome, RuntimeError>,
+) -> Result<RunOutcome, RuntimeError> {
+    match outcome {
+        Err(RuntimeError::FuelExhausted(stage, position)) => {
+            Ok(RunOutcome::FuelExhausted { stage, position })
+        }
+        Err(RuntimeError::OutputClosed) => Ok(RunOutcome::OutputClosed),
+        outcome => outcome,
+    }
+}
+
+#[cfg(feature = "std")]
+///the state shared between a machine and its handles
+#[derive(Default)]
+pub(crate) struct Control {
+    state: AtomicU8,
+    //the paused machine waits on this until it is resumed or cancelled. the lock is taken
+    //whenever the state changes so the wake up can't get lost in between
+    lock: Mutex<()>,
+    wake: Condvar,
+}
+
+#[cfg(feature = "std")]
+impl Control {
+    ///what the machine should do next. blocks for as long as it is paused, and gives back true
+    ///if it has been cancelled
+    pub(crate) fn cancelled(&self) -> bool {
+        match self.state.load(Ordering::Relaxed) {
+            RUNNING => false,
+            CANCELLED => true,
+            _ => {
+                let mut guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+                while self.state.load(Ordering::Acquire) == PAUSED {
+                    guard = self.wake.wait(guard).unwrap_or_else(|e| e.into_inner());
+                }
+                self.state.load(Ordering::Acquire) == CANCELLED
+            }
+        }
+    }
+    fn set(&self, state: u8) {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.state.store(state, Ordering::Release);
+        self.wake.notify_all();
+    }
+}
+
+#[cfg(feature = "std")]
+///controls a running machine from any thread. every clone controls the same machine
+#[derive(Clone)]
+pub struct ControlHandle {
+    control: Arc<Control>,
+}
+
+#[cfg(feature = "std")]
+impl ControlHandle {
+    ///makes the machine stop and wait the next time it checks in, until it's resumed
+    pub fn pause(&self) {
+        //a cancelled run stays cancelled
+        let _ = self.control.state.compare_exchange(
+            RUNNING,
+            PAUSED,
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        );
+    }
+    ///lets a paused machine carry on
+    pub fn resume(&self) {
+        if self.control.state.load(Ordering::Acquire) == PAUSED {
+            self.control.set(RUNNING);
+        }
+    }
+    ///makes run return RunOutcome::Cancelled the next time the machine checks in, even when it
+    ///is paused. the machine can only be run again after a reset
+    pub fn cancel(&self) {
+        self.control.set(CANCELLED);
+    }
+    ///whether cancel has been called since the last reset
+    pub fn is_cancelled(&self) -> bool {
+        self.control.state.load(Ordering::Acquire) == CANCELLED
+    }
+    ///clears a cancel, so the machine can be run again. also lets a paused machine carry on
+    pub fn reset(&self) {
+        self.control.set(RUNNING);
+    }
+}
+
+#[cfg(feature = "std")]
+impl TuringMachine {
+    ///gives back a handle that can pause, resume and cancel this machine from another thread
+    pub fn control_handle(&mut self) -> ControlHandle {
+        let control = self.control.get_or_insert_with(Default::default);
+        ControlHandle {
+            control: Arc::clone(control),
+        }
+    }
+}
@@ -0,0 +1,497 @@
+//! running a whole directory of programs under several configs and checking they all print the
+//! same, for "bfint crosscheck". it's what tells whether an optimization level, tiering or big
+//! cells change what any program does, before they're trusted with it.
+//!
+//! the configs come from a file of named sections, each one the settings of an
+//! InterpreterConfig, with the same names as a profile. the reference config is the one every
+//! other is compared with, the first one unless `reference` says otherwise:
+//!
+//! ```toml
+//! reference = "plain"
+//!
+//! [plain]
+//!
+//! [o2]
+//! optimize = 2
+//!
+//! [tiered]
+//! optimize = 2
+//! tiered = true
+//! ```
+//!
+//! every program in the directory in a language known by its extension is run, with the
+//! "name.in" next to it as its input if there is one. a config's run matches when it printed the
+//! same bytes as the reference and ended the same way, halting or failing with the same error.
+//! when it doesn't, the run is made again to stop right before the first byte that's different,
+//! and the machine there is kept as a core dump for "bfint inspect"
+use crate::checkpoint::CoreDump;
+use crate::config::{ConfigError, InterpreterConfig};
+use crate::control::RunOutcome;
+use crate::lang::Lang;
+use crate::provenance::{self, Provenance};
+use crate::report::json_string;
+use crate::snapshot::TapeDiff;
+use crate::{program, Program, TuringMachine};
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+///everything that can be wrong with a file of configs
+#[derive(PartialEq, Debug)]
+pub enum ConfigsError {
+    ///the line, counting from 1, is neither a section, a setting nor blank
+    Syntax(usize),
+    ///a setting on the line that the config can't take
+    Setting(usize, ConfigError),
+    ///the section on the line has the name of one before it
+    DuplicateName(usize, String),
+    ///a setting on the line before any section
+    OutsideSection(usize),
+    ///reference names a config that isn't in the file
+    UnknownReference(String),
+    ///there are fewer than two configs, which leaves nothing to compare
+    TooFew,
+}
+
+impl fmt::Display for ConfigsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigsError::Syntax(line) => write!(
+                f,
+                "line {} should be a [name], a setting like optimize = 2, or blank",
+                line
+            ),
+            ConfigsError::Setting(line, e) => write!(f, "line {}: {}", line, e),
+            ConfigsError::DuplicateName(line, name) => {
+                write!(f, "line {}: there's already a config called {}", line, name)
+            }
+            ConfigsError::OutsideSection(line) => write!(
+                f,
+                "line {}: settings go in a config's section, only reference goes before them",
+                line
+            ),
+            ConfigsError::UnknownReference(name) => {
+                write!(
+                    f,
+                    "the reference is {}, but there's no config called that",
+                    name
+                )
+            }
+            ConfigsError::TooFew => write!(f, "there have to be at least two configs to compare"),
+        }
+    }
+}
+
+///the configs to run every program under, in the order they're in the file
+#[derive(PartialEq, Debug, Clone)]
+pub struct Configs {
+    pub configs: Vec<(String, InterpreterConfig)>,
+    ///the index of the config the others are compared with
+    pub reference: usize,
+}
+
+impl Configs {
+    ///reads a file of configs, see the top of this file
+    pub fn parse(text: &str) -> Result<Self, ConfigsError> {
+        let mut configs: Vec<(String, InterpreterConfig)> = vec![];
+        let mut reference = None;
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let name = unquote(name.trim());
+                if configs.iter().any(|(existing, _)| *existing == name) {
+                    return Err(ConfigsError::DuplicateName(number, name));
+                }
+                configs.push((name, InterpreterConfig::default()));
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or(ConfigsError::Syntax(number))?;
+            let (key, value) = (key.trim(), unquote(value.trim()));
+            match configs.last_mut() {
+                Some((_, config)) => config
+                    .set(key, &value)
+                    .map_err(|e| ConfigsError::Setting(number, e))?,
+                None if key == "reference" => reference = Some(value),
+                None => return Err(ConfigsError::OutsideSection(number)),
+            }
+        }
+        if configs.len() < 2 {
+            return Err(ConfigsError::TooFew);
+        }
+        let reference = match reference {
+            Some(name) => configs
+                .iter()
+                .position(|(existing, _)| *existing == name)
+                .ok_or(ConfigsError::UnknownReference(name))?,
+            None => 0,
+        };
+        Ok(Configs { configs, reference })
+    }
+}
+
+///the value without the quotes around it, if it's a string
+fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(value) => value.to_string(),
+        None => value.to_string(),
+    }
+}
+
+///a program in the directory, with its input
+#[derive(PartialEq, Debug, Clone)]
+pub struct Entry {
+    ///the name of the program file, without the directory
+    pub name: String,
+    pub path: PathBuf,
+    pub input: Vec<u8>,
+}
+
+///every program in the directory, sorted by name
+pub fn discover(directory: &Path) -> std::io::Result<Vec<Entry>> {
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if Lang::from_extension(&path.to_string_lossy()).is_none() {
+            continue;
+        }
+        let input_path = path.with_extension("in");
+        let input = match input_path.exists() {
+            true => std::fs::read(&input_path)?,
+            false => vec![],
+        };
+        entries.push(Entry {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path,
+            input,
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+///how one run went
+#[derive(PartialEq, Debug, Clone)]
+pub struct Run {
+    pub output: Vec<u8>,
+    ///the error it failed with, nothing if it halted
+    pub error: Option<String>,
+    pub steps: u64,
+}
+
+///runs the program under the config, on the input. the step limit is for configs that don't
+///have one, so a program that never halts doesn't hold everything up. the machine is given back
+///as it was at the end, for a core dump
+pub fn run(
+    source: &str,
+    path: &Path,
+    input: &[u8],
+    config: &InterpreterConfig,
+    step_limit: u64,
+) -> Result<(Run, TuringMachine), ConfigError> {
+    let lang = config
+        .lang
+        .clone()
+        .unwrap_or_else(|| Lang::from_path(&path.to_string_lossy()));
+    let program = Program::compile(source, &lang, config.level).map_err(ConfigError::ParseError)?;
+    let mut tm = TuringMachine::with_program(Arc::new(program));
+    config.apply(&mut tm)?;
+    tm.step_limit = tm.step_limit.or(Some(step_limit));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    //brainfork's machines run on their own and count their own steps
+    let result = match lang {
+        Lang::Brainfork => crate::fork::run(tm.clone()),
+        _ => tm.run(),
+    };
+    let error = match result {
+        Ok(RunOutcome::Halted) => None,
+        Ok(RunOutcome::Cancelled) => Some("cancelled".to_string()),
+        Ok(RunOutcome::FuelExhausted { position, .. }) => {
+            Some(format!("ran out of fuel at {}", position))
+        }
+        Ok(RunOutcome::OutputClosed) => Some(crate::io::OUTPUT_CLOSED.to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+    let run = Run {
+        output: std::mem::take(&mut *crate::shared::lock(&output)),
+        error,
+        steps: tm.steps,
+    };
+    Ok((run, tm))
+}
+
+///the offset of the first byte where the run's output is different from the reference's, or
+///where the shorter one ends. two runs that print the same but end differently are different
+///at the end of their output. nothing when they match
+pub fn divergence(reference: &Run, run: &Run) -> Option<usize> {
+    let first = reference
+        .output
+        .iter()
+        .zip(&run.output)
+        .position(|(a, b)| a != b);
+    match first {
+        Some(offset) => Some(offset),
+        None if reference.output.len() != run.output.len() || reference.error != run.error => {
+            Some(reference.output.len().min(run.output.len()))
+        }
+        None => None,
+    }
+}
+
+///how a config did on a program, next to the reference
+#[derive(PartialEq, Debug, Clone)]
+pub enum Verdict {
+    Match,
+    ///the output was different from the byte at the offset on, and the machine was kept from
+    ///right before it wrote that byte, along with how its tape was different from the
+    ///reference's right before the reference wrote it
+    Mismatch {
+        offset: usize,
+        dump: Box<CoreDump>,
+        tape: TapeDiff,
+    },
+    ///the config couldn't run the program at all, for this reason
+    Unusable(String),
+}
+
+///how every config did on one program
+#[derive(PartialEq, Debug, Clone)]
+pub struct Row {
+    pub name: String,
+    ///by the index of the config, the reference's own being a Match
+    pub verdicts: Vec<Verdict>,
+}
+
+///runs the program under every config and compares them with the reference
+pub fn check(entry: &Entry, configs: &Configs, step_limit: u64) -> Row {
+    let row = |verdicts| Row {
+        name: entry.name.clone(),
+        verdicts,
+    };
+    let source = match program::read_source(&entry.path) {
+        Ok(source) => source,
+        Err(e) => {
+            return row(vec![
+                Verdict::Unusable(e.to_string());
+                configs.configs.len()
+            ])
+        }
+    };
+    let runs: Vec<_> = configs
+        .configs
+        .iter()
+        .map(|(_, config)| run(&source, &entry.path, &entry.input, config, step_limit))
+        .collect();
+    let reference = match &runs[configs.reference] {
+        Ok((reference, _)) => reference,
+        Err(e) => {
+            return row(vec![
+                Verdict::Unusable(e.to_string());
+                configs.configs.len()
+            ])
+        }
+    };
+    let reference_name = &configs.configs[configs.reference].0;
+    let verdicts = runs
+        .iter()
+        .zip(&configs.configs)
+        .map(|(result, (name, config))| {
+            let (other, _) = match result {
+                Ok(other) => other,
+                Err(e) => return Verdict::Unusable(e.to_string()),
+            };
+            let Some(offset) = divergence(reference, other) else {
+                return Verdict::Match;
+            };
+            //once more, stopping before the byte that's different. a run that's different
+            //because it ended early stops at its end anyway
+            let mut stopping = config.clone();
+            stopping.output_limit = Some(offset as u64);
+            let tm = match run(&source, &entry.path, &entry.input, &stopping, step_limit) {
+                Ok((_, tm)) => tm,
+                Err(e) => return Verdict::Unusable(e.to_string()),
+            };
+            let mut reference_stopping = configs.configs[configs.reference].1.clone();
+            reference_stopping.output_limit = Some(offset as u64);
+            let tape = match run(
+                &source,
+                &entry.path,
+                &entry.input,
+                &reference_stopping,
+                step_limit,
+            ) {
+                Ok((_, reference_tm)) => TapeDiff::compute(reference_tm.tape(), tm.tape()),
+                Err(e) => return Verdict::Unusable(e.to_string()),
+            };
+            let error = format!(
+                "the output of {} differs from {}'s at byte {}: {} instead of {}",
+                name,
+                reference_name,
+                offset,
+                describe(other, offset),
+                describe(reference, offset)
+            );
+            Verdict::Mismatch {
+                offset,
+                dump: Box::new(CoreDump {
+                    provenance: Some(provenance(entry, &source, config)),
+                    ..CoreDump::new(&entry.path.to_string_lossy(), &error, &source, &tm)
+                }),
+                tape,
+            }
+        })
+        .collect();
+    row(verdicts)
+}
+
+///what a run of the program under the config was, for its core dump
+fn provenance(entry: &Entry, source: &str, config: &InterpreterConfig) -> Provenance {
+    let path = entry.path.to_string_lossy();
+    let lang = config
+        .lang
+        .clone()
+        .unwrap_or_else(|| Lang::from_path(&path));
+    let input = match entry.input.is_empty() {
+        true => "none".to_string(),
+        false => provenance::describe_input(
+            &entry.path.with_extension("in").to_string_lossy(),
+            &entry.input,
+        ),
+    };
+    Provenance {
+        input,
+        seed: config
+            .seed
+            .map_or("none, different every run".to_string(), |seed| {
+                seed.to_string()
+            }),
+        ..Provenance::new(config, &lang, source, 0)
+    }
+}
+
+///what the run has at the offset of its output: the byte, or how it ended when its output is
+///over by then
+fn describe(run: &Run, offset: usize) -> String {
+    match (run.output.get(offset), &run.error) {
+        (Some(byte), _) if byte.is_ascii_graphic() || *byte == b' ' => {
+            format!("'{}'", *byte as char)
+        }
+        (Some(byte), _) => format!("byte {}", byte),
+        (None, Some(error)) => format!("failing with \"{}\"", error),
+        (None, None) => "halting".to_string(),
+    }
+}
+
+///the table of every program against every config but the reference, "ok" for a match and the
+///offset of the first byte that's different for a mismatch
+pub fn render(configs: &Configs, rows: &[Row]) -> String {
+    let columns: Vec<usize> = (0..configs.configs.len())
+        .filter(|&index| index != configs.reference)
+        .collect();
+    let cell = |verdict: &Verdict| match verdict {
+        Verdict::Match => "ok".to_string(),
+        Verdict::Mismatch { offset, .. } => format!("@{}", offset),
+        Verdict::Unusable(_) => "n/a".to_string(),
+    };
+    let width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("program".len());
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|&column| {
+            let cells = rows.iter().map(|row| cell(&row.verdicts[column]).len());
+            cells
+                .max()
+                .unwrap_or(0)
+                .max(configs.configs[column].0.len())
+        })
+        .collect();
+    let mut text = format!("compared with {}\n", configs.configs[configs.reference].0);
+    let _ = write!(text, "{:width$}", "program");
+    for (&column, &column_width) in columns.iter().zip(&widths) {
+        let _ = write!(text, "  {:column_width$}", configs.configs[column].0);
+    }
+    text.push('\n');
+    for row in rows {
+        let _ = write!(text, "{:width$}", row.name);
+        for (&column, &column_width) in columns.iter().zip(&widths) {
+            let _ = write!(text, "  {:column_width$}", cell(&row.verdicts[column]));
+        }
+        text.truncate(text.trim_end().len());
+        text.push('\n');
+    }
+    text
+}
+
+///the name of the core dump of the config's run of the program, in the directory dumps are
+///written to
+pub fn dump_name(program: &str, config: &str) -> String {
+    let stem = Path::new(program)
+        .file_stem()
+        .map_or("program".into(), |stem| stem.to_string_lossy());
+    format!("{}-{}.bfdump", stem, config)
+}
+
+///the results as json: the configs, the reference, and for every program what every config
+///did, with where the core dump of every mismatch is
+pub fn to_json(configs: &Configs, rows: &[Row], dumps: &Path) -> String {
+    let names: Vec<String> = configs
+        .configs
+        .iter()
+        .map(|(name, _)| json_string(name))
+        .collect();
+    let mut json = format!(
+        "{{\"reference\":{},\"configs\":[{}],\"programs\":[",
+        names[configs.reference],
+        names.join(",")
+    );
+    for (index, row) in rows.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        let _ = write!(
+            json,
+            "\n{{\"name\":{},\"results\":{{",
+            json_string(&row.name)
+        );
+        for (column, verdict) in row.verdicts.iter().enumerate() {
+            if column > 0 {
+                json.push(',');
+            }
+            let result = match verdict {
+                Verdict::Match => "{\"match\":true}".to_string(),
+                Verdict::Mismatch { offset, dump, tape } => {
+                    let path = dumps.join(dump_name(&row.name, &configs.configs[column].0));
+                    format!(
+                        "{{\"match\":false,\"offset\":{},\"detail\":{},\"dump\":{},\"tape\":{}}}",
+                        offset,
+                        json_string(&dump.error),
+                        json_string(&path.to_string_lossy()),
+                        tape.to_json()
+                    )
+                }
+                Verdict::Unusable(reason) => {
+                    format!("{{\"match\":null,\"error\":{}}}", json_string(reason))
+                }
+            };
+            let _ = write!(json, "{}:{}", names[column], result);
+        }
+        json.push_str("}}");
+    }
+    json.push_str("\n]}\n");
+    json
+}
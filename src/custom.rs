@@ -0,0 +1,120 @@
+//! instructions that the program embedding the interpreter adds itself, without changing the
+//! interpreter. every custom instruction is a character along with a closure that runs whenever
+//! the program reaches that character. characters that aren't registered stay comments
+use crate::shared::{lock, shared, Shared};
+use crate::{lang, EofBehavior, ParseError, Program, RuntimeError, TuringMachine};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+///the closure behind a custom instruction, shared between clones of a machine
+pub(crate) type Handler = Shared<dyn FnMut(&mut ExecContext) -> Result<(), RuntimeError> + Send>;
+
+///the custom instructions to give a machine, see TuringMachine::with_custom_instructions
+#[derive(Clone, Default)]
+pub struct CustomInstructions {
+    pub(crate) instructions: Vec<(char, Handler)>,
+}
+
+impl CustomInstructions {
+    pub fn new() -> Self {
+        CustomInstructions::default()
+    }
+    ///makes the character run the closure. registering a character that the language already
+    ///uses replaces what the language does with it, and registering it twice keeps the last one
+    pub fn custom_instruction(
+        &mut self,
+        character: char,
+        handler: impl FnMut(&mut ExecContext) -> Result<(), RuntimeError> + Send + 'static,
+    ) -> &mut Self {
+        self.instructions
+            .retain(|(registered, _)| *registered != character);
+        self.instructions.push((character, shared(handler)));
+        self
+    }
+    ///the registered characters, in the order of the index Instruction::Custom has for them
+    pub(crate) fn characters(&self) -> Vec<char> {
+        self.instructions
+            .iter()
+            .map(|(character, _)| *character)
+            .collect()
+    }
+}
+
+///what a custom instruction can do to the machine running it
+pub struct ExecContext<'a> {
+    tm: &'a mut TuringMachine,
+}
+
+impl ExecContext<'_> {
+    ///the value of the current cell
+    pub fn cell(&self) -> u8 {
+        self.tm.tape[self.tm.pointer]
+    }
+    ///changes the value of the current cell
+    pub fn set_cell(&mut self, value: u8) {
+        self.tm.tape[self.tm.pointer] = value;
+        if let Some(writers) = &mut self.tm.writers {
+            writers.record(
+                self.tm.pointer,
+                crate::writers::Write {
+                    step: self.tm.steps,
+                    position: self.tm.program.positions[self.tm.program_counter],
+                    instruction: self.tm.program.ops[self.tm.program_counter].instruction(),
+                },
+            );
+        }
+    }
+    ///the index of the current cell
+    pub fn pointer(&self) -> usize {
+        self.tm.pointer
+    }
+    ///moves the pointer by this many cells, to the left for negative numbers. fails the same
+    ///way "<" and ">" do when that would take it off the tape
+    pub fn move_pointer(&mut self, cells: isize) -> Result<(), RuntimeError> {
+        match self.tm.pointer.checked_add_signed(cells) {
+            Some(pointer) if pointer < self.tm.tape.len() => {
+                self.tm.pointer = pointer;
+                Ok(())
+            }
+            _ => Err(RuntimeError::PointerOutOfBounds(
+                self.tm.program.positions[self.tm.program_counter],
+            )),
+        }
+    }
+    ///writes a byte to the output, like "." does
+    pub fn output(&mut self, byte: u8) -> Result<(), RuntimeError> {
+        self.tm.write_byte(byte)
+    }
+    ///reads a byte of input, or nothing if the input has run out. when the machine is driven by
+    ///events this can fail with RuntimeError::InputNeeded, the instruction then runs again from
+    ///the start once there is input, so it's best to read before changing anything else
+    pub fn input(&mut self) -> Result<Option<u8>, RuntimeError> {
+        self.tm.read_byte()
+    }
+    ///what "," would put in the cell at the end of the input
+    pub fn eof_behavior(&self) -> EofBehavior {
+        self.tm.eof_behavior
+    }
+}
+
+impl TuringMachine {
+    ///the same as TuringMachine::new, but with the characters of the custom instructions running
+    ///their closures
+    pub fn with_custom_instructions(
+        program: &str,
+        lang: &lang::Lang,
+        custom: CustomInstructions,
+    ) -> Result<Self, ParseError> {
+        let program = Program::parse(program, lang, &custom.characters())?;
+        let mut tm = TuringMachine::with_program(Arc::new(program));
+        tm.custom = custom;
+        Ok(tm)
+    }
+    ///executes a custom instruction by handing its closure the machine
+    pub(crate) fn run_custom(&mut self, index: usize) -> Result<(), RuntimeError> {
+        let handler = Shared::clone(&self.custom.instructions[index].1);
+        (lock(&handler))(&mut ExecContext { tm: self })?;
+        self.program_counter += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! noticing a program that's stuck going around the same states forever, with
+//! "--detect-cycles". every so many steps the machine's state is hashed (see
+//! TuringMachine::state_hash), and the hashes of the last few times are kept. a program that
+//! reads or writes anything can still be going somewhere, so the hashes are forgotten every
+//! time it does.
+//!
+//! a hash that comes up again only says the state probably repeated, two different states can
+//! have the same hash. so the state is kept, and a program that really is going around in a
+//! cycle is back in exactly that state after as many steps again, which is checked by comparing
+//! all of it. only then does the run fail, with RuntimeError::InfiniteLoop.
+//!
+//! the states are only looked at every so many steps, so a cycle is found once some multiple of
+//! its length is a multiple of that, within the hashes that are kept. nothing is looked at while
+//! "%" runs a program of its own, or with big cells, whose bytes don't hold all of what's in them
+use crate::{Instruction, RuntimeError, TuringMachine};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+///how many of the last hashes are kept
+const REMEMBERED: usize = 1024;
+
+///everything about a machine that decides what it does next, apart from what it reads
+#[derive(PartialEq, Debug, Clone)]
+struct State {
+    //the cells up to the last one that isn't 0, or the pointer if that's further
+    tape: Vec<u8>,
+    pointer: usize,
+    program_counter: usize,
+    //where every procedure call returns to, and where every procedure that's defined starts, by
+    //its number
+    calls: Vec<Option<usize>>,
+    procedures: Vec<(u8, usize)>,
+    storage: u8,
+    rng: u64,
+    input_bits: (u8, u32),
+    output_bits: (u8, u32),
+}
+
+impl State {
+    fn hash(&self) -> u64 {
+        let mut bytes = Vec::with_capacity(self.tape.len() + 64);
+        //the length goes first, so a tape can't run into what comes after it
+        bytes.extend((self.tape.len() as u64).to_le_bytes());
+        bytes.extend(&self.tape);
+        bytes.extend((self.pointer as u64).to_le_bytes());
+        bytes.extend((self.program_counter as u64).to_le_bytes());
+        for call in &self.calls {
+            bytes.extend(call.map_or(u64::MAX, |call| call as u64).to_le_bytes());
+        }
+        for (number, start) in &self.procedures {
+            bytes.push(*number);
+            bytes.extend((*start as u64).to_le_bytes());
+        }
+        bytes.push(self.storage);
+        bytes.extend(self.rng.to_le_bytes());
+        bytes.extend([self.input_bits.0, self.output_bits.0]);
+        bytes.extend(self.input_bits.1.to_le_bytes());
+        bytes.extend(self.output_bits.1.to_le_bytes());
+        crate::program::fnv1a_bytes(bytes)
+    }
+}
+
+///what the machine knows about the states it's been in, when it's looking for cycles
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct CycleDetector {
+    pub(crate) every: u64,
+    //the step the state is looked at next
+    next_check: u64,
+    //the hashes of the last states looked at, with the step of each, oldest first
+    recent: VecDeque<(u64, u64)>,
+    //a state whose hash came up before, the step it was in it, and the step it should be in it
+    //again if it's in a cycle
+    suspect: Option<(State, u64, u64)>,
+}
+
+impl TuringMachine {
+    ///makes the machine look at its state every so many steps from now on, and fail with
+    ///RuntimeError::InfiniteLoop once it's sure the program is going around in a cycle without
+    ///reading or writing anything, or stops doing that with None
+    pub fn detect_cycles(&mut self, every: Option<u64>) {
+        self.cycles = every.map(|every| CycleDetector {
+            every: every.max(1),
+            next_check: self.steps,
+            recent: VecDeque::new(),
+            suspect: None,
+        });
+    }
+    ///a hash of everything that decides what the machine does next, apart from its input and
+    ///what it has written that hasn't gone out yet: the used part of the tape, the pointer, the
+    ///program counter, and the call stack, procedures, storage cell, random numbers and bits
+    ///of the languages that have them. it's the same for the same state on every run and every
+    ///build, and cheap enough to take every few thousand steps. with big cells, only their
+    ///lowest bytes are in it
+    pub fn state_hash(&self) -> u64 {
+        self.state().hash()
+    }
+    ///the cells up to the last one that isn't 0, or the pointer if that's further
+    pub(crate) fn used_tape(&self) -> &[u8] {
+        let last_used = self.tape.iter().rposition(|cell| *cell != 0).unwrap_or(0);
+        &self.tape[..=last_used.max(self.pointer)]
+    }
+    fn state(&self) -> State {
+        State {
+            tape: self.used_tape().to_vec(),
+            pointer: self.pointer,
+            program_counter: self.program_counter,
+            calls: self.calls.iter().map(|call| call.return_to).collect(),
+            procedures: (0..=255)
+                .filter_map(|number| Some((number, self.procedures[number as usize]?)))
+                .collect(),
+            storage: self.storage,
+            //the generator only decides anything for a program with bf-rand's "?" in it. every
+            //machine starts it from a seed of its own, so for any other program it would only
+            //make the same state hash differently on every machine
+            rng: match self
+                .program
+                .instructions()
+                .any(|i| i == Instruction::Random)
+            {
+                true => self.rng.state,
+                false => 0,
+            },
+            input_bits: self.input_bits,
+            output_bits: self.output_bits,
+        }
+    }
+    ///forgets every state it has been in, for when the program reads or writes something
+    pub(crate) fn forget_states(&mut self) {
+        if let Some(cycles) = &mut self.cycles {
+            cycles.recent.clear();
+            cycles.suspect = None;
+        }
+    }
+    ///looks at the state when it's time to, and fails when it's sure the program is in a cycle
+    pub(crate) fn check_for_cycle(&mut self) -> Result<(), RuntimeError> {
+        let Some(cycles) = &self.cycles else {
+            return Ok(());
+        };
+        if self.steps < cycles.next_check || self.eval_depth > 0 {
+            return Ok(());
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_cells.is_some() {
+            return Ok(());
+        }
+        let state = self.state();
+        let hash = state.hash();
+        let steps = self.steps;
+        let cycles = self.cycles.as_mut().expect("checked above");
+        cycles.next_check = steps + cycles.every;
+        if let Some((suspect, from, due)) = cycles.suspect.take() {
+            match steps == due && state == suspect {
+                true => {
+                    return Err(RuntimeError::InfiniteLoop(
+                        from,
+                        steps,
+                        self.program.positions[self.program_counter],
+                    ))
+                }
+                //it wasn't a cycle after all, or the steps went past it in one go
+                false if steps < due => cycles.suspect = Some((suspect, from, due)),
+                false => {}
+            }
+        }
+        if cycles.suspect.is_none() {
+            if let Some(&(_, before)) = cycles.recent.iter().rev().find(|(old, _)| *old == hash) {
+                cycles.suspect = Some((state, steps, steps + (steps - before)));
+            }
+        }
+        if let Some((_, _, due)) = &cycles.suspect {
+            cycles.next_check = cycles.next_check.min(*due);
+        }
+        if cycles.recent.len() == REMEMBERED {
+            cycles.recent.pop_front();
+        }
+        cycles.recent.push_back((hash, steps));
+        Ok(())
+    }
+}
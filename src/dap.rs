@@ -0,0 +1,309 @@
+//! a debug adapter, so editors that speak the debug adapter protocol (like vs code) can debug
+//! programs. it speaks the protocol over a reader and a writer, which are stdin and stdout for
+//! "bfint dap", and drives a Debugger the same way the debug command does.
+//!
+//! there's one thread, and the frames of its stack are the loops the program is in, the
+//! innermost on top. the requests it understands are initialize, launch, setBreakpoints,
+//! configurationDone, threads, stackTrace, scopes, variables, continue, next, stepIn, stepOut,
+//! disconnect and terminate. launch takes the `"program"` to run, and optionally an `"input"`
+//! file to read from, a `"stepLimit"` and `"stopOnEntry"`. stepIn runs one instruction, next
+//! runs one too unless it's a "[", then it runs the whole loop, and stepOut runs until the
+//! innermost loop is done
+use crate::debugger::{Debugger, Stop, StopReason};
+use crate::{annotations, framing, lang, Frame, Position, TuringMachine};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+///the variablesReference of each scope
+const MACHINE: i64 = 1;
+const TAPE: i64 = 2;
+const NAMES: i64 = 3;
+
+///how many cells on either side of the pointer the tape scope shows
+const TAPE_WINDOW: usize = 8;
+
+///the program being debugged, once it's been launched
+struct Launched {
+    debugger: Debugger,
+    path: String,
+    stop_on_entry: bool,
+}
+
+///a debug adapter writing to out
+pub struct Server<W: Write> {
+    out: W,
+    //the seq of the last message sent
+    seq: i64,
+    launched: Option<Launched>,
+    //whether the client has said it's done, after which nothing more is read
+    done: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(out: W) -> Self {
+        Server {
+            out,
+            seq: 0,
+            launched: None,
+            done: false,
+        }
+    }
+    ///reads requests from input and answers them until the client disconnects or the input ends
+    pub fn serve(&mut self, mut input: impl BufRead) -> io::Result<()> {
+        while !self.done {
+            let Some(message) = framing::read_message(&mut input)? else {
+                break;
+            };
+            self.handle(&message)?;
+        }
+        Ok(())
+    }
+    ///answers one request
+    pub fn handle(&mut self, request: &Value) -> io::Result<()> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let arguments = &request["arguments"];
+        let body = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsConditionalBreakpoints": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "configurationDone" => Ok(json!({})),
+            "threads" => Ok(json!({ "threads": [{ "id": 1, "name": "main" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => self.scopes(),
+            "variables" => self.variables(arguments),
+            "continue" => Ok(json!({ "allThreadsContinued": true })),
+            "next" | "stepIn" | "stepOut" => Ok(json!({})),
+            "disconnect" | "terminate" => {
+                self.done = true;
+                Ok(json!({}))
+            }
+            _ => Err(format!("{} isn't supported", command)),
+        };
+        let succeeded = body.is_ok();
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": succeeded,
+            "command": command,
+        });
+        match body {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)?;
+        if !succeeded {
+            return Ok(());
+        }
+        //what happens next is told with events, after the response
+        match command {
+            "launch" => self.event("initialized", json!({})),
+            "configurationDone" => match &self.launched {
+                Some(launched) if launched.stop_on_entry => self.stopped("entry", json!({})),
+                Some(_) => self.run(|debugger| debugger.resume(None)),
+                None => Ok(()),
+            },
+            "continue" => self.run(|debugger| debugger.resume(None)),
+            "next" => self.run(Debugger::step_over),
+            "stepIn" => self.run(|debugger| debugger.resume(Some(1))),
+            "stepOut" => self.run(Debugger::step_out),
+            "terminate" => self.event("terminated", json!({})),
+            _ => Ok(()),
+        }
+    }
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String> {
+        let path = arguments["program"]
+            .as_str()
+            .ok_or("launch needs the \"program\" to run")?
+            .to_string();
+        let source = crate::program::read_source(&path).map_err(|e| e.to_string())?;
+        let lang = lang::Lang::from_path(&path);
+        let mut tm = TuringMachine::new(&source, &lang).map_err(|e| format!("{}: {}", path, e))?;
+        tm.step_limit = arguments["stepLimit"].as_u64();
+        let input = match arguments["input"].as_str() {
+            Some(input_path) => std::fs::read(input_path)
+                .map_err(|e| format!("could not read {}: {}", input_path, e))?,
+            None => vec![],
+        };
+        let mut debugger = Debugger::new(tm, input);
+        debugger.names =
+            annotations::cell_names(&source).map_err(|e| format!("{}: {}", path, e))?;
+        self.launched = Some(Launched {
+            debugger,
+            path,
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+        });
+        Ok(json!({}))
+    }
+    ///puts the breakpoints on the first instruction of each line, taking away the ones that were
+    ///there before. a line without instructions gets an unverified breakpoint
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        let launched = self.launched.as_mut().ok_or("nothing has been launched")?;
+        let tm = &mut launched.debugger.tm;
+        for id in tm.breakpoint_ids() {
+            tm.remove_breakpoint(id);
+        }
+        let instructions = tm.find(|_| true);
+        let wanted = arguments["breakpoints"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut breakpoints = vec![];
+        for breakpoint in wanted {
+            let line = breakpoint["line"].as_u64().unwrap_or(0) as usize;
+            let condition = breakpoint["condition"].as_str().filter(|c| !c.is_empty());
+            let first = instructions.iter().find(|position| position.line == line);
+            let set = first.map(|&position| {
+                launched
+                    .debugger
+                    .add_breakpoint(position, condition)
+                    .map(|id| (id, position))
+            });
+            breakpoints.push(match set {
+                Some(Ok((id, position))) => json!({
+                    "id": id,
+                    "verified": true,
+                    "line": position.line,
+                    "column": position.column,
+                }),
+                Some(Err(e)) => {
+                    json!({ "verified": false, "line": line, "message": e.to_string() })
+                }
+                None => json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "there are no instructions on this line",
+                }),
+            });
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+    ///the instruction the machine is at, and under it every loop it's inside
+    fn stack_trace(&self) -> Result<Value, String> {
+        let launched = self.launched.as_ref().ok_or("nothing has been launched")?;
+        let tm = &launched.debugger.tm;
+        let source = json!({ "path": launched.path });
+        let here = tm
+            .position()
+            .or(tm.last_position())
+            .unwrap_or(Position { line: 1, column: 1 });
+        let mut frames = vec![json!({
+            "id": 0,
+            "name": "program",
+            "source": source,
+            "line": here.line,
+            "column": here.column,
+        })];
+        for (depth, frame) in tm.backtrace().into_iter().enumerate() {
+            let (name, start) = match frame {
+                Frame::Loop(start, _) => (format!("loop at {}", start), start),
+                Frame::Call(site) => (format!("call at {}", site), site),
+            };
+            frames.push(json!({
+                "id": depth + 1,
+                "name": name,
+                "source": source,
+                "line": start.line,
+                "column": start.column,
+            }));
+        }
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+    fn scopes(&self) -> Result<Value, String> {
+        let launched = self.launched.as_ref().ok_or("nothing has been launched")?;
+        let mut scopes = vec![
+            json!({ "name": "Machine", "variablesReference": MACHINE, "expensive": false }),
+            json!({ "name": "Tape", "variablesReference": TAPE, "expensive": false }),
+        ];
+        if !launched.debugger.names.is_empty() {
+            scopes.push(
+                json!({ "name": "Named cells", "variablesReference": NAMES, "expensive": false }),
+            );
+        }
+        Ok(json!({ "scopes": scopes }))
+    }
+    fn variables(&self, arguments: &Value) -> Result<Value, String> {
+        let launched = self.launched.as_ref().ok_or("nothing has been launched")?;
+        let debugger = &launched.debugger;
+        let tm = &debugger.tm;
+        let cell = |index: usize| tm.cell_signedness.value(tm.tape()[index]).to_string();
+        let variable = |name: String, value: String| json!({ "name": name, "value": value, "variablesReference": 0 });
+        let variables: Vec<Value> = match arguments["variablesReference"].as_i64() {
+            Some(MACHINE) => vec![
+                variable("pointer".to_string(), tm.pointer().to_string()),
+                variable("cell".to_string(), cell(tm.pointer())),
+                variable("steps".to_string(), tm.steps.to_string()),
+            ],
+            Some(TAPE) => {
+                let start = tm.pointer().saturating_sub(TAPE_WINDOW);
+                let end = (tm.pointer() + TAPE_WINDOW).min(tm.tape().len() - 1);
+                (start..=end)
+                    .map(|index| variable(format!("cell[{}]", index), cell(index)))
+                    .collect()
+            }
+            Some(NAMES) => debugger
+                .names
+                .iter()
+                .map(|(index, name)| variable(format!("{} ({})", name, index), cell(*index)))
+                .collect(),
+            _ => return Err("there are no such variables".to_string()),
+        };
+        Ok(json!({ "variables": variables }))
+    }
+    ///runs the program with the debugger and says how it went
+    fn run(&mut self, run: impl FnOnce(&mut Debugger) -> Stop) -> io::Result<()> {
+        let Some(launched) = self.launched.as_mut() else {
+            return Ok(());
+        };
+        let stop = run(&mut launched.debugger);
+        if !stop.output.is_empty() {
+            let output = String::from_utf8_lossy(&stop.output).into_owned();
+            self.event("output", json!({ "category": "stdout", "output": output }))?;
+        }
+        match stop.reason {
+            StopReason::Stepped => self.stopped("step", json!({})),
+            StopReason::Breakpoint(id) => {
+                self.stopped("breakpoint", json!({ "hitBreakpointIds": [id] }))
+            }
+            StopReason::OutputMatched(position) => self.stopped(
+                "breakpoint",
+                json!({ "description": format!("the output matched at {}", position) }),
+            ),
+            StopReason::Interrupted => self.stopped("pause", json!({})),
+            //a program that failed stays where it failed, so what it got wrong can be looked at
+            StopReason::Error(e) => {
+                let message = e.to_string();
+                self.event(
+                    "output",
+                    json!({ "category": "stderr", "output": format!("error: {}\n", message) }),
+                )?;
+                self.stopped(
+                    "exception",
+                    json!({ "description": message.clone(), "text": message }),
+                )
+            }
+            StopReason::Halted | StopReason::Finished => {
+                self.event("exited", json!({ "exitCode": 0 }))?;
+                self.event("terminated", json!({}))
+            }
+        }
+    }
+    fn stopped(&mut self, reason: &str, mut body: Value) -> io::Result<()> {
+        body["reason"] = json!(reason);
+        body["threadId"] = json!(1);
+        body["allThreadsStopped"] = json!(true);
+        self.event("stopped", body)
+    }
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+    ///writes the message with the next seq, after the header the protocol wants
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        framing::send(&mut self.out, &message)
+    }
+}
@@ -0,0 +1,559 @@
+//! a debugger driven by text commands, the same ones whether they're typed at its prompt or read
+//! from a script. a session holds a machine and carries out one command line at a time, handing
+//! back what it has to say about it:
+//!
+//! - `break <line>:<column> [if <condition>]`, or `b`, puts a breakpoint on the instruction there,
+//!   which only stops when the condition holds if there is one (see condition.rs)
+//! - `run`, or `r`, runs until a breakpoint, an error or the end of the program
+//! - `step [n]`, or `s`, runs one instruction, or n of them, without stopping at breakpoints
+//! - `next`, or `n`, runs one instruction, or a whole loop when it's at a "[", stopping inside
+//!   it only for breakpoints and errors
+//! - `finish`, or `f`, runs until the innermost loop it's inside of is done, stopping earlier
+//!   for breakpoints and errors
+//! - `print [value]`, or `p`, shows a value like `cell[3]` or `ptr`, the current cell by default.
+//!   a cell also shows the step and instruction that last wrote it, if the machine keeps track
+//!   of its writers (see writers.rs), which stopping shows for the current cell too
+//! - `dump`, or `d`, shows where the machine is and the cells around the pointer
+//! - `history`, or `h`, shows the last instructions that ran, if the machine keeps them
+//! - `backtrace`, or `bt`, shows the loops and pbrain procedure calls the machine is inside,
+//!   from the innermost out
+//! - `snapshot` marks the tape as it is now, and `diff` shows the cells that changed since
+//! - `eval <code>` runs a bit of code on the tape as it is, with the pointer where it is, and
+//!   shows what it wrote and the cells it changed, then puts the machine back as it was (see
+//!   snippet.rs). it reads from the same input, gets SNIPPET_STEPS steps to do it in, and is in
+//!   the program's language. `eval! <code>` keeps what it did to the tape and the pointer, when
+//!   it got to its end, and nothing from before that can be gone back to with goto anymore
+//! - `goto <n>`, or `g`, takes the machine to the step n, forwards by running up to it and
+//!   backwards by running again from a checkpoint before it (see timeline.rs). `goto -<n>` and
+//!   `goto +<n>` go that many steps back or forward. breakpoints don't stop it
+//! - `quit`, or `q`, ends the session
+//!
+//! a session can also watch the output for a pattern (see watch.rs), and stop running the
+//! moment the program writes it
+//!
+//! empty lines and lines starting with "#" are skipped. cells that have names in the program's
+//! comments (see annotations.rs) are shown with them, and can be called by them in values and
+//! conditions
+use crate::condition::{Condition, Value};
+use crate::control::RunOutcome;
+use crate::events::Event;
+use crate::lang::Lang;
+use crate::snapshot::{Snapshot, TapeDiff};
+use crate::snippet::{Snippet, SNIPPET_STEPS};
+use crate::style;
+use crate::timeline::{self, Timeline};
+use crate::watch::OutputWatch;
+use crate::{Frame, Position, RuntimeError, TuringMachine};
+use std::collections::BTreeMap;
+use std::fmt;
+
+///a command line the debugger couldn't carry out
+#[derive(PartialEq, Debug, Clone)]
+pub enum DebugError {
+    ///the first word isn't a command, it's in here
+    UnknownCommand(String),
+    ///the command was known but what came after it wasn't right, the message says why
+    BadArgument(String),
+}
+
+impl fmt::Display for DebugError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DebugError::UnknownCommand(command) => write!(f, "unknown command '{}'", command),
+            DebugError::BadArgument(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+///a machine being debugged, along with the input it reads from
+pub struct Debugger {
+    pub tm: TuringMachine,
+    input: Vec<u8>,
+    //how much of the input the program has read
+    read: usize,
+    //the checkpoints made along the way, for going back
+    timeline: Timeline,
+    //whether the program has halted or failed, after which there's nothing left to run
+    finished: bool,
+    //whether quit has been given
+    quit: bool,
+    //the names of cells, by their index
+    pub names: BTreeMap<usize, String>,
+    //the tape as it was at the last snapshot command
+    mark: Option<Snapshot>,
+    //whether every breakpoint that's hit shows the cells that changed since the one before
+    pub diff_at_breakpoints: bool,
+    //the tape as it was when the last breakpoint was hit, or at the start
+    at_breakpoint: Snapshot,
+    //the pattern to stop at when the program writes it
+    pub watch: Option<OutputWatch>,
+    //the language eval's code is in, which should be the program's
+    pub lang: Lang,
+}
+
+impl Debugger {
+    ///a session for the machine, which reads the input when it asks for some. a machine that
+    ///failed already can be handed over too, to look at where it went wrong
+    pub fn new(tm: TuringMachine, input: Vec<u8>) -> Self {
+        let finished = !tm.has_instructions_left();
+        Debugger {
+            at_breakpoint: Snapshot::new(&tm),
+            timeline: Timeline::new(&tm, 0, timeline::DEFAULT_BUDGET),
+            tm,
+            input,
+            read: 0,
+            finished,
+            quit: false,
+            mark: None,
+            diff_at_breakpoints: false,
+            names: BTreeMap::new(),
+            watch: None,
+            lang: Lang::Brainfuck,
+        }
+    }
+    ///whether the session has been ended with quit
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+    ///carries out one command line, giving back what it printed. the program's output while
+    ///running is in there too, as it was written. this is only a front end for people, every
+    ///command is carried out by the methods below that mi.rs uses as well
+    pub fn execute(&mut self, line: &str) -> Result<String, DebugError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(String::new());
+        }
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command {
+            "break" | "b" => {
+                let (place, condition) = match rest.split_once(" if ") {
+                    Some((place, condition)) => (place.trim(), Some(condition)),
+                    None => (rest, None),
+                };
+                let position = parse_place(place)?;
+                let id = self.add_breakpoint(position, condition)?;
+                Ok(format!("breakpoint {} at {}\n", id, position))
+            }
+            "run" | "r" => Ok(self.render(|debugger| debugger.resume(None))),
+            "step" | "s" => {
+                let count = match rest {
+                    "" => 1,
+                    count => count.parse().map_err(|_| {
+                        DebugError::BadArgument(format!("'{}' isn't a number of steps", count))
+                    })?,
+                };
+                Ok(self.render(|debugger| debugger.resume(Some(count))))
+            }
+            "next" | "n" => Ok(self.render(Debugger::step_over)),
+            "finish" | "f" => Ok(self.render(Debugger::step_out)),
+            "print" | "p" => {
+                let value = match rest {
+                    "" => Value::Cell(Box::new(Value::Pointer)),
+                    text => Value::parse_named(text, &self.names)
+                        .map_err(|e| DebugError::BadArgument(e.to_string()))?,
+                };
+                //a cell says who wrote it last too, when the machine keeps track
+                if let (Value::Cell(index), Some(_)) = (&value, self.tm.writers()) {
+                    if let Ok(index) = usize::try_from(index.evaluate(&self.tm)) {
+                        return Ok(format!("{}\n", self.tm.describe_cell(index)));
+                    }
+                }
+                Ok(format!("{}\n", value.evaluate(&self.tm)))
+            }
+            "dump" | "d" => Ok(format!("{}\n", self.tm.describe_state_named(&self.names))),
+            "history" | "h" => match self.tm.history() {
+                Some(history) => Ok(history
+                    .entries()
+                    .map(|entry| format!("{}\n", entry))
+                    .collect()),
+                None => Err(DebugError::BadArgument(
+                    "there's no history, the machine isn't keeping one".to_string(),
+                )),
+            },
+            "backtrace" | "bt" => Ok(render_backtrace(&self.tm)),
+            "snapshot" => {
+                self.mark = Some(Snapshot::new(&self.tm));
+                Ok(format!(
+                    "marked the tape {}\n",
+                    self.tm.describe_state_named(&self.names)
+                ))
+            }
+            "diff" => match &self.mark {
+                Some(mark) => Ok(render_diff(&mark.diff(&self.tm), &self.names)),
+                None => Err(DebugError::BadArgument(
+                    "there's nothing to diff against, use snapshot first".to_string(),
+                )),
+            },
+            "goto" | "g" => {
+                let step = |text: &str| {
+                    text.parse::<u64>().map_err(|_| {
+                        DebugError::BadArgument(format!("'{}' isn't a step like 1234", rest))
+                    })
+                };
+                let step = match rest.split_at(rest.len().min(1)) {
+                    ("-", back) => self.tm.steps.saturating_sub(step(back)?),
+                    ("+", ahead) => self.tm.steps.saturating_add(step(ahead)?),
+                    _ => step(rest)?,
+                };
+                let stop = self.goto(step)?;
+                Ok(self.render(|_| stop))
+            }
+            "eval" | "eval!" => {
+                let keep = command == "eval!";
+                let snippet = self.eval(rest, keep)?;
+                Ok(render_snippet(&snippet, keep, &self.names))
+            }
+            "quit" | "q" => {
+                self.end();
+                Ok(String::new())
+            }
+            _ => Err(DebugError::UnknownCommand(command.to_string())),
+        }
+    }
+    ///ends the session
+    pub fn end(&mut self) {
+        self.quit = true;
+    }
+    ///puts a breakpoint on the first instruction at or after the position, which only stops
+    ///when the condition holds if there is one. gives back the breakpoint's id
+    pub fn add_breakpoint(
+        &mut self,
+        position: Position,
+        condition: Option<&str>,
+    ) -> Result<usize, DebugError> {
+        match condition {
+            Some(condition) => {
+                let condition = Condition::parse_named(condition, &self.names)
+                    .map_err(|e| DebugError::BadArgument(e.to_string()))?;
+                self.tm.add_breakpoint_if(position, condition)
+            }
+            None => self.tm.add_breakpoint(position),
+        }
+        .ok_or_else(|| {
+            DebugError::BadArgument(format!("there is no instruction at or after {}", position))
+        })
+    }
+    ///the value of something like `cell[3] + 1`, with cell names allowed in it
+    pub fn evaluate(&self, value: &str) -> Result<i64, DebugError> {
+        let value = Value::parse_named(value, &self.names)
+            .map_err(|e| DebugError::BadArgument(e.to_string()))?;
+        Ok(value.evaluate(&self.tm))
+    }
+    ///whether a condition like `cell[3] == 1` holds right now, with cell names allowed in it
+    pub fn condition_holds(&self, condition: &str) -> Result<bool, DebugError> {
+        let condition = Condition::parse_named(condition, &self.names)
+            .map_err(|e| DebugError::BadArgument(e.to_string()))?;
+        Ok(condition.holds(&self.tm))
+    }
+    ///runs the code on the machine as it is, see eval at the top of this file, and keeps what it
+    ///did to the tape and the pointer when it's asked to and the code didn't fail
+    pub fn eval(&mut self, code: &str, keep: bool) -> Result<Snippet, DebugError> {
+        if code.is_empty() {
+            return Err(DebugError::BadArgument(
+                "eval needs some code to run".to_string(),
+            ));
+        }
+        let snippet = self
+            .tm
+            .run_snippet(
+                code,
+                &self.lang,
+                &self.input[self.read.min(self.input.len())..],
+                SNIPPET_STEPS,
+            )
+            .map_err(|e| DebugError::BadArgument(e.to_string()))?;
+        if keep && snippet.outcome == Ok(RunOutcome::Halted) {
+            self.tm.keep_snippet(&snippet);
+            self.read += snippet.read;
+            //running again from a checkpoint wouldn't make the same changes
+            self.timeline = Timeline::new(&self.tm, self.read, timeline::DEFAULT_BUDGET);
+        }
+        Ok(snippet)
+    }
+    ///runs until something worth stopping for, or for this many steps. gives back the program's
+    ///output along with why it stopped
+    pub fn resume(&mut self, steps: Option<u64>) -> Stop {
+        match steps {
+            Some(steps) => {
+                let until = self.tm.steps + steps;
+                self.run_until(
+                    Some(&move |tm: &TuringMachine| tm.steps >= until),
+                    Breakpoints::Ignored,
+                )
+            }
+            None => self.run_until(None, Breakpoints::Stop),
+        }
+    }
+    ///takes the machine to the step, running up to it when it's ahead and running up to it again
+    ///from the last checkpoint before it when it's behind. it doesn't stop for breakpoints, and
+    ///only the output of running ahead is given back, what's run again was already written.
+    ///nothing can be gone back to from before the step the session started at
+    pub fn goto(&mut self, step: u64) -> Result<Stop, DebugError> {
+        if step >= self.tm.steps {
+            let done = move |tm: &TuringMachine| tm.steps >= step;
+            return Ok(self.run_until(Some(&done), Breakpoints::Ignored));
+        }
+        let moment = self.timeline.before(step).ok_or_else(|| {
+            DebugError::BadArgument(format!(
+                "can't go back to step {}, nothing before step {} can be gone back to",
+                step,
+                self.timeline.start()
+            ))
+        })?;
+        self.tm
+            .resume(&self.tm.program().source.clone(), &moment.state)
+            .expect("the timeline's checkpoints are of this machine");
+        self.read = moment.read;
+        self.tm.provided_input = None;
+        if self.tm.writers().is_some() {
+            self.tm.set_writers(moment.writers.clone());
+        }
+        if let Some(history) = self.tm.history.as_mut() {
+            history.forget_after(moment.steps);
+        }
+        self.finished = false;
+        //the output was already written and matched the first time around
+        let watch = self.watch.take();
+        let done = move |tm: &TuringMachine| tm.steps >= step;
+        let stop = self.run_until(Some(&done), Breakpoints::Ignored);
+        self.watch = watch;
+        Ok(Stop {
+            output: vec![],
+            reason: stop.reason,
+        })
+    }
+    ///runs one instruction, unless it's a "[", then it runs until the whole loop is done. it
+    ///stops earlier for breakpoints inside the loop, like resume does
+    pub fn step_over(&mut self) -> Stop {
+        match self.tm.loop_at() {
+            //whether the loop ends at its "]" or is skipped at its "[", it carries on just after
+            //the "]", which makes that the last position
+            Some((_, end)) => self.run_until(
+                Some(&move |tm: &TuringMachine| tm.last_position() == Some(end)),
+                Breakpoints::StopAfterHere,
+            ),
+            None => self.resume(Some(1)),
+        }
+    }
+    ///runs until the innermost loop the machine is in has finished, or something else worth
+    ///stopping for, like a breakpoint inside it. outside of loops it runs like resume does
+    pub fn step_out(&mut self) -> Stop {
+        match self.tm.enclosing_loops().first() {
+            //whether the loop ends at its "]" or is skipped at its "[", the "]" ran last
+            Some(&(_, end)) => self.run_until(
+                Some(&move |tm: &TuringMachine| tm.last_position() == Some(end)),
+                Breakpoints::StopAfterHere,
+            ),
+            None => self.resume(None),
+        }
+    }
+    ///runs until done says to stop, which is looked at before every instruction, or until an
+    ///event worth stopping for. breakpoints only stop it when they're asked to
+    fn run_until(
+        &mut self,
+        done: Option<&dyn Fn(&TuringMachine) -> bool>,
+        breakpoints: Breakpoints,
+    ) -> Stop {
+        if self.finished {
+            return Stop {
+                output: vec![],
+                reason: StopReason::Finished,
+            };
+        }
+        let mut output = vec![];
+        let started_at = self.tm.steps;
+        let reason = loop {
+            self.timeline.record(&self.tm, self.read);
+            if done.is_some_and(|done| done(&self.tm)) {
+                break StopReason::Stepped;
+            }
+            //a run without an end in sight still comes back for every checkpoint that's due
+            let event = match done {
+                Some(_) => self.tm.run_for(1),
+                None => self.tm.run_for(self.timeline.until_due(self.tm.steps)),
+            };
+            match event {
+                Some(Event::Output(byte)) => {
+                    output.push(byte);
+                    if let Some(watch) = self.watch.as_mut() {
+                        if watch.feed(byte) {
+                            let position = self.tm.last_position().expect("a '.' just ran");
+                            break StopReason::OutputMatched(position);
+                        }
+                    }
+                }
+                Some(Event::InputNeeded) => {
+                    self.tm.provide_input(self.input.get(self.read).copied());
+                    self.read += 1;
+                }
+                //the next run_for runs the instruction anyway
+                Some(Event::Breakpoint(_)) if breakpoints == Breakpoints::Ignored => {}
+                Some(Event::Breakpoint(_))
+                    if breakpoints == Breakpoints::StopAfterHere && self.tm.steps == started_at => {
+                }
+                Some(Event::Breakpoint(id)) => break StopReason::Breakpoint(id),
+                Some(Event::Halted) => {
+                    self.finished = true;
+                    break StopReason::Halted;
+                }
+                Some(Event::Error(e)) => {
+                    self.finished = true;
+                    break StopReason::Error(e);
+                }
+                Some(Event::Cancelled) => break StopReason::Interrupted,
+                None => {}
+            }
+        };
+        Stop { output, reason }
+    }
+    ///runs the way it's told to, and says what happened the way people read it
+    fn render(&mut self, run: impl FnOnce(&mut Debugger) -> Stop) -> String {
+        let stop = run(self);
+        let mut text = String::from_utf8_lossy(&stop.output).into_owned();
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        let stopped = match stop.reason {
+            StopReason::Finished => return "the program has already finished\n".to_string(),
+            StopReason::Stepped => {
+                text.push_str(&format!("{}\n", self.tm.describe_state_named(&self.names)));
+                return text;
+            }
+            StopReason::Halted => "the program finished".to_string(),
+            StopReason::Error(e) => format!("error: {}", e),
+            StopReason::Interrupted => "interrupted".to_string(),
+            StopReason::Breakpoint(id) => style::bold(&format!("stopped at breakpoint {}", id)),
+            StopReason::OutputMatched(position) => style::bold(&format!(
+                "stopped at the '.' at {}, {}",
+                position,
+                self.watch
+                    .as_ref()
+                    .expect("only a watch matches")
+                    .describe_match()
+            )),
+        };
+        if self.finished {
+            text.push_str(&format!("{}\n", stopped));
+            return text;
+        }
+        text.push_str(&format!(
+            "{} {}\n",
+            stopped,
+            self.tm.describe_state_named(&self.names)
+        ));
+        if self.tm.writers().is_some() {
+            text.push_str(&format!("{}\n", self.tm.describe_cell(self.tm.pointer())));
+        }
+        if self.diff_at_breakpoints {
+            text.push_str(&render_diff(
+                &self.at_breakpoint.diff(&self.tm),
+                &self.names,
+            ));
+            self.at_breakpoint = Snapshot::new(&self.tm);
+        }
+        text
+    }
+}
+
+///which breakpoints stop a run
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Breakpoints {
+    Ignored,
+    Stop,
+    //every one but a breakpoint on the instruction the run starts at, which would stop it
+    //before it got anywhere
+    StopAfterHere,
+}
+
+///what the program wrote while running, and why it stopped
+#[derive(PartialEq, Debug)]
+pub struct Stop {
+    pub output: Vec<u8>,
+    pub reason: StopReason,
+}
+
+///why running stopped
+#[derive(PartialEq, Debug)]
+pub enum StopReason {
+    ///the program had already halted or failed, so nothing ran
+    Finished,
+    ///the number of steps asked for have run, or the loop stepped over or out of has finished
+    Stepped,
+    ///the program is at the breakpoint with this id
+    Breakpoint(usize),
+    ///the output matched the watch, by the "." at the position
+    OutputMatched(Position),
+    ///the program has no instructions left
+    Halted,
+    ///the program failed
+    Error(RuntimeError),
+    ///the run was stopped by ctrl+c
+    Interrupted,
+}
+
+///a place in the program like 3:14
+pub fn parse_place(place: &str) -> Result<Position, DebugError> {
+    place
+        .split_once(':')
+        .and_then(|(line, column)| Some((line.parse().ok()?, column.parse().ok()?)))
+        .map(|(line, column)| Position { line, column })
+        .ok_or_else(|| DebugError::BadArgument(format!("'{}' isn't a place like 3:14", place)))
+}
+
+///what the code eval ran wrote, the cells it changed and how it went
+fn render_snippet(snippet: &Snippet, keep: bool, names: &BTreeMap<usize, String>) -> String {
+    let mut text = String::from_utf8_lossy(&snippet.output).into_owned();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&render_diff(&snippet.diff, names));
+    let ran = format!(
+        "ran {} steps and left the pointer at {}",
+        snippet.steps,
+        snippet.machine.pointer()
+    );
+    let ended = match (&snippet.outcome, keep) {
+        (Err(RuntimeError::StepLimitReached(_)), _) => format!(
+            "gave up after {} steps, the machine is as it was",
+            SNIPPET_STEPS
+        ),
+        (Err(e), _) => format!("error: {}, the machine is as it was", e),
+        (Ok(RunOutcome::Halted), true) => format!("{}, which was kept", ran),
+        (Ok(RunOutcome::Halted), false) => format!("{}, the machine is as it was", ran),
+        (Ok(_), _) => "interrupted, the machine is as it was".to_string(),
+    };
+    text.push_str(&ended);
+    text.push('\n');
+    text
+}
+
+///the cells that changed, a run of them a line, and how many there were
+fn render_diff(diff: &TapeDiff, names: &BTreeMap<usize, String>) -> String {
+    let mut text = diff.render(names);
+    match diff.changes().count() {
+        1 => text.push_str("1 cell changed\n"),
+        count => text.push_str(&format!("{} cells changed\n", count)),
+    }
+    text
+}
+
+///where the machine is, then the loops and calls it's inside, one a line, numbered from the
+///innermost out like gdb does
+fn render_backtrace(tm: &TuringMachine) -> String {
+    let here = match tm.position() {
+        Some(position) => format!("#0  at {}\n", position),
+        None => "#0  at the end\n".to_string(),
+    };
+    let frames = tm
+        .backtrace()
+        .into_iter()
+        .enumerate()
+        .map(|(index, frame)| match frame {
+            Frame::Loop(open, close) => {
+                format!("#{:<3}in the loop {}-{}\n", index + 1, open, close)
+            }
+            Frame::Call(site) => format!("#{:<3}in the call at {}\n", index + 1, site),
+        });
+    std::iter::once(here).chain(frames).collect()
+}
@@ -0,0 +1,136 @@
+//! showing errors along with the part of the source they're about. a diagnostic is a message, the
+//! position it's about and maybe some hints pointing at other places, and it's shown as the line
+//! of source with a caret under the characters that are the problem. parse errors and errors from
+//! a running program are both shown this way
+use crate::{style, ParseError, Position};
+
+///another place in the source that helps explain a diagnostic
+#[derive(PartialEq, Debug, Clone)]
+pub struct Hint {
+    pub message: String,
+    pub position: Position,
+}
+
+///an error, along with where in the source it is
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    ///where the problem starts, nothing for problems that aren't about any one place
+    pub position: Option<Position>,
+    ///how many characters from the position the caret goes under
+    pub width: usize,
+    pub hints: Vec<Hint>,
+}
+
+impl Diagnostic {
+    pub fn new(message: &str, position: Option<Position>) -> Self {
+        Diagnostic {
+            message: message.to_string(),
+            position,
+            width: 1,
+            hints: vec![],
+        }
+    }
+    ///the diagnostic for a program that didn't parse, with hints for brackets that don't match
+    pub fn from_parse_error(error: &ParseError, source: &str) -> Self {
+        let (position, hint) = match error {
+            ParseError::UnmatchedOpen(position) => {
+                (*position, Some(unclosed(source, "[", *position, "]")))
+            }
+            ParseError::UnmatchedProcedureStart(position) => {
+                (*position, Some(unclosed(source, "(", *position, ")")))
+            }
+            ParseError::UnmatchedClose(position) => (*position, None),
+            ParseError::UnmatchedProcedureEnd(position) => (*position, None),
+            ParseError::InvalidPair(_, position)
+            | ParseError::DanglingWord(position)
+            | ParseError::TruncatedCode(position)
+            | ParseError::TooLong(position)
+            | ParseError::TooDeep(position, _)
+            | ParseError::StrayCharacter(_, position) => (*position, None),
+            ParseError::Annotation(e) => (e.position, None),
+        };
+        let mut diagnostic = Diagnostic::new(&error.to_string(), Some(position));
+        //a pair that's all on one line gets the caret under all of it
+        if let ParseError::InvalidPair(pair, _) = error {
+            if !pair.contains('\n') {
+                diagnostic.width = pair.chars().count().max(1);
+            }
+        }
+        diagnostic.hints.extend(hint);
+        diagnostic
+    }
+    ///the diagnostic as text, starting with the path and position the way compilers write them,
+    ///then the line of source with a caret under the problem, then every hint the same way
+    pub fn render(&self, path: &str, source: &str) -> String {
+        let error = style::error("error:");
+        let mut text = match self.position {
+            Some(position) => format!("{}:{}: {} {}\n", path, position, error, self.message),
+            None => format!("{}: {} {}\n", path, error, self.message),
+        };
+        if let Some(position) = self.position {
+            text.push_str(&marked(source, position, self.width, style::error));
+        }
+        for hint in &self.hints {
+            text.push_str(&format!(
+                "{}:{}: {} {}\n",
+                path,
+                hint.position,
+                style::hint("hint:"),
+                hint.message
+            ));
+            text.push_str(&marked(source, hint.position, 1, style::hint));
+        }
+        text
+    }
+}
+
+///all of the diagnostics, one after the other, and a line at the end counting them
+pub fn render_all(path: &str, source: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut text: String = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(path, source))
+        .collect();
+    match diagnostics.len() {
+        0 => {}
+        1 => text.push_str("1 error\n"),
+        count => text.push_str(&format!("{} errors\n", count)),
+    }
+    text
+}
+
+///the line of source the position is on, indented, with a caret under the characters from the
+///position on. tabs before the caret are kept so it still lines up
+pub fn excerpt(source: &str, position: Position, width: usize) -> String {
+    marked(source, position, width, |text| text.to_string())
+}
+
+///the same as excerpt, with the caret painted
+fn marked(source: &str, position: Position, width: usize, paint: fn(&str) -> String) -> String {
+    let line = source.lines().nth(position.line - 1).unwrap_or("");
+    let indent: String = line
+        .chars()
+        .take(position.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    format!(
+        "    {}\n    {}{}\n",
+        line,
+        indent,
+        paint(&"^".repeat(width.max(1)))
+    )
+}
+
+///the hint for a bracket that was never closed, which points at the end of the program where
+///the closing bracket was still missing
+fn unclosed(source: &str, open: &str, position: Position, close: &str) -> Hint {
+    let line = source.lines().count().max(1);
+    let column = source.lines().last().map_or(0, |last| last.chars().count()) + 1;
+    Hint {
+        message: format!(
+            "the program ends here, the '{}' at {} was never closed by a '{}'",
+            open, position, close
+        ),
+        position: Position { line, column },
+    }
+}
@@ -0,0 +1,187 @@
+//! running the machine one event at a time, for programs that embed the interpreter and want to
+//! be in charge of input and output themselves, like GUIs. once a machine is driven by events
+//! nothing goes through its input and output handles anymore: every byte the program writes
+//! comes out as an `Event::Output`, and when it wants to read it stops with `Event::InputNeeded`
+//! until the byte is handed over with `provide_input`. a host that gets its input whenever it
+//! likes, say from a network read or a button press, calls `run_until_event` directly instead of
+//! going through the iterator, and keeps the machine around in between
+use crate::condition::Condition;
+use crate::control::RunOutcome;
+use crate::{Position, RuntimeError, TuringMachine};
+use alloc::vec::Vec;
+
+///something that happened while running the program
+#[derive(PartialEq, Debug)]
+pub enum Event {
+    ///the program wrote a byte
+    Output(u8),
+    ///the program wants to read a byte, it won't carry on until provide_input is called
+    InputNeeded,
+    ///the program is about to run an instruction with a breakpoint on it, by breakpoint id
+    Breakpoint(usize),
+    ///the program has no instructions left, this is always the last event
+    Halted,
+    ///the program failed, this is always the last event
+    Error(RuntimeError),
+    ///the run was stopped by a ControlHandle or the progress callback. the machine is left where
+    ///it stopped, and running it again carries on from there
+    Cancelled,
+}
+
+///the events of a machine, as an iterator. ends after `Event::Halted`, `Event::Error` or
+///`Event::Cancelled`
+pub struct Events<'a> {
+    tm: &'a mut TuringMachine,
+    done: bool,
+}
+
+impl Events<'_> {
+    ///hands the program the byte it asked for with `Event::InputNeeded`. None means the input
+    ///has run out
+    pub fn provide_input(&mut self, byte: Option<u8>) {
+        self.tm.provide_input(byte);
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if self.done {
+            return None;
+        }
+        let event = self.tm.run_until_event();
+        self.done = matches!(event, Event::Halted | Event::Error(_) | Event::Cancelled);
+        Some(event)
+    }
+}
+
+impl TuringMachine {
+    ///switches the machine over to events and gives back an iterator over them
+    pub fn events(&mut self) -> Events<'_> {
+        self.event_driven = true;
+        Events {
+            tm: self,
+            done: false,
+        }
+    }
+    ///hands the program the next byte of input, for when it asked for it with
+    ///`Event::InputNeeded`. None means the input has run out, and the "," then does whatever
+    ///eof_behavior says
+    pub fn provide_input(&mut self, byte: Option<u8>) {
+        self.provided_input = Some(byte);
+    }
+    ///puts a breakpoint on the first instruction at or after the position in the source. gives
+    ///back the id that `Event::Breakpoint` will carry, or nothing if there is no instruction there
+    pub fn add_breakpoint(&mut self, position: Position) -> Option<usize> {
+        let index = self
+            .program
+            .positions
+            .iter()
+            .position(|found| (found.line, found.column) >= (position.line, position.column))?;
+        let next_id = self.next_breakpoint_id;
+        let id = *self.breakpoints.entry(index).or_insert(next_id);
+        if id == next_id {
+            self.next_breakpoint_id += 1;
+        }
+        Some(id)
+    }
+    ///puts a breakpoint on the first instruction at or after the position that only stops when
+    ///the condition holds. a breakpoint already there gets the condition instead of the one it
+    ///had. gives back the breakpoint's id, or nothing if there is no instruction there
+    pub fn add_breakpoint_if(&mut self, position: Position, condition: Condition) -> Option<usize> {
+        let id = self.add_breakpoint(position)?;
+        self.breakpoint_conditions.insert(id, condition);
+        Some(id)
+    }
+    ///the ids of every breakpoint that's set
+    pub fn breakpoint_ids(&self) -> Vec<usize> {
+        self.breakpoints.values().copied().collect()
+    }
+    ///takes away the breakpoint with this id
+    pub fn remove_breakpoint(&mut self, id: usize) {
+        self.breakpoints.retain(|_, breakpoint| *breakpoint != id);
+        self.breakpoint_conditions.remove(&id);
+    }
+    ///runs the program until something happens that the caller should know about. this doesn't
+    ///hold on to anything between calls, so after `Event::InputNeeded` the machine can be put
+    ///away (or cloned) for as long as it takes to get the input, and the next call carries on
+    ///at the "," that asked for it. until then it keeps giving back `Event::InputNeeded`
+    pub fn run_until_event(&mut self) -> Event {
+        loop {
+            if let Some(event) = self.run_for(u64::MAX) {
+                return event;
+            }
+        }
+    }
+    ///the same as run_until_event, but gives up and returns nothing after running this many
+    ///instructions without anything happening. that way whoever is driving the machine gets a
+    ///chance to do other things in between, even while the program is stuck in a long loop
+    pub fn run_for(&mut self, instructions: u64) -> Option<Event> {
+        let event = self.run_events_for(instructions);
+        match &event {
+            Some(Event::Halted) => self.observe_end(Ok(RunOutcome::Halted)),
+            Some(Event::Cancelled) => self.observe_end(Ok(RunOutcome::Cancelled)),
+            Some(Event::Error(RuntimeError::FuelExhausted(stage, position))) => {
+                self.observe_end(Ok(RunOutcome::FuelExhausted {
+                    stage: *stage,
+                    position: *position,
+                }))
+            }
+            Some(Event::Error(RuntimeError::OutputClosed)) => {
+                self.observe_end(Ok(RunOutcome::OutputClosed))
+            }
+            Some(Event::Error(e)) => self.observe_end(Err(e)),
+            _ => {}
+        }
+        event
+    }
+    ///the work for run_for, before the observer is told about the run ending
+    fn run_events_for(&mut self, instructions: u64) -> Option<Event> {
+        self.event_driven = true;
+        for _ in 0..instructions {
+            if let Some(byte) = self.pending_output.pop_front() {
+                return Some(Event::Output(byte));
+            }
+            if !self.has_instructions_left() {
+                if let Err(e) = self.finish() {
+                    return Some(Event::Error(e));
+                }
+                if let Some(byte) = self.pending_output.pop_front() {
+                    return Some(Event::Output(byte));
+                }
+                #[cfg(feature = "std")]
+                self.report_progress(true);
+                return Some(Event::Halted);
+            }
+            if self.should_stop() {
+                return Some(Event::Cancelled);
+            }
+            let counter = self.program_counter;
+            if self.paused_at.take() != Some(counter) {
+                if let Some(&id) = self.breakpoints.get(&counter) {
+                    //the condition is only looked at here, so breakpoints cost nothing elsewhere
+                    let stops = match self.breakpoint_conditions.get(&id) {
+                        Some(condition) => condition.holds(self),
+                        None => true,
+                    };
+                    if stops {
+                        self.paused_at = Some(counter);
+                        return Some(Event::Breakpoint(id));
+                    }
+                }
+            }
+            match self.perform_next_instruction() {
+                Ok(()) => {}
+                Err(RuntimeError::InputNeeded) => {
+                    //the instruction will be run again, without stopping at its breakpoint
+                    self.paused_at = Some(counter);
+                    return Some(Event::InputNeeded);
+                }
+                Err(e) => return Some(Event::Error(e)),
+            }
+        }
+        //the output of the last instruction still has to be handed out before giving up
+        self.pending_output.pop_front().map(Event::Output)
+    }
+}
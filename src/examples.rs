@@ -0,0 +1,133 @@
+//! programs built into bfint, so there's something to run right after installing it, with
+//! "bfint run --example <name>". they're the programs in tests/fixtures, read in when bfint is
+//! built, so "bfint test tests/fixtures" and "bfint self-test" check that every one of them still
+//! prints what it should
+use core::fmt;
+
+///about how long an example takes to run
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Runtime {
+    ///done before there's time to notice, under a hundred thousand steps
+    Instant,
+    ///done in well under a second, a few million steps at most
+    Quick,
+    ///takes a moment, more so in a debug build
+    Slow,
+}
+
+impl fmt::Display for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        //pad, so the list can line it up
+        f.pad(match self {
+            Runtime::Instant => "instant",
+            Runtime::Quick => "quick",
+            Runtime::Slow => "slow",
+        })
+    }
+}
+
+///a program built into bfint
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Example {
+    pub name: &'static str,
+    ///the name of its file in tests/fixtures, which its language is told by
+    pub file: &'static str,
+    ///what it does, in one line
+    pub description: &'static str,
+    pub runtime: Runtime,
+    pub source: &'static str,
+    ///what it's given as input when it's checked, empty for the ones that don't read any
+    pub input: &'static [u8],
+    ///what it prints for that input
+    pub expected: &'static [u8],
+}
+
+impl Example {
+    ///whether it reads input, which it waits for on stdin when it's run
+    pub fn reads_input(&self) -> bool {
+        !self.input.is_empty()
+    }
+}
+
+///every example, sorted by name
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "cat",
+        file: "cat.bf",
+        description: "copies its input to its output until the input ends",
+        runtime: Runtime::Instant,
+        source: include_str!("../tests/fixtures/cat.bf"),
+        input: include_bytes!("../tests/fixtures/cat.in"),
+        expected: include_bytes!("../tests/fixtures/cat.out"),
+    },
+    Example {
+        name: "hello",
+        file: "hello.bf",
+        description: "prints hello world, with every line explained",
+        runtime: Runtime::Instant,
+        source: include_str!("../tests/fixtures/hello.bf"),
+        input: b"",
+        expected: include_bytes!("../tests/fixtures/hello.out"),
+    },
+    Example {
+        name: "mandelbrot",
+        file: "mandelbrot.bf",
+        description: "draws a small mandelbrot set in text, with fixed point numbers in bytes",
+        runtime: Runtime::Slow,
+        source: include_str!("../tests/fixtures/mandelbrot.bf"),
+        input: b"",
+        expected: include_bytes!("../tests/fixtures/mandelbrot.out"),
+    },
+    Example {
+        name: "rot13",
+        file: "rot13.bf",
+        description: "rotates the letters of its input by 13 places",
+        runtime: Runtime::Instant,
+        source: include_str!("../tests/fixtures/rot13.bf"),
+        input: include_bytes!("../tests/fixtures/rot13.in"),
+        expected: include_bytes!("../tests/fixtures/rot13.out"),
+    },
+    Example {
+        name: "sierpinski",
+        file: "sierpinski.bf",
+        description: "draws the sierpinski triangle in stars",
+        runtime: Runtime::Quick,
+        source: include_str!("../tests/fixtures/sierpinski.bf"),
+        input: b"",
+        expected: include_bytes!("../tests/fixtures/sierpinski.out"),
+    },
+    Example {
+        name: "squares",
+        file: "squares.b",
+        description: "prints the square numbers from 0 to 10000",
+        runtime: Runtime::Quick,
+        source: include_str!("../tests/fixtures/squares.b"),
+        input: b"",
+        expected: include_bytes!("../tests/fixtures/squares.out"),
+    },
+];
+
+///the example with the name
+pub fn find(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+///a line for every example with its name, how long it takes and what it does, the same every
+///time for the same examples, for "bfint examples --list"
+pub fn list() -> alloc::string::String {
+    let mut text = alloc::string::String::new();
+    for example in EXAMPLES {
+        let input = match example.reads_input() {
+            true => ", reads input",
+            false => "",
+        };
+        text += &alloc::format!(
+            "{:<12}{:<9}{}{}\n",
+            example.name,
+            example.runtime,
+            example.description,
+            input
+        );
+    }
+    text
+}
@@ -0,0 +1,258 @@
+//! a C interface to the interpreter, built with `--features ffi`, as a shared library with
+//! `cargo rustc --lib --features ffi --crate-type cdylib`. the header for it is generated
+//! into include/bfint.h while building. every function takes an optional out parameter that gets
+//! the error code, and when that isn't `BF_ERROR_OK` the message that goes with it can be had
+//! from `bf_last_error_message`. panics are caught at the edge, they never unwind into C
+use crate::lang::Lang;
+use crate::shared::lock;
+use crate::TuringMachine;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::io::Cursor;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+///what went wrong in a call, `Ok` when nothing did
+#[repr(C)]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BfError {
+    Ok = 0,
+    ///a pointer that may not be null was null
+    NullPointer,
+    ///the program text isn't valid UTF-8. programs are read whatever their bytes are now, see
+    ///bf_machine_new, so this never happens, it's kept so the codes after it stay the same
+    InvalidUtf8,
+    ///the program couldn't be parsed
+    ParseError,
+    ///the program failed while running
+    RuntimeError,
+    ///a cell index past the end of the tape
+    OutOfRange,
+    ///something inside the interpreter panicked
+    Panic,
+}
+
+///a machine with its input and output kept in memory, since C can't hand over a reader or writer
+pub struct BfMachine {
+    tm: TuringMachine,
+    input: Arc<Mutex<Cursor<Vec<u8>>>>,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+thread_local! {
+    //the message for the last error on this thread, kept here so the pointer handed to C stays
+    //valid until the next error
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+///runs "f", catching any panic, and reports how it went through "err_out". gives back "default"
+///whenever it didn't work out
+fn guard<T>(
+    err_out: *mut BfError,
+    default: T,
+    f: impl FnOnce() -> Result<T, (BfError, String)>,
+) -> T {
+    let (value, code) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => (value, BfError::Ok),
+        Ok(Err((code, message))) => {
+            set_last_error(message);
+            (default, code)
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "the interpreter panicked".to_string());
+            set_last_error(message);
+            (default, BfError::Panic)
+        }
+    };
+    if !err_out.is_null() {
+        // SAFETY: the caller promises a non null err_out points to writable memory
+        unsafe { *err_out = code };
+    }
+    value
+}
+
+///remembers the message for bf_last_error_message
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).expect("nul bytes were replaced");
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+///turns a pointer from C into a reference to the machine
+///
+/// # Safety
+///
+///"machine" has to be null or come from bf_machine_new and not have been freed
+unsafe fn machine_mut<'a>(machine: *mut BfMachine) -> Result<&'a mut BfMachine, (BfError, String)> {
+    machine
+        .as_mut()
+        .ok_or((BfError::NullPointer, "machine is null".to_string()))
+}
+
+///parses a NUL-terminated brainfuck program and makes a machine for it, with no input. gives back
+///null if the program can't be parsed. text that isn't UTF-8 is read as latin-1, the way files
+///are
+///
+/// # Safety
+///
+///"program" has to be null or point to a NUL-terminated string, and "err_out" has to be null or
+///point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_new(
+    program: *const c_char,
+    err_out: *mut BfError,
+) -> *mut BfMachine {
+    guard(err_out, std::ptr::null_mut(), || {
+        if program.is_null() {
+            return Err((BfError::NullPointer, "program is null".to_string()));
+        }
+        let program = CStr::from_ptr(program).to_bytes();
+        let mut tm = TuringMachine::from_bytes(program, &Lang::Brainfuck)
+            .map_err(|e| (BfError::ParseError, e.to_string()))?;
+        let input = Arc::new(Mutex::new(Cursor::new(vec![])));
+        let output = Arc::new(Mutex::new(vec![]));
+        tm.input = input.clone();
+        tm.output = output.clone();
+        Ok(Box::into_raw(Box::new(BfMachine { tm, input, output })))
+    })
+}
+
+///runs the program until it ends or fails
+///
+/// # Safety
+///
+///"machine" has to come from bf_machine_new and not have been freed, and "err_out" has to be null
+///or point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_run(machine: *mut BfMachine, err_out: *mut BfError) {
+    guard(err_out, (), || {
+        machine_mut(machine)?
+            .tm
+            .run()
+            .map(|_| ())
+            .map_err(|e| (BfError::RuntimeError, e.to_string()))
+    })
+}
+
+///runs a single instruction. gives back 1 if the program has instructions left after it, and 0
+///once it has ended or failed
+///
+/// # Safety
+///
+///"machine" has to come from bf_machine_new and not have been freed, and "err_out" has to be null
+///or point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_step(machine: *mut BfMachine, err_out: *mut BfError) -> i32 {
+    guard(err_out, 0, || {
+        let tm = &mut machine_mut(machine)?.tm;
+        let step = |tm: &mut TuringMachine| {
+            if tm.has_instructions_left() {
+                tm.perform_next_instruction()?;
+            }
+            if !tm.has_instructions_left() {
+                tm.finish()?;
+            }
+            Ok(())
+        };
+        step(tm).map_err(|e: crate::RuntimeError| (BfError::RuntimeError, e.to_string()))?;
+        Ok(tm.has_instructions_left() as i32)
+    })
+}
+
+///reads the cell at "index" on the tape
+///
+/// # Safety
+///
+///"machine" has to come from bf_machine_new and not have been freed, and "err_out" has to be null
+///or point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_read_cell(
+    machine: *mut BfMachine,
+    index: usize,
+    err_out: *mut BfError,
+) -> u8 {
+    guard(err_out, 0, || {
+        let tape = &machine_mut(machine)?.tm.tape;
+        tape.get(index).copied().ok_or((
+            BfError::OutOfRange,
+            format!("cell {} is past the end of the tape", index),
+        ))
+    })
+}
+
+///gives the program "length" bytes of input starting at "data", replacing whatever input it
+///hadn't read yet
+///
+/// # Safety
+///
+///"machine" has to come from bf_machine_new and not have been freed, "data" has to point to at
+///least "length" readable bytes, and "err_out" has to be null or point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_set_input(
+    machine: *mut BfMachine,
+    data: *const u8,
+    length: usize,
+    err_out: *mut BfError,
+) {
+    guard(err_out, (), || {
+        let machine = machine_mut(machine)?;
+        let data = match length {
+            0 => vec![],
+            _ if data.is_null() => return Err((BfError::NullPointer, "data is null".to_string())),
+            _ => std::slice::from_raw_parts(data, length).to_vec(),
+        };
+        *lock(&machine.input) = Cursor::new(data);
+        Ok(())
+    })
+}
+
+///copies up to "length" bytes of the output the program has written into "buffer", and forgets
+///about them. gives back how many bytes were copied, any that didn't fit are left for next time
+///
+/// # Safety
+///
+///"machine" has to come from bf_machine_new and not have been freed, "buffer" has to point to at
+///least "length" writable bytes, and "err_out" has to be null or point to writable memory
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_take_output(
+    machine: *mut BfMachine,
+    buffer: *mut u8,
+    length: usize,
+    err_out: *mut BfError,
+) -> usize {
+    guard(err_out, 0, || {
+        let machine = machine_mut(machine)?;
+        let mut output = lock(&machine.output);
+        let count = output.len().min(length);
+        if count > 0 {
+            if buffer.is_null() {
+                return Err((BfError::NullPointer, "buffer is null".to_string()));
+            }
+            std::ptr::copy_nonoverlapping(output.as_ptr(), buffer, count);
+            output.drain(..count);
+        }
+        Ok(count)
+    })
+}
+
+///frees a machine made by bf_machine_new. freeing null does nothing
+///
+/// # Safety
+///
+///"machine" has to be null or come from bf_machine_new and not have been freed already
+#[no_mangle]
+pub unsafe extern "C" fn bf_machine_free(machine: *mut BfMachine) {
+    if !machine.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(machine))));
+    }
+}
+
+///the message that goes with the last error on this thread. the string belongs to the library
+///and stays valid until the next call that fails
+#[no_mangle]
+pub extern "C" fn bf_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
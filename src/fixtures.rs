@@ -0,0 +1,254 @@
+//! running a directory of programs against the output they're expected to give. every
+//! "name.bf" with a "name.out" next to it is a test, and a "name.in" next to it, if there is one,
+//! is what the program gets as input. a "steps.toml" in the same directory holds how many steps
+//! each program is expected to take, so a change that makes the interpreter do a lot more work
+//! gets noticed
+//!
+//! a single program can also be a test of its own, by saying what it should print in its
+//! comments, see inline
+use crate::control::RunOutcome;
+use crate::lang::{self, INPUT_MARKER, OUTPUT_MARKER};
+use crate::shared::lock;
+use crate::{program, watch, TuringMachine};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///a program along with what it should print
+#[derive(PartialEq, Debug, Clone)]
+pub struct Fixture {
+    ///the name of the program file, without the directory
+    pub name: String,
+    pub path: PathBuf,
+    pub input: Vec<u8>,
+    pub expected: Vec<u8>,
+}
+
+///how running a fixture went
+#[derive(PartialEq, Debug, Clone)]
+pub enum Outcome {
+    Passed,
+    ///the program printed something other than expected, which is given back
+    WrongOutput(Vec<u8>),
+    ///the program didn't get to the end, for this reason
+    Failed(String),
+    ///the program did what it should, but took a lot more or fewer steps than it used to
+    StepsChanged {
+        baseline: u64,
+        steps: u64,
+    },
+}
+
+///finds every fixture in the directory, sorted by name. programs without a ".out" file next to
+///them aren't fixtures, and are skipped
+pub fn discover(directory: &Path) -> std::io::Result<Vec<Fixture>> {
+    let mut fixtures = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        let is_program = matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some("bf") | Some("b")
+        );
+        let expected_path = path.with_extension("out");
+        if !is_program || !expected_path.exists() {
+            continue;
+        }
+        let input_path = path.with_extension("in");
+        let input = match input_path.exists() {
+            true => std::fs::read(&input_path)?,
+            false => vec![],
+        };
+        fixtures.push(Fixture {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            expected: std::fs::read(&expected_path)?,
+            path,
+            input,
+        });
+    }
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+///the fixture a program describes in its own comments. the text after every ";=>" is output the
+///program should print and the text after every ";<<" is input it gets, each joined up in the
+///order they're in, with escapes like "\n" for a newline, see watch::unescape. a marker can be
+///anywhere in a line, like after the commands on it or inside a loop, and the text goes on to the
+///end of the line, less one space after the marker. the parser takes all of that as a comment,
+///see lang::OUTPUT_MARKER, so running the program normally isn't any different
+///
+///```text
+///;<< abc
+///;=> abc
+///,[.,]
+///```
+pub fn inline(path: &Path) -> Result<Fixture, String> {
+    let source = program::read_source(path).map_err(|e| e.to_string())?;
+    let mut input = vec![];
+    let mut expected = vec![];
+    let mut found = false;
+    for (number, line) in source.lines().enumerate() {
+        for (marker, bytes) in [(OUTPUT_MARKER, &mut expected), (INPUT_MARKER, &mut input)] {
+            let Some((_, text)) = line.split_once(marker) else {
+                continue;
+            };
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            let text = watch::unescape(text).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            bytes.extend(text);
+            found |= marker == OUTPUT_MARKER;
+        }
+    }
+    if !found {
+        return Err(format!(
+            "there's no \"{}\" comment saying what it should print",
+            OUTPUT_MARKER
+        ));
+    }
+    Ok(Fixture {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.to_path_buf(),
+        input,
+        expected,
+    })
+}
+
+///runs the fixture on a machine of its own, stopping it after the step limit or the timeout.
+///along with how it went comes the number of steps it ran
+pub fn run(fixture: &Fixture, step_limit: u64, timeout: Duration) -> (Outcome, u64) {
+    let lang = lang::Lang::from_path(&fixture.path.to_string_lossy());
+    let mut tm = match TuringMachine::from_file(&fixture.path, &lang) {
+        Ok(tm) => tm,
+        Err(e) => return (Outcome::Failed(e.to_string()), 0),
+    };
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(fixture.input.clone())));
+    tm.output = output.clone();
+    tm.step_limit = Some(step_limit);
+    let started = Instant::now();
+    tm.set_progress_callback(100_000, move |_| match started.elapsed() > timeout {
+        true => ControlFlow::Break(()),
+        false => ControlFlow::Continue(()),
+    });
+    //brainfork's machines count their own steps, so there's no one number for those
+    let (result, steps) = match lang {
+        lang::Lang::Brainfork => (crate::fork::run(tm), 0),
+        _ => (tm.run(), tm.steps),
+    };
+    let outcome = match result {
+        Ok(RunOutcome::Halted) => {
+            let output = lock(&output);
+            match *output == fixture.expected {
+                true => Outcome::Passed,
+                false => Outcome::WrongOutput(output.clone()),
+            }
+        }
+        Ok(RunOutcome::Cancelled) => {
+            Outcome::Failed(format!("timed out after {:.1}s", timeout.as_secs_f64()))
+        }
+        Ok(RunOutcome::FuelExhausted { position, .. }) => {
+            Outcome::Failed(format!("ran out of fuel at {}", position))
+        }
+        Ok(RunOutcome::OutputClosed) => Outcome::Failed(crate::io::OUTPUT_CLOSED.to_string()),
+        Err(e) => Outcome::Failed(e.to_string()),
+    };
+    (outcome, steps)
+}
+
+///the name of the file next to the fixtures that holds their step counts
+pub const BASELINES_FILE: &str = "steps.toml";
+
+///how many steps each fixture took when it was last looked at. it's kept as a small toml file:
+///
+///```toml
+///tolerance = 10
+///
+///[steps]
+///"hello.bf" = 903
+///```
+#[derive(PartialEq, Debug, Clone)]
+pub struct Baselines {
+    ///how far off, in percent, a fixture's step count can be before it fails
+    pub tolerance: u64,
+    ///the step count of every fixture by name
+    pub steps: BTreeMap<String, u64>,
+}
+
+impl Default for Baselines {
+    fn default() -> Self {
+        Baselines {
+            tolerance: 10,
+            steps: BTreeMap::new(),
+        }
+    }
+}
+
+///a line in a baselines file that isn't understood, counting from 1
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct BaselinesError(pub usize);
+
+impl fmt::Display for BaselinesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {} isn't a tolerance or a step count", self.0)
+    }
+}
+
+impl Baselines {
+    ///reads the file written by to_toml. comments, blank lines and the "[steps]" header are
+    ///skipped, every other line is a "name = number"
+    pub fn parse(text: &str) -> Result<Self, BaselinesError> {
+        let mut baselines = Baselines::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == "[steps]" {
+                continue;
+            }
+            let error = BaselinesError(number + 1);
+            let (name, value) = line.split_once('=').ok_or(error)?;
+            let value: u64 = value.trim().parse().map_err(|_| error)?;
+            match name.trim() {
+                "tolerance" => baselines.tolerance = value,
+                name => {
+                    let name = name
+                        .strip_prefix('"')
+                        .and_then(|name| name.strip_suffix('"'))
+                        .ok_or(error)?;
+                    baselines.steps.insert(name.to_string(), value);
+                }
+            }
+        }
+        Ok(baselines)
+    }
+    ///the baselines in the layout parse reads
+    pub fn to_toml(&self) -> String {
+        let mut toml = format!(
+            "# how many steps every test takes, updated with \"bfint test --update-baselines\"\n\
+             tolerance = {}\n\n[steps]\n",
+            self.tolerance
+        );
+        for (name, steps) in &self.steps {
+            toml.push_str(&format!("\"{}\" = {}\n", name, steps));
+        }
+        toml
+    }
+    ///the outcome for a fixture that passed but took too many or too few steps compared to its
+    ///baseline. nothing if it's close enough, or has no baseline
+    pub fn check(&self, name: &str, steps: u64) -> Option<Outcome> {
+        let baseline = *self.steps.get(name)?;
+        //brainfork programs don't have a step count to compare
+        if steps == 0 {
+            return None;
+        }
+        match baseline.abs_diff(steps) * 100 > baseline * self.tolerance {
+            true => Some(Outcome::StepsChanged { baseline, steps }),
+            false => None,
+        }
+    }
+}
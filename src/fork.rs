@@ -0,0 +1,34 @@
+//! running brainfork programs, where a single machine can split into many. the machines take
+//! turns running one instruction each, so they all make progress and their output comes out in
+//! an order that is the same every time the program is run
+use crate::control::RunOutcome;
+use crate::{RuntimeError, TuringMachine};
+use std::collections::VecDeque;
+
+///runs the machine, and every machine it forks into, until all of them have run out of
+///instructions. the first machine to fail stops all of them, and so does cancelling any of them
+///through a ControlHandle, since the forked machines share the handles of the one they split from.
+///they share its fuel too, so all of them together stop once it runs out
+pub fn run(tm: TuringMachine) -> Result<RunOutcome, RuntimeError> {
+    crate::control::settle(run_all(tm))
+}
+
+///the work for run, before running out of fuel is made an outcome
+fn run_all(tm: TuringMachine) -> Result<RunOutcome, RuntimeError> {
+    let mut machines = VecDeque::from([tm]);
+    while let Some(mut machine) = machines.pop_front() {
+        if !machine.has_instructions_left() {
+            machine.finish()?;
+            continue;
+        }
+        if machine.should_stop() {
+            machine.flush_output()?;
+            return Ok(RunOutcome::Cancelled);
+        }
+        machine.perform_next_instruction()?;
+        let forked: Vec<TuringMachine> = machine.forked.drain(..).collect();
+        machines.push_back(machine);
+        machines.extend(forked);
+    }
+    Ok(RunOutcome::Halted)
+}
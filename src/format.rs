@@ -0,0 +1,169 @@
+//! laying out brainfuck source so it can be read: every loop on lines of its own with its body
+//! indented, and long runs of commands wrapped. only the layout changes, the instructions stay
+//! exactly the same
+use crate::lang::{self, Lang};
+use crate::Instruction;
+use std::fmt;
+
+///what happens to the text between the commands, which brainfuck treats as comments
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Comments {
+    ///every comment is kept, on lines of its own where it was in the program
+    Keep,
+    ///comments are left out
+    Strip,
+}
+
+impl Comments {
+    ///turns the name used on the command line into the matching choice
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(Comments::Keep),
+            "strip" => Some(Comments::Strip),
+            _ => None,
+        }
+    }
+}
+
+///everything that can keep a program from being formatted
+#[derive(PartialEq, Debug)]
+pub enum FormatError {
+    ///the language has commands longer than a single character, like ook, which the formatter
+    ///can't lay out
+    UnsupportedLang,
+    ///the formatted program has different instructions than the original. this would be a bug
+    ///in the formatter, it's checked so a broken formatter can't break programs
+    ChangedProgram,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::UnsupportedLang => {
+                write!(
+                    f,
+                    "only languages with single character commands can be formatted"
+                )
+            }
+            FormatError::ChangedProgram => {
+                write!(
+                    f,
+                    "formatting would have changed the program, so it was left alone"
+                )
+            }
+        }
+    }
+}
+
+///how many loops deep the indentation goes. loops deeper than that are indented as much as the
+///ones at this depth, so a program with thousands of loops inside each other doesn't get lines
+///thousands of spaces long, and a formatted program as many times longer
+pub const MAX_INDENT: usize = 32;
+
+///the pieces a program is made of, as far as the layout is concerned
+enum Piece {
+    Commands(String),
+    Comment(String),
+    Open(char),
+    Close(char),
+}
+
+///formats the program, wrapping lines at "width" characters where that can be done. a "#!" line
+///at the start is kept as it is
+pub fn format(
+    source: &str,
+    lang: &Lang,
+    width: usize,
+    comments: Comments,
+) -> Result<String, FormatError> {
+    if matches!(lang, Lang::Ook | Lang::Spoon | Lang::Mapped(_)) {
+        return Err(FormatError::UnsupportedLang);
+    }
+    //a "#!" line stays the first line, just as it was
+    let body = lang::skip_shebang(source);
+    let mut out = match &source[..source.len() - body.len()] {
+        "" => String::new(),
+        shebang => format!("{}\n", shebang.trim_end()),
+    };
+    let mut depth = 0;
+    let indent = |depth: usize| "    ".repeat(depth.min(MAX_INDENT));
+    for piece in pieces(body, lang) {
+        let indent = indent(depth);
+        match piece {
+            Piece::Open(c) => {
+                out.push_str(&format!("{}{}\n", indent, c));
+                depth += 1;
+            }
+            Piece::Close(c) => {
+                depth = depth.saturating_sub(1);
+                out.push_str(&format!("{}{}\n", "    ".repeat(depth.min(MAX_INDENT)), c));
+            }
+            Piece::Commands(commands) => {
+                let room = width.saturating_sub(indent.len()).max(1);
+                let chars: Vec<char> = commands.chars().collect();
+                for line in chars.chunks(room) {
+                    out.push_str(&format!("{}{}\n", indent, line.iter().collect::<String>()));
+                }
+            }
+            Piece::Comment(text) if comments == Comments::Keep => {
+                for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                    out.push_str(&format!("{}{}\n", indent, line));
+                }
+            }
+            Piece::Comment(_) => {}
+        }
+    }
+    //the layout is only allowed to move commands around, never to add, drop or reorder them
+    let instructions = |source: &str| -> Vec<Instruction> {
+        lang::tokenize(source, lang)
+            .map(|tokens| {
+                tokens
+                    .into_iter()
+                    .map(|(instruction, _)| instruction)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    if instructions(&out) != instructions(source) {
+        return Err(FormatError::ChangedProgram);
+    }
+    Ok(out)
+}
+
+///splits the program into runs of commands, comments and brackets. commands that only have
+///whitespace between them count as a single run, so they get wrapped together
+fn pieces(source: &str, lang: &Lang) -> Vec<Piece> {
+    let mut pieces: Vec<Piece> = vec![];
+    let chars = source.split_inclusive('\n').flat_map(|line| {
+        //the text after an expectation marker is all comment, see lang::OUTPUT_MARKER
+        let marker = lang::marker_offset(line, lang).unwrap_or(line.len());
+        line.char_indices()
+            .map(move |(offset, c)| (c, offset >= marker))
+    });
+    for (c, in_marker) in chars {
+        //whitespace is never an instruction in the languages that can be formatted, and a
+        //formatted program is mostly indentation
+        let instruction = match in_marker || c.is_whitespace() {
+            true => None,
+            false => lang::tokenize(&c.to_string(), lang)
+                .ok()
+                .and_then(|tokens| tokens.first().map(|(instruction, _)| *instruction)),
+        };
+        match (instruction, pieces.last_mut()) {
+            (Some(Instruction::JumpToClose), _) => pieces.push(Piece::Open(c)),
+            (Some(Instruction::JumpToOpen), _) => pieces.push(Piece::Close(c)),
+            (Some(_), Some(Piece::Commands(commands))) => commands.push(c),
+            (Some(_), Some(Piece::Comment(text))) if text.trim().is_empty() => {
+                pieces.pop();
+                match pieces.last_mut() {
+                    Some(Piece::Commands(commands)) => commands.push(c),
+                    _ => pieces.push(Piece::Commands(c.to_string())),
+                }
+            }
+            (Some(_), _) => pieces.push(Piece::Commands(c.to_string())),
+            (None, Some(Piece::Comment(text))) => text.push(c),
+            (None, _) => pieces.push(Piece::Comment(c.to_string())),
+        }
+    }
+    pieces
+}
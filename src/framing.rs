@@ -0,0 +1,55 @@
+//! the framing the debug adapter protocol and the language server protocol both send their json
+//! in, see dap.rs and lsp.rs: a header with the length of the message in bytes, an empty line,
+//! and then that many bytes of json. a length past MAX_LENGTH is refused before anything is
+//! read for it, so a client that sends a broken or hostile header can't have a buffer that size
+//! allocated for it
+use serde_json::Value;
+use std::io::{self, BufRead, Read, Write};
+
+///the longest message that's read, far longer than any program an editor is going to send
+pub const MAX_LENGTH: usize = 64 * 1024 * 1024;
+
+///writes a message with its header
+pub fn send(out: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    out.flush()
+}
+
+///reads the next message, a header with its length and then the json. nothing once the input
+///has ended
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let length: usize =
+        length.ok_or_else(|| invalid("a message has no Content-Length".to_string()))?;
+    if length > MAX_LENGTH {
+        return Err(invalid(format!(
+            "a message is {} bytes long, and the longest that's read is {}",
+            length, MAX_LENGTH
+        )));
+    }
+    let mut body = Vec::new();
+    input.by_ref().take(length as u64).read_to_end(&mut body)?;
+    if body.len() < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
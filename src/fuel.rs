@@ -0,0 +1,111 @@
+//! fuel, a budget of steps that can be shared between machines, so a limit can't be dodged by
+//! splitting the work up. a step limit belongs to one machine, while fuel is a tank that every
+//! machine given a clone of it burns from: the stages of a pipeline, the programs run one after
+//! the other on a shared tape, and the machines brainfork forks into. what "%" runs and the
+//! procedures pbrain calls are steps of the machine they run in, so they burn its fuel too.
+//!
+//! every instruction that runs burns one unit. the runs of moves, "+" and "-" an optimized
+//! program does in one go and the loops the tiered interpreter does in one go burn as many
+//! units as instructions they stand for, the same as the steps they're counted as, so the same
+//! amount of fuel gets a program as far at -O0 as at -O2. with FuelCost::Ops they burn one unit
+//! each instead, for counting what the machine really did.
+//!
+//! a machine that's out of fuel stops before the instruction that would have burned more,
+//! without doing any of it, so it can be given more and carry on. run and run_with_fuel give
+//! back RunOutcome::FuelExhausted, and step and the events give back
+//! RuntimeError::FuelExhausted, which is also how it gets out of what "%" is running
+use crate::control::RunOutcome;
+use crate::shared::{lock, shared, Shared};
+use crate::{RuntimeError, TuringMachine};
+
+///what the instructions the optimizer or the tiered interpreter put together cost
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum FuelCost {
+    ///as many units as instructions they stand for, the same as the steps they count as
+    #[default]
+    Expanded,
+    ///one unit, like every other instruction
+    Ops,
+}
+
+///a tank of fuel. clones of it burn from the same tank, see the top of this file
+#[derive(Debug, Clone)]
+pub struct Fuel {
+    left: Shared<u64>,
+    ///which machine or stage this clone of it was given to, for saying who ran out. nothing
+    ///unless it was given one with for_stage
+    pub stage: Option<usize>,
+    pub cost: FuelCost,
+}
+
+impl Fuel {
+    ///a tank with this many units in it
+    pub fn new(units: u64) -> Self {
+        Fuel {
+            left: shared(units),
+            stage: None,
+            cost: FuelCost::Expanded,
+        }
+    }
+    ///a clone that burns from the same tank, and says the stage ran out when it does
+    pub fn for_stage(&self, stage: usize) -> Self {
+        Fuel {
+            stage: Some(stage),
+            ..self.clone()
+        }
+    }
+    ///how many units are left in the tank
+    pub fn left(&self) -> u64 {
+        *lock(&self.left)
+    }
+    ///puts more units in the tank
+    pub fn add(&self, units: u64) {
+        let mut left = lock(&self.left);
+        *left = left.saturating_add(units);
+    }
+    fn burn(&self, units: u64) {
+        let mut left = lock(&self.left);
+        *left = left.saturating_sub(units);
+    }
+}
+
+impl TuringMachine {
+    ///runs the program like run does, burning the fuel, which it keeps using afterwards until
+    ///it's given other fuel or none
+    pub fn run_with_fuel(&mut self, fuel: &Fuel) -> Result<RunOutcome, RuntimeError> {
+        self.fuel = Some(fuel.clone());
+        self.run()
+    }
+    ///fails when the tank is empty, before the next instruction runs
+    pub(crate) fn check_fuel(&self) -> Result<(), RuntimeError> {
+        match &self.fuel {
+            Some(fuel) if fuel.left() == 0 => Err(RuntimeError::FuelExhausted(
+                fuel.stage,
+                self.program.positions[self.program_counter],
+            )),
+            _ => Ok(()),
+        }
+    }
+    ///burns the fuel for this many steps run as one instruction
+    pub(crate) fn burn_fuel(&self, steps: u64) {
+        if let Some(fuel) = &self.fuel {
+            match fuel.cost {
+                FuelCost::Expanded => fuel.burn(steps),
+                FuelCost::Ops => fuel.burn(1),
+            }
+        }
+    }
+    ///gives back the fuel a "," burned when it turned out there was no input for it yet
+    pub(crate) fn refund_fuel(&self) {
+        if let Some(fuel) = &self.fuel {
+            fuel.add(1);
+        }
+    }
+    ///how many steps the fuel lasts for when they're run in one go
+    pub(crate) fn fuel_steps(&self) -> u64 {
+        match &self.fuel {
+            Some(fuel) if fuel.cost == FuelCost::Expanded => fuel.left(),
+            _ => u64::MAX,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! running the loops the optimizer found a faster way to run in one go, instead of an
+//! instruction at a time. clear-loop, copy-loop and scan find them, see optimize::Fused, and a
+//! program compiled with those passes finds them again when it's loaded, so they don't have to be
+//! kept with it.
+//!
+//! a loop only runs in one go from its "[", when nothing about the machine needs to see it go
+//! around an instruction at a time, like tier.rs. steps count the same as going around would
+//! have, and it stops short of the step limit and of the next time the control handles or the
+//! progress callback would be checked in with, leaving the rest to go around as usual. a loop
+//! that would go off the tape goes around an instruction at a time too, so the move that goes
+//! off fails with its own position
+use crate::optimize::{self, Fused, Fusions};
+use crate::program::Program;
+use crate::{Instruction, TuringMachine};
+use alloc::vec::Vec;
+
+impl Program {
+    ///finds the loops and runs of moves, "+" and "-" that the fusions say run in one go
+    pub(crate) fn fuse(&mut self, fusions: Fusions) {
+        let instructions: Vec<Instruction> = self.instructions().collect();
+        self.fusions = fusions;
+        self.fused = optimize::fused_loops(&instructions, &fusions);
+        self.find_blocks(&instructions, fusions.runs);
+    }
+}
+
+impl TuringMachine {
+    ///runs the loop whose "[" is the next instruction in one go, if it's one the program says
+    ///can be. gives back whether it did anything
+    pub(crate) fn run_fused(&mut self) -> bool {
+        let start = self.program_counter;
+        if self.program.instruction(start) != Some(Instruction::JumpToClose)
+            || self.tape[self.pointer] == 0
+            || self.eval_depth > 0
+            || self.history.is_some()
+            || self.writers.is_some()
+            || self.loop_iteration_limit.is_some()
+            || self.observer.is_some()
+            || log::log_enabled!(log::Level::Trace)
+        {
+            return false;
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_cells.is_some() {
+            return false;
+        }
+        let Some(fused) = self.program.fused.get(&start) else {
+            return false;
+        };
+        let end = self.program.ops[start].target();
+        if self.breakpoints.range(start..=end).next().is_some() {
+            return false;
+        }
+        //the "[" runs once going in, and every time around runs the body and the "]"
+        let per_iteration = (end - start) as u64;
+        let most = self.steps_before_check_in().saturating_sub(1) / per_iteration;
+        let pointer = self.pointer;
+        let iterations = match fused {
+            Fused::Add(form) => {
+                if !form.fits(pointer, self.tape.len()) {
+                    return false;
+                }
+                let iterations = form.iterations(self.tape[pointer]).min(most);
+                form.go_around(&mut self.tape, pointer, iterations);
+                iterations
+            }
+            Fused::Scan(stride) => {
+                let Some(found) = scan(&self.tape, pointer, *stride) else {
+                    return false;
+                };
+                let iterations = found.min(most);
+                self.pointer = (pointer as isize + stride * iterations as isize) as usize;
+                iterations
+            }
+        };
+        if iterations == 0 {
+            return false;
+        }
+        self.steps += 1 + iterations * per_iteration;
+        self.burn_fuel(1 + iterations * per_iteration);
+        //when it stopped short, the "]" has just gone back to the start of the body
+        self.program_counter = match self.tape[self.pointer] {
+            0 => end + 1,
+            _ => start + 1,
+        };
+        true
+    }
+}
+
+///how many times a scan that far goes around from the pointer before it gets to a cell that's
+///0, or nothing when it would go off the tape first
+fn scan(tape: &[u8], pointer: usize, stride: isize) -> Option<u64> {
+    let step = stride.unsigned_abs();
+    let found = match stride > 0 {
+        true => tape[pointer..]
+            .iter()
+            .step_by(step)
+            .skip(1)
+            .position(|cell| *cell == 0),
+        false => tape[..=pointer]
+            .iter()
+            .rev()
+            .step_by(step)
+            .skip(1)
+            .position(|cell| *cell == 0),
+    };
+    found.map(|skipped| skipped as u64 + 1)
+}
@@ -0,0 +1,90 @@
+//! writing brainfuck programs that print a given text
+use std::fmt;
+
+///how the generated program gets each cell to the next character
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Strategy {
+    ///clears the cell and counts up from 0 for every character
+    Naive,
+    ///adds or takes away the difference from the character before, whichever is shorter
+    Delta,
+    ///like delta, but big differences are made with a multiplying loop on the next cell
+    Loops,
+}
+
+impl Strategy {
+    ///turns the name used on the command line into the matching strategy
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "naive" => Some(Strategy::Naive),
+            "delta" => Some(Strategy::Delta),
+            "loops" => Some(Strategy::Loops),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Strategy::Naive => write!(f, "naive"),
+            Strategy::Delta => write!(f, "delta"),
+            Strategy::Loops => write!(f, "loops"),
+        }
+    }
+}
+
+///gives back a program that prints the text. it only ever uses the first two cells, and the
+///second one is back to 0 after every character
+pub fn generate(text: &[u8], strategy: Strategy) -> String {
+    let mut program = String::new();
+    let mut current: u8 = 0;
+    for &byte in text {
+        match strategy {
+            Strategy::Naive => {
+                if current != 0 {
+                    program.push_str("[-]");
+                }
+                program.push_str(&"+".repeat(byte as usize));
+            }
+            Strategy::Delta => program.push_str(&step(current, byte)),
+            Strategy::Loops => program.push_str(&multiplied_step(current, byte)),
+        }
+        program.push('.');
+        current = byte;
+    }
+    program
+}
+
+///the "+" or "-" that get the cell from one value to the other, going round past 0 or 255 when
+///that's shorter
+fn step(from: u8, to: u8) -> String {
+    let up = to.wrapping_sub(from) as usize;
+    match up <= 128 {
+        true => "+".repeat(up),
+        false => "-".repeat(256 - up),
+    }
+}
+
+///the same as step, but the difference can be made as a product in a loop, like
+///">++++[<++++++>-]<" for 24, when that comes out shorter
+fn multiplied_step(from: u8, to: u8) -> String {
+    let plain = step(from, to);
+    let amount = plain.len();
+    let sign = if plain.starts_with('-') { "-" } else { "+" };
+    let mut best = plain.clone();
+    for times in 2..=amount {
+        let each = amount / times;
+        let rest = amount - times * each;
+        //">" "[<" ">-]<" around the counter, the body and whatever is left over
+        if times + each + rest + 7 < best.len() {
+            best = format!(
+                ">{}[<{}>-]<{}",
+                "+".repeat(times),
+                sign.repeat(each),
+                sign.repeat(rest)
+            );
+        }
+    }
+    best
+}
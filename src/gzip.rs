@@ -0,0 +1,72 @@
+//! reading gzip files, for programs that are kept compressed. the decompressing is flate2's, this
+//! is only the part of it reading a program needs: it reads the file as it decompresses it, and
+//! says what was wrong with one that doesn't decompress.
+//!
+//! a few bytes of deflate can stand for a great many of what they decompress to, so how much is
+//! decompressed is capped, and a file that would go past the cap is an error rather than
+//! something that fills up memory
+use flate2::read::MultiGzDecoder;
+use std::fmt;
+use std::io::Read;
+
+///the two bytes every gzip file starts with
+pub const MAGIC: [u8; 2] = [0x1f, 0x8b];
+///how many bytes a program read from a file can decompress to
+pub const MAX_SIZE: usize = 64 * 1024 * 1024;
+
+///what can go wrong decompressing
+#[derive(Debug)]
+pub enum GzipError {
+    ///it ends before the compressed data or the checksum after it does
+    Truncated,
+    ///it isn't gzip, its compressed data is broken or it doesn't match its checksum, with what
+    ///flate2 said about it
+    Corrupt(std::io::Error),
+    ///it decompresses to more bytes than the limit it was given, with the limit
+    TooLarge(usize),
+    ///what it's decompressed from couldn't be read
+    Read(std::io::Error),
+}
+
+impl fmt::Display for GzipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GzipError::Truncated => write!(f, "the compressed data is cut off"),
+            GzipError::Corrupt(e) => write!(f, "the compressed data is corrupt, {}", e),
+            GzipError::TooLarge(limit) => write!(f, "it decompresses to more than {} bytes", limit),
+            GzipError::Read(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for GzipError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => GzipError::Truncated,
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => {
+                GzipError::Corrupt(e)
+            }
+            _ => GzipError::Read(e),
+        }
+    }
+}
+
+///whether the bytes look like a gzip file
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+///decompresses a gzip file as it's read, as long as it comes to no more than limit bytes. a
+///file made of several gzip files one after the other, like "cat a.gz b.gz" makes, decompresses
+///to all of them one after the other, like gunzip does, and the limit is on all of them together
+pub fn decompress(compressed: impl Read, limit: usize) -> Result<Vec<u8>, GzipError> {
+    let mut bytes = vec![];
+    //one byte past the limit is enough to know it's too large
+    MultiGzDecoder::new(compressed)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut bytes)?;
+    match bytes.len() > limit {
+        true => Err(GzipError::TooLarge(limit)),
+        false => Ok(bytes),
+    }
+}
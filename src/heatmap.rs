@@ -0,0 +1,201 @@
+//! a picture of how a program uses its tape, for --heatmap-image. time goes from left to right,
+//! a column of pixels for every so many steps, and the cells from the top down, a row each. the
+//! more times a cell was written in a column's steps the brighter its pixel, from black through
+//! red and yellow to white, so loops sweeping over the tape show up as stripes.
+//!
+//! how many steps a column is grows with the run, doubling whenever there'd be more than
+//! MAX_COLUMNS of them, unless it was given. the rows are the cells the program wrote, from the
+//! lowest to the highest, unless they were given, and a tape too long for MAX_ROWS rows gets
+//! more than one cell a row. the picture can be written as a binary ppm, which needs nothing
+//! to write, or as a png with the png feature
+use crate::Instruction;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+///the most columns a heat map gets when how many steps a column is wasn't given
+pub const MAX_COLUMNS: usize = 1024;
+///the most rows a heat map gets, a longer range of cells shares rows
+pub const MAX_ROWS: usize = 1024;
+
+///how many times every cell was written, in every column's steps
+#[derive(PartialEq, Debug, Clone)]
+pub struct Heatmap {
+    every: u64,
+    //whether every was given, which keeps it the same however long the run is
+    fixed: bool,
+    //the writes in every column by cell. most columns only touch a few cells, so they're kept
+    //as maps and not as the whole tape
+    columns: Vec<BTreeMap<usize, u32>>,
+}
+
+impl Heatmap {
+    ///a heat map with a column every that many steps, or as many as fit MAX_COLUMNS columns
+    pub fn new(every: Option<u64>) -> Self {
+        Heatmap {
+            every: every.unwrap_or(1).max(1),
+            fixed: every.is_some(),
+            columns: vec![],
+        }
+    }
+    ///how many steps a column is
+    pub fn every(&self) -> u64 {
+        self.every
+    }
+    ///moves on to the step, which counts from 1, making room for its column
+    pub fn step(&mut self, step: u64) {
+        let mut column = (step.saturating_sub(1) / self.every) as usize;
+        while !self.fixed && column >= MAX_COLUMNS {
+            self.halve();
+            column = (step.saturating_sub(1) / self.every) as usize;
+        }
+        if self.columns.len() <= column {
+            self.columns.resize(column + 1, BTreeMap::new());
+        }
+    }
+    ///counts a write to the cell, in the column of the last step
+    pub fn write(&mut self, cell: usize) {
+        if let Some(column) = self.columns.last_mut() {
+            *column.entry(cell).or_insert(0) += 1;
+        }
+    }
+    ///doubles how many steps a column is, putting every two columns together
+    fn halve(&mut self) {
+        let columns = core::mem::take(&mut self.columns);
+        let mut columns = columns.into_iter();
+        while let Some(mut first) = columns.next() {
+            for (cell, writes) in columns.next().into_iter().flatten() {
+                *first.entry(cell).or_insert(0) += writes;
+            }
+            self.columns.push(first);
+        }
+        self.every *= 2;
+    }
+    ///the lowest and the highest cell written, nothing if none were
+    pub fn touched(&self) -> Option<RangeInclusive<usize>> {
+        let lowest = self.columns.iter().filter_map(|c| c.keys().next()).min()?;
+        let highest = self
+            .columns
+            .iter()
+            .filter_map(|c| c.keys().next_back())
+            .max()?;
+        Some(*lowest..=*highest)
+    }
+    ///the picture of the cells, or the ones that were written when there's no range. a run
+    ///that wrote nothing is a single row of black, and a range that ends before it starts has no
+    ///cells, which is a picture with no rows
+    pub fn render(&self, cells: Option<RangeInclusive<usize>>) -> Image {
+        let cells = cells.or_else(|| self.touched()).unwrap_or(0..=0);
+        let length = match cells.is_empty() {
+            true => 0,
+            false => (cells.end() - cells.start()).saturating_add(1),
+        };
+        let per_row = length.div_ceil(MAX_ROWS).max(1);
+        let width = self.columns.len().max(1);
+        let height = length.div_ceil(per_row);
+        let mut writes = vec![0u64; width * height];
+        for (x, column) in self.columns.iter().enumerate().filter(|_| length > 0) {
+            for (cell, count) in column.range(cells.clone()) {
+                let y = (cell - cells.start()) / per_row;
+                writes[y * width + x] += *count as u64;
+            }
+        }
+        //the writes go on a logarithmic scale, one busy loop would leave everything else black
+        let most = writes.iter().copied().max().unwrap_or(0);
+        let scale = (most as f64 + 1.0).ln();
+        let pixels = writes
+            .iter()
+            .map(|&count| match count {
+                0 => [0, 0, 0],
+                _ => heat((count as f64 + 1.0).ln() / scale),
+            })
+            .collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+///whether the instruction writes the cell it's at
+pub fn writes(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Increment
+            | Instruction::Decrement
+            | Instruction::Replace
+            | Instruction::Restore
+            | Instruction::ShiftRight
+            | Instruction::ShiftLeft
+            | Instruction::Not
+            | Instruction::Xor
+            | Instruction::And
+            | Instruction::Or
+            | Instruction::FlipBit
+            | Instruction::ReadBit
+            | Instruction::ReadFile
+            | Instruction::Random
+    )
+}
+
+///the color for how hot a pixel is, from 0 to 1: black, red, yellow and then white
+fn heat(t: f64) -> [u8; 3] {
+    let channel = |from: f64| ((t * 3.0 - from).clamp(0.0, 1.0) * 255.0) as u8;
+    [channel(0.0), channel(1.0), channel(2.0)]
+}
+
+///a picture, its pixels a row at a time from the top left
+#[derive(PartialEq, Debug, Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    ///the picture as a binary ppm (P6)
+    pub fn ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(self.pixels.iter().flatten());
+        ppm
+    }
+    ///the picture as a png, compressed with flate2
+    #[cfg(feature = "png")]
+    pub fn png(&self) -> Vec<u8> {
+        use std::io::Write;
+        let chunk = |png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]| {
+            png.extend((data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend(kind);
+            png.extend(data);
+            let mut crc = flate2::Crc::new();
+            crc.update(&png[start..]);
+            png.extend(crc.sum().to_be_bytes());
+        };
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = vec![];
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        //8 bits a channel, rgb, and the only compression, filter and interlacing there are
+        header.extend([8, 2, 0, 0, 0]);
+        chunk(&mut png, b"IHDR", &header);
+        //every row starts with the filter it uses, which is none
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+        let mut zlib = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        //writing to a vec can't fail
+        let zlib = zlib
+            .write_all(&raw)
+            .and_then(|_| zlib.finish())
+            .unwrap_or_default();
+        chunk(&mut png, b"IDAT", &zlib);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
@@ -0,0 +1,84 @@
+//! a flight recorder for the machine: the last few instructions it ran, kept in a ring so
+//! recording one costs the same no matter how long the program has been running. when a program
+//! fails, these are what show how it got there
+use crate::{Instruction, Position};
+use alloc::vec::Vec;
+use core::fmt;
+
+///one instruction that ran, with the pointer and its cell as they were right after
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Entry {
+    ///which step it was, counting from 1
+    pub step: u64,
+    pub position: Position,
+    pub instruction: Instruction,
+    pub pointer: usize,
+    pub cell: u8,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "step {} at {}: {:?}, pointer {}, cell {}",
+            self.step, self.position, self.instruction, self.pointer, self.cell
+        )
+    }
+}
+
+///the last so many instructions that ran
+#[derive(PartialEq, Debug, Clone)]
+pub struct History {
+    entries: Vec<Entry>,
+    capacity: usize,
+    //where the next entry goes once the ring is full, which is where the oldest one is
+    next: usize,
+}
+
+impl History {
+    ///a history that keeps the last `capacity` instructions
+    pub fn new(capacity: usize) -> Self {
+        History {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+        }
+    }
+    pub(crate) fn record(&mut self, entry: Entry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.next] = entry;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+    ///the instructions kept, from the oldest to the one that ran last
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        let (newer, older) = match self.entries.len() < self.capacity {
+            true => (&self.entries[..], &[][..]),
+            false => self.entries.split_at(self.next),
+        };
+        older.iter().chain(newer)
+    }
+    ///leaves out the instructions that ran after the step, for when the machine is taken back
+    ///to it and they haven't happened any more
+    #[cfg(feature = "std")]
+    pub(crate) fn forget_after(&mut self, step: u64) {
+        let kept: Vec<Entry> = self
+            .entries()
+            .filter(|entry| entry.step <= step)
+            .copied()
+            .collect();
+        self.next = kept.len() % self.capacity.max(1);
+        self.entries = kept;
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
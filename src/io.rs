@@ -0,0 +1,418 @@
+//! where a machine reads its input from and writes its output to. the machine only ever needs one
+//! byte at a time, so these are two tiny traits instead of std's Read and Write, which don't exist
+//! without std. with std, everything that is Read or Write is one of these already, so stdin,
+//! files, cursors and vectors can be handed to a machine as they are. without it, the program
+//! embedding the interpreter implements them for whatever its bytes come from, like a serial port
+use crate::random::Rng;
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+///something a machine can read its input from
+pub trait InputByte {
+    ///the next byte, or nothing once the input has run out. an error is a description of what
+    ///went wrong
+    fn read_byte(&mut self) -> Result<Option<u8>, String>;
+}
+
+///what write_byte and flush_bytes give back when whatever was reading the output has gone away,
+///like "head" once it has all the lines it wants. the machine stops with RunOutcome::OutputClosed
+///then, instead of failing
+pub const OUTPUT_CLOSED: &str = "the output was closed";
+
+///something a machine can write its output to
+pub trait OutputByte {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String>;
+    ///makes sure everything written so far has actually gone out. most outputs don't hold on to
+    ///anything, so by default this does nothing
+    fn flush_bytes(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized> InputByte for R {
+    fn read_byte(&mut self) -> Result<Option<u8>, String> {
+        let mut byte: [u8; 1] = [0; 1];
+        match self.read_exact(&mut byte) {
+            Ok(()) => Ok(Some(byte[0])),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> OutputByte for W {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        self.write_all(&[byte]).map_err(describe_write_error)
+    }
+    fn flush_bytes(&mut self) -> Result<(), String> {
+        self.flush().map_err(describe_write_error)
+    }
+}
+
+///what went wrong writing, which is OUTPUT_CLOSED for a broken pipe
+#[cfg(feature = "std")]
+fn describe_write_error(e: std::io::Error) -> String {
+    match e.kind() {
+        std::io::ErrorKind::BrokenPipe => OUTPUT_CLOSED.to_string(),
+        _ => e.to_string(),
+    }
+}
+
+///output that goes to several outputs at once, like "--output" given more than once, so the
+///machine writing to it doesn't know or care how many there are. every byte goes to each of them
+///in the order they were added, and the first one that fails stops the rest from getting it.
+///
+///the outputs with a name are where the output is kept, like files, and when one fails the error
+///says which one it was. a closed output isn't a failure but OUTPUT_CLOSED, like it is with only
+///one. the ones without a name only look at the bytes going by, like a recording, and their
+///errors are passed on as they are
+#[derive(Default)]
+pub struct Tee {
+    outputs: Vec<(Option<String>, Box<dyn OutputByte + Send>)>,
+}
+
+impl Tee {
+    pub fn new() -> Self {
+        Tee::default()
+    }
+    ///adds an output the bytes are written to, named the way errors about it should call it
+    pub fn add(&mut self, name: &str, output: impl OutputByte + Send + 'static) {
+        self.outputs
+            .push((Some(name.to_string()), Box::new(output)));
+    }
+    ///adds something that only looks at the bytes, and fails with errors of its own
+    pub fn add_unnamed(&mut self, output: impl OutputByte + Send + 'static) {
+        self.outputs.push((None, Box::new(output)));
+    }
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+}
+
+impl OutputByte for Tee {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        for (name, output) in &mut self.outputs {
+            output
+                .write_byte(byte)
+                .map_err(|e| name_error(name.as_deref(), e))?;
+        }
+        Ok(())
+    }
+    ///flushes every output, even after one of them failed, and gives back the first error
+    fn flush_bytes(&mut self) -> Result<(), String> {
+        let mut flushed = Ok(());
+        for (name, output) in &mut self.outputs {
+            if let (Err(e), Ok(())) = (output.flush_bytes(), &flushed) {
+                flushed = Err(name_error(name.as_deref(), e));
+            }
+        }
+        flushed
+    }
+}
+
+///the error an output of a tee gave, saying which output it was when it has a name
+fn name_error(name: Option<&str>, e: String) -> String {
+    match name {
+        Some(name) if e != OUTPUT_CLOSED => format!("could not write {}: {}", name, e),
+        _ => e,
+    }
+}
+
+///input made up by a seeded random number generator, for "--input random:<seed>[:<length>]".
+///the same seed always gives the same bytes, so a run on random input can be had again. with a
+///length the input ends after that many bytes, and "," does what --eof says from then on, without
+///one it never ends
+#[derive(PartialEq, Debug, Clone)]
+pub struct RandomInput {
+    pub seed: u64,
+    rng: Rng,
+    remaining: Option<u64>,
+}
+
+impl RandomInput {
+    pub fn new(seed: u64, length: Option<u64>) -> Self {
+        RandomInput {
+            seed,
+            rng: Rng::from_seed(seed),
+            remaining: length,
+        }
+    }
+    ///the input for what comes after "random:", a seed or "auto" for one that's different every
+    ///run, then a length if there is one, like "42:100". nothing if it isn't that
+    #[cfg(feature = "std")]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (seed, length) = match name.split_once(':') {
+            Some((seed, length)) => (seed, Some(length.parse().ok()?)),
+            None => (name, None),
+        };
+        let seed = match seed {
+            "auto" => Rng::from_entropy().next_u64(),
+            seed => seed.parse().ok()?,
+        };
+        Some(RandomInput::new(seed, length))
+    }
+    fn next(&mut self) -> Option<u8> {
+        match &mut self.remaining {
+            Some(0) => None,
+            Some(remaining) => {
+                *remaining -= 1;
+                Some(self.rng.next_byte())
+            }
+            None => Some(self.rng.next_byte()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for RandomInput {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut read = 0;
+        for byte in buf.iter_mut() {
+            match self.next() {
+                Some(next) => *byte = next,
+                None => break,
+            }
+            read += 1;
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl InputByte for RandomInput {
+    fn read_byte(&mut self) -> Result<Option<u8>, String> {
+        Ok(self.next())
+    }
+}
+
+//std already has these as Read and Write, so they only need writing out without it
+
+#[cfg(not(feature = "std"))]
+impl InputByte for VecDeque<u8> {
+    fn read_byte(&mut self) -> Result<Option<u8>, String> {
+        Ok(self.pop_front())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl OutputByte for Vec<u8> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        self.push(byte);
+        Ok(())
+    }
+}
+
+///a terminal's input with every "\r\n" made into "\n". a windows console hands over both when
+///enter is pressed, where a pipe, a file or a terminal anywhere else only has the "\n", and a
+///program reading a line up to "\n" would see the "\r" too. a "\r" on its own is kept
+#[cfg(feature = "std")]
+pub struct ConsoleInput<R> {
+    input: R,
+    //what's been read and had its "\r\n"s made into "\n", but not handed out yet
+    buffer: Vec<u8>,
+    //whether the last byte read was a "\r", which is held back until it's known whether a "\n"
+    //comes next
+    returned: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R> ConsoleInput<R> {
+    pub fn new(input: R) -> Self {
+        ConsoleInput {
+            input,
+            buffer: Vec::new(),
+            returned: false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> std::io::Read for ConsoleInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.buffer.is_empty() {
+            let mut chunk = [0; 4096];
+            let read = self.input.read(&mut chunk)?;
+            if read == 0 {
+                match core::mem::take(&mut self.returned) {
+                    true => self.buffer.push(b'\r'),
+                    false => return Ok(0),
+                }
+            }
+            for &byte in &chunk[..read] {
+                if core::mem::take(&mut self.returned) && byte != b'\n' {
+                    self.buffer.push(b'\r');
+                }
+                match byte {
+                    b'\r' => self.returned = true,
+                    byte => self.buffer.push(byte),
+                }
+            }
+        }
+        let length = buf.len().min(self.buffer.len());
+        buf[..length].copy_from_slice(&self.buffer[..length]);
+        self.buffer.drain(..length);
+        Ok(length)
+    }
+}
+
+///output to a terminal that only takes utf-8, like a windows console, which std refuses to write
+///anything else to. valid utf-8 goes out as it is, even when its bytes come one at a time, and
+///every byte that can't be part of any is shown as "\u{FFFD}", the way terminals elsewhere show
+///them. files and pipes take any bytes, so output going there doesn't need this
+#[cfg(feature = "std")]
+pub struct ConsoleOutput<W: std::io::Write> {
+    output: W,
+    //the start of a character whose other bytes haven't been written yet
+    pending: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> ConsoleOutput<W> {
+    pub fn new(output: W) -> Self {
+        ConsoleOutput {
+            output,
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for ConsoleOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        loop {
+            let error = match core::str::from_utf8(&self.pending) {
+                Ok(text) => {
+                    self.output.write_all(text.as_bytes())?;
+                    self.pending.clear();
+                    return Ok(buf.len());
+                }
+                Err(error) => error,
+            };
+            let valid = error.valid_up_to();
+            self.output.write_all(&self.pending[..valid])?;
+            match error.error_len() {
+                Some(invalid) => {
+                    self.output.write_all("\u{FFFD}".as_bytes())?;
+                    self.pending.drain(..valid + invalid);
+                }
+                //the character isn't finished, the next write might finish it
+                None => {
+                    self.pending.drain(..valid);
+                    return Ok(buf.len());
+                }
+            }
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.output.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Drop for ConsoleOutput<W> {
+    //a character that never got finished is shown as "\u{FFFD}" too
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            let _ = self.output.write_all("\u{FFFD}".as_bytes());
+            let _ = self.output.flush();
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "std")))]
+mod tests {
+    use super::*;
+    use crate::control::RunOutcome;
+    use crate::lang::Lang;
+    use crate::shared::{self, Shared};
+    use crate::{RuntimeError, TuringMachine};
+
+    ///a serial port that has given out the bytes it had and then goes down
+    struct Port(Vec<u8>);
+
+    impl InputByte for Port {
+        fn read_byte(&mut self) -> Result<Option<u8>, String> {
+            match self.0.is_empty() {
+                true => Err("the port is down".to_string()),
+                false => Ok(Some(self.0.remove(0))),
+            }
+        }
+    }
+
+    ///a display that only has room for so many bytes
+    struct Display(usize);
+
+    impl OutputByte for Display {
+        fn write_byte(&mut self, _: u8) -> Result<(), String> {
+            match self.0 {
+                0 => Err("the display is full".to_string()),
+                _ => {
+                    self.0 -= 1;
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn machine(source: &str) -> TuringMachine {
+        TuringMachine::new(source, &Lang::Brainfuck).expect("it parses")
+    }
+
+    #[test]
+    fn a_queue_and_a_vector() {
+        let mut input: VecDeque<u8> = VecDeque::from(b"ab".to_vec());
+        assert_eq!(input.read_byte(), Ok(Some(b'a')));
+        assert_eq!(input.read_byte(), Ok(Some(b'b')));
+        assert_eq!(input.read_byte(), Ok(None));
+        let mut output = Vec::new();
+        output.write_byte(b'c').expect("a vector always has room");
+        assert_eq!(output.flush_bytes(), Ok(()));
+        assert_eq!(output, b"c");
+    }
+
+    ///cat, reading from a queue and writing to a vector, the way a program without std hands
+    ///them over
+    #[test]
+    fn running_cat() {
+        let mut tm = machine(",[.,]");
+        let output: Shared<Vec<u8>> = shared::shared(Vec::new());
+        tm.input = shared::shared(VecDeque::from(b"hello".to_vec()));
+        tm.output = output.clone();
+        assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+        assert_eq!(*shared::lock(&output), b"hello");
+    }
+
+    #[test]
+    fn failing() {
+        let mut tm = machine(",.,.,");
+        tm.input = shared::shared(Port(b"x".to_vec()));
+        tm.output = shared::shared(Vec::new());
+        assert_eq!(
+            tm.run(),
+            Err(RuntimeError::IoError(
+                "could not read input: the port is down".to_string()
+            ))
+        );
+        let mut tm = machine("+.+.");
+        tm.output = shared::shared(Display(1));
+        assert_eq!(
+            tm.run(),
+            Err(RuntimeError::IoError(
+                "could not write output: the display is full".to_string()
+            ))
+        );
+    }
+}
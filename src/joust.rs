@@ -0,0 +1,425 @@
+//! bf joust, where two programs share a tape and each tries to zero the other's flag. the tape
+//! has a flag at each end, both starting out as 128, and each program starts on its own flag,
+//! facing the other: the program on the right has its ">" and "<" the other way around. every
+//! cycle both programs run one instruction at the same time, and a program loses when its flag
+//! has been 0 at the end of two cycles in a row, or when it moves off the tape. when both lose
+//! in the same cycle, or neither has lost after MAX_CYCLES, it's a tie.
+//!
+//! a match is a duel on every tape length from 10 to 30, once as they are (sieve) and once with
+//! the right program's "+" and "-" swapped (kettle), 42 duels in all.
+//!
+//! programs are brainfuck with a few changes: "." does nothing for a cycle, "," is a comment,
+//! `(a)*n` is a repeated n times, and `(a{b}c)%n` is a repeated n times, then b, then c repeated
+//! n times. a count of -1 repeats for as long as a duel can last. repetitions nest, and brackets
+//! match up after they've been written out, so `(a[)*3 b (])*3` is three loops inside each other.
+//! repetitions and loops can be nested program::MAX_NESTING deep, like any other program
+use crate::{lang, program, Instruction, ParseError, Position, Program};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::ops::RangeInclusive;
+
+///what both flags start out as
+pub const FLAG: u8 = 128;
+///how many cycles a duel lasts at most before it's a tie
+pub const MAX_CYCLES: usize = 100_000;
+///the tape lengths a match is played on
+pub const TAPE_LENGTHS: RangeInclusive<usize> = 10..=30;
+///how many instructions a program can have once its repetitions have been written out
+const MAX_LENGTH: usize = 10_000_000;
+
+///what can keep a bf joust program from being parsed
+#[derive(PartialEq, Debug)]
+pub enum JoustError {
+    ///a bracket that doesn't match, once the repetitions have been written out
+    ParseError(ParseError),
+    ///a "(" that never got closed by a matching ")"
+    UnmatchedOpen(Position),
+    ///a ")", "{" or "}" that isn't part of a repetition
+    Unexpected(char, Position),
+    ///a repetition that isn't followed by "*" or "%" and a count, with where it starts
+    MissingCount(Position),
+    ///a repetition with "{" and "}" that's repeated with "*", or one without them repeated with
+    ///"%"
+    WrongRepeat(Position),
+    ///the program is longer than MAX_LENGTH once its repetitions have been written out, with the
+    ///repetition that made it too long
+    TooLong(Position),
+}
+
+impl fmt::Display for JoustError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JoustError::ParseError(e) => write!(f, "{}", e),
+            JoustError::UnmatchedOpen(position) => write!(f, "unmatched '(' at {}", position),
+            JoustError::Unexpected(c, position) => {
+                write!(f, "'{}' at {} isn't inside a repetition", c, position)
+            }
+            JoustError::MissingCount(position) => {
+                write!(
+                    f,
+                    "the repetition at {} needs a count after it, like *10 or %10",
+                    position
+                )
+            }
+            JoustError::WrongRepeat(position) => {
+                write!(
+                    f,
+                    "the repetition at {} has to be (a)*n, or (a{{b}}c)%n with braces",
+                    position
+                )
+            }
+            JoustError::TooLong(position) => {
+                write!(
+                    f,
+                    "the repetition at {} makes the program longer than {} instructions",
+                    position, MAX_LENGTH
+                )
+            }
+        }
+    }
+}
+
+///turns the source of a bf joust program into a program, with its repetitions written out
+pub fn parse(source: &str) -> Result<Program, JoustError> {
+    parse_with(source, program::MAX_NESTING)
+}
+
+///parse, with repetitions, loops once they've been written out, and the two together nested at
+///most max_nesting deep
+pub fn parse_with(source: &str, max_nesting: usize) -> Result<Program, JoustError> {
+    let mut parser = Parser {
+        chars: lang::chars_with_positions(lang::skip_shebang(source)).collect(),
+        index: 0,
+    };
+    let tokens = parser.tokens(max_nesting)?;
+    Program::check_all(tokens, max_nesting)
+        .map_err(|mut errors| JoustError::ParseError(errors.swap_remove(0)))
+}
+
+///reads the source a character at a time, writing out repetitions as it goes
+struct Parser {
+    chars: Vec<(char, Position)>,
+    index: usize,
+}
+
+///a repetition that's been opened but not closed yet
+struct Repetition {
+    ///where its "(" is
+    start: Position,
+    ///the instructions before its "(", which it gets written out after
+    outer: Vec<(Instruction, Position)>,
+    ///the parts of it that have been read, the one before "{" and the one between "{" and "}"
+    parts: Vec<Vec<(Instruction, Position)>>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(char, Position)> {
+        self.chars.get(self.index).copied()
+    }
+    ///the instructions in the whole source. the repetitions it's in the middle of are kept on a
+    ///stack rather than read by calling into each other, so how deep they go is only up to
+    ///max_nesting
+    fn tokens(&mut self, max_nesting: usize) -> Result<Vec<(Instruction, Position)>, JoustError> {
+        let mut open: Vec<Repetition> = vec![];
+        let mut tokens = vec![];
+        while let Some((c, position)) = self.peek() {
+            self.index += 1;
+            let instruction = match c {
+                '(' => {
+                    if open.len() >= max_nesting {
+                        return Err(JoustError::ParseError(ParseError::TooDeep(
+                            position,
+                            max_nesting,
+                        )));
+                    }
+                    open.push(Repetition {
+                        start: position,
+                        outer: mem::take(&mut tokens),
+                        parts: vec![],
+                    });
+                    continue;
+                }
+                ')' | '{' | '}' => {
+                    let Some(repetition) = open.last_mut() else {
+                        return Err(JoustError::Unexpected(c, position));
+                    };
+                    //"{" ends the first part and "}" the second, ")" the last
+                    match (c, repetition.parts.len()) {
+                        ('{', 0) | ('}', 1) => {
+                            repetition.parts.push(mem::take(&mut tokens));
+                            continue;
+                        }
+                        (')', 0 | 2) => repetition.parts.push(mem::take(&mut tokens)),
+                        _ => return Err(JoustError::UnmatchedOpen(repetition.start)),
+                    }
+                    let repetition = open.pop().expect("it was just looked at");
+                    let written = self.repeat(repetition.start, repetition.parts)?;
+                    tokens = repetition.outer;
+                    tokens.extend(written);
+                    if tokens.len() > MAX_LENGTH {
+                        return Err(JoustError::TooLong(repetition.start));
+                    }
+                    continue;
+                }
+                '>' => Instruction::MoveRight,
+                '<' => Instruction::MoveLeft,
+                '+' => Instruction::Increment,
+                '-' => Instruction::Decrement,
+                '.' => Instruction::Output,
+                '[' => Instruction::JumpToClose,
+                ']' => Instruction::JumpToOpen,
+                _ => continue,
+            };
+            tokens.push((instruction, position));
+        }
+        match open.last() {
+            Some(repetition) => Err(JoustError::UnmatchedOpen(repetition.start)),
+            None => Ok(tokens),
+        }
+    }
+    ///the repetition that starts at the position, with the parts of it between its brackets,
+    ///written out by the "*" or "%" and count after its ")"
+    fn repeat(
+        &mut self,
+        start: Position,
+        mut parts: Vec<Vec<(Instruction, Position)>>,
+    ) -> Result<Vec<(Instruction, Position)>, JoustError> {
+        let repeat = match self.peek() {
+            Some((c @ ('*' | '%'), _)) => c,
+            _ => return Err(JoustError::MissingCount(start)),
+        };
+        self.index += 1;
+        let count = self.count(start)?;
+        let repeated = |tokens: &[(Instruction, Position)]| {
+            let length = tokens.len().saturating_mul(count);
+            match length > MAX_LENGTH {
+                true => Err(JoustError::TooLong(start)),
+                false => Ok(tokens.repeat(count)),
+            }
+        };
+        let tokens = match (repeat, parts.len()) {
+            ('*', 1) => repeated(&parts[0])?,
+            ('%', 3) => {
+                let after = parts.pop().expect("there are three parts");
+                let middle = parts.pop().expect("there are three parts");
+                let mut tokens = repeated(&parts[0])?;
+                tokens.extend(middle);
+                tokens.extend(repeated(&after)?);
+                tokens
+            }
+            _ => return Err(JoustError::WrongRepeat(start)),
+        };
+        if tokens.len() > MAX_LENGTH {
+            return Err(JoustError::TooLong(start));
+        }
+        Ok(tokens)
+    }
+    ///the count after the "*" or "%" of the repetition that starts at the position, where -1 is
+    ///as many times as a duel can use
+    fn count(&mut self, start: Position) -> Result<usize, JoustError> {
+        let mut digits = String::new();
+        while let Some((c, _)) = self.peek() {
+            if !(c.is_ascii_digit() || (c == '-' && digits.is_empty())) {
+                break;
+            }
+            digits.push(c);
+            self.index += 1;
+        }
+        match digits.as_str() {
+            "-1" => Ok(MAX_CYCLES),
+            digits => digits.parse().map_err(|_| JoustError::MissingCount(start)),
+        }
+    }
+}
+
+///whether the right program's "+" and "-" are swapped
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Polarity {
+    ///as they are
+    Sieve,
+    ///swapped
+    Kettle,
+}
+
+///who won a duel
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Winner {
+    Left,
+    Right,
+    Tie,
+}
+
+impl Winner {
+    ///how the duel is shown in the grid: "<" when the left program won, ">" when the right one
+    ///did, and "X" for a tie
+    pub fn symbol(self) -> char {
+        match self {
+            Winner::Left => '<',
+            Winner::Right => '>',
+            Winner::Tie => 'X',
+        }
+    }
+}
+
+///one of the two programs in a duel
+struct Warrior<'a> {
+    program: &'a Program,
+    pc: usize,
+    pointer: usize,
+    //the cell with its flag, and which way its ">" goes
+    flag: usize,
+    forward: isize,
+    //-1 when its "+" and "-" are swapped
+    sign: i8,
+    //how many cycles in a row its flag has been 0 at the end of
+    flag_down: u8,
+}
+
+impl Warrior<'_> {
+    ///runs the next instruction against the tape as it was at the start of the cycle. gives back
+    ///how much the cell it's on changes, or None when it moved off the tape
+    fn step(&mut self, tape: &[u8]) -> Option<i8> {
+        let Some(instruction) = self.program.instruction(self.pc) else {
+            return Some(0);
+        };
+        let mut change = 0;
+        match instruction {
+            Instruction::MoveRight | Instruction::MoveLeft => {
+                let step = match instruction {
+                    Instruction::MoveRight => self.forward,
+                    _ => -self.forward,
+                };
+                self.pointer = self
+                    .pointer
+                    .checked_add_signed(step)
+                    .filter(|&pointer| pointer < tape.len())?;
+            }
+            Instruction::Increment => change = self.sign,
+            Instruction::Decrement => change = -self.sign,
+            Instruction::JumpToClose if tape[self.pointer] == 0 => {
+                self.pc = self.program.ops[self.pc].target()
+            }
+            Instruction::JumpToOpen if tape[self.pointer] != 0 => {
+                self.pc = self.program.ops[self.pc].target()
+            }
+            _ => {}
+        }
+        self.pc += 1;
+        Some(change)
+    }
+}
+
+///plays the two programs against each other on a tape of the length
+pub fn duel(left: &Program, right: &Program, tape_length: usize, polarity: Polarity) -> Winner {
+    let mut tape = vec![0u8; tape_length];
+    tape[0] = FLAG;
+    tape[tape_length - 1] = FLAG;
+    let mut warriors = [
+        Warrior {
+            program: left,
+            pc: 0,
+            pointer: 0,
+            flag: 0,
+            forward: 1,
+            sign: 1,
+            flag_down: 0,
+        },
+        Warrior {
+            program: right,
+            pc: 0,
+            pointer: tape_length - 1,
+            flag: tape_length - 1,
+            forward: -1,
+            sign: match polarity {
+                Polarity::Sieve => 1,
+                Polarity::Kettle => -1,
+            },
+            flag_down: 0,
+        },
+    ];
+    for _ in 0..MAX_CYCLES {
+        let mut lost = [false; 2];
+        let mut changes = [(0, 0); 2];
+        for (side, warrior) in warriors.iter_mut().enumerate() {
+            match warrior.step(&tape) {
+                Some(change) => changes[side] = (warrior.pointer, change),
+                None => lost[side] = true,
+            }
+        }
+        for (cell, change) in changes {
+            tape[cell] = tape[cell].wrapping_add_signed(change);
+        }
+        for (side, warrior) in warriors.iter_mut().enumerate() {
+            warrior.flag_down = match tape[warrior.flag] {
+                0 => warrior.flag_down + 1,
+                _ => 0,
+            };
+            lost[side] |= warrior.flag_down >= 2;
+        }
+        match lost {
+            [true, true] => return Winner::Tie,
+            [true, false] => return Winner::Right,
+            [false, true] => return Winner::Left,
+            [false, false] => {}
+        }
+    }
+    Winner::Tie
+}
+
+///how every duel of a match went, by tape length, from the shortest
+#[derive(PartialEq, Debug, Clone)]
+pub struct Match {
+    pub sieve: Vec<Winner>,
+    pub kettle: Vec<Winner>,
+}
+
+///plays a match, a duel on every tape length in both polarities
+pub fn play(left: &Program, right: &Program) -> Match {
+    let duels = |polarity| {
+        TAPE_LENGTHS
+            .map(|tape_length| duel(left, right, tape_length, polarity))
+            .collect()
+    };
+    Match {
+        sieve: duels(Polarity::Sieve),
+        kettle: duels(Polarity::Kettle),
+    }
+}
+
+impl Match {
+    ///how many duels the winner won
+    pub fn wins(&self, winner: Winner) -> usize {
+        self.sieve
+            .iter()
+            .chain(&self.kettle)
+            .filter(|duel| **duel == winner)
+            .count()
+    }
+    ///the left program's score: one for every duel it won, less one for every duel it lost.
+    ///positive means the left program won the match, negative the right one
+    pub fn score(&self) -> i64 {
+        self.wins(Winner::Left) as i64 - self.wins(Winner::Right) as i64
+    }
+}
+
+///the grid of duels, a row for each polarity and a column for each tape length
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lengths: String = TAPE_LENGTHS
+            .map(|length| match length % 10 {
+                0 => format!("{}", length / 10),
+                _ => String::from(" "),
+            })
+            .collect();
+        writeln!(f, "        {}", lengths)?;
+        for (name, duels) in [("sieve", &self.sieve), ("kettle", &self.kettle)] {
+            let row: String = duels.iter().map(|duel| duel.symbol()).collect();
+            writeln!(f, "{:<8}{}", name, row)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! languages that are brainfuck with the eight commands renamed. the renaming comes from a small
+//! toml file with one line per command, the brainfuck command on the left and the token that
+//! replaces it on the right:
+//!
+//! ```toml
+//! ">" = "a"
+//! "<" = "c"
+//! ```
+//!
+//! only this flat kind of toml is understood, which is all a mapping ever needs
+use super::chars_with_positions;
+use crate::{Instruction, Position};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+///the tokens a dialect uses instead of the eight brainfuck commands
+#[derive(PartialEq, Debug, Clone)]
+pub struct Mapping {
+    //every token with the instruction it stands for, longest tokens first so that scanning can
+    //stop at the first one that matches
+    tokens: Vec<(Vec<char>, Instruction)>,
+}
+
+///everything that can be wrong with a mapping file
+#[derive(PartialEq, Debug)]
+pub enum MappingError {
+    ///a line that isn't a `"key" = "value"` pair, by line number
+    Syntax(usize),
+    ///a key that isn't one of the eight brainfuck commands
+    UnknownCommand(String, usize),
+    ///a command that has a token given more than once
+    Duplicate(String, usize),
+    ///a command that has no token at all
+    Missing(char),
+    ///a command that is mapped to the empty string
+    Empty(String, usize),
+    ///two tokens where the first is the start of the second, so it isn't clear which one is meant
+    Ambiguous(String, String),
+}
+
+impl fmt::Display for MappingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MappingError::Syntax(line) => {
+                write!(
+                    f,
+                    "line {} should look like \"<command>\" = \"<token>\"",
+                    line
+                )
+            }
+            MappingError::UnknownCommand(key, line) => {
+                write!(f, "'{}' on line {} is not a brainfuck command", key, line)
+            }
+            MappingError::Duplicate(key, line) => {
+                write!(f, "'{}' on line {} already has a token", key, line)
+            }
+            MappingError::Missing(command) => write!(f, "'{}' has no token", command),
+            MappingError::Empty(key, line) => {
+                write!(f, "the token for '{}' on line {} is empty", key, line)
+            }
+            MappingError::Ambiguous(prefix, token) => write!(
+                f,
+                "the token '{}' is the start of the token '{}', so they can't be told apart",
+                prefix, token
+            ),
+        }
+    }
+}
+
+///reads a double quoted toml string from the start of "text", handling the usual escapes. gives
+///back the string and whatever comes after the closing quote
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.strip_prefix('"')?.char_indices();
+    let mut string = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((string, &text[index + 2..])),
+            '\\' => string.push(match chars.next()?.1 {
+                'n' => '\n',
+                't' => '\t',
+                '"' => '"',
+                '\\' => '\\',
+                _ => return None,
+            }),
+            _ => string.push(c),
+        }
+    }
+    None
+}
+
+impl Mapping {
+    ///reads a mapping from the text of a mapping file
+    pub fn parse(text: &str) -> Result<Self, MappingError> {
+        let mut tokens: Vec<(Vec<char>, Instruction)> = vec![];
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, rest) = quoted(line).ok_or(MappingError::Syntax(line_number))?;
+            let rest = rest
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or(MappingError::Syntax(line_number))?;
+            let (token, rest) =
+                quoted(rest.trim_start()).ok_or(MappingError::Syntax(line_number))?;
+            let rest = rest.trim_start();
+            if !(rest.is_empty() || rest.starts_with('#')) {
+                return Err(MappingError::Syntax(line_number));
+            }
+            let instruction = match key.as_str() {
+                ">" => Instruction::MoveRight,
+                "<" => Instruction::MoveLeft,
+                "+" => Instruction::Increment,
+                "-" => Instruction::Decrement,
+                "." => Instruction::Output,
+                "," => Instruction::Replace,
+                "[" => Instruction::JumpToClose,
+                "]" => Instruction::JumpToOpen,
+                _ => return Err(MappingError::UnknownCommand(key, line_number)),
+            };
+            if tokens.iter().any(|(_, existing)| *existing == instruction) {
+                return Err(MappingError::Duplicate(key, line_number));
+            }
+            if token.is_empty() {
+                return Err(MappingError::Empty(key, line_number));
+            }
+            tokens.push((token.chars().collect(), instruction));
+        }
+        for (command, instruction) in "><+-.,[]".chars().zip([
+            Instruction::MoveRight,
+            Instruction::MoveLeft,
+            Instruction::Increment,
+            Instruction::Decrement,
+            Instruction::Output,
+            Instruction::Replace,
+            Instruction::JumpToClose,
+            Instruction::JumpToOpen,
+        ]) {
+            if !tokens.iter().any(|(_, existing)| *existing == instruction) {
+                return Err(MappingError::Missing(command));
+            }
+        }
+        tokens.sort_by_key(|(token, _)| core::cmp::Reverse(token.len()));
+        for (index, (token, _)) in tokens.iter().enumerate() {
+            //only shorter (or equally long) tokens come after this one
+            for (other, _) in &tokens[index + 1..] {
+                if token.starts_with(other) {
+                    return Err(MappingError::Ambiguous(
+                        other.iter().collect(),
+                        token.iter().collect(),
+                    ));
+                }
+            }
+        }
+        Ok(Mapping { tokens })
+    }
+}
+
+///turns a program written in a mapped dialect into instructions. at every character the longest
+///token that matches there wins, and anything that doesn't start a token is a comment
+pub fn tokenize(source: &str, mapping: &Mapping) -> Vec<(Instruction, Position)> {
+    let chars: Vec<(char, Position)> = chars_with_positions(source).collect();
+    let mut instructions = vec![];
+    let mut index = 0;
+    while index < chars.len() {
+        let found = mapping.tokens.iter().find(|(token, _)| {
+            chars.len() - index >= token.len()
+                && token
+                    .iter()
+                    .zip(&chars[index..])
+                    .all(|(expected, (c, _))| expected == c)
+        });
+        match found {
+            Some((token, instruction)) => {
+                instructions.push((*instruction, chars[index].1));
+                index += token.len();
+            }
+            None => index += 1,
+        }
+    }
+    instructions
+}
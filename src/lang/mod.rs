@@ -0,0 +1,309 @@
+//! the different languages a program can be written in. they all turn into the same list of
+//! instructions, so once a program has been tokenized nothing else needs to know which language
+//! it was written in
+use crate::{Instruction, ParseError, Position};
+use alloc::vec::Vec;
+
+mod mapping;
+mod ook;
+mod spoon;
+
+pub use mapping::Mapping;
+
+///every input language the interpreter understands
+#[derive(PartialEq, Debug, Clone)]
+pub enum Lang {
+    Brainfuck,
+    Ook,
+    ///brainfuck written in ones and zeroes
+    Spoon,
+    ///brainfuck with procedures, "(" and ")" around a procedure's body and ":" to call one
+    Pbrain,
+    ///extended brainfuck type I, which adds a byte of storage and bitwise operations on cells
+    Ebf1,
+    ///brainfuck where "Y" splits the running program in two
+    Brainfork,
+    ///brainfuck on a tape of bits instead of bytes
+    Boolfuck,
+    ///brainfuck++'s file instructions, which let a program read and write files. only ever
+    ///turned on by asking for it
+    BfFile,
+    ///brainfuck with "%", which runs text on the tape as a program
+    BfEval,
+    ///brainfuck with "?", which puts a random byte in the current cell
+    BfRand,
+    ///brainfuck with "@", which stops the program right away, even from deep inside loops
+    BfHalt,
+    ///brainfuck with "'", which makes "," read from the input channel numbered by the current
+    ///cell, see channels.rs
+    BfChannels,
+    ///brainfuck with the eight commands renamed by a mapping file
+    Mapped(Mapping),
+}
+
+impl Lang {
+    ///every language that has a name, which is all of them but the mapped ones
+    pub const ALL: [Lang; 12] = [
+        Lang::Brainfuck,
+        Lang::Ook,
+        Lang::Spoon,
+        Lang::Pbrain,
+        Lang::Ebf1,
+        Lang::Brainfork,
+        Lang::Boolfuck,
+        Lang::BfFile,
+        Lang::BfEval,
+        Lang::BfRand,
+        Lang::BfHalt,
+        Lang::BfChannels,
+    ];
+    ///the name used on the command line, nothing for a mapped language
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Lang::Brainfuck => Some("bf"),
+            Lang::Ook => Some("ook"),
+            Lang::Spoon => Some("spoon"),
+            Lang::Pbrain => Some("pbrain"),
+            Lang::Ebf1 => Some("ebf1"),
+            Lang::Brainfork => Some("brainfork"),
+            Lang::Boolfuck => Some("boolfuck"),
+            Lang::BfFile => Some("bf-file"),
+            Lang::BfEval => Some("bf-eval"),
+            Lang::BfRand => Some("bf-rand"),
+            Lang::BfHalt => Some("bf-halt"),
+            Lang::BfChannels => Some("bf-channels"),
+            Lang::Mapped(_) => None,
+        }
+    }
+    ///turns the name used on the command line into the matching language. "brainfuck" is
+    ///another name for bf
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = match name {
+            "brainfuck" => "bf",
+            name => name,
+        };
+        Lang::ALL.into_iter().find(|lang| lang.name() == Some(name))
+    }
+    ///guesses the language from the extension of the file the program is in, under a ".gz" if
+    ///it's compressed. anything we don't recognise is assumed to be plain brainfuck
+    #[cfg(feature = "std")]
+    pub fn from_path(path: &str) -> Self {
+        Lang::from_extension(path).unwrap_or(Lang::Brainfuck)
+    }
+    ///the language the extension of the file says the program is in, or None when there's no
+    ///extension or it's one we don't know. a url goes by its path, without the query
+    #[cfg(feature = "std")]
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let path = match crate::program::is_url(path) {
+            true => path.split(['?', '#']).next().unwrap_or(path),
+            false => path,
+        };
+        match std::path::Path::new(path.strip_suffix(".gz").unwrap_or(path))
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("b") | Some("bf") => Some(Lang::Brainfuck),
+            Some("ook") => Some(Lang::Ook),
+            Some("spoon") => Some(Lang::Spoon),
+            Some("pb") | Some("pbrain") => Some(Lang::Pbrain),
+            _ => None,
+        }
+    }
+    ///guesses the language from the program itself: Ook! when more than nine in ten of its
+    ///words are Ook words, Spoon when more than nine in ten of the characters that aren't
+    ///whitespace are ones and zeroes, and brainfuck otherwise
+    pub fn from_content(source: &str) -> Self {
+        let words = source.split_whitespace();
+        let ook_words = words
+            .clone()
+            .filter(|word| matches!(word.as_bytes(), [b'O', b'o', b'k', b'.' | b'?' | b'!']))
+            .count();
+        let chars = source.chars().filter(|c| !c.is_whitespace());
+        let bits = chars.clone().filter(|c| *c == '0' || *c == '1').count();
+        match (ook_words, bits) {
+            (ook_words, _) if ook_words > 0 && ook_words * 10 > words.count() * 9 => Lang::Ook,
+            (_, bits) if bits > 0 && bits * 10 > chars.count() * 9 => Lang::Spoon,
+            _ => Lang::Brainfuck,
+        }
+    }
+    ///the language the extension says the program is in, or when it doesn't say, the one
+    ///from_content guesses from the program
+    #[cfg(feature = "std")]
+    pub fn detect(path: &str, source: &str) -> Self {
+        Lang::from_extension(path).unwrap_or_else(|| Lang::from_content(source))
+    }
+}
+
+///every character of the source text together with the position it is at. every line ends in a
+///'\n', even when the file doesn't, so tokens can't run on from one line into the next
+pub(crate) fn chars_with_positions(source: &str) -> impl Iterator<Item = (char, Position)> + '_ {
+    source.lines().enumerate().flat_map(|(line_index, line)| {
+        line.chars()
+            .chain(['\n'])
+            .enumerate()
+            .map(move |(column_index, c)| {
+                (
+                    c,
+                    Position {
+                        line: line_index + 1,
+                        column: column_index + 1,
+                    },
+                )
+            })
+    })
+}
+
+///the source without the "#!" line at its start, if it has one, so a program can be made
+///executable and run as a script. the line's newline is kept, so everything after it stays on
+///the line it's on in the file
+pub fn skip_shebang(source: &str) -> &str {
+    match source.strip_prefix("#!") {
+        Some(rest) => &rest[rest.find('\n').unwrap_or(rest.len())..],
+        None => source,
+    }
+}
+
+///turns the source text of a program into instructions, along with the position every
+///instruction was found at. brackets are not checked here, that is left to the parser. a "#!"
+///line at the start is skipped, see skip_shebang
+pub fn tokenize(source: &str, lang: &Lang) -> Result<Vec<(Instruction, Position)>, ParseError> {
+    let source = skip_shebang(source);
+    match lang {
+        Lang::Brainfuck
+        | Lang::Pbrain
+        | Lang::Ebf1
+        | Lang::Brainfork
+        | Lang::Boolfuck
+        | Lang::BfFile
+        | Lang::BfEval
+        | Lang::BfRand
+        | Lang::BfHalt
+        | Lang::BfChannels => Ok(brainfuck(source, lang)),
+        Lang::Ook => ook::tokenize(source),
+        Lang::Spoon => spoon::tokenize(source),
+        Lang::Mapped(mapping) => Ok(mapping::tokenize(source, mapping)),
+    }
+}
+
+///brainfuck and the languages that just add a few characters to it, one character per
+///instruction. any character that isn't a command in the language is a comment and gets skipped
+///the same as tokenize, but the characters in "custom" become Instruction::Custom, with their
+///index in "custom". they take the place of whatever the language would have made of the same
+///character
+pub fn tokenize_with_custom(
+    source: &str,
+    lang: &Lang,
+    custom: &[char],
+) -> Result<Vec<(Instruction, Position)>, ParseError> {
+    let mut tokens = tokenize(source, lang)?;
+    if custom.is_empty() {
+        return Ok(tokens);
+    }
+    let custom_tokens: Vec<(Instruction, Position)> = code(skip_shebang(source), lang)
+        .filter_map(|(c, position)| {
+            let index = custom.iter().position(|registered| *registered == c)?;
+            Some((Instruction::Custom(index), position))
+        })
+        .collect();
+    tokens.retain(|(_, position)| {
+        custom_tokens
+            .binary_search_by_key(&(position.line, position.column), |(_, custom)| {
+                (custom.line, custom.column)
+            })
+            .is_err()
+    });
+    tokens.extend(custom_tokens);
+    tokens.sort_by_key(|(_, position)| (position.line, position.column));
+    Ok(tokens)
+}
+///every character of the source that's neither whitespace nor one of the tokens, for a strict
+///parse. only means anything for languages whose instructions are a character each
+pub(crate) fn stray_characters(
+    source: &str,
+    tokens: &[(Instruction, Position)],
+) -> Vec<ParseError> {
+    chars_with_positions(skip_shebang(source))
+        .filter(|(c, position)| {
+            !c.is_whitespace()
+                && tokens
+                    .binary_search_by_key(&(position.line, position.column), |(_, token)| {
+                        (token.line, token.column)
+                    })
+                    .is_err()
+        })
+        .map(|(c, position)| ParseError::StrayCharacter(c, position))
+        .collect()
+}
+///the marker of a comment that says what a program should print, see fixtures::inline
+pub const OUTPUT_MARKER: &str = ";=>";
+///the marker of a comment that says what input a program gets, see fixtures::inline
+pub const INPUT_MARKER: &str = ";<<";
+
+///the characters of the source that can be commands: everything but what comes after an
+///OUTPUT_MARKER or INPUT_MARKER on its line, which is all comment even when it has commands in
+///it, so the text can be anything. in boolfuck and brainfuck++ ";" is a command, so they don't
+///have these comments
+fn code<'a>(source: &'a str, lang: &Lang) -> impl Iterator<Item = (char, Position)> + 'a {
+    //for every line, the column its marker is at, if it has one
+    let markers: Vec<Option<usize>> = source
+        .lines()
+        .map(|line| marker_offset(line, lang).map(|offset| line[..offset].chars().count() + 1))
+        .collect();
+    chars_with_positions(source).filter(move |(_, position)| match markers.get(position.line - 1) {
+        Some(Some(column)) => position.column < *column,
+        _ => true,
+    })
+}
+
+///where in the line the comment that an OUTPUT_MARKER or INPUT_MARKER starts begins, in bytes
+pub(crate) fn marker_offset(line: &str, lang: &Lang) -> Option<usize> {
+    match lang {
+        Lang::Boolfuck | Lang::BfFile => None,
+        _ => [OUTPUT_MARKER, INPUT_MARKER]
+            .iter()
+            .filter_map(|marker| line.find(marker))
+            .min(),
+    }
+}
+
+fn brainfuck(source: &str, lang: &Lang) -> Vec<(Instruction, Position)> {
+    code(source, lang)
+        .filter_map(|(c, position)| {
+            let instruction = match c {
+                '+' if *lang == Lang::Boolfuck => Instruction::FlipBit,
+                ',' if *lang == Lang::Boolfuck => Instruction::ReadBit,
+                ';' if *lang == Lang::Boolfuck => Instruction::WriteBit,
+                '-' | '.' if *lang == Lang::Boolfuck => return None,
+                '>' => Instruction::MoveRight,
+                '<' => Instruction::MoveLeft,
+                '+' => Instruction::Increment,
+                '-' => Instruction::Decrement,
+                '.' => Instruction::Output,
+                ',' => Instruction::Replace,
+                '[' => Instruction::JumpToClose,
+                ']' => Instruction::JumpToOpen,
+                '(' if *lang == Lang::Pbrain => Instruction::ProcedureStart,
+                ')' if *lang == Lang::Pbrain => Instruction::ProcedureEnd,
+                ':' if *lang == Lang::Pbrain => Instruction::Call,
+                '@' if *lang == Lang::Ebf1 || *lang == Lang::BfHalt => Instruction::Halt,
+                '$' if *lang == Lang::Ebf1 => Instruction::Store,
+                '!' if *lang == Lang::Ebf1 => Instruction::Restore,
+                '}' if *lang == Lang::Ebf1 => Instruction::ShiftRight,
+                '{' if *lang == Lang::Ebf1 => Instruction::ShiftLeft,
+                '~' if *lang == Lang::Ebf1 => Instruction::Not,
+                '^' if *lang == Lang::Ebf1 => Instruction::Xor,
+                '&' if *lang == Lang::Ebf1 => Instruction::And,
+                '|' if *lang == Lang::Ebf1 => Instruction::Or,
+                'Y' if *lang == Lang::Brainfork => Instruction::Fork,
+                '#' if *lang == Lang::BfFile => Instruction::OpenFile,
+                ';' if *lang == Lang::BfFile => Instruction::ReadFile,
+                ':' if *lang == Lang::BfFile => Instruction::WriteFile,
+                '%' if *lang == Lang::BfEval => Instruction::Eval,
+                '?' if *lang == Lang::BfRand => Instruction::Random,
+                '\'' if *lang == Lang::BfChannels => Instruction::SwitchChannel,
+                _ => return None,
+            };
+            Some((instruction, position))
+        })
+        .collect()
+}
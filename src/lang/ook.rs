@@ -0,0 +1,59 @@
+//! Ook!, brainfuck for orang-utans. every instruction is a pair of the words "Ook.", "Ook?" and
+//! "Ook!", so the tokenizer first picks out the words and then reads them two at a time. anything
+//! that isn't one of the three words is a comment, just like in brainfuck
+use super::chars_with_positions;
+use crate::{Instruction, ParseError, Position};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+///finds every Ook word in the source, as the punctuation that follows it and the position of its
+///"O"
+fn words(source: &str) -> Vec<(char, Position)> {
+    let chars: Vec<(char, Position)> = chars_with_positions(source).collect();
+    let mut words = vec![];
+    let mut index = 0;
+    while index < chars.len() {
+        let is_word = match chars.get(index..index + 4) {
+            Some([('O', _), ('o', _), ('k', _), (punctuation, _)]) => ".?!".contains(*punctuation),
+            _ => false,
+        };
+        if is_word {
+            words.push((chars[index + 3].0, chars[index].1));
+            index += 4;
+        } else {
+            index += 1;
+        }
+    }
+    words
+}
+
+///turns an Ook! program into instructions
+pub fn tokenize(source: &str) -> Result<Vec<(Instruction, Position)>, ParseError> {
+    let words = words(source);
+    let mut instructions = vec![];
+    for pair in words.chunks(2) {
+        let (first, position) = pair[0];
+        let second = match pair.get(1) {
+            Some((second, _)) => *second,
+            None => return Err(ParseError::DanglingWord(position)),
+        };
+        let instruction = match (first, second) {
+            ('.', '?') => Instruction::MoveRight,
+            ('?', '.') => Instruction::MoveLeft,
+            ('.', '.') => Instruction::Increment,
+            ('!', '!') => Instruction::Decrement,
+            ('!', '.') => Instruction::Output,
+            ('.', '!') => Instruction::Replace,
+            ('!', '?') => Instruction::JumpToClose,
+            ('?', '!') => Instruction::JumpToOpen,
+            _ => {
+                return Err(ParseError::InvalidPair(
+                    format!("Ook{} Ook{}", first, second),
+                    position,
+                ))
+            }
+        };
+        instructions.push((instruction, position));
+    }
+    Ok(instructions)
+}
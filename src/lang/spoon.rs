@@ -0,0 +1,49 @@
+//! Spoon, brainfuck written as a string of bits. every command gets a code, with the most common
+//! commands getting the shortest codes. no code is the start of another one, so the bits can be
+//! read one at a time until they spell out a complete code. anything that isn't a 0 or a 1 is a
+//! comment
+use super::chars_with_positions;
+use crate::{Instruction, ParseError, Position};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+///the code for every command
+const CODES: [(&str, Instruction); 10] = [
+    ("1", Instruction::Increment),
+    ("000", Instruction::Decrement),
+    ("010", Instruction::MoveRight),
+    ("011", Instruction::MoveLeft),
+    ("0011", Instruction::JumpToOpen),
+    ("00100", Instruction::JumpToClose),
+    ("001010", Instruction::Output),
+    ("0010110", Instruction::Replace),
+    ("00101110", Instruction::DebugDump),
+    ("00101111", Instruction::Halt),
+];
+
+///turns a Spoon program into instructions
+pub fn tokenize(source: &str) -> Result<Vec<(Instruction, Position)>, ParseError> {
+    let mut instructions = vec![];
+    //the bits read since the last complete code, and where the first of them was
+    let mut code = String::new();
+    let mut start = None;
+    for (c, position) in chars_with_positions(source) {
+        if c != '0' && c != '1' {
+            continue;
+        }
+        code.push(c);
+        let code_start = *start.get_or_insert(position);
+        if let Some((_, instruction)) = CODES.iter().find(|(bits, _)| *bits == code) {
+            instructions.push((*instruction, code_start));
+            code.clear();
+            start = None;
+        }
+        //every string of bits is either a code or the start of one, so there is no such thing as
+        //an invalid code, only one that is cut off at the end
+    }
+    match start {
+        Some(start) => Err(ParseError::TruncatedCode(start)),
+        None => Ok(instructions),
+    }
+}
@@ -0,0 +1,1821 @@
+//! an interpreter for the brainfuck programming language, made to be readable and clear. the
+//! turing machine in here does all the work, the command line program in main.rs only reads
+//! arguments and files and hands them to it.
+//!
+//! without the "std" feature, which is on by default, only the parser and the machine are left,
+//! built on core and alloc so they run on small computers without an operating system. input and
+//! output then go through the traits in io.rs, and everything that needs files, threads or the
+//! clock is left out
+//!
+//! nothing in here panics on what it's given: any program text, any input and any settings
+//! either work or give back one of the errors. that's why unwrap isn't allowed anywhere in the
+//! library, what can't fail says why with expect instead, and the fuzz targets in fuzz/ go
+//! looking for anything that does panic
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(clippy::unwrap_used)]
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+
+use control::{RunOutcome, StepResult};
+use io::{InputByte, OutputByte};
+pub use program::{parse, LoopInfo, ParseOptions, Program};
+use shared::{lock, shared, Shared};
+
+#[cfg(feature = "std")]
+pub mod analyze;
+pub mod annotations;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "bigint")]
+pub mod big;
+mod blocks;
+pub mod bytecode;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod capabilities;
+pub mod channels;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod chrome;
+pub mod condition;
+pub mod config;
+pub mod control;
+#[cfg(feature = "std")]
+pub mod crosscheck;
+pub mod custom;
+pub mod cycles;
+#[cfg(feature = "dap")]
+pub mod dap;
+#[cfg(feature = "std")]
+pub mod debugger;
+#[cfg(feature = "std")]
+pub mod diagnostics;
+pub mod events;
+pub mod examples;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod fixtures;
+#[cfg(feature = "std")]
+pub mod fork;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(any(feature = "dap", feature = "lsp"))]
+pub mod framing;
+pub mod fuel;
+mod fused;
+#[cfg(feature = "std")]
+pub mod generate;
+#[cfg(feature = "decompress")]
+pub mod gzip;
+#[cfg(feature = "std")]
+pub mod heatmap;
+pub mod history;
+pub mod io;
+pub mod joust;
+pub mod lang;
+#[cfg(feature = "std")]
+pub mod lineedit;
+#[cfg(feature = "std")]
+pub mod lint;
+pub mod listing;
+#[cfg(feature = "std")]
+pub mod lockstep;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "std")]
+pub mod mi;
+#[cfg(feature = "std")]
+pub mod minify;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "std")]
+pub mod obfuscate;
+pub mod observe;
+pub mod optimize;
+pub mod program;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod provenance;
+#[cfg(feature = "python")]
+mod python;
+pub mod random;
+#[cfg(feature = "std")]
+pub mod reduce;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "std")]
+pub mod sampler;
+#[cfg(all(feature = "sandbox", target_os = "linux"))]
+pub mod sandbox;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shared;
+pub mod snapshot;
+pub mod snippet;
+#[cfg(feature = "std")]
+pub mod spec;
+pub mod style;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod tier;
+#[cfg(feature = "std")]
+pub mod timeline;
+#[cfg(feature = "std")]
+pub mod tracediff;
+#[cfg(feature = "std")]
+pub mod transcript;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(feature = "std")]
+pub mod transpile;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod warnings;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watch;
+pub mod writers;
+
+///Every possible instruction in the brainfuck language
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Instruction {
+    MoveRight,
+    MoveLeft,
+    Increment,
+    Decrement,
+    Output,
+    Replace,
+    JumpToClose,
+    JumpToOpen,
+    ///pbrain's "(", defines a procedure numbered by the current cell
+    ProcedureStart,
+    ///pbrain's ")", returns from the procedure being run
+    ProcedureEnd,
+    ///pbrain's ":", calls the procedure numbered by the current cell
+    Call,
+    ///ends the program right away, extended type I's and bf-halt's "@" and spoon's exit
+    Halt,
+    ///spoon's debug command, prints the state of the machine without changing it
+    DebugDump,
+    ///extended type I's "$", copies the current cell into storage
+    Store,
+    ///extended type I's "!", copies storage into the current cell
+    Restore,
+    ///extended type I's "}", shifts the bits of the current cell one to the right
+    ShiftRight,
+    ///extended type I's "{", shifts the bits of the current cell one to the left
+    ShiftLeft,
+    ///extended type I's "~", flips every bit of the current cell
+    Not,
+    ///extended type I's "^", xors the current cell with storage
+    Xor,
+    ///extended type I's "&", ands the current cell with storage
+    And,
+    ///extended type I's "|", ors the current cell with storage
+    Or,
+    ///brainfork's "Y", splits the machine into two that carry on side by side
+    Fork,
+    ///boolfuck's "+", flips the bit in the current cell
+    FlipBit,
+    ///boolfuck's ",", reads a single bit of input into the current cell
+    ReadBit,
+    ///boolfuck's ";", writes the bit in the current cell to the output
+    WriteBit,
+    ///brainfuck++'s "#", opens the file named by the text starting at the current cell
+    OpenFile,
+    ///brainfuck++'s ";", reads a byte from the open file into the current cell
+    ReadFile,
+    ///brainfuck++'s ":", writes the current cell to the open file
+    WriteFile,
+    ///"%", runs the text starting at the current cell as a brainfuck program of its own
+    Eval,
+    ///"?", puts a random byte in the current cell
+    Random,
+    ///bf-channels' "'", makes "," read from the input channel numbered by the current cell
+    SwitchChannel,
+    ///one of the custom instructions the machine was made with, by its index. see custom.rs
+    Custom(usize),
+}
+///a place in the source text of a program, used to point people at the right character when
+///something needs reporting. lines and columns both start counting at 1, like in most editors
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+///everything that can be wrong with a program before it even starts running
+#[derive(PartialEq, Debug)]
+pub enum ParseError {
+    ///a "[" that never got closed by a matching "]"
+    UnmatchedOpen(Position),
+    ///a "]" that has no "[" before it to jump back to
+    UnmatchedClose(Position),
+    ///two words that don't make up an instruction together, like "Ook? Ook?"
+    InvalidPair(String, Position),
+    ///the first half of an instruction made of two words, with nothing after it
+    DanglingWord(Position),
+    ///a code made of bits that the program ends in the middle of
+    TruncatedCode(Position),
+    ///a "(" that never got closed by a matching ")"
+    UnmatchedProcedureStart(Position),
+    ///a ")" that has no "(" before it
+    UnmatchedProcedureEnd(Position),
+    ///an instruction past the 4294967296th, which is as many as a program can have
+    TooLong(Position),
+    ///a "[" or "(" nested deeper than the limit the program was parsed with, with the limit, see
+    ///program::MAX_NESTING
+    TooDeep(Position, usize),
+    ///a character that isn't an instruction or whitespace, in a program parsed strictly
+    StrayCharacter(char, Position),
+    ///a cell name in the comments that couldn't be read, see annotations.rs
+    Annotation(annotations::AnnotationError),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnmatchedOpen(position) => {
+                write!(f, "unmatched '[' at {}", position)
+            }
+            ParseError::UnmatchedClose(position) => {
+                write!(f, "unmatched ']' at {}", position)
+            }
+            ParseError::InvalidPair(pair, position) => {
+                write!(f, "'{}' at {} is not an instruction", pair, position)
+            }
+            ParseError::DanglingWord(position) => {
+                write!(f, "the word at {} is missing its second half", position)
+            }
+            ParseError::TruncatedCode(position) => {
+                write!(
+                    f,
+                    "the code at {} is cut off by the end of the program",
+                    position
+                )
+            }
+            ParseError::UnmatchedProcedureStart(position) => {
+                write!(f, "unmatched '(' at {}", position)
+            }
+            ParseError::UnmatchedProcedureEnd(position) => {
+                write!(f, "unmatched ')' at {}", position)
+            }
+            ParseError::TooLong(position) => {
+                write!(
+                    f,
+                    "the program is too long, the instruction at {} is past the most it can have",
+                    position
+                )
+            }
+            ParseError::TooDeep(position, max) => write!(
+                f,
+                "the bracket at {} is nested more than {} deep, raise max-nesting to allow it",
+                position, max
+            ),
+            ParseError::StrayCharacter(c, position) => {
+                write!(f, "{:?} at {} is not an instruction", c, position)
+            }
+            ParseError::Annotation(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+///a procedure call or an eval that hasn't returned yet
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) struct Call {
+    //where the ":" or "%" is
+    pub(crate) site: Position,
+    //where a procedure call carries on once it returns. nothing for an eval, which carries on
+    //by itself once the program it runs is done
+    pub(crate) return_to: Option<usize>,
+}
+
+///a loop or a procedure call the machine is inside, see TuringMachine::backtrace
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Frame {
+    ///a loop, as the positions of its "[" and "]"
+    Loop(Position, Position),
+    ///a procedure call that hasn't returned yet, at its ":"
+    Call(Position),
+}
+
+///everything that can go wrong while a program is running
+#[derive(PartialEq, Debug)]
+pub enum RuntimeError {
+    ///a ":" for a procedure number that no "(" has defined yet
+    UndefinedProcedure(u8, Position),
+    ///a ":" or a "%" that would go deeper than the call depth limit allows, or than the eval
+    ///depth limit allows for evals, with the limit that was hit. the call sites are every ":"
+    ///and "%" that hasn't returned yet, from the outermost in, ending with the one that failed
+    CallDepthExceeded(usize, Vec<Position>),
+    ///a file instruction that failed, with a description of what went wrong
+    FileError(String, Position),
+    ///a file instruction used before any file was opened
+    NoOpenFile(Position),
+    ///text on the tape that doesn't end in a 0 before the tape does
+    UnterminatedText(Position),
+    ///a "%" whose text on the tape isn't a valid program
+    EvalParseError(ParseError, Position),
+    ///a move that would take the pointer off either end of the tape
+    PointerOutOfBounds(Position),
+    ///the machine was in exactly the same state at both steps, at the instruction at the
+    ///position, without reading or writing anything in between, so it would go on that way
+    ///forever. see cycles.rs
+    InfiniteLoop(u64, u64, Position),
+    ///the program ran for as many steps as it was allowed to
+    StepLimitReached(u64),
+    ///the machine's fuel ran out before the instruction at the position, with the stage the
+    ///fuel was given to if it was given one. see fuel.rs
+    FuelExhausted(Option<usize>, Position),
+    ///the loop from the first position to the second went around more times in a row than the
+    ///loop iteration limit allows
+    LoopIterationLimit(u64, Position, Position),
+    ///the "." at the position would write more than the output limit allows, which is how many
+    ///bytes were written before it
+    OutputLimitExceeded(u64, Position),
+    ///reading the input or writing the output failed
+    IoError(String),
+    ///whatever was reading the output has gone away, see io::OUTPUT_CLOSED
+    OutputClosed,
+    ///a cell was asked for by index that isn't on the tape
+    CellOutOfRange(usize),
+    ///a "-" on a cell that was already 0, when cells have no upper limit and don't wrap around
+    CellUnderflow(Position),
+    ///the "," at the position reads from the channel, which wasn't given any input
+    NoSuchChannel(u8, Position),
+    ///the machine is driven by events and needs input that hasn't been provided yet. the
+    ///instruction that asked for it hasn't run, so it can be run again once there is input
+    InputNeeded,
+}
+
+impl RuntimeError {
+    ///whether the program was stopped by a limit it was given, like the step limit, rather than
+    ///for doing something wrong. a program stopped by a limit might have been fine with a higher
+    ///one, while one caught in an infinite loop never would have been
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            RuntimeError::StepLimitReached(_)
+                | RuntimeError::FuelExhausted(..)
+                | RuntimeError::LoopIterationLimit(..)
+                | RuntimeError::OutputLimitExceeded(..)
+                | RuntimeError::CallDepthExceeded(..)
+        )
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::UndefinedProcedure(number, position) => {
+                write!(f, "call to undefined procedure {} at {}", number, position)
+            }
+            RuntimeError::CallDepthExceeded(limit, sites) => {
+                let (outer, last) = sites.split_at(sites.len().saturating_sub(1));
+                let last = last.first().map_or(String::new(), Position::to_string);
+                write!(
+                    f,
+                    "the call at {} goes past the limit of {} nested calls",
+                    last, limit
+                )?;
+                //deep recursion makes for thousands of them, the ends say the most
+                let sites: Vec<String> = match outer.len() {
+                    0..=8 => outer.iter().map(Position::to_string).collect(),
+                    length => outer[..4]
+                        .iter()
+                        .map(Position::to_string)
+                        .chain([format!("({} more)", length - 8)])
+                        .chain(outer[length - 4..].iter().map(Position::to_string))
+                        .collect(),
+                };
+                match sites.is_empty() {
+                    true => Ok(()),
+                    false => write!(f, ", made from the calls at {}", sites.join(", ")),
+                }
+            }
+            RuntimeError::FileError(message, position) => {
+                write!(f, "{} at {}", message, position)
+            }
+            RuntimeError::NoOpenFile(position) => {
+                write!(f, "file used at {} before one was opened", position)
+            }
+            RuntimeError::UnterminatedText(position) => {
+                write!(
+                    f,
+                    "the text read at {} runs off the end of the tape",
+                    position
+                )
+            }
+            RuntimeError::EvalParseError(error, position) => {
+                write!(f, "the program run at {} is broken: {}", position, error)
+            }
+            RuntimeError::PointerOutOfBounds(position) => {
+                write!(f, "the move at {} goes off the end of the tape", position)
+            }
+            RuntimeError::StepLimitReached(limit) => {
+                write!(f, "stopped after running {} steps", limit)
+            }
+            RuntimeError::FuelExhausted(_, position) => {
+                write!(f, "ran out of fuel at {}", position)
+            }
+            RuntimeError::InfiniteLoop(first, second, position) => write!(
+                f,
+                "the program is in an infinite loop at {} (state repeated at steps {} and {})",
+                position, first, second
+            ),
+            RuntimeError::LoopIterationLimit(limit, open, close) => {
+                write!(
+                    f,
+                    "the loop from {} to {} went around more than {} times in a row",
+                    open, close, limit
+                )
+            }
+            RuntimeError::OutputLimitExceeded(limit, position) => {
+                write!(
+                    f,
+                    "the output at {} goes past the limit of {} bytes",
+                    position, limit
+                )
+            }
+            RuntimeError::IoError(message) => write!(f, "{}", message),
+            RuntimeError::OutputClosed => write!(f, "{}", io::OUTPUT_CLOSED),
+            RuntimeError::CellOutOfRange(index) => {
+                write!(
+                    f,
+                    "there is no cell {}, the tape has {}",
+                    index, TAPE_LENGTH
+                )
+            }
+            RuntimeError::CellUnderflow(position) => {
+                write!(f, "the \"-\" at {} takes a cell below 0", position)
+            }
+            RuntimeError::NoSuchChannel(channel, position) => write!(
+                f,
+                "the \",\" at {} reads from channel {}, which wasn't given any input",
+                position, channel
+            ),
+            RuntimeError::InputNeeded => write!(f, "input is needed but none was provided"),
+        }
+    }
+}
+
+///what writing the output failed with as the error a run fails with
+fn write_error(e: String) -> RuntimeError {
+    match e == io::OUTPUT_CLOSED {
+        true => RuntimeError::OutputClosed,
+        false => RuntimeError::IoError(format!("could not write output: {}", e)),
+    }
+}
+
+///what "," should do to the current cell when there is no more input to read
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum EofBehavior {
+    ///set the cell to 0, which makes programs like ",[.,]" stop at the end of their input
+    Zero,
+    ///set the cell to 255, some older programs expect this
+    Max,
+    ///leave the cell exactly as it was
+    Unchanged,
+}
+
+impl EofBehavior {
+    pub const ALL: [EofBehavior; 3] = [EofBehavior::Zero, EofBehavior::Max, EofBehavior::Unchanged];
+    ///the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            EofBehavior::Zero => "zero",
+            EofBehavior::Max => "max",
+            EofBehavior::Unchanged => "unchanged",
+        }
+    }
+    ///turns the name used on the command line into the matching behavior. "0" and "255" are
+    ///other names for zero and max
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = match name {
+            "0" => "zero",
+            "255" => "max",
+            name => name,
+        };
+        EofBehavior::ALL.into_iter().find(|eof| eof.name() == name)
+    }
+}
+
+///how a cell's byte is read as a number when it's shown. "+" and "-" wrap around the same way
+///either way, so this never changes what a program does, only how its cells are written out
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Signedness {
+    ///0 to 255, the usual
+    Unsigned,
+    ///-128 to 127, so "-" on a 0 cell shows -1, for material that treats cells that way
+    Signed,
+}
+
+impl Signedness {
+    pub const ALL: [Signedness; 2] = [Signedness::Unsigned, Signedness::Signed];
+    ///the name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Signedness::Unsigned => "unsigned",
+            Signedness::Signed => "signed",
+        }
+    }
+    ///turns the name used on the command line into the matching signedness
+    pub fn from_name(name: &str) -> Option<Self> {
+        Signedness::ALL
+            .into_iter()
+            .find(|signedness| signedness.name() == name)
+    }
+    ///the number a cell holding this byte stands for
+    pub fn value(self, cell: u8) -> i16 {
+        match self {
+            Signedness::Unsigned => cell as i16,
+            Signedness::Signed => cell as i8 as i16,
+        }
+    }
+    ///the byte a cell holds to stand for the number, or nothing when it's out of range, like 200
+    ///for signed cells or -1 for unsigned ones
+    pub fn byte(self, value: i64) -> Option<u8> {
+        match self {
+            Signedness::Unsigned => u8::try_from(value).ok(),
+            Signedness::Signed => i8::try_from(value).ok().map(|value| value as u8),
+        }
+    }
+}
+
+///how many cells the tape has
+pub const TAPE_LENGTH: usize = 30000;
+
+#[derive(Clone)]
+///object oriented kind of struct that represents the turing machine that runs the bf programs
+pub struct TuringMachine {
+    //the memory of the little turing machine, or "tape". TAPE_LENGTH cells in size. each cell being one
+    //byte in size
+    tape: [u8; TAPE_LENGTH],
+    //the pointer that indicates where the turing machines head is on the tape
+    pointer: usize,
+    //the program itself, represented as a long list of instructions, along with where they came
+    //from in the source. it can be shared with other machines running the same program
+    program: Arc<Program>,
+    //the program counter, that indicates which instruction in the program we are currently
+    //executing
+    program_counter: usize,
+    //what to put in the current cell when "," finds that the input has run out
+    pub eof_behavior: EofBehavior,
+    //how cells are shown in dumps and descriptions of where the machine is
+    pub cell_signedness: Signedness,
+    //where input is read from and output written to. stdin and stdout unless something else is
+    //put in here, and shared with any machines this one forks into, see shared.rs. without std
+    //there's no stdin or stdout, so the input starts out empty and the output is kept in a vector
+    pub input: Shared<dyn InputByte + Send>,
+    pub output: Shared<dyn OutputByte + Send>,
+    //how many instructions have been run so far, and how many are allowed to be run before the
+    //machine gives up. every instruction counts, including the ones run by "%"
+    pub steps: u64,
+    pub step_limit: Option<u64>,
+    //the fuel the machine burns, which can be shared with other machines, see fuel.rs
+    pub fuel: Option<fuel::Fuel>,
+    //how many bytes the program may write in all before it's stopped, so a program stuck
+    //printing can't fill up a disk
+    pub output_limit: Option<u64>,
+    //how many times in a row any one loop may go around before the machine gives up, which
+    //points at the loop a program is stuck in, and how many times each loop has gone around so
+    //far, by the index of its "]"
+    pub loop_iteration_limit: Option<u64>,
+    loop_iterations: Vec<u64>,
+    //where the body of every procedure that has been defined so far starts, by procedure number
+    procedures: [Option<usize>; 256],
+    //the procedure calls and evals that haven't returned yet, innermost last
+    calls: Vec<Call>,
+    //how many procedure calls and evals deep a program is allowed to go, together
+    pub call_depth_limit: usize,
+    //what the cells hold above their lowest byte, when they have no upper limit. nothing when
+    //they're bytes that wrap around, as usual
+    #[cfg(feature = "bigint")]
+    big_cells: Option<big::BigCells>,
+    //the single byte of storage that extended type I programs can copy cells to and from
+    storage: u8,
+    //the byte of input that boolfuck is reading bits from, and how many of its bits are left
+    input_bits: (u8, u32),
+    //the bits that boolfuck has written so far that don't make up a whole byte yet, and how many
+    //of them there are
+    output_bits: (u8, u32),
+    //how many "%" are running inside each other right now, and how many are allowed to. evals
+    //nest on bfint's own stack, so they get a limit of their own on top of call_depth_limit
+    eval_depth: usize,
+    pub eval_depth_limit: usize,
+    //where bf-rand's "?" gets its random bytes from
+    pub rng: random::Rng,
+    //the file that brainfuck++ programs read from and write to. it is shared with any machines
+    //this one forks into, so they all see the same file
+    #[cfg(feature = "std")]
+    file: Option<Arc<File>>,
+    //machines that this one has split off with brainfork's "Y" and that haven't been handed to
+    //the scheduler yet
+    forked: Vec<TuringMachine>,
+    //whether input and output go through events instead of the input and output handles, see
+    //events.rs
+    event_driven: bool,
+    //the input handed over with provide_input that hasn't been read yet. the inner None means
+    //the input has run out
+    provided_input: Option<Option<u8>>,
+    //output written while event driven that hasn't been handed out as an event yet
+    pending_output: VecDeque<u8>,
+    //the breakpoints that are set, as the index of the instruction they stop before and the id
+    //they were given
+    breakpoints: BTreeMap<usize, usize>,
+    next_breakpoint_id: usize,
+    //the last instructions that ran, when the machine was asked to keep them
+    history: Option<history::History>,
+    //who wrote every cell last, when the machine was asked to keep track
+    writers: Option<writers::Writers>,
+    //the conditions on the breakpoints that only stop when something holds, by breakpoint id
+    breakpoint_conditions: BTreeMap<usize, condition::Condition>,
+    //the instruction the machine last stopped at for a breakpoint, so carrying on doesn't stop
+    //at the same breakpoint straight away
+    paused_at: Option<usize>,
+    //what the handles from control_handle use to pause and cancel the machine, if any were made
+    #[cfg(feature = "std")]
+    control: Option<Arc<control::Control>>,
+    //how many instructions run between checks for a pause or cancel. checking takes a little
+    //time, so programs that need to be fast can check less often
+    pub control_interval: u64,
+    //the callback set with set_progress_callback, see progress.rs
+    #[cfg(feature = "std")]
+    progress: Option<progress::Reporter>,
+    //how many bytes the program has written, for the progress callback
+    output_bytes: u64,
+    //the byte the program wrote last, for iter_steps to pick up, see observe.rs
+    last_output: Option<u8>,
+    //the custom instructions the machine was made with, see custom.rs
+    custom: custom::CustomInstructions,
+    //where the "Halt" instruction that ended the program was, if one did
+    halted_at: Option<Position>,
+    //what's been found out about the loops while running, when hot loops are run faster, see
+    //tier.rs
+    tiering: Option<tier::Tiering>,
+    //what's called around every instruction, see observe.rs
+    observer: Option<observe::SharedObserver>,
+    //the states the machine has been in lately, when it's looking for cycles, see cycles.rs
+    cycles: Option<cycles::CycleDetector>,
+    //what the detectors of --runtime-warnings have seen lately, see warnings.rs
+    warnings: Option<warnings::Watcher>,
+    //the input channels of bf-channels, once the program switches channels or one is added,
+    //see channels.rs
+    channels: Option<Box<channels::Channels>>,
+}
+
+impl TuringMachine {
+    ///create a new turing machine, sets every cell in memory to be 0 initially, sets the pointer
+    ///and program pointer to 0, and turns bf programs into lists of instructions.
+    pub fn new(program: &str, lang: &lang::Lang) -> Result<Self, ParseError> {
+        let options = ParseOptions {
+            lang: lang.clone(),
+            ..ParseOptions::default()
+        };
+        TuringMachine::with_options(program, &options)
+    }
+    ///new, but with the program parsed the way the options say
+    pub fn with_options(program: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let program = parse(program, options).map_err(|mut errors| errors.swap_remove(0))?;
+        Ok(TuringMachine::with_program(Arc::new(program)))
+    }
+    ///create a new turing machine for a program given as bytes, which don't have to be utf-8,
+    ///see program::decode_source
+    pub fn from_bytes(program: &[u8], lang: &lang::Lang) -> Result<Self, ParseError> {
+        TuringMachine::new(&program::decode_source(program), lang)
+    }
+    ///create a new turing machine for the program in a file, see program::read_source for how
+    ///the file is read
+    #[cfg(feature = "std")]
+    pub fn from_file(
+        path: impl AsRef<std::path::Path>,
+        lang: &lang::Lang,
+    ) -> Result<Self, program::LoadError> {
+        let source = program::read_source(path)?;
+        TuringMachine::new(&source, lang).map_err(program::LoadError::ParseError)
+    }
+    ///a new machine for a program that has been compiled already, see program.rs. making one
+    ///this way doesn't parse anything, so it's cheap enough to do for every run
+    pub fn with_program(program: Arc<Program>) -> Self {
+        //a program that came with its input reads that, see ParseOptions::input_separator
+        #[cfg(feature = "std")]
+        let input: Shared<dyn InputByte + Send> = match &program.input {
+            Some(input) => shared(std::io::Cursor::new(input.clone())),
+            None => shared(std::io::stdin()),
+        };
+        #[cfg(not(feature = "std"))]
+        let input = shared(VecDeque::from(program.input.clone().unwrap_or_default()));
+        TuringMachine {
+            tape: [0; TAPE_LENGTH],
+            pointer: 0,
+            program,
+            program_counter: 0,
+            eof_behavior: EofBehavior::Zero,
+            cell_signedness: Signedness::Unsigned,
+            #[cfg(feature = "std")]
+            input,
+            #[cfg(feature = "std")]
+            output: shared(std::io::stdout()),
+            #[cfg(not(feature = "std"))]
+            input,
+            #[cfg(not(feature = "std"))]
+            output: shared(Vec::new()),
+            steps: 0,
+            step_limit: None,
+            fuel: None,
+            output_limit: None,
+            loop_iteration_limit: None,
+            loop_iterations: vec![],
+            procedures: [None; 256],
+            calls: vec![],
+            call_depth_limit: 1024,
+            storage: 0,
+            input_bits: (0, 0),
+            output_bits: (0, 0),
+            eval_depth: 0,
+            eval_depth_limit: 64,
+            #[cfg(feature = "std")]
+            rng: random::Rng::from_entropy(),
+            //without std there's nowhere to get a seed from, so every run gets the same bytes
+            //until the machine is given a seed of its own
+            #[cfg(not(feature = "std"))]
+            rng: random::Rng::from_seed(0),
+            #[cfg(feature = "std")]
+            file: None,
+            forked: vec![],
+            event_driven: false,
+            provided_input: None,
+            pending_output: VecDeque::new(),
+            breakpoints: BTreeMap::new(),
+            next_breakpoint_id: 0,
+            breakpoint_conditions: BTreeMap::new(),
+            history: None,
+            writers: None,
+            paused_at: None,
+            #[cfg(feature = "std")]
+            control: None,
+            control_interval: 1000,
+            #[cfg(feature = "std")]
+            progress: None,
+            output_bytes: 0,
+            last_output: None,
+            custom: custom::CustomInstructions::new(),
+            halted_at: None,
+            tiering: None,
+            observer: None,
+            cycles: None,
+            warnings: None,
+            channels: None,
+            #[cfg(feature = "bigint")]
+            big_cells: None,
+        }
+    }
+    ///the program the machine is running, which can be handed to with_program to run it again
+    pub fn program(&self) -> &Arc<Program> {
+        &self.program
+    }
+    ///swaps the program for another one, keeping the tape and the pointer as they are, so the
+    ///new program carries on from where the old one left off. procedures and breakpoints belong
+    ///to the old program, so they're forgotten
+    pub fn load(&mut self, program: &str, lang: &lang::Lang) -> Result<(), ParseError> {
+        let program = Program::parse(program, lang, &self.custom.characters())?;
+        self.load_program(Arc::new(program));
+        Ok(())
+    }
+    ///load, for a program that has been parsed already
+    pub fn load_program(&mut self, program: Arc<Program>) {
+        self.program = program;
+        if let Some(tiering) = &mut self.tiering {
+            tiering.forget_loops();
+        }
+        self.program_counter = 0;
+        self.procedures = [None; 256];
+        self.calls.clear();
+        self.breakpoints.clear();
+        self.breakpoint_conditions.clear();
+        self.paused_at = None;
+        self.loop_iterations.clear();
+        self.halted_at = None;
+        self.forget_states();
+    }
+    ///where in the source the next instruction to run is, or nothing once the program has ended
+    pub fn position(&self) -> Option<Position> {
+        self.program.positions.get(self.program_counter).copied()
+    }
+    ///where in the source the next instruction is, or the last one once the program has ended,
+    ///for errors that can happen after that, like boolfuck writing the bits it has left over
+    fn position_or_last(&self) -> Position {
+        self.position()
+            .or_else(|| self.program.positions.last().copied())
+            .unwrap_or(Position { line: 1, column: 1 })
+    }
+    ///where in the source the instruction just before the next one is. that's the one that ran
+    ///last, unless it was a jump
+    pub fn last_position(&self) -> Option<Position> {
+        self.program_counter
+            .checked_sub(1)
+            .and_then(|index| self.program.positions.get(index).copied())
+    }
+    ///where in the source every instruction the test picks out is, in the order they come in.
+    ///for looking over a program before running it
+    pub fn find(&self, wanted: impl Fn(Instruction) -> bool) -> Vec<Position> {
+        self.program
+            .instructions()
+            .zip(&self.program.positions)
+            .filter(|(instruction, _)| wanted(*instruction))
+            .map(|(_, position)| *position)
+            .collect()
+    }
+    ///runs the pipeline's passes over the program, see optimize::Pipeline::run, and gives back
+    ///what every pass did. this only does anything before the program has started, and
+    ///breakpoints set before it may end up on other instructions
+    pub fn optimize(
+        &mut self,
+        pipeline: &optimize::Pipeline,
+        after: impl FnMut(&str, &optimize::Ir),
+    ) -> Vec<(&'static str, optimize::PassStats)> {
+        if self.program_counter != 0 || self.steps != 0 {
+            return vec![];
+        }
+        let (program, stats) = self
+            .program
+            .optimized(pipeline, self.current_is_zero(), after);
+        self.program = Arc::new(program);
+        if let Some(tiering) = &mut self.tiering {
+            tiering.forget_loops();
+        }
+        stats
+    }
+    ///the loops the next instruction is inside, from the innermost out, as the positions of their
+    ///"[" and "]". a "]" counts as inside its loop, a "[" doesn't yet
+    pub fn enclosing_loops(&self) -> Vec<(Position, Position)> {
+        self.loops_around(self.program_counter)
+    }
+    ///the procedure calls that haven't returned yet, from the innermost out, by where their ":"
+    ///is. evals never show up here, they're done by the time anything can look
+    pub fn call_stack(&self) -> Vec<Position> {
+        self.calls.iter().rev().map(|call| call.site).collect()
+    }
+    ///the loops and procedure calls the next instruction is inside, from the innermost out. the
+    ///loops of a procedure come before the call it's in, followed by the loops that call is in
+    pub fn backtrace(&self) -> Vec<Frame> {
+        let loops = |index| {
+            self.loops_around(index)
+                .into_iter()
+                .map(|(open, close)| Frame::Loop(open, close))
+        };
+        let mut frames: Vec<Frame> = loops(self.program_counter).collect();
+        for call in self.calls.iter().rev() {
+            frames.push(Frame::Call(call.site));
+            if let Some(return_to) = call.return_to {
+                frames.extend(loops(return_to - 1));
+            }
+        }
+        frames
+    }
+    ///the loops the instruction at the index is inside, like enclosing_loops. inside a procedure
+    ///that's only the loops in its body, the ones around where it's defined don't run it
+    fn loops_around(&self, index: usize) -> Vec<(Position, Position)> {
+        let procedure = (0..index).rev().find(|&start| {
+            self.program.instruction(start) == Some(Instruction::ProcedureStart)
+                && self.program.ops[start].target() >= index
+        });
+        //the loops come in the order of their "[", which for the ones around an instruction is
+        //from the outermost in
+        let mut loops: Vec<(Position, Position)> = self
+            .program
+            .loops()
+            .filter(|info| info.open < index && index <= info.close)
+            .filter(|info| procedure.is_none_or(|start| info.open > start))
+            .map(|info| {
+                (
+                    self.program.positions[info.open],
+                    self.program.positions[info.close],
+                )
+            })
+            .collect();
+        loops.reverse();
+        loops
+    }
+    ///the loop whose "[" is the next instruction, as the positions of its "[" and "]"
+    pub fn loop_at(&self) -> Option<(Position, Position)> {
+        let open = self.program_counter;
+        match self.program.instruction(open)? {
+            Instruction::JumpToClose => {
+                let close = self.program.matching_bracket(open)?;
+                Some((self.program.positions[open], self.program.positions[close]))
+            }
+            _ => None,
+        }
+    }
+    ///the index of the cell the pointer is at
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+    ///every cell of the tape
+    pub fn tape(&self) -> &[u8] {
+        &self.tape
+    }
+    ///moves the pointer to the cell
+    pub fn set_pointer(&mut self, index: usize) -> Result<(), RuntimeError> {
+        if index >= self.tape.len() {
+            return Err(RuntimeError::CellOutOfRange(index));
+        }
+        self.pointer = index;
+        Ok(())
+    }
+    ///puts the value in the cell
+    pub fn set_cell(&mut self, index: usize, value: u8) -> Result<(), RuntimeError> {
+        let cell = self
+            .tape
+            .get_mut(index)
+            .ok_or(RuntimeError::CellOutOfRange(index))?;
+        *cell = value;
+        #[cfg(feature = "bigint")]
+        if let Some(big_cells) = &mut self.big_cells {
+            big_cells.forget(index);
+        }
+        Ok(())
+    }
+    ///makes the cells hold numbers with no upper limit from now on, instead of bytes that wrap
+    ///around. only the eight instructions of plain brainfuck (and "#" and "@") know about cells
+    ///like that, so a program with any other instruction in it is refused, with the position of
+    ///the first one
+    #[cfg(feature = "bigint")]
+    pub fn use_big_cells(&mut self, underflow: big::Underflow) -> Result<(), Position> {
+        let plain = |instruction: &Instruction| {
+            matches!(
+                instruction,
+                Instruction::MoveRight
+                    | Instruction::MoveLeft
+                    | Instruction::Increment
+                    | Instruction::Decrement
+                    | Instruction::Output
+                    | Instruction::Replace
+                    | Instruction::JumpToClose
+                    | Instruction::JumpToOpen
+                    | Instruction::DebugDump
+                    | Instruction::Halt
+            )
+        };
+        if let Some(index) = self
+            .program
+            .instructions()
+            .position(|instruction| !plain(&instruction))
+        {
+            return Err(self.program.positions[index]);
+        }
+        self.big_cells = Some(big::BigCells::new(underflow));
+        Ok(())
+    }
+    ///starts keeping the last `capacity` instructions that run, see history.rs
+    pub fn keep_history(&mut self, capacity: usize) {
+        self.history = Some(history::History::new(capacity));
+    }
+    ///the last instructions that ran, if keep_history was called
+    pub fn history(&self) -> Option<&history::History> {
+        self.history.as_ref()
+    }
+    ///starts keeping track of who wrote every cell last, see writers.rs
+    pub fn track_writers(&mut self) {
+        self.writers = Some(writers::Writers::new());
+    }
+    ///who wrote every cell last, if track_writers was called
+    pub fn writers(&self) -> Option<&writers::Writers> {
+        self.writers.as_ref()
+    }
+    ///puts back who wrote every cell last, like when the machine is taken back to a step that
+    ///they were kept from
+    pub fn set_writers(&mut self, writers: Option<writers::Writers>) {
+        self.writers = writers;
+    }
+    ///the cell and its value, like "cell 17 = 42", and who wrote it last when that's kept track
+    ///of, like "(last written at step 10233 by '+' at 7:12)"
+    pub fn describe_cell(&self, index: usize) -> String {
+        let value = match index < self.tape.len() {
+            true => self.cell_text(index),
+            false => "0".to_string(),
+        };
+        let writer = match self.writers.as_ref().map(|writers| writers.last(index)) {
+            Some(Some(write)) => format!(" ({})", write),
+            Some(None) => " (never written)".to_string(),
+            None => String::new(),
+        };
+        format!("cell {} = {}{}", index, value, writer)
+    }
+    ///the cells above their lowest byte, if they have no upper limit
+    #[cfg(feature = "bigint")]
+    pub fn big_cells(&self) -> Option<&big::BigCells> {
+        self.big_cells.as_ref()
+    }
+    ///sets every cell back to 0 and the pointer back to the first cell, along with extended type
+    ///I's storage and the step count, as if the machine had just been made
+    pub fn reset(&mut self) {
+        self.tape = [0; TAPE_LENGTH];
+        #[cfg(feature = "bigint")]
+        if let Some(big_cells) = &mut self.big_cells {
+            big_cells.clear();
+        }
+        self.pointer = 0;
+        self.storage = 0;
+        self.steps = 0;
+    }
+    ///executes the "MoveRight" instruction on the turing machine, so it just moves the head, or
+    ///pointer, one value to the right on the tape
+    fn move_right(&mut self) -> Result<(), RuntimeError> {
+        if self.pointer + 1 >= self.tape.len() {
+            return Err(RuntimeError::PointerOutOfBounds(
+                self.program.positions[self.program_counter],
+            ));
+        }
+        self.pointer += 1;
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes the "MoveLeft" instruction on the turing machine, so it just moves the head, or
+    ///pointer, one value to the left on the tape
+    fn move_left(&mut self) -> Result<(), RuntimeError> {
+        if self.pointer == 0 {
+            return Err(RuntimeError::PointerOutOfBounds(
+                self.program.positions[self.program_counter],
+            ));
+        }
+        self.pointer -= 1;
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes the "Increment" instruction on the turing machine, does nothing more than Increment
+    ///the value of the current cell being pointed at by the pointer or head
+    fn increment(&mut self) {
+        self.tape[self.pointer] = self.tape[self.pointer].wrapping_add(1);
+        #[cfg(feature = "bigint")]
+        if let Some(big_cells) = &mut self.big_cells {
+            big_cells.incremented(self.pointer, self.tape[self.pointer]);
+        }
+        self.program_counter += 1;
+    }
+    ///executes the "Decrement" instruction on the turing machine, does nothing more than Decrement
+    ///the value of the current cell being pointed at by the pointer or head
+    fn decrement(&mut self) -> Result<(), RuntimeError> {
+        #[cfg(feature = "bigint")]
+        if let Some(big_cells) = &mut self.big_cells {
+            if !big_cells.decrementing(self.pointer, self.tape[self.pointer]) {
+                if big_cells.underflow == big::Underflow::Error {
+                    return Err(RuntimeError::CellUnderflow(
+                        self.program.positions[self.program_counter],
+                    ));
+                }
+                self.program_counter += 1;
+                return Ok(());
+            }
+        }
+        self.tape[self.pointer] = self.tape[self.pointer].wrapping_sub(1);
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes the "Write" instruction on the turing machine, prints the value of the current cell
+    ///being pointed at by the pointer
+    fn write(&mut self) -> Result<(), RuntimeError> {
+        self.write_byte(self.tape[self.pointer])?;
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///writes a single byte to the output, exactly as it is
+    fn write_byte(&mut self, byte: u8) -> Result<(), RuntimeError> {
+        if self.output_limit == Some(self.output_bytes) {
+            return Err(RuntimeError::OutputLimitExceeded(
+                self.output_bytes,
+                self.position_or_last(),
+            ));
+        }
+        self.output_bytes += 1;
+        self.last_output = Some(byte);
+        self.forget_states();
+        if self.warnings.is_some() {
+            self.watch_output(byte);
+        }
+        if self.event_driven {
+            self.pending_output.push_back(byte);
+            return Ok(());
+        }
+        lock(&self.output).write_byte(byte).map_err(write_error)
+    }
+
+    ///reads a single byte of input, or nothing if the input has run out, from the channel
+    ///that's switched to when there are channels
+    fn read_byte(&mut self) -> Result<Option<u8>, RuntimeError> {
+        match self.channels.is_some() {
+            true => self.read_channel(),
+            false => self.read_input(),
+        }
+    }
+    ///reads a single byte from the input handle, or the input provided when driven by events
+    fn read_input(&mut self) -> Result<Option<u8>, RuntimeError> {
+        self.forget_states();
+        //a program run by "%" can't stop halfway to wait for input, it has to run to the end in
+        //one go, so it reads from the input handle even when the machine is driven by events
+        if self.event_driven && (self.eval_depth == 0 || self.provided_input.is_some()) {
+            return self.provided_input.take().ok_or(RuntimeError::InputNeeded);
+        }
+        //anything printed so far should be visible before we sit and wait for the user to type
+        self.flush_output()?;
+        lock(&self.input)
+            .read_byte()
+            .map_err(|e| RuntimeError::IoError(format!("could not read input: {}", e)))
+    }
+    ///makes sure everything written to the output so far has actually gone out
+    fn flush_output(&mut self) -> Result<(), RuntimeError> {
+        if self.event_driven {
+            return Ok(());
+        }
+        lock(&self.output).flush_bytes().map_err(write_error)
+    }
+    ///executes the "Replace" instruction, reads a single byte of input and puts it in the current
+    ///cell. if the input has run out, the eof behavior decides what happens to the cell instead,
+    ///the one of the channel it read from when it has one of its own
+    fn replace(&mut self) -> Result<(), RuntimeError> {
+        let byte = self.read_byte()?;
+        let eof_behavior = self.channel_eof().unwrap_or(self.eof_behavior);
+        #[cfg(feature = "bigint")]
+        if let (Some(big_cells), false) = (
+            &mut self.big_cells,
+            byte.is_none() && eof_behavior == EofBehavior::Unchanged,
+        ) {
+            big_cells.forget(self.pointer);
+        }
+        match byte {
+            Some(byte) => self.tape[self.pointer] = byte,
+            None => match eof_behavior {
+                EofBehavior::Zero => self.tape[self.pointer] = 0,
+                EofBehavior::Max => self.tape[self.pointer] = 255,
+                EofBehavior::Unchanged => {}
+            },
+        }
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes boolfuck's "+" instruction. cells only ever hold a 0 or a 1 in boolfuck
+    fn flip_bit(&mut self) {
+        self.tape[self.pointer] ^= 1;
+        self.program_counter += 1;
+    }
+    ///executes boolfuck's "," instruction. bits are read from each byte of input starting with
+    ///the lowest one, and once the input runs out every bit read is a 0
+    fn read_bit(&mut self) -> Result<(), RuntimeError> {
+        if self.input_bits.1 == 0 {
+            self.input_bits = (self.read_byte()?.unwrap_or(0), 8);
+        }
+        self.tape[self.pointer] = self.input_bits.0 & 1;
+        self.input_bits = (self.input_bits.0 >> 1, self.input_bits.1 - 1);
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes boolfuck's ";" instruction. bits go into each byte of output starting with the
+    ///lowest one, and the byte gets written once all eight of its bits are there
+    fn write_bit(&mut self) -> Result<(), RuntimeError> {
+        let (byte, count) = self.output_bits;
+        self.output_bits = (byte | (self.tape[self.pointer] & 1) << count, count + 1);
+        if self.output_bits.1 == 8 {
+            self.flush_bits()?;
+        }
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes bf-rand's "?" instruction
+    fn random(&mut self) {
+        self.tape[self.pointer] = self.rng.next_byte();
+        self.program_counter += 1;
+    }
+    ///reads the text on the tape that starts at the current cell and goes up to the first cell
+    ///that is 0
+    fn text_at_pointer(&self) -> Result<String, RuntimeError> {
+        let position = self.program.positions[self.program_counter];
+        let length = self.tape[self.pointer..]
+            .iter()
+            .position(|cell| *cell == 0)
+            .ok_or(RuntimeError::UnterminatedText(position))?;
+        let text = &self.tape[self.pointer..self.pointer + length];
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+    ///executes the "%" instruction. the text at the pointer is parsed and run on this same
+    ///machine, so it shares the tape and the pointer with the program around it. once it is done
+    ///the program around it carries on where it was
+    fn eval(&mut self) -> Result<(), RuntimeError> {
+        let position = self.program.positions[self.program_counter];
+        let source = self.text_at_pointer()?;
+        let program = Program::parse(&source, &lang::Lang::BfEval, &self.custom.characters())
+            .map_err(|e| RuntimeError::EvalParseError(e, position))?;
+        self.enter(Call {
+            site: position,
+            return_to: None,
+        })?;
+        let outer_program = core::mem::replace(&mut self.program, Arc::new(program));
+        let outer_program_counter = core::mem::replace(&mut self.program_counter, 0);
+        self.eval_depth += 1;
+        let mut result = Ok(());
+        while result.is_ok() && self.has_instructions_left() {
+            result = self.perform_next_instruction();
+        }
+        self.eval_depth -= 1;
+        self.calls.pop();
+        self.program = outer_program;
+        self.program_counter = outer_program_counter + 1;
+        result
+    }
+    ///executes brainfuck++'s "#" instruction. the file name is read from the tape, starting at
+    ///the current cell and going up to the first cell that is 0. the file is created if it isn't
+    ///there yet, and replaces whatever file was open before
+    #[cfg(feature = "std")]
+    fn open_file(&mut self) -> Result<(), RuntimeError> {
+        let position = self.program.positions[self.program_counter];
+        let name = self.text_at_pointer()?;
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&name)
+            .map_err(|e| {
+                RuntimeError::FileError(format!("could not open '{}': {}", name, e), position)
+            })?;
+        self.file = Some(Arc::new(file));
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes brainfuck++'s ";" instruction. the end of the file is handled the same way as the
+    ///end of the input
+    #[cfg(feature = "std")]
+    fn read_file(&mut self) -> Result<(), RuntimeError> {
+        let position = self.program.positions[self.program_counter];
+        self.forget_states();
+        let mut file = self
+            .file
+            .as_deref()
+            .ok_or(RuntimeError::NoOpenFile(position))?;
+        let mut byte: [u8; 1] = [0; 1];
+        match file.read(&mut byte) {
+            Ok(1) => self.tape[self.pointer] = byte[0],
+            Ok(_) => match self.eof_behavior {
+                EofBehavior::Zero => self.tape[self.pointer] = 0,
+                EofBehavior::Max => self.tape[self.pointer] = 255,
+                EofBehavior::Unchanged => {}
+            },
+            Err(e) => {
+                return Err(RuntimeError::FileError(
+                    format!("could not read from the file: {}", e),
+                    position,
+                ))
+            }
+        }
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///executes brainfuck++'s ":" instruction
+    #[cfg(feature = "std")]
+    fn write_file(&mut self) -> Result<(), RuntimeError> {
+        let position = self.program.positions[self.program_counter];
+        self.forget_states();
+        let mut file = self
+            .file
+            .as_deref()
+            .ok_or(RuntimeError::NoOpenFile(position))?;
+        file.write_all(&[self.tape[self.pointer]]).map_err(|e| {
+            RuntimeError::FileError(format!("could not write to the file: {}", e), position)
+        })?;
+        self.program_counter += 1;
+        Ok(())
+    }
+    ///without std there are no files, so brainfuck++'s file instructions all fail
+    #[cfg(not(feature = "std"))]
+    fn no_files(&self) -> Result<(), RuntimeError> {
+        Err(RuntimeError::FileError(
+            "there are no files without std".to_string(),
+            self.program.positions[self.program_counter],
+        ))
+    }
+    ///writes out the bits boolfuck has written that haven't made up a whole byte yet, with the
+    ///missing high bits set to 0
+    fn flush_bits(&mut self) -> Result<(), RuntimeError> {
+        if self.output_bits.1 > 0 {
+            self.write_byte(self.output_bits.0)?;
+            self.output_bits = (0, 0);
+        }
+        Ok(())
+    }
+    ///executes the "JumpToClose" instruction, going past the matching "]" when the cell is 0, so
+    ///the "]" doesn't run as well
+    fn jump_if_zero(&mut self) {
+        match self.current_is_zero() {
+            true => {
+                self.program_counter = self.program.ops[self.program_counter].target() + 1;
+            }
+            false => {
+                self.program_counter += 1;
+            }
+        }
+    }
+    ///whether the current cell is 0, which a cell with no upper limit only is when none of it is
+    ///left above the lowest byte either
+    fn current_is_zero(&self) -> bool {
+        self.is_zero(self.pointer)
+    }
+    ///whether the cell is 0, all of it when cells have no upper limit
+    fn is_zero(&self, index: usize) -> bool {
+        #[cfg(feature = "bigint")]
+        if let Some(big_cells) = &self.big_cells {
+            return big_cells.is_zero(index, self.tape[index]);
+        }
+        self.tape[index] == 0
+    }
+    ///executes the "JumpToOpen" Instruction, going back to just after the matching "[" when the
+    ///cell isn't 0. the "[" would only look at the same cell again. with a loop iteration limit,
+    ///it also counts how many times in a row the loop has gone around, starting over whenever
+    ///the loop is left
+    fn jump_unless_zero(&mut self) -> Result<(), RuntimeError> {
+        match self.current_is_zero() {
+            true => {
+                if let Some(iterations) = self.loop_iterations.get_mut(self.program_counter) {
+                    *iterations = 0;
+                }
+                self.program_counter += 1;
+            }
+            false => {
+                let new_counter = self.program.ops[self.program_counter].target();
+                if let Some(limit) = self.loop_iteration_limit {
+                    if self.loop_iterations.len() < self.program.len() {
+                        self.loop_iterations.resize(self.program.len(), 0);
+                    }
+                    let iterations = &mut self.loop_iterations[self.program_counter];
+                    *iterations += 1;
+                    if *iterations > limit {
+                        return Err(RuntimeError::LoopIterationLimit(
+                            limit,
+                            self.program.positions[new_counter],
+                            self.program.positions[self.program_counter],
+                        ));
+                    }
+                }
+                self.count_back_edge(self.program_counter);
+                self.program_counter = new_counter + 1;
+            }
+        }
+        Ok(())
+    }
+
+    ///executes pbrain's "(" instruction. the procedure gets the number in the current cell, and
+    ///its body is skipped over, it only runs when it gets called
+    fn define_procedure(&mut self) {
+        self.procedures[self.tape[self.pointer] as usize] = Some(self.program_counter + 1);
+        self.program_counter = self.program.ops[self.program_counter].target() + 1;
+    }
+    ///executes pbrain's ")" instruction, going back to just after the ":" that called the
+    ///procedure
+    fn return_from_procedure(&mut self) {
+        match self.calls.last().and_then(|call| call.return_to) {
+            Some(return_to) => {
+                self.calls.pop();
+                self.program_counter = return_to;
+            }
+            None => self.program_counter += 1,
+        }
+    }
+    ///executes pbrain's ":" instruction, jumping into the procedure numbered by the current cell
+    fn call(&mut self) -> Result<(), RuntimeError> {
+        let number = self.tape[self.pointer];
+        let position = self.program.positions[self.program_counter];
+        let start = self.procedures[number as usize]
+            .ok_or(RuntimeError::UndefinedProcedure(number, position))?;
+        self.enter(Call {
+            site: position,
+            return_to: Some(self.program_counter + 1),
+        })?;
+        self.program_counter = start;
+        Ok(())
+    }
+    ///puts a procedure call or an eval on the call stack, unless that would go deeper than the
+    ///limits allow. this is the only place they're checked
+    fn enter(&mut self, call: Call) -> Result<(), RuntimeError> {
+        let limit = match call.return_to {
+            _ if self.calls.len() >= self.call_depth_limit => Some(self.call_depth_limit),
+            None if self.eval_depth >= self.eval_depth_limit => Some(self.eval_depth_limit),
+            _ => None,
+        };
+        if let Some(limit) = limit {
+            let mut sites: Vec<Position> = self.calls.iter().map(|call| call.site).collect();
+            sites.push(call.site);
+            return Err(RuntimeError::CallDepthExceeded(limit, sites));
+        }
+        self.calls.push(call);
+        Ok(())
+    }
+    ///executes one of the extended type I instructions that only work on the current cell and
+    ///storage
+    fn extended(&mut self, instruction: Instruction) {
+        let cell = &mut self.tape[self.pointer];
+        match instruction {
+            Instruction::Store => self.storage = *cell,
+            Instruction::Restore => *cell = self.storage,
+            Instruction::ShiftRight => *cell >>= 1,
+            Instruction::ShiftLeft => *cell <<= 1,
+            Instruction::Not => *cell = !*cell,
+            Instruction::Xor => *cell ^= self.storage,
+            Instruction::And => *cell &= self.storage,
+            Instruction::Or => *cell |= self.storage,
+            _ => unreachable!("not an extended type I instruction: {:?}", instruction),
+        }
+        self.program_counter += 1;
+    }
+    ///executes the "Halt" instruction by moving the program counter past the end of the program,
+    ///so there is nothing left to run
+    fn halt(&mut self) {
+        self.halted_at = Some(self.program.positions[self.program_counter]);
+        self.program_counter = self.program.len();
+    }
+    ///where the "Halt" instruction that ended the program is, or nothing when the program ran
+    ///off its end instead, or hasn't ended yet
+    pub fn halted_at(&self) -> Option<Position> {
+        self.halted_at
+    }
+    ///executes the "DebugDump" instruction, printing where the pointer is and the cells from the
+    ///start of the tape up to the last one that is in use. the current cell is put in brackets
+    fn dump(&mut self) {
+        let last_used = self.tape.iter().rposition(|cell| *cell != 0).unwrap_or(0);
+        let cells = self.cells(0..=last_used.max(self.pointer), &BTreeMap::new());
+        //the dump goes to stderr, anything the program printed before it should come out first
+        let _ = lock(&self.output).flush_bytes();
+        let dump = format!(
+            "dump at {}: pointer {}, cells {}",
+            self.program.positions[self.program_counter], self.pointer, cells
+        );
+        //without std there's no stderr, so it goes to the log instead
+        #[cfg(feature = "std")]
+        crate::report::report("dump", &dump);
+        #[cfg(not(feature = "std"))]
+        log::info!("{}", dump);
+        self.program_counter += 1;
+    }
+    ///the cells in the range written out next to each other, with the current cell in brackets
+    ///cells with a name are written as name=value
+    fn cells(
+        &self,
+        range: core::ops::RangeInclusive<usize>,
+        names: &BTreeMap<usize, String>,
+    ) -> String {
+        let cells: Vec<String> = range
+            .map(|index| {
+                let text = match names.get(&index) {
+                    Some(name) => format!("{}={}", name, self.cell_text(index)),
+                    None => self.cell_text(index),
+                };
+                match index == self.pointer {
+                    true => style::current(&format!("[{}]", text)),
+                    false => text,
+                }
+            })
+            .collect();
+        cells.join(" ")
+    }
+    ///the value of the cell as a number, all of it when cells have no upper limit
+    fn cell_text(&self, index: usize) -> String {
+        #[cfg(feature = "bigint")]
+        if let Some(big_cells) = &self.big_cells {
+            return big_cells.value(index, self.tape[index]).to_string();
+        }
+        self.cell_signedness.value(self.tape[index]).to_string()
+    }
+    ///the cells that aren't 0, as runs of cells next to each other, from the start of the tape.
+    ///for finding what a program left behind once it's done, see --check-leaks
+    pub fn nonzero_cells(&self) -> Vec<core::ops::RangeInclusive<usize>> {
+        let mut runs: Vec<core::ops::RangeInclusive<usize>> = vec![];
+        for index in (0..self.tape.len()).filter(|index| !self.is_zero(*index)) {
+            match runs.last_mut() {
+                Some(run) if *run.end() + 1 == index => *run = *run.start()..=index,
+                _ => runs.push(index..=index),
+            }
+        }
+        runs
+    }
+    ///the cells in the range written out the way describe_state writes them, with the ones the
+    ///program named shown along with their names. the part of the range past the end of the tape
+    ///is left out
+    pub fn show_cells(&self, range: core::ops::RangeInclusive<usize>) -> String {
+        let end = (*range.end()).min(self.tape.len() - 1);
+        self.cells(*range.start()..=end, self.program.cell_names())
+    }
+    ///a line about where the machine is: the instruction it's at, the pointer, how many steps it
+    ///has run and the cells around the pointer. for telling people where a program was stopped
+    pub fn describe_state(&self) -> String {
+        self.describe_state_named(&BTreeMap::new())
+    }
+    ///the same as describe_state, with the cells that have names (see annotations.rs) shown
+    ///along with them
+    pub fn describe_state_named(&self, names: &BTreeMap<usize, String>) -> String {
+        let place = match self.program.positions.get(self.program_counter) {
+            Some(position) => position.to_string(),
+            None => "the end".to_string(),
+        };
+        let window = self.pointer.saturating_sub(8)..=(self.pointer + 8).min(self.tape.len() - 1);
+        format!(
+            "at {}: pointer {}, {} steps, cells {}: {}",
+            place,
+            self.pointer,
+            self.steps,
+            window.start(),
+            self.cells(window.clone(), names)
+        )
+    }
+    ///executes brainfork's "Y" instruction. the new machine gets a copy of the tape and moves one
+    ///cell to the right, setting that cell to 1, while the current cell of this machine is set to
+    ///0. that way the program can tell from the tape which of the two it is
+    fn fork(&mut self) -> Result<(), RuntimeError> {
+        if self.pointer + 1 >= self.tape.len() {
+            return Err(RuntimeError::PointerOutOfBounds(
+                self.program.positions[self.program_counter],
+            ));
+        }
+        let position = self.program.positions[self.program_counter];
+        self.program_counter += 1;
+        let mut child = self.clone();
+        child.forked.clear();
+        child.pointer += 1;
+        child.tape[child.pointer] = 1;
+        if let Some(writers) = &mut child.writers {
+            writers.record(
+                child.pointer,
+                writers::Write {
+                    step: self.steps,
+                    position,
+                    instruction: Instruction::Fork,
+                },
+            );
+        }
+        self.tape[self.pointer] = 0;
+        self.forked.push(child);
+        Ok(())
+    }
+
+    ///checks if the turing machine still has instructions left to exeute
+    pub fn has_instructions_left(&self) -> bool {
+        self.program_counter < self.program.len()
+    }
+    ///executes the current instruction pointed to by the program counter for our turing machine,
+    ///for callers that don't need to know whether there was one, see step
+    pub fn perform_next_instruction(&mut self) -> Result<(), RuntimeError> {
+        self.step().map(|_| ())
+    }
+    ///executes the current instruction pointed to by the program counter. once the counter is
+    ///past the last instruction the program has ended, and nothing runs or is counted
+    pub fn step(&mut self) -> Result<StepResult, RuntimeError> {
+        if !self.has_instructions_left() {
+            return Ok(StepResult::Halted);
+        }
+        if let Some(limit) = self.step_limit {
+            if self.steps >= limit {
+                return Err(RuntimeError::StepLimitReached(limit));
+            }
+        }
+        self.check_fuel()?;
+        if self.cycles.is_some() {
+            self.check_for_cycle()?;
+        }
+        if self.warnings.is_some() {
+            self.check_warnings();
+        }
+        if self.tiering.is_some() && self.run_tiered() {
+            return Ok(StepResult::Ran);
+        }
+        if !self.program.fused.is_empty() && self.run_fused() {
+            return Ok(StepResult::Ran);
+        }
+        if let Some(block) = self.program.ops[self.program_counter].block() {
+            if self.run_block(block) {
+                return Ok(StepResult::Ran);
+            }
+        }
+        self.steps += 1;
+        self.burn_fuel(1);
+        //checking the level first keeps the trace from costing anything when it's off
+        if log::log_enabled!(log::Level::Trace) {
+            let instruction = self.program.instruction(self.program_counter);
+            if let (Some(instruction), Some(position)) = (
+                instruction,
+                self.program.positions.get(self.program_counter),
+            ) {
+                log::trace!(
+                    "step {}: {} at {}, pointer {}",
+                    self.steps,
+                    style::instruction(&format!("{:?}", instruction)),
+                    position,
+                    self.pointer
+                );
+            }
+        }
+        let counter = self.program_counter;
+        let pointer = self.pointer;
+        if self.observer.is_some() {
+            self.observe_before(counter);
+        }
+        let result = self.execute_instruction();
+        if self.observer.is_some() && result.is_ok() {
+            self.observe_after(counter);
+        }
+        if let (Some(history), Ok(())) = (&mut self.history, &result) {
+            history.record(history::Entry {
+                step: self.steps,
+                position: self.program.positions[counter],
+                instruction: self.program.ops[counter].instruction(),
+                pointer: self.pointer,
+                cell: self.tape[self.pointer],
+            });
+        }
+        if let (Some(writers), Ok(())) = (&mut self.writers, &result) {
+            let instruction = self.program.ops[counter].instruction();
+            if writers::writes_cell(instruction) {
+                writers.record(
+                    pointer,
+                    writers::Write {
+                        step: self.steps,
+                        position: self.program.positions[counter],
+                        instruction,
+                    },
+                );
+            }
+        }
+        if result == Err(RuntimeError::InputNeeded) {
+            //the instruction didn't actually run, it will be run again once there is input
+            self.steps -= 1;
+            self.refund_fuel();
+        }
+        result.map(|_| StepResult::Ran)
+    }
+    ///does the work for perform_next_instruction, without the counting
+    fn execute_instruction(&mut self) -> Result<(), RuntimeError> {
+        match self.program.instruction(self.program_counter) {
+            Some(Instruction::MoveRight) => {
+                self.move_right()?;
+            }
+            Some(Instruction::MoveLeft) => {
+                self.move_left()?;
+            }
+            Some(Instruction::Increment) => {
+                self.increment();
+            }
+            Some(Instruction::Decrement) => {
+                self.decrement()?;
+            }
+            Some(Instruction::Output) => {
+                self.write()?;
+            }
+            Some(Instruction::Replace) => {
+                self.replace()?;
+            }
+            Some(Instruction::JumpToClose) => {
+                self.jump_if_zero();
+            }
+            Some(Instruction::JumpToOpen) => {
+                self.jump_unless_zero()?;
+            }
+            Some(Instruction::ProcedureStart) => {
+                self.define_procedure();
+            }
+            Some(Instruction::ProcedureEnd) => {
+                self.return_from_procedure();
+            }
+            Some(Instruction::Call) => {
+                self.call()?;
+            }
+            Some(Instruction::Halt) => {
+                self.halt();
+            }
+            Some(Instruction::DebugDump) => {
+                self.dump();
+            }
+            Some(Instruction::Fork) => {
+                self.fork()?;
+            }
+            Some(Instruction::FlipBit) => {
+                self.flip_bit();
+            }
+            Some(Instruction::ReadBit) => {
+                self.read_bit()?;
+            }
+            Some(Instruction::WriteBit) => {
+                self.write_bit()?;
+            }
+            #[cfg(feature = "std")]
+            Some(Instruction::OpenFile) => {
+                self.open_file()?;
+            }
+            #[cfg(feature = "std")]
+            Some(Instruction::ReadFile) => {
+                self.read_file()?;
+            }
+            #[cfg(feature = "std")]
+            Some(Instruction::WriteFile) => {
+                self.write_file()?;
+            }
+            #[cfg(not(feature = "std"))]
+            Some(Instruction::OpenFile | Instruction::ReadFile | Instruction::WriteFile) => {
+                self.no_files()?;
+            }
+            Some(Instruction::Eval) => {
+                self.eval()?;
+            }
+            Some(Instruction::Random) => {
+                self.random();
+            }
+            Some(Instruction::SwitchChannel) => {
+                self.switch_channel();
+            }
+            Some(Instruction::Custom(index)) => {
+                self.run_custom(index)?;
+            }
+            Some(
+                instruction @ (Instruction::Store
+                | Instruction::Restore
+                | Instruction::ShiftRight
+                | Instruction::ShiftLeft
+                | Instruction::Not
+                | Instruction::Xor
+                | Instruction::And
+                | Instruction::Or),
+            ) => {
+                self.extended(instruction);
+            }
+            //step never gets here past the end of the program
+            None => {}
+        }
+        Ok(())
+    }
+    ///starts executing the program loaded into our turing machine, until it runs out of
+    ///instructions, something goes wrong, or it's cancelled through a handle from control_handle
+    pub fn run(&mut self) -> Result<RunOutcome, RuntimeError> {
+        let outcome = control::settle(self.run_to_end());
+        self.observe_end(outcome.as_ref().copied());
+        outcome
+    }
+    ///the work for run, before the observer is told how it went
+    fn run_to_end(&mut self) -> Result<RunOutcome, RuntimeError> {
+        while self.has_instructions_left() {
+            if self.should_stop() {
+                self.flush_output()?;
+                return Ok(RunOutcome::Cancelled);
+            }
+            self.perform_next_instruction()?;
+        }
+        self.finish()?;
+        #[cfg(feature = "std")]
+        self.report_progress(true);
+        Ok(RunOutcome::Halted)
+    }
+    ///checks in with the control handles and the progress callback between two instructions.
+    ///gives back true if either of them wants the run to stop
+    #[cfg(feature = "std")]
+    fn should_stop(&mut self) -> bool {
+        if let Some(control) = &self.control {
+            if self.steps.is_multiple_of(self.control_interval.max(1)) && control.cancelled() {
+                return true;
+            }
+        }
+        self.report_progress(false)
+    }
+    ///without std there are no control handles or progress callbacks to stop a run
+    #[cfg(not(feature = "std"))]
+    fn should_stop(&mut self) -> bool {
+        false
+    }
+    ///writes out anything the program left half done once it has no instructions left, like the
+    ///last few bits boolfuck wrote, and flushes the output
+    pub fn finish(&mut self) -> Result<(), RuntimeError> {
+        self.flush_bits()?;
+        self.flush_output()
+    }
+}
@@ -0,0 +1,477 @@
+//! a small line editor for the repl, so typing at it works like typing at a shell: moving around
+//! the line and changing it, going back through earlier lines with up and down or searching them
+//! with ctrl+r, and finishing ":" commands and their arguments with tab. it only turns keys into
+//! changes to the line, and the line into what the terminal should show. reading the keys and
+//! putting the terminal in the mode for it is up to whoever uses it, and so is what tab offers,
+//! see complete
+//!
+//! the lines typed are kept in a History, which can be read from and written to a file so they're
+//! still there the next time
+use std::path::{Path, PathBuf};
+
+///the most lines a history keeps, the oldest ones go first
+pub const HISTORY_LIMIT: usize = 1000;
+
+///the lines typed before, oldest first
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct History {
+    pub entries: Vec<String>,
+}
+
+impl History {
+    ///the history in the file, a line each, or none if there's no file yet
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(History {
+                entries: text.lines().map(String::from).collect(),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(History::default()),
+            Err(e) => Err(e),
+        }
+    }
+    ///writes the history to the file, making the directory it's in if there isn't one
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| format!("{}\n", entry))
+            .collect();
+        std::fs::write(path, text)
+    }
+    ///adds a line, unless it's blank or the same as the last one
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        if self.entries.len() > HISTORY_LIMIT {
+            self.entries.drain(..self.entries.len() - HISTORY_LIMIT);
+        }
+    }
+    ///the newest line before the one at `before` with the text in it
+    pub fn search(&self, text: &str, before: usize) -> Option<usize> {
+        self.entries[..before.min(self.entries.len())]
+            .iter()
+            .rposition(|entry| entry.contains(text))
+    }
+}
+
+///where the repl keeps its history when it isn't told: bfint/repl_history in $XDG_DATA_HOME,
+///or in ~/.local/share when that isn't set. nothing when there's no home directory either
+pub fn default_history_path() -> Option<PathBuf> {
+    let absolute = |directory: &std::ffi::OsString| Path::new(directory).is_absolute();
+    let data = match std::env::var_os("XDG_DATA_HOME").filter(absolute) {
+        Some(data) => PathBuf::from(data),
+        None => PathBuf::from(std::env::var_os("HOME").filter(absolute)?).join(".local/share"),
+    };
+    Some(data.join("bfint").join("repl_history"))
+}
+
+///a key, as the terminal sends it
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Key {
+    Char(char),
+    ///a letter with ctrl held down, for the ones that don't have a key of their own
+    Ctrl(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Escape,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    ///ctrl or alt with left and right, which go a word at a time
+    WordLeft,
+    WordRight,
+    ///an escape sequence for a key that doesn't do anything here
+    Other,
+}
+
+impl Key {
+    ///the first key in the bytes the terminal sent, and how many bytes it was. nothing when the
+    ///bytes stop partway through a key, and more have to be read first. an escape on its own is
+    ///the escape key, since the sequences the other keys send come all at once
+    pub fn parse(bytes: &[u8]) -> Option<(Key, usize)> {
+        let key = match *bytes.first()? {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 0x08 => Key::Backspace,
+            0x1b => return Key::escape(bytes),
+            byte @ 0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+            0x00 | 0x1c..=0x1f => Key::Other,
+            byte if byte < 0x80 => Key::Char(byte as char),
+            byte => {
+                //the first byte of a character says how many there are
+                let length = match byte {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+                if bytes.len() < length {
+                    return None;
+                }
+                let key = match std::str::from_utf8(&bytes[..length]) {
+                    Ok(text) => text.chars().next().map_or(Key::Other, Key::Char),
+                    Err(_) => Key::Other,
+                };
+                return Some((key, length));
+            }
+        };
+        Some((key, 1))
+    }
+    ///the key an escape sequence stands for, like "\x1b[A" for up
+    fn escape(bytes: &[u8]) -> Option<(Key, usize)> {
+        match bytes.get(1) {
+            None => Some((Key::Escape, 1)),
+            Some(b'b') => Some((Key::WordLeft, 2)),
+            Some(b'f') => Some((Key::WordRight, 2)),
+            Some(b'[' | b'O') => {
+                //numbers split up by ";" and then the letter that ends it
+                let end = bytes[2..]
+                    .iter()
+                    .position(|byte| (0x40..=0x7e).contains(byte))?
+                    + 2;
+                let parameters = String::from_utf8_lossy(&bytes[2..end]);
+                let mut parameters = parameters.split(';');
+                let first = parameters.next().unwrap_or_default();
+                let held = parameters.next().is_some_and(|held| held != "1");
+                let key = match (bytes[end], first) {
+                    (b'A', _) => Key::Up,
+                    (b'B', _) => Key::Down,
+                    (b'C', _) if held => Key::WordRight,
+                    (b'D', _) if held => Key::WordLeft,
+                    (b'C', _) => Key::Right,
+                    (b'D', _) => Key::Left,
+                    (b'H', _) | (b'~', "1" | "7") => Key::Home,
+                    (b'F', _) | (b'~', "4" | "8") => Key::End,
+                    (b'~', "3") => Key::Delete,
+                    _ => Key::Other,
+                };
+                Some((key, end + 1))
+            }
+            Some(_) => Some((Key::Escape, 1)),
+        }
+    }
+}
+
+///what tab can finish the line with: where the word it finishes starts, in characters, and every
+///word it could be
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+///what the argument of a ":" command is, for finishing it with tab
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Argument {
+    None,
+    ///a file, which is looked for on disk
+    Path,
+    ///a cell, by one of the names it's been given
+    Cell,
+}
+
+///what tab offers for the line up to the cursor. only ":" commands are finished, the names of the
+///commands and then their first argument, and a line of brainfuck gets nothing
+pub fn complete(line: &str, commands: &[(&str, Argument)], names: &[String]) -> Completion {
+    if !line.trim_start().starts_with(':') {
+        return Completion::default();
+    }
+    let start = line.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+    let word = &line[start..];
+    let before: Vec<&str> = line[..start].split_whitespace().collect();
+    let matching = |options: &mut dyn Iterator<Item = String>| -> Vec<String> {
+        options.filter(|option| option.starts_with(word)).collect()
+    };
+    let candidates = match before[..] {
+        [] => matching(&mut commands.iter().map(|(command, _)| command.to_string())),
+        [command] => match commands.iter().find(|(name, _)| *name == command) {
+            Some((_, Argument::Path)) => paths(word),
+            Some((_, Argument::Cell)) => matching(&mut names.iter().cloned()),
+            _ => vec![],
+        },
+        _ => vec![],
+    };
+    Completion {
+        start: line[..start].chars().count(),
+        candidates,
+    }
+}
+
+///the files and directories whose paths start with the text, directories ending in "/". files
+///starting with "." only show up once the text has the "." too
+fn paths(text: &str) -> Vec<String> {
+    let (directory, file) = match text.rfind('/') {
+        Some(slash) => (&text[..=slash], &text[slash + 1..]),
+        None => ("", text),
+    };
+    let Ok(entries) = std::fs::read_dir(match directory {
+        "" => ".",
+        directory => directory,
+    }) else {
+        return vec![];
+    };
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(file) || (name.starts_with('.') && !file.starts_with('.')) {
+                return None;
+            }
+            let slash = match entry.path().is_dir() {
+                true => "/",
+                false => "",
+            };
+            Some(format!("{}{}{}", directory, name, slash))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+///what the editor wants done after a key
+#[derive(PartialEq, Debug, Clone)]
+pub enum Action {
+    ///the line changed, or the cursor moved, and should be shown again
+    Redraw,
+    ///enter was pressed on the line
+    Done(String),
+    ///ctrl+c threw the line away
+    Cancelled,
+    ///ctrl+d on an empty line, which leaves
+    Eof,
+    ///tab has more than one way of finishing the word, which should be listed before the line is
+    ///shown again
+    Show(Vec<String>),
+}
+
+///a ctrl+r search back through history
+#[derive(PartialEq, Debug, Clone)]
+struct Search {
+    text: String,
+    ///the line of history the text was last found in
+    found: Option<usize>,
+    ///the line as it was before the search, for when it's given up
+    before: Vec<char>,
+}
+
+///a line being typed
+#[derive(PartialEq, Debug, Clone)]
+pub struct Editor {
+    line: Vec<char>,
+    cursor: usize,
+    //the line of history up and down are on, one past the newest being the line being typed
+    browsing: usize,
+    //the line being typed, kept while up and down go through history
+    typed: Vec<char>,
+    search: Option<Search>,
+}
+
+impl Editor {
+    ///an empty line, with up going back to the newest line of the history
+    pub fn new(history: &History) -> Self {
+        Editor {
+            line: vec![],
+            cursor: 0,
+            browsing: history.entries.len(),
+            typed: vec![],
+            search: None,
+        }
+    }
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+    ///does what the key does to the line. tab asks complete for the line up to the cursor
+    pub fn key(
+        &mut self,
+        key: Key,
+        history: &History,
+        complete: &dyn Fn(&str) -> Completion,
+    ) -> Action {
+        if self.search.is_some() {
+            match self.search_key(key, history) {
+                Some(action) => return action,
+                //any other key takes the line that was found and goes on as usual
+                None => self.search = None,
+            }
+        }
+        match key {
+            Key::Char(c) => {
+                self.line.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Enter => return Action::Done(self.line()),
+            Key::Ctrl('c') => return Action::Cancelled,
+            Key::Ctrl('d') if self.line.is_empty() => return Action::Eof,
+            Key::Delete | Key::Ctrl('d') if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            Key::Left | Key::Ctrl('b') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right | Key::Ctrl('f') => self.cursor = (self.cursor + 1).min(self.line.len()),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.line.len(),
+            Key::WordLeft => self.cursor = self.word_start(),
+            Key::WordRight => {
+                let rest = &self.line[self.cursor..];
+                let spaces = rest.iter().take_while(|c| c.is_whitespace()).count();
+                let word = rest[spaces..]
+                    .iter()
+                    .take_while(|c| !c.is_whitespace())
+                    .count();
+                self.cursor += spaces + word;
+            }
+            Key::Ctrl('u') => {
+                self.line.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::Ctrl('k') => self.line.truncate(self.cursor),
+            Key::Ctrl('w') => {
+                let start = self.word_start();
+                self.line.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::Up | Key::Ctrl('p') => self.browse(history, self.browsing.checked_sub(1)),
+            Key::Down | Key::Ctrl('n') => {
+                let next = Some(self.browsing + 1).filter(|next| *next <= history.entries.len());
+                self.browse(history, next);
+            }
+            Key::Ctrl('r') => {
+                self.search = Some(Search {
+                    text: String::new(),
+                    found: None,
+                    before: self.line.clone(),
+                });
+            }
+            Key::Tab => return self.complete(complete),
+            _ => {}
+        }
+        Action::Redraw
+    }
+    ///where the word before the cursor starts, past any spaces right before it
+    fn word_start(&self) -> usize {
+        let before = &self.line[..self.cursor];
+        let spaces = before
+            .iter()
+            .rev()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        let word = before[..before.len() - spaces]
+            .iter()
+            .rev()
+            .take_while(|c| !c.is_whitespace())
+            .count();
+        self.cursor - spaces - word
+    }
+    ///shows the line of history, or the line being typed one past the newest. nothing stays put
+    fn browse(&mut self, history: &History, to: Option<usize>) {
+        let Some(to) = to else {
+            return;
+        };
+        if self.browsing == history.entries.len() {
+            self.typed = self.line.clone();
+        }
+        self.line = match history.entries.get(to) {
+            Some(entry) => entry.chars().collect(),
+            None => self.typed.clone(),
+        };
+        self.browsing = to;
+        self.cursor = self.line.len();
+    }
+    ///a key during a ctrl+r search. nothing for the keys that end it and are then handled as usual
+    fn search_key(&mut self, key: Key, history: &History) -> Option<Action> {
+        let search = self.search.as_mut()?;
+        let found = match key {
+            Key::Char(c) => {
+                search.text.push(c);
+                //the line found so far might still have the longer text in it
+                history.search(
+                    &search.text,
+                    search.found.map_or(usize::MAX, |found| found + 1),
+                )
+            }
+            Key::Backspace => {
+                search.text.pop();
+                history.search(&search.text, usize::MAX)
+            }
+            Key::Ctrl('r') => history.search(&search.text, search.found.unwrap_or(usize::MAX)),
+            Key::Ctrl('g') | Key::Ctrl('c') => {
+                self.line = search.before.clone();
+                self.cursor = self.line.len();
+                self.search = None;
+                return Some(Action::Redraw);
+            }
+            Key::Enter => {
+                self.search = None;
+                return Some(Action::Done(self.line()));
+            }
+            Key::Escape => {
+                self.search = None;
+                return Some(Action::Redraw);
+            }
+            _ => return None,
+        };
+        //a search that finds nothing more stays on what it found
+        if let Some(found) = found {
+            search.found = Some(found);
+            self.line = history.entries[found].chars().collect();
+            self.cursor = self.line.len();
+        }
+        Some(Action::Redraw)
+    }
+    ///finishes the word before the cursor as far as every way of finishing it agrees
+    fn complete(&mut self, complete: &dyn Fn(&str) -> Completion) -> Action {
+        let before: String = self.line[..self.cursor].iter().collect();
+        let completion = complete(&before);
+        let Some(first) = completion.candidates.first() else {
+            return Action::Redraw;
+        };
+        let mut common: Vec<char> = first.chars().collect();
+        for candidate in &completion.candidates[1..] {
+            let length = common
+                .iter()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| **a == *b)
+                .count();
+            common.truncate(length);
+        }
+        //a word that's finished gets a space after it, unless it's a directory, which goes on
+        if completion.candidates.len() == 1 && common.last() != Some(&'/') {
+            common.push(' ');
+        }
+        let start = completion.start.min(self.cursor);
+        if completion.candidates.len() > 1 && common.len() <= self.cursor - start {
+            return Action::Show(completion.candidates);
+        }
+        self.line.splice(start..self.cursor, common.iter().copied());
+        self.cursor = start + common.len();
+        Action::Redraw
+    }
+    ///what to write to the terminal to show the line after the prompt, over what was there, with
+    ///the cursor where it is in the line
+    pub fn render(&self, prompt: &str) -> String {
+        let (prompt, line) = match &self.search {
+            Some(search) => (format!("(reverse-i-search)`{}': ", search.text), &self.line),
+            None => (prompt.to_string(), &self.line),
+        };
+        let mut shown = format!("\r\x1b[K{}", prompt);
+        shown.extend(line.iter());
+        let after = line.len() - self.cursor;
+        if after > 0 {
+            shown.push_str(&format!("\x1b[{}D", after));
+        }
+        shown
+    }
+}
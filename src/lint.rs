@@ -0,0 +1,177 @@
+//! looking for likely bugs in a program without running it. a program that lints clean can still
+//! be wrong, and one with findings can be exactly what its author meant, these are only hints
+use crate::{Instruction, Position};
+use std::collections::HashSet;
+use std::fmt;
+
+///how sure the linter is that a finding is a mistake
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Severity {
+    ///almost always a mistake
+    Warning,
+    ///often fine, but worth a look
+    Info,
+}
+
+///something the linter noticed about a program
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Finding {
+    pub severity: Severity,
+    ///a short code for the kind of finding, like "BF001"
+    pub code: &'static str,
+    ///where in the source it was found
+    pub position: Position,
+    ///a line explaining the finding
+    pub message: &'static str,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        write!(
+            f,
+            "{}: {} {}: {}",
+            self.position, severity, self.code, self.message
+        )
+    }
+}
+
+///looks through the tokens of a program that parses, and gives back what it found in the order
+///it appears in the program
+pub fn lint(tokens: &[(Instruction, Position)]) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut found = |severity, code, position, message| {
+        findings.push(Finding {
+            severity,
+            code,
+            position,
+            message,
+        })
+    };
+    if let Some((Instruction::JumpToClose, position)) = tokens.first() {
+        found(
+            Severity::Warning,
+            "BF001",
+            *position,
+            "this loop never runs, every cell is 0 when the program starts",
+        );
+    }
+    for (index, window) in tokens.windows(2).enumerate() {
+        match (window[0], window[1]) {
+            ((Instruction::JumpToClose, position), (Instruction::JumpToOpen, _)) => found(
+                Severity::Warning,
+                "BF002",
+                position,
+                "this loop is empty, it never ends once it's entered",
+            ),
+            ((Instruction::Replace, position), (Instruction::Replace, _)) => found(
+                Severity::Warning,
+                "BF003",
+                position,
+                "this input is thrown away, the next \",\" overwrites it",
+            ),
+            _ => {}
+        }
+        if let (Instruction::JumpToClose, position) = window[0] {
+            if leaves_cell_alone(&tokens[index + 1..]) {
+                found(
+                    Severity::Warning,
+                    "BF006",
+                    position,
+                    "this loop never ends once it's entered, every time round leaves its cell the same",
+                );
+            }
+            if let Some(drift) = drift(&tokens[index + 1..]) {
+                if drift != 0 {
+                    found(
+                        Severity::Info,
+                        "BF005",
+                        position,
+                        "this loop ends somewhere else than where it started every time round",
+                    );
+                }
+            }
+        }
+    }
+    for position in unwritten_outputs(tokens) {
+        found(
+            Severity::Warning,
+            "BF004",
+            position,
+            "this prints a cell that nothing has been written to yet, so it is always 0",
+        );
+    }
+    findings.sort_by_key(|finding| (finding.position.line, finding.position.column));
+    findings
+}
+
+///how far the pointer moves in a loop body that has no loops of its own, starting right after
+///its "[". nothing for loops with loops inside, where that can't be known
+fn drift(body: &[(Instruction, Position)]) -> Option<isize> {
+    let mut drift = 0;
+    for (instruction, _) in body {
+        match instruction {
+            Instruction::MoveRight => drift += 1,
+            Instruction::MoveLeft => drift -= 1,
+            Instruction::JumpToOpen => return Some(drift),
+            Instruction::JumpToClose
+            | Instruction::ProcedureStart
+            | Instruction::ProcedureEnd
+            | Instruction::Call
+            | Instruction::Fork
+            | Instruction::Eval
+            | Instruction::Custom(_) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+///whether a loop body, starting right after its "[", is made of nothing but moves and changes
+///to cells, ends up back on the loop's cell and adds up to no change to it. a loop like that
+///finds its cell just as it was every time round, so it can never stop. loops with anything else
+///inside, input or loops of their own, might, so they're left alone
+fn leaves_cell_alone(body: &[(Instruction, Position)]) -> bool {
+    let mut offset: isize = 0;
+    let mut change: u8 = 0;
+    for (index, (instruction, _)) in body.iter().enumerate() {
+        match instruction {
+            Instruction::MoveRight => offset += 1,
+            Instruction::MoveLeft => offset -= 1,
+            Instruction::Increment if offset == 0 => change = change.wrapping_add(1),
+            Instruction::Decrement if offset == 0 => change = change.wrapping_sub(1),
+            Instruction::Increment | Instruction::Decrement => {}
+            //an empty loop is already BF002
+            Instruction::JumpToOpen => return index > 0 && offset == 0 && change == 0,
+            _ => return false,
+        }
+    }
+    false
+}
+
+///the "." instructions that print a cell nothing has been written to. this follows the program
+///from the start for as long as it can tell where the pointer is, which is up to the first loop
+fn unwritten_outputs(tokens: &[(Instruction, Position)]) -> Vec<Position> {
+    let mut outputs = vec![];
+    let mut written = HashSet::new();
+    let mut pointer: usize = 0;
+    for (instruction, position) in tokens {
+        match instruction {
+            Instruction::MoveRight => pointer += 1,
+            Instruction::MoveLeft => match pointer.checked_sub(1) {
+                Some(left) => pointer = left,
+                None => break,
+            },
+            Instruction::Increment | Instruction::Decrement | Instruction::Replace => {
+                written.insert(pointer);
+            }
+            Instruction::Output if !written.contains(&pointer) => outputs.push(*position),
+            Instruction::Output => {}
+            _ => break,
+        }
+    }
+    outputs
+}
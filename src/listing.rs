@@ -0,0 +1,310 @@
+//! a listing of a compiled program, the way objdump shows machine code: one line for every
+//! instruction, or every run of the same move, "+" or "-", which is how the machine could run
+//! them in one go. a line has the index of the instruction, how many times it ran when there's a
+//! profile, where it came from in the source and what it does, with the instructions inside
+//! loops and procedures indented. brackets show the index they jump to.
+//!
+//! a profile is a file of how many times every instruction of a program ran, which run --profile
+//! writes. it's a line "program <hash>" with the hash of the source, a line "instructions <n>",
+//! and then a line "<index> <count>" for every instruction that ran at all.
+//!
+//! a profile can also be written as folded stacks, which flamegraph.pl and inferno draw flame
+//! graphs from: a line for every nesting of loops instructions ran in, like
+//! "prog;loop@3:1;loop@5:9 1234", the loops named by where their "[" is and the number how many
+//! steps ran inside the innermost one and not in a loop inside it
+use crate::program::Program;
+use crate::{Instruction, Position};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+///how many loops deep a listing is indented, loops deeper than that are indented as much as the
+///ones at this depth. it keeps a program with thousands of loops inside each other from making
+///a listing that's mostly spaces
+const MAX_INDENT: usize = 32;
+
+///how many loops a folded stack has at most. whatever ran in loops deeper than that is counted
+///in a "deeper loops" frame on top of them, flame graphs don't show that many anyway
+const MAX_FRAMES: usize = 128;
+
+///how many times every instruction of a program ran, by its index
+#[derive(PartialEq, Debug, Clone)]
+pub struct Profile {
+    ///the hash of the source of the program, see Program::hash
+    pub hash: u64,
+    pub counts: Vec<u64>,
+}
+
+///everything that can be wrong with a profile
+#[derive(PartialEq, Debug, Clone)]
+pub enum ProfileError {
+    ///the line, counting from 1, isn't what a profile has there
+    Syntax(usize),
+    ///the profile is of a different source than the program
+    OtherProgram,
+    ///the profile has this many instructions and the program the other number, which happens
+    ///when they were optimized differently
+    WrongLength(usize, usize),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProfileError::Syntax(line) => write!(f, "line {} isn't part of a profile", line),
+            ProfileError::OtherProgram => write!(f, "the profile is of another program"),
+            ProfileError::WrongLength(profiled, program) => write!(
+                f,
+                "the profile has {} instructions and the program {}, were they both optimized \
+                with the same -O level?",
+                profiled, program
+            ),
+        }
+    }
+}
+
+impl Profile {
+    ///a profile of the program where nothing has run yet
+    pub fn new(program: &Program) -> Self {
+        Profile {
+            hash: program.hash(),
+            counts: vec![0; program.len()],
+        }
+    }
+    ///reads a profile in the format Display writes
+    pub fn parse(text: &str) -> Result<Self, ProfileError> {
+        let mut lines = text.lines().enumerate();
+        let mut header = |name: &str| {
+            let (index, line) = lines.next().ok_or(ProfileError::Syntax(1))?;
+            line.strip_prefix(name)
+                .and_then(|value| value.strip_prefix(' '))
+                .map(str::to_string)
+                .ok_or(ProfileError::Syntax(index + 1))
+        };
+        let hash = header("program")?;
+        let hash = u64::from_str_radix(&hash, 16).map_err(|_| ProfileError::Syntax(1))?;
+        let length: usize = header("instructions")?
+            .parse()
+            .map_err(|_| ProfileError::Syntax(2))?;
+        let mut counts = vec![0; length];
+        for (index, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            let (instruction, count) = line
+                .split_once(' ')
+                .and_then(|(instruction, count)| {
+                    Some((instruction.parse::<usize>().ok()?, count.parse().ok()?))
+                })
+                .filter(|(instruction, _)| *instruction < length)
+                .ok_or(ProfileError::Syntax(index + 1))?;
+            counts[instruction] = count;
+        }
+        Ok(Profile { hash, counts })
+    }
+    ///makes sure the profile is of this program, as it is after optimizing
+    pub fn check(&self, program: &Program) -> Result<(), ProfileError> {
+        if self.hash != program.hash() {
+            return Err(ProfileError::OtherProgram);
+        }
+        match self.counts.len() == program.len() {
+            true => Ok(()),
+            false => Err(ProfileError::WrongLength(self.counts.len(), program.len())),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "program {:016x}", self.hash)?;
+        writeln!(f, "instructions {}", self.counts.len())?;
+        for (index, count) in self.counts.iter().enumerate() {
+            if *count > 0 {
+                writeln!(f, "{} {}", index, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+///the profile as folded stacks, see the top of this file, with name as the frame at the bottom
+///of every stack, and no more than MAX_FRAMES loops above it. the profile should have been
+///checked against the program with Profile::check. an instruction is in the loops its brackets
+///are between, the same as enclosing_loops says while it runs, so a "]" is in its loop and a
+///"[" not yet. the optimizer only ever takes instructions out, so every one that's left still
+///has its original loop around it
+pub fn folded(program: &Program, profile: &Profile, name: &str) -> String {
+    //the stacks by the indices of their "[", outermost first, which keeps them in the order
+    //they're in the program
+    let mut stacks: BTreeMap<Vec<usize>, u64> = BTreeMap::new();
+    let mut stack = vec![];
+    for (index, count) in profile.counts.iter().enumerate() {
+        if *count > 0 {
+            let mut key = stack[..stack.len().min(MAX_FRAMES)].to_vec();
+            if stack.len() > MAX_FRAMES {
+                key.push(usize::MAX);
+            }
+            *stacks.entry(key).or_insert(0) += count;
+        }
+        //a "[" is counted before going into its loop, and a "]" before coming out of it
+        match program.instruction(index) {
+            Some(Instruction::JumpToClose) => stack.push(index),
+            Some(Instruction::JumpToOpen) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    //";" is what frames are split on, and the last " " is where the count starts
+    let name = name.replace([';', ' '], "_");
+    let mut folded = String::new();
+    for (stack, count) in stacks {
+        folded += &name;
+        for open in stack {
+            match program.positions.get(open) {
+                Some(position) => folded += &format!(";loop@{}", position),
+                None => folded += ";deeper loops",
+            }
+        }
+        folded += &format!(" {}\n", count);
+    }
+    folded
+}
+
+///one line of a listing
+struct Line {
+    index: usize,
+    count: Option<u64>,
+    source: String,
+    depth: usize,
+    text: String,
+}
+
+///the program as a listing, with how many times every instruction ran when there's a profile.
+///the profile should have been checked against the program with Profile::check
+pub fn listing(program: &Program, profile: Option<&Profile>) -> String {
+    let tokens = program.tokens();
+    let mut lines = vec![];
+    //how many loops and procedures the instruction is inside of
+    let mut depth = 0;
+    let mut index = 0;
+    while let Some(&(instruction, start)) = tokens.get(index) {
+        let length = match fused(instruction) {
+            true => tokens[index..]
+                .iter()
+                .take_while(|(next, _)| *next == instruction)
+                .count(),
+            false => 1,
+        };
+        let end = tokens[index + length - 1].1;
+        if matches!(
+            instruction,
+            Instruction::JumpToOpen | Instruction::ProcedureEnd
+        ) {
+            depth -= 1;
+        }
+        lines.push(Line {
+            index,
+            count: profile.map(|profile| profile.counts.get(index).copied().unwrap_or(0)),
+            source: span(start, end),
+            depth,
+            text: text(program, index, instruction, length),
+        });
+        if matches!(
+            instruction,
+            Instruction::JumpToClose | Instruction::ProcedureStart
+        ) {
+            depth += 1;
+        }
+        index += length;
+    }
+    let width = |column: fn(&Line) -> usize| lines.iter().map(column).max().unwrap_or(0);
+    let index_width = width(|line| line.index.to_string().len());
+    let count_width = width(|line| line.count.map_or(0, |count| count.to_string().len()));
+    let source_width = width(|line| line.source.len());
+    let mut listing = String::new();
+    for line in &lines {
+        let count = match line.count {
+            Some(count) => format!("{:>1$}  ", count, count_width),
+            None => String::new(),
+        };
+        listing += &format!(
+            "{:>index_width$}  {}{:source_width$}  {}{}\n",
+            line.index,
+            count,
+            line.source,
+            "  ".repeat(line.depth.min(MAX_INDENT)),
+            line.text,
+        );
+    }
+    listing
+}
+
+///whether a run of the instruction is one line, which it is for moves, "+" and "-"
+fn fused(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::MoveRight
+            | Instruction::MoveLeft
+            | Instruction::Increment
+            | Instruction::Decrement
+    )
+}
+
+///where an instruction or a run of them came from, like "3:7" or "3:7-3:12"
+fn span(start: Position, end: Position) -> String {
+    match start == end {
+        true => start.to_string(),
+        false => format!("{}-{}", start, end),
+    }
+}
+
+///what the instruction at the index does, repeated length times
+fn text(program: &Program, index: usize, instruction: Instruction, length: usize) -> String {
+    let name = match instruction {
+        Instruction::MoveRight => "right",
+        Instruction::MoveLeft => "left",
+        Instruction::Increment => "inc",
+        Instruction::Decrement => "dec",
+        Instruction::Output => "out",
+        Instruction::Replace => "in",
+        //"[" jumps past its "]" when the cell is 0, and "]" back past its "[" when it isn't
+        Instruction::JumpToClose => "jz",
+        Instruction::JumpToOpen => "jnz",
+        Instruction::ProcedureStart => "proc",
+        Instruction::ProcedureEnd => "ret",
+        Instruction::Call => "call",
+        Instruction::Halt => "halt",
+        Instruction::DebugDump => "dump",
+        Instruction::Store => "store",
+        Instruction::Restore => "restore",
+        Instruction::ShiftRight => "shr",
+        Instruction::ShiftLeft => "shl",
+        Instruction::Not => "not",
+        Instruction::Xor => "xor",
+        Instruction::And => "and",
+        Instruction::Or => "or",
+        Instruction::Fork => "fork",
+        Instruction::FlipBit => "flip",
+        Instruction::ReadBit => "inbit",
+        Instruction::WriteBit => "outbit",
+        Instruction::OpenFile => "fopen",
+        Instruction::ReadFile => "fread",
+        Instruction::WriteFile => "fwrite",
+        Instruction::Eval => "eval",
+        Instruction::Random => "rand",
+        Instruction::SwitchChannel => "chan",
+        Instruction::Custom(custom) => return format!("custom {}", custom),
+    };
+    let target = match instruction {
+        Instruction::JumpToClose | Instruction::JumpToOpen => program.matching_bracket(index),
+        //a procedure is skipped over when it's defined, to the ")" that ends it
+        Instruction::ProcedureStart => Some(program.ops[index].target()),
+        _ => None,
+    };
+    match target {
+        Some(target) if target > index => format!("{} -> {} (down)", name, target),
+        Some(target) => format!("{} -> {} (up)", name, target),
+        None if fused(instruction) => format!("{} {}", name, length),
+        None => name.to_string(),
+    }
+}
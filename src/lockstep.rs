@@ -0,0 +1,274 @@
+//! running a program as it's written and as the optimizer left it side by side, for "bfint debug
+//! --lockstep", to find where an optimization starts doing something else. both machines run
+//! up to their next sync point and are compared there, and the first point where they aren't
+//! at the same place with the same pointer and tape is where they diverged.
+//!
+//! the sync points are every byte written, every byte read and every "[" and "]" both programs
+//! still stop at. the passes keep the positions brackets had in the source (see optimize.rs), so
+//! a bracket is the same one in both programs when it's at the same position, while a loop
+//! dead-code took out is only in the program as written, and one copy-loop or the others made run
+//! in one go never stops at its brackets, so they're run through like anything else.
+//! what cancel rewrote between two brackets only ever has to come out the same by the next one
+use crate::events::Event;
+use crate::snapshot::{Snapshot, TapeDiff};
+use crate::{Instruction, Position, RuntimeError, TuringMachine};
+use std::collections::BTreeSet;
+use std::fmt;
+
+///what a machine was doing when it was stopped to be compared
+#[derive(PartialEq, Debug, Clone)]
+pub enum Point {
+    ///it's about to run the bracket at the position
+    Bracket(Position),
+    ///it wrote a byte, with the "." at the position
+    Output(u8, Position),
+    ///it's about to read a byte, with the "," at the position
+    Input(Position),
+    ///it got to the end
+    Halted,
+    ///it failed. any two failures count as the same, since cancel can take out a move that
+    ///would have fallen off the tape
+    Failed(String),
+    ///it ran for the step limit
+    OutOfSteps,
+}
+
+impl Point {
+    ///whether the other machine being there is the same
+    pub fn same(&self, other: &Point) -> bool {
+        match (self, other) {
+            (Point::Failed(_), Point::Failed(_)) => true,
+            _ => self == other,
+        }
+    }
+    ///whether nothing comes after it
+    pub fn ends(&self) -> bool {
+        matches!(self, Point::Halted | Point::Failed(_) | Point::OutOfSteps)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Point::Bracket(position) => write!(f, "at the bracket at {}", position),
+            Point::Output(byte, position) => write!(f, "wrote {} at {}", byte, position),
+            Point::Input(position) => write!(f, "reading at {}", position),
+            Point::Halted => write!(f, "halted"),
+            Point::Failed(e) => write!(f, "failed: {}", e),
+            Point::OutOfSteps => write!(f, "ran out of steps"),
+        }
+    }
+}
+
+///one of the machines, where it was stopped
+#[derive(PartialEq, Debug, Clone)]
+pub struct Side {
+    pub point: Point,
+    pub steps: u64,
+    pub pointer: usize,
+    ///the loops it was in, innermost first, as the positions of their "[" and "]"
+    pub loops: Vec<(Position, Position)>,
+    ///the machine's state, see TuringMachine::describe_state
+    pub state: String,
+}
+
+impl Side {
+    fn new(point: Point, tm: &TuringMachine) -> Self {
+        Side {
+            point,
+            steps: tm.steps,
+            pointer: tm.pointer(),
+            loops: tm.enclosing_loops(),
+            state: tm.describe_state(),
+        }
+    }
+}
+
+///how the run in lockstep went
+#[derive(PartialEq, Debug, Clone)]
+pub enum Lockstep {
+    ///the machines agreed at this many sync points, and ended the same way. running out of
+    ///steps counts as the same for both, the optimized one takes fewer
+    Agreed { points: usize, end: Point },
+    ///they agreed at this many sync points, and then the sides are where they didn't, with the
+    ///cells that are different, old being the program as written and new the optimized one.
+    ///they're only compared when both are at the same point
+    Diverged {
+        points: usize,
+        sides: [Side; 2],
+        changes: TapeDiff,
+    },
+}
+
+///one of the machines, how much of the input it has read, and which of its instructions are
+///sync points
+struct Runner {
+    tm: TuringMachine,
+    read: usize,
+    syncs: Vec<bool>,
+}
+
+impl Runner {
+    ///runs the machine up to its next sync point. whatever it's at now runs first, since that's
+    ///where the last one was
+    fn next(&mut self, input: &[u8]) -> Point {
+        let mut started = false;
+        loop {
+            if started && self.syncs.get(self.tm.program_counter) == Some(&true) {
+                let position = self.tm.position().expect("a sync point is an instruction");
+                return Point::Bracket(position);
+            }
+            started = true;
+            match self.tm.run_for(1) {
+                Some(Event::Output(byte)) => {
+                    let position = self.tm.last_position().expect("a '.' just ran");
+                    return Point::Output(byte, position);
+                }
+                Some(Event::InputNeeded) => {
+                    //the "," is still the next instruction, it takes the byte when it runs
+                    let byte = input.get(self.read).copied();
+                    self.read += usize::from(byte.is_some());
+                    self.tm.provide_input(byte);
+                    let position = self.tm.position().expect("a ',' is next");
+                    return Point::Input(position);
+                }
+                Some(Event::Halted) => return Point::Halted,
+                Some(Event::Error(RuntimeError::StepLimitReached(_))) => return Point::OutOfSteps,
+                Some(Event::Error(e)) => return Point::Failed(e.to_string()),
+                Some(Event::Cancelled) => {
+                    return Point::Failed("the run was cancelled".to_string())
+                }
+                Some(Event::Breakpoint(_)) | None => {}
+            }
+        }
+    }
+}
+
+///where the brackets of the program are, as lines and columns. a loop the passes said runs in
+///one go never stops at its brackets, so they aren't counted
+fn brackets(tm: &TuringMachine) -> BTreeSet<(usize, usize)> {
+    let program = &tm.program;
+    let fused: BTreeSet<usize> = program
+        .fused
+        .keys()
+        .flat_map(|&open| [open, program.ops[open].target()])
+        .collect();
+    program
+        .instructions()
+        .zip(&program.positions)
+        .enumerate()
+        .filter(|(index, (instruction, _))| {
+            matches!(
+                instruction,
+                Instruction::JumpToClose | Instruction::JumpToOpen
+            ) && !fused.contains(index)
+        })
+        .map(|(_, (_, position))| (position.line, position.column))
+        .collect()
+}
+
+///runs the program as written and the optimized one in lockstep on the input, each stopped
+///after the step limit, until they don't agree at a sync point or they've both ended. the
+///machines haven't started yet, and the second has had the passes run over it
+pub fn run(machines: [TuringMachine; 2], input: &[u8], step_limit: u64) -> Lockstep {
+    let [written, optimized] = machines.each_ref().map(brackets);
+    let shared: BTreeSet<(usize, usize)> = written.intersection(&optimized).copied().collect();
+    let mut runners = machines.map(|mut tm| {
+        tm.step_limit = Some(step_limit);
+        let syncs = tm
+            .program
+            .instructions()
+            .zip(&tm.program.positions)
+            .map(|(instruction, position)| {
+                matches!(
+                    instruction,
+                    Instruction::JumpToClose | Instruction::JumpToOpen
+                ) && shared.contains(&(position.line, position.column))
+            })
+            .collect();
+        Runner { tm, read: 0, syncs }
+    });
+    let mut points = 0;
+    loop {
+        let [first, second] = runners.each_mut().map(|runner| runner.next(input));
+        if first == Point::OutOfSteps || second == Point::OutOfSteps {
+            return Lockstep::Agreed {
+                points,
+                end: Point::OutOfSteps,
+            };
+        }
+        //copying the tape for every sync point would be most of the run
+        let changes = match runners[0].tm.tape() == runners[1].tm.tape() {
+            true => TapeDiff::default(),
+            false => Snapshot::new(&runners[0].tm).diff(&runners[1].tm),
+        };
+        let pointers_agree = runners[0].tm.pointer() == runners[1].tm.pointer();
+        //a failure leaves the tape wherever it got to, which can be a different place
+        if !first.same(&second) || (!first.ends() && (!pointers_agree || !changes.is_empty())) {
+            let [written, optimized] = &runners;
+            return Lockstep::Diverged {
+                points,
+                sides: [
+                    Side::new(first, &written.tm),
+                    Side::new(second, &optimized.tm),
+                ],
+                changes,
+            };
+        }
+        if first.ends() {
+            return Lockstep::Agreed { points, end: first };
+        }
+        points += 1;
+    }
+}
+
+///what running in lockstep found, for people to read, with the machines called by their names
+pub fn describe(lockstep: &Lockstep, names: [&str; 2]) -> String {
+    match lockstep {
+        Lockstep::Agreed { points, end } => format!(
+            "{} and {} agreed at all {} sync points and then both {}\n",
+            names[0],
+            names[1],
+            points,
+            match end {
+                Point::Failed(_) => "failed".to_string(),
+                end => end.to_string(),
+            }
+        ),
+        Lockstep::Diverged {
+            points,
+            sides,
+            changes,
+        } => {
+            let mut text = format!(
+                "{} and {} agreed at {} sync points, and then:\n",
+                names[0], names[1], points
+            );
+            for (name, side) in names.iter().zip(sides) {
+                text += &format!("{} {}, after {} steps\n", name, side.point, side.steps);
+                if !side.loops.is_empty() {
+                    let loops: Vec<String> = side
+                        .loops
+                        .iter()
+                        .map(|(open, close)| format!("{}-{}", open, close))
+                        .collect();
+                    text += &format!("  in the loops {}\n", loops.join(", "));
+                }
+                text += &format!("  {}\n", side.state);
+            }
+            if sides[0].pointer != sides[1].pointer {
+                text += &format!(
+                    "the pointer is at {} for {} and at {} for {}\n",
+                    sides[0].pointer, names[0], sides[1].pointer, names[1]
+                );
+            }
+            for change in changes.changes() {
+                text += &format!(
+                    "cell {}: {} for {}, {} for {}\n",
+                    change.index, change.old, names[0], change.new, names[1]
+                );
+            }
+            text
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! a language server, so editors that speak the language server protocol (like vs code, neovim
+//! or helix) can check programs as they're written. it speaks the protocol over a reader and a
+//! writer, which are stdin and stdout for "bfint lsp", framed the way framing.rs says.
+//!
+//! every time a document is opened or changed it's parsed, and the errors the parser finds, like
+//! brackets that don't match, are published as diagnostics, along with what lint.rs finds when
+//! it parses, codes and all. the language of a document goes by the extension of its uri. the
+//! requests it understands are initialize, shutdown, textDocument/documentSymbol, which gives
+//! the loops that aren't in any other loop with the ones inside them as their children,
+//! textDocument/hover, which says where the bracket under the cursor is matched and how deep
+//! its loop is, and textDocument/formatting, which lays the document out the way "bfint fmt"
+//! does. bfint/capabilities gives back what this build can do, the same json as "bfint
+//! capabilities --json" (see capabilities.rs), which is also in the answer to initialize, under
+//! capabilities.experimental.bfint. the client can pass `"strict": true` in its
+//! initializationOptions to have characters that aren't instructions reported, `"lint": false`
+//! to leave the linter out, and a `"width"` for formatting.
+//!
+//! the protocol counts the characters of a line in utf-16 code units, and Position counts them
+//! in chars, so everything going in or out goes through to_lsp and from_lsp, which keeps a
+//! program with emoji in its comments from getting its diagnostics in the wrong place
+use crate::capabilities::Capabilities;
+use crate::diagnostics::Diagnostic;
+use crate::format::{self, Comments};
+use crate::program::{self, ParseOptions, Program};
+use crate::{framing, lang, lint, Instruction, Position};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+///the width documents are formatted to when the client doesn't say
+const DEFAULT_WIDTH: usize = 80;
+
+///how many loops deep the document symbols go. the loops deeper than that are left out, an
+///outline that deep is no use to anyone, and json nested much deeper than this is more than a
+///lot of clients will read
+const MAX_SYMBOL_DEPTH: usize = 64;
+
+///what the protocol numbers its error codes, diagnostic severities and symbol kinds as
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32803;
+const ERROR: i64 = 1;
+const WARNING: i64 = 2;
+const INFORMATION: i64 = 3;
+const SYMBOL_NAMESPACE: i64 = 3;
+
+///an open document, and the program in it if it parses
+struct Document {
+    text: String,
+    lang: lang::Lang,
+    program: Option<Program>,
+}
+
+///a language server writing to out
+pub struct Server<W: Write> {
+    out: W,
+    documents: BTreeMap<String, Document>,
+    strict: bool,
+    lint: bool,
+    width: usize,
+    //whether the client has asked for a shutdown, after which exit ends things cleanly
+    shut_down: bool,
+    //whether the client has said to exit, after which nothing more is read
+    done: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(out: W) -> Self {
+        Server {
+            out,
+            documents: BTreeMap::new(),
+            strict: false,
+            lint: true,
+            width: DEFAULT_WIDTH,
+            shut_down: false,
+            done: false,
+        }
+    }
+    ///reads messages from input and answers them until the client says to exit or the input
+    ///ends. gives back whether the client asked for a shutdown first, which is what the
+    ///protocol wants the exit code to say
+    pub fn serve(&mut self, mut input: impl BufRead) -> io::Result<bool> {
+        while !self.done {
+            let Some(message) = framing::read_message(&mut input)? else {
+                break;
+            };
+            self.handle(&message)?;
+        }
+        Ok(self.shut_down)
+    }
+    ///answers one request, or takes in one notification
+    pub fn handle(&mut self, message: &Value) -> io::Result<()> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => Ok(self.initialize(params)),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Value::Null)
+            }
+            "exit" => {
+                self.done = true;
+                return Ok(());
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let uri = document["uri"].as_str().unwrap_or_default();
+                let text = document["text"].as_str().unwrap_or_default();
+                return self.update(uri, text.to_string());
+            }
+            "textDocument/didChange" => return self.did_change(params),
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                //the diagnostics of a closed document would otherwise stay up
+                return self.notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                );
+            }
+            "textDocument/documentSymbol" => self.document(params).map(symbols),
+            "textDocument/hover" => self
+                .document(params)
+                .map(|document| hover(document, &params["position"])),
+            "textDocument/formatting" => self.formatting(params),
+            "bfint/capabilities" => Ok(capabilities()),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        //notifications have no id and get no answer, even the ones that aren't understood
+        let Some(id) = message.get("id") else {
+            return Ok(());
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        framing::send(&mut self.out, &response)
+    }
+    fn initialize(&mut self, params: &Value) -> Value {
+        let options = &params["initializationOptions"];
+        self.strict = options["strict"].as_bool().unwrap_or(false);
+        self.lint = options["lint"].as_bool().unwrap_or(true);
+        self.width = options["width"]
+            .as_u64()
+            .map_or(DEFAULT_WIDTH, |width| width as usize);
+        json!({
+            "capabilities": {
+                //changes come as edits, not the whole document every time
+                "textDocumentSync": { "openClose": true, "change": 2 },
+                "documentSymbolProvider": true,
+                "hoverProvider": true,
+                "documentFormattingProvider": true,
+                "experimental": { "bfint": capabilities() },
+            },
+            "serverInfo": { "name": "bfint", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+    ///the open document a request is about
+    fn document(&self, params: &Value) -> Result<&Document, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        self.documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("{} isn't open", uri)))
+    }
+    fn did_change(&mut self, params: &Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(document) = self.documents.get(uri) else {
+            return Ok(());
+        };
+        let mut text = document.text.clone();
+        let changes = params["contentChanges"].as_array().cloned();
+        for change in changes.unwrap_or_default() {
+            let replacement = change["text"].as_str().unwrap_or_default();
+            match change.get("range") {
+                Some(range) => {
+                    let start = byte_offset(&text, &range["start"]);
+                    let end = byte_offset(&text, &range["end"]).max(start);
+                    text.replace_range(start..end, replacement);
+                }
+                //a change without a range is the whole document
+                None => text = replacement.to_string(),
+            }
+        }
+        self.update(uri, text)
+    }
+    ///keeps the new text of the document and publishes what's wrong with it
+    fn update(&mut self, uri: &str, text: String) -> io::Result<()> {
+        let lang = lang::Lang::from_path(uri);
+        let options = ParseOptions {
+            lang: lang.clone(),
+            strict: self.strict,
+            ..ParseOptions::default()
+        };
+        let (program, mut diagnostics) = match program::parse(&text, &options) {
+            Ok(program) => (Some(program), vec![]),
+            Err(errors) => {
+                let diagnostics = errors
+                    .iter()
+                    .map(|e| {
+                        let diagnostic = Diagnostic::from_parse_error(e, &text);
+                        parse_diagnostic(uri, &text, &diagnostic)
+                    })
+                    .collect();
+                (None, diagnostics)
+            }
+        };
+        if self.lint && program.is_some() {
+            if let Ok(tokens) = lang::tokenize(&text, &lang) {
+                diagnostics.extend(lint::lint(&tokens).iter().map(|finding| {
+                    let severity = match finding.severity {
+                        lint::Severity::Warning => WARNING,
+                        lint::Severity::Info => INFORMATION,
+                    };
+                    json!({
+                        "range": range(&text, finding.position, 1),
+                        "severity": severity,
+                        "code": finding.code,
+                        "source": "bfint",
+                        "message": finding.message,
+                    })
+                }));
+            }
+        }
+        self.documents.insert(
+            uri.to_string(),
+            Document {
+                text,
+                lang,
+                program,
+            },
+        );
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+    fn formatting(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let document = self.document(params)?;
+        let formatted = format::format(&document.text, &document.lang, self.width, Comments::Keep)
+            .map_err(|e| (REQUEST_FAILED, e.to_string()))?;
+        if formatted == document.text {
+            return Ok(json!([]));
+        }
+        //one edit replacing everything, from the start to past the last character
+        let last = document.text.rsplit('\n').next().unwrap_or_default();
+        let end = json!({
+            "line": document.text.matches('\n').count(),
+            "character": utf16_len(last),
+        });
+        Ok(json!([{
+            "range": { "start": { "line": 0, "character": 0 }, "end": end },
+            "newText": formatted,
+        }]))
+    }
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        framing::send(&mut self.out, &message)
+    }
+}
+
+///a parse error as an lsp diagnostic, with its hints as related information
+fn parse_diagnostic(uri: &str, text: &str, diagnostic: &Diagnostic) -> Value {
+    let position = diagnostic
+        .position
+        .unwrap_or(Position { line: 1, column: 1 });
+    let mut value = json!({
+        "range": range(text, position, diagnostic.width),
+        "severity": ERROR,
+        "source": "bfint",
+        "message": diagnostic.message,
+    });
+    if !diagnostic.hints.is_empty() {
+        value["relatedInformation"] = diagnostic
+            .hints
+            .iter()
+            .map(|hint| {
+                json!({
+                    "location": { "uri": uri, "range": range(text, hint.position, 1) },
+                    "message": hint.message,
+                })
+            })
+            .collect();
+    }
+    value
+}
+
+///the loops that aren't in any other loop, as document symbols with the loops inside them as
+///their children, down to MAX_SYMBOL_DEPTH
+fn symbols(document: &Document) -> Value {
+    let Some(program) = &document.program else {
+        return json!([]);
+    };
+    //the symbols of the loops that are still open, innermost last. a loop's symbol goes in its
+    //parent's children once a loop that isn't inside it comes along, or the program ends
+    let mut open: Vec<Value> = vec![];
+    let mut top = vec![];
+    let close = |open: &mut Vec<Value>, top: &mut Vec<Value>| {
+        if let Some(symbol) = open.pop() {
+            match open
+                .last_mut()
+                .and_then(|parent| parent["children"].as_array_mut())
+            {
+                Some(children) => children.push(symbol),
+                None => top.push(symbol),
+            }
+        }
+    };
+    for info in program.loops().filter(|info| info.depth < MAX_SYMBOL_DEPTH) {
+        while open.len() > info.depth {
+            close(&mut open, &mut top);
+        }
+        let start = program.positions[info.open];
+        let end = program.positions[info.close];
+        let symbol = json!({
+            "name": format!("loop@{}", start),
+            "detail": format!("{} instructions, depth {}", info.body.len(), info.depth),
+            "kind": SYMBOL_NAMESPACE,
+            "range": {
+                "start": to_lsp(&document.text, start),
+                "end": to_lsp(&document.text, after(end)),
+            },
+            "selectionRange": range(&document.text, start, 1),
+            "children": [],
+        });
+        open.push(symbol);
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut top);
+    }
+    Value::Array(top)
+}
+
+///what this build can do, for bfint/capabilities. it's written as json text, which always
+///parses back
+fn capabilities() -> Value {
+    serde_json::from_str(&Capabilities::new().to_json()).unwrap_or_default()
+}
+
+///where the bracket under the cursor is matched and how many loops its loop is in, nothing
+///when the cursor isn't on a bracket
+fn hover(document: &Document, position: &Value) -> Value {
+    let Some(program) = &document.program else {
+        return Value::Null;
+    };
+    let cursor = from_lsp(&document.text, position);
+    //in languages whose commands are words, the cursor can be anywhere in the word, which
+    //starts at the last command before it on the line
+    let words = matches!(
+        document.lang,
+        lang::Lang::Ook | lang::Lang::Spoon | lang::Lang::Mapped(_)
+    );
+    let index = (0..program.len()).rev().find(|&index| {
+        let at = program.positions[index];
+        match words {
+            true => at.line == cursor.line && at.column <= cursor.column,
+            false => at == cursor,
+        }
+    });
+    let Some(index) = index else {
+        return Value::Null;
+    };
+    let Some(other) = program.matching_bracket(index) else {
+        return Value::Null;
+    };
+    let (open, close) = (index.min(other), index.max(other));
+    let Some(info) = program.loops().find(|info| info.open == open) else {
+        return Value::Null;
+    };
+    let message = match program.instruction(index) {
+        Some(Instruction::JumpToClose) => "opens",
+        _ => "closes",
+    };
+    let here = program.positions[index];
+    let text = format!(
+        "this bracket {} the loop from {} to {}, which is {} loop{} deep",
+        message,
+        program.positions[open],
+        program.positions[close],
+        info.depth + 1,
+        match info.depth {
+            0 => "",
+            _ => "s",
+        },
+    );
+    json!({
+        "contents": { "kind": "plaintext", "value": text },
+        "range": range(&document.text, here, 1),
+    })
+}
+
+///the position after the character at the position, on the same line
+fn after(position: Position) -> Position {
+    Position {
+        line: position.line,
+        column: position.column + 1,
+    }
+}
+
+///the range of that many characters from the position
+fn range(text: &str, position: Position, width: usize) -> Value {
+    let end = Position {
+        line: position.line,
+        column: position.column + width.max(1),
+    };
+    json!({ "start": to_lsp(text, position), "end": to_lsp(text, end) })
+}
+
+///the line of the text with that index, counting from 0, nothing past the last line
+fn line(text: &str, index: usize) -> Option<&str> {
+    text.split('\n')
+        .nth(index)
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+///how many utf-16 code units the text is
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+///the position as the protocol counts it: lines from 0, and characters from 0 in utf-16 code
+///units. a column past the end of its line stops at the end
+fn to_lsp(text: &str, position: Position) -> Value {
+    let line_index = position.line.saturating_sub(1);
+    let before = line(text, line_index).map_or(0, |line| {
+        line.chars()
+            .take(position.column.saturating_sub(1))
+            .map(char::len_utf16)
+            .sum()
+    });
+    json!({ "line": line_index, "character": before })
+}
+
+///the Position of a position the protocol sent. a character in the middle of a character
+///that takes two code units is taken to be that character
+fn from_lsp(text: &str, position: &Value) -> Position {
+    let line_index = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let mut units = 0;
+    let mut column = 1;
+    for c in line(text, line_index).unwrap_or("").chars() {
+        units += c.len_utf16();
+        if units > character {
+            break;
+        }
+        column += 1;
+    }
+    Position {
+        line: line_index + 1,
+        column,
+    }
+}
+
+///the byte in the text a position the protocol sent is at, for applying edits. past the end
+///of a line is the end of the line, and past the last line is the end of the text
+fn byte_offset(text: &str, position: &Value) -> usize {
+    let line_index = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let mut start = 0;
+    for _ in 0..line_index {
+        match text[start..].find('\n') {
+            Some(newline) => start += newline + 1,
+            None => return text.len(),
+        }
+    }
+    let line = &text[start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut units = 0;
+    for (offset, c) in line.char_indices() {
+        if units >= character {
+            return start + offset;
+        }
+        units += c.len_utf16();
+    }
+    start + line.len()
+}
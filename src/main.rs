@@ -1,214 +1,5544 @@
-use std::io::Read;
-
-///Every possible instruction in the brainfuck language
-#[derive(PartialEq, Debug)]
-enum Instruction {
-    MoveRight,
-    MoveLeft,
-    Increment,
-    Decrement,
-    Output,
-    Replace,
-    JumpToClose,
-    JumpToOpen,
-}
-///object oriented kind of struct that represents the turing machine that runs the bf programs
-struct TuringMachine {
-    //the memory of the little turing machine, or "tape". 30000 cells in size. each cell being one
-    //byte in size
-    tape: [u8; 30000],
-    //the pointer that indicates where the turing machines head is on the tape
-    pointer: usize,
-    //the program itself, represented as a long list of instructions
-    program: Vec<Instruction>,
-    //the program counter, that indicates which instruction in the program we are currently
-    //executing
-    program_counter: usize,
-}
-
-impl TuringMachine {
-    ///create a new turing machine, sets every cell in memory to be 0 initially, sets the pointer
-    ///and program pointer to 0, and turns bf programs into lists of instructions.
-    fn new(program: &str) -> Self {
-        TuringMachine {
-            tape: [0; 30000],
-            pointer: 0,
-            program: TuringMachine::parse(program),
-            program_counter: 0,
-        }
-    }
-    ///executes the "MoveRight" instruction on the turing machine, so it just moves the head, or
-    ///pointer, one value to the right on the tape
-    fn move_right(&mut self) {
-        self.pointer += 1;
-        self.program_counter += 1;
-    }
-    ///executes the "MoveLeft" instruction on the turing machine, so it just moves the head, or
-    ///pointer, one value to the left on the tape
-    fn move_left(&mut self) {
-        self.pointer -= 1;
-        self.program_counter += 1;
-    }
-    ///executes the "Increment" instruction on the turing machine, does nothing more than Increment
-    ///the value of the current cell being pointed at by the pointer or head
-    fn increment(&mut self) {
-        self.tape[self.pointer] = self.tape[self.pointer].wrapping_add(1);
-        self.program_counter += 1;
-    }
-    ///executes the "Decrement" instruction on the turing machine, does nothing more than Decrement
-    ///the value of the current cell being pointed at by the pointer or head
-    fn decrement(&mut self) {
-        self.tape[self.pointer] = self.tape[self.pointer].wrapping_sub(1);
-        self.program_counter += 1;
-    }
-    ///executes the "Write" instruction on the turing machine, prints the value of the current cell
-    ///being pointed at by the pointer
-    fn write(&mut self) {
-        print!("{}", self.tape[self.pointer] as char);
-        self.program_counter += 1;
-    }
-
-    fn replace(&mut self) {
-        let mut input: [u8; 1] = [0; 1];
-        std::io::stdin().read_exact(&mut input).unwrap();
-        self.tape[self.pointer] = input[0];
-        self.program_counter += 1;
-    }
-    ///gets the maching closing bracket for the opening bracket indicated by "bracket_to_match".
-    fn get_matching_closing_bracket(&self, bracket_to_match: usize) -> usize {
-        let mut stack: Vec<Instruction> = vec![];
-        let mut return_token = 0;
-        for token in (bracket_to_match)..self.program.len() {
-            match self.program[token] {
-                Instruction::JumpToOpen => {
-                    if stack.is_empty() {
-                        return_token = token;
-                        break;
-                    } else {
-                        stack.pop();
+use bfint::cache::{Cache, CacheError};
+use bfint::capabilities::Capabilities;
+use bfint::channels::{ChannelSpec, Source};
+use bfint::config::{CellWidth, ConfigError, InterpreterConfig};
+use bfint::control::{ControlHandle, RunOutcome};
+use bfint::events::Event;
+use bfint::fuel::Fuel;
+use bfint::io::{self, InputByte, OutputByte, RandomInput, Tee};
+use bfint::observe::{Observer, StepCtx};
+use bfint::optimize::{Level, Pipeline};
+use bfint::progress::{self, Progress, StateLog};
+use bfint::provenance::{self, Provenance};
+use bfint::shared::lock;
+use bfint::warnings::Detector;
+use bfint::watch::OutputWatch;
+use bfint::{
+    analyze, annotations, batch, bench, checkpoint, chrome, crosscheck, debugger, diagnostics,
+    examples, fixtures, fork, format, generate, heatmap, joust, lang, lineedit, lint, listing,
+    lockstep, mi, minify, obfuscate, program, random, reduce, report, sampler, snippet, spec,
+    tracediff, transcript, transpile, EofBehavior, Instruction, ParseError, RuntimeError,
+    TuringMachine,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Read, Write};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///the source text of the program in the file, or stops with a failing exit code if it can't be
+///read, see program::read_source
+fn read_program(path: &str) -> String {
+    //"-" is the program on stdin
+    if path == "-" {
+        let mut bytes = vec![];
+        if let Err(e) = std::io::stdin().read_to_end(&mut bytes) {
+            report::error(&format!("could not read the program from stdin: {}", e));
+            std::process::exit(1);
+        }
+        return program::decode_source(&bytes);
+    }
+    program::read_source(path).unwrap_or_else(|e| {
+        report::error(&e.to_string());
+        std::process::exit(1);
+    })
+}
+
+///prints how to use the program and stops with a failing exit code
+fn usage() -> ! {
+    eprintln!("usage: bfint [run] [options] <program.bf> [<more.bf>... --shared-tape]");
+    eprintln!("       bfint fmt [--width <n>] [--comments keep|strip] [--check] <program.bf>");
+    eprintln!("       bfint minify [--verify] [--input <file>] <program.bf>");
+    eprintln!(
+        "       bfint reduce [--check <command>] [--configs <configs.toml>] [--input <file>]"
+    );
+    eprintln!(
+        "                    [--max-steps <n>] [--timeout <secs>] [-o <out.bf>] <program.bf>"
+    );
+    eprintln!("       bfint obfuscate [--seed <n>] [--factor <x>] [--verify] [--input <file>]");
+    eprintln!("                       [-o <out.bf>] <program.bf>");
+    eprintln!("       bfint equiv [--inputs <dir>] [--random <n>] [--seed <n>] [--max-steps <n>]");
+    eprintln!("                   [--save <file>] <a.bf> <b.bf>");
+    eprintln!("       bfint tracediff [--input <file>] [--max-steps <n>] [--debug] <a.bf> <b.bf>");
+    eprintln!(
+        "       bfint pipeline [--buffer <bytes>] [--max-steps <n>] <first.bf> <second.bf>..."
+    );
+    eprintln!("       bfint self-test [-O0|-O1|-O2] [--tiered]");
+    eprintln!("       bfint examples --list | --dump <name>");
+    eprintln!("       bfint joust <left.bfjoust> <right.bfjoust>");
+    eprintln!("       bfint test [--filter <text>] [--format text|tap|json] [--max-steps <n>]");
+    eprintln!("                  [--timeout <time>] [--update-baselines] <dir> | <program.bf>");
+    eprintln!(
+        "       bfint verify [--lang <name>] [--jobs <n>] <program.bf> <spec.toml|spec.json>"
+    );
+    eprintln!("       bfint crosscheck --configs <configs.toml> [--max-steps <n>] [--json <file>]");
+    eprintln!("                  [--dumps <dir>] <dir>");
+    eprintln!("       bfint bench [--runs <n>] [--warmup <n>] [-O0|-O1|-O2 | --compare <levels>]");
+    eprintln!("                   [--input <file>] [--max-steps <n>] [--json] <program.bf>");
+    eprintln!("       bfint debug [--input <file>] [--script <file>] [--history <n>] [<settings>]");
+    eprintln!("                   [--diff-at-breakpoints] [--break-on-output <text>] [--mi]");
+    eprintln!("                   [--goto-step <n>] <program.bf>");
+    eprintln!("       bfint debug --lockstep [-O0|-O1|-O2 | --passes <a,b>] [--input <file>]");
+    eprintln!("                   [<settings>] <program.bf>");
+    eprintln!("       bfint repl [--history-file <file>] [--no-history] [<settings>]");
+    eprintln!("       bfint inspect [--lang <name>] [-O0|-O1|-O2] <dump.bfdump>");
+    eprintln!("       bfint provenance <file>");
+    eprintln!("       bfint cache [--cache-dir <dir>] [--list | --clear]");
+    eprintln!("       bfint capabilities [--json]");
+    eprintln!("       bfint check [--lint] [--deny-warnings] <program.bf>");
+    eprintln!("       bfint disasm [--listing] [-O0|-O1|-O2] [--profile <file>] <program.bf>");
+    eprintln!(
+        "       bfint analyze [--cfg <out.dot>] [--metrics [--json]] [--dead-writes] <program.bf>"
+    );
+    eprintln!(
+        "       bfint generate [--strategy naive|delta|loops] --text <text> | --input-file <file>"
+    );
+    eprintln!();
+    eprintln!("options:");
+    eprintln!("  -v, -vv, -vvv, -q          show info, debug messages and every step, or only");
+    eprintln!("                             errors. what's asked for, like --stats, always shows");
+    eprintln!("  --message-format text|json how bfint's own messages on stderr look, json is one");
+    eprintln!(
+        "                             object a line, like {{\"kind\":\"warn\",\"message\":..}}"
+    );
+    eprintln!(
+        "  --config <profile.toml>    take the settings below from a profile, with their names"
+    );
+    eprintln!(
+        "                             as keys, like max-steps = 1000. options given along with"
+    );
+    eprintln!("                             it win over the profile. these are the <settings>");
+    eprintln!("                             debug and repl take too");
+    eprintln!("  --channel <n>=<source>     give bf-channels' input channel n something to read:");
+    eprintln!("                             file:<path>, str:<text> or stdin, with ,eof=max or");
+    eprintln!("                             another eof behavior after it for one of its own");
+    eprintln!("  --color always|never|auto  color errors, traces and the debugger (default: auto,");
+    eprintln!("                             which colors them on a terminal)");
+    eprintln!("  --eof zero|max|unchanged   what \",\" does at the end of input (default: zero)");
+    eprintln!("  --epipe ok|error           what to exit with when stdout is closed before the");
+    eprintln!("                             output is all written, like with | head: ok is 0 and");
+    eprintln!("                             error, the default, is 141 like SIGPIPE would give");
+    eprintln!("  --example <name>           run one of the programs built into bfint instead of a");
+    eprintln!("                             file, see bfint examples --list");
+    eprintln!("  --input <file>             read the program's input from the file, not stdin");
+    eprintln!("  --input random:<seed>[:<n>]");
+    eprintln!(
+        "                             read random bytes made from the seed, or from a new one"
+    );
+    eprintln!("                             every run with random:auto. the same seed gives the");
+    eprintln!("                             same bytes, n of them if it's given, or with no end");
+    eprintln!("  --output <file>            write the program's output to the file, with - for");
+    eprintln!("                             stdout. given more than once, the output goes to all");
+    eprintln!("                             of them (default: -)");
+    eprintln!(
+        "  --lang <name>              the language the program is written in: bf, ook, spoon,"
+    );
+    eprintln!("                             pbrain, ebf1, brainfork, boolfuck, bf-file, bf-eval,");
+    eprintln!(
+        "                             bf-rand, bf-halt or bf-channels (default: from the
+                             extension, or guessed from the program when it has
+                             none, or is \"-\" for stdin. -v says which one was picked)"
+    );
+    eprintln!(
+        "  --cell-width 8|big         bytes that wrap around, or numbers with no upper limit"
+    );
+    eprintln!("                             that \".\" and \",\" see mod 256 (default: 8)");
+    eprintln!("  --cell-underflow saturate|error");
+    eprintln!(
+        "                             what \"-\" on 0 does with big cells (default: saturate)"
+    );
+    eprintln!("  --cell-signedness signed|unsigned");
+    eprintln!("                             whether cells are shown from -128 to 127 or from 0 to");
+    eprintln!("                             255 in dumps and errors (default: unsigned)");
+    eprintln!(
+        "  --ext-halt                 let \"@\" stop the program, the same as --lang bf-halt"
+    );
+    eprintln!(
+        "  --ext-channels             let \"'\" make \",\" read from the channel numbered by the"
+    );
+    eprintln!("                             current cell, see --channel. the same as --lang");
+    eprintln!("                             bf-channels");
+    eprintln!(
+        "  --max-call-depth <n>       how deep pbrain procedure calls and bf-eval's \"%\" may"
+    );
+    eprintln!("                             go together (default: 1024)");
+    eprintln!("  --max-steps <n>            stop the program after running this many instructions");
+    eprintln!("                             in all, counting every stage, fork and \"%\"");
+    eprintln!("  --max-output <bytes>       stop the program when it writes more than this much");
+    eprintln!(
+        "  --max-loop-iterations <n>  stop the program when a loop goes around more than n times"
+    );
+    eprintln!("                             in a row, and say which loop it was");
+    eprintln!("  --max-nesting <n>          how deep loops and procedures may be nested in the");
+    eprintln!("                             program (default: 1000000)");
+    eprintln!(
+        "  --detect-cycles <n>        look at the machine's state every n steps, and stop the"
+    );
+    eprintln!("                             program once it's sure the state repeats without");
+    eprintln!("                             anything read or written in between");
+    eprintln!(
+        "  --runtime-warnings         warn, without stopping, about a cell going back and forth"
+    );
+    eprintln!("                             between two values, the pointer staying on the same");
+    eprintln!("                             two cells, or the output repeating the same block.");
+    eprintln!(
+        "                             looks as often as --detect-cycles, or every 10007 steps"
+    );
+    eprintln!("  --no-warn <detectors>      leave out some of them: oscillation, ping-pong or");
+    eprintln!("                             repeated-output, separated by commas");
+    eprintln!("  --sandbox                  on linux, lock bfint down before running the program:");
+    eprintln!("                             only the system calls a run needs, and at most 30s of");
+    eprintln!("                             cpu time, 1 GiB of memory and 64 MiB files. needs the");
+    eprintln!("                             sandbox feature");
+    eprintln!(
+        "  --no-input                 refuse to run a program that reads input, or uses \"?\""
+    );
+    eprintln!(
+        "                             without --seed, so its output only depends on its source"
+    );
+    eprintln!(
+        "  --check-leaks              once the program is done, warn about every cell it left"
+    );
+    eprintln!("                             holding something other than 0");
+    eprintln!(
+        "  --leak-allow <cells>       cells that are meant to hold something at the end, like"
+    );
+    eprintln!("                             0..4 or 2,7..=9, which --check-leaks leaves out");
+    eprintln!("  --strict                   fail the run when --check-leaks finds anything");
+    eprintln!("  --watch                    run the program again every time its file changes,");
+    eprintln!("                             stopping the run before if it's still going");
+    eprintln!("  --watch-clear              clear the terminal before every run with --watch");
+    eprintln!("  --shared-tape              run every program given, one after the other, on the");
+    eprintln!("                             same tape. --max-steps counts all of them together");
+    eprintln!(
+        "  --stage-max-steps <n>      stop any one of the programs after this many instructions"
+    );
+    eprintln!("  --io-command <command>     run the command alongside the program, with what the");
+    eprintln!("                             program writes as its input and what it writes as the");
+    eprintln!("                             program's input");
+    eprintln!(
+        "  --input-nonblocking        never wait for input, \",\" gives the sentinel when no"
+    );
+    eprintln!("                             byte has come in yet. the end of input is still --eof");
+    eprintln!("  --input-sentinel <byte>    what \",\" gives with no input yet (default: 0)");
+    eprintln!(
+        "  --input-prompt <text>      what to show while \",\" waits for input from a terminal"
+    );
+    eprintln!("                             (default: bf: waiting for input (1 byte)...)");
+    eprintln!("  --no-input-prompt          don't show anything while \",\" waits for input");
+    eprintln!("  --output-rate <n>          write at most n bytes of output a second");
+    eprintln!("  --progress                 show how many steps have run so far while running");
+    eprintln!("  --log-state-every <n>      write a line about the run every so long (like 10s or");
+    eprintln!("                             5m) or every n steps, and one when it ends");
+    eprintln!("  --log-state-file <path>    add the --log-state-every lines to a file, not stderr");
+    eprintln!(
+        "  --delay <time>             sleep this long (like 50ms or 1s) between instructions"
+    );
+    eprintln!("  --delay-every <n>          sleep after every n instructions instead of every one");
+    eprintln!(
+        "  --eval-depth <n>           how deep bf-eval's \"%\" may nest on its own (default: 64)"
+    );
+    eprintln!("  --seed <n>                 make bf-rand's \"?\" give the same bytes every run");
+    eprintln!(
+        "  --record <file>            write every byte read and written to a json transcript"
+    );
+    eprintln!(
+        "  --transcript <file>        write what was read and written to a file as text, with"
+    );
+    eprintln!("                             the input in «» and the step each line started on");
+    eprintln!(
+        "  --history <n>              keep the last n steps, and show them if the program fails"
+    );
+    eprintln!(
+        "  --on-error <script>        when the program fails, run the debugger commands in the"
+    );
+    eprintln!("                             script on it, to see where it went wrong");
+    eprintln!(
+        "  --break-on-output <text>   stop with an error the moment the program writes the text,"
+    );
+    eprintln!("                             which can have escapes like \\x1b and \\n");
+    eprintln!(
+        "  -O0, -O1, -O2              how much to optimize the program before running it: not at"
+    );
+    eprintln!(
+        "                             all, writing moves, + and - the shortest way and running"
+    );
+    eprintln!(
+        "                             them in one go, or also taking out loops that can never run"
+    );
+    eprintln!("                             and running loops that clear, copy or scan in one go");
+    eprintln!("  --optimize                 the same as -O2");
+    eprintln!(
+        "  --passes <a,b,c>           run exactly these optimizer passes: cancel, dead-code,"
+    );
+    eprintln!("                             clear-loop, copy-loop, scan, rle, offset-fusion");
+    eprintln!(
+        "  --print-ir-after <pass>    show the program on stderr every time the pass has run"
+    );
+    eprintln!(
+        "  --cache-dir <dir>          keep programs compiled and optimized in the directory,"
+    );
+    eprintln!("                             so running them again skips that. there's no cache");
+    eprintln!("                             without it. only a run of one program without");
+    eprintln!("                             --print-ir-after uses it");
+    eprintln!("  --no-cache                 compile the program without the cache, even with");
+    eprintln!("                             --cache-dir");
+    eprintln!(
+        "  --stats                    say how many instructions there were and steps ran, at the end"
+    );
+    eprintln!(
+        "  --tiered                   run loops that only add in one go once they've gone around"
+    );
+    eprintln!("                             a hundred times, --stats shows which ones did");
+    eprintln!(
+        "  --track-writers            keep track of the step and instruction that last wrote"
+    );
+    eprintln!(
+        "                             every cell, and say who wrote the current cell when the"
+    );
+    eprintln!("                             program fails. doesn't go with --tiered or --target");
+    eprintln!(
+        "  --batch <dir>              run the program on every file in the directory as its input,"
+    );
+    eprintln!("                             each on a machine of its own, writing what it prints");
+    eprintln!("                             for name.in to name.out in --out-dir, and a table of");
+    eprintln!(
+        "                             how every run went. --jobs of them run at once (default:"
+    );
+    eprintln!("                             one for every cpu)");
+    eprintln!("  --out-dir <dir>            where --batch writes the output");
+    eprintln!("  --jobs <n>                 how many inputs --batch runs at once");
+    eprintln!(
+        "  --replay <file>            feed the program a transcript's input and check that its"
+    );
+    eprintln!("                             output still matches");
+    eprintln!(
+        "  --profile <file>           write how many times every instruction ran to a file, for"
+    );
+    eprintln!("                             disasm --listing --profile");
+    eprintln!(
+        "  --profile-folded <file>    write how many steps ran in every nesting of loops to a file,"
+    );
+    eprintln!("                             as folded stacks for flamegraph.pl or inferno");
+    eprintln!(
+        "  --heatmap-image <file>     draw which cells were written when to a .ppm file, or a .png"
+    );
+    eprintln!("                             with the png feature");
+    eprintln!(
+        "  --heatmap-every <n>        make every column of the heat map n steps (default: as"
+    );
+    eprintln!("                             many as fit 1024 columns)");
+    eprintln!(
+        "  --heatmap-cells <a..b>     the cells the heat map shows (default: the ones written)"
+    );
+    eprintln!(
+        "  --profile-sample <hz>      look at which instruction is running that many times a second,"
+    );
+    eprintln!("                             and say which places and loops it was most often at.");
+    eprintln!("                             --profile-folded is made from the samples then");
+    eprintln!(
+        "  --profile-sample-steps <n> the same, every n steps, which samples the same every run"
+    );
+    eprintln!(
+        "  --trace-chrome <file>      write a trace of the loops the program went through and its"
+    );
+    eprintln!("                             input and output, for chrome://tracing or perfetto");
+    eprintln!("  --offline                  refuse to fetch programs given as http urls");
+    eprintln!(
+        "  --target python            print the program translated to python instead of running it"
+    );
+    eprintln!("  --stamp                    end what --target prints with a comment saying what");
+    eprintln!("                             made it, see provenance below");
+    eprintln!();
+    eprintln!("fmt lays the program out with its loops indented and prints it. --width is where");
+    eprintln!("lines get wrapped (default: 80), --comments says what happens to the text that");
+    eprintln!("isn't commands (default: keep), and --check prints nothing but fails when the");
+    eprintln!("file isn't formatted already");
+    eprintln!();
+    eprintln!("minify prints the smallest program that does the same thing. --verify runs both");
+    eprintln!("programs on the contents of --input (default: no input) and fails if their output");
+    eprintln!("differs");
+    eprintln!();
+    eprintln!("reduce takes as much out of a program that shows a bug as it can while it still");
+    eprintln!("shows it, and prints what's left, or writes it to -o. the bug is shown when");
+    eprintln!("--check exits the same way it does on the program, with {{}} in it replaced by a");
+    eprintln!("file holding the smaller one, and it's killed after --timeout (default: 10s).");
+    eprintln!("without --check it's shown when a config in --configs (default: plain, -O2 and");
+    eprintln!("tiered) prints something else than the reference the way crosscheck finds, with");
+    eprintln!("--input as the input and every run stopped after --max-steps (default: 10000000)");
+    eprintln!();
+    eprintln!("obfuscate writes the program about --factor times as long (default: 3) by putting");
+    eprintln!("things that do nothing between its commands, picked by --seed (default: 0). it's");
+    eprintln!("printed, or written to -o. --verify runs both the way minify's does");
+    eprintln!();
+    eprintln!("check makes sure the program parses without running it. --lint also points out");
+    eprintln!("likely bugs, and --deny-warnings makes those warnings fail the check");
+    eprintln!();
+    eprintln!("analyze looks at the shape of the program. --cfg writes its control flow graph");
+    eprintln!("to a file for graphviz, and --metrics prints numbers about it, as a table or as");
+    eprintln!("json with --json. --dead-writes guesses which cells are written and then written");
+    eprintln!("over before anything reads them, in the parts of the program it can follow");
+    eprintln!();
+    eprintln!("disasm prints the instructions of the program after optimizing it, one a line.");
+    eprintln!("--listing lays them out like objdump does, with runs of moves, + and - on one");
+    eprintln!("line, where every bracket jumps to, and loops indented. --profile adds how many");
+    eprintln!("times every instruction ran, from a file run --profile wrote at the same level");
+    eprintln!();
+    eprintln!("equiv runs both programs on no input, every file in --inputs and --random inputs");
+    eprintln!("(default: 0) and fails on the first input they treat differently, which is saved");
+    eprintln!("to --save (default: equiv-input.bin)");
+    eprintln!();
+    eprintln!("tracediff runs both programs on --input (default: none) a read or a write at a");
+    eprintln!("time, and fails at the first thing one does that the other doesn't, showing where");
+    eprintln!("each of them was. every run stops after --max-steps (default: 10000000). --debug");
+    eprintln!("then debugs both from there: a command goes to both, or \"a <command>\" and");
+    eprintln!("\"b <command>\" to only one");
+    eprintln!();
+    eprintln!("pipeline runs the programs side by side, with what each one writes going into the");
+    eprintln!("next one as its input, up to --buffer bytes (default: 4096) at a time. the first");
+    eprintln!("one reads what bfint is given and what the last one writes is printed. -v shows");
+    eprintln!("how much each of them read and wrote. --max-steps stops the pipeline once its");
+    eprintln!("programs have run that many instructions between them");
+    eprintln!();
+    eprintln!("self-test runs a few programs built into bfint, to check that this build works.");
+    eprintln!("-O1, -O2 and --tiered run them optimized and tiered the way run does");
+    eprintln!();
+    eprintln!("examples --list shows the example programs built into bfint, with how long they");
+    eprintln!("take, and --dump prints one's source. run one with bfint run --example <name>");
+    eprintln!();
+    eprintln!("bench runs the program --runs times (default: 5) after --warmup runs that don't");
+    eprintln!("count (default: 1), with its output thrown away, and shows how long every run");
+    eprintln!("took and the fastest, median and slowest. --compare -O0,-O2 times it at every");
+    eprintln!("level one after the other, with how much faster each is than the first. --json");
+    eprintln!("prints the results as json, to keep track of them over time");
+    eprintln!();
+    eprintln!("debug runs the program under a debugger that takes the commands break <line:col>");
+    eprintln!("[if <condition>], run, step [n], print [value], dump, history, snapshot, diff and");
+    eprintln!("quit, typed at its prompt or read from --script. the program reads its input from");
+    eprintln!("--input (default: none), history shows the last --history steps, diff shows the");
+    eprintln!("cells that changed since the last snapshot, and --diff-at-breakpoints shows the");
+    eprintln!("cells that changed since the last breakpoint every time one is hit. cells can be");
+    eprintln!("named in the program's comments with \";@3 counter\" or \";@ptr counter\", and are");
+    eprintln!("then shown with their names and can be called by them. goto <n> goes to step n,");
+    eprintln!("backwards too, by running again from the checkpoints the debugger makes as it");
+    eprintln!("goes, and --goto-step starts the session there. with --track-writers, print and");
+    eprintln!("every stop also say which step and instruction last wrote the cell. eval <code>");
+    eprintln!("runs a bit of code on the tape as it is, for at most 100000 steps, and shows what");
+    eprintln!("it wrote and the cells it changed, then puts everything back. eval! <code> keeps");
+    eprintln!("what it did to the tape and the pointer");
+    eprintln!();
+    eprintln!("debug --lockstep runs the program as written and optimized at -O2, or the level");
+    eprintln!("or --passes given, side by side, and compares them at every byte read or written");
+    eprintln!("and every bracket both still have. at the first place their pointer or tape");
+    eprintln!("differ it shows both and the cells that differ, and debugs both from there like");
+    eprintln!("tracediff --debug does. every run stops after --max-steps (default: 10000000)");
+    eprintln!();
+    eprintln!(
+        "repl runs brainfuck a line at a time on a tape that is kept from line to line. at a"
+    );
+    eprintln!("terminal, lines can be edited, up and down and ctrl+r go through the lines typed");
+    eprintln!("before, and tab finishes :commands. the lines are kept in --history-file (default:");
+    eprintln!("$XDG_DATA_HOME/bfint/repl_history) for next time, unless it's --no-history");
+    eprintln!();
+    eprintln!("test runs every name.bf in the directory that has a name.out next to it, with");
+    eprintln!("name.in as its input if there is one, and fails if any output doesn't match.");
+    eprintln!(
+        "every test stops after --max-steps (default: 10000000) and --timeout (default: 10s)"
+    );
+    eprintln!("and fails if it took more than a tolerance off the step count in steps.toml.");
+    eprintln!("--update-baselines writes the step counts of this run to steps.toml instead");
+    eprintln!();
+    eprintln!("verify runs one program on every case in a spec, a toml or json file of inputs and");
+    eprintln!("the output each should give, on --jobs threads (default: one a core), and fails");
+    eprintln!("if any output doesn't match. see src/spec.rs for what a spec looks like");
+    eprintln!();
+    eprintln!("crosscheck runs every program in the directory, with name.in as its input, under");
+    eprintln!("every config in --configs and compares each one's output with the reference");
+    eprintln!("config's. the table of results is printed and written to --json (default:");
+    eprintln!("crosscheck.json), and the state of every run that differs, right before the byte");
+    eprintln!("that's different, goes in --dumps (default: crosscheck-dumps) for inspect. see");
+    eprintln!("src/crosscheck.rs for what the configs look like");
+    eprintln!();
+    eprintln!("provenance shows what made a file: the version of bfint, the engine, -O level,");
+    eprintln!("cells, program hash, input, seed, start time and host of the run. it's in the");
+    eprintln!("core dumps and checkpoints of every run, in --stats, and at the end of --target's");
+    eprintln!("output with --stamp");
+    eprintln!();
+    eprintln!("cache lists the compiled programs in --cache-dir with their sizes in bytes.");
+    eprintln!("--clear takes them all out");
+    eprintln!();
+    eprintln!("capabilities shows what this bfint can do: its languages, cell widths, engines,");
+    eprintln!("passes, every setting with what it takes and its default, and the features it");
+    eprintln!("was built with. --json prints it as json, see src/capabilities.rs");
+    #[cfg(feature = "tui")]
+    eprintln!("tui shows the program running full screen, a step at a time or as fast as you like");
+    #[cfg(feature = "dap")]
+    eprintln!("dap is a debug adapter on stdin and stdout, for editors to start");
+    #[cfg(feature = "lsp")]
+    eprintln!("lsp is a language server on stdin and stdout, for editors to start");
+    #[cfg(feature = "server")]
+    eprintln!("serve [--host <address>] [--port <n>] runs a playground page and a json api on");
+    #[cfg(feature = "server")]
+    eprintln!("http (default: 127.0.0.1:8080), until ctrl+c");
+    #[cfg(feature = "rpc")]
+    eprintln!("rpc answers json-rpc 2.0 requests on stdin and stdout, one a line");
+    eprintln!();
+    eprintln!("generate prints a program that prints the text (default strategy: loops)");
+    eprintln!();
+    eprintln!("a run exits with 0 when the program halts, 1 when it goes wrong while running,");
+    eprintln!("2 for options that don't make sense, 3 when it doesn't parse, 4 when it runs");
+    eprintln!("into a limit like --max-steps, 130 when it's interrupted and 141 when stdout is");
+    eprintln!("closed, see --epipe. stdout only ever has what the program wrote");
+    std::process::exit(2);
+}
+
+///what happens to the bytes a program reads and writes, besides them going to the terminal
+enum Transcribing {
+    Off,
+    Record(transcript::Transcript),
+    ///the input comes from the transcript instead of the terminal
+    Replay(transcript::Replay),
+}
+
+///the step the program is at when it writes a byte, and the place of the "." that wrote it, for
+///what looks at its output as it goes and only gets the bytes
+type Now = Arc<Mutex<(u64, Option<bfint::Position>)>>;
+
+///the program's output going into the transcript being recorded, or checked against the one being
+///replayed
+struct Transcribed {
+    transcribing: Arc<Mutex<Transcribing>>,
+    now: Now,
+}
+
+impl OutputByte for Transcribed {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        let (step, position) = *lock(&self.now);
+        match &mut *lock(&self.transcribing) {
+            Transcribing::Off => Ok(()),
+            Transcribing::Record(transcript) => {
+                transcript
+                    .entries
+                    .push(transcript::Entry::Write { step, byte });
+                Ok(())
+            }
+            Transcribing::Replay(replay) => replay
+                .write(step, byte)
+                .map_err(|divergence| diverged(position, divergence)),
+        }
+    }
+}
+
+///stops the run the moment the output matches --break-on-output
+struct Watching(Arc<Mutex<OutputWatch>>);
+
+impl OutputByte for Watching {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        let mut watch = lock(&self.0);
+        match watch.feed(byte) {
+            true => Err(watch.describe_match()),
+            false => Ok(()),
+        }
+    }
+}
+
+///counts how many times every instruction ran, for --profile
+struct Counting(Arc<Mutex<listing::Profile>>);
+
+impl Observer for Counting {
+    fn after_step(&mut self, ctx: &StepCtx) {
+        lock(&self.0).counts[ctx.pc()] += 1;
+    }
+}
+
+///counts the writes to every cell as the steps go by, for --heatmap-image
+struct Mapping(Arc<Mutex<heatmap::Heatmap>>);
+
+impl Observer for Mapping {
+    fn after_step(&mut self, ctx: &StepCtx) {
+        let mut heatmap = lock(&self.0);
+        heatmap.step(ctx.steps());
+        if heatmap::writes(ctx.op()) {
+            heatmap.write(ctx.pointer());
+        }
+    }
+}
+
+///where and how often a long run saves its state, so it can be picked up again with --resume
+struct Checkpointing {
+    path: String,
+    every: Duration,
+    //the program's source, which the checkpoint keeps a hash of
+    source: String,
+    //what the run is, written after the state
+    provenance: String,
+    saved_at: Instant,
+}
+
+impl Checkpointing {
+    ///writes the checkpoint next to the file first and then moves it over the file, so a crash
+    ///halfway through writing never leaves a broken checkpoint behind
+    fn save(&mut self, tm: &TuringMachine) -> Result<(), String> {
+        let temporary = format!("{}.tmp", self.path);
+        std::fs::write(&temporary, tm.checkpoint(&self.source) + &self.provenance)
+            .and_then(|()| std::fs::rename(&temporary, &self.path))
+            .map_err(|e| format!("could not write {}: {}", self.path, e))?;
+        self.saved_at = Instant::now();
+        log::debug!("saved a checkpoint to {} at step {}", self.path, tm.steps);
+        Ok(())
+    }
+}
+
+///prints where a program that doesn't parse went wrong, with the source around it, and exits
+fn parse_failed(path: &str, source: &str, error: &ParseError) -> ! {
+    let diagnostic = diagnostics::Diagnostic::from_parse_error(error, source);
+    report::error(&diagnostics::render_all(path, source, &[diagnostic]));
+    std::process::exit(EXIT_PARSE);
+}
+
+///gives the machine the channels given with --channel, and gives back what they are for the
+///provenance's input, like ", channel 1 the text \"hello\"". a channel that reads stdin reads
+///whatever the program's input is
+fn add_channels(tm: &mut TuringMachine, channels: &[ChannelSpec]) -> String {
+    let mut described = String::new();
+    for channel in channels {
+        let (input, description): (Option<Arc<Mutex<dyn InputByte + Send>>>, String) =
+            match &channel.source {
+                Source::File(path) => {
+                    let file = std::fs::File::open(path).unwrap_or_else(|e| {
+                        report::error(&format!("could not read {}: {}", path, e));
+                        std::process::exit(1);
+                    });
+                    (
+                        Some(Arc::new(Mutex::new(std::io::BufReader::new(file)))),
+                        provenance::describe_file(path),
+                    )
+                }
+                Source::Text(text) => (
+                    Some(Arc::new(Mutex::new(std::io::Cursor::new(
+                        text.clone().into_bytes(),
+                    )))),
+                    format!("the text {:?}", text),
+                ),
+                Source::Stdin => (None, "the input".to_string()),
+            };
+        tm.add_channel(channel.number, input, channel.eof);
+        described += &format!(", channel {} {}", channel.number, description);
+    }
+    described
+}
+
+///for --no-input, exits with every place the program could read input, or get bytes that change
+///from run to run, so its output only ever depends on its source. "?" is fine with a seed
+fn refuse_input(tm: &TuringMachine, path: &str, source: &str, seeded: bool) {
+    let reads = tm
+        .find(|instruction| matches!(instruction, Instruction::Replace | Instruction::ReadBit))
+        .into_iter()
+        .map(|position| {
+            diagnostics::Diagnostic::new(
+                "this reads input, which --no-input doesn't allow",
+                Some(position),
+            )
+        });
+    let randoms = tm
+        .find(|instruction| instruction == Instruction::Random && !seeded)
+        .into_iter()
+        .map(|position| {
+            diagnostics::Diagnostic::new(
+                "this gives different bytes every run, use --seed to make it the same",
+                Some(position),
+            )
+        });
+    let mut refused: Vec<_> = reads.chain(randoms).collect();
+    if refused.is_empty() {
+        return;
+    }
+    refused.sort_by_key(|diagnostic| diagnostic.position.map(|p| (p.line, p.column)));
+    report::error(&diagnostics::render_all(path, source, &refused));
+    std::process::exit(1);
+}
+
+///saves the state of a failed run to a file in the directory, named after the program and the
+///time, for looking at later with "inspect", with what the run was at the end
+fn write_coredump(
+    directory: &str,
+    path: &str,
+    error: &str,
+    source: &str,
+    tm: &TuringMachine,
+    provenance: &Provenance,
+) {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let name = std::path::Path::new(path)
+        .file_stem()
+        .map_or("program".into(), |stem| stem.to_string_lossy());
+    let dump_path = std::path::Path::new(directory).join(format!("{}-{}.bfdump", name, seconds));
+    let dump = checkpoint::CoreDump {
+        provenance: Some(provenance.clone()),
+        ..checkpoint::CoreDump::new(path, error, source, tm)
+    };
+    match std::fs::create_dir_all(directory)
+        .and_then(|()| std::fs::write(&dump_path, dump.to_text()))
+    {
+        Ok(()) => report::report(
+            "coredump",
+            &format!("the state of the run is saved in {}", dump_path.display()),
+        ),
+        Err(e) => report::error(&format!("could not write {}: {}", dump_path.display(), e)),
+    }
+}
+
+///the "provenance" command, which shows what made a file, from the provenance in it
+fn provenance_command(mut args: impl Iterator<Item = String>) {
+    let path = args.next().unwrap_or_else(|| usage());
+    if path.starts_with('-') || args.next().is_some() {
+        usage();
+    }
+    let text = std::fs::read(&path).unwrap_or_else(|e| {
+        report::error(&format!("could not read {}: {}", path, e));
+        std::process::exit(1);
+    });
+    match Provenance::find(&String::from_utf8_lossy(&text)) {
+        Some(provenance) => print!("{}", provenance),
+        None => {
+            report::error(&format!("{}: there's no provenance in it", path));
+            std::process::exit(1);
+        }
+    }
+}
+
+///the "cache" command, which lists the programs in the cache of compiled programs with their
+///sizes, or clears it
+fn cache_command(mut args: impl Iterator<Item = String>) {
+    let mut directory = None;
+    let mut clear = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cache-dir" => directory = Some(args.next().unwrap_or_else(|| usage())),
+            "--list" => clear = false,
+            "--clear" => clear = true,
+            _ => usage(),
+        }
+    }
+    let Some(directory) = directory else {
+        report::error("cache needs the --cache-dir to look in");
+        std::process::exit(2);
+    };
+    let cache = Cache::new(directory);
+    let entries = match clear {
+        true => cache.clear(),
+        false => cache.entries(),
+    }
+    .unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", cache.directory().display(), e));
+        std::process::exit(1);
+    });
+    let size: u64 = entries.iter().map(|entry| entry.size).sum();
+    match clear {
+        true => println!(
+            "took {} programs out of {}, {} bytes",
+            entries.len(),
+            cache.directory().display(),
+            size
+        ),
+        false => {
+            for entry in &entries {
+                println!("{:>12}  {}", entry.size, entry.name);
+            }
+            println!(
+                "{} programs in {}, {} bytes",
+                entries.len(),
+                cache.directory().display(),
+                size
+            );
+        }
+    }
+}
+
+///the "capabilities" command, which shows what this build can do, as text or json
+fn capabilities_command(args: impl Iterator<Item = String>) {
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ => usage(),
+        }
+    }
+    let capabilities = Capabilities::new();
+    match json {
+        true => println!("{}", capabilities.to_json()),
+        false => print!("{}", capabilities),
+    }
+}
+
+///the "inspect" command, which shows what a core dump says about the run that failed
+fn inspect_command(mut args: impl Iterator<Item = String>) {
+    let mut dump_path = None;
+    let mut lang = None;
+    let mut level = Level::O0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            //a dump from an optimized run points into the optimized program
+            "-O0" | "-O1" | "-O2" => {
+                level = Level::from_name(&arg[2..]).expect("the level is 0, 1 or 2")
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => dump_path = Some(arg),
+        }
+    }
+    let dump_path = dump_path.unwrap_or_else(|| usage());
+    let dump = std::fs::read_to_string(&dump_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| checkpoint::CoreDump::parse(&text).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", dump_path, e));
+            std::process::exit(1);
+        });
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&dump.path));
+    let tm = program::Program::compile(&dump.source, &lang, level)
+        .map(|program| TuringMachine::with_program(std::sync::Arc::new(program)))
+        .map_err(|e| e.to_string())
+        .and_then(|mut tm| {
+            tm.resume(&dump.source, &dump.state)
+                .map_err(|e| e.to_string())?;
+            Ok(tm)
+        })
+        .unwrap_or_else(|e| {
+            report::error(&format!("{}: {}", dump_path, e));
+            std::process::exit(1);
+        });
+    println!("{}: {}", dump.path, dump.error);
+    if let Some(position) = tm.position() {
+        println!();
+        print!("{}", diagnostics::excerpt(&dump.source, position, 1));
+        //the loops the failing instruction is inside of, innermost first
+        let mut open = vec![];
+        if let Ok(tokens) = lang::tokenize(&dump.source, &lang) {
+            for (instruction, token_position) in tokens {
+                if (token_position.line, token_position.column) >= (position.line, position.column)
+                {
+                    break;
+                }
+                match instruction {
+                    Instruction::JumpToClose => open.push(token_position),
+                    Instruction::JumpToOpen => {
+                        open.pop();
                     }
+                    _ => {}
+                }
+            }
+        }
+        for loop_start in open.iter().rev() {
+            println!("    in the loop at {}", loop_start);
+        }
+    }
+    println!();
+    println!("{}", tm.describe_state());
+}
+
+///keeps the output to a number of bytes a second, so a program that prints a lot can be
+///watched. it's a bucket that fills up with bytes it may write as time goes by, up to a tenth of a
+///second's worth, and every byte written takes one out
+struct Throttle {
+    rate: u64,
+    bytes: f64,
+    filled_at: Instant,
+}
+
+impl Throttle {
+    fn new(rate: u64) -> Self {
+        Throttle {
+            rate,
+            bytes: 0.0,
+            filled_at: Instant::now(),
+        }
+    }
+    ///waits until another byte may be written, giving up early when ctrl+c cancels the run
+    fn wait(&mut self, handle: &ControlHandle) {
+        let capacity = (self.rate as f64 / 10.0).max(1.0);
+        let elapsed = self.filled_at.elapsed().as_secs_f64();
+        self.bytes = (self.bytes + elapsed * self.rate as f64).min(capacity);
+        self.filled_at = Instant::now();
+        if self.bytes < 1.0 {
+            sleep(
+                Duration::from_secs_f64((1.0 - self.bytes) / self.rate as f64),
+                handle,
+            );
+            self.bytes = 1.0;
+            self.filled_at = Instant::now();
+        }
+        self.bytes -= 1.0;
+    }
+}
+
+///how many steps run between looking at the clock to see whether a checkpoint is due
+const CHECKPOINT_CHECK_STEPS: u64 = 100_000;
+
+///runs the program through its events, doing the reading and writing for it on its input and
+///output. everything that looks at the output, like the transcript, is one of the outputs of its
+///tee, and finds out when the byte was written from now.
+///with a delay it sleeps that long every so many instructions, so people can watch it go
+fn run_events(
+    tm: &mut TuringMachine,
+    delay: Option<(Duration, u64)>,
+    transcribing: &Arc<Mutex<Transcribing>>,
+    now: &Now,
+    mut checkpointing: Option<&mut Checkpointing>,
+    mut throttle: Option<Throttle>,
+) -> Result<RunOutcome, Failed> {
+    let output = tm.output.clone();
+    let reader = tm.input.clone();
+    let handle = tm.control_handle();
+    //the step count at the last sleep
+    let mut slept_at = tm.steps;
+    loop {
+        if let Some(checkpointing) = checkpointing.as_deref_mut() {
+            if checkpointing.saved_at.elapsed() >= checkpointing.every {
+                //the output so far goes out first, so as little of it as possible gets written a
+                //second time after resuming
+                lock(&output).flush_bytes()?;
+                checkpointing.save(tm)?;
+            }
+        }
+        let event = match delay {
+            //the clock has to be looked at every so often, even while nothing is happening
+            None if checkpointing.is_some() => match tm.run_for(CHECKPOINT_CHECK_STEPS) {
+                Some(event) => event,
+                None => continue,
+            },
+            None => tm.run_until_event(),
+            Some((delay, every)) => {
+                if tm.steps - slept_at >= every {
+                    //whatever has been printed should be seen while we wait
+                    lock(&output).flush_bytes()?;
+                    sleep(delay, &handle);
+                    slept_at = tm.steps;
+                }
+                match tm.run_for(every - (tm.steps - slept_at)) {
+                    Some(event) => event,
+                    None => continue,
+                }
+            }
+        };
+        match event {
+            Event::Output(byte) => {
+                if let Some(throttle) = throttle.as_mut() {
+                    //what's been written so far should be seen before waiting for more
+                    lock(&output).flush_bytes()?;
+                    throttle.wait(&handle);
+                }
+                *lock(now) = (tm.steps, tm.last_position());
+                lock(&output).write_byte(byte)?;
+            }
+            Event::InputNeeded => {
+                //anything printed so far should be visible before we sit and wait for the user
+                //to type
+                lock(&output).flush_bytes()?;
+                //the "," hasn't counted as a step yet, it does once it gets its input
+                let step = tm.steps + 1;
+                let mut transcribing = lock(transcribing);
+                let input = match &mut *transcribing {
+                    Transcribing::Replay(replay) => replay
+                        .read(step)
+                        .map_err(|divergence| diverged(tm.position(), divergence))?,
+                    _ => lock(&reader).read_byte()?,
+                };
+                if let Transcribing::Record(transcript) = &mut *transcribing {
+                    transcript
+                        .entries
+                        .push(transcript::Entry::Read { step, byte: input });
                 }
-                Instruction::JumpToClose => {
-                    stack.push(Instruction::JumpToOpen);
+                tm.provide_input(input);
+            }
+            Event::Breakpoint(_) => {}
+            Event::Halted | Event::Cancelled => {
+                lock(&output).flush_bytes()?;
+                if let (Event::Halted, Transcribing::Replay(replay)) =
+                    (&event, &*lock(transcribing))
+                {
+                    replay
+                        .finish()
+                        .map_err(|divergence| diverged(None, divergence))?;
                 }
-                _ => { /*ignoring*/ }
+                return Ok(match event {
+                    Event::Cancelled => RunOutcome::Cancelled,
+                    _ => RunOutcome::Halted,
+                });
+            }
+            Event::Error(e) => {
+                let _ = lock(&output).flush_bytes();
+                return Err(e.into());
             }
         }
-        return_token
     }
-    ///gets the matching opening bracket for the closing bracket indicated by "bracket_to_match"
-    fn get_matching_opening_bracket(&self, bracket_to_match: usize) -> usize {
-        let mut stack: Vec<Instruction> = vec![];
-        let mut return_token = 0;
-        for token in (0..(bracket_to_match)).rev() {
-            match self.program[token] {
-                Instruction::JumpToOpen => {
-                    stack.push(Instruction::JumpToOpen);
+}
+
+///input that's copied to a file a byte at a time as the program reads it, so the file ends up
+///holding exactly what the program consumed, in order. reading stops at the same place when the
+///file is given back with --input, so the end of the input comes at the same moment too
+struct CapturedInput<R> {
+    input: R,
+    capture: std::fs::File,
+}
+
+impl<R: Read> Read for CapturedInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.input.read(buf)?;
+        self.capture.write_all(&buf[..read])?;
+        Ok(read)
+    }
+}
+
+///stdin from a terminal, which shows a prompt on stderr while "," waits for the user to type, so
+///a program that's waiting doesn't look like one that hung. the prompt goes away once the bytes
+///come in, and what was typed is left where it was. bytes that were typed ahead are read without
+///showing it
+struct PromptedInput {
+    input: std::io::BufReader<Box<dyn Read + Send>>,
+    prompt: String,
+}
+
+impl Read for PromptedInput {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::io::BufRead;
+        if self.input.buffer().is_empty() {
+            //moving down and back up makes the terminal scroll now if it's on the last line, so
+            //it doesn't later when enter is pressed, which would leave the saved cursor a line off
+            eprint!("\x1bD\x1bM\x1b7\x1b[2m{}\x1b[0m", self.prompt);
+            let typed = self.input.fill_buf()?;
+            //back to where the prompt started, clearing it and what the terminal echoed after it,
+            //then putting back what was typed
+            eprint!("\x1b8\x1b[J{}", String::from_utf8_lossy(typed));
+        }
+        self.input.read(buf)
+    }
+}
+
+///whether "," should show a prompt while it waits. only a person at a terminal sees it, never a
+///file or a pipe, and it isn't written into stderr when that's going somewhere else
+fn prompts() -> bool {
+    use std::io::IsTerminal;
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+///input for --input-nonblocking, where "," takes a byte if one has come in and the sentinel if
+///none has yet, instead of waiting for one. a thread does the waiting and hands bytes over as they
+///come, and the end of the input is still the end, so "," does what --eof says from then on
+struct NonBlockingInput {
+    bytes: mpsc::Receiver<u8>,
+    sentinel: u8,
+}
+
+impl NonBlockingInput {
+    fn new(mut input: Box<dyn Read + Send>, sentinel: u8) -> Self {
+        let (sender, bytes) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            //an error reading is taken as the end of the input, the program can't wait for more
+            while let Ok(read @ 1..) = input.read(&mut buffer) {
+                if buffer[..read]
+                    .iter()
+                    .any(|byte| sender.send(*byte).is_err())
+                {
+                    return;
                 }
-                Instruction::JumpToClose => {
-                    if stack.is_empty() {
-                        return_token = token;
-                        break;
-                    } else {
-                        stack.pop();
+            }
+        });
+        NonBlockingInput { bytes, sentinel }
+    }
+}
+
+impl Read for NonBlockingInput {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = match self.bytes.try_recv() {
+            Ok(byte) => byte,
+            Err(mpsc::TryRecvError::Empty) => self.sentinel,
+            Err(mpsc::TryRecvError::Disconnected) => return Ok(0),
+        };
+        Ok(1)
+    }
+}
+
+///whether stdin is a file, which has all of its bytes there from the start. --input-nonblocking
+///reads one as usual, so "," never sees the sentinel before the end of the file
+#[cfg(unix)]
+fn stdin_is_file() -> bool {
+    use std::os::fd::AsFd;
+    std::io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .and_then(|fd| std::fs::File::from(fd).metadata())
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+#[cfg(windows)]
+fn stdin_is_file() -> bool {
+    use std::os::windows::io::AsHandle;
+    std::io::stdin()
+        .as_handle()
+        .try_clone_to_owned()
+        .and_then(|handle| std::fs::File::from(handle).metadata())
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn stdin_is_file() -> bool {
+    false
+}
+
+///stdout for the program's output. std writes bytes to files and pipes as they are on every
+///platform, but a windows console only takes utf-8, so a program writing any other byte there
+///would fail. those are shown as "\u{FFFD}" instead, like terminals elsewhere do
+fn program_stdout() -> Box<dyn Write + Send> {
+    #[cfg(windows)]
+    if std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+        return Box::new(bfint::io::ConsoleOutput::new(std::io::stdout()));
+    }
+    Box::new(std::io::stdout())
+}
+
+///stdin for the program's input. a windows console gives "\r\n" when enter is pressed, which is
+///made into the "\n" terminals give everywhere else. files and pipes are read as they are
+fn program_stdin() -> Box<dyn Read + Send> {
+    #[cfg(windows)]
+    if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Box::new(bfint::io::ConsoleInput::new(std::io::stdin()));
+    }
+    Box::new(std::io::stdin())
+}
+
+//the terminal's settings from before --input-nonblocking changed them
+#[cfg(unix)]
+static TERMINAL: std::sync::OnceLock<libc::termios> = std::sync::OnceLock::new();
+
+///for --input-nonblocking, makes the terminal hand over every key as it's pressed instead of a
+///line at a time after enter, and stops it echoing them. ctrl+c still stops the program. the
+///settings are put back whenever bfint exits, after a panic or ctrl+c too
+#[cfg(unix)]
+fn keys_as_pressed() {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() {
+        return;
+    }
+    //SAFETY: termios is plain data, and tcgetattr fills it in before it's used
+    let mut settings: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut settings) } != 0 {
+        return;
+    }
+    if TERMINAL.set(settings).is_err() {
+        return;
+    }
+    settings.c_lflag &= !(libc::ICANON | libc::ECHO);
+    settings.c_cc[libc::VMIN] = 1;
+    settings.c_cc[libc::VTIME] = 0;
+    //SAFETY: restore_terminal only reads TERMINAL, which is set by now
+    unsafe {
+        libc::atexit(restore_terminal);
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &settings);
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn restore_terminal() {
+    if let Some(settings) = TERMINAL.get() {
+        //SAFETY: these are the settings tcgetattr gave back
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, settings) };
+    }
+}
+
+//other terminals already hand over keys as they're pressed, or can't be told to
+#[cfg(not(unix))]
+fn keys_as_pressed() {}
+
+///a command running next to the program for --io-command, reading what the program writes and
+///writing what it reads. threads move the bytes both ways, so neither side can get stuck writing
+///into a full pipe while the other one is stuck doing the same
+struct Command {
+    child: std::process::Child,
+    //the thread writing to the command's input, which ends once the program's output is closed
+    writer: std::thread::JoinHandle<()>,
+}
+
+///what the command has written, waiting for the program to read it
+struct FromCommand {
+    chunks: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    //how much of the chunk has been read already
+    read: usize,
+}
+
+///what the program writes, gathered up and handed to the thread writing to the command
+struct ToCommand {
+    chunks: mpsc::Sender<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl Command {
+    ///starts the command with the shell, and gives back the program's ends of its output and
+    ///input
+    fn spawn(command: &str) -> std::io::Result<(Command, FromCommand, ToCommand)> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut child = std::process::Command::new(shell)
+            .args([flag, command])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("the command's input is piped");
+        let mut stdout = child.stdout.take().expect("the command's output is piped");
+        let (to_command, chunks) = mpsc::channel::<Vec<u8>>();
+        let writer = std::thread::spawn(move || {
+            for chunk in chunks {
+                //once the command stops reading, the rest of what the program writes is thrown
+                //away, the same as with a closed pipe
+                if stdin
+                    .write_all(&chunk)
+                    .and_then(|()| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        let (from_command, chunks) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            loop {
+                match stdout.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(read) => {
+                        if from_command.send(buffer[..read].to_vec()).is_err() {
+                            break;
+                        }
                     }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(_) => break,
                 }
-                _ => { /*ignoring*/ }
             }
+        });
+        let from = FromCommand {
+            chunks,
+            chunk: vec![],
+            read: 0,
+        };
+        let to = ToCommand {
+            chunks: to_command,
+            buffer: vec![],
+        };
+        Ok((Command { child, writer }, from, to))
+    }
+    ///waits for the command to end, after everything the program wrote has gone to it. the
+    ///program's end of the output has to be dropped first, that's what tells the command there's
+    ///nothing more coming
+    fn wait(mut self) -> std::io::Result<std::process::ExitStatus> {
+        let _ = self.writer.join();
+        self.child.wait()
+    }
+}
+
+impl Read for FromCommand {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.read == self.chunk.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => (self.chunk, self.read) = (chunk, 0),
+                //the command has closed its output, so the input has run out
+                Err(_) => return Ok(0),
+            }
+        }
+        let count = buf.len().min(self.chunk.len() - self.read);
+        buf[..count].copy_from_slice(&self.chunk[self.read..self.read + count]);
+        self.read += count;
+        Ok(count)
+    }
+}
+
+impl Write for ToCommand {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= 4096 {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buffer.is_empty() {
+            //a command that has stopped reading gets nothing, see Command::spawn
+            let _ = self.chunks.send(std::mem::take(&mut self.buffer));
+        }
+        Ok(())
+    }
+}
+
+///the error for a replayed run that stopped matching its transcript, with where in the program
+///that happened
+fn diverged(position: Option<bfint::Position>, divergence: transcript::Divergence) -> String {
+    match position {
+        Some(position) => format!("at {}, {}", position, divergence),
+        None => divergence.to_string(),
+    }
+}
+
+///sleeps for the delay, waking up early if ctrl+c cancels the run in the meantime
+fn sleep(delay: Duration, handle: &ControlHandle) {
+    let wake_at = Instant::now() + delay;
+    while !handle.is_cancelled() {
+        let left = wake_at.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(10)));
+    }
+}
+
+///reads a duration like "50ms", "2s" or a plain number of milliseconds
+fn parse_duration(text: &str) -> Option<Duration> {
+    if let Some(millis) = text.strip_suffix("ms") {
+        return millis.parse().ok().map(Duration::from_millis);
+    }
+    if let Some(seconds) = text.strip_suffix('s') {
+        return seconds.parse().ok().map(Duration::from_secs_f64);
+    }
+    text.parse().ok().map(Duration::from_millis)
+}
+
+///prints the step count and speed on a line of stderr that is overwritten every time
+fn show_progress(progress: &Progress) -> ControlFlow<()> {
+    let mips = progress.steps as f64 / progress.elapsed.as_secs_f64().max(1e-9) / 1e6;
+    report::progress(&format!("{} steps, {:.1} MIPS", progress.steps, mips));
+    ControlFlow::Continue(())
+}
+
+///the most memory bfint has used so far, in KiB, for --log-state-every
+#[cfg(unix)]
+fn peak_memory() -> Option<u64> {
+    //SAFETY: rusage is plain data, and getrusage fills it in before it's used
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    //linux counts it in KiB and macos in bytes
+    let peak = usage.ru_maxrss as u64;
+    match cfg!(target_os = "macos") {
+        true => Some(peak / 1024),
+        false => Some(peak),
+    }
+}
+
+#[cfg(not(unix))]
+fn peak_memory() -> Option<u64> {
+    None
+}
+
+///the greatest number both are a multiple of
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+///makes the first ctrl+c stop the run gracefully, so main can say where it was, and the second
+///one stop the whole program straight away, for when the run doesn't stop (like when it's
+///waiting for input)
+fn catch_interrupt(handle: ControlHandle) {
+    let interrupted = AtomicBool::new(false);
+    let result = ctrlc::set_handler(move || {
+        if interrupted.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+        handle.cancel();
+    });
+    if let Err(e) = result {
+        report::error(&format!("could not catch ctrl+c: {}", e));
+    }
+}
+
+///for --watch, runs bfint with the same arguments but --watch every time one of the program
+///files changes, stopping the run before it if it hasn't finished yet. the run being a process
+///of its own means it starts over completely, parsing the program and opening --input again, and
+///one that fails to parse only prints its errors while this carries on watching
+fn watch_programs(paths: &[String], clear: bool) -> ! {
+    let watched: Vec<&String> = paths
+        .iter()
+        .filter(|path| *path != "-" && !program::is_url(path))
+        .collect();
+    if watched.is_empty() {
+        report::error("--watch needs a program in a file to watch");
+        std::process::exit(2);
+    }
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| arg != "--watch" && arg != "--watch-clear")
+        .collect();
+    let exe = std::env::current_exe().unwrap_or_else(|e| {
+        report::error(&format!("could not find bfint to run it again: {}", e));
+        std::process::exit(1);
+    });
+    //when every file was last changed, None when it can't be read, like while an editor is
+    //replacing it
+    let stamps = || -> Vec<Option<std::time::SystemTime>> {
+        watched
+            .iter()
+            .map(|path| {
+                std::fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+            })
+            .collect()
+    };
+    let mut seen = stamps();
+    loop {
+        let mut child = std::process::Command::new(&exe)
+            .args(&args)
+            .spawn()
+            .unwrap_or_else(|e| {
+                report::error(&format!("could not run {}: {}", exe.display(), e));
+                std::process::exit(1);
+            });
+        let mut running = true;
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            if running && !matches!(child.try_wait(), Ok(None)) {
+                running = false;
+                log::info!("watching {} for changes", watched[0]);
+            }
+            if stamps() != seen {
+                break;
+            }
+        }
+        //an editor saving can change a file several times in a row, so the run waits for it to
+        //stay the same for a moment
+        loop {
+            seen = stamps();
+            std::thread::sleep(WATCH_INTERVAL * 2);
+            if stamps() == seen {
+                break;
+            }
+        }
+        if running {
+            let _ = child.kill();
         }
-        return_token
+        let _ = child.wait();
+        let _ = std::io::stdout().flush();
+        match clear {
+            //clears the terminal and moves the cursor to its top
+            true => print!("\x1b[2J\x1b[H"),
+            false => report::report("watch", "--- the program changed, running it again ---"),
+        }
+        let _ = std::io::stdout().flush();
     }
-    ///executes the "JumpToClose" instruction
-    fn jump_if_zero(&mut self) {
-        match self.tape[self.pointer] {
-            0 => {
-                let new_counter = self.get_matching_closing_bracket(self.program_counter);
-                self.program_counter = new_counter;
+}
+
+///how often --watch looks at the files
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+///the "fmt" command, which formats a program instead of running it
+fn format_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut width = 80;
+    let mut comments = format::Comments::Keep;
+    let mut check = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => {
+                width = args
+                    .next()
+                    .and_then(|width| width.parse().ok())
+                    .unwrap_or_else(|| usage());
             }
-            _ => {
-                self.program_counter += 1;
+            "--comments" => {
+                comments = args
+                    .next()
+                    .and_then(|name| format::Comments::from_name(&name))
+                    .unwrap_or_else(|| usage());
+            }
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
             }
+            "--check" => check = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
         }
     }
-    ///executes the "JumpToOpen" Instruction
-    fn jump_unless_zero(&mut self) {
-        match self.tape[self.pointer] {
-            0 => {
-                self.program_counter += 1;
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let formatted = format::format(&source, &lang, width, comments).unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", path, e));
+        std::process::exit(1);
+    });
+    if !check {
+        print!("{}", formatted);
+    } else if formatted != source {
+        report::error(&format!("{} is not formatted", path));
+        std::process::exit(1);
+    }
+}
+
+///the "bench" command, which times how long the program takes to run
+fn bench_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut runs = 5;
+    let mut warmup = 1;
+    let mut levels = vec![Level::O0];
+    let mut input_path = None;
+    let mut step_limit = None;
+    let mut json = false;
+    let number = |value: Option<String>| {
+        value
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| usage())
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
             }
-            _ => {
-                let new_counter = self.get_matching_opening_bracket(self.program_counter);
-                self.program_counter = new_counter;
-            }
-        }
-    }
-
-    ///turns a string representation of a brainfuck program into a list of instructions
-    fn parse(program: &str) -> Vec<Instruction> {
-        program
-            .trim()
-            .to_string()
-            .chars()
-            .map(|c| match c {
-                '>' => Instruction::MoveRight,
-                '<' => Instruction::MoveLeft,
-                '+' => Instruction::Increment,
-                '-' => Instruction::Decrement,
-                '.' => Instruction::Output,
-                ',' => Instruction::Replace,
-                '[' => Instruction::JumpToClose,
-                ']' => Instruction::JumpToOpen,
-                _ => panic!("unrecognized character: {}", c),
-            })
-            .collect::<Vec<_>>()
+            "--runs" => runs = number(args.next()),
+            "--warmup" => warmup = number(args.next()),
+            "-O0" | "-O1" | "-O2" => {
+                levels = vec![Level::from_name(&arg[2..]).expect("the level is 0, 1 or 2")]
+            }
+            "--compare" => {
+                levels = args
+                    .next()
+                    .unwrap_or_else(|| usage())
+                    .split(',')
+                    .map(|level| {
+                        level
+                            .trim()
+                            .strip_prefix("-O")
+                            .and_then(Level::from_name)
+                            .unwrap_or_else(|| usage())
+                    })
+                    .collect()
+            }
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--max-steps" => step_limit = Some(number(args.next()) as u64),
+            "--json" => json = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    if runs == 0 {
+        usage();
+    }
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let input = match input_path {
+        Some(input_path) => std::fs::read(&input_path).unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", input_path, e));
+            std::process::exit(1);
+        }),
+        None => vec![],
+    };
+    let after_run = |level: Level, index: usize, run: &bench::Run| {
+        //the runs go to stderr with --json, so that stdout is nothing but the json
+        let line = format!(
+            "{} run {}: {:.2?}, {} instructions, {} instructions/s",
+            level,
+            index,
+            run.time,
+            run.steps,
+            bench::per_second(run.steps as f64 / run.time.as_secs_f64())
+        );
+        match json {
+            true => report::report("bench", &line.to_string()),
+            false => println!("{}", line),
+        }
+    };
+    let timings = bench::time(
+        &source,
+        &lang,
+        &levels,
+        (warmup, runs),
+        &input,
+        step_limit,
+        after_run,
+    )
+    .unwrap_or_else(|e| match e {
+        bench::BenchError::ParseError(e) => parse_failed(&path, &source, &e),
+        e => {
+            report::error(&format!("{}: {}", path, e));
+            std::process::exit(1);
+        }
+    });
+    match json {
+        true => println!("{}", bench::json(&path, warmup, &timings)),
+        false => print!("\n{}", bench::table(&timings)),
+    }
+}
+
+///the "minify" command, which prints the smallest version of a program instead of running it
+///the "joust" command, which plays two bf joust programs against each other and prints how every
+///duel went, see joust.rs
+fn joust_command(args: impl Iterator<Item = String>) {
+    let paths: Vec<String> = args.collect();
+    let [left_path, right_path] = paths.as_slice() else {
+        usage()
+    };
+    let warrior = |path: &str| {
+        joust::parse(&read_program(path)).unwrap_or_else(|e| {
+            report::error(&format!("{}: {}", path, e));
+            std::process::exit(1);
+        })
+    };
+    let result = joust::play(&warrior(left_path), &warrior(right_path));
+    print!("{}", result);
+    println!(
+        "{} wins {}, {} wins {}, {} ties, score {}",
+        left_path,
+        result.wins(joust::Winner::Left),
+        right_path,
+        result.wins(joust::Winner::Right),
+        result.wins(joust::Winner::Tie),
+        result.score()
+    );
+}
+
+fn minify_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut verify = false;
+    let mut input_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--verify" => verify = true,
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
     }
-    ///checks if the turing machine still has instructions left to exeute
-    fn has_instructions_left(&self) -> bool {
-        self.program_counter < self.program.len()
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let minified = minify::minify(&source, &lang).unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", path, e));
+        std::process::exit(1);
+    });
+    if verify {
+        let input = match input_path {
+            Some(input_path) => std::fs::read(&input_path).unwrap_or_else(|e| {
+                report::error(&format!("could not read {}: {}", input_path, e));
+                std::process::exit(1);
+            }),
+            None => vec![],
+        };
+        let step_limit = 100_000_000;
+        if run_captured(&source, &lang, &input, step_limit)
+            != run_captured(&minified, &lang, &input, step_limit)
+        {
+            report::error(&format!(
+                "{}: the minified program behaves differently",
+                path
+            ));
+            std::process::exit(1);
+        }
     }
-    ///executes the current instruction pointed to by the program counter for our turing machine
-    fn perform_next_instruction(&mut self) {
-        match self.program.get(self.program_counter) {
-            Some(Instruction::MoveRight) => {
-                self.move_right();
+    println!("{}", minified);
+    report::report(
+        "summary",
+        &format!(
+            "{}: {} bytes minified to {}",
+            path,
+            source.len(),
+            minified.len()
+        ),
+    );
+}
+
+///what "bfint reduce" compares when it isn't given a check command or configs: the program as
+///it's written, at -O2 and tiered
+const REDUCE_CONFIGS: &str = "[plain]\n[o2]\noptimize = 2\n[tiered]\noptimize = 2\ntiered = true\n";
+
+///the "reduce" command, which shrinks a program that shows a bug down to as little of it as still
+///shows it, see reduce.rs. the bug is shown when the check command exits the same way it does
+///on the program itself, with the same exit code or timing out, or without one, when a config
+///goes its own way from the reference like it does for "bfint crosscheck"
+fn reduce_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut check = None;
+    let mut configs_path = None;
+    let mut input_path = None;
+    let mut step_limit = spec::DEFAULT_MAX_STEPS;
+    let mut timeout = Duration::from_secs(10);
+    let mut out_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
             }
-            Some(Instruction::MoveLeft) => {
-                self.move_left();
+            "--check" => check = Some(args.next().unwrap_or_else(|| usage())),
+            "--configs" => configs_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--max-steps" => {
+                step_limit = args
+                    .next()
+                    .and_then(|steps| steps.parse().ok())
+                    .unwrap_or_else(|| usage());
             }
-            Some(Instruction::Increment) => {
-                self.increment();
+            "--timeout" => {
+                timeout = args
+                    .next()
+                    .and_then(|secs| secs.parse().ok())
+                    .map(Duration::from_secs_f64)
+                    .unwrap_or_else(|| usage());
             }
-            Some(Instruction::Decrement) => {
-                self.decrement();
+            "-o" => out_path = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let result = match check {
+        Some(command) => {
+            //the program goes in a file with its own extension, so the command can tell its
+            //language the same way
+            let extension = std::path::Path::new(&path)
+                .extension()
+                .map_or("b".into(), |extension| extension.to_string_lossy());
+            let file = std::env::temp_dir().join(format!(
+                "bfint-reduce-{}.{}",
+                std::process::id(),
+                extension
+            ));
+            let exited = run_check(&command, &file, &source, timeout);
+            match exited {
+                Some(status) => log::info!("{}: the check ends with {}", path, status),
+                None => log::info!("{}: the check times out", path),
             }
-            Some(Instruction::Output) => {
-                self.write();
+            let result = reduce::reduce(&source, &lang, |candidate| {
+                run_check(&command, &file, candidate, timeout) == exited
+            });
+            let _ = std::fs::remove_file(&file);
+            result
+        }
+        None => {
+            let configs = match &configs_path {
+                Some(configs_path) => std::fs::read_to_string(configs_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| crosscheck::Configs::parse(&text).map_err(|e| e.to_string()))
+                    .unwrap_or_else(|e| {
+                        report::error(&format!("{}: {}", configs_path, e));
+                        std::process::exit(1);
+                    }),
+                None => crosscheck::Configs::parse(REDUCE_CONFIGS)
+                    .expect("the configs reduce compares by default parse"),
+            };
+            let input = match &input_path {
+                Some(input_path) => std::fs::read(input_path).unwrap_or_else(|e| {
+                    report::error(&format!("could not read {}: {}", input_path, e));
+                    std::process::exit(1);
+                }),
+                None => vec![],
+            };
+            let divergence = reduce::Divergence::find(
+                &source,
+                std::path::Path::new(&path),
+                &input,
+                configs,
+                step_limit,
+            )
+            .unwrap_or_else(|| {
+                report::error(&format!(
+                    "{}: every config runs it the same, there's no bug to reduce",
+                    path
+                ));
+                std::process::exit(1);
+            });
+            log::info!("{}: {} goes its own way", path, divergence.config_name());
+            reduce::reduce(&source, &lang, |candidate| divergence.reproduces(candidate))
+        }
+    };
+    let reduction = result.unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", path, e));
+        std::process::exit(1);
+    });
+    match &out_path {
+        Some(out_path) => {
+            if let Err(e) = std::fs::write(out_path, format!("{}\n", reduction.source)) {
+                report::error(&format!("could not write {}: {}", out_path, e));
+                std::process::exit(1);
             }
-            Some(Instruction::Replace) => {
-                self.replace();
+        }
+        None => println!("{}", reduction.source),
+    }
+    report::report(
+        "summary",
+        &format!(
+            "{}: {} instructions reduced to {}, {} programs checked",
+            path, reduction.before, reduction.after, reduction.checks
+        ),
+    );
+}
+
+///runs the check command of "bfint reduce" on the program, with the "{}" in it replaced by the
+///file the program is written to, or the file put at the end when there's no "{}". gives back
+///how it exited, or nothing when it had to be killed for running longer than the timeout
+fn run_check(
+    command: &str,
+    file: &std::path::Path,
+    source: &str,
+    timeout: Duration,
+) -> Option<std::process::ExitStatus> {
+    if let Err(e) = std::fs::write(file, source) {
+        report::error(&format!("could not write {}: {}", file.display(), e));
+        std::process::exit(1);
+    }
+    let file = file.to_string_lossy();
+    let command = match command.contains("{}") {
+        true => command.replace("{}", &file),
+        false => format!("{} {}", command, file),
+    };
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = std::process::Command::new(shell)
+        .args([flag, &command])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap_or_else(|e| {
+            report::error(&format!("could not run {}: {}", command, e));
+            std::process::exit(1);
+        });
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) if started.elapsed() < timeout => std::thread::sleep(Duration::from_millis(5)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
             }
-            Some(Instruction::JumpToClose) => {
-                self.jump_if_zero();
+        }
+    }
+}
+
+///the "obfuscate" command, which writes a program out longer and harder to read, see obfuscate.rs
+fn obfuscate_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut seed = 0;
+    let mut factor = 3.0;
+    let mut verify = false;
+    let mut input_path = None;
+    let mut out_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
             }
-            Some(Instruction::JumpToOpen) => {
-                self.jump_unless_zero();
+            "--seed" => {
+                seed = args
+                    .next()
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or_else(|| usage());
             }
-            None => {
-                println!("not doing anything");
+            "--factor" => {
+                factor = args
+                    .next()
+                    .and_then(|factor| factor.parse().ok())
+                    .filter(|factor: &f64| *factor >= 1.0)
+                    .unwrap_or_else(|| usage());
             }
+            "--verify" => verify = true,
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            "-o" => out_path = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
         }
     }
-    ///starts executing the program loaded into our turing machine.
-    fn run(&mut self) {
-        while self.has_instructions_left() {
-            self.perform_next_instruction();
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let obfuscated = obfuscate::obfuscate(&source, &lang, seed, factor).unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", path, e));
+        std::process::exit(1);
+    });
+    if verify {
+        let input = match input_path {
+            Some(input_path) => std::fs::read(&input_path).unwrap_or_else(|e| {
+                report::error(&format!("could not read {}: {}", input_path, e));
+                std::process::exit(1);
+            }),
+            None => vec![],
+        };
+        let step_limit = 100_000_000;
+        if run_captured(&source, &lang, &input, step_limit)
+            != run_captured(&obfuscated, &lang, &input, step_limit)
+        {
+            report::error(&format!(
+                "{}: the obfuscated program behaves differently",
+                path
+            ));
+            std::process::exit(1);
         }
     }
+    match &out_path {
+        Some(out_path) => {
+            std::fs::write(out_path, format!("{}\n", obfuscated)).unwrap_or_else(|e| {
+                report::error(&format!("could not write {}: {}", out_path, e));
+                std::process::exit(1);
+            })
+        }
+        None => println!("{}", obfuscated),
+    }
+    report::report(
+        "summary",
+        &format!(
+            "{}: {} bytes obfuscated to {}",
+            path,
+            source.len(),
+            obfuscated.len()
+        ),
+    );
 }
 
-fn main() {
-    let input = ",";
-    //let input = "++++++++++[>+++++++>++++++++++>+++>+<<<<-]>++.>+.+++++++..+++.>++.<<+++++++++++++++.>.+++.------.--------.>+.>.";
-    let mut tm = TuringMachine::new(input);
-    tm.run();
+///runs a program on the input, giving back what it wrote and whether it got to the end. it's
+///stopped after the step limit, so a program that never ends can still be compared
+fn run_captured(source: &str, lang: &lang::Lang, input: &[u8], step_limit: u64) -> (Vec<u8>, bool) {
+    let output = Arc::new(Mutex::new(vec![]));
+    let Ok(mut tm) = TuringMachine::new(source, lang) else {
+        return (vec![], false);
+    };
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    tm.step_limit = Some(step_limit);
+    tm.rng = random::Rng::from_seed(0);
+    let finished = match lang {
+        lang::Lang::Brainfork => fork::run(tm).is_ok(),
+        _ => tm.run().is_ok(),
+    };
+    let output = lock(&output).clone();
+    (output, finished)
+}
+
+///one of the programs in a pipeline, with the bytes waiting to go into it
+struct Stage {
+    path: String,
+    source: String,
+    tm: TuringMachine,
+    //what the stage before has written that this one hasn't read yet
+    input: VecDeque<u8>,
+    //a byte this stage wrote that there's no room for in the next one's input yet
+    held: Option<u8>,
+    waiting_for_input: bool,
+    done: bool,
+    read: u64,
+    written: u64,
+}
+
+///how many instructions a stage of a pipeline runs before the next stage gets a turn
+const PIPELINE_SLICE: u64 = 10_000;
+
+///the "pipeline" command, which runs programs side by side with the output of each one going
+///into the input of the next. they take turns on this one thread, each running until it has to
+///wait for the one before it or for room in the one after it
+fn pipeline_command(mut args: impl Iterator<Item = String>) {
+    let mut paths = vec![];
+    let mut lang = None;
+    let mut eof_behavior = EofBehavior::Zero;
+    let mut buffer = 4096;
+    let mut fuel = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--eof" => {
+                eof_behavior = args
+                    .next()
+                    .and_then(|name| EofBehavior::from_name(&name))
+                    .unwrap_or_else(|| usage());
+            }
+            "--buffer" => {
+                buffer = args
+                    .next()
+                    .and_then(|bytes| bytes.parse::<usize>().ok())
+                    .filter(|bytes| *bytes > 0)
+                    .unwrap_or_else(|| usage());
+            }
+            //one tank for all the stages, so splitting the work up doesn't get any more of it
+            "--max-steps" => {
+                fuel = Some(Fuel::new(
+                    args.next()
+                        .and_then(|steps| steps.parse().ok())
+                        .unwrap_or_else(|| usage()),
+                ));
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        usage();
+    }
+    let mut stages: Vec<Stage> = paths
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let source = read_program(&path);
+            let lang = lang.clone().unwrap_or_else(|| lang::Lang::from_path(&path));
+            if lang == lang::Lang::Brainfork {
+                report::error(&format!(
+                    "{}: brainfork programs can't be part of a pipeline",
+                    path
+                ));
+                std::process::exit(2);
+            }
+            let mut tm = TuringMachine::new(&source, &lang)
+                .unwrap_or_else(|e| parse_failed(&path, &source, &e));
+            tm.eof_behavior = eof_behavior;
+            tm.fuel = fuel.as_ref().map(|fuel| fuel.for_stage(index + 1));
+            Stage {
+                path,
+                source,
+                tm,
+                input: VecDeque::new(),
+                held: None,
+                waiting_for_input: false,
+                done: false,
+                read: 0,
+                written: 0,
+            }
+        })
+        .collect();
+    let mut stdin = std::io::stdin();
+    let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+    let last = stages.len() - 1;
+    while stages.iter().any(|stage| !stage.done) {
+        let mut moved = false;
+        for index in 0..stages.len() {
+            loop {
+                //a byte that didn't fit last time goes first, nothing else happens until it's gone
+                if let Some(byte) = stages[index].held {
+                    if index == last {
+                        stdout.write_all(&[byte]).unwrap_or_else(write_failed);
+                    } else if stages[index + 1].done {
+                        //nothing is going to read it anymore, so it's thrown away
+                    } else if stages[index + 1].input.len() < buffer {
+                        stages[index + 1].input.push_back(byte);
+                    } else {
+                        break;
+                    }
+                    stages[index].held = None;
+                    moved = true;
+                }
+                if stages[index].done {
+                    break;
+                }
+                if stages[index].waiting_for_input {
+                    //the input runs out once the stage before has stopped and everything it
+                    //wrote has been read
+                    let byte = if index == 0 {
+                        let _ = stdout.flush();
+                        stdin.read_byte().unwrap_or_else(|e| {
+                            report::error(&format!("could not read input: {}", e));
+                            std::process::exit(1);
+                        })
+                    } else if let Some(byte) = stages[index].input.pop_front() {
+                        Some(byte)
+                    } else if stages[index - 1].done {
+                        None
+                    } else {
+                        break;
+                    };
+                    let stage = &mut stages[index];
+                    stage.read += byte.is_some() as u64;
+                    stage.tm.provide_input(byte);
+                    stage.waiting_for_input = false;
+                    moved = true;
+                }
+                let stage = &mut stages[index];
+                let steps = stage.tm.steps;
+                let event = stage.tm.run_for(PIPELINE_SLICE);
+                moved |= stage.tm.steps != steps;
+                match event {
+                    //the slice ran out, the next stage gets a turn
+                    None => break,
+                    Some(Event::Output(byte)) => {
+                        stage.held = Some(byte);
+                        stage.written += 1;
+                    }
+                    Some(Event::InputNeeded) => stage.waiting_for_input = true,
+                    Some(Event::Breakpoint(_)) => {}
+                    Some(Event::Halted | Event::Cancelled) => stage.done = true,
+                    Some(Event::Error(e)) => {
+                        let _ = stdout.flush();
+                        let message = format!("{} (in stage {} of the pipeline)", e, index + 1);
+                        let diagnostic =
+                            diagnostics::Diagnostic::new(&message, stage.tm.position());
+                        report::error(&diagnostic.render(&stage.path, &stage.source));
+                        std::process::exit(exit_code(&e));
+                    }
+                }
+            }
+        }
+        //every stage is stuck waiting on another one. the stages only ever wait on the ones
+        //next to them, so this shouldn't happen, but hanging forever would be worse than saying so
+        if !moved {
+            let _ = stdout.flush();
+            let mut message = String::from("the pipeline is stuck, no stage can carry on:");
+            for stage in stages.iter().filter(|stage| !stage.done) {
+                let waiting = match stage.held {
+                    Some(_) => "waiting for room to write",
+                    None => "waiting for input",
+                };
+                message += &format!("\n  {}: {}", stage.path, waiting);
+            }
+            report::error(&message);
+            std::process::exit(1);
+        }
+    }
+    stdout.flush().unwrap_or_else(write_failed);
+    for stage in &stages {
+        log::info!(
+            "{}: {} steps, read {} bytes, wrote {} bytes",
+            stage.path,
+            stage.tm.steps,
+            stage.read,
+            stage.written
+        );
+    }
+}
+
+///the "equiv" command, which runs two programs on the same inputs looking for one they treat
+///differently. finding none doesn't prove they're the same, but it's a good sign after a rewrite
+fn equiv_command(mut args: impl Iterator<Item = String>) {
+    let mut paths = vec![];
+    let mut lang = None;
+    let mut inputs_directory = None;
+    let mut random_inputs = 0;
+    let mut seed = 0;
+    let mut step_limit = 10_000_000;
+    let mut save_path = "equiv-input.bin".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--inputs" => inputs_directory = Some(args.next().unwrap_or_else(|| usage())),
+            "--random" => {
+                random_inputs = args
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--seed" => {
+                seed = args
+                    .next()
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--max-steps" => {
+                step_limit = args
+                    .next()
+                    .and_then(|steps| steps.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--save" => save_path = args.next().unwrap_or_else(|| usage()),
+            _ if arg.starts_with('-') => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    let [first, second] = &paths[..] else {
+        usage();
+    };
+    let programs = [first, second].map(|path| {
+        let source = read_program(path);
+        let lang = lang.clone().unwrap_or_else(|| lang::Lang::from_path(path));
+        if let Err(e) = TuringMachine::new(&source, &lang) {
+            parse_failed(path, &source, &e);
+        }
+        (source, lang)
+    });
+    //no input at all comes first, then the files, then the random inputs
+    let mut inputs = vec![("no input".to_string(), vec![])];
+    if let Some(directory) = inputs_directory {
+        let mut files: Vec<std::path::PathBuf> = std::fs::read_dir(&directory)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                report::error(&format!("could not read {}: {}", directory, e));
+                std::process::exit(1);
+            });
+        files.retain(|file| file.is_file());
+        files.sort();
+        for file in files {
+            let input = std::fs::read(&file).unwrap_or_else(|e| {
+                report::error(&format!("could not read {}: {}", file.display(), e));
+                std::process::exit(1);
+            });
+            inputs.push((file.display().to_string(), input));
+        }
+    }
+    let mut rng = random::Rng::from_seed(seed);
+    for number in 1..=random_inputs {
+        let length = rng.next_byte() % 64;
+        let input = (0..length).map(|_| rng.next_byte()).collect();
+        inputs.push((format!("random input {} (seed {})", number, seed), input));
+    }
+    for (name, input) in &inputs {
+        let results = programs
+            .each_ref()
+            .map(|(source, lang)| run_captured(source, lang, input, step_limit));
+        let [(first_output, first_finished), (second_output, second_finished)] = &results;
+        let difference = if first_finished != second_finished {
+            let (finished, unfinished) = match first_finished {
+                true => (first, second),
+                false => (second, first),
+            };
+            format!(
+                "{} got to the end but {} didn't within {} steps",
+                finished, unfinished, step_limit
+            )
+        } else if first_output != second_output {
+            let same = first_output
+                .iter()
+                .zip(second_output)
+                .take_while(|(a, b)| a == b)
+                .count();
+            format!("their output is different from byte {}", same)
+        } else {
+            continue;
+        };
+        println!(
+            "{} and {} differ on {}: {}",
+            first, second, name, difference
+        );
+        match std::fs::write(&save_path, input) {
+            Ok(()) => println!("the input is saved in {}", save_path),
+            Err(e) => report::error(&format!("could not write {}: {}", save_path, e)),
+        }
+        std::process::exit(1);
+    }
+    println!(
+        "{} and {} did the same on all {} inputs, which doesn't prove they always will",
+        first,
+        second,
+        inputs.len()
+    );
+}
+
+///the "tracediff" command, which finds the first read or write two programs don't agree on, see
+///tracediff.rs
+fn tracediff_command(mut args: impl Iterator<Item = String>) {
+    let mut paths = vec![];
+    let mut lang = None;
+    let mut input_path = None;
+    let mut step_limit = 10_000_000;
+    let mut debug = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--max-steps" => {
+                step_limit = args
+                    .next()
+                    .and_then(|steps| steps.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--debug" => debug = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    let [first, second] = &paths[..] else {
+        usage();
+    };
+    let input = match input_path {
+        Some(input_path) => std::fs::read(&input_path).unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", input_path, e));
+            std::process::exit(1);
+        }),
+        None => vec![],
+    };
+    let machines = [first, second].map(|path| {
+        let source = read_program(path);
+        let lang = lang.clone().unwrap_or_else(|| lang::Lang::from_path(path));
+        TuringMachine::new(&source, &lang).unwrap_or_else(|e| parse_failed(path, &source, &e))
+    });
+    let comparison = tracediff::compare(machines.clone(), &input, step_limit);
+    print!(
+        "{}",
+        tracediff::describe(&comparison, [first.as_str(), second.as_str()])
+    );
+    let tracediff::Comparison::Different { sides, .. } = comparison else {
+        return;
+    };
+    if debug {
+        //the debuggers start over and go to where the machines were, so they can go back from
+        //there too
+        let debuggers = machines.map(|tm| debugger::Debugger::new(tm, input.clone()));
+        let steps = sides.each_ref().map(|side| side.steps);
+        debug_side_by_side(debuggers, steps, [first, second], "tracediff");
+    }
+    std::process::exit(1);
+}
+
+///takes both debuggers to their steps and then debugs them together, with commands typed at
+///a prompt going to both, or "a <command>" and "b <command>" to only one. what each says is
+///shown under its name
+fn debug_side_by_side(
+    mut debuggers: [debugger::Debugger; 2],
+    steps: [u64; 2],
+    names: [&str; 2],
+    prompt: &str,
+) {
+    for (debugger, step) in debuggers.iter_mut().zip(steps) {
+        let _ = debugger.goto(step);
+    }
+    let mut lines = std::io::stdin().lines();
+    while !debuggers.iter().any(debugger::Debugger::quit) {
+        print!("({}) ", prompt);
+        let _ = std::io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            break;
+        };
+        let (which, command) = match line.trim().split_once(' ') {
+            Some(("a", command)) => (&[0][..], command),
+            Some(("b", command)) => (&[1][..], command),
+            _ => (&[0, 1][..], line.as_str()),
+        };
+        for &index in which {
+            println!("{}:", names[index]);
+            match debuggers[index].execute(command) {
+                Ok(text) => print!("{}", text),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+}
+
+///a program built into the binary, along with what it should print
+struct SelfTest {
+    name: String,
+    source: String,
+    input: &'static [u8],
+    eof_behavior: EofBehavior,
+    expected: Vec<u8>,
+}
+
+impl SelfTest {
+    fn new(name: &str, source: &str, input: &'static [u8], expected: &[u8]) -> Self {
+        SelfTest {
+            name: name.to_string(),
+            source: source.to_string(),
+            input,
+            eof_behavior: EofBehavior::Zero,
+            expected: expected.to_vec(),
+        }
+    }
+}
+
+///the programs "self-test" runs. the examples and one more come from tests/fixtures, the rest
+///poke at the corners of the interpreter: cells wrapping around, deep loops, lots of loops and
+///the end of the input. every one is linted and formatted as well as run, and the formatted
+///program run too
+fn self_tests() -> Vec<SelfTest> {
+    //every example is one, along with what it prints
+    let mut tests: Vec<SelfTest> = examples::EXAMPLES
+        .iter()
+        .map(|example| {
+            SelfTest::new(
+                example.name,
+                example.source,
+                example.input,
+                example.expected,
+            )
+        })
+        .collect();
+    tests.extend([
+        SelfTest::new(
+            "numbers",
+            include_str!("../tests/fixtures/numbers.bf"),
+            b"",
+            include_bytes!("../tests/fixtures/numbers.out"),
+        ),
+        //0 goes down to 255 and back up to 0, and 256 "+" go all the way round
+        SelfTest::new(
+            "wrapping",
+            &format!("-.+.{}.", "+".repeat(256)),
+            b"",
+            &[255, 0, 0],
+        ),
+        //every loop but the innermost runs once, the innermost clears the cell so they all stop
+        SelfTest::new(
+            "deep nesting",
+            &format!("+{}-{}+.", "[".repeat(1000), "]".repeat(1000)),
+            b"",
+            &[1],
+        ),
+        //as deep as a generated program might go, which has to parse, check, format and run
+        //on nothing more than the stack every thread starts out with
+        SelfTest::new(
+            "very deep nesting",
+            &format!("+{}-{}+.", "[".repeat(100_000), "]".repeat(100_000)),
+            b"",
+            &[1],
+        ),
+        //thousands of loops, side by side and inside each other, all matched up right
+        SelfTest::new(
+            "many loops",
+            &format!("{}++++++[>++++++++<-]>.", "+[[-]>[]<]".repeat(5000)),
+            b"",
+            b"0",
+        ),
+    ]);
+    //"," at the end of the input on a cell that holds 5
+    for (name, eof_behavior, expected) in [
+        ("end of input, zero", EofBehavior::Zero, 0),
+        ("end of input, max", EofBehavior::Max, 255),
+        ("end of input, unchanged", EofBehavior::Unchanged, 5),
+    ] {
+        let mut test = SelfTest::new(name, "+++++,.", b"", &[expected]);
+        test.eof_behavior = eof_behavior;
+        tests.push(test);
+    }
+    tests
+}
+
+///the "examples" command, which lists the programs built into bfint or prints one of them
+fn examples_command(mut args: impl Iterator<Item = String>) {
+    match (args.next().as_deref(), args.next(), args.next()) {
+        (Some("--list"), None, _) => print!("{}", examples::list()),
+        (Some("--dump"), Some(name), None) => match examples::find(&name) {
+            Some(example) => print!("{}", example.source),
+            None => {
+                report::error(&format!(
+                    "there's no example called {}, bfint examples --list shows them",
+                    name
+                ));
+                std::process::exit(2);
+            }
+        },
+        _ => usage(),
+    }
+}
+
+///the "self-test" command, which runs the programs built into the binary to check that it works
+fn self_test_command(args: impl Iterator<Item = String>) {
+    let mut config = InterpreterConfig::default();
+    for arg in args {
+        let set = match arg.as_str() {
+            "-O0" | "-O1" | "-O2" => config.set("optimize", &arg[2..]),
+            "--tiered" => config.set("tiered", "true"),
+            _ => usage(),
+        };
+        if let Err(e) = set {
+            config_failed(e);
+        }
+    }
+    //a thread of its own gets the stack every thread starts out with, which is smaller than the
+    //main thread's, so the deep tests show nothing needs more than that
+    let failed = std::thread::spawn(move || run_self_tests(&config))
+        .join()
+        .unwrap_or_else(|_| std::process::exit(1));
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+///runs every self test on machines set up the way the config says, saying how each one went, and
+///gives back how many failed
+fn run_self_tests(config: &InterpreterConfig) -> usize {
+    let tests = self_tests();
+    let mut failed = 0;
+    for test in &tests {
+        let result = lang::tokenize(&test.source, &lang::Lang::Brainfuck)
+            .map(|tokens| lint::lint(&tokens))
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                format::format(
+                    &test.source,
+                    &lang::Lang::Brainfuck,
+                    80,
+                    format::Comments::Keep,
+                )
+                .map_err(|e| e.to_string())
+            })
+            .and_then(|formatted| {
+                let output = run_self_test(test, &test.source, config)?;
+                match run_self_test(test, &formatted, config)? == output {
+                    true => Ok(output),
+                    false => Err("the formatted program printed something else".to_string()),
+                }
+            });
+        match result {
+            Ok(output) if output == test.expected => println!("ok   {}", test.name),
+            Ok(output) => {
+                failed += 1;
+                println!("FAIL {}", test.name);
+                println!(
+                    "     expected {:?}",
+                    String::from_utf8_lossy(&test.expected)
+                );
+                println!("     got      {:?}", String::from_utf8_lossy(&output));
+            }
+            Err(e) => {
+                failed += 1;
+                println!("FAIL {}", test.name);
+                println!("     {}", e);
+            }
+        }
+    }
+    println!("{} passed, {} failed", tests.len() - failed, failed);
+    failed
+}
+
+///what the program prints, run the way the test says on a machine set up the way the config says
+fn run_self_test(
+    test: &SelfTest,
+    source: &str,
+    config: &InterpreterConfig,
+) -> Result<Vec<u8>, String> {
+    let config = InterpreterConfig {
+        eof: test.eof_behavior,
+        step_limit: Some(10_000_000),
+        ..config.clone()
+    };
+    let output = Arc::new(Mutex::new(vec![]));
+    let mut tm = TuringMachine::with_config(source, &config).map_err(|e| e.to_string())?;
+    tm.input = Arc::new(Mutex::new(std::io::Cursor::new(test.input)));
+    tm.output = output.clone();
+    tm.run().map_err(|e| e.to_string())?;
+    let output = lock(&output).clone();
+    Ok(output)
+}
+
+///the "disasm" command, which prints the program the way the machine runs it
+fn disasm_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut level = Level::O0;
+    let mut listing = false;
+    let mut profile_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "-O0" | "-O1" | "-O2" => {
+                level = Level::from_name(&arg[2..]).expect("the level is 0, 1 or 2")
+            }
+            "--listing" => listing = true,
+            "--profile" => profile_path = Some(args.next().unwrap_or_else(|| usage())),
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    //the counts only line up with the listing
+    if profile_path.is_some() && !listing {
+        usage();
+    }
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let program = program::Program::compile(&source, &lang, level)
+        .unwrap_or_else(|e| parse_failed(&path, &source, &e));
+    let profile = profile_path.map(|profile_path| {
+        std::fs::read_to_string(&profile_path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| listing::Profile::parse(&text).map_err(|e| e.to_string()))
+            .and_then(|profile| {
+                profile.check(&program).map_err(|e| e.to_string())?;
+                Ok(profile)
+            })
+            .unwrap_or_else(|e| {
+                report::error(&format!("{}: {}", profile_path, e));
+                std::process::exit(1);
+            })
+    });
+    match listing {
+        true => print!("{}", listing::listing(&program, profile.as_ref())),
+        false => {
+            for (index, (instruction, position)) in program.tokens().into_iter().enumerate() {
+                println!("{:>6} {:>8} {:?}", index, position.to_string(), instruction);
+            }
+        }
+    }
+}
+
+///the "check" command, which parses a program and looks for likely bugs without running it
+fn check_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut lint = false;
+    let mut deny_warnings = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--lint" => lint = true,
+            "--deny-warnings" => deny_warnings = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let tokens = TuringMachine::new(&source, &lang)
+        .and_then(|_| lang::tokenize(&source, &lang))
+        .unwrap_or_else(|e| parse_failed(&path, &source, &e));
+    if !lint {
+        return;
+    }
+    let findings = lint::lint(&tokens);
+    for finding in &findings {
+        println!("{}:{}", path, finding);
+    }
+    let warnings = findings
+        .iter()
+        .filter(|finding| finding.severity == lint::Severity::Warning)
+        .count();
+    if deny_warnings && warnings > 0 {
+        std::process::exit(1);
+    }
+}
+
+///the "analyze" command, which writes out what the program looks like without running it
+fn analyze_command(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut lang = None;
+    let mut cfg_path = None;
+    let mut metrics = false;
+    let mut json = false;
+    let mut dead_writes = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--cfg" => cfg_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--metrics" => metrics = true,
+            "--json" => json = true,
+            "--dead-writes" => dead_writes = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(&path));
+    let tokens = TuringMachine::new(&source, &lang)
+        .and_then(|_| lang::tokenize(&source, &lang))
+        .unwrap_or_else(|e| parse_failed(&path, &source, &e));
+    if let Some(cfg_path) = cfg_path {
+        let dot = analyze::cfg::to_dot(&source, &tokens);
+        if let Err(e) = std::fs::write(&cfg_path, dot) {
+            report::error(&format!("could not write {}: {}", cfg_path, e));
+            std::process::exit(1);
+        }
+    }
+    if metrics {
+        let metrics = analyze::metrics::Metrics::new(&source, &tokens);
+        match json {
+            true => println!("{}", metrics.json()),
+            false => print!("{}", metrics.table()),
+        }
+    }
+    if dead_writes {
+        for write in analyze::writes::dead_writes(&tokens) {
+            println!("{}:{}", path, write);
+        }
+    }
+}
+
+///the "generate" command, which prints a program that prints a text
+fn generate_command(mut args: impl Iterator<Item = String>) {
+    let mut text = None;
+    let mut strategy = generate::Strategy::Loops;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--text" => text = Some(args.next().unwrap_or_else(|| usage()).into_bytes()),
+            "--input-file" => {
+                let path = args.next().unwrap_or_else(|| usage());
+                text = Some(std::fs::read(&path).unwrap_or_else(|e| {
+                    report::error(&format!("could not read {}: {}", path, e));
+                    std::process::exit(1);
+                }));
+            }
+            "--strategy" => {
+                strategy = args
+                    .next()
+                    .and_then(|name| generate::Strategy::from_name(&name))
+                    .unwrap_or_else(|| usage());
+            }
+            _ => usage(),
+        }
+    }
+    let text = text.unwrap_or_else(|| usage());
+    let program = generate::generate(&text, strategy);
+    //the program is run before it's handed out, so a mistake in the generator can't go unnoticed
+    if run_captured(&program, &lang::Lang::Brainfuck, &[], 100_000_000) != (text, true) {
+        report::error(&format!(
+            "the {} strategy made a program that prints something else",
+            strategy
+        ));
+        std::process::exit(1);
+    }
+    println!("{}", program);
+}
+
+///the "debug" command, which runs a program under the debugger, taking commands from the
+///terminal or from a script
+fn debug_command(args: impl Iterator<Item = String>) {
+    let args: Vec<String> = args.collect();
+    let mut config = initial_config(&args);
+    let mut args = args.into_iter();
+    let mut path = None;
+    let mut input_path = None;
+    let mut script_path = None;
+    let mut history = None;
+    let mut diff_at_breakpoints = false;
+    let mut watch = None;
+    let mut mi = false;
+    let mut goto_step = None;
+    let mut lockstep = false;
+    let mut pipeline = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--script" => script_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--diff-at-breakpoints" => diff_at_breakpoints = true,
+            "--mi" => mi = true,
+            "--history" => history = Some(history_length(args.next())),
+            "--break-on-output" => watch = Some(output_watch(args.next())),
+            "--goto-step" => {
+                goto_step = Some(
+                    args.next()
+                        .and_then(|step| step.parse::<u64>().ok())
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--lockstep" => lockstep = true,
+            //the program being debugged runs as it's written, so these are only for the machine
+            //run in lockstep with it
+            "-O0" | "-O1" | "-O2" => {
+                let level = Level::from_name(&arg[2..]).expect("the level is 0, 1 or 2");
+                pipeline = Some((level.to_string(), level.pipeline()));
+            }
+            "--passes" => pipeline = Some(("--passes".to_string(), passes(args.next()))),
+            _ if config_option(&mut config, &arg, &mut args) => {}
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    //the machine run in lockstep takes its commands once the run is over
+    if !lockstep && pipeline.is_some()
+        || lockstep && (mi || script_path.is_some() || goto_step.is_some())
+    {
+        usage();
+    }
+    let source = read_program(&path);
+    let lang = config.lang_for(&path, &source);
+    let mut tm = TuringMachine::with_options(&source, &config.parse_options(&lang))
+        .unwrap_or_else(|e| parse_failed(&path, &source, &e));
+    if let Some(history) = history {
+        tm.keep_history(history);
+    }
+    apply_config(&config, &mut tm, &path, &source);
+    //the commands come in on stdin, so the program's input can only come from a file
+    let input = match input_path {
+        Some(input_path) => std::fs::read(&input_path).unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", input_path, e));
+            std::process::exit(1);
+        }),
+        None => vec![],
+    };
+    let names = annotations::cell_names(&source).unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", path, e));
+        std::process::exit(1);
+    });
+    if lockstep {
+        let (name, pipeline) =
+            pipeline.unwrap_or_else(|| ("-O2".to_string(), Level::O2.pipeline()));
+        let mut optimized = tm.clone();
+        optimized.optimize(&pipeline, |_, _| {});
+        let machines = [tm, optimized];
+        let labels = ["-O0", name.as_str()];
+        let step_limit = config.step_limit.unwrap_or(10_000_000);
+        let lockstep = lockstep::run(machines.clone(), &input, step_limit);
+        print!("{}", lockstep::describe(&lockstep, labels));
+        let lockstep::Lockstep::Diverged { sides, .. } = lockstep else {
+            return;
+        };
+        let debuggers = machines.map(|tm| {
+            let mut debugger = debugger::Debugger::new(tm, input.clone());
+            debugger.diff_at_breakpoints = diff_at_breakpoints;
+            debugger.names = names.clone();
+            debugger.lang = lang.clone();
+            debugger
+        });
+        let steps = sides.each_ref().map(|side| side.steps);
+        debug_side_by_side(debuggers, steps, labels, "lockstep");
+        std::process::exit(1);
+    }
+    let mut debugger = debugger::Debugger::new(tm, input);
+    debugger.diff_at_breakpoints = diff_at_breakpoints;
+    debugger.names = names;
+    debugger.lang = lang;
+    debugger.watch = watch;
+    if let Some(step) = goto_step {
+        //the output on the way there is the program's, which mi keeps off its stdout
+        match mi {
+            true => {
+                let _ = debugger.goto(step);
+            }
+            false => print!(
+                "{}",
+                debugger
+                    .execute(&format!("goto {}", step))
+                    .unwrap_or_default()
+            ),
+        }
+    }
+    if mi {
+        //json commands a line on stdin, json events a line on stdout, see mi.rs
+        let mut session = mi::Session::new(debugger);
+        let mut stdout = std::io::stdout();
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            for event in session.handle(&line) {
+                let _ = writeln!(stdout, "{}", event);
+            }
+            let _ = stdout.flush();
+            if session.done() {
+                break;
+            }
+        }
+        return;
+    }
+    if let Some(script_path) = script_path {
+        if let Err(e) = run_debug_script(&mut debugger, &script_path, &mut std::io::stdout()) {
+            report::error(&e.to_string());
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut lines = std::io::stdin().lines();
+    while !debugger.quit() {
+        print!("(bfdb) ");
+        let _ = std::io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            break;
+        };
+        match debugger.execute(&line) {
+            Ok(text) => print!("{}", text),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+///carries out every command in the script, writing each one to out followed by what it printed,
+///the way they'd look typed at the prompt. stops at the first command that fails, with an error
+///saying which line of the script it was on
+fn run_debug_script(
+    debugger: &mut debugger::Debugger,
+    script_path: &str,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let script = std::fs::read_to_string(script_path)
+        .map_err(|e| format!("could not read {}: {}", script_path, e))?;
+    for (index, line) in script.lines().enumerate() {
+        if debugger.quit() {
+            break;
+        }
+        let text = debugger
+            .execute(line)
+            .map_err(|e| format!("{}:{}: {}", script_path, index + 1, e))?;
+        if !line.trim().is_empty() && !line.trim_start().starts_with('#') {
+            let _ = write!(out, "(bfdb) {}\n{}", line.trim(), text);
+        }
+    }
+    let _ = out.flush();
+    Ok(())
+}
+
+///the "repl" command, which runs lines of brainfuck as they're typed, all on the same tape.
+///lines starting with ":" are commands for the repl itself, see repl_meta
+fn repl_command(args: impl Iterator<Item = String>) {
+    let args: Vec<String> = args.collect();
+    let mut config = initial_config(&args);
+    let mut args = args.into_iter();
+    let mut history_path = lineedit::default_history_path();
+    let mut keep_history = true;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--history-file" => {
+                history_path = Some(args.next().unwrap_or_else(|| usage()).into());
+            }
+            "--no-history" => keep_history = false,
+            _ if config_option(&mut config, &arg, &mut args) => {}
+            _ => usage(),
+        }
+    }
+    //big cells are only for programs that are all plain brainfuck, which can't be known of the
+    //lines that haven't been typed yet
+    if config.cell_width == CellWidth::Big {
+        report::error("--cell-width big can't be used with the repl");
+        std::process::exit(2);
+    }
+    let lang = config.lang.clone().unwrap_or(lang::Lang::Brainfuck);
+    let mut tm = TuringMachine::new("", &lang).unwrap_or_else(|e| {
+        report::error(&e.to_string());
+        std::process::exit(1);
+    });
+    apply_config(&config, &mut tm, "repl", "");
+    //lines are only edited, and kept in the history, when they're typed at a terminal, not when
+    //they're piped in
+    let editing = std::io::IsTerminal::is_terminal(&std::io::stdin());
+    let mut history_path = history_path.filter(|_| keep_history && editing);
+    let mut history = match &history_path {
+        Some(path) => lineedit::History::load(path).unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", path.display(), e));
+            lineedit::History::default()
+        }),
+        None => lineedit::History::default(),
+    };
+    //the names cells were given in the comments of everything run so far
+    let mut names = BTreeMap::new();
+    //the lines typed so far of a program whose loops haven't all been closed yet
+    let mut pending = String::new();
+    loop {
+        let prompt = if pending.is_empty() { "bf> " } else { "... " };
+        let line = match editing {
+            true => edit_repl_line(prompt, &history, &names),
+            false => read_repl_line(prompt),
+        };
+        let line = match line {
+            ReplLine::Line(line) => line,
+            //ctrl+c gives up on the lines of an unfinished loop too
+            ReplLine::Cancelled => {
+                pending.clear();
+                continue;
+            }
+            ReplLine::Eof => {
+                println!();
+                return;
+            }
+        };
+        if editing {
+            history.add(&line);
+            if let Some(path) = &history_path {
+                if let Err(e) = history.save(path) {
+                    report::error(&format!(
+                        "could not write {}: {}, the history won't be kept",
+                        path.display(),
+                        e
+                    ));
+                    history_path = None;
+                }
+            }
+        }
+        if pending.is_empty() && line.trim_start().starts_with(':') {
+            if let Err(e) = repl_meta(&mut tm, line.trim(), &lang, &mut names) {
+                println!("{}", e);
+            }
+            continue;
+        }
+        pending.push_str(&line);
+        match repl_run(&mut tm, &pending, &lang, &mut names) {
+            Err(ParseError::UnmatchedOpen(_) | ParseError::UnmatchedProcedureStart(_)) => continue,
+            Err(e) => println!("error: {}", e),
+            Ok(()) => {}
+        }
+        pending.clear();
+    }
+}
+
+///a line typed at the repl, ending in a newline
+enum ReplLine {
+    Line(String),
+    ///ctrl+c, with the line editor
+    Cancelled,
+    ///ctrl+d, or the end of what was piped in
+    Eof,
+}
+
+///the repl's commands, and what tab finishes their first argument with
+const REPL_COMMANDS: [(&str, lineedit::Argument); 9] = [
+    (":tape", lineedit::Argument::Cell),
+    (":ptr", lineedit::Argument::Cell),
+    (":set", lineedit::Argument::Cell),
+    (":reset", lineedit::Argument::None),
+    (":load", lineedit::Argument::Path),
+    (":save", lineedit::Argument::Path),
+    (":restore", lineedit::Argument::Path),
+    (":try", lineedit::Argument::None),
+    (":help", lineedit::Argument::None),
+];
+
+///reads a line at the prompt as it comes, for lines that are piped in
+fn read_repl_line(prompt: &str) -> ReplLine {
+    print!("{}", prompt);
+    let _ = std::io::stdout().flush();
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) => ReplLine::Eof,
+        Ok(_) => ReplLine::Line(line),
+        Err(e) => {
+            report::error(&e.to_string());
+            std::process::exit(1);
+        }
+    }
+}
+
+///reads a line at the prompt with the line editor, see lineedit.rs. the terminal is only in the
+///editor's mode while the line is typed, so what the program reads with "," is typed as usual
+///and never goes in the history
+#[cfg(unix)]
+fn edit_repl_line(
+    prompt: &str,
+    history: &lineedit::History,
+    names: &BTreeMap<usize, String>,
+) -> ReplLine {
+    let Some(_raw) = RawTerminal::new() else {
+        return read_repl_line(prompt);
+    };
+    let names: Vec<String> = names.values().cloned().collect();
+    let complete = |line: &str| lineedit::complete(line, &REPL_COMMANDS, &names);
+    let mut editor = lineedit::Editor::new(history);
+    let mut stdout = std::io::stdout();
+    let mut bytes = vec![];
+    loop {
+        let _ = write!(stdout, "{}", editor.render(prompt));
+        let _ = stdout.flush();
+        let mut read = [0; 64];
+        match std::io::stdin().read(&mut read) {
+            Ok(0) | Err(_) => return ReplLine::Eof,
+            Ok(length) => bytes.extend_from_slice(&read[..length]),
+        }
+        while let Some((key, length)) = lineedit::Key::parse(&bytes) {
+            bytes.drain(..length);
+            match editor.key(key, history, &complete) {
+                lineedit::Action::Redraw => {}
+                lineedit::Action::Done(line) => {
+                    let _ = write!(stdout, "{}\r\n", editor.render(prompt));
+                    return ReplLine::Line(line + "\n");
+                }
+                lineedit::Action::Cancelled => {
+                    let _ = write!(stdout, "^C\r\n");
+                    return ReplLine::Cancelled;
+                }
+                lineedit::Action::Eof => return ReplLine::Eof,
+                lineedit::Action::Show(candidates) => {
+                    let _ = write!(stdout, "\r\n{}\r\n", candidates.join("  "));
+                }
+            }
+        }
+    }
+}
+
+//other terminals don't get told how to hand over keys, see keys_as_pressed
+#[cfg(not(unix))]
+fn edit_repl_line(
+    prompt: &str,
+    _history: &lineedit::History,
+    _names: &BTreeMap<usize, String>,
+) -> ReplLine {
+    read_repl_line(prompt)
+}
+
+///the terminal in the mode the repl's line editor needs while it's dropped: keys come in as
+///they're pressed without being echoed, and ctrl+c comes in as a key instead of stopping bfint
+#[cfg(unix)]
+struct RawTerminal(libc::termios);
+
+#[cfg(unix)]
+impl RawTerminal {
+    ///nothing when stdin isn't a terminal that can be put in that mode
+    fn new() -> Option<Self> {
+        //SAFETY: termios is plain data, and tcgetattr fills it in before it's used
+        let mut settings: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut settings) } != 0 {
+            return None;
+        }
+        let before = settings;
+        settings.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        settings.c_iflag &= !(libc::ICRNL | libc::IXON);
+        settings.c_cc[libc::VMIN] = 1;
+        settings.c_cc[libc::VTIME] = 0;
+        //SAFETY: these are the settings tcgetattr gave back, changed a little
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &settings) } != 0 {
+            return None;
+        }
+        Some(RawTerminal(before))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        //SAFETY: these are the settings tcgetattr gave back
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0) };
+    }
+}
+
+///runs a bit of program on the repl's machine and shows where it ended up. if it fails at
+///runtime the tape goes back to how it was before
+fn repl_run(
+    tm: &mut TuringMachine,
+    source: &str,
+    lang: &lang::Lang,
+    names: &mut BTreeMap<usize, String>,
+) -> Result<(), ParseError> {
+    let before = tm.clone();
+    tm.load(source, lang)?;
+    //names that can't be read are only for the debugger to complain about
+    names.extend(annotations::cell_names(source).unwrap_or_default());
+    let mut stdout = std::io::stdout();
+    let mut last_output = None;
+    let mut events = tm.events();
+    let mut failed = None;
+    while let Some(event) = events.next() {
+        match event {
+            Event::Output(byte) => {
+                let _ = stdout.write_all(&[byte]);
+                last_output = Some(byte);
+            }
+            Event::InputNeeded => {
+                let _ = stdout.flush();
+                let mut input = [0u8; 1];
+                let byte = std::io::stdin()
+                    .lock()
+                    .read_exact(&mut input)
+                    .ok()
+                    .map(|()| input[0]);
+                events.provide_input(byte);
+            }
+            Event::Error(e) => failed = Some(e),
+            Event::Breakpoint(_) | Event::Halted | Event::Cancelled => {}
+        }
+    }
+    //the status goes on a line of its own, even when the output didn't end one
+    if last_output.is_some_and(|byte| byte != b'\n') {
+        println!();
+    }
+    if let Some(e) = failed {
+        println!("error: {}", e);
+        *tm = before;
+    }
+    let cell = tm.cell_signedness.value(tm.tape()[tm.pointer()]);
+    println!("pointer {}, cell {}", tm.pointer(), cell);
+    Ok(())
+}
+
+///the repl's own commands, for looking at and changing the session instead of running code
+fn repl_meta(
+    tm: &mut TuringMachine,
+    line: &str,
+    lang: &lang::Lang,
+    names: &mut BTreeMap<usize, String>,
+) -> Result<(), String> {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or(":");
+    let arguments: Vec<&str> = words.collect();
+    let number = |index: usize| -> Result<usize, String> {
+        arguments
+            .get(index)
+            .and_then(|argument| argument.parse().ok())
+            .ok_or_else(|| format!("{} needs a number, see :help", command))
+    };
+    //a cell goes by its number or by a name it was given
+    let cell = |index: usize| -> Result<usize, String> {
+        let argument = arguments
+            .get(index)
+            .ok_or_else(|| format!("{} needs a cell, see :help", command))?;
+        argument
+            .parse()
+            .ok()
+            .or_else(|| {
+                let mut named = names.iter();
+                named
+                    .find(|(_, name)| name == argument)
+                    .map(|(cell, _)| *cell)
+            })
+            .ok_or_else(|| format!("{} isn't a cell number or name", argument))
+    };
+    let path = || -> Result<&str, String> {
+        arguments
+            .first()
+            .copied()
+            .ok_or_else(|| format!("{} needs a file name, see :help", command))
+    };
+    match command {
+        ":tape" => {
+            let start = match arguments.is_empty() {
+                true => tm.pointer().saturating_sub(8),
+                false => cell(0)?,
+            };
+            let length = number(1).unwrap_or(17);
+            let end = (start + length).min(tm.tape().len());
+            let cells: Vec<String> = (start.min(end)..end)
+                .map(|index| {
+                    let value = tm.cell_signedness.value(tm.tape()[index]);
+                    match index == tm.pointer() {
+                        true => format!("[{}]", value),
+                        false => value.to_string(),
+                    }
+                })
+                .collect();
+            println!("{}: {}", start, cells.join(" "));
+            return Ok(());
+        }
+        ":ptr" => {
+            tm.set_pointer(cell(0)?).map_err(|e| e.to_string())?;
+        }
+        ":set" => {
+            let value = arguments
+                .get(1)
+                .and_then(|argument| argument.parse().ok())
+                .ok_or_else(|| format!("{} needs a number, see :help", command))?;
+            let value = tm
+                .cell_signedness
+                .byte(value)
+                .ok_or(match tm.cell_signedness {
+                    bfint::Signedness::Unsigned => "cells hold 0 to 255",
+                    bfint::Signedness::Signed => "cells hold -128 to 127",
+                })?;
+            tm.set_cell(cell(0)?, value).map_err(|e| e.to_string())?;
+        }
+        ":reset" => tm.reset(),
+        ":load" => {
+            let path = path()?;
+            let source = program::read_source(path).map_err(|e| e.to_string())?;
+            return repl_run(tm, &source, lang, names).map_err(|e| format!("{}: {}", path, e));
+        }
+        ":save" => {
+            let path = path()?;
+            std::fs::write(path, save_session(tm))
+                .map_err(|e| format!("could not write {}: {}", path, e))?;
+        }
+        ":restore" => {
+            let path = path()?;
+            let state = std::fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {}", path, e))?;
+            restore_session(tm, &state).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        ":try" => {
+            let code = line[command.len()..].trim();
+            let snippet = tm
+                .run_snippet(code, lang, &[], snippet::SNIPPET_STEPS)
+                .map_err(|e| format!("error: {}", e))?;
+            let output = String::from_utf8_lossy(&snippet.output);
+            print!("{}", output);
+            if !output.is_empty() && !output.ends_with('\n') {
+                println!();
+            }
+            print!("{}", snippet.diff.render(names));
+            match snippet.outcome {
+                Ok(_) => println!(
+                    "pointer {}, cell {}, and the tape is back as it was",
+                    snippet.machine.pointer(),
+                    snippet.machine.tape()[snippet.machine.pointer()]
+                ),
+                Err(RuntimeError::StepLimitReached(_)) => println!(
+                    "gave up after {} steps, and the tape is back as it was",
+                    snippet::SNIPPET_STEPS
+                ),
+                Err(e) => println!("error: {}, and the tape is back as it was", e),
+            }
+            return Ok(());
+        }
+        ":help" => {
+            println!(
+                ":tape [start len]   show the cells from start on (default: around the pointer)"
+            );
+            println!(":ptr n              move the pointer to cell n");
+            println!(":set n v            put the value v in cell n");
+            println!(":reset              set every cell to 0 and the pointer back to the start");
+            println!(":load file.bf       run a file on the tape");
+            println!(":save file.state    write the tape and pointer to a file");
+            println!(":restore file.state read the tape and pointer back from a file");
+            println!(":try code           run code without reading input and show what it did,");
+            println!("                    then put the tape and pointer back");
+            println!(":help               show this");
+            println!("a cell n can also be a name given to it with \";@n name\" in what was run");
+            println!("anything else is run as brainfuck, ctrl+d leaves");
+            return Ok(());
+        }
+        _ => return Err(format!("there is no {} command, try :help", command)),
+    }
+    let cell = tm.cell_signedness.value(tm.tape()[tm.pointer()]);
+    println!("pointer {}, cell {}", tm.pointer(), cell);
+    Ok(())
+}
+
+///writes the pointer and the tape up to the last cell in use as text, like
+///"pointer 2\ncells 0 65 3\n"
+fn save_session(tm: &TuringMachine) -> String {
+    let last_used = tm
+        .tape()
+        .iter()
+        .rposition(|cell| *cell != 0)
+        .map_or(0, |last| last + 1);
+    let cells: Vec<String> = tm.tape()[..last_used].iter().map(u8::to_string).collect();
+    format!("pointer {}\ncells {}\n", tm.pointer(), cells.join(" "))
+}
+
+///reads back what save_session wrote
+fn restore_session(tm: &mut TuringMachine, state: &str) -> Result<(), String> {
+    let mut lines = state.lines();
+    let pointer = lines
+        .next()
+        .and_then(|line| line.strip_prefix("pointer "))
+        .and_then(|pointer| pointer.trim().parse().ok())
+        .ok_or("the first line should be the pointer")?;
+    let cells = lines
+        .next()
+        .and_then(|line| line.strip_prefix("cells"))
+        .ok_or("the second line should be the cells")?
+        .split_whitespace()
+        .map(|cell| cell.parse::<u8>().map_err(|e| format!("{}: {}", cell, e)))
+        .collect::<Result<Vec<u8>, String>>()?;
+    let mut restored = tm.clone();
+    restored.reset();
+    for (index, cell) in cells.into_iter().enumerate() {
+        restored.set_cell(index, cell).map_err(|e| e.to_string())?;
+    }
+    restored.set_pointer(pointer).map_err(|e| e.to_string())?;
+    *tm = restored;
+    Ok(())
+}
+
+///sets the machine up the way the config says, or exits saying why it can't
+fn apply_config(config: &InterpreterConfig, tm: &mut TuringMachine, path: &str, source: &str) {
+    match config.apply(tm) {
+        Ok(()) => {}
+        Err(ConfigError::NotPlain(position)) => {
+            let diagnostic = diagnostics::Diagnostic::new(
+                "only plain brainfuck instructions work with --cell-width big",
+                Some(position),
+            );
+            report::error(&diagnostic.render(path, source));
+            std::process::exit(1);
+        }
+        //a program taken from the cache is only checked against max-nesting here
+        Err(ConfigError::ParseError(e)) => parse_failed(path, source, &e),
+        Err(e) => config_failed(e),
+    }
+}
+
+///the profile named after --config, or the default settings when there's none. it's read before
+///any of the other options, wherever --config is, so the ones given along with it win
+fn initial_config(args: &[String]) -> InterpreterConfig {
+    match args.iter().position(|arg| arg == "--config") {
+        Some(index) => load_profile(args.get(index + 1).unwrap_or_else(|| usage())),
+        None => InterpreterConfig::default(),
+    }
+}
+
+///sets what the option says in the config, taking its value from args, for the options that
+///run, debug and repl all take, see InterpreterConfig::set. gives back false when it isn't one
+fn config_option(
+    config: &mut InterpreterConfig,
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> bool {
+    match arg {
+        "--config" => {
+            args.next();
+        }
+        "--lang"
+        | "--eof"
+        | "--cell-width"
+        | "--cell-underflow"
+        | "--cell-signedness"
+        | "--seed"
+        | "--max-steps"
+        | "--max-output"
+        | "--max-loop-iterations"
+        | "--detect-cycles"
+        | "--eval-depth"
+        | "--max-nesting"
+        | "--max-call-depth"
+        | "--call-depth" => {
+            let value = args.next().unwrap_or_else(|| usage());
+            if let Err(e) = config.set(&arg[2..], &value) {
+                config_failed(e);
+            }
+        }
+        "--ext-halt" | "--ext-channels" | "--tiered" | "--track-writers" => {
+            if let Err(e) = config.set(&arg[2..], "true") {
+                config_failed(e);
+            }
+        }
+        "-O0" | "-O1" | "-O2" | "--optimize" => {
+            let level = arg.strip_prefix("-O").unwrap_or("2");
+            if let Err(e) = config.set("optimize", level) {
+                config_failed(e);
+            }
+        }
+        "--lang-map" => {
+            let mapping_path = args.next().unwrap_or_else(|| usage());
+            let text = std::fs::read_to_string(&mapping_path).unwrap_or_else(|e| {
+                report::error(&format!("could not read {}: {}", mapping_path, e));
+                std::process::exit(1);
+            });
+            let mapping = lang::Mapping::parse(&text).unwrap_or_else(|e| {
+                report::error(&format!("{}: {}", mapping_path, e));
+                std::process::exit(1);
+            });
+            config.lang = Some(lang::Lang::Mapped(mapping));
+        }
+        _ => return false,
+    }
+    true
+}
+
+///says what's wrong with the settings and exits
+fn config_failed(e: ConfigError) -> ! {
+    report::error(&e.to_string());
+    std::process::exit(2);
+}
+
+///the settings in the profile named after --config, see config.rs
+#[cfg(feature = "serde")]
+fn load_profile(path: &str) -> InterpreterConfig {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        report::error(&format!("could not read {}: {}", path, e));
+        std::process::exit(1);
+    });
+    InterpreterConfig::from_toml(&text).unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", path, e));
+        std::process::exit(2);
+    })
+}
+
+#[cfg(not(feature = "serde"))]
+fn load_profile(_: &str) -> InterpreterConfig {
+    report::error("this bfint was built without profiles, they need the \"serde\" feature");
+    std::process::exit(2);
+}
+
+///the number of steps named after --history
+fn history_length(length: Option<String>) -> usize {
+    length
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+///runs the optimizer over the program the machine is about to start, see -O2, and gives back
+///how many instructions it took out. the program is shown every time the pass named by
+///--print-ir-after has run
+fn optimize_program(
+    tm: &mut TuringMachine,
+    pipeline: &Pipeline,
+    print_ir_after: Option<&str>,
+    path: &str,
+) -> usize {
+    let mut runs = 0;
+    let stats = tm.optimize(pipeline, |pass, ir| {
+        if print_ir_after == Some(pass) {
+            runs += 1;
+            report::report(
+                "ir",
+                &format!("{} after {} (run {}):\n{}", path, pass, runs, ir),
+            );
+        }
+    });
+    for (pass, stats) in &stats {
+        match stats.fused {
+            0 => log::info!("{}: {} took out {} instructions", path, pass, stats.removed),
+            fused => log::info!("{}: {} found {} to run in one go", path, pass, fused),
+        }
+    }
+    stats.iter().map(|(_, stats)| stats.removed).sum()
+}
+
+///the pipeline named after --passes, as a list like "cancel,dead-code"
+fn passes(names: Option<String>) -> Pipeline {
+    let names = names.unwrap_or_else(|| usage());
+    let names: Vec<&str> = names.split(',').map(str::trim).collect();
+    Pipeline::from_names(&names).unwrap_or_else(|e| {
+        report::error(&format!("--passes: {}", e));
+        std::process::exit(2);
+    })
+}
+
+///runs the machine's program on every file in the directory, see --batch, writing what it
+///prints for "name.in" to "name.out" in out_dir and a table of how every run went to stdout.
+///every machine gets the settings the one given has, and tiering and the seed, which can't be
+///copied from it. exits with 1 if any run failed
+fn run_batch_directory(
+    tm: &TuringMachine,
+    directory: &str,
+    out_dir: &str,
+    jobs: usize,
+    config: &InterpreterConfig,
+) {
+    let read_failed = |path: &dyn std::fmt::Display, e: std::io::Error| -> ! {
+        report::error(&format!("could not read {}: {}", path, e));
+        std::process::exit(1);
+    };
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(directory)
+        .unwrap_or_else(|e| read_failed(&directory, e))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    let inputs = paths.iter().map(|path| batch::InputSpec {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        input: std::fs::read(path).unwrap_or_else(|e| read_failed(&path.display(), e)),
+        step_limit: None,
+    });
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        report::error(&format!("could not create {}: {}", out_dir, e));
+        std::process::exit(1);
+    }
+    //the first machine was set up with the config already, so it can't fail here
+    let setup = |tm: &mut TuringMachine| {
+        config
+            .apply(tm)
+            .expect("the config was applied to a machine before")
+    };
+    let results = batch::run_batch(tm.program(), inputs, jobs, setup);
+    let width = results
+        .iter()
+        .map(|result| result.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("input".len());
+    println!(
+        "{:<width$}  {:>12}  {:>10}  status",
+        "input", "steps", "time"
+    );
+    let mut failed = 0;
+    for result in &results {
+        let out_path = std::path::Path::new(out_dir)
+            .join(&result.name)
+            .with_extension("out");
+        if let Err(e) = std::fs::write(&out_path, &result.output) {
+            report::error(&format!("could not write {}: {}", out_path.display(), e));
+            failed += 1;
+        }
+        let status = match &result.result {
+            Ok(()) => String::from("ok"),
+            Err(e) => {
+                failed += 1;
+                format!("failed: {}", e)
+            }
+        };
+        println!(
+            "{:<width$}  {:>12}  {:>10}  {}",
+            result.name,
+            result.steps,
+            format!("{:.2?}", result.time),
+            status
+        );
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+///the cells named after an option like --leak-allow, as a list of cells like "3", ranges like
+///"0..4" or "0..=3", or several of them like "0..4,9"
+fn cell_ranges(option: &str, text: Option<String>) -> Vec<std::ops::RangeInclusive<usize>> {
+    let text = text.unwrap_or_else(|| usage());
+    text.split(',')
+        .map(|part| {
+            let part = part.trim();
+            let range = match (part.split_once("..="), part.split_once("..")) {
+                (Some((start, end)), _) => start.parse().ok().zip(end.parse().ok()),
+                (None, Some((start, end))) => start
+                    .parse()
+                    .ok()
+                    .zip(end.parse::<usize>().ok().and_then(|end| end.checked_sub(1))),
+                (None, None) => part.parse().ok().map(|cell| (cell, cell)),
+            };
+            //a range that ends where it starts, or before, has no cells in it
+            range
+                .filter(|(start, end)| start <= end)
+                .map(|(start, end)| start..=end)
+                .unwrap_or_else(|| {
+                    report::error(&format!(
+                        "{}: {} isn't a cell or a range of cells",
+                        option, part
+                    ));
+                    std::process::exit(2);
+                })
+        })
+        .collect()
+}
+
+///for --check-leaks, warns about every cell the program left holding something that isn't in
+///one of the allowed ranges, and gives back how many there were
+fn report_leaks(
+    tm: &TuringMachine,
+    path: &str,
+    allowed: &[std::ops::RangeInclusive<usize>],
+) -> usize {
+    let mut leaks: Vec<std::ops::RangeInclusive<usize>> = vec![];
+    let cells = tm.nonzero_cells().into_iter().flatten();
+    for cell in cells.filter(|cell| !allowed.iter().any(|range| range.contains(cell))) {
+        match leaks.last_mut() {
+            Some(leak) if *leak.end() + 1 == cell => *leak = *leak.start()..=cell,
+            _ => leaks.push(cell..=cell),
+        }
+    }
+    let count: usize = leaks.iter().map(|leak| leak.clone().count()).sum();
+    if count == 0 {
+        return 0;
+    }
+    let cells = match count {
+        1 => String::from("1 cell was"),
+        _ => format!("{} cells were", count),
+    };
+    let places = match leaks.len() {
+        1 => String::from("1 place"),
+        places => format!("{} places", places),
+    };
+    let mut message = format!(
+        "{}: warning: {} left holding something, in {}",
+        path, cells, places
+    );
+    for leak in leaks {
+        let place = match leak.start() == leak.end() {
+            true => format!("cell {}", leak.start()),
+            false => format!(
+                "cells {} to {} ({} cells)",
+                leak.start(),
+                leak.end(),
+                leak.clone().count()
+            ),
+        };
+        message += &format!("\n  {}: {}", place, tm.show_cells(leak));
+    }
+    report::report("leaks", &message);
+    count
+}
+
+///the pattern given to --break-on-output
+fn output_watch(pattern: Option<String>) -> OutputWatch {
+    OutputWatch::parse(&pattern.unwrap_or_else(|| usage())).unwrap_or_else(|e| {
+        report::error(&format!("--break-on-output: {}", e));
+        std::process::exit(2);
+    })
+}
+
+///the "tui" command, which shows the program running full screen
+#[cfg(feature = "tui")]
+fn tui_command(args: impl Iterator<Item = String>) {
+    let args: Vec<String> = args.collect();
+    let mut config = initial_config(&args);
+    let mut args = args.into_iter();
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if config_option(&mut config, &arg, &mut args) => {}
+            _ if arg.starts_with('-') => usage(),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or_else(|| usage());
+    let source = read_program(&path);
+    let lang = config.lang_for(&path, &source);
+    let mut tm = TuringMachine::with_options(&source, &config.parse_options(&lang))
+        .unwrap_or_else(|e| parse_failed(&path, &source, &e));
+    apply_config(&config, &mut tm, &path, &source);
+    if let Err(e) = bfint::tui::run(tm, &source) {
+        report::error(&e.to_string());
+        std::process::exit(1);
+    }
+}
+
+///the "dap" command, which speaks the debug adapter protocol with an editor over stdin and stdout
+#[cfg(feature = "dap")]
+fn dap_command() {
+    let mut server = bfint::dap::Server::new(std::io::stdout());
+    if let Err(e) = server.serve(std::io::stdin().lock()) {
+        report::error(&e.to_string());
+        std::process::exit(1);
+    }
+}
+
+///the "lsp" command, which speaks the language server protocol with an editor over stdin and
+///stdout. it exits with 1 when the editor didn't ask for a shutdown before it said to exit
+#[cfg(feature = "lsp")]
+fn lsp_command() {
+    let mut server = bfint::lsp::Server::new(std::io::stdout());
+    match server.serve(std::io::stdin().lock()) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            report::error(&e.to_string());
+            std::process::exit(1);
+        }
+    }
+}
+
+///the "rpc" command, which answers json-rpc requests on stdin and stdout, see rpc.rs
+#[cfg(feature = "rpc")]
+fn rpc_command() {
+    let mut service = bfint::rpc::Service::new();
+    if let Err(e) = service.serve(std::io::stdin().lock(), std::io::stdout()) {
+        report::error(&e.to_string());
+        std::process::exit(1);
+    }
+}
+
+///the "serve" command, which runs the web playground until ctrl+c, see server.rs. requests being
+///answered when it's stopped are finished first
+#[cfg(feature = "server")]
+fn serve_command(mut args: impl Iterator<Item = String>) {
+    let mut host = String::from("127.0.0.1");
+    let mut port = 8080;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => host = args.next().unwrap_or_else(|| usage()),
+            "--port" => {
+                port = args
+                    .next()
+                    .and_then(|port| port.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            _ => usage(),
+        }
+    }
+    let server = bfint::server::Server::bind((host.as_str(), port), Default::default())
+        .unwrap_or_else(|e| {
+            report::error(&format!("could not listen on {}:{}: {}", host, port, e));
+            std::process::exit(1);
+        });
+    let stop = std::sync::Arc::new(AtomicBool::new(false));
+    let stopping = std::sync::Arc::clone(&stop);
+    if let Err(e) = ctrlc::set_handler(move || stopping.store(true, Ordering::Relaxed)) {
+        report::error(&format!("could not catch ctrl+c: {}", e));
+    }
+    if let Ok(address) = server.local_addr() {
+        report::report(
+            "serve",
+            &format!("serving the playground on http://{}", address),
+        );
+    }
+    if let Err(e) = server.serve(&stop) {
+        report::error(&e.to_string());
+        std::process::exit(1);
+    }
+}
+
+///the "test" command, which runs a directory of programs and compares what they print with
+///what they should print. given a program instead of a directory, it runs that one program with
+///the input and output its comments give, see fixtures::inline
+fn test_command(mut args: impl Iterator<Item = String>) {
+    let mut directory = None;
+    let mut filter = String::new();
+    let mut format = "text".to_string();
+    let mut step_limit = 10_000_000;
+    let mut timeout = Duration::from_secs(10);
+    let mut update_baselines = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--filter" => filter = args.next().unwrap_or_else(|| usage()),
+            "--update-baselines" => update_baselines = true,
+            "--format" => {
+                format = args
+                    .next()
+                    .filter(|format| ["text", "tap", "json"].contains(&format.as_str()))
+                    .unwrap_or_else(|| usage());
+            }
+            "--max-steps" => {
+                step_limit = args
+                    .next()
+                    .and_then(|steps| steps.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--timeout" => {
+                timeout = args
+                    .next()
+                    .and_then(|timeout| parse_duration(&timeout))
+                    .unwrap_or_else(|| usage());
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => directory = Some(arg),
+        }
+    }
+    let mut directory = directory.unwrap_or_else(|| usage());
+    let path = std::path::Path::new(&directory);
+    let mut fixtures = match path.is_file() {
+        true => vec![fixtures::inline(path).unwrap_or_else(|e| {
+            report::error(&format!("{}: {}", directory, e));
+            std::process::exit(1);
+        })],
+        false => fixtures::discover(path).unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", directory, e));
+            std::process::exit(1);
+        }),
+    };
+    //a single program goes by the step counts of the directory it's in
+    if path.is_file() {
+        directory = match path.parent().map(|parent| parent.display().to_string()) {
+            Some(parent) if !parent.is_empty() => parent,
+            _ => ".".to_string(),
+        };
+    }
+    fixtures.retain(|fixture| fixture.name.contains(&filter));
+    //every test gets a machine of its own, so they can all run side by side, a few at a time
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let outcomes = std::sync::Mutex::new(vec![None; fixtures.len()]);
+    let workers = std::thread::available_parallelism().map_or(1, |workers| workers.get());
+    std::thread::scope(|scope| {
+        for _ in 0..workers.min(fixtures.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(fixture) = fixtures.get(index) else {
+                    return;
+                };
+                let outcome = fixtures::run(fixture, step_limit, timeout);
+                outcomes.lock().unwrap()[index] = Some(outcome);
+            });
+        }
+    });
+    let baselines_path = std::path::Path::new(&directory).join(fixtures::BASELINES_FILE);
+    let mut baselines = match std::fs::read_to_string(&baselines_path) {
+        Ok(text) => fixtures::Baselines::parse(&text).unwrap_or_else(|e| {
+            report::error(&format!("{}: {}", baselines_path.display(), e));
+            std::process::exit(1);
+        }),
+        Err(_) => fixtures::Baselines::default(),
+    };
+    let outcomes: Vec<fixtures::Outcome> = outcomes
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .zip(&fixtures)
+        .map(|(outcome, fixture)| {
+            let (outcome, steps) = outcome.expect("every test has been run");
+            match outcome {
+                //a step count that's changed on purpose isn't a failure when updating
+                fixtures::Outcome::Passed if !update_baselines => baselines
+                    .check(&fixture.name, steps)
+                    .unwrap_or(fixtures::Outcome::Passed),
+                fixtures::Outcome::Passed if steps > 0 => {
+                    let old = baselines.steps.insert(fixture.name.clone(), steps);
+                    match old {
+                        Some(old) if old != steps => report::report(
+                            "baselines",
+                            &format!("{}: {} steps, used to be {}", fixture.name, steps, old),
+                        ),
+                        Some(_) => {}
+                        None => report::report(
+                            "baselines",
+                            &format!("{}: {} steps", fixture.name, steps),
+                        ),
+                    }
+                    outcome
+                }
+                outcome => outcome,
+            }
+        })
+        .collect();
+    if update_baselines {
+        if let Err(e) = std::fs::write(&baselines_path, baselines.to_toml()) {
+            report::error(&format!(
+                "could not write {}: {}",
+                baselines_path.display(),
+                e
+            ));
+            std::process::exit(1);
+        }
+    }
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| **outcome != fixtures::Outcome::Passed)
+        .count();
+    match format.as_str() {
+        "tap" => {
+            println!("1..{}", fixtures.len());
+            for (number, (fixture, outcome)) in fixtures.iter().zip(&outcomes).enumerate() {
+                match outcome {
+                    fixtures::Outcome::Passed => println!("ok {} - {}", number + 1, fixture.name),
+                    _ => {
+                        println!("not ok {} - {}", number + 1, fixture.name);
+                        for line in failure(&fixture.expected, outcome).lines() {
+                            println!("# {}", line);
+                        }
+                    }
+                }
+            }
+        }
+        "json" => {
+            let results: Vec<String> = fixtures
+                .iter()
+                .zip(&outcomes)
+                .map(|(fixture, outcome)| {
+                    let passed = *outcome == fixtures::Outcome::Passed;
+                    format!(
+                        "{{\"name\": {}, \"passed\": {}, \"message\": {}}}",
+                        report::json_string(&fixture.name),
+                        passed,
+                        report::json_string(&failure(&fixture.expected, outcome))
+                    )
+                })
+                .collect();
+            println!(
+                "{{\"passed\": {}, \"failed\": {}, \"tests\": [{}]}}",
+                fixtures.len() - failed,
+                failed,
+                results.join(", ")
+            );
+        }
+        _ => {
+            for (fixture, outcome) in fixtures.iter().zip(&outcomes) {
+                match outcome {
+                    fixtures::Outcome::Passed => println!("ok   {}", fixture.name),
+                    _ => {
+                        println!("FAIL {}", fixture.name);
+                        for line in failure(&fixture.expected, outcome).lines() {
+                            println!("     {}", line);
+                        }
+                    }
+                }
+            }
+            println!("{} passed, {} failed", fixtures.len() - failed, failed);
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn verify_command(mut args: impl Iterator<Item = String>) {
+    let mut paths = vec![];
+    let mut lang = None;
+    let mut jobs = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                lang = Some(
+                    args.next()
+                        .and_then(|name| lang::Lang::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--jobs" => {
+                jobs = Some(
+                    args.next()
+                        .and_then(|jobs| jobs.parse().ok())
+                        .filter(|jobs| *jobs > 0)
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    let [program_path, spec_path] = &paths[..] else {
+        usage();
+    };
+    let source = read_program(program_path);
+    let lang = lang.unwrap_or_else(|| lang::Lang::from_path(program_path));
+    //every case runs on a machine of its own, and brainfork's "Y" would make more
+    if lang == lang::Lang::Brainfork {
+        report::error("verify can't run brainfork programs");
+        std::process::exit(2);
+    }
+    let tm = TuringMachine::new(&source, &lang)
+        .unwrap_or_else(|e| parse_failed(program_path, &source, &e));
+    let text = std::fs::read_to_string(spec_path).unwrap_or_else(|e| {
+        report::error(&format!("could not read {}: {}", spec_path, e));
+        std::process::exit(1);
+    });
+    let spec = match spec_path.ends_with(".json") {
+        true => spec::Spec::from_json(&text),
+        false => spec::Spec::from_toml(&text),
+    };
+    let spec = spec.unwrap_or_else(|e| {
+        report::error(&format!("{}: {}", spec_path, e));
+        std::process::exit(1);
+    });
+    let jobs =
+        jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+    let outcomes = spec.run(tm.program(), jobs, |_| {});
+    let mut failed = 0;
+    for (case, (outcome, _)) in spec.cases.iter().zip(&outcomes) {
+        match outcome {
+            fixtures::Outcome::Passed => println!("ok   {}", case.name),
+            _ => {
+                failed += 1;
+                println!("FAIL {}", case.name);
+                for line in failure(&case.expected_output, outcome).lines() {
+                    println!("     {}", line);
+                }
+            }
+        }
+    }
+    println!("{} passed, {} failed", spec.cases.len() - failed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+///the "crosscheck" command, which runs a directory of programs under several configs and
+///compares their output, see crosscheck.rs
+fn crosscheck_command(mut args: impl Iterator<Item = String>) {
+    let mut directory = None;
+    let mut configs_path = None;
+    let mut step_limit = spec::DEFAULT_MAX_STEPS;
+    let mut json_path = String::from("crosscheck.json");
+    let mut dumps = String::from("crosscheck-dumps");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--configs" => configs_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--max-steps" => {
+                step_limit = args
+                    .next()
+                    .and_then(|steps| steps.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--json" => json_path = args.next().unwrap_or_else(|| usage()),
+            "--dumps" => dumps = args.next().unwrap_or_else(|| usage()),
+            _ if arg.starts_with('-') => usage(),
+            _ => directory = Some(arg),
+        }
+    }
+    let (Some(directory), Some(configs_path)) = (directory, configs_path) else {
+        usage();
+    };
+    let configs = std::fs::read_to_string(&configs_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| crosscheck::Configs::parse(&text).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            report::error(&format!("{}: {}", configs_path, e));
+            std::process::exit(1);
+        });
+    let entries = crosscheck::discover(std::path::Path::new(&directory)).unwrap_or_else(|e| {
+        report::error(&format!("could not read {}: {}", directory, e));
+        std::process::exit(1);
+    });
+    let rows: Vec<_> = entries
+        .iter()
+        .map(|entry| crosscheck::check(entry, &configs, step_limit))
+        .collect();
+    print!("{}", crosscheck::render(&configs, &rows));
+    let dumps = std::path::Path::new(&dumps);
+    let mut mismatches = 0;
+    for row in &rows {
+        for (verdict, (name, config)) in row.verdicts.iter().zip(&configs.configs) {
+            match verdict {
+                crosscheck::Verdict::Match => {}
+                crosscheck::Verdict::Unusable(reason) => {
+                    report::error(&format!("{} under {}: {}", row.name, name, reason));
+                }
+                crosscheck::Verdict::Mismatch { dump, tape, .. } => {
+                    mismatches += 1;
+                    let path = dumps.join(crosscheck::dump_name(&row.name, name));
+                    match std::fs::create_dir_all(dumps)
+                        .and_then(|()| std::fs::write(&path, dump.to_text()))
+                    {
+                        Ok(()) => report::error(&format!(
+                            "{}: {}, see bfint inspect {} {}{}",
+                            row.name,
+                            dump.error,
+                            config.level,
+                            path.display(),
+                            match tape.is_empty() {
+                                true => String::new(),
+                                false => format!(
+                                    "\nthe tape there, against {}'s:\n{}",
+                                    configs.configs[configs.reference].0,
+                                    tape.to_string().trim_end()
+                                ),
+                            }
+                        )),
+                        Err(e) => {
+                            report::error(&format!("could not write {}: {}", path.display(), e))
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let json = crosscheck::to_json(&configs, &rows, dumps);
+    if let Err(e) = std::fs::write(&json_path, json) {
+        report::error(&format!("could not write {}: {}", json_path, e));
+        std::process::exit(1);
+    }
+    println!(
+        "{} programs, {} mismatches, written to {}",
+        rows.len(),
+        mismatches,
+        json_path
+    );
+    if mismatches > 0 {
+        std::process::exit(1);
+    }
+}
+
+///why a test failed, with the lines of output that differ marked "-" for what was expected and
+///"+" for what came out. empty for tests that passed
+fn failure(expected: &[u8], outcome: &fixtures::Outcome) -> String {
+    match outcome {
+        fixtures::Outcome::Passed => String::new(),
+        fixtures::Outcome::Failed(reason) => reason.clone(),
+        fixtures::Outcome::StepsChanged { baseline, steps } => {
+            format!("took {} steps where it used to take {}", steps, baseline)
+        }
+        fixtures::Outcome::WrongOutput(actual) => {
+            let expected = String::from_utf8_lossy(expected);
+            let actual = String::from_utf8_lossy(actual);
+            let expected: Vec<&str> = expected.split_inclusive('\n').collect();
+            let actual: Vec<&str> = actual.split_inclusive('\n').collect();
+            let mut diff = String::from("the output is different:\n");
+            for line in 0..expected.len().max(actual.len()) {
+                match (expected.get(line), actual.get(line)) {
+                    (Some(expected), Some(actual)) if expected == actual => {}
+                    (expected, actual) => {
+                        if let Some(expected) = expected {
+                            diff.push_str(&format!("-{:?}\n", expected));
+                        }
+                        if let Some(actual) = actual {
+                            diff.push_str(&format!("+{:?}\n", actual));
+                        }
+                    }
+                }
+            }
+            diff
+        }
+    }
+}
+
+///takes -q, -v, -vv, -vvv and --message-format out of the arguments, wherever they are, and
+///sets up bfint's messages to match, see report.rs. warnings are shown unless there's a -q,
+///which leaves only errors
+fn verbosity(args: Vec<String>) -> Vec<String> {
+    let mut level = log::LevelFilter::Warn;
+    let mut format = report::MessageFormat::Text;
+    let mut kept = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-q" | "--quiet" => level = log::LevelFilter::Error,
+            "-v" | "--verbose" => level = log::LevelFilter::Info,
+            "-vv" => level = log::LevelFilter::Debug,
+            "-vvv" => level = log::LevelFilter::Trace,
+            "--message-format" => {
+                let name = args.next().unwrap_or_else(|| usage());
+                format = report::MessageFormat::from_name(&name).unwrap_or_else(|| usage());
+            }
+            _ => kept.push(arg),
+        }
+    }
+    report::init(level, format);
+    kept
+}
+
+///takes --color out of the arguments, wherever it is, and turns coloring on or off to match.
+///auto, the default, colors what goes to a terminal unless NO_COLOR is set
+fn color(args: Vec<String>) -> Vec<String> {
+    let mut choice = String::from("auto");
+    let mut kept = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--color") {
+            Some("") => choice = args.next().unwrap_or_else(|| usage()),
+            Some(rest) if rest.starts_with('=') => choice = rest[1..].to_string(),
+            _ => kept.push(arg),
+        }
+    }
+    let enabled = match choice.as_str() {
+        "always" => true,
+        "never" => false,
+        "auto" => {
+            use std::io::IsTerminal;
+            std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+        }
+        _ => usage(),
+    };
+    bfint::style::set_enabled(enabled);
+    kept
+}
+
+///what bfint exits with when the program went wrong while running, or something it needed did,
+///like its input. anything else that fails, like a file that can't be read, exits with it too
+const EXIT_RUNTIME: i32 = 1;
+///what bfint exits with when the program doesn't parse. 2 is for options that don't make sense
+const EXIT_PARSE: i32 = 3;
+///what bfint exits with when the program was stopped by a limit it was given, see
+///RuntimeError::is_limit
+const EXIT_LIMIT: i32 = 4;
+
+///what bfint exits with for the error
+fn exit_code(error: &RuntimeError) -> i32 {
+    match error.is_limit() {
+        true => EXIT_LIMIT,
+        false => EXIT_RUNTIME,
+    }
+}
+
+///why a run stopped before the program did, and what bfint exits with for it
+#[derive(Debug, Clone)]
+struct Failed {
+    message: String,
+    code: i32,
+}
+
+impl From<String> for Failed {
+    ///something the run needed went wrong, like reading the input
+    fn from(message: String) -> Self {
+        Failed {
+            message,
+            code: EXIT_RUNTIME,
+        }
+    }
+}
+
+impl From<RuntimeError> for Failed {
+    fn from(error: RuntimeError) -> Self {
+        Failed {
+            message: error.to_string(),
+            code: exit_code(&error),
+        }
+    }
+}
+
+///what bfint exits with when whatever was reading its output went away, see epipe
+static EPIPE_EXIT_CODE: AtomicI32 = AtomicI32::new(141);
+
+///takes --epipe out of the arguments, wherever it is. it says what to exit with when stdout is
+///closed before everything is written to it, like with "bfint yes.bf | head". error, the default,
+///exits with 141, what a program killed by SIGPIPE gives, so "set -o pipefail" still notices, and
+///ok exits with 0. either way nothing is said about it, it's not the program's fault
+fn epipe(args: Vec<String>) -> Vec<String> {
+    let mut choice = String::from("error");
+    let mut kept = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--epipe") {
+            Some("") => choice = args.next().unwrap_or_else(|| usage()),
+            Some(rest) if rest.starts_with('=') => choice = rest[1..].to_string(),
+            _ => kept.push(arg),
+        }
+    }
+    let code = match choice.as_str() {
+        "ok" => 0,
+        "error" => 141,
+        _ => usage(),
+    };
+    EPIPE_EXIT_CODE.store(code, Ordering::Relaxed);
+    //println! panics when stdout is closed, which is just the reader going away for the
+    //subcommands that print with it, so that stops quietly too
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<String>() {
+            Some(message) => message.as_str(),
+            None => info.payload().downcast_ref::<&str>().copied().unwrap_or(""),
+        };
+        match message.starts_with("failed printing to stdout") && message.contains("Broken pipe") {
+            true => output_closed(),
+            false => default_hook(info),
+        }
+    }));
+    kept
+}
+
+///stops bfint after whatever was reading its output went away, with the exit code --epipe chose
+fn output_closed() -> ! {
+    std::process::exit(EPIPE_EXIT_CODE.load(Ordering::Relaxed))
+}
+
+///stops bfint after writing the output failed, quietly if it's because the output was closed
+fn write_failed(e: std::io::Error) {
+    if e.kind() == std::io::ErrorKind::BrokenPipe {
+        output_closed();
+    }
+    report::error(&format!("could not write output: {}", e));
+    std::process::exit(1);
+}
+
+fn main() {
+    let args = epipe(verbosity(color(std::env::args().collect())));
+    let subcommand_args = || args.clone().into_iter().skip(2);
+    match args.get(1).map(String::as_str) {
+        Some("test") => return test_command(subcommand_args()),
+        Some("verify") => return verify_command(subcommand_args()),
+        Some("crosscheck") => return crosscheck_command(subcommand_args()),
+        #[cfg(feature = "tui")]
+        Some("tui") => return tui_command(subcommand_args()),
+        #[cfg(feature = "dap")]
+        Some("dap") => return dap_command(),
+        #[cfg(feature = "lsp")]
+        Some("lsp") => return lsp_command(),
+        #[cfg(feature = "server")]
+        Some("serve") => return serve_command(subcommand_args()),
+        #[cfg(feature = "rpc")]
+        Some("rpc") => return rpc_command(),
+        Some("repl") => return repl_command(subcommand_args()),
+        Some("debug") => return debug_command(subcommand_args()),
+        Some("generate") => return generate_command(subcommand_args()),
+        Some("analyze") => return analyze_command(subcommand_args()),
+        Some("inspect") => return inspect_command(subcommand_args()),
+        Some("provenance") => return provenance_command(subcommand_args()),
+        Some("cache") => return cache_command(subcommand_args()),
+        Some("capabilities") => return capabilities_command(subcommand_args()),
+        Some("self-test") => return self_test_command(subcommand_args()),
+        Some("bench") => return bench_command(subcommand_args()),
+        Some("equiv") => return equiv_command(subcommand_args()),
+        Some("tracediff") => return tracediff_command(subcommand_args()),
+        Some("pipeline") => return pipeline_command(subcommand_args()),
+        Some("check") => return check_command(subcommand_args()),
+        Some("disasm") => return disasm_command(subcommand_args()),
+        Some("fmt") => return format_command(subcommand_args()),
+        Some("minify") => return minify_command(subcommand_args()),
+        Some("obfuscate") => return obfuscate_command(subcommand_args()),
+        Some("reduce") => return reduce_command(subcommand_args()),
+        Some("examples") => return examples_command(subcommand_args()),
+        Some("joust") => return joust_command(subcommand_args()),
+        _ => {}
+    }
+    let mut paths = vec![];
+    let mut shared_tape = false;
+    let mut stage_step_limit = None;
+    let mut target = None;
+    let mut output_rate = 0;
+    let mut progress = false;
+    let mut state_log_every = None;
+    let mut state_log_path = None;
+    let mut delay = Duration::ZERO;
+    let mut delay_every = 1;
+    let mut record = None;
+    let mut outputs = vec![];
+    let mut replay = None;
+    let mut input_path = None;
+    let mut channels = vec![];
+    let mut example = None;
+    let mut capture_path = None;
+    let mut checkpoint_path = None;
+    let mut checkpoint_every = Duration::from_secs(60);
+    let mut resume_path = None;
+    let mut coredump_directory = None;
+    let mut no_input = false;
+    let mut check_leaks = false;
+    let mut runtime_warnings = false;
+    let mut no_warn = vec![];
+    let mut watch_files = false;
+    let mut watch_clear = false;
+    let mut allowed_leaks = vec![];
+    let mut strict = false;
+    let mut io_command = None;
+    let mut transcript_path = None;
+    let mut on_error = None;
+    let mut history = None;
+    let mut watch = None;
+    let mut pipeline = None;
+    let mut print_ir_after = None;
+    let mut cache_dir = None;
+    let mut no_cache = false;
+    let mut stats = false;
+    //whether --target's output ends with the provenance
+    let mut stamp = false;
+    let mut profile_path = None;
+    let mut folded_path = None;
+    let mut heatmap_path = None;
+    let mut heatmap_every = None;
+    let mut heatmap_cells = None;
+    let mut chrome_path = None;
+    let mut sample_clock = None;
+    let mut offline = false;
+    let mut batch = None;
+    let mut out_dir = None;
+    let mut jobs = None;
+    let mut nonblocking = false;
+    let mut sandbox = false;
+    let mut sentinel = None;
+    let mut prompt = Some(String::from("bf: waiting for input (1 byte)..."));
+    //"bfint run program.bf" is the same as "bfint program.bf"
+    let skip = match args.get(1).map(String::as_str) {
+        Some("run") => 2,
+        _ => 1,
+    };
+    let mut config = initial_config(&args);
+    let mut args = args.into_iter().skip(skip);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            _ if config_option(&mut config, &arg, &mut args) => {}
+            "--no-input" => no_input = true,
+            "--check-leaks" => check_leaks = true,
+            "--runtime-warnings" => runtime_warnings = true,
+            "--no-warn" => {
+                let names = args.next().unwrap_or_else(|| usage());
+                for name in names.split(',') {
+                    no_warn.push(Detector::from_name(name).unwrap_or_else(|| {
+                        report::error(&format!(
+                            "there's no runtime warning called {}, the ones there are are {}",
+                            name,
+                            Detector::ALL.map(|detector| detector.name()).join(", ")
+                        ));
+                        std::process::exit(2);
+                    }));
+                }
+            }
+            "--watch" => watch_files = true,
+            "--watch-clear" => watch_clear = true,
+            "--leak-allow" => allowed_leaks.extend(cell_ranges("--leak-allow", args.next())),
+            "--strict" => strict = true,
+            "--io-command" => io_command = Some(args.next().unwrap_or_else(|| usage())),
+            "--input-nonblocking" => nonblocking = true,
+            "--sandbox" => sandbox = true,
+            "--input-prompt" => prompt = Some(args.next().unwrap_or_else(|| usage())),
+            "--no-input-prompt" => prompt = None,
+            "--input-sentinel" => {
+                sentinel = Some(
+                    args.next()
+                        .and_then(|sentinel| sentinel.parse::<u8>().ok())
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--shared-tape" => shared_tape = true,
+            "--stage-max-steps" => {
+                stage_step_limit = Some(
+                    args.next()
+                        .and_then(|limit| limit.parse::<u64>().ok())
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--output-rate" => {
+                output_rate = args
+                    .next()
+                    .and_then(|rate| rate.parse().ok())
+                    .unwrap_or_else(|| usage());
+            }
+            "--progress" => progress = true,
+            "--log-state-every" => {
+                state_log_every = Some(
+                    args.next()
+                        .and_then(|every| progress::Every::from_name(&every))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--log-state-file" => state_log_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--delay" => {
+                delay = args
+                    .next()
+                    .and_then(|delay| parse_duration(&delay))
+                    .unwrap_or_else(|| usage());
+            }
+            "--delay-every" => {
+                delay_every = args
+                    .next()
+                    .and_then(|every| every.parse().ok())
+                    .filter(|every| *every > 0)
+                    .unwrap_or_else(|| usage());
+            }
+            "--checkpoint-file" => checkpoint_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--checkpoint-every" => {
+                checkpoint_every = args
+                    .next()
+                    .and_then(|every| parse_duration(&every))
+                    .unwrap_or_else(|| usage());
+            }
+            "--coredump-on-error" => {
+                coredump_directory = Some(args.next().unwrap_or_else(|| usage()))
+            }
+            "--resume" => resume_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--input" => input_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--channel" => {
+                let spec = args.next().unwrap_or_else(|| usage());
+                channels.push(ChannelSpec::parse(&spec).unwrap_or_else(|e| {
+                    report::error(&format!("--channel {}", e));
+                    std::process::exit(2);
+                }));
+            }
+            "--example" => example = Some(args.next().unwrap_or_else(|| usage())),
+            "--capture-input" => capture_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--record" => record = Some(args.next().unwrap_or_else(|| usage())),
+            "--output" => outputs.push(args.next().unwrap_or_else(|| usage())),
+            "--transcript" => transcript_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--on-error" => on_error = Some(args.next().unwrap_or_else(|| usage())),
+            "--history" => history = Some(history_length(args.next())),
+            "--break-on-output" => watch = Some(output_watch(args.next())),
+            "--passes" => pipeline = Some(passes(args.next())),
+            "--print-ir-after" => print_ir_after = Some(args.next().unwrap_or_else(|| usage())),
+            "--cache-dir" => cache_dir = Some(args.next().unwrap_or_else(|| usage())),
+            "--no-cache" => no_cache = true,
+            "--stats" => stats = true,
+            "--stamp" => stamp = true,
+            "--profile" => profile_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--profile-folded" => folded_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--profile-sample" => {
+                sample_clock = Some(sampler::Clock::Hertz(
+                    args.next()
+                        .and_then(|hertz| hertz.parse().ok())
+                        .filter(|hertz| *hertz > 0)
+                        .unwrap_or_else(|| usage()),
+                ))
+            }
+            "--profile-sample-steps" => {
+                sample_clock = Some(sampler::Clock::Steps(
+                    args.next()
+                        .and_then(|steps| steps.parse().ok())
+                        .filter(|steps| *steps > 0)
+                        .unwrap_or_else(|| usage()),
+                ))
+            }
+            "--trace-chrome" => chrome_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--heatmap-image" => heatmap_path = Some(args.next().unwrap_or_else(|| usage())),
+            "--heatmap-every" => {
+                heatmap_every = Some(
+                    args.next()
+                        .and_then(|every| every.parse().ok())
+                        .filter(|every| *every > 0)
+                        .unwrap_or_else(|| usage()),
+                )
+            }
+            "--heatmap-cells" => {
+                let ranges = cell_ranges("--heatmap-cells", args.next());
+                let [range] = ranges.as_slice() else {
+                    report::error("--heatmap-cells takes one range of cells, like 0..100");
+                    std::process::exit(2);
+                };
+                heatmap_cells = Some(range.clone());
+            }
+            "--offline" => offline = true,
+            "--batch" => batch = Some(args.next().unwrap_or_else(|| usage())),
+            "--out-dir" => out_dir = Some(args.next().unwrap_or_else(|| usage())),
+            "--jobs" => {
+                jobs = Some(
+                    args.next()
+                        .and_then(|jobs| jobs.parse().ok())
+                        .filter(|jobs| *jobs > 0)
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            "--replay" => replay = Some(args.next().unwrap_or_else(|| usage())),
+            "--target" => {
+                target = Some(
+                    args.next()
+                        .and_then(|name| transpile::Target::from_name(&name))
+                        .unwrap_or_else(|| usage()),
+                );
+            }
+            //the program on stdin
+            "-" => paths.push(arg),
+            _ if arg.starts_with('-') => usage(),
+            _ => paths.push(arg),
+        }
+    }
+    //an example takes the place of the program's file, and goes by the name of the file it was
+    //made from
+    let example = example.map(|name| {
+        if !paths.is_empty() || watch_files {
+            usage();
+        }
+        let example = examples::find(&name).unwrap_or_else(|| {
+            report::error(&format!(
+                "there's no example called {}, bfint examples --list shows them",
+                name
+            ));
+            std::process::exit(2);
+        });
+        paths.push(example.file.to_string());
+        example
+    });
+    //more than one program only makes sense when they share the tape
+    if paths.is_empty() || (paths.len() > 1 && !shared_tape) {
+        usage();
+    }
+    if let Err(e) = config.validate() {
+        config_failed(e);
+    }
+    //there's nothing for --strict to fail on without --check-leaks
+    if strict && !check_leaks {
+        usage();
+    }
+    if !no_warn.is_empty() && !runtime_warnings {
+        usage();
+    }
+    if let Some(url) = paths.iter().find(|path| offline && program::is_url(path)) {
+        report::error(&format!(
+            "{}: --offline doesn't fetch programs from urls",
+            url
+        ));
+        std::process::exit(2);
+    }
+    if sandbox && !cfg!(all(feature = "sandbox", target_os = "linux")) {
+        match cfg!(target_os = "linux") {
+            true => report::error("--sandbox needs bfint built with the sandbox feature"),
+            false => report::error("--sandbox only works on linux"),
+        }
+        std::process::exit(2);
+    }
+    //nothing can be opened or started once the sandbox is in place, and these open files or
+    //start processes while the program runs or after it
+    if sandbox
+        && ([
+            &io_command,
+            &record,
+            &transcript_path,
+            &profile_path,
+            &folded_path,
+            &heatmap_path,
+            &chrome_path,
+            &checkpoint_path,
+            &coredump_directory,
+            &on_error,
+            &batch,
+        ]
+        .iter()
+        .any(|option| option.is_some())
+            || nonblocking
+            || watch_files
+            || target.is_some())
+    {
+        report::error(
+            "--sandbox can't be used with --io-command, --input-nonblocking, --watch, --batch,\n\
+             --target, recording, profiling, checkpoints, --coredump-on-error or --on-error",
+        );
+        std::process::exit(2);
+    }
+    if watch_clear && !watch_files {
+        usage();
+    }
+    if watch_files {
+        watch_programs(&paths, watch_clear);
+    }
+    //every stage is read and parsed before any of them runs, so a broken last stage doesn't
+    //waste the time the ones before it take
+    //with --cache-dir, a single program is looked for in the cache of compiled programs, which
+    //skips parsing and optimizing it, see cache.rs. --print-ir-after wants to see the passes run
+    let cache = cache_dir
+        .filter(|_| !no_cache && paths.len() == 1 && print_ir_after.is_none())
+        .map(Cache::new);
+    let compiled_with = pipeline
+        .as_ref()
+        .map_or_else(|| config.level.pipeline().names(), Pipeline::names);
+    //the program from the cache and what it's kept under, or what it's going to be kept under
+    let mut cached = None;
+    let mut cache_key = None;
+    let mut stages: Vec<(String, String, lang::Lang)> = paths
+        .into_iter()
+        .map(|path| {
+            //a bf joust program has nothing to do on its own, it needs another one to play
+            if config.lang.is_none() && path.trim_end_matches(".gz").ends_with(".bfjoust") {
+                report::error(&format!("{}: bf joust programs play against each other, use bfint joust", path));
+                std::process::exit(2);
+            }
+            let source = match example {
+                Some(example) => example.source.to_string(),
+                None => read_program(&path),
+            };
+            let lang = config.lang_for(&path, &source);
+            if config.lang.is_none() {
+                log::info!("{}: reading it as {:?}", path, lang);
+            }
+            if let Some(cache) = &cache {
+                let key = Cache::key(&source, &lang, &compiled_with);
+                match cache.load(&key, &source) {
+                    Ok(hit) => {
+                        log::info!("{}: compiled already, in {}", path, cache.directory().display());
+                        cached = Some(hit);
+                        return (path, source, lang);
+                    }
+                    Err(CacheError::Missing) => {}
+                    //it's compiled again and the cache written over
+                    Err(e) => log::debug!("{}: not taken from the cache, {}", path, e),
+                }
+                cache_key = Some(key);
+            }
+            if let Err(e) = TuringMachine::with_options(&source, &config.parse_options(&lang)) {
+                let diagnostic = diagnostics::Diagnostic::from_parse_error(&e, &source);
+                report::error(&diagnostics::render_all(&path, &source, &[diagnostic]));
+                //a wrong guess at the language shows up as a parse error, so say it was a guess,
+                //though no language makes a program nested too deep read as something else
+                if config.lang.is_none() && !matches!(e, ParseError::TooDeep(..)) {
+                    report::error(&format!("{} was read as {:?}, guessed from its {}; use --lang if it's something else",
+                        path,
+                        lang,
+                        match lang::Lang::from_extension(&path) {
+                            Some(_) => "extension",
+                            None => "contents",
+                        }));
+                }
+                std::process::exit(EXIT_PARSE);
+            }
+            (path, source, lang)
+        })
+        .collect();
+    let stage_count = stages.len();
+    if let Some((path, ..)) = stages
+        .iter()
+        .find(|(_, _, lang)| sandbox && *lang == lang::Lang::BfFile)
+    {
+        report::error(&format!(
+            "{}: --sandbox can't run bf-file programs, they open files",
+            path
+        ));
+        std::process::exit(2);
+    }
+    let (mut path, mut source, lang) = stages.remove(0);
+    let mut stages = stages.into_iter();
+    if stage_count > 1
+        && (target.is_some()
+            || checkpoint_path.is_some()
+            || resume_path.is_some()
+            || lang == lang::Lang::Brainfork
+            || stages
+                .as_slice()
+                .iter()
+                .any(|(_, _, lang)| *lang == lang::Lang::Brainfork))
+    {
+        report::error(
+            "--shared-tape can't be used with brainfork, --target, checkpoints or --resume",
+        );
+        std::process::exit(2);
+    }
+    log::debug!("running {} as {:?}", path, lang);
+    let mut tm = match &cached {
+        Some(cached) => TuringMachine::with_program(std::sync::Arc::new(cached.program.clone())),
+        None => TuringMachine::with_options(&source, &config.parse_options(&lang))
+            .unwrap_or_else(|e| parse_failed(&path, &source, &e)),
+    };
+    if let Some(history) = history {
+        tm.keep_history(history);
+    }
+    if config.cell_width == CellWidth::Big
+        && (stage_count > 1
+            || target.is_some()
+            || checkpoint_path.is_some()
+            || resume_path.is_some())
+    {
+        report::error(
+            "--cell-width big can't be used with --shared-tape, --target, checkpoints or --resume",
+        );
+        std::process::exit(2);
+    }
+    apply_config(&config, &mut tm, &path, &source);
+    if io_command.is_some() && (input_path.is_some() || no_input) {
+        usage();
+    }
+    if stamp && target.is_none() {
+        usage();
+    }
+    if !channels.is_empty() && lang != lang::Lang::BfChannels {
+        report::error("--channel only goes with --ext-channels or --lang bf-channels");
+        std::process::exit(2);
+    }
+    if !channels.is_empty() && target.is_some() {
+        report::error(
+            "--channel doesn't go with --target, the translated program takes its channels\n\
+             as its arguments instead, like 1=str:hello",
+        );
+        std::process::exit(2);
+    }
+    if !outputs.is_empty() && target.is_some() {
+        report::error("--output doesn't go with --target, which prints the translated program");
+        std::process::exit(2);
+    }
+    if let Some(output) = outputs
+        .iter()
+        .enumerate()
+        .find_map(|(index, output)| outputs[..index].contains(output).then_some(output))
+    {
+        report::error(&format!("--output {} is given twice", output));
+        std::process::exit(2);
+    }
+    if config.track_writers && target.is_some() {
+        report::error(
+            "--track-writers doesn't go with --target, the translated program runs without\n\
+             bfint there to keep track",
+        );
+        std::process::exit(2);
+    }
+    if runtime_warnings && target.is_some() {
+        report::error(
+            "--runtime-warnings doesn't go with --target, the translated program runs without\n\
+             bfint there to watch it",
+        );
+        std::process::exit(2);
+    }
+    if (sentinel.is_some() && !nonblocking) || (nonblocking && no_input) {
+        usage();
+    }
+    if no_input {
+        if input_path.is_some() || capture_path.is_some() {
+            usage();
+        }
+        refuse_input(&tm, &path, &source, config.seed.is_some());
+    }
+    if let Some(resume_path) = &resume_path {
+        let checkpoint = std::fs::read_to_string(resume_path).unwrap_or_else(|e| {
+            report::error(&format!("could not read {}: {}", resume_path, e));
+            std::process::exit(1);
+        });
+        if let Err(e) = tm.resume(&source, &checkpoint) {
+            report::error(&format!("{}: {}", resume_path, e));
+            std::process::exit(1);
+        }
+    }
+    //--max-steps is fuel, so every stage with --shared-tape, every machine brainfork forks into
+    //and everything "%" runs burn from the same budget. a resumed run has already burned what it
+    //ran before the checkpoint
+    if let Some(limit) = tm.step_limit.take() {
+        tm.fuel = Some(Fuel::new(limit.saturating_sub(tm.steps)));
+    }
+    //how many instructions the programs have, and how many of them the optimizer took out, for
+    //--stats
+    let mut instructions = match &cached {
+        Some(cached) => cached.parsed,
+        None => tm.find(|_| true).len(),
+    };
+    //the loops that got hot with --tiered, in every stage, for --stats
+    let mut tier_ups = vec![];
+    let mut optimized = 0;
+    //how the program is optimized, for the provenance
+    let optimization = match &pipeline {
+        Some(passes) => format!("--passes {}", passes.names().join(",")),
+        None => config.level.to_string(),
+    };
+    //-O0 is the same as not optimizing at all
+    let pipeline = Some(pipeline.unwrap_or_else(|| config.level.pipeline()))
+        .filter(|pipeline| !pipeline.is_empty());
+    if let Some(name) = &print_ir_after {
+        if !pipeline.as_ref().is_some_and(|pipeline| pipeline.has(name)) {
+            report::error(&format!(
+                "--print-ir-after {}: that pass isn't going to run",
+                name
+            ));
+            std::process::exit(2);
+        }
+    }
+    if let Some(pipeline) = &pipeline {
+        //a checkpoint points at instructions by where they are in the program, which the
+        //optimizer moves around
+        if checkpoint_path.is_some() || resume_path.is_some() {
+            report::error("optimizing can't be used with checkpoints or --resume");
+            std::process::exit(2);
+        }
+        optimized += match &cached {
+            Some(cached) => cached.parsed.saturating_sub(tm.program().len()),
+            None => optimize_program(&mut tm, pipeline, print_ir_after.as_deref(), &path),
+        };
+    }
+    if let (Some(cache), Some(cache_key)) = (&cache, &cache_key) {
+        //a run that can't write to the cache runs the same, it's only slower next time
+        if let Err(e) = cache.store(cache_key, tm.program(), instructions) {
+            log::debug!("could not write {}: {}", cache.directory().display(), e);
+        }
+    }
+    if let Some(batch) = batch {
+        //every input gets a machine of its own that nothing else can see, and runs to the end
+        //on its own
+        if stage_count > 1
+            || lang == lang::Lang::Brainfork
+            || config.cell_width == CellWidth::Big
+            || target.is_some()
+            || history.is_some()
+            || watch.is_some()
+            || on_error.is_some()
+            || sample_clock.is_some()
+            || [
+                &input_path,
+                &io_command,
+                &capture_path,
+                &record,
+                &transcript_path,
+                &replay,
+                &profile_path,
+                &folded_path,
+                &heatmap_path,
+                &chrome_path,
+            ]
+            .iter()
+            .any(|option| option.is_some())
+            || [&checkpoint_path, &resume_path, &coredump_directory]
+                .iter()
+                .any(|option| option.is_some())
+            || no_input
+            || check_leaks
+            || runtime_warnings
+            || !channels.is_empty()
+            || !outputs.is_empty()
+        {
+            report::error(
+                "--batch can't be used with --shared-tape, brainfork, big cells, --target,\n\
+                 other ways of giving input or output, recording, checkpoints, runtime warnings\n\
+                 or debugging",
+            );
+            std::process::exit(2);
+        }
+        let out_dir = out_dir.unwrap_or_else(|| usage());
+        let jobs = jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+        return run_batch_directory(&tm, &batch, &out_dir, jobs, &config);
+    } else if out_dir.is_some() || jobs.is_some() {
+        usage();
+    }
+    //every instruction's count goes by its index in the one program that's running, and
+    //tiering runs loops without stepping through them
+    let observed = [&profile_path, &folded_path, &heatmap_path, &chrome_path];
+    if (observed.iter().any(|option| option.is_some()) || sample_clock.is_some())
+        && (stage_count > 1 || config.tiered || target.is_some() || lang == lang::Lang::Brainfork)
+    {
+        report::error(
+            "--profile, --profile-folded, --profile-sample, --heatmap-image and --trace-chrome\n\
+             can't be used with --shared-tape, --tiered, --target or brainfork",
+        );
+        std::process::exit(2);
+    }
+    if (heatmap_every.is_some() || heatmap_cells.is_some()) && heatmap_path.is_none() {
+        report::error("--heatmap-every and --heatmap-cells only go with --heatmap-image");
+        std::process::exit(2);
+    }
+    if heatmap_path
+        .as_ref()
+        .is_some_and(|path| path.ends_with(".png") && cfg!(not(feature = "png")))
+    {
+        report::error(
+            "this bfint was built without png, build it with the \"png\" feature or write\n\
+             a .ppm file",
+        );
+        std::process::exit(2);
+    }
+    let mut observers: Vec<Box<dyn Observer + Send>> = vec![];
+    //--profile-folded is made from the same counts as --profile, or from the samples
+    let counted = profile_path.is_some() || (folded_path.is_some() && sample_clock.is_none());
+    let profile = counted.then(|| {
+        let profile = Arc::new(Mutex::new(listing::Profile::new(tm.program())));
+        observers.push(Box::new(Counting(Arc::clone(&profile))));
+        profile
+    });
+    let heatmap = heatmap_path.map(|heatmap_path| {
+        let heatmap = Arc::new(Mutex::new(heatmap::Heatmap::new(heatmap_every)));
+        observers.push(Box::new(Mapping(Arc::clone(&heatmap))));
+        (heatmap_path, heatmap)
+    });
+    let sampler = sample_clock.map(|clock| {
+        let sampler = Arc::new(Mutex::new(sampler::Sampler::new(clock)));
+        observers.push(Box::new(Arc::clone(&sampler)));
+        sampler
+    });
+    let chrome = chrome_path.map(|chrome_path| {
+        let trace = Arc::new(Mutex::new(chrome::ChromeTrace::new()));
+        observers.push(Box::new(Arc::clone(&trace)));
+        (chrome_path, trace)
+    });
+    if !observers.is_empty() {
+        tm.set_observer(observers);
+    }
+    //the program's output is buffered, and goes out whenever it reads or stops. it's where
+    //"--output -" writes
+    let mut stdout: (String, Box<dyn Write + Send>) = (
+        "stdout".to_string(),
+        Box::new(std::io::BufWriter::new(program_stdout())),
+    );
+    let mut command = None;
+    //a file has every byte there already, so --input-nonblocking reads one as usual
+    let waits = match (&input_path, &io_command) {
+        (Some(_), _) => false,
+        (None, Some(_)) => true,
+        (None, None) => !stdin_is_file(),
+    };
+    //the seed of --input random:, which --stats says, since "auto" makes one up
+    let mut random_seed = None;
+    //where the input comes from, for the provenance
+    let mut input_source = match (&replay, no_input) {
+        (Some(replay), _) => format!("the transcript {}", replay),
+        (None, true) => "none".to_string(),
+        (None, false) => "stdin".to_string(),
+    };
+    let input: Box<dyn Read + Send> = match (input_path, &io_command) {
+        (Some(input_path), _) => match input_path.strip_prefix("random:") {
+            Some(name) => {
+                let random = RandomInput::from_name(name).unwrap_or_else(|| {
+                    report::error(
+                        "--input random: takes a seed or auto, then a length if the input\n\
+                         should end, like random:42 or random:auto:100",
+                    );
+                    std::process::exit(2);
+                });
+                log::info!(
+                    "{}: the input is random bytes from seed {}",
+                    path,
+                    random.seed
+                );
+                random_seed = Some(random.seed);
+                input_source = format!("random bytes from seed {}", random.seed);
+                Box::new(random)
+            }
+            None => {
+                input_source = provenance::describe_file(&input_path);
+                Box::new(std::fs::File::open(&input_path).unwrap_or_else(|e| {
+                    report::error(&format!("could not read {}: {}", input_path, e));
+                    std::process::exit(1);
+                }))
+            }
+        },
+        (None, Some(io_command)) => {
+            input_source = format!("the output of {}", io_command);
+            let (spawned, from, to) = Command::spawn(io_command).unwrap_or_else(|e| {
+                report::error(&format!("could not run {}: {}", io_command, e));
+                std::process::exit(1);
+            });
+            command = Some(spawned);
+            stdout = (io_command.clone(), Box::new(to));
+            Box::new(from)
+        }
+        //whatever custom instructions try to read, there's nothing there
+        (None, None) if no_input => Box::new(std::io::empty()),
+        (None, None) => match prompt {
+            Some(prompt) if !nonblocking && prompts() => Box::new(PromptedInput {
+                input: std::io::BufReader::new(program_stdin()),
+                prompt,
+            }),
+            _ => program_stdin(),
+        },
+    };
+    let input: Box<dyn Read + Send> = match nonblocking && waits {
+        true => {
+            if io_command.is_none() {
+                keys_as_pressed();
+            }
+            Box::new(NonBlockingInput::new(input, sentinel.unwrap_or(0)))
+        }
+        false => input,
+    };
+    tm.input = match capture_path {
+        Some(capture_path) => {
+            let capture = std::fs::File::create(&capture_path).unwrap_or_else(|e| {
+                report::error(&format!("could not write {}: {}", capture_path, e));
+                std::process::exit(1);
+            });
+            Arc::new(Mutex::new(CapturedInput { input, capture }))
+        }
+        None => Arc::new(Mutex::new(input)),
+    };
+    if !channels.is_empty() {
+        input_source += &add_channels(&mut tm, &channels);
+    }
+    let mut provenance = Provenance {
+        level: optimization,
+        input: input_source,
+        ..Provenance::new(&config, &lang, &source, tm.rng.state)
+    };
+    if let Some(target) = target {
+        provenance.engine = format!("translated to {}", target.name());
+    }
+    let state_log = state_log_every.map(|every| {
+        let out: Box<dyn Write + Send> = match &state_log_path {
+            Some(state_log_path) => Box::new(
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(state_log_path)
+                    .unwrap_or_else(|e| {
+                        report::error(&format!("could not write {}: {}", state_log_path, e));
+                        std::process::exit(1);
+                    }),
+            ),
+            None => Box::new(std::io::stderr()),
+        };
+        Arc::new(Mutex::new(StateLog::new(every, out)))
+    });
+    match &state_log {
+        None if progress => tm.set_progress_callback(1_000_000, show_progress),
+        None => {}
+        //there's one progress callback, so it's called as often as both of them need
+        Some(state_log) => {
+            let log_every = lock(state_log).every().check_every();
+            let every = match progress {
+                true => gcd(log_every, 1_000_000),
+                false => log_every,
+            };
+            let state_log = Arc::clone(state_log);
+            tm.set_progress_callback(every, move |now| {
+                lock(&state_log).update(now, peak_memory);
+                //a step count that isn't a multiple is the call at the end of the run
+                match progress && (now.steps % 1_000_000 == 0 || now.steps % every != 0) {
+                    true => show_progress(now),
+                    false => ControlFlow::Continue(()),
+                }
+            });
+        }
+    }
+    if runtime_warnings {
+        let detectors = Detector::ALL
+            .into_iter()
+            .filter(|detector| !no_warn.contains(detector))
+            .collect::<Vec<_>>();
+        tm.set_runtime_warnings(&detectors, report::runtime_warning);
+    }
+    match target {
+        Some(target) => {
+            print!("{}", transpile::transpile(&tm, target, &path));
+            if stamp {
+                print!("{}", provenance.to_comment());
+            }
+        }
+        None => {
+            catch_interrupt(tm.control_handle());
+            //no delay runs the usual way, without sleeping at all
+            let delay = match delay.is_zero() {
+                true => None,
+                false => {
+                    //ctrl+c is checked for between every sleep, not only every so often
+                    tm.control_interval = delay_every;
+                    Some((delay, delay_every))
+                }
+            };
+            let brainfork = lang == lang::Lang::Brainfork;
+            //a readable transcript is made from the same recording as a json one
+            let recording = record.is_some() || transcript_path.is_some();
+            let transcribing = match (recording, &replay) {
+                (false, None) => Transcribing::Off,
+                //brainfork's machines read and write side by side, there's no one order to record
+                _ if brainfork => {
+                    report::error("brainfork programs can't be recorded or replayed");
+                    std::process::exit(2);
+                }
+                (true, None) => Transcribing::Record(transcript::Transcript::new()),
+                (false, Some(replay_path)) => {
+                    let replayed = std::fs::read_to_string(replay_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|json| {
+                            transcript::Transcript::from_json(&json).map_err(|e| e.to_string())
+                        })
+                        .unwrap_or_else(|e| {
+                            report::error(&format!("could not read {}: {}", replay_path, e));
+                            std::process::exit(1);
+                        });
+                    Transcribing::Replay(transcript::Replay::new(replayed))
+                }
+                (true, Some(_)) => usage(),
+            };
+            if brainfork && (checkpoint_path.is_some() || resume_path.is_some()) {
+                report::error("brainfork programs can't be checkpointed");
+                std::process::exit(2);
+            }
+            //brainfork's machines are gone by the time the run stops, there's no tape left to
+            //look at
+            if brainfork && check_leaks {
+                report::error("brainfork programs can't have their tape checked for leaks");
+                std::process::exit(2);
+            }
+            if brainfork && output_rate > 0 {
+                report::error("brainfork programs can't have their output rate limited");
+                std::process::exit(2);
+            }
+            if brainfork && watch.is_some() {
+                report::error("brainfork programs can't have their output watched");
+                std::process::exit(2);
+            }
+            //everything that looks at the output gets it from the tee, the transcript before
+            //it's written so a byte that doesn't match isn't, and the watch after
+            let transcribing = Arc::new(Mutex::new(transcribing));
+            let now = Now::default();
+            let watch = watch.map(|watch| Arc::new(Mutex::new(watch)));
+            let mut tee = Tee::new();
+            if !matches!(*lock(&transcribing), Transcribing::Off) {
+                tee.add_unnamed(Transcribed {
+                    transcribing: Arc::clone(&transcribing),
+                    now: Arc::clone(&now),
+                });
+            }
+            if outputs.is_empty() {
+                outputs.push("-".to_string());
+            }
+            let mut stdout = Some(stdout);
+            for output in &outputs {
+                match output.as_str() {
+                    "-" => {
+                        let (name, stdout) = stdout.take().expect("- was only given once");
+                        tee.add(&name, stdout);
+                    }
+                    _ => {
+                        let file = std::fs::File::create(output).unwrap_or_else(|e| {
+                            report::error(&format!("could not write {}: {}", output, e));
+                            std::process::exit(1);
+                        });
+                        tee.add(output, std::io::BufWriter::new(file));
+                    }
+                }
+            }
+            if let Some(watch) = &watch {
+                tee.add_unnamed(Watching(Arc::clone(watch)));
+            }
+            tm.output = Arc::new(Mutex::new(tee));
+            //everything the run needs is open by now
+            #[cfg(all(feature = "sandbox", target_os = "linux"))]
+            if sandbox {
+                if let Err(e) = bfint::sandbox::enter(&bfint::sandbox::Limits::default()) {
+                    report::error(&e.to_string());
+                    std::process::exit(1);
+                }
+            }
+            let mut checkpointing = checkpoint_path.map(|path| Checkpointing {
+                path,
+                every: checkpoint_every,
+                source: source.clone(),
+                provenance: provenance.to_text(),
+                saved_at: Instant::now(),
+            });
+            let mut stage = 1;
+            let result = loop {
+                if brainfork {
+                    break fork::run(tm.clone()).map_err(Failed::from);
+                }
+                //the limit of the whole run is the fuel, which still counts when the stage's
+                //own limit is further off
+                if let Some(stage_step_limit) = stage_step_limit {
+                    tm.step_limit = Some(tm.steps + stage_step_limit);
+                }
+                //a rate of 0 means no limit
+                let throttle = (output_rate > 0).then(|| Throttle::new(output_rate));
+                let result = run_events(
+                    &mut tm,
+                    delay,
+                    &transcribing,
+                    &now,
+                    checkpointing.as_mut(),
+                    throttle,
+                );
+                let Ok(RunOutcome::Halted) = result else {
+                    break result;
+                };
+                let Some((next_path, next_source, next_lang)) = stages.next() else {
+                    break result;
+                };
+                //the tape and the pointer carry on into the next stage, the program starts over,
+                //and what was found out about its loops goes with it
+                tier_ups.extend(tm.tiered_loops().into_iter().map(|up| (path.clone(), up)));
+                let next = program::parse(&next_source, &config.parse_options(&next_lang))
+                    .expect("every stage was parsed before running");
+                tm.load_program(std::sync::Arc::new(next));
+                instructions += tm.find(|_| true).len();
+                if let Some(pipeline) = &pipeline {
+                    optimized +=
+                        optimize_program(&mut tm, pipeline, print_ir_after.as_deref(), &next_path);
+                }
+                (path, source) = (next_path, next_source);
+                stage += 1;
+                log::debug!("running stage {} of {}, {}", stage, stage_count, path);
+            };
+            //running out of --max-steps is a failure like any other for the command line, which
+            //only brainfork's machines give back as an outcome
+            //exiting doesn't drop anything, so whatever output is still buffered goes out now,
+            //and a program that halted didn't get all of its output out if that fails because
+            //the output was closed
+            let flushed = lock(&tm.output).flush_bytes();
+            let result = match (result, flushed) {
+                (Ok(RunOutcome::FuelExhausted { stage, position }), _) => {
+                    Err(RuntimeError::FuelExhausted(stage, position).into())
+                }
+                (Err(Failed { message: e, .. }), _) | (Ok(RunOutcome::Halted), Err(e))
+                    if e == io::OUTPUT_CLOSED =>
+                {
+                    Ok(RunOutcome::OutputClosed)
+                }
+                (result, _) => result,
+            };
+            let mut command_failed = false;
+            if let (Some(command), Some(io_command)) = (command, &io_command) {
+                //dropping the program's end of the command's input is what tells the command
+                //that nothing more is coming
+                tm.output = Arc::new(Mutex::new(std::io::sink()));
+                match command.wait() {
+                    Ok(status) if status.success() => {}
+                    Ok(status) => {
+                        report::error(&format!("{} stopped with {}", io_command, status));
+                        command_failed = true;
+                    }
+                    Err(e) => {
+                        report::error(&format!("could not wait for {}: {}", io_command, e));
+                        command_failed = true;
+                    }
+                }
+            }
+            //the transcript is written however the run ended, a run that went wrong is the one
+            //most worth having a record of
+            if let Transcribing::Record(transcript) = &*lock(&transcribing) {
+                let written = [
+                    (&record, transcript.to_json()),
+                    (&transcript_path, transcript.to_text()),
+                ];
+                for (path, text) in written {
+                    if let Some(path) = path {
+                        if let Err(e) = std::fs::write(path, text) {
+                            report::error(&format!("could not write {}: {}", path, e));
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            //the frame at the bottom of every stack is the program's file name
+            let name = std::path::Path::new(&path)
+                .file_name()
+                .map_or(path.clone(), |name| name.to_string_lossy().into_owned());
+            let sampled = sampler.as_ref().map(|sampler| {
+                let mut sampler = lock(sampler);
+                sampler.stop();
+                report::report("profile", &sampler.report(tm.program()));
+                sampler.profile(tm.program())
+            });
+            if let (Some(folded_path), Some(sampled)) = (&folded_path, &sampled) {
+                let folded = listing::folded(tm.program(), sampled, &name);
+                if let Err(e) = std::fs::write(folded_path, folded) {
+                    report::error(&format!("could not write {}: {}", folded_path, e));
+                    std::process::exit(1);
+                }
+            }
+            if let Some(profile) = &profile {
+                let profile = lock(profile);
+                let folded = match &sampled {
+                    Some(_) => None,
+                    None => Some(listing::folded(tm.program(), &profile, &name)),
+                };
+                let written = [
+                    (&profile_path, Some(profile.to_string())),
+                    (&folded_path, folded),
+                ];
+                for (path, text) in written {
+                    if let (Some(path), Some(text)) = (path, text) {
+                        if let Err(e) = std::fs::write(path, text) {
+                            report::error(&format!("could not write {}: {}", path, e));
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            if let Some((chrome_path, trace)) = &chrome {
+                if let Err(e) = std::fs::write(chrome_path, lock(trace).to_json(&path, &tm)) {
+                    report::error(&format!("could not write {}: {}", chrome_path, e));
+                    std::process::exit(1);
+                }
+            }
+            if let Some((heatmap_path, heatmap)) = &heatmap {
+                let heatmap = lock(heatmap);
+                let image = heatmap.render(heatmap_cells.clone());
+                #[cfg(feature = "png")]
+                let bytes = match heatmap_path.ends_with(".png") {
+                    true => image.png(),
+                    false => image.ppm(),
+                };
+                #[cfg(not(feature = "png"))]
+                let bytes = image.ppm();
+                if let Err(e) = std::fs::write(heatmap_path, bytes) {
+                    report::error(&format!("could not write {}: {}", heatmap_path, e));
+                    std::process::exit(1);
+                }
+                log::info!(
+                    "{}: the heat map is {}x{}, {} steps a column",
+                    heatmap_path,
+                    image.width,
+                    image.height,
+                    heatmap.every()
+                );
+            }
+            if progress {
+                report::progress_done();
+            }
+            if let Some(state_log) = &state_log {
+                let outcome = match &result {
+                    Ok(RunOutcome::Halted) => "halted".to_string(),
+                    Ok(RunOutcome::Cancelled) => "interrupted".to_string(),
+                    Ok(RunOutcome::OutputClosed) => "output closed".to_string(),
+                    Ok(RunOutcome::FuelExhausted { .. }) => {
+                        unreachable!("running out of fuel was made an error above")
+                    }
+                    Err(e) => format!("error: {}", e.message),
+                };
+                lock(state_log).finish(&tm.progress(), peak_memory(), &outcome);
+            }
+            if stats {
+                report::report(
+                    "stats",
+                    &format!(
+                        "{} instructions, {} of them taken out by the optimizer, {} steps",
+                        instructions, optimized, tm.steps
+                    ),
+                );
+                #[cfg(feature = "bigint")]
+                if let Some(big_cells) = tm.big_cells() {
+                    report::report(
+                        "stats",
+                        &format!("the largest cell held {}", big_cells.largest()),
+                    );
+                }
+                if let Some(seed) = random_seed {
+                    report::report(
+                        "stats",
+                        &format!("the input was random bytes from seed {}", seed),
+                    );
+                }
+                for (number, used) in tm.channel_use() {
+                    let ended = match used.ended {
+                        true => ", and it ran out",
+                        false => "",
+                    };
+                    report::report(
+                        "stats",
+                        &format!("read {} bytes from channel {}{}", used.read, number, ended),
+                    );
+                }
+                report::report("provenance", &provenance.to_text());
+                tier_ups.extend(tm.tiered_loops().into_iter().map(|up| (path.clone(), up)));
+                for (path, up) in &tier_ups {
+                    match up.faster {
+                        true => report::report("stats", &format!("{}:{}: the loop up to {} was tiered up, and went around {} times in {} runs",
+                            path, up.start, up.end, up.iterations, up.runs)),
+                        false => report::report("stats", &format!("{}:{}: the loop up to {} got hot, but can't be run in one go so it ran as usual",
+                            path, up.start, up.end)),
+                    }
+                }
+            }
+            match result {
+                Ok(RunOutcome::Halted) => {
+                    if let Some(position) = tm.halted_at() {
+                        log::info!("{}: stopped by the \"@\" at {}", path, position);
+                    }
+                    if check_leaks && report_leaks(&tm, &path, &allowed_leaks) > 0 {
+                        command_failed |= strict;
+                    }
+                    if command_failed {
+                        std::process::exit(1);
+                    }
+                }
+                //brainfork's machines are gone by the time the run stops, so there's no state
+                //to show
+                Ok(RunOutcome::Cancelled) if brainfork => {
+                    report::error(&format!("{}: interrupted", path));
+                    std::process::exit(130);
+                }
+                Ok(RunOutcome::Cancelled) => {
+                    report::error(&format!("{}: interrupted {}", path, tm.describe_state()));
+                    std::process::exit(130);
+                }
+                Ok(RunOutcome::OutputClosed) => {
+                    log::info!("{}: {}", path, io::OUTPUT_CLOSED);
+                    output_closed();
+                }
+                Ok(RunOutcome::FuelExhausted { .. }) => {
+                    unreachable!("running out of fuel was made an error above")
+                }
+                Err(Failed { message: e, code }) => {
+                    //there's no one place a brainfork program stops at
+                    let position = match watch.as_ref().is_some_and(|watch| lock(watch).matched()) {
+                        _ if brainfork => None,
+                        //the "." that finished the match has already run
+                        true => tm.last_position(),
+                        false => tm.position(),
+                    };
+                    let message = match stage_count {
+                        1 => e.clone(),
+                        _ => format!("{} (in stage {} of {})", e, stage, stage_count),
+                    };
+                    let diagnostic = diagnostics::Diagnostic::new(&message, position);
+                    report::error(&diagnostic.render(&path, &source));
+                    if let (Some(_), false) = (tm.writers(), brainfork) {
+                        report::report("writer", &tm.describe_cell(tm.pointer()));
+                    }
+                    //brainfork's machines are gone by now, there's nothing to dump
+                    if let (Some(directory), false) = (&coredump_directory, brainfork) {
+                        write_coredump(directory, &path, &e, &source, &tm, &provenance);
+                    }
+                    if let Some(history) = tm.history().filter(|history| !history.is_empty()) {
+                        let mut steps = format!("the last {} steps before that:", history.len());
+                        for entry in history.entries() {
+                            steps += &format!("\n  {}", entry);
+                        }
+                        report::report("history", &steps);
+                    }
+                    //the post-mortem goes to stderr, stdout only ever has the program's output
+                    if let (Some(script_path), false) = (&on_error, brainfork) {
+                        let mut debugger = debugger::Debugger::new(tm, vec![]);
+                        let mut stderr = std::io::stderr();
+                        if let Err(e) = run_debug_script(&mut debugger, script_path, &mut stderr) {
+                            report::error(&e.to_string());
+                        }
+                    }
+                    std::process::exit(code);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,299 @@
+//! the debugger for programs instead of people, like editor plugins. it takes one json object a
+//! line and answers with json objects a line, so nothing has to be scraped from what the
+//! debugger prints for people. both are front ends for the same Debugger, so they can't
+//! disagree about what a command does.
+//!
+//! every command is an object with a "cmd" and the fields that command takes:
+//!
+//! - `{"cmd": "break", "loc": "12:3"}` puts a breakpoint on the first instruction at or after
+//!   the place, `"if": "<condition>"` makes it stop only when the condition holds
+//! - `{"cmd": "step"}` runs one instruction, `"count": n` runs n of them
+//! - `{"cmd": "next"}` runs one instruction, or the whole loop when it's at a "["
+//! - `{"cmd": "finish"}` runs until the innermost loop it's in is done
+//! - `{"cmd": "continue"}` runs until a breakpoint, a matched output watch, an error or the end
+//! - `{"cmd": "read-cells"}` gives the values of cells, from `"from"` (default 0) for `"count"`
+//!   cells (default 16), as far as the tape goes
+//! - `{"cmd": "eval", "value": "cell[3] + 1"}` gives the value of something
+//! - `{"cmd": "eval-condition", "condition": "ptr == 2"}` gives whether a condition holds
+//! - `{"cmd": "quit"}` ends the session
+//!
+//! every line that comes back has a "seq", counting up from 1 over the whole session, and an
+//! "event" saying what it is:
+//!
+//! - `breakpoint`, for break: the `"id"` of the breakpoint and the `"loc"` it was asked for at
+//! - `output`, before anything else when running wrote something: the `"bytes"` and the same
+//!   as `"text"`
+//! - `stopped`: the `"reason"` (`step`, `breakpoint` or `output-matched`), the `"loc"` of the
+//!   next instruction, the `"pointer"` and the `"steps"` run. step is also the reason once next
+//!   or finish is done. a breakpoint has its `"breakpoint"` id, an output match the
+//!   `"match-loc"` of the "." that finished it
+//! - `halted`: the program has no instructions left, with the `"steps"` run
+//! - `error`: the program failed, with the `"message"` and the `"loc"` it failed at
+//! - `interrupted`: the run was stopped by ctrl+c, with the `"loc"`
+//! - `cells`: for read-cells, where they're `"from"` and their `"values"`
+//! - `value`: for eval, the `"value"`
+//! - `condition`: for eval-condition, whether it `"holds"`
+//! - `rejected`: a line that isn't a command that can be carried out, with the `"message"`
+//!   saying why. nothing has been done
+//!
+//! a "loc" is a string like "12:3", or null when the program is at its end
+use crate::debugger::{self, DebugError, Debugger, Stop, StopReason};
+use crate::report::json_string;
+use crate::Position;
+use std::collections::BTreeMap;
+
+///a field of a command
+#[derive(PartialEq, Debug, Clone)]
+enum Field {
+    Text(String),
+    Number(i64),
+}
+
+///a debugger taking json commands
+pub struct Session {
+    pub debugger: Debugger,
+    //the seq of the last event
+    seq: u64,
+}
+
+impl Session {
+    pub fn new(debugger: Debugger) -> Self {
+        Session { debugger, seq: 0 }
+    }
+    ///whether the session has been ended with quit
+    pub fn done(&self) -> bool {
+        self.debugger.quit()
+    }
+    ///carries out one command line, giving back the events it makes, a json object each
+    pub fn handle(&mut self, line: &str) -> Vec<String> {
+        let mut events = vec![];
+        if let Err(message) = self.carry_out(line, &mut events) {
+            events = vec![self.event("rejected", &[("message", json_string(&message))])];
+        }
+        events
+    }
+    fn carry_out(&mut self, line: &str, events: &mut Vec<String>) -> Result<(), String> {
+        let fields = parse_object(line)?;
+        let text = |name: &str| match fields.get(name) {
+            Some(Field::Text(text)) => Ok(Some(text.as_str())),
+            Some(Field::Number(_)) => Err(format!("\"{}\" should be a string", name)),
+            None => Ok(None),
+        };
+        let number = |name: &str| match fields.get(name) {
+            Some(Field::Number(number)) => Ok(Some(*number)),
+            Some(Field::Text(_)) => Err(format!("\"{}\" should be a number", name)),
+            None => Ok(None),
+        };
+        let wanted = |name: &str| format!("\"{}\" is missing", name);
+        let bad = |e: DebugError| e.to_string();
+        match text("cmd")?.ok_or_else(|| wanted("cmd"))? {
+            "break" => {
+                let place = text("loc")?.ok_or_else(|| wanted("loc"))?;
+                let position = debugger::parse_place(place).map_err(bad)?;
+                let id = self
+                    .debugger
+                    .add_breakpoint(position, text("if")?)
+                    .map_err(bad)?;
+                let event = self.event(
+                    "breakpoint",
+                    &[("id", id.to_string()), ("loc", location(Some(position)))],
+                );
+                events.push(event);
+            }
+            "step" => {
+                let count = number("count")?.unwrap_or(1);
+                let count = u64::try_from(count).map_err(|_| "\"count\" can't be negative")?;
+                self.resume(|debugger| debugger.resume(Some(count)), events)?;
+            }
+            "continue" => self.resume(|debugger| debugger.resume(None), events)?,
+            "next" => self.resume(Debugger::step_over, events)?,
+            "finish" => self.resume(Debugger::step_out, events)?,
+            "read-cells" => {
+                let from = number("from")?.unwrap_or(0);
+                let count = number("count")?.unwrap_or(16);
+                let (Ok(from), Ok(count)) = (usize::try_from(from), usize::try_from(count)) else {
+                    return Err("\"from\" and \"count\" can't be negative".to_string());
+                };
+                let tape = self.debugger.tm.tape();
+                let from = from.min(tape.len());
+                let values: Vec<String> = tape[from..(from.saturating_add(count)).min(tape.len())]
+                    .iter()
+                    .map(u8::to_string)
+                    .collect();
+                let values = format!("[{}]", values.join(","));
+                let event = self.event("cells", &[("from", from.to_string()), ("values", values)]);
+                events.push(event);
+            }
+            "eval" => {
+                let value = text("value")?.ok_or_else(|| wanted("value"))?;
+                let value = self.debugger.evaluate(value).map_err(bad)?;
+                let event = self.event("value", &[("value", value.to_string())]);
+                events.push(event);
+            }
+            "eval-condition" => {
+                let condition = text("condition")?.ok_or_else(|| wanted("condition"))?;
+                let holds = self.debugger.condition_holds(condition).map_err(bad)?;
+                let event = self.event("condition", &[("holds", holds.to_string())]);
+                events.push(event);
+            }
+            "quit" => self.debugger.end(),
+            command => return Err(format!("unknown command '{}'", command)),
+        }
+        Ok(())
+    }
+    ///the events for running, see Debugger::resume
+    fn resume(
+        &mut self,
+        run: impl FnOnce(&mut Debugger) -> Stop,
+        events: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let stop = run(&mut self.debugger);
+        if stop.reason == StopReason::Finished {
+            return Err("the program has already finished".to_string());
+        }
+        if !stop.output.is_empty() {
+            let bytes: Vec<String> = stop.output.iter().map(u8::to_string).collect();
+            let event = self.event(
+                "output",
+                &[
+                    ("bytes", format!("[{}]", bytes.join(","))),
+                    ("text", json_string(&String::from_utf8_lossy(&stop.output))),
+                ],
+            );
+            events.push(event);
+        }
+        let tm = &self.debugger.tm;
+        let loc = ("loc", location(tm.position()));
+        let pointer = ("pointer", tm.pointer().to_string());
+        let steps = ("steps", tm.steps.to_string());
+        let event = match stop.reason {
+            StopReason::Finished => unreachable!("finished was turned down above"),
+            StopReason::Stepped => self.event(
+                "stopped",
+                &[("reason", json_string("step")), loc, pointer, steps],
+            ),
+            StopReason::Breakpoint(id) => self.event(
+                "stopped",
+                &[
+                    ("reason", json_string("breakpoint")),
+                    ("breakpoint", id.to_string()),
+                    loc,
+                    pointer,
+                    steps,
+                ],
+            ),
+            StopReason::OutputMatched(position) => self.event(
+                "stopped",
+                &[
+                    ("reason", json_string("output-matched")),
+                    ("match-loc", location(Some(position))),
+                    loc,
+                    pointer,
+                    steps,
+                ],
+            ),
+            StopReason::Halted => self.event("halted", &[steps]),
+            StopReason::Error(e) => {
+                self.event("error", &[("message", json_string(&e.to_string())), loc])
+            }
+            StopReason::Interrupted => self.event("interrupted", &[loc]),
+        };
+        events.push(event);
+        Ok(())
+    }
+    ///an event with the next seq, the fields' values are json already
+    fn event(&mut self, event: &str, fields: &[(&str, String)]) -> String {
+        self.seq += 1;
+        let mut json = format!("{{\"seq\":{},\"event\":{}", self.seq, json_string(event));
+        for (name, value) in fields {
+            json.push_str(&format!(",{}:{}", json_string(name), value));
+        }
+        json.push('}');
+        json
+    }
+}
+
+///a place as json
+fn location(position: Option<Position>) -> String {
+    match position {
+        Some(position) => json_string(&position.to_string()),
+        None => "null".to_string(),
+    }
+}
+
+///reads a command, which is a json object whose values are strings or whole numbers
+fn parse_object(line: &str) -> Result<BTreeMap<String, Field>, String> {
+    let mut chars = line.trim().chars().peekable();
+    let mut fields = BTreeMap::new();
+    let skip_spaces = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    };
+    if chars.next() != Some('{') {
+        return Err("a command is a json object".to_string());
+    }
+    skip_spaces(&mut chars);
+    if chars.next_if_eq(&'}').is_none() {
+        loop {
+            skip_spaces(&mut chars);
+            let name = parse_string(&mut chars)?;
+            skip_spaces(&mut chars);
+            if chars.next() != Some(':') {
+                return Err(format!("there's no ':' after \"{}\"", name));
+            }
+            skip_spaces(&mut chars);
+            let value = match chars.peek() {
+                Some('"') => Field::Text(parse_string(&mut chars)?),
+                _ => {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(|c| *c == '-' || c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    Field::Number(digits.parse().map_err(|_| {
+                        format!("\"{}\" should be a string or a whole number", name)
+                    })?)
+                }
+            };
+            fields.insert(name, value);
+            skip_spaces(&mut chars);
+            match chars.next() {
+                Some(',') => {}
+                Some('}') => break,
+                _ => return Err("the fields should be separated by ','".to_string()),
+            }
+        }
+    }
+    if chars.next().is_some() {
+        return Err("there's more after the object".to_string());
+    }
+    Ok(fields)
+}
+
+///reads a json string, the '"' it starts with included
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    if chars.next() != Some('"') {
+        return Err("expected a string".to_string());
+    }
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some('r') => text.push('\r'),
+                Some('u') => {
+                    let digits: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("'\\u{}' isn't a character", digits))?;
+                    text.push(c);
+                }
+                Some(c @ ('"' | '\\' | '/')) => text.push(c),
+                _ => return Err("a string has an escape that isn't one".to_string()),
+            },
+            Some(c) => text.push(c),
+            None => return Err("a string doesn't end".to_string()),
+        }
+    }
+}
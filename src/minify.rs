@@ -0,0 +1,70 @@
+//! making a program as small as it can be: every comment left out, moves, "+" and "-" written
+//! the shortest way, and loops that can never run dropped. see optimize.rs for the passes
+use crate::lang::{self, Lang};
+use crate::{optimize, Instruction, ParseError, TuringMachine};
+use std::fmt;
+
+///everything that can keep a program from being minified
+#[derive(PartialEq, Debug)]
+pub enum MinifyError {
+    ///the language has commands longer than a single character, like ook, so there's nothing
+    ///to minify it into
+    UnsupportedLang,
+    ///the program doesn't parse, so it can't be told apart from its comments safely
+    ParseError(ParseError),
+}
+
+impl fmt::Display for MinifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MinifyError::UnsupportedLang => {
+                write!(
+                    f,
+                    "only languages with single character commands can be minified"
+                )
+            }
+            MinifyError::ParseError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+///gives back the smallest program that does the same as this one
+pub fn minify(source: &str, lang: &Lang) -> Result<String, MinifyError> {
+    if matches!(lang, Lang::Ook | Lang::Spoon | Lang::Mapped(_)) {
+        return Err(MinifyError::UnsupportedLang);
+    }
+    TuringMachine::new(source, lang).map_err(MinifyError::ParseError)?;
+    let mut ir = optimize::Ir::new(
+        lang::tokenize(source, lang).map_err(MinifyError::ParseError)?,
+        true,
+    );
+    for (pass, stats) in optimize::Level::O2.pipeline().run(&mut ir, |_, _| {}) {
+        log::debug!("{} took out {} instructions", pass, stats.removed);
+    }
+    let tokens = ir.tokens;
+    let symbols = symbols(lang);
+    tokens
+        .iter()
+        .map(|(instruction, _)| {
+            symbols
+                .iter()
+                .find(|(symbol_for, _)| symbol_for == instruction)
+                .map(|(_, symbol)| *symbol)
+                .ok_or(MinifyError::UnsupportedLang)
+        })
+        .collect()
+}
+
+///the character every instruction of the language is written as, found by trying every
+///printable ascii character
+pub(crate) fn symbols(lang: &Lang) -> Vec<(Instruction, char)> {
+    let mut symbols = vec![];
+    for c in '!'..='~' {
+        if let Ok(tokens) = lang::tokenize(&c.to_string(), lang) {
+            if let Some((instruction, _)) = tokens.first() {
+                symbols.push((*instruction, c));
+            }
+        }
+    }
+    symbols
+}
@@ -0,0 +1,85 @@
+//! fetching a program from a url, so one shared on the web runs like a file. the fetching is
+//! ureq's, over http or over https with rustls, following redirects. the body is read as it is,
+//! without asking the server to compress it, so a program that's a gzip file is decompressed by
+//! read_source like any other, up to MAX_SIZE too
+use std::fmt;
+use std::time::Duration;
+
+///the most a program fetched from a url can be, in bytes, before and after it's decompressed
+pub const MAX_SIZE: usize = 16 * 1024 * 1024;
+///how long fetching a program can take altogether, redirects and all, before giving up
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+///how many redirects are followed before giving up on a url
+const MAX_REDIRECTS: u32 = 5;
+
+///everything that can go wrong fetching a url
+#[derive(Debug)]
+pub enum FetchError {
+    ///the url isn't an http or https url that makes sense
+    BadUrl,
+    ///the server answered with a status other than 200, with the status and its reason
+    Status(u16, String),
+    ///the server kept redirecting
+    TooManyRedirects,
+    ///the program is more than MAX_SIZE bytes
+    TooLarge,
+    ///the host couldn't be looked up or talked to, it stopped answering, or it didn't answer
+    ///with http
+    Request(ureq::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::BadUrl => write!(f, "that's not a url bfint can fetch"),
+            FetchError::Status(status, reason) => {
+                write!(f, "the server said {} {}", status, reason)
+            }
+            FetchError::TooManyRedirects => {
+                write!(f, "redirected more than {} times", MAX_REDIRECTS)
+            }
+            FetchError::TooLarge => write!(f, "it's more than {} bytes", MAX_SIZE),
+            FetchError::Request(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ureq::Error> for FetchError {
+    fn from(e: ureq::Error) -> Self {
+        match e {
+            ureq::Error::BadUri(_) => FetchError::BadUrl,
+            ureq::Error::TooManyRedirects => FetchError::TooManyRedirects,
+            ureq::Error::BodyExceedsLimit(_) => FetchError::TooLarge,
+            e => FetchError::Request(e),
+        }
+    }
+}
+
+///fetches the body of the url with a GET, following redirects. anything other than a 200 in the
+///end is an error, and so is a body of more than MAX_SIZE bytes
+pub fn fetch(url: &str) -> Result<Vec<u8>, FetchError> {
+    if !crate::program::is_url(url) {
+        return Err(FetchError::BadUrl);
+    }
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .max_redirects(MAX_REDIRECTS)
+        .http_status_as_error(false)
+        .user_agent("bfint")
+        .build()
+        .into();
+    let mut response = agent.get(url).call()?;
+    let status = response.status();
+    if status != 200 {
+        return Err(FetchError::Status(
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("").to_string(),
+        ));
+    }
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(MAX_SIZE as u64)
+        .read_to_vec()?;
+    Ok(body)
+}
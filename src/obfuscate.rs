@@ -0,0 +1,216 @@
+//! the other way around from minify.rs: making a program bigger and harder to read without
+//! changing what it does, for puzzles. between its commands go:
+//!
+//! - comments, random letters, spaces and line breaks, which also split runs of the same command
+//!   into uneven chunks
+//! - "+-", which puts the cell back the way it was
+//! - "<>" after a ">" and "><" after a "<", which are only put where the pointer has just come
+//!   from, so they can't take it off either end of the tape
+//! - loops right after a "]" or at the very start, where the cell is 0 so they never run. what's
+//!   in them is random
+//!
+//! the same seed always gives the same program. the optimizer writes all of these away again
+//! (see optimize.rs), so once both programs have been through -O2, with their runs of moves, "+"
+//! and "-" written the same way, they have to be the same instructions. that's checked before
+//! the new program is given back
+use crate::lang::{self, Lang};
+use crate::random::Rng;
+use crate::{minify, optimize, Instruction, ParseError, TuringMachine};
+use std::fmt;
+
+///everything that can keep a program from being obfuscated
+#[derive(PartialEq, Debug)]
+pub enum ObfuscateError {
+    ///the language has commands longer than a single character, like ook, the same as minify
+    UnsupportedLang,
+    ///the program doesn't parse, so it can't be told apart from its comments safely
+    ParseError(ParseError),
+    ///the new program didn't come out the same as the old one after the optimizer, which is a
+    ///bug in the obfuscator
+    Changed,
+}
+
+impl fmt::Display for ObfuscateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObfuscateError::UnsupportedLang => write!(
+                f,
+                "only languages with single character commands can be obfuscated"
+            ),
+            ObfuscateError::ParseError(e) => write!(f, "{}", e),
+            ObfuscateError::Changed => write!(
+                f,
+                "the obfuscated program doesn't do the same as the original, which is a bug"
+            ),
+        }
+    }
+}
+
+///gives back a program that does the same as this one, about factor times as long, with the
+///junk picked by the seed. the program's own comments aren't kept, junk takes their place
+pub fn obfuscate(
+    source: &str,
+    lang: &Lang,
+    seed: u64,
+    factor: f64,
+) -> Result<String, ObfuscateError> {
+    if matches!(lang, Lang::Ook | Lang::Spoon | Lang::Mapped(_)) {
+        return Err(ObfuscateError::UnsupportedLang);
+    }
+    TuringMachine::new(source, lang).map_err(ObfuscateError::ParseError)?;
+    let tokens = lang::tokenize(source, lang).map_err(ObfuscateError::ParseError)?;
+    let symbols = minify::symbols(lang);
+    let symbol = |instruction: Instruction| {
+        symbols
+            .iter()
+            .find(|(symbol_for, _)| *symbol_for == instruction)
+            .map(|(_, symbol)| *symbol)
+    };
+    let mut junk = Junk {
+        rng: Rng::from_seed(seed),
+        comments: ('a'..='z')
+            .chain([' ', '\n'])
+            .filter(|c| lang::tokenize(&c.to_string(), lang).is_ok_and(|t| t.is_empty()))
+            .collect(),
+        commands: [
+            Instruction::MoveRight,
+            Instruction::MoveLeft,
+            Instruction::Increment,
+            Instruction::Decrement,
+            Instruction::Output,
+            Instruction::Replace,
+        ]
+        .into_iter()
+        .filter_map(symbol)
+        .collect(),
+        increment: symbol(Instruction::Increment).zip(symbol(Instruction::Decrement)),
+        open: symbol(Instruction::JumpToClose).ok_or(ObfuscateError::UnsupportedLang)?,
+        close: symbol(Instruction::JumpToOpen).ok_or(ObfuscateError::UnsupportedLang)?,
+        right: symbol(Instruction::MoveRight),
+        left: symbol(Instruction::MoveLeft),
+        last: None,
+        //the program starts on a cell that's 0
+        zero: true,
+    };
+    let mut left = ((source.len() as f64 * factor) as usize).saturating_sub(tokens.len());
+    let mut obfuscated = String::new();
+    for gap in 0..=tokens.len() {
+        //every gap gets anything from none to twice its share of what's left, so the chunks
+        //come out uneven but the total about right
+        let share = left / (tokens.len() + 1 - gap);
+        let amount = (junk.below(2 * share as u64 + 1) as usize).min(left);
+        left -= junk.write(&mut obfuscated, amount);
+        if let Some((instruction, _)) = tokens.get(gap) {
+            obfuscated.push(symbol(*instruction).ok_or(ObfuscateError::UnsupportedLang)?);
+            junk.last = Some(*instruction);
+            //a loop is only ever left when the cell is 0
+            junk.zero = *instruction == Instruction::JumpToOpen;
+        }
+    }
+    //the junk is all written away by -O2 and normalize, so what's left has to be the same
+    let normal = |source: &str| -> Result<Vec<Instruction>, ParseError> {
+        TuringMachine::new(source, lang)?;
+        let mut ir = optimize::Ir::new(lang::tokenize(source, lang)?, true);
+        optimize::Level::O2.pipeline().run(&mut ir, |_, _| {});
+        Ok(optimize::normalize(&ir.tokens))
+    };
+    match normal(&obfuscated) == normal(source) {
+        true => Ok(obfuscated),
+        false => Err(ObfuscateError::Changed),
+    }
+}
+
+///what can be written between two commands, and what's known about the program there
+struct Junk {
+    rng: Rng,
+    comments: Vec<char>,
+    //the commands that can go in a loop that never runs
+    commands: Vec<char>,
+    //"+" and "-", if the language has both
+    increment: Option<(char, char)>,
+    open: char,
+    close: char,
+    right: Option<char>,
+    left: Option<char>,
+    //the last command written, junk or not
+    last: Option<Instruction>,
+    //whether the cell is known to be 0 at this point
+    zero: bool,
+}
+
+impl Junk {
+    ///a random number from 0 up to but not including the bound
+    fn below(&mut self, bound: u64) -> u64 {
+        self.rng.next_u64() % bound.max(1)
+    }
+    ///writes about that many characters of junk, giving back how many it did
+    fn write(&mut self, out: &mut String, amount: usize) -> usize {
+        let mut written = 0;
+        while written < amount {
+            let room = amount - written;
+            let piece = match self.below(4) {
+                0 if room >= 2 && self.zero => Some(self.dead_loop(room)),
+                1 if room >= 2 => self.no_op(),
+                _ => None,
+            };
+            let piece = piece.unwrap_or_else(|| {
+                let length = 1 + self.below(room.min(6) as u64) as usize;
+                (0..length)
+                    .map(|_| pick(&mut self.rng, &self.comments))
+                    .collect()
+            });
+            written += piece.chars().count();
+            out.push_str(&piece);
+        }
+        written
+    }
+    ///a loop that never runs, at most room characters long
+    fn dead_loop(&mut self, room: usize) -> String {
+        let length = self.below(room.min(24) as u64 - 1) as usize;
+        let body = self.body(length);
+        //the cell stays 0, so another one can follow
+        self.last = Some(Instruction::JumpToOpen);
+        format!("{}{}{}", self.open, body, self.close)
+    }
+    ///that many random commands, with loops in them balanced
+    fn body(&mut self, length: usize) -> String {
+        if length >= 4 && self.below(3) == 0 {
+            let inner = self.below(length as u64 - 1) as usize;
+            let before = self.below((length - 2 - inner) as u64 + 1) as usize;
+            let after = length - 2 - inner - before;
+            let (before, inner, after) = (self.body(before), self.body(inner), self.body(after));
+            return format!("{}{}{}{}{}", before, self.open, inner, self.close, after);
+        }
+        (0..length)
+            .map(|_| pick(&mut self.rng, &self.commands))
+            .collect()
+    }
+    ///a pair of commands that undo each other, if one fits here
+    fn no_op(&mut self) -> Option<String> {
+        //a move back and forth only goes where the pointer was a step ago
+        let moves = match self.last {
+            Some(Instruction::MoveRight) => self.left.zip(self.right),
+            Some(Instruction::MoveLeft) => self.right.zip(self.left),
+            _ => None,
+        };
+        let (first, second) = match (moves, self.increment) {
+            (Some(moves), Some(increment)) => match self.below(2) {
+                0 => moves,
+                _ => increment,
+            },
+            (Some(moves), None) => moves,
+            (None, increment) => increment?,
+        };
+        //after "-" the cell isn't known to be 0 any more, and after a move it's another cell
+        if Some((first, second)) == self.increment {
+            self.last = Some(Instruction::Decrement);
+        }
+        self.zero = false;
+        Some(format!("{}{}", first, second))
+    }
+}
+
+///one of the characters, at random
+fn pick(rng: &mut Rng, from: &[char]) -> char {
+    from[(rng.next_u64() % from.len() as u64) as usize]
+}
@@ -0,0 +1,214 @@
+//! running the machine one instruction at a time as an iterator, for drawing a program as it runs
+//! or looking at how it ran afterwards. every instruction gives back a small observation of what
+//! it did, that belongs to whoever gets it, so the iterator can be chained with take, filter or
+//! step_by like any other: `tm.iter_steps().step_by(1000)` looks at every thousandth one. the
+//! program only runs as far as the observations are taken.
+//!
+//! an Observer is the other way around: it's handed to the machine with set_observer and gets
+//! called around every instruction however the machine is being run, with run, with events or
+//! from the debugger, for tools that want to watch without being the ones running it
+use crate::control::RunOutcome;
+use crate::shared::{lock, shared, Shared};
+use crate::{Instruction, RuntimeError, TuringMachine};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+///what running one instruction did
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct StepObservation {
+    ///how many steps have run, counting this one. a loop that --tiered runs in one go counts
+    ///every step it would have taken, so this can go up by more than one
+    pub step: u64,
+    ///the index of the instruction in the program, and the instruction
+    pub pc: usize,
+    pub op: Instruction,
+    ///where the pointer was afterwards, and what was in the cell there. big cells only show
+    ///their lowest 8 bits
+    pub pointer: usize,
+    pub cell_after: u8,
+    ///the byte the instruction wrote, if it wrote one. a "%" that writes more than one shows the
+    ///last of them
+    pub output: Option<u8>,
+}
+
+///what the iterator gives back
+#[derive(PartialEq, Debug)]
+pub enum Observation {
+    Step(StepObservation),
+    ///how the run ended, the same as TuringMachine::run would have said. this is always the last
+    ///observation
+    Finished(Result<RunOutcome, RuntimeError>),
+}
+
+///the observations of a running machine, see TuringMachine::iter_steps
+pub struct Steps<'a> {
+    tm: &'a mut TuringMachine,
+    done: bool,
+}
+
+impl Iterator for Steps<'_> {
+    type Item = Observation;
+
+    fn next(&mut self) -> Option<Observation> {
+        if self.done {
+            return None;
+        }
+        let tm = &mut *self.tm;
+        if !tm.has_instructions_left() {
+            self.done = true;
+            let outcome = tm.finish().map(|_| RunOutcome::Halted);
+            #[cfg(feature = "std")]
+            tm.report_progress(true);
+            tm.observe_end(outcome.as_ref().copied());
+            return Some(Observation::Finished(outcome));
+        }
+        if tm.should_stop() {
+            self.done = true;
+            let outcome = tm.flush_output().map(|_| RunOutcome::Cancelled);
+            tm.observe_end(outcome.as_ref().copied());
+            return Some(Observation::Finished(outcome));
+        }
+        let pc = tm.program_counter;
+        let op = tm.program.instruction(pc)?;
+        tm.last_output = None;
+        if let Err(e) = tm.perform_next_instruction() {
+            self.done = true;
+            tm.observe_end(Err(&e));
+            return Some(Observation::Finished(Err(e)));
+        }
+        Some(Observation::Step(StepObservation {
+            step: tm.steps,
+            pc,
+            op,
+            pointer: tm.pointer,
+            cell_after: tm.tape[tm.pointer],
+            output: tm.last_output.take(),
+        }))
+    }
+}
+
+///something that watches a machine run, see set_observer. every method does nothing unless
+///it's implemented
+pub trait Observer {
+    ///called right before the instruction runs. an instruction that has to wait for input is
+    ///seen again when it's run with the input
+    fn before_step(&mut self, _ctx: &StepCtx) {}
+    ///called right after the instruction ran, unless it failed
+    fn after_step(&mut self, _ctx: &StepCtx) {}
+    ///called once the run has ended, however it ended
+    fn on_halt(&mut self, _outcome: Result<RunOutcome, &RuntimeError>) {}
+}
+
+impl<T: Observer + ?Sized> Observer for Box<T> {
+    fn before_step(&mut self, ctx: &StepCtx) {
+        (**self).before_step(ctx)
+    }
+    fn after_step(&mut self, ctx: &StepCtx) {
+        (**self).after_step(ctx)
+    }
+    fn on_halt(&mut self, outcome: Result<RunOutcome, &RuntimeError>) {
+        (**self).on_halt(outcome)
+    }
+}
+
+///an observer that's shared, so whoever set it can look at what it saw once the run is over
+impl<T: Observer> Observer for Shared<T> {
+    fn before_step(&mut self, ctx: &StepCtx) {
+        lock(self).before_step(ctx)
+    }
+    fn after_step(&mut self, ctx: &StepCtx) {
+        lock(self).after_step(ctx)
+    }
+    fn on_halt(&mut self, outcome: Result<RunOutcome, &RuntimeError>) {
+        lock(self).on_halt(outcome)
+    }
+}
+
+///every observer in turn, so a run can be watched by more than one
+impl<T: Observer> Observer for Vec<T> {
+    fn before_step(&mut self, ctx: &StepCtx) {
+        self.iter_mut()
+            .for_each(|observer| observer.before_step(ctx))
+    }
+    fn after_step(&mut self, ctx: &StepCtx) {
+        self.iter_mut()
+            .for_each(|observer| observer.after_step(ctx))
+    }
+    fn on_halt(&mut self, outcome: Result<RunOutcome, &RuntimeError>) {
+        self.iter_mut()
+            .for_each(|observer| observer.on_halt(outcome))
+    }
+}
+
+///the observer, shared between clones of a machine
+pub(crate) type SharedObserver = Shared<dyn Observer + Send>;
+
+///a look at the machine for an observer, around the instruction that's running
+pub struct StepCtx<'a> {
+    tm: &'a TuringMachine,
+    pc: usize,
+}
+
+impl StepCtx<'_> {
+    ///the index of the instruction in the program
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+    pub fn op(&self) -> Instruction {
+        self.tm.program.ops[self.pc].instruction()
+    }
+    ///how many steps have run, counting this one
+    pub fn steps(&self) -> u64 {
+        self.tm.steps
+    }
+    pub fn pointer(&self) -> usize {
+        self.tm.pointer
+    }
+    ///what's in the cell, or nothing if it's past the end of the tape
+    pub fn cell(&self, index: usize) -> Option<u8> {
+        self.tm.tape.get(index).copied()
+    }
+    ///the whole machine, for anything else an observer wants to look at
+    pub fn machine(&self) -> &TuringMachine {
+        self.tm
+    }
+}
+
+impl TuringMachine {
+    ///has the observer called around every instruction from now on, in place of the one set
+    ///before. a machine without one doesn't pay anything for it but a check that it's not there
+    pub fn set_observer(&mut self, observer: impl Observer + Send + 'static) {
+        self.observer = Some(shared(observer));
+    }
+    ///stops calling the observer
+    pub fn remove_observer(&mut self) {
+        self.observer = None;
+    }
+    ///calls the observer before the instruction at the index runs
+    pub(crate) fn observe_before(&self, pc: usize) {
+        if let Some(observer) = &self.observer {
+            lock(observer).before_step(&StepCtx { tm: self, pc });
+        }
+    }
+    ///calls the observer after the instruction at the index ran
+    pub(crate) fn observe_after(&self, pc: usize) {
+        if let Some(observer) = &self.observer {
+            lock(observer).after_step(&StepCtx { tm: self, pc });
+        }
+    }
+    ///tells the observer the run has ended
+    pub(crate) fn observe_end(&self, outcome: Result<RunOutcome, &RuntimeError>) {
+        if let Some(observer) = &self.observer {
+            lock(observer).on_halt(outcome);
+        }
+    }
+    ///runs the program as the observations are taken from the iterator, one instruction for
+    ///every observation and then one saying how it ended. dropping the iterator leaves the
+    ///machine where it got to, so it can carry on with run
+    pub fn iter_steps(&mut self) -> Steps<'_> {
+        Steps {
+            tm: self,
+            done: false,
+        }
+    }
+}
@@ -0,0 +1,930 @@
+//! passes that make a program smaller without changing what it does. they work on the tokens
+//! the languages turn into, before the program is checked and loaded, so anything that reads
+//! tokens can use them.
+//!
+//! every pass is a Pass, and passes() has all of them in the order a pipeline runs them:
+//!
+//! 1. cancel, which writes runs of moves, "+" and "-" the shortest way
+//! 2. dead-code, which takes out loops that can never run
+//! 3. clear-loop, which finds loops like "[-]" that only clear the cell
+//! 4. copy-loop, which finds loops like "[->+>++<<]" that add the cell to others
+//! 5. scan, which finds loops like "[>]" that look for a cell that's 0
+//! 6. rle, which finds runs of the same move, "+" or "-", like "+++"
+//! 7. offset-fusion, which finds every run of moves, "+" and "-", adding to every cell at how far
+//!    it is from where the run starts
+//!
+//! cancel goes first, since taking out what's between two loops can leave the second one dead.
+//! those two change the instructions and the rest don't, they only find what can be run in one
+//! go instead of an instruction at a time, see Fusions. they go last so what they found is where
+//! it was once the instructions stop changing. a pipeline runs its passes over and over in that
+//! order until none of them takes anything more out. a new pass goes in passes() where it should
+//! run, and gets a name to be picked with --passes. -O0 runs no passes, -O1 cancel and
+//! offset-fusion, and -O2 all of them
+use crate::{Instruction, Position};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+///the program as the passes see it: every instruction with where it came from in the source,
+///and what's known about the tape before it starts
+#[derive(PartialEq, Debug, Clone)]
+pub struct Ir {
+    pub tokens: Vec<(Instruction, Position)>,
+    ///whether the cell the program starts on is known to be 0
+    pub starts_at_zero: bool,
+    ///what the passes so far have said can be run in one go
+    pub fusions: Fusions,
+    ///the loops that run in one go, by the index of their "[", and the runs of moves, "+" and
+    ///"-" that do, as they are in the tokens now. see refresh
+    pub fused: BTreeMap<usize, Fused>,
+    pub blocks: Vec<Range<usize>>,
+}
+
+impl Ir {
+    ///the tokens, with nothing found in them yet
+    pub fn new(tokens: Vec<(Instruction, Position)>, starts_at_zero: bool) -> Self {
+        Ir {
+            tokens,
+            starts_at_zero,
+            fusions: Fusions::default(),
+            fused: BTreeMap::new(),
+            blocks: vec![],
+        }
+    }
+    ///finds the loops and runs that fusions says can run in one go again, for when the tokens
+    ///have changed
+    pub fn refresh(&mut self) {
+        let instructions: Vec<Instruction> = self
+            .tokens
+            .iter()
+            .map(|(instruction, _)| *instruction)
+            .collect();
+        self.fused = fused_loops(&instructions, &self.fusions);
+        self.blocks = runs(&instructions, self.fusions.runs);
+    }
+}
+
+impl fmt::Display for Ir {
+    ///one instruction a line, after its position, with what runs in one go from it
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let blocks: BTreeMap<usize, usize> = self
+            .blocks
+            .iter()
+            .map(|block| (block.start, block.len()))
+            .collect();
+        for (index, (instruction, position)) in self.tokens.iter().enumerate() {
+            write!(f, "{:>8} {:?}", position.to_string(), instruction)?;
+            if let Some(fused) = self.fused.get(&index) {
+                write!(f, "  {}", fused)?;
+            }
+            if let Some(length) = blocks.get(&index) {
+                write!(f, "  block of {}", length)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+///the runs of moves, "+" and "-" that run in one go, see blocks.rs
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Runs {
+    ///none of them, they run an instruction at a time
+    #[default]
+    None,
+    ///only runs of the same instruction, like "+++" or ">>", found by rle
+    Repeats,
+    ///every run of two or more of them, found by offset-fusion
+    Mixed,
+}
+
+///what a program can run in one go instead of an instruction at a time. the passes that don't
+///change the instructions only turn these on, and the program finds the loops and runs again
+///when it's loaded, see Program::fuse. steps are counted the same either way
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Fusions {
+    pub runs: Runs,
+    pub clear_loops: bool,
+    pub copy_loops: bool,
+    pub scans: bool,
+}
+
+///a loop that runs in one go, see fused.rs
+#[derive(PartialEq, Debug, Clone)]
+pub enum Fused {
+    ///a loop that only adds, see AddLoop. the ones that add nothing to other cells, like "[-]",
+    ///only clear the cell and are found by clear-loop, the rest by copy-loop
+    Add(AddLoop),
+    ///a loop with nothing in it but the same move, like "[>]" or "[<<]", which goes that far
+    ///until it gets to a cell that's 0. how far and which way it goes, found by scan
+    Scan(isize),
+}
+
+impl Fused {
+    ///the loop with these instructions between its brackets, if it's a kind the fusions say
+    ///runs in one go
+    pub fn new(body: &[Instruction], fusions: &Fusions) -> Option<Self> {
+        if let Some(form) = AddLoop::new(body.iter().copied()) {
+            let found = match form.adds.is_empty() {
+                true => fusions.clear_loops,
+                false => fusions.copy_loops,
+            };
+            return found.then_some(Fused::Add(form));
+        }
+        let first = *body.first()?;
+        let stride = match first {
+            Instruction::MoveRight => body.len() as isize,
+            Instruction::MoveLeft => -(body.len() as isize),
+            _ => return None,
+        };
+        (fusions.scans && body.iter().all(|instruction| *instruction == first))
+            .then_some(Fused::Scan(stride))
+    }
+    ///the pass that finds the loop
+    pub fn pass(&self) -> &'static str {
+        match self {
+            Fused::Add(form) if form.adds.is_empty() => "clear-loop",
+            Fused::Add(_) => "copy-loop",
+            Fused::Scan(_) => "scan",
+        }
+    }
+}
+
+impl fmt::Display for Fused {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fused::Add(form) if form.adds.is_empty() => write!(f, "clear loop"),
+            Fused::Add(_) => write!(f, "copy loop"),
+            Fused::Scan(stride) => write!(f, "scan by {}", stride),
+        }
+    }
+}
+
+///the loops in the instructions that run in one go, by the index of their "[". only loops
+///with no other loops in them can
+pub(crate) fn fused_loops(
+    instructions: &[Instruction],
+    fusions: &Fusions,
+) -> BTreeMap<usize, Fused> {
+    let mut fused = BTreeMap::new();
+    if !(fusions.clear_loops || fusions.copy_loops || fusions.scans) {
+        return fused;
+    }
+    //the last "[", as long as no bracket came after it
+    let mut open = None;
+    for (index, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::JumpToClose => open = Some(index),
+            Instruction::JumpToOpen => {
+                if let Some(open) = open.take() {
+                    if let Some(found) = Fused::new(&instructions[open + 1..index], fusions) {
+                        fused.insert(open, found);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    fused
+}
+
+///the runs of two or more moves, "+" and "-" in the instructions that run in one go
+pub(crate) fn runs(instructions: &[Instruction], runs: Runs) -> Vec<Range<usize>> {
+    let mut found = vec![];
+    if runs == Runs::None {
+        return found;
+    }
+    let mut start = 0;
+    while start < instructions.len() {
+        let mut end = start;
+        while let Some(&instruction) = instructions.get(end) {
+            if movement(instruction).is_none()
+                || (runs == Runs::Repeats && instruction != instructions[start])
+            {
+                break;
+            }
+            end += 1;
+        }
+        if end - start >= 2 {
+            found.push(start..end);
+        }
+        start = end.max(start + 1);
+    }
+    found
+}
+
+///what a pass did to the program
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct PassStats {
+    ///how many instructions fewer there are
+    pub removed: usize,
+    ///how many loops or runs it found that run in one go
+    pub fused: usize,
+}
+
+///one way of making a program smaller
+pub trait Pass {
+    ///what it's called in --passes and --print-ir-after
+    fn name(&self) -> &'static str;
+    fn run(&self, ir: &mut Ir) -> PassStats;
+}
+
+///see canonicalize
+pub struct Cancel;
+
+impl Pass for Cancel {
+    fn name(&self) -> &'static str {
+        "cancel"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        let before = ir.tokens.len();
+        canonicalize(&mut ir.tokens);
+        PassStats {
+            removed: before - ir.tokens.len(),
+            fused: 0,
+        }
+    }
+}
+
+///see remove_dead_loops
+pub struct DeadCode;
+
+impl Pass for DeadCode {
+    fn name(&self) -> &'static str {
+        "dead-code"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        let before = ir.tokens.len();
+        remove_dead_loops(&mut ir.tokens, ir.starts_at_zero);
+        PassStats {
+            removed: before - ir.tokens.len(),
+            fused: 0,
+        }
+    }
+}
+
+///turns on the loops the pass finds and finds them, see Fused
+fn fuse_loops(ir: &mut Ir, pass: &str, turn_on: impl FnOnce(&mut Fusions)) -> PassStats {
+    turn_on(&mut ir.fusions);
+    ir.refresh();
+    PassStats {
+        removed: 0,
+        fused: ir
+            .fused
+            .values()
+            .filter(|fused| fused.pass() == pass)
+            .count(),
+    }
+}
+
+///see Fused::Add
+pub struct ClearLoop;
+
+impl Pass for ClearLoop {
+    fn name(&self) -> &'static str {
+        "clear-loop"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        fuse_loops(ir, self.name(), |fusions| fusions.clear_loops = true)
+    }
+}
+
+///see Fused::Add
+pub struct CopyLoop;
+
+impl Pass for CopyLoop {
+    fn name(&self) -> &'static str {
+        "copy-loop"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        fuse_loops(ir, self.name(), |fusions| fusions.copy_loops = true)
+    }
+}
+
+///see Fused::Scan
+pub struct Scan;
+
+impl Pass for Scan {
+    fn name(&self) -> &'static str {
+        "scan"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        fuse_loops(ir, self.name(), |fusions| fusions.scans = true)
+    }
+}
+
+///see Runs::Repeats
+pub struct Rle;
+
+impl Pass for Rle {
+    fn name(&self) -> &'static str {
+        "rle"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        //offset-fusion's runs have these in them already
+        if ir.fusions.runs == Runs::None {
+            ir.fusions.runs = Runs::Repeats;
+        }
+        ir.refresh();
+        PassStats {
+            removed: 0,
+            fused: ir.blocks.len(),
+        }
+    }
+}
+
+///see Runs::Mixed
+pub struct OffsetFusion;
+
+impl Pass for OffsetFusion {
+    fn name(&self) -> &'static str {
+        "offset-fusion"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        ir.fusions.runs = Runs::Mixed;
+        ir.refresh();
+        PassStats {
+            removed: 0,
+            fused: ir.blocks.len(),
+        }
+    }
+}
+
+///every pass there is, in the order they run
+pub fn passes() -> Vec<Box<dyn Pass>> {
+    vec![
+        Box::new(Cancel),
+        Box::new(DeadCode),
+        Box::new(ClearLoop),
+        Box::new(CopyLoop),
+        Box::new(Scan),
+        Box::new(Rle),
+        Box::new(OffsetFusion),
+    ]
+}
+
+///how hard to try, with -O0, -O1 and -O2
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Level {
+    ///no passes at all, the program runs as it's written
+    O0,
+    ///cancel, which never changes which loops there are, and offset-fusion
+    O1,
+    ///every pass
+    O2,
+}
+
+impl Level {
+    pub const ALL: [Level; 3] = [Level::O0, Level::O1, Level::O2];
+    ///"0", "1" or "2", the way it's set in a profile
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::O0 => "0",
+            Level::O1 => "1",
+            Level::O2 => "2",
+        }
+    }
+    ///the level for "0", "1" or "2"
+    pub fn from_name(name: &str) -> Option<Self> {
+        Level::ALL.into_iter().find(|level| level.name() == name)
+    }
+    ///the pipeline of the level's passes
+    pub fn pipeline(self) -> Pipeline {
+        let names: &[&str] = match self {
+            Level::O0 => &[],
+            Level::O1 => &["cancel", "offset-fusion"],
+            Level::O2 => &[
+                "cancel",
+                "dead-code",
+                "clear-loop",
+                "copy-loop",
+                "scan",
+                "rle",
+                "offset-fusion",
+            ],
+        };
+        Pipeline::from_names(names).expect("the levels only name passes there are")
+    }
+}
+
+impl fmt::Display for Level {
+    ///the level as it's written on the command line, like "-O2"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "-O{}", self.name())
+    }
+}
+
+///passes to run over a program, in the order of passes()
+pub struct Pipeline {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl Pipeline {
+    ///a pipeline with the passes that have these names, which run in the order of passes() and
+    ///not the order they're named in. gives back the first name there's no pass for
+    pub fn from_names(names: &[&str]) -> Result<Self, String> {
+        if let Some(unknown) = names
+            .iter()
+            .find(|name| !passes().iter().any(|pass| pass.name() == **name))
+        {
+            let known: Vec<&str> = passes().iter().map(|pass| pass.name()).collect();
+            return Err(format!(
+                "there is no pass called '{}', the passes are {}",
+                unknown,
+                known.join(", ")
+            ));
+        }
+        Ok(Pipeline {
+            passes: passes()
+                .into_iter()
+                .filter(|pass| names.contains(&pass.name()))
+                .collect(),
+        })
+    }
+    ///whether the pipeline has a pass with this name
+    pub fn has(&self, name: &str) -> bool {
+        self.passes.iter().any(|pass| pass.name() == name)
+    }
+    ///the names of the passes, in the order they run in
+    pub fn names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+    ///whether the pipeline has no passes, like -O0
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+    ///runs the passes until none of them finds anything more to do, calling after with the
+    ///program as it is after every pass that ran. gives back what every pass did in all, by
+    ///its name
+    pub fn run(
+        &self,
+        ir: &mut Ir,
+        mut after: impl FnMut(&str, &Ir),
+    ) -> Vec<(&'static str, PassStats)> {
+        let mut stats: Vec<(&'static str, PassStats)> = self
+            .passes
+            .iter()
+            .map(|pass| (pass.name(), PassStats::default()))
+            .collect();
+        loop {
+            let mut removed = 0;
+            for (pass, (_, total)) in self.passes.iter().zip(&mut stats) {
+                let done = pass.run(ir);
+                //what was found before is somewhere else once instructions are taken out
+                if done.removed > 0 {
+                    ir.refresh();
+                }
+                after(pass.name(), ir);
+                total.removed += done.removed;
+                total.fused = done.fused;
+                removed += done.removed;
+            }
+            if removed == 0 {
+                return stats;
+            }
+        }
+    }
+}
+
+///how far an instruction moves the pointer and how much it adds to the cell, for the
+///instructions that do nothing but that
+pub(crate) fn movement(instruction: Instruction) -> Option<(i64, i64)> {
+    match instruction {
+        Instruction::MoveRight => Some((1, 0)),
+        Instruction::MoveLeft => Some((-1, 0)),
+        Instruction::Increment => Some((0, 1)),
+        Instruction::Decrement => Some((0, -1)),
+        _ => None,
+    }
+}
+
+///a loop with nothing in it but moves, "+" and "-", that comes back to the cell it loops on having
+///added 1 to it or taken 1 from it, like "[->+>++<<]". it adds the same to the same cells every
+///time around, so what it does in all only depends on how many times it goes around
+#[derive(PartialEq, Debug, Clone)]
+pub struct AddLoop {
+    ///what going around once adds to the cell the loop is on, 1 or 255
+    pub step: u8,
+    ///what going around once adds to every other cell, by how far it is from the one the loop
+    ///is on
+    pub adds: Vec<(isize, u8)>,
+    ///how far the pointer gets to the left and to the right of the cell the loop is on while
+    ///going around
+    pub reach: (isize, isize),
+}
+
+impl AddLoop {
+    ///the loop with these instructions between its brackets, if it's one
+    pub fn new(body: impl IntoIterator<Item = Instruction>) -> Option<Self> {
+        let mut adds: BTreeMap<isize, u8> = BTreeMap::new();
+        let (mut offset, mut reach) = (0isize, (0isize, 0isize));
+        for instruction in body {
+            let (moved, added) = movement(instruction)?;
+            offset += moved as isize;
+            reach = (reach.0.min(offset), reach.1.max(offset));
+            let add = adds.entry(offset).or_insert(0);
+            *add = add.wrapping_add(added as u8);
+        }
+        let step = adds.remove(&0).unwrap_or(0);
+        if offset != 0 || !(step == 1 || step == 255) {
+            return None;
+        }
+        Some(AddLoop {
+            step,
+            adds: adds.into_iter().filter(|(_, add)| *add != 0).collect(),
+            reach,
+        })
+    }
+    ///how many times the loop goes around when the cell it's on starts out with the value
+    pub fn iterations(&self, value: u8) -> u64 {
+        match self.step {
+            1 => (256 - value as u64) % 256,
+            _ => value as u64,
+        }
+    }
+    ///whether the pointer stays on a tape that long the whole time the loop goes around, when
+    ///the loop is on the cell at the pointer
+    pub(crate) fn fits(&self, pointer: usize, length: usize) -> bool {
+        let pointer = pointer as isize;
+        pointer + self.reach.0 >= 0 && pointer + self.reach.1 < length as isize
+    }
+    ///does to the tape what going around that many times does, with the loop on the cell at
+    ///the pointer. it has to fit
+    pub(crate) fn go_around(&self, tape: &mut [u8], pointer: usize, iterations: u64) {
+        //what's added to a cell in all only matters mod 256
+        let times = (iterations % 256) as u8;
+        for (offset, add) in &self.adds {
+            let cell = &mut tape[(pointer as isize + offset) as usize];
+            *cell = cell.wrapping_add(add.wrapping_mul(times));
+        }
+        let cell = &mut tape[pointer];
+        *cell = cell.wrapping_add(self.step.wrapping_mul(times));
+    }
+}
+
+///writes every run of moves, "+" and "-" the shortest way it can be: what it adds to each cell
+///in one go and then where the pointer ends up, so "+-" and "<>" go away and "+>-<-" becomes
+///">-<". a run ends at anything else, like a bracket, "," or ".", so nothing is ever moved past
+///those. the pointer still gets as far both ways as the run did, so "<>" on the first cell goes
+///off the tape the same as it would have, and every move takes the position of the first one in
+///the run that got to the same cell, so the error points at the same move
+pub fn canonicalize(tokens: &mut Vec<(Instruction, Position)>) {
+    let mut kept: Vec<(Instruction, Position)> = Vec::with_capacity(tokens.len());
+    let mut run: Vec<(Instruction, Position)> = vec![];
+    for (instruction, position) in tokens.drain(..) {
+        if movement(instruction).is_some() {
+            run.push((instruction, position));
+            continue;
+        }
+        kept.extend(shortest(&run));
+        run.clear();
+        kept.push((instruction, position));
+    }
+    kept.extend(shortest(&run));
+    *tokens = kept;
+}
+
+///the shortest way to write a run of moves, "+" and "-", or the run itself when there's
+///nothing shorter
+fn shortest(run: &[(Instruction, Position)]) -> Vec<(Instruction, Position)> {
+    let instructions = rewrite(run.iter().map(|(instruction, _)| *instruction));
+    let Some(&(_, first)) = run.first() else {
+        return vec![];
+    };
+    if instructions.len() >= run.len() {
+        return run.to_vec();
+    }
+    //where every cell the run visits was first got to, by how far it is from where it starts
+    let mut reached: BTreeMap<i64, Position> = BTreeMap::from([(0, first)]);
+    let mut offset = 0;
+    for (instruction, position) in run {
+        offset += movement(*instruction).map_or(0, |(moved, _)| moved);
+        reached.entry(offset).or_insert(*position);
+    }
+    let mut offset = 0;
+    instructions
+        .into_iter()
+        .map(|instruction| {
+            offset += movement(instruction).map_or(0, |(moved, _)| moved);
+            (instruction, reached.get(&offset).copied().unwrap_or(first))
+        })
+        .collect()
+}
+
+///every run of moves, "+" and "-" written the way shortest would, even when that's no shorter.
+///two runs that do the same always come out the same, so programs can be compared with it
+#[cfg(feature = "std")]
+pub(crate) fn normalize(tokens: &[(Instruction, Position)]) -> Vec<Instruction> {
+    let mut normal = vec![];
+    let mut run = vec![];
+    for (instruction, _) in tokens {
+        if movement(*instruction).is_some() {
+            run.push(*instruction);
+            continue;
+        }
+        normal.extend(rewrite(run.drain(..)));
+        normal.push(*instruction);
+    }
+    normal.extend(rewrite(run));
+    normal
+}
+
+///a run of moves, "+" and "-" written with every cell it changes visited once, along with the
+///furthest cells it gets to either way
+fn rewrite(run: impl IntoIterator<Item = Instruction>) -> Vec<Instruction> {
+    //what the run adds to every cell it changes, by how far that cell is from where it starts
+    let mut added: BTreeMap<i64, i64> = BTreeMap::new();
+    let (mut offset, mut reach) = (0, (0, 0));
+    for instruction in run {
+        let (moved, add) = movement(instruction).expect("runs only hold moves, + and -");
+        offset += moved;
+        reach = (reach.0.min(offset), reach.1.max(offset));
+        *added.entry(offset).or_default() += add;
+    }
+    added.retain(|_, add| *add != 0);
+    //a cell nothing is added to is still gone to when it's as far as the run gets, so the
+    //pointer goes off the tape where the run would have
+    added.entry(reach.0).or_default();
+    added.entry(reach.1).or_default();
+    let end = offset;
+    //the cells are visited from left to right or from right to left, whichever is shorter
+    //counting the way to where the pointer ends up
+    let (leftmost, rightmost) = match (added.keys().next(), added.keys().next_back()) {
+        (Some(&leftmost), Some(&rightmost)) => (leftmost, rightmost),
+        _ => (0, 0),
+    };
+    let rightwards = leftmost.abs() + (rightmost - leftmost) + (end - rightmost).abs();
+    let leftwards = rightmost.abs() + (rightmost - leftmost) + (end - leftmost).abs();
+    let mut order: Vec<(i64, i64)> = added.into_iter().collect();
+    if leftwards < rightwards {
+        order.reverse();
+    }
+    let mut instructions = vec![];
+    let mut at = 0;
+    let go = |from: i64, to: i64, instructions: &mut Vec<Instruction>| {
+        let step = match to > from {
+            true => Instruction::MoveRight,
+            false => Instruction::MoveLeft,
+        };
+        instructions.extend(core::iter::repeat_n(step, from.abs_diff(to) as usize));
+    };
+    for (cell, add) in order {
+        go(at, cell, &mut instructions);
+        at = cell;
+        let change = match add > 0 {
+            true => Instruction::Increment,
+            false => Instruction::Decrement,
+        };
+        instructions.extend(core::iter::repeat_n(change, add.unsigned_abs() as usize));
+    }
+    go(at, end, &mut instructions);
+    instructions
+}
+
+///takes out loops that can never run because the cell is known to be 0 when they're reached,
+///which is the case straight after another loop, and at the very start of the program when the
+///cell it starts on is 0. anything between the "]" and the next "[", even a move or a ",",
+///keeps the loop. the brackets have to match, so this should only be used on tokens that parse
+pub fn remove_dead_loops(tokens: &mut Vec<(Instruction, Position)>, starts_at_zero: bool) {
+    let mut kept = Vec::with_capacity(tokens.len());
+    //whether the current cell is known to be 0 at this point
+    let mut zero = starts_at_zero;
+    //how deep inside a loop that is being taken out we are, 0 when nothing is
+    let mut skipping = 0;
+    for (instruction, position) in tokens.drain(..) {
+        if skipping > 0 {
+            match instruction {
+                Instruction::JumpToClose => skipping += 1,
+                Instruction::JumpToOpen => skipping -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        match instruction {
+            Instruction::JumpToClose if zero => {
+                skipping = 1;
+                continue;
+            }
+            //a loop is only ever left when the cell is 0
+            Instruction::JumpToOpen => zero = true,
+            _ => zero = false,
+        }
+        kept.push((instruction, position));
+    }
+    *tokens = kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::{self, Lang};
+
+    fn tokens(source: &str) -> Vec<(Instruction, Position)> {
+        lang::tokenize(source, &Lang::Brainfuck).expect("it tokenizes")
+    }
+
+    ///the program as brainfuck, and where every instruction came from
+    fn written(tokens: &[(Instruction, Position)]) -> (String, Vec<usize>) {
+        tokens
+            .iter()
+            .map(|(instruction, position)| {
+                (
+                    crate::program::symbol(*instruction).expect("it's brainfuck"),
+                    position.column,
+                )
+            })
+            .unzip()
+    }
+
+    fn canonical(source: &str) -> (String, Vec<usize>) {
+        let mut tokens = tokens(source);
+        canonicalize(&mut tokens);
+        written(&tokens)
+    }
+
+    #[test]
+    fn canonicalize_writes_runs_the_shortest_way() {
+        assert_eq!(canonical("+-").0, "");
+        assert_eq!(canonical("+>-<-").0, ">-<");
+        assert_eq!(canonical(">>+<<>>+").0, ">>++");
+        assert_eq!(canonical("+.+-[->+<]").0, "+.[->+<]");
+    }
+
+    ///a move there and back still goes there, so it still goes off the tape when that's off it
+    #[test]
+    fn canonicalize_keeps_how_far_the_pointer_goes() {
+        assert_eq!(canonical("<>").0, "<>");
+        assert_eq!(canonical("><").0, "><");
+        assert_eq!(canonical("+<>.").0, "+<>.");
+        assert_eq!(canonical("<<>+>>>+<<<").0, ">>+<<<+<>");
+    }
+
+    ///every move gets the position of the first one that got to the same cell, which is the
+    ///one that would have gone off the tape
+    #[test]
+    fn canonicalize_keeps_where_the_moves_came_from() {
+        let (program, columns) = canonical("+<-><<>>>+<");
+        assert_eq!(program, "<<>->+>+<");
+        assert_eq!(columns, [2, 6, 2, 2, 1, 1, 9, 9, 1]);
+    }
+
+    ///the ir after the pass has run over the program once
+    fn after(pass: &dyn Pass, source: &str) -> (Ir, PassStats) {
+        let mut ir = Ir::new(tokens(source), true);
+        let stats = pass.run(&mut ir);
+        (ir, stats)
+    }
+
+    #[test]
+    fn cancel_counts_what_it_took_out() {
+        let (ir, stats) = after(&Cancel, "+-+-+.>+<>-");
+        assert_eq!(written(&ir.tokens).0, "+.>");
+        assert_eq!(
+            stats,
+            PassStats {
+                removed: 8,
+                fused: 0
+            }
+        );
+    }
+
+    #[test]
+    fn dead_code_takes_out_loops_that_never_run() {
+        let (ir, stats) = after(&DeadCode, "[-][+>]+[-][.]");
+        assert_eq!(written(&ir.tokens).0, "+[-]");
+        assert_eq!(stats.removed, 10);
+        //a cell that isn't known to be 0 keeps its loop
+        let mut ir = Ir::new(tokens("[.]"), false);
+        DeadCode.run(&mut ir);
+        assert_eq!(written(&ir.tokens).0, "[.]");
+        //and so does a loop with anything between it and the last one
+        let (ir, _) = after(&DeadCode, "+[-]>[-]");
+        assert_eq!(written(&ir.tokens).0, "+[-]>[-]");
+    }
+
+    #[test]
+    fn clear_loop_finds_loops_that_only_clear() {
+        let (ir, stats) = after(&ClearLoop, "+[-]>[+]>[--]>[->+<]");
+        assert_eq!(stats.fused, 2);
+        assert_eq!(ir.fused.keys().copied().collect::<Vec<_>>(), [1, 5]);
+        assert!(ir.fused.values().all(|fused| fused.pass() == "clear-loop"));
+        //they're only found, the instructions stay as they were
+        assert_eq!(written(&ir.tokens).0, "+[-]>[+]>[--]>[->+<]");
+    }
+
+    #[test]
+    fn copy_loop_finds_loops_that_add_the_cell_to_others() {
+        let (ir, stats) = after(&CopyLoop, "+[->+>++<<][-][<+>-][->+<<]");
+        assert_eq!(stats.fused, 2);
+        let Some(Fused::Add(form)) = ir.fused.get(&1) else {
+            panic!("the first loop is a copy loop: {:?}", ir.fused);
+        };
+        assert_eq!(form.step, 255);
+        assert_eq!(form.adds, [(1, 1), (2, 2)]);
+        assert_eq!(form.reach, (0, 2));
+        assert!(matches!(ir.fused.get(&14), Some(Fused::Add(_))));
+    }
+
+    #[test]
+    fn scan_finds_loops_that_only_move_one_way() {
+        let (ir, stats) = after(&Scan, "[>][<<][><][>+][>>>]");
+        assert_eq!(stats.fused, 3);
+        assert_eq!(
+            ir.fused.into_iter().collect::<Vec<_>>(),
+            [
+                (0, Fused::Scan(1)),
+                (3, Fused::Scan(-2)),
+                (15, Fused::Scan(3))
+            ]
+        );
+    }
+
+    ///a loop with another loop in it never runs in one go, and neither does the one inside when
+    ///it isn't a kind that does
+    #[test]
+    fn only_innermost_loops_are_fused() {
+        let mut ir = Ir::new(tokens("+[[-]>[.]<-]"), true);
+        ClearLoop.run(&mut ir);
+        CopyLoop.run(&mut ir);
+        Scan.run(&mut ir);
+        assert_eq!(ir.fused.keys().copied().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn rle_finds_runs_of_the_same_instruction() {
+        let (ir, stats) = after(&Rle, "+++>>-<.>+<<<");
+        assert_eq!(ir.blocks, [0..3, 3..5, 10..13]);
+        assert_eq!(stats.fused, 3);
+        assert_eq!(ir.fusions.runs, Runs::Repeats);
+    }
+
+    #[test]
+    fn offset_fusion_finds_every_run() {
+        let (ir, stats) = after(&OffsetFusion, "+++>>-<.>+<<<.+[-]");
+        assert_eq!(ir.blocks, [0..7, 8..13]);
+        assert_eq!(stats.fused, 2);
+        //rle after it doesn't make its runs any smaller
+        let mut ir = ir;
+        Rle.run(&mut ir);
+        assert_eq!(ir.blocks, [0..7, 8..13]);
+    }
+
+    ///the passes run in the order of passes(), whatever order they're named in, and what's found
+    ///is where it is in the instructions once they stop changing
+    #[test]
+    fn pipeline_runs_passes_in_order() {
+        let pipeline =
+            Pipeline::from_names(&["scan", "cancel", "clear-loop"]).expect("they're all passes");
+        assert_eq!(pipeline.names(), ["cancel", "clear-loop", "scan"]);
+        let mut ir = Ir::new(tokens("+-+-[-]>+<>[-]+-[>]"), false);
+        let mut ran = vec![];
+        let stats = pipeline.run(&mut ir, |pass, _| ran.push(pass.to_string()));
+        assert_eq!(written(&ir.tokens).0, "[-]>+[-][>]");
+        assert_eq!(
+            ir.fused
+                .iter()
+                .map(|(at, fused)| (*at, fused.pass()))
+                .collect::<Vec<_>>(),
+            [(0, "clear-loop"), (5, "clear-loop"), (8, "scan")]
+        );
+        assert_eq!(
+            ran,
+            [
+                "cancel",
+                "clear-loop",
+                "scan",
+                "cancel",
+                "clear-loop",
+                "scan"
+            ]
+        );
+        assert_eq!(
+            stats[0],
+            (
+                "cancel",
+                PassStats {
+                    removed: 8,
+                    fused: 0
+                }
+            )
+        );
+        assert_eq!(stats[1].1.fused, 2);
+        assert_eq!(stats[2].1.fused, 1);
+        assert!(Pipeline::from_names(&["cancel", "unroll"]).is_err());
+    }
+
+    #[test]
+    fn levels_run_more_passes_as_they_go_up() {
+        assert!(Level::O0.pipeline().is_empty());
+        assert_eq!(Level::O1.pipeline().names(), ["cancel", "offset-fusion"]);
+        assert_eq!(
+            Level::O2.pipeline().names(),
+            passes().iter().map(|pass| pass.name()).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,694 @@
+//! a program that has been parsed, checked and optimized once, ready to be run by as many
+//! machines as need it. parsing and optimizing is most of the work of starting a machine, so
+//! running the same program over and over, like when fuzzing it or serving it to many people,
+//! should compile it once and make every machine with TuringMachine::with_program. a program
+//! never changes once it's made, so it can be shared between machines and threads in an Arc.
+//!
+//! the parser and the optimizer work on Instructions, which are easy to match on but take 16
+//! bytes each. the very last thing compiling does is pack them into Ops of 8 bytes, with what
+//! every jump jumps to in them, which is all the machine runs
+use crate::blocks::Block;
+use crate::optimize::{self, Fused, Fusions, Level, PassStats, Pipeline};
+use crate::{annotations, lang, Instruction, ParseError, Position};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+///every instruction that has no number in it, so an op can say which one it is in a byte.
+///custom instructions are the one kind left out, see CUSTOM
+pub(crate) const INSTRUCTIONS: [Instruction; 31] = [
+    Instruction::MoveRight,
+    Instruction::MoveLeft,
+    Instruction::Increment,
+    Instruction::Decrement,
+    Instruction::Output,
+    Instruction::Replace,
+    Instruction::JumpToClose,
+    Instruction::JumpToOpen,
+    Instruction::ProcedureStart,
+    Instruction::ProcedureEnd,
+    Instruction::Call,
+    Instruction::Halt,
+    Instruction::DebugDump,
+    Instruction::Store,
+    Instruction::Restore,
+    Instruction::ShiftRight,
+    Instruction::ShiftLeft,
+    Instruction::Not,
+    Instruction::Xor,
+    Instruction::And,
+    Instruction::Or,
+    Instruction::Fork,
+    Instruction::FlipBit,
+    Instruction::ReadBit,
+    Instruction::WriteBit,
+    Instruction::OpenFile,
+    Instruction::ReadFile,
+    Instruction::WriteFile,
+    Instruction::Eval,
+    Instruction::Random,
+    Instruction::SwitchChannel,
+];
+///the code of a custom instruction, whose index is its operand
+pub(crate) const CUSTOM: u8 = INSTRUCTIONS.len() as u8;
+
+///an instruction packed the way the machine runs it: which instruction it is in one byte, and
+///a number that goes with it. that's 8 bytes, where the instruction with a separate table of
+///jumps took 24, so huge programs take a third of the memory and more of them fits in the cache
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Op {
+    //where the instruction is in INSTRUCTIONS, or CUSTOM
+    code: u8,
+    //for "[" and "]" the index of the bracket that matches it, for "(" the index of the ")"
+    //that closes it, and for a custom instruction which one it is. for a move, "+" or "-" that
+    //starts a block, see blocks.rs, the index of the block plus 1. 0 for everything else
+    operand: u32,
+}
+
+impl Op {
+    fn new(instruction: Instruction) -> Self {
+        match instruction {
+            Instruction::Custom(index) => Op {
+                code: CUSTOM,
+                operand: u32::try_from(index)
+                    .expect("there are never that many custom instructions"),
+            },
+            _ => Op {
+                code: INSTRUCTIONS
+                    .iter()
+                    .position(|known| *known == instruction)
+                    .expect("every instruction but custom ones is in INSTRUCTIONS")
+                    as u8,
+                operand: 0,
+            },
+        }
+    }
+    ///the instruction the op was packed from
+    pub fn instruction(self) -> Instruction {
+        match INSTRUCTIONS.get(self.code as usize) {
+            Some(instruction) => *instruction,
+            None => Instruction::Custom(self.operand as usize),
+        }
+    }
+    ///which instruction it is, see code
+    pub(crate) fn code(self) -> u8 {
+        self.code
+    }
+    ///the index of what the op goes with, see operand. jumps land just after it
+    pub(crate) fn target(self) -> usize {
+        self.operand as usize
+    }
+    ///the index of the program's block the op starts, if it starts one
+    pub(crate) fn block(self) -> Option<usize> {
+        match self.operand != 0
+            && matches!(
+                self.instruction(),
+                Instruction::MoveRight
+                    | Instruction::MoveLeft
+                    | Instruction::Increment
+                    | Instruction::Decrement
+            ) {
+            true => Some(self.operand as usize - 1),
+            false => None,
+        }
+    }
+    ///the op, marked as the start of the program's block with the index
+    pub(crate) fn starting_block(self, index: usize) -> Self {
+        Op {
+            operand: u32::try_from(index + 1).expect("there are fewer blocks than ops"),
+            ..self
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Program {
+    //the instructions, and where in the source every one of them came from, so positions[i] is
+    //the position of ops[i]
+    pub(crate) ops: Vec<Op>,
+    pub(crate) positions: Vec<Position>,
+    //what came after the "!", when the program was parsed with ParseOptions::input_separator
+    pub(crate) input: Option<Vec<u8>>,
+    //the cells named in the comments, when the program was parsed with ParseOptions::cell_names
+    pub(crate) names: BTreeMap<usize, String>,
+    //the text the program was parsed from, up to the "!" if there was one
+    pub(crate) source: Arc<str>,
+    //the runs of moves, "+" and "-" that run with one bounds check, see blocks.rs, and the loops
+    //that run in one go by the index of their "[", see fused.rs. only programs optimized with
+    //the passes that find them have any, and fusions says which those were
+    pub(crate) blocks: Vec<Block>,
+    pub(crate) fused: BTreeMap<usize, Fused>,
+    pub(crate) fusions: Fusions,
+}
+
+///a loop in a program, see Program::loops
+#[derive(PartialEq, Debug, Clone)]
+pub struct LoopInfo {
+    ///the index of the "["
+    pub open: usize,
+    ///the index of the "]"
+    pub close: usize,
+    ///how many loops it's inside of, 0 for a loop that isn't in any
+    pub depth: usize,
+    ///the indices of the instructions between the brackets
+    pub body: Range<usize>,
+}
+
+///how deep loops and procedures can be nested unless the program is parsed with a different
+///limit. nothing that goes through a program runs out of stack on one this deep, the limit is
+///there so a runaway generator gets an error pointing at where it went wrong instead
+pub const MAX_NESTING: usize = 1_000_000;
+
+///how parse reads a program
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParseOptions {
+    pub lang: lang::Lang,
+    ///whether a character that's neither an instruction nor whitespace is an error, instead of a
+    ///comment. only languages whose instructions are a character each can be parsed strictly,
+    ///for ook, spoon and mapped languages this does nothing
+    pub strict: bool,
+    ///whether the program ends at the first "!", with everything after it being its input, the
+    ///way a lot of online interpreters take a program and its input together. ebf1's "!" can't
+    ///be used then
+    pub input_separator: bool,
+    ///whether to read the names given to cells in the comments, see annotations.rs
+    pub cell_names: bool,
+    ///how many loops and procedures deep the program can go, a bracket any deeper is an error
+    pub max_nesting: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            lang: lang::Lang::Brainfuck,
+            strict: false,
+            input_separator: false,
+            cell_names: false,
+            max_nesting: MAX_NESTING,
+        }
+    }
+}
+
+///parses a program the way the options say. where TuringMachine::new stops at the first thing
+///wrong with the program, this finds everything it can, like every unmatched bracket, so an
+///editor can show them all at once
+pub fn parse(source: &str, options: &ParseOptions) -> Result<Program, Vec<ParseError>> {
+    //a "#!" line goes before anything else, so its "!" isn't taken for the separator
+    let body = lang::skip_shebang(source);
+    let shebang = source.len() - body.len();
+    let (body, input) = match body.split_once('!') {
+        Some((body, input)) if options.input_separator => (body, Some(input.as_bytes().to_vec())),
+        _ => (body, None),
+    };
+    let source = &source[..shebang + body.len()];
+    let tokens = lang::tokenize(body, &options.lang).map_err(|e| vec![e])?;
+    let single_characters = !matches!(
+        options.lang,
+        lang::Lang::Ook | lang::Lang::Spoon | lang::Lang::Mapped(_)
+    );
+    let mut errors = vec![];
+    if options.strict && single_characters {
+        errors.extend(lang::stray_characters(body, &tokens));
+    }
+    let names = match options.cell_names {
+        true => annotations::cell_names(body).unwrap_or_else(|e| {
+            errors.push(ParseError::Annotation(e));
+            BTreeMap::new()
+        }),
+        false => BTreeMap::new(),
+    };
+    match Program::check_all(tokens, options.max_nesting) {
+        Ok(program) if errors.is_empty() => Ok(Program {
+            input,
+            names,
+            source: Arc::from(source),
+            ..program
+        }),
+        Ok(_) => Err(errors),
+        Err(mut bracket_errors) => {
+            errors.append(&mut bracket_errors);
+            Err(errors)
+        }
+    }
+}
+
+///what can keep a program from being loaded from a file
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum LoadError {
+    ///the file couldn't be read, with the path it was read from
+    Io(String, std::io::Error),
+    ///the file is compressed with gzip and didn't decompress, with its path
+    #[cfg(feature = "decompress")]
+    Decompress(String, crate::gzip::GzipError),
+    ///the file is compressed with gzip, which needs the decompress feature to read, with its
+    ///path
+    #[cfg(not(feature = "decompress"))]
+    Compressed(String),
+    ///the program couldn't be fetched from the url
+    #[cfg(feature = "net")]
+    Fetch(String, crate::net::FetchError),
+    ///the path is a url, which needs the net feature to fetch
+    #[cfg(not(feature = "net"))]
+    Url(String),
+    ParseError(ParseError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(path, e) if e.kind() == std::io::ErrorKind::NotFound => {
+                write!(f, "no such file: {}", path)
+            }
+            LoadError::Io(path, e) => write!(f, "could not read {}: {}", path, e),
+            #[cfg(feature = "decompress")]
+            LoadError::Decompress(path, e) => write!(f, "could not decompress {}: {}", path, e),
+            #[cfg(not(feature = "decompress"))]
+            LoadError::Compressed(path) => write!(
+                f,
+                "{} is compressed with gzip, which bfint can only read when it's built with the \
+                 decompress feature, or decompress it first with gunzip",
+                path
+            ),
+            #[cfg(feature = "net")]
+            LoadError::Fetch(url, e) => write!(f, "could not fetch {}: {}", url, e),
+            #[cfg(not(feature = "net"))]
+            LoadError::Url(url) => write!(
+                f,
+                "{} is a url, which bfint can only fetch when it's built with the net feature",
+                url
+            ),
+            LoadError::ParseError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+///reads the source text of a program from a file. a byte order mark at the start is skipped,
+///and a file that isn't utf-8 is read a byte to a character, as latin-1, since a program whose
+///comments are in some other encoding still has its instructions in ascii. a file compressed
+///with gzip is decompressed as it's read, whatever it's called, see gzip.rs. an http url is
+///fetched and read the same way, see net.rs
+#[cfg(feature = "std")]
+pub fn read_source(path: impl AsRef<std::path::Path>) -> Result<String, LoadError> {
+    let path = path.as_ref();
+    match path.to_str().filter(|path| is_url(path)) {
+        #[cfg(feature = "net")]
+        Some(url) => {
+            let bytes =
+                crate::net::fetch(url).map_err(|e| LoadError::Fetch(String::from(url), e))?;
+            read_stream(&bytes[..], url)
+        }
+        #[cfg(not(feature = "net"))]
+        Some(url) => Err(LoadError::Url(String::from(url))),
+        None => {
+            let name = path.display().to_string();
+            let file = std::fs::File::open(path).map_err(|e| LoadError::Io(name.clone(), e))?;
+            read_stream(std::io::BufReader::new(file), &name)
+        }
+    }
+}
+
+///reads the source text from the stream, decompressing it as it goes when it's a gzip file.
+///the name is the path or the url it's read from
+#[cfg(feature = "std")]
+fn read_stream(mut stream: impl std::io::BufRead, name: &str) -> Result<String, LoadError> {
+    let io = |e| LoadError::Io(String::from(name), e);
+    //the two bytes every gzip file starts with, which no program does
+    if stream.fill_buf().map_err(io)?.starts_with(&[0x1f, 0x8b]) {
+        //a program from a url can't come to any more decompressed than it could have been sent
+        #[cfg(all(feature = "decompress", feature = "net"))]
+        let limit = match is_url(name) {
+            true => crate::net::MAX_SIZE,
+            false => crate::gzip::MAX_SIZE,
+        };
+        #[cfg(all(feature = "decompress", not(feature = "net")))]
+        let limit = crate::gzip::MAX_SIZE;
+        #[cfg(feature = "decompress")]
+        return crate::gzip::decompress(stream, limit)
+            .map(|bytes| decode_source(&bytes))
+            .map_err(|e| LoadError::Decompress(String::from(name), e));
+        #[cfg(not(feature = "decompress"))]
+        return Err(LoadError::Compressed(String::from(name)));
+    }
+    let mut bytes = vec![];
+    std::io::Read::read_to_end(&mut stream, &mut bytes).map_err(io)?;
+    Ok(decode_source(&bytes))
+}
+
+///whether the path of a program is a url to fetch rather than a file
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+///the source text in the bytes, read the way read_source reads a file
+pub fn decode_source(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    match core::str::from_utf8(bytes) {
+        Ok(source) => String::from(source),
+        Err(_) => bytes.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+impl Program {
+    ///reads the program from a file, see read_source, and compiles it like compile does
+    #[cfg(feature = "std")]
+    pub fn from_file(
+        path: impl AsRef<std::path::Path>,
+        lang: &lang::Lang,
+        level: Level,
+    ) -> Result<Self, LoadError> {
+        Program::compile(&read_source(path)?, lang, level).map_err(LoadError::ParseError)
+    }
+    ///parses the program and runs the optimizer passes of the level over it. the passes count on
+    ///the tape starting out as 0, like it does in a new machine
+    pub fn compile(source: &str, lang: &lang::Lang, level: Level) -> Result<Self, ParseError> {
+        let program = Program::parse(source, lang, &[])?;
+        Ok(program.optimized(&level.pipeline(), true, |_, _| {}).0)
+    }
+    ///turns the source text of a program into a list of instructions, along with the position
+    ///every instruction was found at. brackets and procedures are checked here so that a program
+    ///that parses always has a matching bracket to jump to. the characters in "custom" are
+    ///custom instructions, see custom.rs
+    pub(crate) fn parse(
+        source: &str,
+        lang: &lang::Lang,
+        custom: &[char],
+    ) -> Result<Self, ParseError> {
+        let program = Program::check(lang::tokenize_with_custom(source, lang, custom)?)?;
+        Ok(Program {
+            source: Arc::from(source),
+            ..program
+        })
+    }
+    ///the part of parse that comes after the program has been turned into tokens
+    pub(crate) fn check(tokens: Vec<(Instruction, Position)>) -> Result<Self, ParseError> {
+        Program::check_all(tokens, MAX_NESTING).map_err(|mut errors| errors.swap_remove(0))
+    }
+    ///check, but going on past an unmatched bracket to find the rest. the "]" and ")" that don't
+    ///match come first, in the order they're in, then the "[" and "(" left open, innermost first
+    pub(crate) fn check_all(
+        tokens: Vec<(Instruction, Position)>,
+        max_nesting: usize,
+    ) -> Result<Self, Vec<ParseError>> {
+        let mut errors = vec![];
+        let mut ops: Vec<Op> = vec![];
+        let mut positions = vec![];
+        //the "[" and "(" that haven't been closed yet, with their index in the program. they
+        //share a stack so that they have to nest properly inside each other
+        let mut open: Vec<(Instruction, usize)> = vec![];
+        for (instruction, position) in tokens {
+            //an op only has room for indices that fit in 32 bits
+            let index = ops.len();
+            let Ok(operand) = u32::try_from(index) else {
+                errors.push(ParseError::TooLong(position));
+                return Err(errors);
+            };
+            let mut op = Op::new(instruction);
+            match instruction {
+                Instruction::JumpToClose | Instruction::ProcedureStart => {
+                    if open.len() >= max_nesting {
+                        errors.push(ParseError::TooDeep(position, max_nesting));
+                        return Err(errors);
+                    }
+                    open.push((instruction, index))
+                }
+                Instruction::JumpToOpen => match open.pop() {
+                    Some((Instruction::JumpToClose, start)) => {
+                        ops[start].operand = operand;
+                        op.operand = start as u32;
+                    }
+                    //whatever was popped stays open, so it gets reported too
+                    unmatched => {
+                        open.extend(unmatched);
+                        errors.push(ParseError::UnmatchedClose(position))
+                    }
+                },
+                Instruction::ProcedureEnd => match open.pop() {
+                    Some((Instruction::ProcedureStart, start)) => ops[start].operand = operand,
+                    unmatched => {
+                        open.extend(unmatched);
+                        errors.push(ParseError::UnmatchedProcedureEnd(position))
+                    }
+                },
+                _ => {}
+            }
+            ops.push(op);
+            positions.push(position);
+        }
+        errors.extend(
+            open.iter()
+                .rev()
+                .map(|(instruction, index)| match instruction {
+                    Instruction::ProcedureStart => {
+                        ParseError::UnmatchedProcedureStart(positions[*index])
+                    }
+                    _ => ParseError::UnmatchedOpen(positions[*index]),
+                }),
+        );
+        match errors.is_empty() {
+            true => Ok(Program {
+                ops,
+                positions,
+                input: None,
+                names: BTreeMap::new(),
+                source: Arc::from(""),
+                blocks: vec![],
+                fused: BTreeMap::new(),
+                fusions: Fusions::default(),
+            }),
+            false => Err(errors),
+        }
+    }
+    ///the program after the pipeline's passes, see optimize::Pipeline::run, and what every pass
+    ///did. starts_at_zero is whether the cell the program starts on is known to be 0
+    pub(crate) fn optimized(
+        &self,
+        pipeline: &Pipeline,
+        starts_at_zero: bool,
+        after: impl FnMut(&str, &optimize::Ir),
+    ) -> (Self, Vec<(&'static str, PassStats)>) {
+        let mut ir = optimize::Ir::new(self.tokens(), starts_at_zero);
+        let stats = pipeline.run(&mut ir, after);
+        //the program was parsed with whatever limit it was parsed with, so it isn't checked again
+        let program = Program::check_all(ir.tokens, usize::MAX)
+            .expect("the optimizer passes leave the brackets as they were");
+        let mut program = Program {
+            input: self.input.clone(),
+            names: self.names.clone(),
+            source: Arc::clone(&self.source),
+            ..program
+        };
+        program.fuse(ir.fusions);
+        (program, stats)
+    }
+    ///every instruction with where it came from in the source
+    pub fn tokens(&self) -> Vec<(Instruction, Position)> {
+        self.instructions()
+            .zip(self.positions.iter().copied())
+            .collect()
+    }
+    ///every instruction, in order
+    pub fn instructions(&self) -> impl Iterator<Item = Instruction> + '_ {
+        self.ops.iter().map(|op| op.instruction())
+    }
+    ///the instruction at the index, if the program goes that far
+    pub fn instruction(&self, index: usize) -> Option<Instruction> {
+        self.ops.get(index).map(|op| op.instruction())
+    }
+    ///the instruction at the index and where in the source it came from
+    pub fn instruction_at(&self, index: usize) -> Option<(Instruction, Position)> {
+        Some((self.instruction(index)?, self.positions[index]))
+    }
+    ///the index of the bracket that matches the one at the index, or None when there's no
+    ///bracket there
+    pub fn matching_bracket(&self, index: usize) -> Option<usize> {
+        match self.instruction(index)? {
+            Instruction::JumpToClose | Instruction::JumpToOpen => Some(self.ops[index].target()),
+            _ => None,
+        }
+    }
+    ///every loop in the program, in the order of their "["
+    pub fn loops(&self) -> impl Iterator<Item = LoopInfo> + '_ {
+        //how many loops the instruction is inside of
+        let mut depth = 0;
+        self.ops
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, op)| match op.instruction() {
+                Instruction::JumpToClose => {
+                    depth += 1;
+                    Some(LoopInfo {
+                        open: index,
+                        close: op.target(),
+                        depth: depth - 1,
+                        body: index + 1..op.target(),
+                    })
+                }
+                Instruction::JumpToOpen => {
+                    depth -= 1;
+                    None
+                }
+                _ => None,
+            })
+    }
+    ///where the first "[" or "(" nested more than max deep is, or nothing when none is. for a
+    ///program that was parsed with a different limit than the one it's run with
+    pub fn nested_past(&self, max: usize) -> Option<Position> {
+        let mut depth = 0;
+        for (index, op) in self.ops.iter().enumerate() {
+            match op.instruction() {
+                Instruction::JumpToClose | Instruction::ProcedureStart if depth >= max => {
+                    return Some(self.positions[index])
+                }
+                Instruction::JumpToClose | Instruction::ProcedureStart => depth += 1,
+                Instruction::JumpToOpen | Instruction::ProcedureEnd => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+    ///the source text from one position to another, both included, like the text of a loop
+    ///from the positions of its brackets. positions past the end of the source stop at the end
+    pub fn source_excerpt(&self, from: Position, to: Position) -> &str {
+        let start = offset(&self.source, from);
+        let end = offset(&self.source, to);
+        let end = end + self.source[end..].chars().next().map_or(0, char::len_utf8);
+        self.source.get(start..end).unwrap_or("")
+    }
+    ///a hash of the source the program was parsed from, the same one checkpoints are checked
+    ///with. it's fnv-1a, which gives the same number on every computer and every version of rust
+    pub fn hash(&self) -> u64 {
+        fnv1a(&self.source)
+    }
+    ///how many instructions there are
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+    ///the input that came after the program's "!", see ParseOptions::input_separator
+    pub fn embedded_input(&self) -> Option<&[u8]> {
+        self.input.as_deref()
+    }
+    ///the names given to cells in the program's comments, by the index of the cell, see
+    ///ParseOptions::cell_names
+    pub fn cell_names(&self) -> &BTreeMap<usize, String> {
+        &self.names
+    }
+    ///how many bytes the ops take up, without where they came from in the source
+    pub fn code_size(&self) -> usize {
+        self.ops.len() * core::mem::size_of::<Op>()
+    }
+}
+
+///parses brainfuck with the default options, see parse. the error is the first thing wrong with
+///the program, like TuringMachine::new gives
+impl FromStr for Program {
+    type Err = ParseError;
+    fn from_str(source: &str) -> Result<Self, ParseError> {
+        parse(source, &ParseOptions::default()).map_err(|mut errors| errors.swap_remove(0))
+    }
+}
+
+impl TryFrom<&str> for Program {
+    type Error = ParseError;
+    fn try_from(source: &str) -> Result<Self, ParseError> {
+        source.parse()
+    }
+}
+
+///the source is read from the bytes like read_source reads a file, see decode_source
+impl TryFrom<&[u8]> for Program {
+    type Error = ParseError;
+    fn try_from(bytes: &[u8]) -> Result<Self, ParseError> {
+        decode_source(bytes).parse()
+    }
+}
+
+///the program without its comments, every instruction written as its usual character, so it
+///parses back into the same instructions in the language it was written in. custom
+///instructions are written as the character they were in the source
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (instruction, position) in self.tokens() {
+            let symbol = symbol(instruction).unwrap_or_else(|| {
+                self.source[offset(&self.source, position)..]
+                    .chars()
+                    .next()
+                    .unwrap_or('?')
+            });
+            write!(f, "{}", symbol)?;
+        }
+        Ok(())
+    }
+}
+
+///the usual character of the instruction, the one it's written as in brainfuck or the
+///extension it comes from. custom instructions don't have one
+pub(crate) fn symbol(instruction: Instruction) -> Option<char> {
+    match instruction {
+        Instruction::MoveRight => Some('>'),
+        Instruction::MoveLeft => Some('<'),
+        Instruction::Increment | Instruction::FlipBit => Some('+'),
+        Instruction::Decrement => Some('-'),
+        Instruction::Output => Some('.'),
+        Instruction::Replace | Instruction::ReadBit => Some(','),
+        Instruction::JumpToClose => Some('['),
+        Instruction::JumpToOpen => Some(']'),
+        Instruction::ProcedureStart => Some('('),
+        Instruction::ProcedureEnd => Some(')'),
+        Instruction::Call | Instruction::WriteFile => Some(':'),
+        Instruction::Halt => Some('@'),
+        Instruction::DebugDump | Instruction::OpenFile => Some('#'),
+        Instruction::Store => Some('$'),
+        Instruction::Restore => Some('!'),
+        Instruction::ShiftRight => Some('}'),
+        Instruction::ShiftLeft => Some('{'),
+        Instruction::Not => Some('~'),
+        Instruction::Xor => Some('^'),
+        Instruction::And => Some('&'),
+        Instruction::Or => Some('|'),
+        Instruction::Fork => Some('Y'),
+        Instruction::WriteBit | Instruction::ReadFile => Some(';'),
+        Instruction::Eval => Some('%'),
+        Instruction::Random => Some('?'),
+        Instruction::SwitchChannel => Some('\''),
+        Instruction::Custom(_) => None,
+    }
+}
+
+///the fnv-1a hash of the text
+pub(crate) fn fnv1a(source: &str) -> u64 {
+    fnv1a_bytes(source.bytes())
+}
+
+///the fnv-1a hash of the bytes
+pub(crate) fn fnv1a_bytes(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+///where the position is in the source, in bytes
+fn offset(source: &str, position: Position) -> usize {
+    let mut start = 0;
+    for line in source
+        .split_inclusive('\n')
+        .take(position.line.saturating_sub(1))
+    {
+        start += line.len();
+    }
+    let line = &source[start..];
+    start
+        + line
+            .char_indices()
+            .nth(position.column.saturating_sub(1))
+            .map_or(line.len(), |(index, _)| index)
+}
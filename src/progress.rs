@@ -0,0 +1,229 @@
+//! reporting on long runs while they happen, so they aren't silent until the end
+use crate::shared::{lock, shared, Shared};
+use crate::TuringMachine;
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant, SystemTime};
+
+///how far a run has got, handed to the progress callback
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Progress {
+    ///how many instructions have run so far
+    pub steps: u64,
+    ///how long ago the progress callback was set
+    pub elapsed: Duration,
+    ///how many bytes the program has written so far
+    pub output_bytes: u64,
+    ///the cell the pointer is at
+    pub pointer: usize,
+    ///what's in the cell the pointer is at
+    pub cell: u8,
+}
+
+///the progress callback, shared between clones of a machine
+pub(crate) type Callback = Shared<dyn FnMut(&Progress) -> ControlFlow<()> + Send>;
+
+///the progress callback along with how often to call it
+#[derive(Clone)]
+pub(crate) struct Reporter {
+    pub(crate) every: u64,
+    started: Instant,
+    //the step count the callback was last called at, so a machine that's waiting for input
+    //doesn't report the same step over and over
+    reported_at: u64,
+    callback: Callback,
+}
+
+impl TuringMachine {
+    ///calls the callback every so many instructions while the program runs, and once more when it
+    ///ends so the totals are right. if the callback gives back ControlFlow::Break the run stops
+    ///the same way it does when it's cancelled
+    pub fn set_progress_callback(
+        &mut self,
+        every_n_steps: u64,
+        callback: impl FnMut(&Progress) -> ControlFlow<()> + Send + 'static,
+    ) {
+        self.progress = Some(Reporter {
+            every: every_n_steps.max(1),
+            started: Instant::now(),
+            reported_at: self.steps,
+            callback: shared(callback),
+        });
+    }
+    ///calls the progress callback if it's due, or whenever "always" is set. gives back true if
+    ///the callback wants the run to stop
+    pub(crate) fn report_progress(&mut self, always: bool) -> bool {
+        let Some(reporter) = &mut self.progress else {
+            return false;
+        };
+        if !always
+            && (self.steps == reporter.reported_at || !self.steps.is_multiple_of(reporter.every))
+        {
+            return false;
+        }
+        reporter.reported_at = self.steps;
+        let callback = Shared::clone(&reporter.callback);
+        let progress = self.progress();
+        let flow = (lock(&callback))(&progress);
+        flow.is_break()
+    }
+    ///how far the run has got right now, the same as the progress callback is handed. the time
+    ///is since the callback was set, or nothing if there isn't one
+    pub fn progress(&self) -> Progress {
+        Progress {
+            steps: self.steps,
+            elapsed: self
+                .progress
+                .as_ref()
+                .map_or(Duration::ZERO, |reporter| reporter.started.elapsed()),
+            output_bytes: self.output_bytes,
+            pointer: self.pointer,
+            cell: self.tape[self.pointer],
+        }
+    }
+}
+
+///how often a state log writes a line: after so much time, or so many steps
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Every {
+    Time(Duration),
+    Steps(u64),
+}
+
+impl Every {
+    ///reads "10s", "500ms" or "5m" as a time, and a plain number like "1000000" as steps
+    pub fn from_name(name: &str) -> Option<Self> {
+        let every = match name {
+            _ if name.ends_with("ms") => {
+                Every::Time(Duration::from_millis(name[..name.len() - 2].parse().ok()?))
+            }
+            _ if name.ends_with('s') => Every::Time(Duration::from_secs_f64(
+                name[..name.len() - 1].parse().ok()?,
+            )),
+            _ if name.ends_with('m') => Every::Time(Duration::from_secs_f64(
+                name[..name.len() - 1].parse::<f64>().ok()? * 60.0,
+            )),
+            _ => Every::Steps(name.parse().ok()?),
+        };
+        match every {
+            Every::Time(time) if time.is_zero() => None,
+            Every::Steps(0) => None,
+            every => Some(every),
+        }
+    }
+    ///how many steps apart the progress callback should be called to keep up with this. time
+    ///is only looked at that often, so between two looks a run does nothing extra
+    pub fn check_every(&self) -> u64 {
+        match self {
+            Every::Time(_) => 100_000,
+            Every::Steps(steps) => *steps,
+        }
+    }
+}
+
+///a line about the state of the run every so often, for runs that go on for hours with no one
+///watching. every line is "key=value" pairs, like:
+///
+///```text
+///time=2024-05-01T12:00:00Z steps=1000000 steps_per_sec=81234567 pointer=12 cell=0 output_bytes=40 peak_memory_kb=2048
+///```
+///
+///with steps_per_sec worked out over the time since the line before, and the last line has an
+///outcome too
+pub struct StateLog {
+    every: Every,
+    out: Box<dyn Write + Send>,
+    //the steps and time of the line before, for the speed
+    last_steps: u64,
+    last_elapsed: Duration,
+}
+
+impl StateLog {
+    pub fn new(every: Every, out: Box<dyn Write + Send>) -> Self {
+        StateLog {
+            every,
+            out,
+            last_steps: 0,
+            last_elapsed: Duration::ZERO,
+        }
+    }
+    pub fn every(&self) -> Every {
+        self.every
+    }
+    ///writes a line if one is due by the progress. peak_memory is how much memory the process
+    ///has used at most, in KiB, if that can be found out
+    pub fn update(&mut self, progress: &Progress, peak_memory: impl FnOnce() -> Option<u64>) {
+        let due = match self.every {
+            Every::Time(time) => progress.elapsed.saturating_sub(self.last_elapsed) >= time,
+            Every::Steps(steps) => progress.steps - self.last_steps >= steps,
+        };
+        if due {
+            self.write(progress, peak_memory(), None);
+        }
+    }
+    ///writes the last line, saying how the run ended, like "halted" or "error"
+    pub fn finish(&mut self, progress: &Progress, peak_memory: Option<u64>, outcome: &str) {
+        self.write(progress, peak_memory, Some(outcome));
+    }
+    fn write(&mut self, progress: &Progress, peak_memory: Option<u64>, outcome: Option<&str>) {
+        let seconds = progress
+            .elapsed
+            .saturating_sub(self.last_elapsed)
+            .as_secs_f64();
+        let steps = progress.steps - self.last_steps;
+        let speed = match seconds > 0.0 {
+            true => (steps as f64 / seconds) as u64,
+            false => 0,
+        };
+        let mut line = format!(
+            "time={} steps={} steps_per_sec={} pointer={} cell={} output_bytes={}",
+            timestamp(SystemTime::now()),
+            progress.steps,
+            speed,
+            progress.pointer,
+            progress.cell,
+            progress.output_bytes
+        );
+        if let Some(peak_memory) = peak_memory {
+            line += &format!(" peak_memory_kb={}", peak_memory);
+        }
+        if let Some(outcome) = outcome {
+            line += &format!(" outcome={:?}", outcome);
+        }
+        self.last_steps = progress.steps;
+        self.last_elapsed = progress.elapsed;
+        //a log that can't be written to isn't a reason to stop the run
+        let _ = writeln!(self.out, "{}", line).and_then(|()| self.out.flush());
+    }
+}
+
+///the time as "2024-05-01T12:00:00Z", in utc
+pub(crate) fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, rest) = (seconds / 86400, seconds % 86400);
+    //the days since 1970 to a date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = match mp < 10 {
+        true => mp + 3,
+        false => mp - 9,
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
@@ -0,0 +1,210 @@
+//! what produced a result, so a stats report, a core dump, a checkpoint or a translated program
+//! looked at a week later says how to make it again: the version of bfint, how the program was
+//! run, a hash of its source, where its input came from, the seed of "?", when it started and on
+//! what. it's written as lines like "provenance seed 42", one for everything it records, and
+//! anything that has those lines in it, even behind a "# " in a comment or in the messages of
+//! "--message-format json", can be read back with find, which is what "bfint provenance" does
+use crate::config::{CellWidth, InterpreterConfig};
+use crate::lang;
+use std::fmt;
+
+///what a run was, by its name in the lines it's written as, in the order they're written in
+#[derive(PartialEq, Debug, Clone)]
+pub struct Provenance {
+    ///the version of bfint
+    pub version: String,
+    ///what ran the program, like "interpreter" or "tiered interpreter"
+    pub engine: String,
+    ///how the program was optimized, like "-O2"
+    pub level: String,
+    ///what the cells are, and what happens at the end of input, as the settings that say so
+    pub cells: String,
+    ///the hash of the program's source, the one checkpoints keep
+    pub program: String,
+    ///where the input came from, like "stdin" or a file with the hash of what was in it
+    pub input: String,
+    ///what "?" started from, which --seed makes it start from again
+    pub seed: String,
+    ///when the run started, in utc
+    pub started: String,
+    ///the operating system and architecture it ran on
+    pub host: String,
+}
+
+impl Provenance {
+    ///the provenance of a run starting now, of the source with the config, and with "?" starting
+    ///from the seed. the input is stdin, whoever knows better says so
+    pub fn new(config: &InterpreterConfig, lang: &lang::Lang, source: &str, seed: u64) -> Self {
+        let engine = engine(lang, config.tiered);
+        let eof = config.eof.name();
+        let signedness = config.cell_signedness.name();
+        let width = match config.cell_width {
+            CellWidth::Eight => "8".to_string(),
+            #[cfg(feature = "bigint")]
+            CellWidth::Big => format!(
+                "big, cell-underflow {}",
+                config
+                    .cell_underflow
+                    .unwrap_or(crate::big::Underflow::Saturate)
+                    .name()
+            ),
+            #[cfg(not(feature = "bigint"))]
+            CellWidth::Big => "big".to_string(),
+        };
+        Provenance {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            engine: engine.to_string(),
+            level: config.level.to_string(),
+            cells: format!(
+                "cell-width {}, eof {}, cell-signedness {}",
+                width, eof, signedness
+            ),
+            program: format!("{:016x}", crate::checkpoint::program_hash(source)),
+            input: "stdin".to_string(),
+            seed: seed.to_string(),
+            started: crate::progress::timestamp(std::time::SystemTime::now()),
+            host: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        }
+    }
+    ///the name of every field and what's in it, in order
+    fn fields(&self) -> [(&'static str, &String); 9] {
+        [
+            ("version", &self.version),
+            ("engine", &self.engine),
+            ("level", &self.level),
+            ("cells", &self.cells),
+            ("program", &self.program),
+            ("input", &self.input),
+            ("seed", &self.seed),
+            ("started", &self.started),
+            ("host", &self.host),
+        ]
+    }
+    ///the lines it's written as, each ending in a newline
+    pub fn to_text(&self) -> String {
+        self.fields()
+            .iter()
+            .map(|(name, value)| format!("provenance {} {}\n", name, value.replace('\n', " ")))
+            .collect()
+    }
+    ///the lines it's written as, each behind a "# ", for files that take comments like that
+    pub fn to_comment(&self) -> String {
+        self.to_text()
+            .lines()
+            .map(|line| format!("# {}\n", line))
+            .collect()
+    }
+    ///the last provenance written in the text, which can be the lines to_text writes, the same
+    ///in comments, or the json messages of a run with --stats. a line from the "version" on
+    ///starts a new one, so lines that happen to look like them earlier on, like in the source
+    ///kept in a core dump, don't get mixed in
+    pub fn find(text: &str) -> Option<Self> {
+        let mut found: Option<Provenance> = None;
+        let mut read = |line: &str| {
+            let Some((name, value)) = line
+                .trim_start_matches("# ")
+                .strip_prefix("provenance ")
+                .and_then(|rest| rest.split_once(' '))
+            else {
+                return;
+            };
+            if name == "version" {
+                found = Some(Provenance {
+                    version: value.to_string(),
+                    engine: String::new(),
+                    level: String::new(),
+                    cells: String::new(),
+                    program: String::new(),
+                    input: String::new(),
+                    seed: String::new(),
+                    started: String::new(),
+                    host: String::new(),
+                });
+            }
+            let Some(provenance) = &mut found else {
+                return;
+            };
+            let field = match name {
+                "engine" => &mut provenance.engine,
+                "level" => &mut provenance.level,
+                "cells" => &mut provenance.cells,
+                "program" => &mut provenance.program,
+                "input" => &mut provenance.input,
+                "seed" => &mut provenance.seed,
+                "started" => &mut provenance.started,
+                "host" => &mut provenance.host,
+                _ => return,
+            };
+            *field = value.to_string();
+        };
+        for line in text.lines() {
+            match line.contains("\"kind\":\"provenance\"") {
+                true => {
+                    for line in json_message(line).unwrap_or_default().lines() {
+                        read(line)
+                    }
+                }
+                false => read(line),
+            }
+        }
+        found
+    }
+}
+
+impl fmt::Display for Provenance {
+    ///every field on a line of its own, with the values lined up
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in self.fields() {
+            writeln!(f, "{:<9}{}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+///what runs a program in the language, with --tiered or without it
+pub fn engine(lang: &lang::Lang, tiered: bool) -> &'static str {
+    match (lang, tiered) {
+        (lang::Lang::Brainfork, _) => "brainfork scheduler",
+        (_, true) => "tiered interpreter",
+        (_, false) => "interpreter",
+    }
+}
+
+///the file at the path, with the hash of what's in it, for describing where input came from
+pub fn describe_file(path: &str) -> String {
+    match std::fs::read(path) {
+        Ok(bytes) => describe_input(path, &bytes),
+        Err(_) => format!("the file {}", path),
+    }
+}
+
+///input that was read from the file at the path, with its hash
+pub fn describe_input(path: &str, bytes: &[u8]) -> String {
+    format!(
+        "the file {} ({:016x})",
+        path,
+        crate::program::fnv1a_bytes(bytes.iter().copied())
+    )
+}
+
+///the message of a line written by report with "--message-format json", see report.rs
+fn json_message(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("\"message\":\"")?;
+    let mut message = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(message),
+            '\\' => match chars.next()? {
+                'n' => message.push('\n'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    message.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => message.push(c),
+            },
+            c => message.push(c),
+        }
+    }
+    None
+}
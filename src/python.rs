@@ -0,0 +1,157 @@
+//! python bindings, built with `--features python` (maturin does this using pyproject.toml). the
+//! module is called `brainfuck` and has a `run` function for running a program in one go, and a
+//! `Machine` class for stepping through one
+use crate::lang::Lang;
+use crate::shared::lock;
+use crate::{Position, TuringMachine};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+create_exception!(
+    brainfuck,
+    ParseError,
+    PyException,
+    "the program could not be parsed"
+);
+create_exception!(
+    brainfuck,
+    RuntimeError,
+    PyException,
+    "the program failed while running"
+);
+
+///makes a machine for the program that reads from "input" and writes into the returned buffer.
+///a program that doesn't parse becomes a ParseError
+fn machine(program: &str, input: &[u8]) -> PyResult<Machine> {
+    let mut tm = TuringMachine::new(program, &Lang::Brainfuck)
+        .map_err(|e| ParseError::new_err(e.to_string()))?;
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    Ok(Machine {
+        tm,
+        output,
+        breakpoints: vec![],
+    })
+}
+
+///runs a whole program and gives back everything it wrote
+#[pyfunction]
+#[pyo3(signature = (program, input = b"".as_slice(), max_steps = 10_000_000))]
+fn run<'py>(
+    py: Python<'py>,
+    program: &str,
+    input: &[u8],
+    max_steps: u64,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mut machine = machine(program, input)?;
+    machine.tm.step_limit = Some(max_steps);
+    machine
+        .tm
+        .run()
+        .map_err(|e| RuntimeError::new_err(e.to_string()))?;
+    Ok(machine.take_output(py))
+}
+
+///a loaded program that can be run a step at a time, looked at, and stopped at breakpoints
+#[pyclass]
+struct Machine {
+    tm: TuringMachine,
+    output: Arc<Mutex<Vec<u8>>>,
+    //the places in the source that running stops at, as (line, column)
+    breakpoints: Vec<Position>,
+}
+
+#[pymethods]
+impl Machine {
+    #[new]
+    #[pyo3(signature = (program, input = b"".as_slice()))]
+    fn new(program: &str, input: &[u8]) -> PyResult<Self> {
+        machine(program, input)
+    }
+    ///runs a single instruction. gives back whether the program has instructions left after it
+    fn step(&mut self) -> PyResult<bool> {
+        let tm = &mut self.tm;
+        let mut step = || {
+            if tm.has_instructions_left() {
+                tm.perform_next_instruction()?;
+            }
+            if !tm.has_instructions_left() {
+                tm.finish()?;
+            }
+            Ok(tm.has_instructions_left())
+        };
+        step().map_err(|e: crate::RuntimeError| RuntimeError::new_err(e.to_string()))
+    }
+    ///runs until the program ends or reaches a breakpoint. always runs at least one instruction,
+    ///so calling it again carries on past the breakpoint it stopped at. gives back whether it
+    ///stopped at a breakpoint
+    fn run(&mut self) -> PyResult<bool> {
+        while self.step()? {
+            let position = self.tm.program.positions[self.tm.program_counter];
+            if self.breakpoints.contains(&position) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+    ///stops "run" before the instruction at this line and column of the source
+    fn add_breakpoint(&mut self, line: usize, column: usize) {
+        let position = Position { line, column };
+        if !self.breakpoints.contains(&position) {
+            self.breakpoints.push(position);
+        }
+    }
+    fn remove_breakpoint(&mut self, line: usize, column: usize) {
+        self.breakpoints
+            .retain(|position| *position != Position { line, column });
+    }
+    fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+    ///the breakpoints that are set, as (line, column) pairs
+    #[getter]
+    fn breakpoints(&self) -> Vec<(usize, usize)> {
+        self.breakpoints
+            .iter()
+            .map(|position| (position.line, position.column))
+            .collect()
+    }
+    #[getter]
+    fn pointer(&self) -> usize {
+        self.tm.pointer
+    }
+    #[getter]
+    fn steps(&self) -> u64 {
+        self.tm.steps
+    }
+    ///the value of the cell at "index"
+    fn cell(&self, index: usize) -> PyResult<u8> {
+        self.tm.tape.get(index).copied().ok_or_else(|| {
+            pyo3::exceptions::PyIndexError::new_err(format!("no cell {} on the tape", index))
+        })
+    }
+    ///the cells from "start" up to but not including "end", like a python slice
+    #[pyo3(signature = (start = 0, end = 30000))]
+    fn tape<'py>(&self, py: Python<'py>, start: usize, end: usize) -> Bound<'py, PyBytes> {
+        let end = end.min(self.tm.tape.len());
+        PyBytes::new(py, &self.tm.tape[start.min(end)..end])
+    }
+    ///the bytes the program has written since the last time this was called
+    fn take_output<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &std::mem::take(&mut *lock(&self.output)))
+    }
+}
+
+#[pymodule]
+fn brainfuck(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(run, m)?)?;
+    m.add_class::<Machine>()?;
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+    m.add("RuntimeError", m.py().get_type::<RuntimeError>())?;
+    Ok(())
+}
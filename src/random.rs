@@ -0,0 +1,39 @@
+//! the random numbers behind bf-rand's "?". this is splitmix64, which is tiny, fast and good
+//! enough for toy programs. giving it the same seed always gives the same bytes, which makes runs
+//! that use randomness reproducible
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hasher};
+
+///a pseudo random number generator, its whole state is a single number
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Rng {
+    pub state: u64,
+}
+
+impl Rng {
+    ///a generator that always gives the same bytes for the same seed
+    pub fn from_seed(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+    ///a generator seeded from the randomness the operating system hands to hash maps, so every
+    ///run gets different bytes
+    #[cfg(feature = "std")]
+    pub fn from_entropy() -> Self {
+        let seed = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        Rng::from_seed(seed)
+    }
+    ///the next random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+    ///the next random byte, which is just the lowest byte of the next number
+    pub fn next_byte(&mut self) -> u8 {
+        self.next_u64() as u8
+    }
+}
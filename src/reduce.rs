@@ -0,0 +1,312 @@
+//! shrinking a program that shows a bug down to as little of it as still shows it, for
+//! "bfint reduce". whatever found the bug, a crosscheck, a fuzzer or a user, is left with a
+//! program full of things that have nothing to do with it, and this takes them out.
+//!
+//! it's delta debugging over the instructions, one level of the program at a time: the top of
+//! it and then every loop's body, in the order of their "[". the pieces of a level are its
+//! instructions and its loops, whole, so taking any of them out leaves every bracket matched.
+//! every run of the same instruction is tried without first, then the whole level, its halves,
+//! quarters and so on down to one at a time, and whatever still shows the bug is kept. once a
+//! round over every level takes nothing out the program is as small as this gets it. nothing is
+//! random, so the same program and check always end up at the same place.
+//!
+//! the check is anything that says whether a program still shows the bug. the command line has
+//! one that runs a command on it, and Divergence is the one crosscheck would use
+use crate::crosscheck::{self, Configs};
+use crate::lang::Lang;
+use crate::optimize::Level;
+use crate::{minify, Instruction, ParseError, Program};
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+///everything that can keep a program from being reduced
+#[derive(PartialEq, Debug)]
+pub enum ReduceError {
+    ///the language has commands longer than a single character, like ook, so there's nothing
+    ///to write the smaller programs in
+    UnsupportedLang,
+    ParseError(ParseError),
+    ///the program doesn't show the bug in the first place, or stops showing it once its
+    ///comments are taken out
+    NotReproduced,
+}
+
+impl fmt::Display for ReduceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReduceError::UnsupportedLang => {
+                write!(
+                    f,
+                    "only languages with single character commands can be reduced"
+                )
+            }
+            ReduceError::ParseError(e) => write!(f, "{}", e),
+            ReduceError::NotReproduced => write!(
+                f,
+                "the program stops showing the bug once its comments are taken out"
+            ),
+        }
+    }
+}
+
+///what reducing a program came to
+#[derive(PartialEq, Debug, Clone)]
+pub struct Reduction {
+    ///the smallest program found, without any comments
+    pub source: String,
+    ///how many instructions the program had, and how many are left
+    pub before: usize,
+    pub after: usize,
+    ///how many programs were checked to get there
+    pub checks: usize,
+}
+
+///shrinks the program for as long as still_fails says the smaller one still shows the bug, see
+///the top of this file. still_fails is never asked about the same program twice
+pub fn reduce(
+    source: &str,
+    lang: &Lang,
+    still_fails: impl FnMut(&str) -> bool,
+) -> Result<Reduction, ReduceError> {
+    if matches!(lang, Lang::Ook | Lang::Spoon | Lang::Mapped(_)) {
+        return Err(ReduceError::UnsupportedLang);
+    }
+    let program = Program::compile(source, lang, Level::O0).map_err(ReduceError::ParseError)?;
+    let symbols = minify::symbols(lang);
+    if program.instructions().any(|instruction| {
+        !symbols
+            .iter()
+            .any(|(symbol_for, _)| *symbol_for == instruction)
+    }) {
+        return Err(ReduceError::UnsupportedLang);
+    }
+    let before = program.len();
+    let mut reducer = Reducer {
+        lang: lang.clone(),
+        symbols,
+        program,
+        still_fails,
+        tried: HashSet::new(),
+    };
+    let whole = reducer.program.instructions().collect::<Vec<_>>();
+    if !reducer.check(&whole) {
+        return Err(ReduceError::NotReproduced);
+    }
+    loop {
+        let mut shrunk = false;
+        //the levels are counted again every time, since taking a loop out takes its levels
+        //with it. the ones before the level being reduced are never touched by it
+        let mut level = 0;
+        while let Some(body) = reducer.levels().get(level).cloned() {
+            shrunk |= reducer.reduce_level(body);
+            level += 1;
+        }
+        if !shrunk {
+            break;
+        }
+    }
+    let instructions = reducer.program.instructions().collect::<Vec<_>>();
+    Ok(Reduction {
+        source: reducer.render(&instructions),
+        before,
+        after: instructions.len(),
+        checks: reducer.tried.len(),
+    })
+}
+
+struct Reducer<F> {
+    lang: Lang,
+    symbols: Vec<(Instruction, char)>,
+    ///the smallest program that still shows the bug so far
+    program: Program,
+    still_fails: F,
+    ///every program that has been checked, so none of them is checked twice
+    tried: HashSet<String>,
+}
+
+impl<F: FnMut(&str) -> bool> Reducer<F> {
+    ///the program written out in the language
+    fn render(&self, instructions: &[Instruction]) -> String {
+        instructions
+            .iter()
+            .map(|instruction| {
+                self.symbols
+                    .iter()
+                    .find(|(symbol_for, _)| symbol_for == instruction)
+                    .map(|(_, symbol)| *symbol)
+                    .expect("every instruction was checked to have a symbol before reducing")
+            })
+            .collect()
+    }
+    ///whether the program shows the bug, keeping it when it does
+    fn check(&mut self, instructions: &[Instruction]) -> bool {
+        let source = self.render(instructions);
+        if !self.tried.insert(source.clone()) || !(self.still_fails)(&source) {
+            return false;
+        }
+        self.program = Program::compile(&source, &self.lang, Level::O0)
+            .expect("taking whole pieces out of a program leaves it parsing");
+        true
+    }
+    ///the instructions of the top of the program and of every loop's body, in the order of
+    ///their "["
+    fn levels(&self) -> Vec<Range<usize>> {
+        std::iter::once(0..self.program.len())
+            .chain(self.program.loops().map(|info| info.body))
+            .collect()
+    }
+    ///the pieces of the level, each one an instruction or a whole loop or procedure
+    fn pieces(&self, level: &Range<usize>) -> Vec<Range<usize>> {
+        let mut pieces = vec![];
+        let mut index = level.start;
+        while index < level.end {
+            let end = match self.program.instruction(index) {
+                Some(Instruction::JumpToClose) => self
+                    .program
+                    .matching_bracket(index)
+                    .expect("a loop's \"[\" has a \"]\""),
+                Some(Instruction::ProcedureStart) => self.procedure_end(index),
+                _ => index,
+            };
+            pieces.push(index..end + 1);
+            index = end + 1;
+        }
+        pieces
+    }
+    ///the index of the ")" that ends the procedure starting at the index
+    fn procedure_end(&self, start: usize) -> usize {
+        let mut depth = 0;
+        for (index, instruction) in self.program.instructions().enumerate().skip(start) {
+            match instruction {
+                Instruction::ProcedureStart => depth += 1,
+                Instruction::ProcedureEnd if depth == 1 => return index,
+                Instruction::ProcedureEnd => depth -= 1,
+                _ => {}
+            }
+        }
+        unreachable!("a program that parses has a \")\" for every \"(\"")
+    }
+    ///tries the program without the instructions, and keeps it if it still shows the bug
+    fn remove(&mut self, instructions: Range<usize>) -> bool {
+        let mut smaller = self.program.instructions().collect::<Vec<_>>();
+        smaller.drain(instructions);
+        self.check(&smaller)
+    }
+    ///takes out as much of the level as it can, and gives back whether it took out anything
+    fn reduce_level(&mut self, mut level: Range<usize>) -> bool {
+        let mut shrunk = false;
+        //every run of the same instruction first, like the "+++++" that sets up a cell
+        let mut index = 0;
+        loop {
+            let pieces = self.pieces(&level);
+            let Some(start) = pieces.get(index).map(|piece| piece.start) else {
+                break;
+            };
+            let instruction = self.program.instruction(start);
+            let length = pieces[index..]
+                .iter()
+                .take_while(|piece| {
+                    piece.len() == 1 && self.program.instruction(piece.start) == instruction
+                })
+                .count();
+            if length > 1 && self.remove(start..start + length) {
+                level.end -= length;
+                shrunk = true;
+                continue;
+            }
+            index += length.max(1);
+        }
+        //then the pieces in chunks, the whole level first and then halving them every time none
+        //of them can go
+        let mut chunks = 1;
+        loop {
+            let pieces = self.pieces(&level);
+            if pieces.is_empty() {
+                break;
+            }
+            let size = pieces.len().div_ceil(chunks.min(pieces.len()));
+            let removed = pieces.chunks(size).find_map(|chunk| {
+                let instructions = chunk[0].start..chunk[chunk.len() - 1].end;
+                let length = instructions.len();
+                self.remove(instructions).then_some(length)
+            });
+            match removed {
+                Some(length) => {
+                    level.end -= length;
+                    shrunk = true;
+                    chunks = (chunks - 1).max(1);
+                }
+                None if size == 1 => break,
+                None => chunks *= 2,
+            }
+        }
+        shrunk
+    }
+}
+
+///the check crosscheck would make: whether one of the configs still goes its own way from the
+///reference. it's the same config that went its own way on the program being reduced, and the
+///reference has to end like it did there, halting or failing, so the program doesn't turn into
+///one that never stops, which every config would run out of steps on in its own place
+pub struct Divergence {
+    configs: Configs,
+    path: PathBuf,
+    input: Vec<u8>,
+    step_limit: u64,
+    ///the config that went its own way, and whether the reference halted
+    config: usize,
+    halted: bool,
+}
+
+impl Divergence {
+    ///what went wrong with the program under the configs, or nothing when every config runs it
+    ///the same as the reference. the path is only for telling its language
+    pub fn find(
+        source: &str,
+        path: &Path,
+        input: &[u8],
+        configs: Configs,
+        step_limit: u64,
+    ) -> Option<Self> {
+        let mut divergence = Divergence {
+            configs,
+            path: path.to_path_buf(),
+            input: input.to_vec(),
+            step_limit,
+            config: 0,
+            halted: false,
+        };
+        (divergence.config, divergence.halted) = (0..divergence.configs.configs.len())
+            .filter(|config| *config != divergence.configs.reference)
+            .find_map(|config| Some((config, divergence.diverges(source, config)?)))?;
+        Some(divergence)
+    }
+    ///whether the reference halted on the program, when the config goes its own way on it
+    fn diverges(&self, source: &str, config: usize) -> Option<bool> {
+        let run = |config: usize| {
+            crosscheck::run(
+                source,
+                &self.path,
+                &self.input,
+                &self.configs.configs[config].1,
+                self.step_limit,
+            )
+            .ok()
+            .map(|(run, _)| run)
+        };
+        let reference = run(self.configs.reference)?;
+        let run = run(config)?;
+        crosscheck::divergence(&reference, &run)?;
+        Some(reference.error.is_none())
+    }
+    ///whether the program still goes wrong the same way
+    pub fn reproduces(&self, source: &str) -> bool {
+        self.diverges(source, self.config) == Some(self.halted)
+    }
+    ///the name of the config that goes its own way
+    pub fn config_name(&self) -> &str {
+        &self.configs.configs[self.config].0
+    }
+}
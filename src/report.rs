@@ -0,0 +1,171 @@
+//! everything bfint has to say about a run, as opposed to what the program itself writes. it all
+//! goes to stderr, so stdout only ever has the program's output, and it all goes through here,
+//! so how much of it there is and what it looks like is decided in one place:
+//!
+//! - errors, which are always shown
+//! - the log, see the log crate, with warnings shown unless there's a -q, info with -v, debug
+//!   with -vv and the trace of every step with -vvv
+//! - reports asked for with a flag, like --stats, which are shown at any verbosity since asking
+//!   for them is asking to see them
+//! - the progress line, which is written over as the run goes on
+//!
+//! with "--message-format json" every message is a line of json instead, like
+//! `{"kind":"warn","message":"..."}`, where the kind is the level for errors and the log, the
+//! name of the report, or "progress"
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+///what messages look like
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MessageFormat {
+    ///for people, the way it's always been
+    Text,
+    ///a line of json for every message, for tools
+    Json,
+}
+
+impl MessageFormat {
+    ///turns the name used on the command line into the matching format
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(MessageFormat::Text),
+            "json" => Some(MessageFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+//whether the progress line is showing, and needs a newline before anything else is written
+static PROGRESS_SHOWING: AtomicBool = AtomicBool::new(false);
+
+///the format for every message from now on
+pub fn set_format(format: MessageFormat) {
+    JSON.store(format == MessageFormat::Json, Ordering::Relaxed);
+}
+
+pub fn format() -> MessageFormat {
+    match JSON.load(Ordering::Relaxed) {
+        true => MessageFormat::Json,
+        false => MessageFormat::Text,
+    }
+}
+
+///sends the log to stderr in the format, showing messages up to the level. json is never
+///colored
+pub fn init(level: log::LevelFilter, format: MessageFormat) {
+    set_format(format);
+    if format == MessageFormat::Json {
+        crate::style::set_enabled(false);
+    }
+    if log::set_logger(&Logger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+///writes the message as one piece, so messages from different threads don't get mixed up. in
+///text the prefix goes in front of it
+fn write(kind: &str, prefix: &str, message: &str) {
+    write_with(kind, "", prefix, message);
+}
+
+///write, with more fields in json, which go in front of the message
+fn write_with(kind: &str, fields: &str, prefix: &str, message: &str) {
+    let mut stderr = std::io::stderr().lock();
+    let text = match format() {
+        MessageFormat::Json => format!(
+            "{{\"kind\":{},{}\"message\":{}}}\n",
+            json_string(kind),
+            fields,
+            json_string(message.trim_end())
+        ),
+        MessageFormat::Text => {
+            let newline = match PROGRESS_SHOWING.swap(false, Ordering::Relaxed) {
+                true => "\n",
+                false => "",
+            };
+            format!("{}{}{}\n", newline, prefix, message.trim_end_matches('\n'))
+        }
+    };
+    //there's nowhere left to say that stderr is gone
+    let _ = stderr.write_all(text.as_bytes());
+}
+
+///something that went wrong, always shown
+pub fn error(message: &str) {
+    write("error", "", message);
+}
+
+///a report asked for with a flag, like "stats" for --stats, shown whatever the verbosity
+pub fn report(kind: &str, message: &str) {
+    write(kind, "", message);
+}
+
+///a warning from --runtime-warnings, shown whatever the verbosity like a report. in json the
+///detector, step, line and column are fields of their own, with the kind "runtime-warning"
+pub fn runtime_warning(warning: &crate::warnings::RuntimeWarning) {
+    match format() {
+        MessageFormat::Json => {
+            let fields = format!(
+                "\"detector\":{},\"step\":{},\"line\":{},\"column\":{},",
+                json_string(warning.detector.name()),
+                warning.step,
+                warning.position.line,
+                warning.position.column
+            );
+            write_with("runtime-warning", &fields, "", &warning.message);
+        }
+        MessageFormat::Text => write("runtime-warning", "[warn] ", &warning.to_string()),
+    }
+}
+
+///shows how far the run has got. in text it's a line that's written over every time, until
+///progress_done or another message
+pub fn progress(message: &str) {
+    match format() {
+        MessageFormat::Json => write("progress", "", message),
+        MessageFormat::Text => {
+            let _ = write!(std::io::stderr().lock(), "\r{}", message);
+            PROGRESS_SHOWING.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+///ends the progress line, so what comes after it starts on a line of its own
+pub fn progress_done() {
+    if PROGRESS_SHOWING.swap(false, Ordering::Relaxed) {
+        let _ = writeln!(std::io::stderr().lock());
+    }
+}
+
+///writes the log through write, with the level in front of every message
+struct Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let level = record.level().as_str().to_lowercase();
+            write(&level, &format!("[{}] ", level), &record.args().to_string());
+        }
+    }
+    fn flush(&self) {}
+}
+
+///the text as a json string, quotes and all. everything that writes json by hand uses this
+pub fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,485 @@
+//! a json-rpc 2.0 service, for "bfint rpc", so editor tooling and scripts can use the
+//! interpreter without http. every line on the input is a request, or a batch of them in an
+//! array, and every answer is a line on the output. a request without an "id" is a
+//! notification and isn't answered.
+//!
+//! the methods are:
+//!
+//! - `parse`, with the `"program"`: the `"diagnostics"` for it, the error when it doesn't parse
+//!   and what the linter found when it does, each with a `"severity"`, a `"message"`, and the
+//!   `"line"` and `"column"` it's about
+//! - `run`, with the `"program"` and optionally the `"input"` and a `"config"` object of the
+//!   settings a config file has, like `{"max-steps": 100000, "eof": "0"}`: the
+//!   `"output"` as text and as `"bytes"`, and the `"stats"`
+//! - `format`, with the `"program"` and optionally the `"width"` and `"comments"`: the
+//!   `"formatted"` program
+//! - `analyze`, with the `"program"`: the metrics analyze --metrics --json gives
+//! - `session.create`, with what run takes: a machine to step through, and the `"session"` id
+//!   to use it by. there can be as many as needed at once
+//! - `session.step`, with the `"session"` and optionally a `"count"`, `session.continue`,
+//!   `session.next` and `session.finish`: run it like the debugger does, and say where it
+//!   stopped and why, see resume
+//! - `session.readCells`, with the `"session"` and optionally `"from"` and `"count"`: the
+//!   `"values"` of the cells
+//! - `session.setBreakpoint`, with the `"session"`, the `"loc"` like "3:14" and optionally an
+//!   `"if"` condition: the breakpoint's `"id"`
+//! - `session.close`, with the `"session"`: forgets it
+//!
+//! a program's language is taken from its contents unless the config has a "lang". the sessions
+//! are driven by the same Debugger the debug command, mi.rs and dap.rs are, so stepping and
+//! breakpoints work the same in all of them
+use crate::config::{ConfigError, InterpreterConfig};
+use crate::debugger::{self, Debugger, Stop, StopReason};
+use crate::diagnostics::Diagnostic;
+use crate::{annotations, format, lang, lint, ParseError, Position, TuringMachine};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+///the error codes json-rpc has for requests that are wrong
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+///the error codes for what went wrong with the program. they're -32000 minus the number
+///BfError in ffi.rs has for the same thing, and the ones it has nothing for come after
+pub const PROGRAM_PARSE_ERROR: i64 = -32003;
+pub const RUNTIME_ERROR: i64 = -32004;
+pub const UNKNOWN_SESSION: i64 = -32010;
+pub const FINISHED: i64 = -32011;
+pub const CONFIG_ERROR: i64 = -32012;
+pub const FORMAT_ERROR: i64 = -32013;
+
+///an error to answer a request with
+#[derive(PartialEq, Debug, Clone)]
+pub struct Failure {
+    pub code: i64,
+    pub message: String,
+    ///more about what went wrong, like the diagnostic for a program that didn't parse
+    pub data: Option<Value>,
+}
+
+impl Failure {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Failure {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+    fn params(message: impl Into<String>) -> Self {
+        Failure::new(INVALID_PARAMS, message)
+    }
+}
+
+impl From<ConfigError> for Failure {
+    fn from(e: ConfigError) -> Self {
+        match e {
+            ConfigError::ParseError(e) => Failure {
+                code: PROGRAM_PARSE_ERROR,
+                message: e.to_string(),
+                data: None,
+            },
+            e => Failure::new(CONFIG_ERROR, e.to_string()),
+        }
+    }
+}
+
+///the service, with the sessions that have been created and not closed
+#[derive(Default)]
+pub struct Service {
+    sessions: BTreeMap<u64, Debugger>,
+    //the id of the last session created
+    last_session: u64,
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Service::default()
+    }
+    ///answers the lines of input until it ends
+    pub fn serve(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(answer) = self.handle_line(&line) {
+                writeln!(out, "{}", answer)?;
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+    ///answers one line, a request or a batch. nothing when it was all notifications
+    pub fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error(Value::Null, Failure::new(PARSE_ERROR, e.to_string()))),
+        };
+        match message {
+            Value::Array(batch) if batch.is_empty() => Some(error(
+                Value::Null,
+                Failure::new(INVALID_REQUEST, "a batch can't be empty"),
+            )),
+            Value::Array(batch) => {
+                let answers: Vec<Value> = batch
+                    .iter()
+                    .filter_map(|request| self.handle(request))
+                    .collect();
+                (!answers.is_empty()).then_some(Value::Array(answers))
+            }
+            request => self.handle(&request),
+        }
+    }
+    ///answers one request, nothing for a notification
+    pub fn handle(&mut self, request: &Value) -> Option<Value> {
+        let id = match request.get("id") {
+            None => None,
+            Some(id @ (Value::Null | Value::Number(_) | Value::String(_))) => Some(id.clone()),
+            Some(_) => Some(Value::Null),
+        };
+        let method = match (&request["jsonrpc"], &request["method"]) {
+            (Value::String(version), Value::String(method)) if version == "2.0" => method,
+            _ => {
+                return Some(error(
+                    id.unwrap_or(Value::Null),
+                    Failure::new(INVALID_REQUEST, "that's not a json-rpc 2.0 request"),
+                ))
+            }
+        };
+        let params = match &request["params"] {
+            Value::Null => json!({}),
+            params @ Value::Object(_) => params.clone(),
+            _ => return id.map(|id| error(id, Failure::params("\"params\" has to be an object"))),
+        };
+        let result = self.call(method, &params);
+        //a notification is carried out all the same, it's only not answered
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(failure) => error(id, failure),
+        })
+    }
+    ///carries out the method
+    pub fn call(&mut self, method: &str, params: &Value) -> Result<Value, Failure> {
+        match method {
+            "parse" => parse(params),
+            "run" => run(params),
+            "format" => format_program(params),
+            "analyze" => analyze(params),
+            "session.create" => {
+                let (tm, input, source) = machine(params)?;
+                let mut debugger = Debugger::new(tm, input);
+                debugger.names = annotations::cell_names(&source)
+                    .map_err(|e| Failure::new(PROGRAM_PARSE_ERROR, e.to_string()))?;
+                self.last_session += 1;
+                self.sessions.insert(self.last_session, debugger);
+                Ok(json!({ "session": self.last_session }))
+            }
+            "session.step" => {
+                let count = match &params["count"] {
+                    Value::Null => 1,
+                    count => count
+                        .as_u64()
+                        .ok_or_else(|| Failure::params("\"count\" has to be a whole number"))?,
+                };
+                resume(self.session(params)?, |debugger| {
+                    debugger.resume(Some(count))
+                })
+            }
+            "session.continue" => resume(self.session(params)?, |debugger| debugger.resume(None)),
+            "session.next" => resume(self.session(params)?, Debugger::step_over),
+            "session.finish" => resume(self.session(params)?, Debugger::step_out),
+            "session.readCells" => {
+                let from = number(params, "from")?.unwrap_or(0) as usize;
+                let count = number(params, "count")?.unwrap_or(16) as usize;
+                let tm = &self.session(params)?.tm;
+                let tape = tm.tape();
+                let from = from.min(tape.len());
+                let end = from.saturating_add(count).min(tape.len());
+                let values: Vec<i16> = tape[from..end]
+                    .iter()
+                    .map(|cell| tm.cell_signedness.value(*cell))
+                    .collect();
+                Ok(json!({ "from": from, "values": values }))
+            }
+            "session.setBreakpoint" => {
+                let place = text(params, "loc")?.ok_or_else(|| wanted("loc"))?;
+                let condition = text(params, "if")?;
+                let bad = |e: debugger::DebugError| Failure::params(e.to_string());
+                let position = debugger::parse_place(place).map_err(bad)?;
+                let id = self
+                    .session(params)?
+                    .add_breakpoint(position, condition)
+                    .map_err(bad)?;
+                Ok(json!({ "id": id, "loc": position.to_string() }))
+            }
+            "session.close" => {
+                let id = session_id(params)?;
+                match self.sessions.remove(&id) {
+                    Some(_) => Ok(json!({})),
+                    None => Err(unknown_session(id)),
+                }
+            }
+            _ => Err(Failure::new(
+                METHOD_NOT_FOUND,
+                format!("there's no method called '{}'", method),
+            )),
+        }
+    }
+    ///the session the params say
+    fn session(&mut self, params: &Value) -> Result<&mut Debugger, Failure> {
+        let id = session_id(params)?;
+        self.sessions
+            .get_mut(&id)
+            .ok_or_else(|| unknown_session(id))
+    }
+}
+
+///an answer saying the request failed
+fn error(id: Value, failure: Failure) -> Value {
+    let mut error = json!({ "code": failure.code, "message": failure.message });
+    if let Some(data) = failure.data {
+        error["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+fn wanted(name: &str) -> Failure {
+    Failure::params(format!("\"{}\" is missing", name))
+}
+
+fn unknown_session(id: u64) -> Failure {
+    Failure::new(UNKNOWN_SESSION, format!("there's no session {}", id))
+}
+
+///a string parameter, nothing when it isn't there
+fn text<'a>(params: &'a Value, name: &str) -> Result<Option<&'a str>, Failure> {
+    match &params[name] {
+        Value::Null => Ok(None),
+        Value::String(text) => Ok(Some(text)),
+        _ => Err(Failure::params(format!("\"{}\" has to be a string", name))),
+    }
+}
+
+///a whole number parameter, nothing when it isn't there
+fn number(params: &Value, name: &str) -> Result<Option<u64>, Failure> {
+    match &params[name] {
+        Value::Null => Ok(None),
+        value => value
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| Failure::params(format!("\"{}\" has to be a whole number", name))),
+    }
+}
+
+fn session_id(params: &Value) -> Result<u64, Failure> {
+    number(params, "session")?.ok_or_else(|| wanted("session"))
+}
+
+fn program(params: &Value) -> Result<&str, Failure> {
+    text(params, "program")?.ok_or_else(|| wanted("program"))
+}
+
+///the config the "config" parameter has, with the language taken from the program when it
+///doesn't say
+fn config(params: &Value, source: &str) -> Result<InterpreterConfig, Failure> {
+    let mut config = InterpreterConfig::default();
+    match &params["config"] {
+        Value::Null => {}
+        Value::Object(settings) => {
+            for (name, value) in settings {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Number(_) | Value::Bool(_) => value.to_string(),
+                    _ => {
+                        return Err(Failure::params(format!(
+                            "the setting {} has to be a string, a number or true or false",
+                            name
+                        )))
+                    }
+                };
+                config.set(name, &value)?;
+            }
+        }
+        _ => return Err(Failure::params("\"config\" has to be an object")),
+    }
+    config.lang = Some(config.lang_for("", source));
+    Ok(config)
+}
+
+///a machine for the program the params have, set up the way they say, and the input it's given
+fn machine(params: &Value) -> Result<(TuringMachine, Vec<u8>, String), Failure> {
+    let source = program(params)?;
+    let config = config(params, source)?;
+    let tm = TuringMachine::with_config(source, &config).map_err(|e| match e {
+        ConfigError::ParseError(e) => parse_failure(&e, source),
+        e => e.into(),
+    })?;
+    let input = text(params, "input")?.unwrap_or("").as_bytes().to_vec();
+    Ok((tm, input, source.to_string()))
+}
+
+///the failure for a program that didn't parse, with its diagnostic
+fn parse_failure(e: &ParseError, source: &str) -> Failure {
+    Failure {
+        code: PROGRAM_PARSE_ERROR,
+        message: e.to_string(),
+        data: Some(diagnostic(&Diagnostic::from_parse_error(e, source))),
+    }
+}
+
+fn diagnostic(diagnostic: &Diagnostic) -> Value {
+    let hints: Vec<Value> = diagnostic
+        .hints
+        .iter()
+        .map(|hint| json!({ "message": hint.message, "line": hint.position.line, "column": hint.position.column }))
+        .collect();
+    let mut value = json!({
+        "severity": "error",
+        "message": diagnostic.message,
+        "width": diagnostic.width,
+        "hints": hints,
+    });
+    if let Some(position) = diagnostic.position {
+        value["line"] = json!(position.line);
+        value["column"] = json!(position.column);
+    }
+    value
+}
+
+fn parse(params: &Value) -> Result<Value, Failure> {
+    let source = program(params)?;
+    let lang = config(params, source)?
+        .lang
+        .unwrap_or(lang::Lang::Brainfuck);
+    let diagnostics: Vec<Value> =
+        match TuringMachine::new(source, &lang).and_then(|_| lang::tokenize(source, &lang)) {
+            Ok(tokens) => lint::lint(&tokens)
+                .iter()
+                .map(|finding| {
+                    let severity = match finding.severity {
+                        lint::Severity::Warning => "warning",
+                        lint::Severity::Info => "info",
+                    };
+                    json!({
+                        "severity": severity,
+                        "code": finding.code,
+                        "message": finding.message,
+                        "line": finding.position.line,
+                        "column": finding.position.column,
+                    })
+                })
+                .collect(),
+            Err(e) => vec![diagnostic(&Diagnostic::from_parse_error(&e, source))],
+        };
+    Ok(json!({ "diagnostics": diagnostics }))
+}
+
+fn run(params: &Value) -> Result<Value, Failure> {
+    let (mut tm, input, _) = machine(params)?;
+    tm.input = std::sync::Arc::new(std::sync::Mutex::new(io::Cursor::new(input)));
+    let output = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    tm.output = output.clone();
+    let result = tm.run();
+    let output = crate::shared::lock(&output);
+    let mut answer = json!({
+        "output": String::from_utf8_lossy(&output),
+        "bytes": *output,
+        "stats": {
+            "instructions": tm.program().len(),
+            "steps": tm.steps,
+            "pointer": tm.pointer(),
+        },
+    });
+    match result {
+        Ok(_) => Ok(answer),
+        //what it wrote and how far it got before failing go along with the error
+        Err(e) => {
+            answer["loc"] = location(tm.position().or(tm.last_position()));
+            Err(Failure {
+                code: RUNTIME_ERROR,
+                message: e.to_string(),
+                data: Some(answer),
+            })
+        }
+    }
+}
+
+fn format_program(params: &Value) -> Result<Value, Failure> {
+    let source = program(params)?;
+    let lang = config(params, source)?
+        .lang
+        .unwrap_or(lang::Lang::Brainfuck);
+    let width = number(params, "width")?.unwrap_or(80) as usize;
+    let comments = match text(params, "comments")? {
+        Some(name) => format::Comments::from_name(name).ok_or_else(|| {
+            Failure::params(format!("'{}' isn't a way to lay out comments", name))
+        })?,
+        None => format::Comments::Keep,
+    };
+    TuringMachine::new(source, &lang).map_err(|e| parse_failure(&e, source))?;
+    let formatted = format::format(source, &lang, width, comments)
+        .map_err(|e| Failure::new(FORMAT_ERROR, e.to_string()))?;
+    Ok(json!({ "formatted": formatted }))
+}
+
+fn analyze(params: &Value) -> Result<Value, Failure> {
+    let source = program(params)?;
+    let lang = config(params, source)?
+        .lang
+        .unwrap_or(lang::Lang::Brainfuck);
+    let tokens = TuringMachine::new(source, &lang)
+        .and_then(|_| lang::tokenize(source, &lang))
+        .map_err(|e| parse_failure(&e, source))?;
+    let metrics = crate::analyze::metrics::Metrics::new(source, &tokens);
+    serde_json::from_str(&metrics.json()).map_err(|e| Failure::new(FORMAT_ERROR, e.to_string()))
+}
+
+///a place as json, null for none
+fn location(position: Option<Position>) -> Value {
+    match position {
+        Some(position) => json!(position.to_string()),
+        None => Value::Null,
+    }
+}
+
+///runs the session and says how it stopped: the `"reason"` (`step`, `breakpoint`,
+///`output-matched`, `halted`, `error` or `interrupted`, the same as mi.rs), the `"output"` it
+///wrote, the `"loc"` of the next instruction, the `"pointer"` and the `"steps"` run. a
+///breakpoint has its `"breakpoint"` id, an output match the `"matchLoc"` of the "." that
+///finished it and an error its `"message"`
+fn resume(
+    debugger: &mut Debugger,
+    run: impl FnOnce(&mut Debugger) -> Stop,
+) -> Result<Value, Failure> {
+    let stop = run(debugger);
+    let reason = match &stop.reason {
+        StopReason::Finished => {
+            return Err(Failure::new(FINISHED, "the program has already finished"))
+        }
+        StopReason::Stepped => "step",
+        StopReason::Breakpoint(_) => "breakpoint",
+        StopReason::OutputMatched(_) => "output-matched",
+        StopReason::Halted => "halted",
+        StopReason::Error(_) => "error",
+        StopReason::Interrupted => "interrupted",
+    };
+    let tm = &debugger.tm;
+    let mut answer = json!({
+        "reason": reason,
+        "output": String::from_utf8_lossy(&stop.output),
+        "bytes": stop.output,
+        "loc": location(tm.position()),
+        "pointer": tm.pointer(),
+        "steps": tm.steps,
+    });
+    match stop.reason {
+        StopReason::Breakpoint(id) => answer["breakpoint"] = json!(id),
+        StopReason::OutputMatched(position) => answer["matchLoc"] = location(Some(position)),
+        StopReason::Error(e) => answer["message"] = json!(e.to_string()),
+        _ => {}
+    }
+    Ok(answer)
+}
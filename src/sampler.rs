@@ -0,0 +1,177 @@
+//! a sampling profiler, for --profile-sample: instead of counting every instruction, it looks
+//! at which one is running every so often and keeps only that. a run that would take hours
+//! with counting still says where its time goes, as the share of samples every place and loop
+//! got.
+//!
+//! a sample is taken when a timer thread says it's due, some number of times a second, or every
+//! so many steps, which picks the same instructions on every run. either way a sample is only
+//! the index of the instruction, 4 bytes, since the loops around it can be worked out from the
+//! program afterwards. the samples can be turned into a Profile, which everything that reads
+//! one takes, like listing::folded for flame graphs
+use crate::control::RunOutcome;
+use crate::listing::Profile;
+use crate::observe::{Observer, StepCtx};
+use crate::program::Program;
+use crate::{Instruction, RuntimeError};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+///how many places and loops the report lists
+pub const REPORT_LENGTH: usize = 10;
+
+///when samples are taken
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Clock {
+    ///this many times a second
+    Hertz(u32),
+    ///every this many steps
+    Steps(u64),
+}
+
+///the samples of a run, as it goes
+pub struct Sampler {
+    clock: Clock,
+    //set by the timer thread when the next sample is due, and cleared when it's taken
+    due: Arc<AtomicBool>,
+    //set when the run is over, so the timer thread stops
+    stopped: Arc<AtomicBool>,
+    timer: Option<JoinHandle<()>>,
+    //the index of the instruction every sample found running
+    samples: Vec<u32>,
+}
+
+impl Sampler {
+    ///a sampler that starts taking samples right away, with its own timer thread for Hertz
+    pub fn new(clock: Clock) -> Self {
+        let due = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let timer = match clock {
+            Clock::Hertz(hertz) => {
+                let (due, stopped) = (Arc::clone(&due), Arc::clone(&stopped));
+                let interval = Duration::from_secs(1) / hertz.max(1);
+                Some(std::thread::spawn(move || {
+                    while !stopped.load(Ordering::Relaxed) {
+                        //stop wakes it up, so the run doesn't wait out a whole interval
+                        std::thread::park_timeout(interval);
+                        due.store(true, Ordering::Relaxed);
+                    }
+                }))
+            }
+            Clock::Steps(_) => None,
+        };
+        Sampler {
+            clock,
+            due,
+            stopped,
+            timer,
+            samples: vec![],
+        }
+    }
+    ///stops the timer thread, once there are no more samples to take
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(timer) = self.timer.take() {
+            timer.thread().unpark();
+            let _ = timer.join();
+        }
+    }
+    ///the samples as a profile of the program, every instruction's count being how many
+    ///samples found it running
+    pub fn profile(&self, program: &Program) -> Profile {
+        let mut profile = Profile::new(program);
+        for &pc in &self.samples {
+            profile.counts[pc as usize] += 1;
+        }
+        profile
+    }
+    ///the places in the source and the loops that got the most samples, with their share of
+    ///them. a loop's samples are the ones in it and in every loop inside it
+    pub fn report(&self, program: &Program) -> String {
+        let total = self.samples.len();
+        let mut report = match self.clock {
+            Clock::Hertz(hertz) => format!("{} samples at {} Hz\n", total, hertz),
+            Clock::Steps(steps) => format!("{} samples, one every {} steps\n", total, steps),
+        };
+        if total == 0 {
+            return report;
+        }
+        let counts = self.profile(program).counts;
+        let mut places: BTreeMap<String, usize> = BTreeMap::new();
+        let mut loops: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut open = vec![];
+        for (index, &count) in counts.iter().enumerate() {
+            if let Some((_, position)) = program.instruction_at(index) {
+                *places.entry(position.to_string()).or_insert(0) += count as usize;
+            }
+            //the loops an instruction is in are the ones its brackets are between, the same as
+            //in listing::folded
+            for open in &open {
+                *loops.entry(*open).or_insert(0) += count as usize;
+            }
+            match program.instruction(index) {
+                Some(Instruction::JumpToClose) => open.push(index),
+                Some(Instruction::JumpToOpen) => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        let loops = loops.into_iter().map(|(open, samples)| {
+            let position = program.instruction_at(open).map(|(_, position)| position);
+            let position = position.map_or(String::new(), |position| position.to_string());
+            (format!("loop@{}", position), samples)
+        });
+        let sections = [
+            ("place", places.into_iter().collect::<Vec<_>>()),
+            ("loop", loops.collect()),
+        ];
+        for (heading, mut rows) in sections {
+            rows.retain(|(_, samples)| *samples > 0);
+            rows.sort_by_key(|(_, samples)| core::cmp::Reverse(*samples));
+            rows.truncate(REPORT_LENGTH);
+            if rows.is_empty() {
+                continue;
+            }
+            let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            let width = width.max(heading.len());
+            let _ = writeln!(
+                report,
+                "{:width$}  {:>7}  {:>6}",
+                heading, "samples", "share"
+            );
+            for (name, samples) in rows {
+                let share = samples as f64 * 100.0 / total as f64;
+                let _ = writeln!(report, "{:width$}  {:>7}  {:5.1}%", name, samples, share);
+            }
+        }
+        report
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Observer for Sampler {
+    fn after_step(&mut self, ctx: &StepCtx) {
+        let due = match self.clock {
+            //loading first keeps the steps between samples from writing to the flag
+            Clock::Hertz(_) => {
+                self.due.load(Ordering::Relaxed) && self.due.swap(false, Ordering::Relaxed)
+            }
+            Clock::Steps(every) => ctx.steps().is_multiple_of(every.max(1)),
+        };
+        if due {
+            self.samples.push(ctx.pc() as u32);
+        }
+    }
+    fn on_halt(&mut self, _outcome: Result<RunOutcome, &RuntimeError>) {
+        self.stop();
+    }
+}
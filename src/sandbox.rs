@@ -0,0 +1,308 @@
+//! locking bfint down before it runs a program nobody has checked, for "--sandbox" on linux.
+//! the step, memory and output limits keep a program in check, this is for when the interpreter
+//! itself has a bug a program can get at. once everything the run needs is open:
+//!
+//! - the process gets limits on its cpu time, its address space and the size of files it writes
+//! - a seccomp filter only lets through the system calls running a program needs: reading and
+//!   writing what's open already, closing it, getting and giving back memory, the clock and
+//!   sleeping, waiting on locks, and exiting. the others std needs to abort, to make hash maps
+//!   and to start a thread are let through too, since they can't open or reach anything
+//!
+//! nothing can be opened or started after that, so whatever would need it has to be refused
+//! before, see main.rs. a system call the filter stops, or running out of cpu time, is said on
+//! stderr before bfint exits, instead of it just being killed
+use std::fmt;
+use std::time::Duration;
+
+///how far the sandboxed process can go
+#[derive(PartialEq, Debug, Clone)]
+pub struct Limits {
+    ///the cpu time it can use, all threads together
+    pub cpu: Duration,
+    ///the memory it can have mapped, in bytes
+    pub address_space: u64,
+    ///how big a file it writes can get, in bytes, which is stdout too when it's a file
+    pub file_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            cpu: Duration::from_secs(30),
+            address_space: 1024 * 1024 * 1024,
+            file_size: 64 * 1024 * 1024,
+        }
+    }
+}
+
+///everything that can keep the sandbox from being set up
+#[derive(Debug)]
+pub enum SandboxError {
+    ///the filter only knows the system calls of x86_64 and aarch64
+    UnsupportedArch,
+    ///one of the limits couldn't be set, with the name of the limit
+    Limit(&'static str, std::io::Error),
+    ///the handler that says what happened couldn't be put in place
+    Signal(std::io::Error),
+    ///the seccomp filter couldn't be put in place
+    Filter(std::io::Error),
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SandboxError::UnsupportedArch => {
+                write!(f, "the sandbox only works on x86_64 and aarch64 for now")
+            }
+            SandboxError::Limit(name, e) => write!(f, "could not limit the {}: {}", name, e),
+            SandboxError::Signal(e) => write!(f, "could not catch what the sandbox stops: {}", e),
+            SandboxError::Filter(e) => write!(f, "could not set up the seccomp filter: {}", e),
+        }
+    }
+}
+
+//what seccomp calls the architectures, from linux's audit.h, which libc doesn't have
+#[cfg(target_arch = "x86_64")]
+const ARCH: Option<u32> = Some(0xC000003E);
+#[cfg(target_arch = "aarch64")]
+const ARCH: Option<u32> = Some(0xC00000B7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const ARCH: Option<u32> = None;
+
+///the system calls the filter lets through, everything else is stopped
+const ALLOWED: &[libc::c_long] = &[
+    libc::SYS_read,
+    libc::SYS_readv,
+    libc::SYS_write,
+    libc::SYS_writev,
+    libc::SYS_close,
+    libc::SYS_brk,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_madvise,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_futex,
+    libc::SYS_sched_yield,
+    libc::SYS_getrusage,
+    libc::SYS_getrandom,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_sigaltstack,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_tgkill,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    //what a thread std starts does before it gets to its own code, for the one waiting for
+    //ctrl+c, which can still be starting when the filter goes in
+    libc::SYS_mprotect,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+];
+
+///the system calls the filter only lets through with one of their arguments, which is the
+///argument's number and the value it has to have
+const ALLOWED_WITH: &[(libc::c_long, u32, libc::c_int)] = &[
+    //naming the thread, as it starts
+    (libc::SYS_prctl, 0, libc::PR_SET_NAME),
+    //a debug build checks a file is still open before closing it
+    (libc::SYS_fcntl, 1, libc::F_GETFD),
+];
+
+//the cpu time limit, for the message when it runs out
+static CPU_SECONDS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+///puts the limits and the filter in place for every thread of the process. there's no way back
+pub fn enter(limits: &Limits) -> Result<(), SandboxError> {
+    let arch = ARCH.ok_or(SandboxError::UnsupportedArch)?;
+    //running out of cpu time sends SIGXCPU at the soft limit, and only kills at the hard one
+    let cpu = limits.cpu.as_secs().max(1);
+    CPU_SECONDS.store(cpu, std::sync::atomic::Ordering::Relaxed);
+    let out_of_cpu: extern "C" fn(libc::c_int) = out_of_cpu;
+    catch(libc::SIGXCPU, out_of_cpu as libc::sighandler_t, 0)?;
+    let stopped: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) = stopped;
+    catch(
+        libc::SIGSYS,
+        stopped as libc::sighandler_t,
+        libc::SA_SIGINFO,
+    )?;
+    //a file that got too big fails the write that did it, which the run says like any other
+    //error writing its output
+    catch(libc::SIGXFSZ, libc::SIG_IGN, 0)?;
+    limit(libc::RLIMIT_CPU, "cpu time", cpu, cpu + 1)?;
+    let (memory, file_size) = (limits.address_space, limits.file_size);
+    limit(libc::RLIMIT_AS, "address space", memory, memory)?;
+    limit(libc::RLIMIT_FSIZE, "file size", file_size, file_size)?;
+    filter(arch)
+}
+
+fn limit(
+    resource: libc::__rlimit_resource_t,
+    name: &'static str,
+    soft: u64,
+    hard: u64,
+) -> Result<(), SandboxError> {
+    let limit = libc::rlimit {
+        rlim_cur: soft,
+        rlim_max: hard,
+    };
+    //SAFETY: the rlimit is plain data that outlives the call
+    match unsafe { libc::setrlimit(resource, &limit) } {
+        0 => Ok(()),
+        _ => Err(SandboxError::Limit(name, std::io::Error::last_os_error())),
+    }
+}
+
+fn catch(
+    signal: libc::c_int,
+    handler: libc::sighandler_t,
+    flags: libc::c_int,
+) -> Result<(), SandboxError> {
+    //SAFETY: sigaction is plain data, and the handlers only call what's safe in one
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler;
+        action.sa_flags = flags;
+        libc::sigemptyset(&mut action.sa_mask);
+        match libc::sigaction(signal, &action, std::ptr::null_mut()) {
+            0 => Ok(()),
+            _ => Err(SandboxError::Signal(std::io::Error::last_os_error())),
+        }
+    }
+}
+
+///builds the filter and puts it in place. it checks the architecture first, since the same
+///number is a different system call on another one, then goes down the allowed ones
+fn filter(arch: u32) -> Result<(), SandboxError> {
+    let statement = |code: u32, k: u32| libc::sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    //where the number, the architecture and the low half of the arguments are in seccomp_data
+    let (number, architecture) = (0, 4);
+    let argument = |index: u32| 16 + 8 * index;
+    let load = libc::BPF_LD | libc::BPF_W | libc::BPF_ABS;
+    let equal = libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K;
+    let ret = libc::BPF_RET | libc::BPF_K;
+    let mut program = vec![
+        statement(load, architecture),
+        libc::sock_filter {
+            jt: 1,
+            ..statement(equal, arch)
+        },
+        statement(ret, libc::SECCOMP_RET_KILL_PROCESS),
+        statement(load, number),
+    ];
+    //a match jumps over the rest of the list, the checked ones and the trap, to the allow at
+    //the very end
+    let checked = 3 * ALLOWED_WITH.len();
+    for (index, call) in ALLOWED.iter().enumerate() {
+        program.push(libc::sock_filter {
+            jt: (ALLOWED.len() + checked - index) as u8,
+            ..statement(equal, *call as u32)
+        });
+    }
+    //a checked one that matches has its argument looked at, which goes to the allow or the
+    //trap. one that doesn't goes on to the next
+    for (index, (call, which, value)) in ALLOWED_WITH.iter().enumerate() {
+        let after = checked - 3 * index - 3;
+        program.push(libc::sock_filter {
+            jf: 2,
+            ..statement(equal, *call as u32)
+        });
+        program.push(statement(load, argument(*which)));
+        program.push(libc::sock_filter {
+            jt: (after + 1) as u8,
+            jf: after as u8,
+            ..statement(equal, *value as u32)
+        });
+    }
+    program.push(statement(ret, libc::SECCOMP_RET_TRAP));
+    program.push(statement(ret, libc::SECCOMP_RET_ALLOW));
+    let program = libc::sock_fprog {
+        len: program.len() as u16,
+        filter: program.as_mut_ptr(),
+    };
+    //SAFETY: the filter is only read during the calls, and outlives them
+    unsafe {
+        //without this only root could set a filter
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(SandboxError::Filter(std::io::Error::last_os_error()));
+        }
+        //every thread gets it, like the one waiting for ctrl+c
+        let result = libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_TSYNC,
+            &program,
+        );
+        match result {
+            0 => Ok(()),
+            -1 => Err(SandboxError::Filter(std::io::Error::last_os_error())),
+            thread => Err(SandboxError::Filter(std::io::Error::other(format!(
+                "thread {} couldn't be given the filter",
+                thread
+            )))),
+        }
+    }
+}
+
+//what the kernel puts in the siginfo of a SIGSYS, which libc has no way to get at
+#[repr(C)]
+struct SysInfo {
+    signo: libc::c_int,
+    errno: libc::c_int,
+    code: libc::c_int,
+    call_address: *mut libc::c_void,
+    syscall: libc::c_int,
+    arch: libc::c_uint,
+}
+
+//a signal handler can't allocate, so the messages are put together on the stack and written
+//straight to stderr
+extern "C" fn stopped(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    //SAFETY: the kernel hands a SIGSYS handler a siginfo laid out like SysInfo
+    let syscall = unsafe { (*(info as *const SysInfo)).syscall };
+    say(
+        b"bfint: the sandbox stopped system call ",
+        syscall as u64,
+        b", which running the program shouldn't need\n",
+    );
+}
+
+extern "C" fn out_of_cpu(_: libc::c_int) {
+    let cpu = CPU_SECONDS.load(std::sync::atomic::Ordering::Relaxed);
+    say(
+        b"bfint: the sandbox's limit of ",
+        cpu,
+        b"s of cpu time ran out\n",
+    );
+}
+
+///writes the message with the number in the middle to stderr, and exits
+fn say(before: &[u8], number: u64, after: &[u8]) {
+    let mut digits = [0; 20];
+    let mut start = digits.len();
+    let mut left = number;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (left % 10) as u8;
+        left /= 10;
+        if left == 0 {
+            break;
+        }
+    }
+    //SAFETY: write and _exit are safe in a signal handler, and the buffers outlive the calls
+    unsafe {
+        for part in [before, &digits[start..], after] {
+            libc::write(libc::STDERR_FILENO, part.as_ptr().cast(), part.len());
+        }
+        libc::_exit(1);
+    }
+}
@@ -0,0 +1,407 @@
+//! a playground to run programs in from a web page, for "bfint serve". it speaks just enough
+//! http/1.1 over a tcp listener for three things: GET / gives back a page to type programs into,
+//! POST /run runs one, and GET /capabilities gives back the json of what this build can do, see
+//! capabilities.rs. the body of a run is json like
+//! `{"program": "+[.+]", "input": "", "max_steps": 1000, "cell_width": "8"}`, and the answer is
+//! `{"output_base64": "...", "steps": 1000, "status": "step_limit", "error": "..."}`, where the
+//! status is halted, parse_error, step_limit, output_limit, timeout or error.
+//!
+//! it's meant to be safe to leave running, so everything a request can ask for has a limit: the
+//! size of the request and its headers, how long the client takes to send it, how many steps and
+//! how long the program runs, and how much it writes. the tape is always the same 30000 cells.
+//! programs are compiled once and kept by the hash of their source, every run gets a machine of its
+//! own made from the compiled program, and every connection is handled on a thread of its own, up
+//! to a limit on how many at once
+use crate::capabilities::Capabilities;
+use crate::config::InterpreterConfig;
+use crate::control::RunOutcome;
+use crate::fuel::Fuel;
+use crate::lang::Lang;
+use crate::optimize::Level;
+use crate::program::{fnv1a, Program};
+use crate::shared::lock;
+use crate::{RuntimeError, TuringMachine};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///the page GET / gives back
+const PAGE: &str = include_str!("server.html");
+
+///how many compiled programs are kept before the cache starts over
+const MAX_CACHED: usize = 256;
+
+///how long the client gets to take the answer
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+///how often serve looks at whether it's been told to stop, while nothing is connecting
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+///the most any request gets
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Limits {
+    ///steps a run takes, a request can only ask for fewer
+    pub max_steps: u64,
+    ///how long a run can take
+    pub max_time: Duration,
+    ///bytes a run writes
+    pub max_output: u64,
+    ///bytes of the body of a request, which the program and its input are in
+    pub max_request: usize,
+    ///headers a request has, any more get a 431
+    pub max_headers: usize,
+    ///bytes of the request line and the headers together, any more get a 431
+    pub max_header_bytes: usize,
+    ///how long the client gets to send the whole request. every read has to be done by then,
+    ///so a client can't keep a connection open by sending a byte at a time
+    pub max_request_time: Duration,
+    ///connections handled at the same time, any more get a 503
+    pub max_connections: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_steps: 10_000_000,
+            max_time: Duration::from_secs(5),
+            max_output: 1024 * 1024,
+            max_request: 256 * 1024,
+            max_headers: 64,
+            max_header_bytes: 16 * 1024,
+            max_request_time: Duration::from_secs(10),
+            max_connections: 16,
+        }
+    }
+}
+
+///programs compiled already, by the hash of their source
+#[derive(Default)]
+pub struct Cache {
+    programs: Mutex<HashMap<u64, Arc<Program>>>,
+}
+
+impl Cache {
+    ///the program compiled from the source, compiling it if it hasn't been yet
+    fn get(&self, source: &str) -> Result<Arc<Program>, crate::ParseError> {
+        let hash = fnv1a(source);
+        let mut programs = self.programs.lock().unwrap_or_else(|e| e.into_inner());
+        //two sources can have the same hash, so the source itself has to match too
+        if let Some(program) = programs.get(&hash).filter(|p| &*p.source == source) {
+            return Ok(Arc::clone(program));
+        }
+        let program = Arc::new(Program::compile(source, &Lang::Brainfuck, Level::O2)?);
+        if programs.len() >= MAX_CACHED {
+            programs.clear();
+        }
+        programs.insert(hash, Arc::clone(&program));
+        Ok(program)
+    }
+}
+
+///runs the program in the body of a POST /run, and gives back the json to answer with. an
+///Err is a request that doesn't make sense, which gets a 400
+pub fn run_request(body: &[u8], cache: &Cache, limits: &Limits) -> Result<Value, String> {
+    let request: Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    let program = request["program"]
+        .as_str()
+        .ok_or("the request has no \"program\"")?;
+    let input = match &request["input"] {
+        Value::Null => "",
+        Value::String(input) => input.as_str(),
+        _ => return Err(String::from("\"input\" has to be a string")),
+    };
+    let max_steps = match &request["max_steps"] {
+        Value::Null => limits.max_steps,
+        steps => steps
+            .as_u64()
+            .ok_or("\"max_steps\" has to be a number")?
+            .min(limits.max_steps),
+    };
+    let mut config = InterpreterConfig::default();
+    match &request["cell_width"] {
+        Value::Null => {}
+        Value::String(width) => config.set("cell-width", width).map_err(|e| e.to_string())?,
+        Value::Number(width) => config
+            .set("cell-width", &width.to_string())
+            .map_err(|e| e.to_string())?,
+        _ => return Err(String::from("\"cell_width\" has to be 8 or \"big\"")),
+    }
+    config.output_limit = Some(limits.max_output);
+    let program = match cache.get(program) {
+        Ok(program) => program,
+        Err(e) => return Ok(answer(&[], 0, "parse_error", Some(e.to_string()))),
+    };
+    let mut tm = TuringMachine::with_program(program);
+    if let Err(e) = config.apply(&mut tm) {
+        return Ok(answer(&[], 0, "error", Some(e.to_string())));
+    }
+    tm.input = Arc::new(Mutex::new(io::Cursor::new(input.as_bytes().to_vec())));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    //the run is cancelled once it's taken too long, unless it's done before that
+    let handle = tm.control_handle();
+    let (done, finished) = mpsc::channel::<()>();
+    let max_time = limits.max_time;
+    let timer = std::thread::spawn(move || {
+        if finished.recv_timeout(max_time) == Err(mpsc::RecvTimeoutError::Timeout) {
+            handle.cancel();
+        }
+    });
+    let result = tm.run_with_fuel(&Fuel::new(max_steps));
+    drop(done);
+    let _ = timer.join();
+    let output = lock(&output);
+    let (status, error) = match result {
+        Ok(RunOutcome::Halted) => ("halted", None),
+        Ok(RunOutcome::Cancelled) => ("timeout", Some(format!("ran for more than {:?}", max_time))),
+        Ok(RunOutcome::FuelExhausted { .. }) => (
+            "step_limit",
+            Some(format!("stopped after running {} steps", max_steps)),
+        ),
+        Ok(RunOutcome::OutputClosed) => ("error", Some(crate::io::OUTPUT_CLOSED.to_string())),
+        Err(e @ RuntimeError::OutputLimitExceeded(..)) => ("output_limit", Some(e.to_string())),
+        Err(e) => ("error", Some(e.to_string())),
+    };
+    Ok(answer(&output, tm.steps, status, error))
+}
+
+///the json a run is answered with
+fn answer(output: &[u8], steps: u64, status: &str, error: Option<String>) -> Value {
+    json!({
+        "output_base64": base64(output),
+        "steps": steps,
+        "status": status,
+        "error": error,
+    })
+}
+
+///the bytes in base64, with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| {
+            bits | (*byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => encoded.push(ALPHABET[(bits >> (18 - 6 * index) & 63) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+///the playground, listening for connections
+pub struct Server {
+    listener: TcpListener,
+    limits: Limits,
+    cache: Arc<Cache>,
+}
+
+impl Server {
+    ///starts listening on the address. port 0 picks a free port, see local_addr
+    pub fn bind(address: impl std::net::ToSocketAddrs, limits: Limits) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        //accepting doesn't wait, so serve can notice when it's told to stop
+        listener.set_nonblocking(true)?;
+        Ok(Server {
+            listener,
+            limits,
+            cache: Arc::new(Cache::default()),
+        })
+    }
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+    ///answers requests until stop is set, then waits for the ones being answered to finish
+    pub fn serve(&self, stop: &AtomicBool) -> io::Result<()> {
+        let busy = Arc::new(AtomicUsize::new(0));
+        let mut handlers = vec![];
+        while !stop.load(Ordering::Relaxed) {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("could not accept a connection: {}", e);
+                    continue;
+                }
+            };
+            handlers.retain(|handler: &std::thread::JoinHandle<()>| !handler.is_finished());
+            if busy.fetch_add(1, Ordering::AcqRel) >= self.limits.max_connections {
+                busy.fetch_sub(1, Ordering::AcqRel);
+                let _ = stream.set_nonblocking(false);
+                let _ = respond(&stream, 503, "text/plain", b"too many requests at once");
+                continue;
+            }
+            let (busy, cache, limits) = (Arc::clone(&busy), Arc::clone(&self.cache), self.limits);
+            handlers.push(std::thread::spawn(move || {
+                if let Err(e) = handle(stream, &cache, &limits) {
+                    log::debug!("a connection failed: {}", e);
+                }
+                busy.fetch_sub(1, Ordering::AcqRel);
+            }));
+        }
+        for handler in handlers {
+            let _ = handler.join();
+        }
+        Ok(())
+    }
+}
+
+///reads from the connection until the deadline, and not after it
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the request took too long to send",
+            ));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+///reads one request from the connection and answers it
+fn handle(stream: TcpStream, cache: &Cache, limits: &Limits) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(Deadline {
+        stream: &stream,
+        until: Instant::now() + limits.max_request_time,
+    });
+    //what's left of max_header_bytes for the rest of the lines
+    let mut left = limits.max_header_bytes;
+    let request_line = match read_line(&mut reader, &mut left)? {
+        Line::Read(line) => line,
+        Line::TooLong => return respond(&stream, 431, "text/plain", b"the headers are too large"),
+        Line::NotHttp => return respond(&stream, 400, "text/plain", b"that's not http"),
+    };
+    let mut parts = request_line.split(' ');
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let mut length = 0;
+    let mut headers = 0;
+    loop {
+        let line = match read_line(&mut reader, &mut left)? {
+            Line::Read(line) => line,
+            Line::TooLong => {
+                return respond(&stream, 431, "text/plain", b"the headers are too large")
+            }
+            Line::NotHttp => return respond(&stream, 400, "text/plain", b"that's not http"),
+        };
+        if line.is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > limits.max_headers {
+            return respond(&stream, 431, "text/plain", b"there are too many headers");
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(usize::MAX);
+            }
+        }
+    }
+    match (method, path) {
+        ("GET", "/") => respond(&stream, 200, "text/html; charset=utf-8", PAGE.as_bytes()),
+        ("GET", "/capabilities") => respond(
+            &stream,
+            200,
+            "application/json",
+            Capabilities::new().to_json().as_bytes(),
+        ),
+        ("POST", "/run") if length > limits.max_request => {
+            respond(&stream, 413, "text/plain", b"the request is too large")
+        }
+        ("POST", "/run") => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            match run_request(&body, cache, limits) {
+                Ok(answer) => respond(
+                    &stream,
+                    200,
+                    "application/json",
+                    answer.to_string().as_bytes(),
+                ),
+                Err(e) => {
+                    let answer = json!({ "status": "bad_request", "error": e });
+                    respond(
+                        &stream,
+                        400,
+                        "application/json",
+                        answer.to_string().as_bytes(),
+                    )
+                }
+            }
+        }
+        (_, "/" | "/run" | "/capabilities") => {
+            respond(&stream, 405, "text/plain", b"method not allowed")
+        }
+        _ => respond(&stream, 404, "text/plain", b"not found"),
+    }
+}
+
+///what reading a line of the request line or the headers got
+enum Line {
+    ///the line, without its "\r\n"
+    Read(String),
+    ///it went past what was left of the bytes the headers can have
+    TooLong,
+    ///the connection ended before the line did, or it isn't utf-8
+    NotHttp,
+}
+
+///reads a line of the request line or the headers, taking its length off left
+fn read_line(reader: &mut impl BufRead, left: &mut usize) -> io::Result<Line> {
+    let mut line = vec![];
+    reader.take(*left as u64).read_until(b'\n', &mut line)?;
+    *left -= line.len();
+    if line.last() != Some(&b'\n') {
+        return Ok(match *left {
+            0 => Line::TooLong,
+            _ => Line::NotHttp,
+        });
+    }
+    Ok(match String::from_utf8(line) {
+        Ok(line) => Line::Read(line.trim_end_matches(['\r', '\n']).to_string()),
+        Err(_) => Line::NotHttp,
+    })
+}
+
+///writes the answer and closes the connection
+fn respond(mut stream: &TcpStream, status: u16, content_type: &str, body: &[u8]) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Service Unavailable",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
@@ -0,0 +1,37 @@
+//! what a machine shares with whoever made it and with the machines it forks into: its input and
+//! output, its observer and its callbacks. with std they're behind a mutex, so the machine can be
+//! sent to another thread to run and handed back afterwards. without std there are no threads,
+//! and no mutex either, so they're only behind a RefCell
+#[cfg(not(feature = "std"))]
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+use core::ops::DerefMut;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+///something shared, see the top of this file. `Arc::new(Mutex::new(value))` makes one with std,
+///or `shared(value)` with or without it
+#[cfg(feature = "std")]
+pub type Shared<T> = Arc<Mutex<T>>;
+#[cfg(not(feature = "std"))]
+pub type Shared<T> = Rc<RefCell<T>>;
+
+///shares the value
+pub fn shared<T>(value: T) -> Shared<T> {
+    #[cfg(feature = "std")]
+    return Arc::new(Mutex::new(value));
+    #[cfg(not(feature = "std"))]
+    return Rc::new(RefCell::new(value));
+}
+
+///the shared value, for as long as what's given back is kept. a thread that panicked while it
+///had it doesn't stop anyone else having it
+pub fn lock<T: ?Sized>(shared: &Shared<T>) -> impl DerefMut<Target = T> + '_ {
+    #[cfg(feature = "std")]
+    return shared.lock().unwrap_or_else(|e| e.into_inner());
+    #[cfg(not(feature = "std"))]
+    return shared.borrow_mut();
+}
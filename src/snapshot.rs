@@ -0,0 +1,225 @@
+//! copies of the tape taken at some point of a run, to see later which cells changed since. only
+//! the part of the tape from the first cell to the last one that isn't 0 (or the pointer, if
+//! that's further) is copied, everything past it is known to be 0 anyway.
+//!
+//! how two tapes differ is a TapeDiff, the runs of cells next to each other that hold something
+//! else, each with what they held before and after. it's what the debugger's diff shows, what
+//! "debug --lockstep" finds between the two machines and what crosscheck finds between two
+//! configs. a tape is all of its cells from the first one, and a cell past the end of a shorter
+//! tape holds 0, so tapes that were used up to different places can still be compared
+use crate::TuringMachine;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+///the tape as it was when the snapshot was taken
+#[derive(PartialEq, Debug, Clone)]
+pub struct Snapshot {
+    cells: Vec<u8>,
+}
+
+///a cell that holds something else now than it did in the snapshot
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Change {
+    pub index: usize,
+    pub old: u8,
+    pub new: u8,
+}
+
+impl Snapshot {
+    pub fn new(tm: &TuringMachine) -> Self {
+        let tape = tm.tape();
+        let last_used = tape.iter().rposition(|cell| *cell != 0).unwrap_or(0);
+        Snapshot {
+            cells: tape[..=last_used.max(tm.pointer())].to_vec(),
+        }
+    }
+    ///how the machine's tape is different now
+    pub fn diff(&self, tm: &TuringMachine) -> TapeDiff {
+        TapeDiff::compute(&self.cells, tm.tape())
+    }
+}
+
+///a run of cells next to each other that are different between two tapes
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffRun {
+    ///the index of the first cell of the run
+    pub start: usize,
+    ///what the cells held on the first tape and on the second, as many of them in both
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+///how one tape is different from another, as the runs of cells that are, from the left
+#[derive(PartialEq, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TapeDiff {
+    pub runs: Vec<DiffRun>,
+}
+
+///why a diff can't be applied to a tape
+#[derive(PartialEq, Debug, Clone)]
+pub enum DiffError {
+    ///the diff changes the cell at the index, which is past the end of the tape
+    OffTape(usize),
+    ///the cell at the index holds something else than the diff was made from, the first number
+    ///is what the diff expects and the second what it holds
+    Mismatch(usize, u8, u8),
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffError::OffTape(index) => {
+                write!(
+                    f,
+                    "the diff changes cell {}, past the end of the tape",
+                    index
+                )
+            }
+            DiffError::Mismatch(index, expected, found) => write!(
+                f,
+                "cell {} holds {}, the diff was made from a tape where it holds {}",
+                index, found, expected
+            ),
+        }
+    }
+}
+
+impl TapeDiff {
+    ///how the new tape is different from the old one
+    pub fn compute(old: &[u8], new: &[u8]) -> Self {
+        let mut runs: Vec<DiffRun> = Vec::new();
+        for index in 0..old.len().max(new.len()) {
+            let before = old.get(index).copied().unwrap_or(0);
+            let after = new.get(index).copied().unwrap_or(0);
+            if before == after {
+                continue;
+            }
+            match runs.last_mut() {
+                Some(run) if run.start + run.old.len() == index => {
+                    run.old.push(before);
+                    run.new.push(after);
+                }
+                _ => runs.push(DiffRun {
+                    start: index,
+                    old: alloc::vec![before],
+                    new: alloc::vec![after],
+                }),
+            }
+        }
+        TapeDiff { runs }
+    }
+    ///makes the tape the diff was made from into the one it was made to. it checks every cell
+    ///first, and leaves the tape as it was when any of them isn't what the diff expects
+    pub fn apply(&self, tape: &mut [u8]) -> Result<(), DiffError> {
+        for run in &self.runs {
+            for (offset, expected) in run.old.iter().enumerate() {
+                let index = run.start + offset;
+                match tape.get(index) {
+                    None => return Err(DiffError::OffTape(index)),
+                    Some(found) if found != expected => {
+                        return Err(DiffError::Mismatch(index, *expected, *found))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        for run in &self.runs {
+            tape[run.start..run.start + run.new.len()].copy_from_slice(&run.new);
+        }
+        Ok(())
+    }
+    ///the diff that goes back from the new tape to the old one
+    pub fn invert(&self) -> Self {
+        TapeDiff {
+            runs: self
+                .runs
+                .iter()
+                .map(|run| DiffRun {
+                    start: run.start,
+                    old: run.new.clone(),
+                    new: run.old.clone(),
+                })
+                .collect(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+    ///every cell that's different, from the left
+    pub fn changes(&self) -> impl Iterator<Item = Change> + '_ {
+        self.runs.iter().flat_map(|run| {
+            run.old
+                .iter()
+                .zip(&run.new)
+                .enumerate()
+                .map(|(offset, (old, new))| Change {
+                    index: run.start + offset,
+                    old: *old,
+                    new: *new,
+                })
+        })
+    }
+    ///the diff written out a run a line, like "cells 3-5: 0 1 2 -> 5 5 5", with the cells that
+    ///have names (see annotations.rs) on lines of their own, like "x(4): 1 -> 5"
+    pub fn render(&self, names: &BTreeMap<usize, String>) -> String {
+        let mut text = String::new();
+        for run in &self.runs {
+            let end = run.start + run.old.len();
+            let mut start = run.start;
+            while start < end {
+                let stop = match names.contains_key(&start) {
+                    true => start + 1,
+                    false => (start..end)
+                        .find(|index| names.contains_key(index))
+                        .unwrap_or(end),
+                };
+                let (old, new) = (
+                    &run.old[start - run.start..stop - run.start],
+                    &run.new[start - run.start..stop - run.start],
+                );
+                let cells = match (names.get(&start), stop - start) {
+                    (Some(name), _) => format!("{}({})", name, start),
+                    (None, 1) => format!("cell {}", start),
+                    (None, _) => format!("cells {}-{}", start, stop - 1),
+                };
+                text += &format!("{}: {} -> {}\n", cells, join(old, " "), join(new, " "));
+                start = stop;
+            }
+        }
+        text
+    }
+    ///the diff as json, like {"runs":[{"start":3,"old":[0,1,2],"new":[5,5,5]}]}, which is the
+    ///same as what serde makes of it
+    pub fn to_json(&self) -> String {
+        let runs: Vec<String> = self
+            .runs
+            .iter()
+            .map(|run| {
+                format!(
+                    "{{\"start\":{},\"old\":[{}],\"new\":[{}]}}",
+                    run.start,
+                    join(&run.old, ","),
+                    join(&run.new, ",")
+                )
+            })
+            .collect();
+        format!("{{\"runs\":[{}]}}", runs.join(","))
+    }
+}
+
+///the bytes as numbers with the separator between them
+fn join(bytes: &[u8], separator: &str) -> String {
+    let bytes: Vec<String> = bytes.iter().map(|byte| format!("{}", byte)).collect();
+    bytes.join(separator)
+}
+
+impl fmt::Display for TapeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(&BTreeMap::new()))
+    }
+}
@@ -0,0 +1,96 @@
+//! running a throwaway bit of program against a machine without disturbing it, for the
+//! debugger's eval: the bf version of gdb's "call". the snippet runs on a copy of the machine,
+//! with the same tape and pointer, from its own first instruction, and with a step budget of its
+//! own so a snippet that never ends can't hang the session. the machine itself is left as it
+//! was, and keep_snippet takes what the snippet did to the tape and the pointer over when
+//! that's wanted.
+//!
+//! the copy doesn't burn the machine's fuel or call its observer, progress callback or runtime
+//! warnings, those are about the program. its steps are counted on from the machine's, so the
+//! budget is the steps it can run past them
+use crate::control::RunOutcome;
+use crate::events::Event;
+use crate::lang::Lang;
+use crate::snapshot::{Snapshot, TapeDiff};
+use crate::{ParseError, RuntimeError, TuringMachine};
+use alloc::vec;
+use alloc::vec::Vec;
+
+///how many steps a snippet gets, unless it's given some other number
+pub const SNIPPET_STEPS: u64 = 100_000;
+
+///what running a snippet did
+pub struct Snippet {
+    ///the copy of the machine the snippet ran on, as it left it
+    pub machine: TuringMachine,
+    pub output: Vec<u8>,
+    ///how many steps it ran, and how many bytes of the input it read
+    pub steps: u64,
+    pub read: usize,
+    ///the cells it changed
+    pub diff: TapeDiff,
+    ///how it ended: halted, cancelled, or failed with something like
+    ///RuntimeError::StepLimitReached when it ran out of steps
+    pub outcome: Result<RunOutcome, RuntimeError>,
+}
+
+impl TuringMachine {
+    ///runs the source on a copy of the machine, see the top of this file, reading from the
+    ///input when it asks for some. it fails only when the source doesn't parse, a snippet that
+    ///fails while running says so in its outcome
+    pub fn run_snippet(
+        &self,
+        source: &str,
+        lang: &Lang,
+        input: &[u8],
+        steps: u64,
+    ) -> Result<Snippet, ParseError> {
+        let before = Snapshot::new(self);
+        let mut machine = self.clone();
+        machine.load(source, lang)?;
+        machine.step_limit = Some(self.steps.saturating_add(steps));
+        machine.fuel = None;
+        machine.observer = None;
+        machine.warnings = None;
+        #[cfg(feature = "std")]
+        {
+            machine.progress = None;
+        }
+        machine.provided_input = None;
+        machine.pending_output.clear();
+        let mut output = vec![];
+        let mut read = 0;
+        let outcome = loop {
+            match machine.run_until_event() {
+                Event::Output(byte) => output.push(byte),
+                Event::InputNeeded => {
+                    machine.provide_input(input.get(read).copied());
+                    read += 1;
+                }
+                Event::Breakpoint(_) => {}
+                Event::Halted => break Ok(RunOutcome::Halted),
+                Event::Error(e) => break Err(e),
+                Event::Cancelled => break Ok(RunOutcome::Cancelled),
+            }
+        };
+        Ok(Snippet {
+            steps: machine.steps - self.steps,
+            read: read.min(input.len()),
+            diff: before.diff(&machine),
+            machine,
+            output,
+            outcome,
+        })
+    }
+    ///takes over what the snippet did to the tape and the pointer, leaving everything else,
+    ///like where the program is, as it is
+    pub fn keep_snippet(&mut self, snippet: &Snippet) {
+        self.tape = snippet.machine.tape;
+        self.pointer = snippet.machine.pointer;
+        #[cfg(feature = "bigint")]
+        {
+            self.big_cells = snippet.machine.big_cells.clone();
+        }
+        self.forget_states();
+    }
+}
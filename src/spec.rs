@@ -0,0 +1,576 @@
+//! a spec of what one program should do, for "bfint verify": a table of cases, each an input and
+//! the output the program should give for it. where "bfint test" runs a directory of programs
+//! each against its own output, a spec runs one program against a whole table of them, which
+//! suits programs that were generated to fit one.
+//!
+//! a spec is a toml file:
+//!
+//! ```toml
+//! # the step limit of every case that doesn't give its own, 10000000 if it's left out
+//! max_steps = 100000
+//!
+//! [[cases]]
+//! name = "nothing"
+//! expected_output = ""
+//!
+//! [[cases]]
+//! input = "abc\n"
+//! expected_output = "cba\n"
+//! max_steps = 5000
+//!
+//! [[cases]]
+//! name = "binary"
+//! input_base64 = "AAH/"
+//! expected_output = "\x00\x01\xff"
+//! ```
+//!
+//! or the same as json, with the cases in a list:
+//!
+//! ```json
+//! {"max_steps": 100000, "cases": [{"name": "nothing", "expected_output": ""}]}
+//! ```
+//!
+//! every case needs the output it should give, as expected_output or expected_output_base64 but
+//! not both. its input, which goes the same two ways, is nothing when it's left out, its step
+//! limit is the spec's, and its name is "case 1", "case 2" and so on. text has the escapes both
+//! formats have, like "\n", "\"" and "\u00e9", along with "\x00" to "\xff" for any single byte,
+//! so binary cases don't need base64. toml is only read as far as specs go: a key and its value
+//! on every line, the values being "basic" or 'literal' strings on one line or whole numbers,
+//! and no tables but [[cases]]
+use crate::batch::{self, InputSpec};
+use crate::fixtures::Outcome;
+use crate::{Program, TuringMachine};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+///the step limit of a case when neither it nor its spec gives one
+pub const DEFAULT_MAX_STEPS: u64 = 10_000_000;
+
+///an input and the output the program should give for it
+#[derive(PartialEq, Debug, Clone)]
+pub struct Case {
+    pub name: String,
+    pub input: Vec<u8>,
+    pub expected_output: Vec<u8>,
+    ///the step limit of this case alone
+    pub max_steps: Option<u64>,
+}
+
+///every case a program should pass
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Spec {
+    ///the step limit of every case that doesn't give its own
+    pub max_steps: Option<u64>,
+    pub cases: Vec<Case>,
+}
+
+///everything that can be wrong with a spec file. lines count from 1
+#[derive(PartialEq, Debug, Clone)]
+pub enum SpecError {
+    ///the text isn't toml or json the way a spec is written
+    Syntax { line: usize, problem: String },
+    ///a key that isn't in the schema, see the top of this file
+    UnknownKey { line: usize, key: String },
+    ///a key that's there twice in the same place
+    DuplicateKey { line: usize, key: String },
+    ///a value that isn't right for its key, like text for a step limit, or base64 that isn't
+    BadValue {
+        line: usize,
+        key: String,
+        problem: String,
+    },
+    ///a case, counting from 1, without the output it should give, or with a field both as text
+    ///and as base64
+    BadCase {
+        case: usize,
+        line: usize,
+        problem: String,
+    },
+    ///there are no cases, which would pass whatever the program did
+    NoCases,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecError::Syntax { line, problem } => write!(f, "line {}: {}", line, problem),
+            SpecError::UnknownKey { line, key } => {
+                write!(f, "line {}: \"{}\" isn't something a spec has", line, key)
+            }
+            SpecError::DuplicateKey { line, key } => {
+                write!(f, "line {}: \"{}\" is given twice", line, key)
+            }
+            SpecError::BadValue { line, key, problem } => {
+                write!(f, "line {}: {} {}", line, key, problem)
+            }
+            SpecError::BadCase {
+                case,
+                line,
+                problem,
+            } => write!(f, "case {} on line {}: {}", case, line, problem),
+            SpecError::NoCases => write!(f, "there are no cases"),
+        }
+    }
+}
+
+///a value as it was read, before it's known what it's for
+#[derive(PartialEq, Debug, Clone)]
+enum Value {
+    Text(Vec<u8>),
+    Number(u64),
+}
+
+///a key and its value, and the line it's on
+#[derive(PartialEq, Debug, Clone)]
+struct Field {
+    key: String,
+    value: Value,
+    line: usize,
+}
+
+impl Field {
+    fn text(&self) -> Result<&[u8], SpecError> {
+        match &self.value {
+            Value::Text(text) => Ok(text),
+            Value::Number(_) => Err(self.bad("should be text in quotes")),
+        }
+    }
+    fn number(&self) -> Result<u64, SpecError> {
+        match self.value {
+            Value::Number(number) => Ok(number),
+            Value::Text(_) => Err(self.bad("should be a whole number")),
+        }
+    }
+    fn bad(&self, problem: &str) -> SpecError {
+        SpecError::BadValue {
+            line: self.line,
+            key: self.key.clone(),
+            problem: problem.to_string(),
+        }
+    }
+}
+
+///the fields of a case, and the line it starts on
+#[derive(PartialEq, Debug, Clone)]
+struct Table {
+    line: usize,
+    fields: Vec<Field>,
+}
+
+///the fields by key, as long as every one is a key that can be there and none is there twice
+fn by_key<'a>(
+    fields: &'a [Field],
+    keys: &[&str],
+) -> Result<BTreeMap<&'a str, &'a Field>, SpecError> {
+    let mut by_key = BTreeMap::new();
+    for field in fields {
+        if !keys.contains(&field.key.as_str()) {
+            return Err(SpecError::UnknownKey {
+                line: field.line,
+                key: field.key.clone(),
+            });
+        }
+        if by_key.insert(field.key.as_str(), field).is_some() {
+            return Err(SpecError::DuplicateKey {
+                line: field.line,
+                key: field.key.clone(),
+            });
+        }
+    }
+    Ok(by_key)
+}
+
+impl Spec {
+    ///reads a spec written as toml, see the top of this file
+    pub fn from_toml(text: &str) -> Result<Self, SpecError> {
+        let mut top = vec![];
+        let mut cases: Vec<Table> = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let number = number + 1;
+            let syntax = |problem: &str| SpecError::Syntax {
+                line: number,
+                problem: problem.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                let header = line.split('#').next().unwrap_or_default().trim_end();
+                if header != "[[cases]]" {
+                    return Err(syntax("the only tables in a spec are [[cases]]"));
+                }
+                cases.push(Table {
+                    line: number,
+                    fields: vec![],
+                });
+                continue;
+            }
+            let (key, rest) = line
+                .split_once('=')
+                .ok_or_else(|| syntax("isn't a key = value"))?;
+            let key = key.trim();
+            let key = key
+                .strip_prefix('"')
+                .and_then(|key| key.strip_suffix('"'))
+                .unwrap_or(key);
+            let (value, rest) = toml_value(rest.trim_start()).map_err(|e| syntax(&e))?;
+            let rest = rest.trim_start();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(syntax("there's more on the line after the value"));
+            }
+            let field = Field {
+                key: key.to_string(),
+                value,
+                line: number,
+            };
+            match cases.last_mut() {
+                Some(case) => case.fields.push(field),
+                None => top.push(field),
+            }
+        }
+        Spec::build(&top, &cases)
+    }
+    ///reads a spec written as json, see the top of this file
+    pub fn from_json(json: &str) -> Result<Self, SpecError> {
+        let mut reader = Reader { json, offset: 0 };
+        let mut top = vec![];
+        let mut cases = vec![];
+        let mut cases_line = None;
+        reader.object(|reader, key, line| {
+            if key != "cases" {
+                let value = reader.value()?;
+                top.push(Field { key, value, line });
+                return Ok(());
+            }
+            if cases_line.replace(line).is_some() {
+                return Err(SpecError::DuplicateKey { line, key });
+            }
+            reader.expect('[')?;
+            if reader.eat(']') {
+                return Ok(());
+            }
+            loop {
+                reader.rest();
+                let mut table = Table {
+                    line: reader.line(),
+                    fields: vec![],
+                };
+                reader.object(|reader, key, line| {
+                    let value = reader.value()?;
+                    table.fields.push(Field { key, value, line });
+                    Ok(())
+                })?;
+                cases.push(table);
+                if !reader.eat(',') {
+                    return reader.expect(']');
+                }
+            }
+        })?;
+        match reader.rest().is_empty() {
+            true => Spec::build(&top, &cases),
+            false => Err(reader.error("there's more after the spec")),
+        }
+    }
+    ///the spec the fields make, whichever format they were read from
+    fn build(top: &[Field], cases: &[Table]) -> Result<Self, SpecError> {
+        let top = by_key(top, &["max_steps"])?;
+        let max_steps = top
+            .get("max_steps")
+            .map(|field| field.number())
+            .transpose()?;
+        let keys = [
+            "name",
+            "input",
+            "input_base64",
+            "expected_output",
+            "expected_output_base64",
+            "max_steps",
+        ];
+        let mut built = vec![];
+        for (index, table) in cases.iter().enumerate() {
+            let fields = by_key(&table.fields, &keys)?;
+            let bad_case = |problem: String| SpecError::BadCase {
+                case: index + 1,
+                line: table.line,
+                problem,
+            };
+            //a field can be given as text or as base64, but not both
+            let bytes = |key: &str| -> Result<Option<Vec<u8>>, SpecError> {
+                let base64_key = format!("{}_base64", key);
+                match (fields.get(key), fields.get(base64_key.as_str())) {
+                    (Some(_), Some(_)) => Err(bad_case(format!(
+                        "has both {} and {}, it can only have one",
+                        key, base64_key
+                    ))),
+                    (Some(field), None) => Ok(Some(field.text()?.to_vec())),
+                    (None, Some(field)) => {
+                        let text = String::from_utf8_lossy(field.text()?).into_owned();
+                        base64(&text).map(Some).map_err(|e| field.bad(&e))
+                    }
+                    (None, None) => Ok(None),
+                }
+            };
+            let input = bytes("input")?.unwrap_or_default();
+            let expected_output = bytes("expected_output")?.ok_or_else(|| {
+                bad_case("doesn't say what it should print, with expected_output".to_string())
+            })?;
+            let name = match fields.get("name") {
+                Some(field) => String::from_utf8_lossy(field.text()?).into_owned(),
+                None => format!("case {}", index + 1),
+            };
+            let max_steps = fields
+                .get("max_steps")
+                .map(|field| field.number())
+                .transpose()?;
+            built.push(Case {
+                name,
+                input,
+                expected_output,
+                max_steps,
+            });
+        }
+        match built.is_empty() {
+            true => Err(SpecError::NoCases),
+            false => Ok(Spec {
+                max_steps,
+                cases: built,
+            }),
+        }
+    }
+    ///runs the program on every case, on up to `jobs` threads, and gives back how every case
+    ///went and how many steps it took, in the order of the cases. setup is called on every
+    ///machine before it starts, see batch::run_batch
+    pub fn run(
+        &self,
+        program: &Arc<Program>,
+        jobs: usize,
+        setup: impl Fn(&mut TuringMachine) + Sync,
+    ) -> Vec<(Outcome, u64)> {
+        let inputs = self.cases.iter().map(|case| InputSpec {
+            name: case.name.clone(),
+            input: case.input.clone(),
+            step_limit: Some(
+                case.max_steps
+                    .or(self.max_steps)
+                    .unwrap_or(DEFAULT_MAX_STEPS),
+            ),
+        });
+        batch::run_batch(program, inputs, jobs, setup)
+            .into_iter()
+            .zip(&self.cases)
+            .map(|(result, case)| {
+                let outcome = match result.result {
+                    Ok(()) if result.output == case.expected_output => Outcome::Passed,
+                    Ok(()) => Outcome::WrongOutput(result.output),
+                    Err(e) => Outcome::Failed(e.to_string()),
+                };
+                (outcome, result.steps)
+            })
+            .collect()
+    }
+}
+
+///a toml value at the start of the text, along with the rest of the text after it
+fn toml_value(text: &str) -> Result<(Value, &str), String> {
+    if text.starts_with("\"\"\"") || text.starts_with("'''") {
+        return Err("strings over more than one line aren't read, use \"\\n\"".to_string());
+    }
+    if let Some(text) = text.strip_prefix('"') {
+        let (inside, rest) = quoted(text).ok_or("the text doesn't end on its line")?;
+        return Ok((Value::Text(unescape(inside)?), rest));
+    }
+    //a literal string has no escapes, it's everything up to the next quote
+    if let Some(text) = text.strip_prefix('\'') {
+        let (inside, rest) = text
+            .split_once('\'')
+            .ok_or("the text doesn't end on its line")?;
+        return Ok((Value::Text(inside.as_bytes().to_vec()), rest));
+    }
+    let digits = text.len()
+        - text
+            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '_')
+            .len();
+    match text[..digits].replace('_', "").parse() {
+        Ok(number) => Ok((Value::Number(number), &text[digits..])),
+        Err(_) => Err("the value should be text in quotes or a whole number".to_string()),
+    }
+}
+
+///the text up to the quote that ends it, which the text starts after, and the rest of the text
+///after that quote. escaped quotes don't end it
+fn quoted(text: &str) -> Option<(&str, &str)> {
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some((&text[..index], &text[index + 1..])),
+            '\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+///the bytes of text in quotes, with its escapes, see the top of this file
+fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('0') => '\0',
+                Some(c @ ('\\' | '"' | '/')) => c,
+                Some('x') => {
+                    let digits: String = chars.by_ref().take(2).collect();
+                    match u8::from_str_radix(&digits, 16) {
+                        Ok(byte) if digits.len() == 2 => {
+                            bytes.push(byte);
+                            continue;
+                        }
+                        _ => return Err(format!("'\\x{}' isn't a byte like \\x0a", digits)),
+                    }
+                }
+                Some(u @ ('u' | 'U')) => {
+                    let length = match u {
+                        'u' => 4,
+                        _ => 8,
+                    };
+                    let digits: String = chars.by_ref().take(length).collect();
+                    u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .filter(|_| digits.len() == length)
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("'\\{}{}' isn't a character", u, digits))?
+                }
+                Some(other) => return Err(format!("'\\{}' isn't an escape", other)),
+                None => return Err("the text ends in a lone '\\'".to_string()),
+            },
+            c => c,
+        };
+        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+    Ok(bytes)
+}
+
+///the bytes base64 text stands for, with or without its padding. whitespace is skipped, so
+///long values can be broken up
+fn base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text
+        .trim_end_matches(|c: char| c == '=' || c.is_whitespace())
+        .chars()
+    {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            c if c.is_whitespace() => continue,
+            c => return Err(format!("isn't base64, '{}' isn't one of its characters", c)),
+        };
+        bits = bits << 6 | value;
+        count += 1;
+        if count % 4 == 0 {
+            bytes.extend_from_slice(&bits.to_be_bytes()[1..]);
+            bits = 0;
+        }
+    }
+    match count % 4 {
+        0 => {}
+        2 => bytes.push((bits >> 4) as u8),
+        3 => bytes.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+        _ => return Err("isn't base64, it's a character too long".to_string()),
+    }
+    Ok(bytes)
+}
+
+///walks through the json text of a spec, skipping whitespace before everything it reads
+struct Reader<'a> {
+    json: &'a str,
+    offset: usize,
+}
+
+impl Reader<'_> {
+    ///what's left after the whitespace at the current place
+    fn rest(&mut self) -> &str {
+        let rest = &self.json[self.offset..];
+        self.offset += rest.len() - rest.trim_start().len();
+        &self.json[self.offset..]
+    }
+    ///the line of the current place
+    fn line(&self) -> usize {
+        self.json[..self.offset].matches('\n').count() + 1
+    }
+    fn error(&self, problem: &str) -> SpecError {
+        SpecError::Syntax {
+            line: self.line(),
+            problem: problem.to_string(),
+        }
+    }
+    ///moves past the character if it's next, and says whether it was
+    fn eat(&mut self, c: char) -> bool {
+        let next = self.rest().starts_with(c);
+        if next {
+            self.offset += c.len_utf8();
+        }
+        next
+    }
+    fn expect(&mut self, c: char) -> Result<(), SpecError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected '{}'", c))),
+        }
+    }
+    fn value(&mut self) -> Result<Value, SpecError> {
+        if self.rest().starts_with('"') {
+            return self.string().map(Value::Text);
+        }
+        let rest = self.rest();
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let number = rest[..digits]
+            .parse()
+            .map_err(|_| self.error("expected text in quotes or a whole number"))?;
+        self.offset += digits;
+        Ok(Value::Number(number))
+    }
+    fn string(&mut self) -> Result<Vec<u8>, SpecError> {
+        self.expect('"')?;
+        let rest = &self.json[self.offset..];
+        let (inside, after) = quoted(rest).ok_or_else(|| self.error("the text doesn't end"))?;
+        let text = unescape(inside).map_err(|e| self.error(&e))?;
+        self.offset += rest.len() - after.len();
+        Ok(text)
+    }
+    ///reads an object, calling field with every key and the line it's on to read its value
+    fn object(
+        &mut self,
+        mut field: impl FnMut(&mut Self, String, usize) -> Result<(), SpecError>,
+    ) -> Result<(), SpecError> {
+        self.expect('{')?;
+        if self.eat('}') {
+            return Ok(());
+        }
+        loop {
+            self.rest();
+            let line = self.line();
+            let key = String::from_utf8_lossy(&self.string()?).into_owned();
+            self.expect(':')?;
+            field(self, key, line)?;
+            if !self.eat(',') {
+                return self.expect('}');
+            }
+        }
+    }
+}
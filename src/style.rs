@@ -0,0 +1,58 @@
+//! colors for what bfint shows people. everything that gets colored goes through here, and
+//! coloring is off until it's turned on, so the same text comes out plain when it's going to a
+//! file or a pipe. the command line program turns it on for terminals, see --color
+use alloc::format;
+use alloc::string::String;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+///turns coloring on or off for everything from now on
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+///the text wrapped in the escape codes, or as it is when coloring is off
+fn paint(codes: &str, text: &str) -> String {
+    match enabled() {
+        true => format!("\x1b[{}m{}\x1b[0m", codes, text),
+        false => String::from(text),
+    }
+}
+
+///for errors and the carets pointing at them
+pub fn error(text: &str) -> String {
+    paint("1;31", text)
+}
+
+///for hints, and the carets pointing at them
+pub fn hint(text: &str) -> String {
+    paint("36", text)
+}
+
+///for things that should stand out, like a breakpoint being hit
+pub fn bold(text: &str) -> String {
+    paint("1", text)
+}
+
+///for the cell the pointer is on, among the cells around it
+pub fn current(text: &str) -> String {
+    paint("7", text)
+}
+
+///for the name of an instruction in a trace, a different color for every kind of instruction:
+///moves, changes to cells, input and output, and loops
+pub fn instruction(name: &str) -> String {
+    let codes = match name {
+        "MoveRight" | "MoveLeft" => "34",
+        "Increment" | "Decrement" => "32",
+        "Output" | "Replace" => "35",
+        "JumpToClose" | "JumpToOpen" => "33",
+        _ => "36",
+    };
+    paint(codes, name)
+}
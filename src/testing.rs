@@ -0,0 +1,109 @@
+//! random brainfuck programs that are sure to stop, for throwing at the interpreter to see whether
+//! it holds up. every loop counts down a counter that nothing else touches, so it can only go
+//! around so many times
+use crate::random::Rng;
+
+///what the random programs should look like
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Shape {
+    ///roughly how many commands go into each stretch of program, loop bodies included
+    pub length: usize,
+    ///how deep loops can be inside each other
+    pub max_depth: usize,
+    ///the most times a loop goes around
+    pub max_iterations: u8,
+    ///whether the program reads input with ","
+    pub input: bool,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape {
+            length: 16,
+            max_depth: 2,
+            max_iterations: 8,
+            input: true,
+        }
+    }
+}
+
+///a generated program, along with the input to give it
+#[derive(PartialEq, Debug, Clone)]
+pub struct RandomProgram {
+    pub source: String,
+    pub input: Vec<u8>,
+    ///the program is done within this many steps, whatever its input
+    pub step_bound: u64,
+}
+
+///how many cells to the right of where it starts a stretch of program is allowed to go
+const WIDTH: usize = 8;
+
+///gives back a program that stops, the same one every time for the same seed
+pub fn random_program(seed: u64, shape: &Shape) -> RandomProgram {
+    let mut rng = Rng::from_seed(seed);
+    let mut source = String::new();
+    let step_bound = stretch(&mut rng, shape, shape.max_depth, &mut source);
+    let input = match shape.input {
+        true => (0..below(&mut rng, 16)).map(|_| rng.next_byte()).collect(),
+        false => vec![],
+    };
+    RandomProgram {
+        source,
+        input,
+        step_bound,
+    }
+}
+
+///a number from 0 up to, but not including, the limit
+fn below(rng: &mut Rng, limit: usize) -> usize {
+    (rng.next_u64() % limit as u64) as usize
+}
+
+///writes a stretch of program that starts and ends on the same cell and never goes left of it,
+///and gives back how many steps it can take at most
+fn stretch(rng: &mut Rng, shape: &Shape, depth: usize, source: &mut String) -> u64 {
+    let mut offset = 0;
+    let mut steps = 0;
+    for _ in 0..shape.length {
+        match below(rng, 8) {
+            0 | 1 => source.push(if rng.next_byte() < 128 { '+' } else { '-' }),
+            2 if offset + 1 < WIDTH => {
+                source.push('>');
+                offset += 1;
+            }
+            3 if offset > 0 => {
+                source.push('<');
+                offset -= 1;
+            }
+            4 => source.push('.'),
+            5 if shape.input => source.push(','),
+            6 if depth > 0 => {
+                steps += counted_loop(rng, shape, depth - 1, source);
+                continue;
+            }
+            _ => continue,
+        }
+        steps += 1;
+    }
+    source.push_str(&"<".repeat(offset));
+    steps + offset as u64
+}
+
+///writes "[-]", sets the cell to a small count and then loops that many times over a body that
+///only works on the cells to the right of it, taking one off the count each time around. gives
+///back how many steps that can take at most
+fn counted_loop(rng: &mut Rng, shape: &Shape, depth: usize, source: &mut String) -> u64 {
+    let iterations = 1 + below(rng, shape.max_iterations.max(1) as usize) as u64;
+    source.push_str("[-]");
+    source.push_str(&"+".repeat(iterations as usize));
+    source.push_str("[>");
+    let mut body = String::new();
+    let body_steps = stretch(rng, shape, depth, &mut body);
+    source.push_str(&body);
+    source.push_str("<-]");
+    //clearing the cell goes around at most 255 times, and every time around the counted loop
+    //runs the body along with the "[", ">", "<", "-" and "]" around it
+    let clear = 2 + 255 * 3;
+    clear + iterations + 2 + iterations * (body_steps + 5)
+}
@@ -0,0 +1,214 @@
+//! running hot loops faster, with "--tiered". the machine counts how many times every loop goes
+//! back to its start, and once a loop has done that often enough it gets looked at. a loop that
+//! only adds to cells around the one it loops on, see optimize::AddLoop, then goes around as many
+//! times as it needs to in one go whenever it's entered, instead of an instruction at a time.
+//!
+//! the program itself never changes, it can be shared with other machines and its positions are
+//! what errors and breakpoints point at. what's found out about its loops is kept next to it, by
+//! the index of their "[", and it's only used from a "[", where the loop hasn't started going
+//! around yet, so there's nothing half done to patch up. going around again jumps past the "[",
+//! so a loop that gets hot, or is stopped short, finishes going around an instruction at a time
+//! and runs in one go from the next time it's entered. a loop that's entered while an outer one
+//! is going around is no different
+use crate::optimize::AddLoop;
+use crate::{Instruction, Position, TuringMachine};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+///how many times a loop goes back to its start before it gets looked at, unless told otherwise
+pub const DEFAULT_THRESHOLD: u64 = 100;
+
+///what the machine knows about its loops, when it's tiered
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) struct Tiering {
+    threshold: u64,
+    //how many times every loop has gone back to its start, by the index of its "]"
+    back_edges: Vec<u64>,
+    //the loops that went around often enough to be looked at, by the index of their "["
+    loops: BTreeMap<usize, Tiered>,
+}
+
+impl Tiering {
+    ///forgets everything about the loops, for when the program is swapped for another one
+    pub(crate) fn forget_loops(&mut self) {
+        self.back_edges.clear();
+        self.loops.clear();
+    }
+}
+
+///a loop that was looked at
+#[derive(PartialEq, Debug, Clone)]
+struct Tiered {
+    //what it does each time around, or nothing if it can't be run any faster
+    form: Option<AddLoop>,
+    //how many times it went around in one go, and how many times it went around that way in all
+    runs: u64,
+    iterations: u64,
+}
+
+///a loop that got looked at, see TuringMachine::tiered_loops
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct TierUp {
+    ///where its "[" and "]" are
+    pub start: Position,
+    pub end: Position,
+    ///whether it could be run faster. only loops that do nothing but add can
+    pub faster: bool,
+    ///how many times it was run in one go since, and how many times it went around that way
+    pub runs: u64,
+    pub iterations: u64,
+}
+
+impl TuringMachine {
+    ///makes the machine run loops that have gone back to their start `threshold` times faster
+    ///from then on, where it can, or stops doing that with None. what was found out about the
+    ///loops so far is forgotten
+    pub fn set_tiering(&mut self, threshold: Option<u64>) {
+        self.tiering = threshold.map(|threshold| Tiering {
+            threshold: threshold.max(1),
+            back_edges: vec![],
+            loops: BTreeMap::new(),
+        });
+    }
+    ///the loops that went around often enough to be looked at, in the order they come in the
+    ///program
+    pub fn tiered_loops(&self) -> Vec<TierUp> {
+        let Some(tiering) = &self.tiering else {
+            return vec![];
+        };
+        tiering
+            .loops
+            .iter()
+            .map(|(&start, tiered)| TierUp {
+                start: self.program.positions[start],
+                end: self.program.positions[self.program.ops[start].target()],
+                faster: tiered.form.is_some(),
+                runs: tiered.runs,
+                iterations: tiered.iterations,
+            })
+            .collect()
+    }
+    ///counts the loop whose "]" is at the index going back to its start, and looks at the loop
+    ///if that makes it hot
+    pub(crate) fn count_back_edge(&mut self, end: usize) {
+        let length = self.program.len();
+        let Some(tiering) = &mut self.tiering else {
+            return;
+        };
+        //a "%" runs a program of its own, whose indices aren't the ones the loops are kept by
+        if self.eval_depth > 0 {
+            return;
+        }
+        if tiering.back_edges.len() < length {
+            tiering.back_edges.resize(length, 0);
+        }
+        tiering.back_edges[end] += 1;
+        if tiering.back_edges[end] != tiering.threshold {
+            return;
+        }
+        let start = self.program.ops[end].target();
+        let form =
+            AddLoop::new((start + 1..end).map(|index| self.program.ops[index].instruction()));
+        log::debug!(
+            "the loop at {} went around {} times, {}",
+            self.program.positions[start],
+            tiering.threshold,
+            match form {
+                Some(_) => "it only adds so it will run in one go from now on",
+                None => "it can't be run in one go so it's left as it is",
+            }
+        );
+        tiering.loops.insert(
+            start,
+            Tiered {
+                form,
+                runs: 0,
+                iterations: 0,
+            },
+        );
+    }
+    ///runs the loop whose "[" is the next instruction in one go, if it's been found to only add
+    ///and nothing about the machine needs to see it go around an instruction at a time, like an
+    ///observer. steps count the same as running every instruction would have, and it stops short
+    ///of the step limit and of the next time the control handles or the progress callback would
+    ///be checked in with, leaving the rest to go around as usual. gives back whether it did
+    ///anything
+    pub(crate) fn run_tiered(&mut self) -> bool {
+        let start = self.program_counter;
+        if self.program.instruction(start) != Some(Instruction::JumpToClose)
+            || self.tape[self.pointer] == 0
+            || self.eval_depth > 0
+            || self.history.is_some()
+            || self.writers.is_some()
+            || self.loop_iteration_limit.is_some()
+            || self.observer.is_some()
+            || log::log_enabled!(log::Level::Trace)
+        {
+            return false;
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_cells.is_some() {
+            return false;
+        }
+        let end = self.program.ops[start].target();
+        if self.breakpoints.range(start..=end).next().is_some() {
+            return false;
+        }
+        let budget = self.steps_before_check_in();
+        let pointer = self.pointer;
+        let tape_length = self.tape.len();
+        let Some(tiered) = self
+            .tiering
+            .as_mut()
+            .and_then(|tiering| tiering.loops.get_mut(&start))
+        else {
+            return false;
+        };
+        let Some(form) = &tiered.form else {
+            return false;
+        };
+        if !form.fits(pointer, tape_length) {
+            return false;
+        }
+        //the "[" runs once going in, and every time around runs the body and the "]"
+        let per_iteration = (end - start) as u64;
+        let iterations = form
+            .iterations(self.tape[self.pointer])
+            .min(budget.saturating_sub(1) / per_iteration);
+        if iterations == 0 {
+            return false;
+        }
+        form.go_around(&mut self.tape, pointer, iterations);
+        tiered.runs += 1;
+        tiered.iterations += iterations;
+        self.steps += 1 + iterations * per_iteration;
+        self.burn_fuel(1 + iterations * per_iteration);
+        //when it stopped short, the "]" has just gone back to the start of the body
+        self.program_counter = match self.tape[self.pointer] {
+            0 => end + 1,
+            _ => start + 1,
+        };
+        true
+    }
+    ///how many steps can run before the step limit or the fuel runs out, or before the machine
+    ///next checks in with the control handles or the progress callback, which happens every so
+    ///many steps
+    pub(crate) fn steps_before_check_in(&self) -> u64 {
+        let mut budget = self.fuel_steps();
+        if let Some(limit) = self.step_limit {
+            budget = budget.min(limit.saturating_sub(self.steps));
+        }
+        #[cfg(feature = "std")]
+        {
+            let until_multiple = |every: u64| every - self.steps % every;
+            if self.control.is_some() {
+                budget = budget.min(until_multiple(self.control_interval.max(1)));
+            }
+            if let Some(every) = self.progress.as_ref().map(|reporter| reporter.every) {
+                budget = budget.min(until_multiple(every));
+            }
+        }
+        budget
+    }
+}
@@ -0,0 +1,113 @@
+//! where the debugger can go back to. with the same input a program always runs the same way,
+//! so any step it has been through can be gotten back to by starting from a checkpoint made
+//! before it and running up to it again. the debugger makes a checkpoint every so many steps
+//! as it runs, and goto goes back to the last one before the step it wants.
+//!
+//! how many steps there are between checkpoints grows with the run: when the checkpoints take
+//! up more than the budget, every other one is let go and the gap doubles, so a run of any
+//! length fits and going anywhere in it never runs more than the gap to get there
+use crate::{writers::Writers, TuringMachine};
+
+///how many steps there are between checkpoints to begin with
+pub const FIRST_GAP: u64 = 1024;
+///how many bytes the checkpoints take up at most, unless the timeline is told otherwise
+pub const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+///the machine as it was at one step, and how much of its input it had read by then
+#[derive(PartialEq, Debug, Clone)]
+pub struct Moment {
+    pub steps: u64,
+    ///the machine's checkpoint, see checkpoint.rs
+    pub state: String,
+    pub read: usize,
+    ///who last wrote every cell, if the machine keeps track, which a checkpoint doesn't have
+    pub writers: Option<Writers>,
+}
+
+///the checkpoints of a run so far
+#[derive(PartialEq, Debug, Clone)]
+pub struct Timeline {
+    gap: u64,
+    budget: usize,
+    //oldest first. the first is where the run started, which is kept whatever the gap is
+    moments: Vec<Moment>,
+    size: usize,
+}
+
+impl Timeline {
+    ///a timeline starting at the step the machine is at, which has read that much input
+    pub fn new(tm: &TuringMachine, read: usize, budget: usize) -> Self {
+        let mut timeline = Timeline {
+            gap: FIRST_GAP,
+            budget,
+            moments: vec![],
+            size: 0,
+        };
+        timeline.add(tm, read);
+        timeline
+    }
+    ///how many steps there are between checkpoints right now
+    pub fn gap(&self) -> u64 {
+        self.gap
+    }
+    ///the first step there's a checkpoint for, which nothing before can be gone back to
+    pub fn start(&self) -> u64 {
+        self.moments[0].steps
+    }
+    ///makes a checkpoint of the machine if one is due at its step and there isn't one yet.
+    ///it's called before every stretch of steps the debugger runs
+    pub fn record(&mut self, tm: &TuringMachine, read: usize) {
+        if !tm.steps.is_multiple_of(self.gap) {
+            return;
+        }
+        if let Err(index) = self.moments.binary_search_by_key(&tm.steps, |m| m.steps) {
+            //a step after the last one is the usual case, earlier ones are being run again
+            //after going back, and were let go when the gap grew
+            if index == self.moments.len() {
+                self.add(tm, read);
+            }
+        }
+    }
+    fn add(&mut self, tm: &TuringMachine, read: usize) {
+        let state = tm.checkpoint(&tm.program().source);
+        let writers = tm.writers().cloned();
+        self.size += moment_size(&state, &writers);
+        self.moments.push(Moment {
+            steps: tm.steps,
+            state,
+            read,
+            writers,
+        });
+        while self.size > self.budget && self.moments.len() > 2 {
+            self.gap *= 2;
+            let gap = self.gap;
+            let first = self.moments[0].steps;
+            self.moments
+                .retain(|moment| moment.steps == first || moment.steps.is_multiple_of(gap));
+            self.size = self
+                .moments
+                .iter()
+                .map(|moment| moment_size(&moment.state, &moment.writers))
+                .sum();
+        }
+    }
+    ///how many steps until the next checkpoint is due, from the step
+    pub fn until_due(&self, steps: u64) -> u64 {
+        self.gap - steps % self.gap
+    }
+    ///the last checkpoint at or before the step, nothing if the step is before the first one
+    pub fn before(&self, step: u64) -> Option<&Moment> {
+        self.moments
+            .iter()
+            .rev()
+            .find(|moment| moment.steps <= step)
+    }
+}
+
+///how many bytes a checkpoint takes up, roughly
+fn moment_size(state: &str, writers: &Option<Writers>) -> usize {
+    state.len()
+        + writers.as_ref().map_or(0, |writers| {
+            writers.len() * core::mem::size_of::<Option<crate::writers::Write>>()
+        })
+}
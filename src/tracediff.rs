@@ -0,0 +1,211 @@
+//! running two programs side by side on the same input and finding the first thing one of them
+//! does that the other doesn't, for "bfint tracediff". what's compared is what they read and
+//! write, in order, and how they end, so a program that was rewritten with different loops and
+//! cells still compares the same as long as it reads and writes the same. each machine is run on
+//! events (see events.rs) up to its next read or write, and then the other one is.
+//!
+//! where they part ways, each machine is stopped right at what it did there, and its step count,
+//! place in the source, the loops it's in and the cells around its pointer are kept, to show what
+//! it was up to
+use crate::events::Event;
+use crate::{Position, RuntimeError, TuringMachine};
+use std::fmt;
+
+///something a program did that the other should do too
+#[derive(PartialEq, Debug, Clone)]
+pub enum Io {
+    ///it wrote a byte
+    Output(u8),
+    ///it read a byte, or nothing when the input had run out
+    Read(Option<u8>),
+    ///it got to the end
+    Halted,
+    ///it failed. any two failures count as the same, since where they happen is different
+    ///between programs
+    Failed(String),
+    ///it ran for the step limit
+    OutOfSteps,
+}
+
+impl Io {
+    ///whether another program doing this does the same
+    pub fn same(&self, other: &Io) -> bool {
+        match (self, other) {
+            (Io::Failed(_), Io::Failed(_)) => true,
+            _ => self == other,
+        }
+    }
+    ///whether nothing comes after it
+    pub fn ends(&self) -> bool {
+        matches!(self, Io::Halted | Io::Failed(_) | Io::OutOfSteps)
+    }
+}
+
+impl fmt::Display for Io {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let byte = |f: &mut fmt::Formatter, byte: u8| match byte.is_ascii_graphic() || byte == b' '
+        {
+            true => write!(f, "{} '{}'", byte, byte as char),
+            false => write!(f, "{}", byte),
+        };
+        match self {
+            Io::Output(b) => {
+                write!(f, "wrote ")?;
+                byte(f, *b)
+            }
+            Io::Read(Some(b)) => {
+                write!(f, "read ")?;
+                byte(f, *b)
+            }
+            Io::Read(None) => write!(f, "read past the end of the input"),
+            Io::Halted => write!(f, "halted"),
+            Io::Failed(e) => write!(f, "failed: {}", e),
+            Io::OutOfSteps => write!(f, "ran out of steps"),
+        }
+    }
+}
+
+///one of the machines, where it did something
+#[derive(PartialEq, Debug, Clone)]
+pub struct Side {
+    pub io: Io,
+    pub steps: u64,
+    ///the instruction that did it, nothing when the program halted
+    pub position: Option<Position>,
+    ///the loops it was in, innermost first, as the positions of their "[" and "]"
+    pub loops: Vec<(Position, Position)>,
+    ///the machine's state, see TuringMachine::describe_state
+    pub state: String,
+}
+
+impl Side {
+    fn new(io: Io, position: Option<Position>, tm: &TuringMachine) -> Self {
+        Side {
+            io,
+            steps: tm.steps,
+            position,
+            loops: tm.enclosing_loops(),
+            state: tm.describe_state(),
+        }
+    }
+}
+
+///how the two runs compared
+#[derive(PartialEq, Debug, Clone)]
+pub enum Comparison {
+    ///they read and wrote the same, this many times, and ended the same way
+    Same { events: usize, end: Io },
+    ///they read and wrote the same this many times, and then did what the sides say
+    Different { events: usize, sides: [Side; 2] },
+}
+
+///one of the machines and how much of the input it has read
+struct Runner {
+    tm: TuringMachine,
+    read: usize,
+}
+
+impl Runner {
+    ///runs the machine up to the next thing it does
+    fn next(&mut self, input: &[u8]) -> Side {
+        loop {
+            let event = self.tm.run_until_event();
+            let io = match event {
+                Event::Output(byte) => Io::Output(byte),
+                Event::InputNeeded => {
+                    //the "," is still the next instruction, it takes the byte when it runs
+                    let byte = input.get(self.read).copied();
+                    self.read += usize::from(byte.is_some());
+                    self.tm.provide_input(byte);
+                    return Side::new(Io::Read(byte), self.tm.position(), &self.tm);
+                }
+                Event::Breakpoint(_) => continue,
+                Event::Halted => return Side::new(Io::Halted, None, &self.tm),
+                Event::Error(RuntimeError::StepLimitReached(_)) => Io::OutOfSteps,
+                Event::Error(e) => Io::Failed(e.to_string()),
+                Event::Cancelled => Io::Failed("the run was cancelled".to_string()),
+            };
+            //the instruction that wrote or failed has run already, or couldn't
+            let position = match io {
+                Io::Output(_) => self.tm.last_position(),
+                _ => self.tm.position(),
+            };
+            return Side::new(io, position, &self.tm);
+        }
+    }
+}
+
+///runs both machines on the input, each stopped after the step limit, until one of them does
+///something the other doesn't or they've both ended
+pub fn compare(machines: [TuringMachine; 2], input: &[u8], step_limit: u64) -> Comparison {
+    let mut runners = machines.map(|mut tm| {
+        tm.step_limit = Some(step_limit);
+        Runner { tm, read: 0 }
+    });
+    let mut events = 0;
+    loop {
+        let sides = runners.each_mut().map(|runner| runner.next(input));
+        if !sides[0].io.same(&sides[1].io) {
+            return Comparison::Different { events, sides };
+        }
+        if sides[0].io.ends() {
+            let [side, _] = sides;
+            return Comparison::Same {
+                events,
+                end: side.io,
+            };
+        }
+        events += 1;
+    }
+}
+
+///what the comparison found, for people to read, with the programs called by their names
+pub fn describe(comparison: &Comparison, names: [&str; 2]) -> String {
+    match comparison {
+        Comparison::Same { events, end } => format!(
+            "{} and {} did the same {} and then both {}\n",
+            names[0],
+            names[1],
+            reads_and_writes(*events),
+            match end {
+                Io::Failed(_) => "failed".to_string(),
+                end => end.to_string(),
+            }
+        ),
+        Comparison::Different { events, sides } => {
+            let mut text = format!(
+                "{} and {} did the same {}, and then:\n",
+                names[0],
+                names[1],
+                reads_and_writes(*events)
+            );
+            for (name, side) in names.iter().zip(sides) {
+                let place = match side.position {
+                    Some(position) => format!("at {}", position),
+                    None => "at the end".to_string(),
+                };
+                text += &format!(
+                    "{} {} {}, after {} steps\n",
+                    name, side.io, place, side.steps
+                );
+                if !side.loops.is_empty() {
+                    let loops: Vec<String> = side
+                        .loops
+                        .iter()
+                        .map(|(open, close)| format!("{}-{}", open, close))
+                        .collect();
+                    text += &format!("  in the loops {}\n", loops.join(", "));
+                }
+                text += &format!("  {}\n", side.state);
+            }
+            text
+        }
+    }
+}
+
+fn reads_and_writes(count: usize) -> String {
+    match count {
+        1 => "1 read or write".to_string(),
+        count => format!("{} reads and writes", count),
+    }
+}
@@ -0,0 +1,328 @@
+//! a record of every byte a program read and wrote, along with the step it happened on. a run
+//! can be recorded once and played back later, which feeds the program the same input and checks
+//! that it still writes the same output at the same moments
+use std::fmt;
+
+///one byte going in or out of the program
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Entry {
+    ///"," read the byte, or found the end of the input if there was none
+    Read { step: u64, byte: Option<u8> },
+    ///"." wrote the byte
+    Write { step: u64, byte: u8 },
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Read {
+                step,
+                byte: Some(byte),
+            } => write!(f, "read {} on step {}", byte, step),
+            Entry::Read { step, byte: None } => {
+                write!(f, "read the end of the input on step {}", step)
+            }
+            Entry::Write { step, byte } => write!(f, "wrote {} on step {}", byte, step),
+        }
+    }
+}
+
+///everything that was read and written during a run, in the order it happened
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Transcript {
+    pub entries: Vec<Entry>,
+}
+
+///everything that can be wrong with a transcript file
+#[derive(PartialEq, Debug, Clone)]
+pub enum TranscriptError {
+    ///the text isn't a transcript, the problem was found this many characters in
+    Syntax(usize),
+    ///the transcript was written by a newer version of the format
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranscriptError::Syntax(offset) => {
+                write!(
+                    f,
+                    "not a transcript, the problem is at character {}",
+                    offset
+                )
+            }
+            TranscriptError::UnsupportedVersion(version) => {
+                write!(f, "version {} transcripts aren't supported", version)
+            }
+        }
+    }
+}
+
+///the version written into every transcript, so the format can change without old files being
+///misread
+const VERSION: u64 = 1;
+
+impl Transcript {
+    pub fn new() -> Self {
+        Transcript::default()
+    }
+    ///the transcript as json, with one entry per line so they're easy to read and diff
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\n  \"version\": {},\n  \"events\": [", VERSION);
+        for (index, entry) in self.entries.iter().enumerate() {
+            json.push_str(if index == 0 { "\n" } else { ",\n" });
+            match entry {
+                Entry::Read { step, byte } => {
+                    let byte = byte.map_or("null".to_string(), |byte| byte.to_string());
+                    json.push_str(&format!("    {{\"step\": {}, \"read\": {}}}", step, byte));
+                }
+                Entry::Write { step, byte } => {
+                    json.push_str(&format!("    {{\"step\": {}, \"write\": {}}}", step, byte));
+                }
+            }
+        }
+        json.push_str("\n  ]\n}\n");
+        json
+    }
+    ///the transcript the way someone watching the run saw it, for reading rather than replaying.
+    ///what was written is shown as text with what was read next to it in «», and every line
+    ///starts with the step its first byte came on. bytes that aren't printable are written as
+    ///escapes like \x07, and the end of the input as «EOF»
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        //whether the line being written has had its first byte yet
+        let mut started = false;
+        let mut reading = false;
+        for entry in &self.entries {
+            let (step, byte, read) = match *entry {
+                Entry::Read { step, byte } => (step, byte, true),
+                Entry::Write { step, byte } => (step, Some(byte), false),
+            };
+            if !started {
+                text.push_str(&format!("{:>8} | ", step));
+                started = true;
+            }
+            if read != reading {
+                text.push(if read { '«' } else { '»' });
+                reading = read;
+            }
+            match byte {
+                //a newline that was read is shown like one, only the output's start new lines
+                Some(b'\n') if read => text.push_str("\\n"),
+                Some(b'\n') => {
+                    text.push('\n');
+                    started = false;
+                }
+                Some(byte) => text.push_str(&escape(byte)),
+                None => text.push_str("EOF"),
+            }
+        }
+        if reading {
+            text.push('»');
+        }
+        if started {
+            text.push('\n');
+        }
+        text
+    }
+    ///reads back a transcript written by to_json. the keys have to come in the same order, but
+    ///the whitespace between things doesn't matter
+    pub fn from_json(json: &str) -> Result<Self, TranscriptError> {
+        let mut reader = Reader { json, offset: 0 };
+        reader.expect("{")?;
+        reader.expect("\"version\"")?;
+        reader.expect(":")?;
+        let version = reader.number()?;
+        if version != VERSION {
+            return Err(TranscriptError::UnsupportedVersion(version));
+        }
+        reader.expect(",")?;
+        reader.expect("\"events\"")?;
+        reader.expect(":")?;
+        reader.expect("[")?;
+        let mut entries = vec![];
+        if !reader.next_is("]") {
+            loop {
+                reader.expect("{")?;
+                reader.expect("\"step\"")?;
+                reader.expect(":")?;
+                let step = reader.number()?;
+                reader.expect(",")?;
+                let entry = if reader.next_is("\"read\"") {
+                    reader.expect("\"read\"")?;
+                    reader.expect(":")?;
+                    let byte = match reader.next_is("null") {
+                        true => reader.expect("null").map(|_| None)?,
+                        false => Some(reader.byte()?),
+                    };
+                    Entry::Read { step, byte }
+                } else {
+                    reader.expect("\"write\"")?;
+                    reader.expect(":")?;
+                    Entry::Write {
+                        step,
+                        byte: reader.byte()?,
+                    }
+                };
+                entries.push(entry);
+                reader.expect("}")?;
+                if !reader.next_is(",") {
+                    break;
+                }
+                reader.expect(",")?;
+            }
+        }
+        reader.expect("]")?;
+        reader.expect("}")?;
+        match reader.rest().is_empty() {
+            true => Ok(Transcript { entries }),
+            false => Err(TranscriptError::Syntax(reader.offset)),
+        }
+    }
+}
+
+///a byte the way to_text shows it
+fn escape(byte: u8) -> String {
+    match byte {
+        b'\\' => "\\\\".to_string(),
+        b'\t' => "\\t".to_string(),
+        b'\r' => "\\r".to_string(),
+        b' '..=b'~' => (byte as char).to_string(),
+        _ => format!("\\x{:02x}", byte),
+    }
+}
+
+///walks through the json text of a transcript, skipping whitespace before everything it reads
+struct Reader<'a> {
+    json: &'a str,
+    offset: usize,
+}
+
+impl Reader<'_> {
+    ///what's left after the whitespace at the current place
+    fn rest(&mut self) -> &str {
+        let rest = &self.json[self.offset..];
+        self.offset += rest.len() - rest.trim_start().len();
+        &self.json[self.offset..]
+    }
+    fn next_is(&mut self, text: &str) -> bool {
+        self.rest().starts_with(text)
+    }
+    fn expect(&mut self, text: &str) -> Result<(), TranscriptError> {
+        match self.next_is(text) {
+            true => {
+                self.offset += text.len();
+                Ok(())
+            }
+            false => Err(TranscriptError::Syntax(self.offset)),
+        }
+    }
+    fn number(&mut self) -> Result<u64, TranscriptError> {
+        let rest = self.rest();
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let number = rest[..digits]
+            .parse()
+            .map_err(|_| TranscriptError::Syntax(self.offset))?;
+        self.offset += digits;
+        Ok(number)
+    }
+    fn byte(&mut self) -> Result<u8, TranscriptError> {
+        self.rest();
+        let offset = self.offset;
+        self.number()?
+            .try_into()
+            .map_err(|_| TranscriptError::Syntax(offset))
+    }
+}
+
+///where a replayed run stopped doing what the transcript says it did
+#[derive(PartialEq, Debug, Clone)]
+pub struct Divergence {
+    ///what the program did, nothing if it stopped
+    pub actual: Option<Entry>,
+    ///what the transcript says should have happened, nothing if it had already ended
+    pub expected: Option<Entry>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        //the program hasn't been given anything when it reads where it shouldn't, so there's no
+        //byte to show
+        let actual = self.actual.map(|actual| match actual {
+            Entry::Read { step, .. } => format!("read on step {}", step),
+            write => write.to_string(),
+        });
+        match (actual, self.expected) {
+            (Some(actual), Some(expected)) => {
+                write!(f, "the program {} where the recording {}", actual, expected)
+            }
+            (Some(actual), None) => {
+                write!(f, "the program {} after the end of the recording", actual)
+            }
+            (None, Some(expected)) => {
+                write!(f, "the program stopped where the recording {}", expected)
+            }
+            (None, None) => write!(f, "the program did what the recording did"),
+        }
+    }
+}
+
+///plays a transcript back to a running program, one entry at a time
+#[derive(PartialEq, Debug, Clone)]
+pub struct Replay {
+    transcript: Transcript,
+    //the entry that should happen next
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(transcript: Transcript) -> Self {
+        Replay {
+            transcript,
+            next: 0,
+        }
+    }
+    ///the input the program should get when it reads on this step
+    pub fn read(&mut self, step: u64) -> Result<Option<u8>, Divergence> {
+        let expected = self.transcript.entries.get(self.next).copied();
+        match expected {
+            Some(Entry::Read {
+                step: recorded,
+                byte,
+            }) if recorded == step => {
+                self.next += 1;
+                Ok(byte)
+            }
+            _ => Err(Divergence {
+                actual: Some(Entry::Read { step, byte: None }),
+                expected,
+            }),
+        }
+    }
+    ///checks that the program was meant to write this byte on this step
+    pub fn write(&mut self, step: u64, byte: u8) -> Result<(), Divergence> {
+        let actual = Entry::Write { step, byte };
+        let expected = self.transcript.entries.get(self.next).copied();
+        match expected == Some(actual) {
+            true => {
+                self.next += 1;
+                Ok(())
+            }
+            false => Err(Divergence {
+                actual: Some(actual),
+                expected,
+            }),
+        }
+    }
+    ///checks that nothing in the transcript was left over when the program stopped
+    pub fn finish(&self) -> Result<(), Divergence> {
+        match self.transcript.entries.get(self.next) {
+            None => Ok(()),
+            Some(&expected) => Err(Divergence {
+                actual: None,
+                expected: Some(expected),
+            }),
+        }
+    }
+}
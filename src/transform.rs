@@ -0,0 +1,168 @@
+//! a program as a piece of a std::io chain, like a filter in a shell pipeline: what's written
+//! to a BfTransform is what the program reads with ",", and what it writes with "." is what
+//! comes out of reading it. so putting a file through rot13 is
+//!
+//! ```ignore
+//! let mut rot13 = BfTransform::new(TuringMachine::new(&source, &Lang::Brainfuck)?);
+//! io::copy(&mut file, &mut rot13)?;
+//! rot13.read_to_end(&mut output)?;
+//! ```
+//!
+//! it's all on one thread, driven by the events in events.rs, so nothing ever blocks on the other
+//! side and nothing gives back WouldBlock. writing runs the program as far as the input written so
+//! far takes it, and whatever it writes meanwhile is kept until it's read. reading gives back
+//! what's been kept, and once that's all gone runs the program on until there's enough to fill the
+//! buffer. when the program is waiting for input that hasn't been written, nothing else can write
+//! it while the reader waits, so reading closes the input then and the program gets to the end of
+//! it, the same as with close_input, and writing after that is an error. to read in between writes
+//! without that, read only while available says there's something, which is everything the input so
+//! far has made.
+//!
+//! a program that halts before it has read all of its input, like one that only wants the
+//! first line, is done: whatever is written after that is thrown away without an error, the
+//! way a filter that stopped reading doesn't care what comes after, and unread counts it for
+//! whoever does care. a program that fails makes the write it failed in and every one after
+//! it, and the read after the output it had written, give back the error
+use crate::events::Event;
+use crate::TuringMachine;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+///a program with a Write for its input and a Read for its output, see the top of this file
+pub struct BfTransform {
+    machine: TuringMachine,
+    //what's been written that the program hasn't read yet
+    input: VecDeque<u8>,
+    //whether the input has been closed, after which the program gets to the end of it
+    closed: bool,
+    //what the program has written that hasn't been read yet
+    output: VecDeque<u8>,
+    //how far the program has got
+    ended: Ended,
+    //how many bytes were written after the program halted, or were still unread when it did
+    thrown_away: u64,
+}
+
+///how far the program has got
+#[derive(PartialEq, Debug)]
+enum Ended {
+    No,
+    Halted,
+    Failed(String),
+}
+
+impl BfTransform {
+    ///puts the machine in the middle of the chain, as it is. from now on it's driven by events,
+    ///so nothing goes through its own input and output handles
+    pub fn new(machine: TuringMachine) -> Self {
+        BfTransform {
+            machine,
+            input: VecDeque::new(),
+            closed: false,
+            output: VecDeque::new(),
+            ended: Ended::No,
+            thrown_away: 0,
+        }
+    }
+    ///the machine, as far as the program has got
+    pub fn machine(&self) -> &TuringMachine {
+        &self.machine
+    }
+    pub fn into_machine(self) -> TuringMachine {
+        self.machine
+    }
+    ///how many bytes can be read without running the program any further
+    pub fn available(&self) -> usize {
+        self.output.len()
+    }
+    ///how many of the bytes written the program never read, because it halted first
+    pub fn unread(&self) -> u64 {
+        self.thrown_away + self.input.len() as u64
+    }
+    ///whether the program has halted or failed
+    pub fn is_done(&self) -> bool {
+        self.ended != Ended::No
+    }
+    ///says there's nothing more to write, so once the program has read everything written it
+    ///gets the end of its input, and runs it to the end
+    pub fn close_input(&mut self) -> io::Result<()> {
+        self.closed = true;
+        self.run(None);
+        self.failure()
+    }
+    ///runs the program until it wants input that hasn't been written, or it's done. when
+    ///reading, as many bytes as are wanted, the input is closed instead of waiting for it, and
+    ///it stops once there are that many to read, so the output isn't all kept at once
+    fn run(&mut self, reading: Option<usize>) {
+        while self.ended == Ended::No {
+            match self.machine.run_until_event() {
+                Event::Output(byte) => {
+                    self.output.push_back(byte);
+                    if reading.is_some_and(|wanted| self.output.len() >= wanted) {
+                        return;
+                    }
+                }
+                Event::InputNeeded => {
+                    match (self.input.pop_front(), self.closed || reading.is_some()) {
+                        (Some(byte), _) => self.machine.provide_input(Some(byte)),
+                        (None, true) => {
+                            self.closed = true;
+                            self.machine.provide_input(None);
+                        }
+                        (None, false) => return,
+                    }
+                }
+                Event::Breakpoint(_) => {}
+                Event::Halted => {
+                    self.thrown_away += self.input.len() as u64;
+                    self.input.clear();
+                    self.ended = Ended::Halted;
+                }
+                Event::Error(e) => self.ended = Ended::Failed(e.to_string()),
+                Event::Cancelled => self.ended = Ended::Failed("the run was cancelled".into()),
+            }
+        }
+    }
+    fn failure(&self) -> io::Result<()> {
+        match &self.ended {
+            Ended::Failed(message) => Err(io::Error::other(message.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Write for BfTransform {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.failure()?;
+        match (&self.ended, self.closed) {
+            (Ended::Halted, _) => self.thrown_away += bytes.len() as u64,
+            (_, true) => return Err(io::Error::other("the input was already closed")),
+            (_, false) => {
+                self.input.extend(bytes);
+                self.run(None);
+                self.failure()?;
+            }
+        }
+        Ok(bytes.len())
+    }
+    ///the program has already got as far as it can with what's been written
+    fn flush(&mut self) -> io::Result<()> {
+        self.failure()
+    }
+}
+
+impl Read for BfTransform {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.output.is_empty() {
+            self.run(Some(buffer.len()));
+        }
+        if self.output.is_empty() {
+            self.failure()?;
+        }
+        let length = buffer.len().min(self.output.len());
+        for (into, byte) in buffer.iter_mut().zip(self.output.drain(..length)) {
+            *into = byte;
+        }
+        Ok(length)
+    }
+}
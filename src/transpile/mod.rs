@@ -0,0 +1,176 @@
+//! turning brainfuck programs into equivalent programs in other languages, so people can see what
+//! a brainfuck program "means" written out in a language they already know
+use crate::{Instruction, Position, TuringMachine};
+
+mod python;
+
+///every language a brainfuck program can be translated into
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Target {
+    Python,
+}
+
+impl Target {
+    pub const ALL: [Target; 1] = [Target::Python];
+    ///turns the name used on the command line into the matching target. "py" is another name
+    ///for python
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = match name {
+            "py" => "python",
+            name => name,
+        };
+        Target::ALL.into_iter().find(|target| target.name() == name)
+    }
+    ///the name of the language it's translated to
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Python => "python",
+        }
+    }
+}
+
+///a slightly higher level view of the program that the backends work from. runs of "+" and "-"
+///are added up into a single change to the cell, runs of ">" and "<" into a single move, and
+///loops hold their body directly so the backends can just follow the nesting. every node keeps
+///the position of the first instruction it was made from
+#[derive(PartialEq, Debug)]
+enum Node {
+    //change the current cell by the amount, wrapping around
+    Add(i32, Position),
+    //move the pointer by the amount, negative is to the left
+    Move(isize, Position),
+    Output(Position),
+    Input(Position),
+    Loop(Vec<Node>, Position),
+    //pbrain's "(" ... ")", defines a procedure numbered by the current cell
+    Procedure(Vec<Node>, Position),
+    //pbrain's ":"
+    Call(Position),
+    //one of the instructions that only some dialects have, which are never folded together
+    Extended(Instruction, Position),
+    //brainfork's "Y"
+    Fork(Position),
+}
+
+impl Drop for Node {
+    ///takes the nodes inside apart one at a time, so a program with a hundred thousand loops
+    ///inside each other doesn't drop them by going a hundred thousand calls deep
+    fn drop(&mut self) {
+        if let Node::Loop(body, _) | Node::Procedure(body, _) = self {
+            let mut nodes = core::mem::take(body);
+            while let Some(mut node) = nodes.pop() {
+                if let Node::Loop(body, _) | Node::Procedure(body, _) = &mut node {
+                    nodes.append(body);
+                }
+            }
+        }
+    }
+}
+
+///builds the list of nodes for a parsed program. the program has already been bracket checked by
+///the parser, so every loop and procedure is known to be closed
+fn build(program: &[Instruction], positions: &[Position]) -> Vec<Node> {
+    //the bodies of loops and procedures that are still open, the outermost one at the bottom. the
+    //very first entry is the program itself, which is why it has no position
+    let mut bodies: Vec<(Vec<Node>, Option<Position>)> = vec![(vec![], None)];
+    for (instruction, position) in program.iter().zip(positions.iter().copied()) {
+        let body = &mut bodies
+            .last_mut()
+            .expect("the program itself is never popped")
+            .0;
+        match instruction {
+            Instruction::Increment | Instruction::Decrement => {
+                let amount = if *instruction == Instruction::Increment {
+                    1
+                } else {
+                    -1
+                };
+                match body.last_mut() {
+                    Some(Node::Add(total, _)) => *total += amount,
+                    _ => body.push(Node::Add(amount, position)),
+                }
+            }
+            Instruction::MoveRight | Instruction::MoveLeft => {
+                let amount = if *instruction == Instruction::MoveRight {
+                    1
+                } else {
+                    -1
+                };
+                match body.last_mut() {
+                    Some(Node::Move(total, _)) => *total += amount,
+                    _ => body.push(Node::Move(amount, position)),
+                }
+            }
+            Instruction::Output => body.push(Node::Output(position)),
+            Instruction::Replace => body.push(Node::Input(position)),
+            Instruction::Call => body.push(Node::Call(position)),
+            Instruction::Fork => body.push(Node::Fork(position)),
+            Instruction::Halt
+            | Instruction::DebugDump
+            | Instruction::Store
+            | Instruction::Restore
+            | Instruction::ShiftRight
+            | Instruction::ShiftLeft
+            | Instruction::Not
+            | Instruction::Xor
+            | Instruction::And
+            | Instruction::Or
+            | Instruction::FlipBit
+            | Instruction::ReadBit
+            | Instruction::WriteBit
+            | Instruction::OpenFile
+            | Instruction::ReadFile
+            | Instruction::WriteFile
+            | Instruction::Eval
+            | Instruction::Random
+            | Instruction::SwitchChannel
+            | Instruction::Custom(_) => body.push(Node::Extended(*instruction, position)),
+            Instruction::JumpToClose | Instruction::ProcedureStart => {
+                bodies.push((vec![], Some(position)))
+            }
+            Instruction::JumpToOpen | Instruction::ProcedureEnd => {
+                let (body, position) = bodies.pop().expect("brackets are checked by the parser");
+                let position = position.expect("only loops and procedures are popped");
+                let node = if *instruction == Instruction::JumpToOpen {
+                    Node::Loop(body, position)
+                } else {
+                    Node::Procedure(body, position)
+                };
+                bodies
+                    .last_mut()
+                    .expect("a loop always has something around it")
+                    .0
+                    .push(node);
+            }
+        }
+    }
+    bodies.pop().expect("the program itself is always there").0
+}
+
+///translates the program loaded into the turing machine into the target language. "name" is only
+///used to say where the program came from in the generated header
+pub fn transpile(tm: &TuringMachine, target: Target, name: &str) -> String {
+    let instructions: Vec<Instruction> = tm.program.instructions().collect();
+    let nodes = build(&instructions, &tm.program.positions);
+    match target {
+        Target::Python => python::emit(&nodes, tm, name),
+    }
+}
+
+///checks if any of the nodes, or the nodes inside loops and procedures, match "check". lets the
+///backends leave out setup code that the program doesn't need. the bodies still to look through
+///are kept on a stack, however deep they go
+fn any_node(nodes: &[Node], check: &dyn Fn(&Node) -> bool) -> bool {
+    let mut bodies = vec![nodes];
+    while let Some(body) = bodies.pop() {
+        for node in body {
+            if check(node) {
+                return true;
+            }
+            if let Node::Loop(body, _) | Node::Procedure(body, _) = node {
+                bodies.push(body);
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,468 @@
+//! the python backend. the output is meant to be read by people first, so it uses a plain
+//! bytearray for the tape, one statement per (folded) instruction, and a comment on every line
+//! saying where in the brainfuck source it came from
+use super::{any_node, Node};
+use crate::{EofBehavior, Instruction, Position, TuringMachine};
+use std::fmt::{self, Write};
+
+///writes out a complete python program for the nodes, set up the same way as the turing machine
+pub fn emit(nodes: &[Node], tm: &TuringMachine, name: &str) -> String {
+    let mut out = String::new();
+    write_program(&mut out, nodes, tm, name).expect("writing to a String can't fail");
+    out
+}
+
+///what emit gives back, written to the string
+fn write_program(out: &mut String, nodes: &[Node], tm: &TuringMachine, name: &str) -> fmt::Result {
+    let eof_behavior = tm.eof_behavior;
+    let eof_value = match eof_behavior {
+        EofBehavior::Zero => "0",
+        EofBehavior::Max => "255",
+        EofBehavior::Unchanged => "cell",
+    };
+    writeln!(out, "#!/usr/bin/env python3")?;
+    writeln!(out, "# translated from {} by bfint", name)?;
+    writeln!(
+        out,
+        "# the comment after every line is the line:column it came from"
+    )?;
+    //whether the program uses any of the instructions, so setup code is only written when it is
+    //needed
+    let uses = |instructions: &[Instruction]| {
+        any_node(nodes, &|node| match node {
+            Node::Extended(instruction, _) => instructions.contains(instruction),
+            _ => false,
+        })
+    };
+    let uses_bits = uses(&[Instruction::ReadBit, Instruction::WriteBit]);
+    if any_node(nodes, &|node| matches!(node, Node::Fork(_))) || uses(&[Instruction::OpenFile]) {
+        writeln!(out, "import os")?;
+    }
+    let uses_channels = uses(&[Instruction::SwitchChannel]);
+    if uses_channels {
+        writeln!(out, "import io")?;
+    }
+    writeln!(out, "import sys")?;
+    writeln!(out)?;
+    writeln!(out, "tape = bytearray({})", tm.tape.len())?;
+    writeln!(out, "p = 0")?;
+    if uses(&[
+        Instruction::Store,
+        Instruction::Restore,
+        Instruction::Xor,
+        Instruction::And,
+        Instruction::Or,
+    ]) {
+        writeln!(out, "storage = 0")?;
+    }
+    if any_node(nodes, &|node| {
+        matches!(node, Node::Procedure(..) | Node::Call(_))
+    }) {
+        writeln!(
+            out,
+            "# pbrain procedures that have been defined so far, by number"
+        )?;
+        writeln!(out, "procedures = {{}}")?;
+    }
+    writeln!(out)?;
+    writeln!(out)?;
+    writeln!(out, "def read(cell):")?;
+    writeln!(
+        out,
+        "    # end of input is handled as '{}'",
+        eof_behavior.name()
+    )?;
+    writeln!(out, "    sys.stdout.buffer.flush()")?;
+    match uses_channels {
+        true => emit_channels(out, eof_value)?,
+        false => {
+            writeln!(out, "    byte = sys.stdin.buffer.read(1)")?;
+            writeln!(out, "    return byte[0] if byte else {}", eof_value)?;
+        }
+    }
+    writeln!(out)?;
+    writeln!(out)?;
+    if uses_bits {
+        emit_bit_io(out)?;
+    }
+    if uses(&[
+        Instruction::OpenFile,
+        Instruction::ReadFile,
+        Instruction::WriteFile,
+    ]) {
+        emit_file_io(out, eof_value)?;
+    }
+    if uses(&[Instruction::Random]) {
+        emit_random(out, tm.rng.state)?;
+    }
+    if uses(&[Instruction::Eval]) {
+        emit_eval(out)?;
+    }
+    emit_hoisted(out, nodes)?;
+    if uses_bits {
+        writeln!(out, "flush_bits()")?;
+    }
+    writeln!(out, "sys.stdout.buffer.flush()")?;
+    Ok(())
+}
+
+///writes the helpers boolfuck needs to read and write one bit at a time. bits are packed into
+///bytes starting with the lowest one, and input that has run out reads as 0 bits
+fn emit_bit_io(out: &mut String) -> fmt::Result {
+    for line in [
+        "bits_in = []",
+        "bits_out = []",
+        "",
+        "",
+        "def read_bit():",
+        "    if not bits_in:",
+        "        sys.stdout.buffer.flush()",
+        "        byte = sys.stdin.buffer.read(1)",
+        "        bits_in.extend((byte[0] >> i) & 1 if byte else 0 for i in range(8))",
+        "    return bits_in.pop(0)",
+        "",
+        "",
+        "def write_bit(bit):",
+        "    bits_out.append(bit)",
+        "    if len(bits_out) == 8:",
+        "        flush_bits()",
+        "",
+        "",
+        "def flush_bits():",
+        "    if bits_out:",
+        "        sys.stdout.buffer.write(bytes([sum(bit << i for i, bit in enumerate(bits_out))]))",
+        "        bits_out.clear()",
+        "",
+        "",
+    ] {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+///writes the helpers brainfuck++ needs to work with files. the file name is the text on the tape
+///up to the next 0, and the end of the file is handled like the end of the input
+fn emit_file_io(out: &mut String, eof_value: &str) -> fmt::Result {
+    writeln!(out, "file = None")?;
+    writeln!(out)?;
+    writeln!(out)?;
+    writeln!(out, "def open_file():")?;
+    writeln!(out, "    name = tape[p:tape.index(0, p)].decode()")?;
+    writeln!(out, "    if file:")?;
+    writeln!(out, "        file.close()")?;
+    writeln!(
+        out,
+        "    return open(name, 'r+b' if os.path.exists(name) else 'w+b')"
+    )?;
+    writeln!(out)?;
+    writeln!(out)?;
+    writeln!(out, "def read_file(cell):")?;
+    writeln!(out, "    byte = file.read(1)")?;
+    writeln!(out, "    return byte[0] if byte else {}", eof_value)?;
+    writeln!(out)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+///writes the rest of read for bf-channels, which reads from the channel that's switched to.
+///the channels are given to the python program as its arguments, the same way as to bfint's
+///--channel, like "0=file:a.bin 1=str:hello 2=stdin", and the eof behaviors in them
+fn emit_channels(out: &mut String, eof_value: &str) -> fmt::Result {
+    for line in [
+        "    source, eof = channels.get(channel, (sys.stdin.buffer if channel == 0 else None, None))",
+        "    if source is None:",
+        "        sys.exit(f\"reading from channel {channel}, which wasn't given any input\")",
+        "    byte = source.read(1) if channel not in ended else b''",
+        "    if byte:",
+        "        return byte[0]",
+        "    ended.add(channel)",
+    ] {
+        writeln!(out, "{}", line)?;
+    }
+    writeln!(out, "    return eof(cell) if eof else {}", eof_value)?;
+    for line in [
+        "",
+        "",
+        "channel = 0",
+        "channels = {}",
+        "ended = set()",
+        "EOF = {'zero': lambda cell: 0, 'max': lambda cell: 255, 'unchanged': lambda cell: cell}",
+        "for spec in sys.argv[1:]:",
+        "    number, _, spec = spec.partition('=')",
+        "    spec, _, eof = spec.rpartition(',eof=') if ',eof=' in spec else (spec, '', '')",
+        "    if spec.startswith('file:'):",
+        "        source = open(spec[5:], 'rb')",
+        "    elif spec.startswith('str:'):",
+        "        source = io.BytesIO(spec[4:].encode())",
+        "    else:",
+        "        source = sys.stdin.buffer",
+        "    channels[int(number)] = (source, EOF.get(eof))",
+    ] {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+///writes the same random number generator the interpreter uses, starting from the same state, so
+///a program run with a seed prints the same bytes either way
+fn emit_random(out: &mut String, state: u64) -> fmt::Result {
+    writeln!(out, "rng_state = {}", state)?;
+    for line in [
+        "",
+        "",
+        "def random_byte():",
+        "    global rng_state",
+        "    rng_state = (rng_state + 0x9E3779B97F4A7C15) % 2**64",
+        "    z = rng_state",
+        "    z = ((z ^ (z >> 30)) * 0xBF58476D1CE4E5B9) % 2**64",
+        "    z = ((z ^ (z >> 27)) * 0x94D049BB133111EB) % 2**64",
+        "    return (z ^ (z >> 31)) & 255",
+        "",
+        "",
+    ] {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+///writes a small brainfuck interpreter for "%" to run the text on the tape with
+fn emit_eval(out: &mut String) -> fmt::Result {
+    for line in [
+        "def evaluate():",
+        "    global p",
+        "    code = tape[p:tape.index(0, p)].decode()",
+        "    jumps, opened = {}, []",
+        "    for i, c in enumerate(code):",
+        "        if c == '[':",
+        "            opened.append(i)",
+        "        elif c == ']':",
+        "            j = opened.pop()",
+        "            jumps[i], jumps[j] = j, i",
+        "    pc = 0",
+        "    while pc < len(code):",
+        "        c = code[pc]",
+        "        if c == '>':",
+        "            p += 1",
+        "        elif c == '<':",
+        "            p -= 1",
+        "        elif c == '+':",
+        "            tape[p] = (tape[p] + 1) % 256",
+        "        elif c == '-':",
+        "            tape[p] = (tape[p] - 1) % 256",
+        "        elif c == '.':",
+        "            sys.stdout.buffer.write(tape[p:p + 1])",
+        "        elif c == ',':",
+        "            tape[p] = read(tape[p])",
+        "        elif c == '[' and tape[p] == 0 or c == ']' and tape[p] != 0:",
+        "            pc = jumps[pc]",
+        "        elif c == '%':",
+        "            evaluate()",
+        "        pc += 1",
+        "",
+        "",
+    ] {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+///writes the statements for the program, and before them the loops and procedures that are too
+///deep to be written where they are, as functions of their own. calling them goes one function
+///deeper for every MAX_INDENT loops, so when that's deeper than python lets a program go, the
+///program says how deep it needs
+fn emit_hoisted(out: &mut String, nodes: &[Node]) -> fmt::Result {
+    let mut program = String::new();
+    let mut hoisted = vec![];
+    emit_body(&mut program, nodes, 0, 0, &mut hoisted)?;
+    //writing a function can find more to hoist, which is written after it
+    let mut next = 0;
+    let mut deepest = 0;
+    while let Some(&(node, calls)) = hoisted.get(next) {
+        next += 1;
+        deepest = deepest.max(calls);
+        match node {
+            Node::Loop(_, position) => {
+                writeln!(
+                    out,
+                    "def {}():  # {}",
+                    function_name("loop", position),
+                    position
+                )?;
+                //a loop can be anywhere, so every global any instruction sets is said to be one
+                writeln!(out, "    global p, storage, file, channel")?;
+                emit_body(out, core::slice::from_ref(node), 1, calls, &mut hoisted)?;
+            }
+            Node::Procedure(body, position) => {
+                let name = function_name("procedure", position);
+                writeln!(out, "def {}():  # {}", name, position)?;
+                writeln!(out, "    global p")?;
+                emit_body(out, body, 1, calls, &mut hoisted)?;
+            }
+            _ => unreachable!("only loops and procedures are hoisted"),
+        }
+        writeln!(out)?;
+        writeln!(out)?;
+    }
+    //python's own limit is 1000 calls deep, and the program itself is one of them
+    if deepest >= 900 {
+        writeln!(out, "sys.setrecursionlimit({})", deepest + 1000)?;
+    }
+    out.push_str(&program);
+    Ok(())
+}
+
+///how many levels loops and procedures are indented before the ones inside them are written as
+///functions of their own. python won't take more than 20 loops inside each other in a function,
+///or more than 100 levels of indentation
+const MAX_INDENT: usize = 16;
+
+///the name of the function for a loop or procedure, which is unique and easy to find in the
+///source
+fn function_name(kind: &str, position: &Position) -> String {
+    format!("{}_{}_{}", kind, position.line, position.column)
+}
+
+///writes the statements for a list of nodes at the given loop depth. the loops and procedures
+///inside are gone through with a stack of the bodies being written instead of calling this
+///again, so it goes as deep as they do, and the ones at MAX_INDENT go in hoisted instead, with
+///how many calls deep they'll run, for emit_hoisted to write
+fn emit_body<'a>(
+    out: &mut String,
+    nodes: &'a [Node],
+    depth: usize,
+    calls: usize,
+    hoisted: &mut Vec<(&'a Node, usize)>,
+) -> fmt::Result {
+    //every body being written, the innermost last, with its depth and a line that goes after it
+    let mut bodies: Vec<(core::slice::Iter<Node>, usize, Option<String>)> =
+        vec![(nodes.iter(), depth, None)];
+    while let Some((body, depth, _)) = bodies.last_mut() {
+        let depth = *depth;
+        let Some(node) = body.next() else {
+            if let Some((_, _, Some(after))) = bodies.pop() {
+                writeln!(out, "{}", after)?;
+            }
+            continue;
+        };
+        let indent = "    ".repeat(depth);
+        match node {
+            Node::Add(0, _) | Node::Move(0, _) => {}
+            Node::Add(amount, position) => {
+                let (sign, amount) = if *amount < 0 {
+                    ('-', -amount)
+                } else {
+                    ('+', *amount)
+                };
+                writeln!(
+                    out,
+                    "{}tape[p] = (tape[p] {} {}) % 256  # {}",
+                    indent, sign, amount, position
+                )?;
+            }
+            Node::Move(amount, position) => {
+                let (sign, amount) = if *amount < 0 {
+                    ('-', -amount)
+                } else {
+                    ('+', *amount)
+                };
+                writeln!(out, "{}p {}= {}  # {}", indent, sign, amount, position)?;
+            }
+            Node::Output(position) => {
+                writeln!(
+                    out,
+                    "{}sys.stdout.buffer.write(tape[p:p + 1])  # {}",
+                    indent, position
+                )?;
+            }
+            Node::Input(position) => {
+                writeln!(out, "{}tape[p] = read(tape[p])  # {}", indent, position)?;
+            }
+            Node::Loop(_, position) if depth >= MAX_INDENT => {
+                let name = function_name("loop", position);
+                writeln!(out, "{}{}()  # {}", indent, name, position)?;
+                hoisted.push((node, calls + 1));
+            }
+            Node::Procedure(_, position) if depth >= MAX_INDENT => {
+                let name = function_name("procedure", position);
+                writeln!(
+                    out,
+                    "{}procedures[tape[p]] = {}  # {}",
+                    indent, name, position
+                )?;
+                hoisted.push((node, calls));
+            }
+            Node::Loop(body, position) => {
+                writeln!(out, "{}while tape[p] != 0:  # {}", indent, position)?;
+                //python doesn't allow an empty block, and a body that folded down to nothing
+                //still has to spin forever just like the brainfuck would
+                if body
+                    .iter()
+                    .all(|node| matches!(node, Node::Add(0, _) | Node::Move(0, _)))
+                {
+                    writeln!(out, "{}    pass", indent)?;
+                }
+                bodies.push((body.iter(), depth + 1, None));
+            }
+            Node::Procedure(body, position) => {
+                let name = function_name("procedure", position);
+                writeln!(out, "{}def {}():  # {}", indent, name, position)?;
+                writeln!(out, "{}    global p", indent)?;
+                let after = format!("{}procedures[tape[p]] = {}", indent, name);
+                bodies.push((body.iter(), depth + 1, Some(after)));
+            }
+            Node::Call(position) => {
+                writeln!(out, "{}procedures[tape[p]]()  # {}", indent, position)?;
+            }
+            Node::Fork(position) => {
+                //a real process fork copies the tape just like brainfork does, the operating
+                //system decides who runs when instead of taking turns though
+                writeln!(out, "{}sys.stdout.buffer.flush()  # {}", indent, position)?;
+                writeln!(out, "{}if os.fork() == 0:", indent)?;
+                writeln!(out, "{}    p += 1", indent)?;
+                writeln!(out, "{}    tape[p] = 1", indent)?;
+                writeln!(out, "{}else:", indent)?;
+                writeln!(out, "{}    tape[p] = 0", indent)?;
+            }
+            Node::Extended(Instruction::Halt, position) => {
+                writeln!(out, "{}sys.stdout.buffer.flush()  # {}", indent, position)?;
+                writeln!(out, "{}sys.exit()", indent)?;
+            }
+            Node::Extended(Instruction::Custom(_), position) => {
+                //the closure behind a custom instruction is rust code, there's nothing to
+                //translate it into
+                writeln!(
+                    out,
+                    "{}raise NotImplementedError('custom instruction')  # {}",
+                    indent, position
+                )?;
+            }
+            Node::Extended(instruction, position) => {
+                let statement = match instruction {
+                    Instruction::DebugDump => {
+                        "print('pointer', p, 'cells', list(tape[:max(p, 9) + 1]), file=sys.stderr)"
+                    }
+                    Instruction::Store => "storage = tape[p]",
+                    Instruction::Restore => "tape[p] = storage",
+                    Instruction::ShiftRight => "tape[p] >>= 1",
+                    Instruction::ShiftLeft => "tape[p] = (tape[p] << 1) % 256",
+                    Instruction::Not => "tape[p] ^= 255",
+                    Instruction::Xor => "tape[p] ^= storage",
+                    Instruction::And => "tape[p] &= storage",
+                    Instruction::Or => "tape[p] |= storage",
+                    Instruction::FlipBit => "tape[p] ^= 1",
+                    Instruction::ReadBit => "tape[p] = read_bit()",
+                    Instruction::WriteBit => "write_bit(tape[p])",
+                    Instruction::OpenFile => "file = open_file()",
+                    Instruction::ReadFile => "tape[p] = read_file(tape[p])",
+                    Instruction::WriteFile => "file.write(tape[p:p + 1])",
+                    Instruction::Eval => "evaluate()",
+                    Instruction::Random => "tape[p] = random_byte()",
+                    Instruction::SwitchChannel => "channel = tape[p]",
+                    _ => unreachable!("not an extended type I instruction: {:?}", instruction),
+                };
+                writeln!(out, "{}{}  # {}", indent, statement, position)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! a full screen view of a running program in the terminal: the source with the instruction
+//! that runs next lit up, the tape around the pointer, what the program has printed, and how fast
+//! it's going. it only uses the same public parts of the machine any other program could use
+use crate::events::Event as MachineEvent;
+use crate::{Position, TuringMachine};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::time::{Duration, Instant};
+
+//the colours the brackets of each level of nesting get, going round when loops nest deeper
+const NESTING_COLORS: [Color; 5] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Blue,
+    Color::LightRed,
+];
+
+///what the view is doing with the machine
+#[derive(PartialEq, Debug, Clone)]
+enum State {
+    ///waiting for a key to step or run
+    Paused,
+    ///running a number of instructions every frame
+    Running,
+    ///running as fast as it can until it reaches a breakpoint
+    ToBreakpoint,
+    ///the program wants a byte of input, and the view is asking for it. once it's typed the
+    ///view goes back to the state it was in before
+    WaitingForInput(Box<State>),
+    ///the program has ended, with the reason why
+    Ended(String),
+}
+
+struct App<'a> {
+    tm: TuringMachine,
+    source: &'a str,
+    output: Vec<u8>,
+    state: State,
+    //how many instructions run every frame while running
+    speed: u64,
+    //the first cell shown on the tape, or nothing to keep the pointer in the middle
+    tape_offset: Option<usize>,
+    //the breakpoints that are set, with the ids the machine gave them
+    breakpoints: Vec<(Position, usize)>,
+    //for working out the speed the program is really running at
+    measured_at: (Instant, u64),
+    steps_per_second: f64,
+}
+
+///shows the program running in the terminal until the user quits
+pub fn run(tm: TuringMachine, source: &str) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new(tm, source).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<'a> App<'a> {
+    fn new(tm: TuringMachine, source: &'a str) -> Self {
+        App {
+            tm,
+            source,
+            output: vec![],
+            state: State::Paused,
+            speed: 10,
+            tape_offset: None,
+            breakpoints: vec![],
+            measured_at: (Instant::now(), 0),
+            steps_per_second: 0.0,
+        }
+    }
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(Duration::from_millis(16))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.key(key.code, key.modifiers) {
+                        return Ok(());
+                    }
+                }
+            }
+            match self.state {
+                State::Running => self.advance(self.speed),
+                State::ToBreakpoint => self.advance(1_000_000),
+                _ => {}
+            }
+            let (since, steps) = self.measured_at;
+            if since.elapsed() >= Duration::from_millis(500) {
+                let ran = self.tm.steps - steps;
+                self.steps_per_second = ran as f64 / since.elapsed().as_secs_f64();
+                self.measured_at = (Instant::now(), self.tm.steps);
+            }
+        }
+    }
+    ///deals with a key press. gives back false when it's time to quit
+    fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if let State::WaitingForInput(resume) = &self.state {
+            let input = match code {
+                KeyCode::Char('d') if modifiers.contains(KeyModifiers::CONTROL) => None,
+                KeyCode::Esc => None,
+                KeyCode::Enter => Some(b'\n'),
+                KeyCode::Char(c) => Some(c.to_string().as_bytes()[0]),
+                _ => return true,
+            };
+            self.state = *resume.clone();
+            self.tm.provide_input(input);
+            return true;
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') | KeyCode::Char('s') if self.state == State::Paused => {
+                self.state = State::Running;
+                self.advance(1);
+                if self.state == State::Running {
+                    self.state = State::Paused;
+                }
+            }
+            KeyCode::Char('r') => match self.state {
+                State::Paused => self.state = State::Running,
+                State::Running | State::ToBreakpoint => self.state = State::Paused,
+                _ => {}
+            },
+            KeyCode::Char('n') if self.state == State::Paused => self.state = State::ToBreakpoint,
+            KeyCode::Char('+') => self.speed = (self.speed * 10).min(1_000_000),
+            KeyCode::Char('-') => self.speed = (self.speed / 10).max(1),
+            KeyCode::Char('b') => self.toggle_breakpoint(),
+            KeyCode::Left => {
+                let start = self.tape_start(0);
+                self.tape_offset = Some(start.saturating_sub(1));
+            }
+            KeyCode::Right => {
+                let start = self.tape_start(0);
+                self.tape_offset = Some((start + 1).min(self.tm.tape().len() - 1));
+            }
+            KeyCode::Char('f') => self.tape_offset = None,
+            _ => {}
+        }
+        true
+    }
+    ///runs up to this many instructions, for as long as the state says to keep running
+    fn advance(&mut self, instructions: u64) {
+        let mut left = instructions;
+        while left > 0 && matches!(self.state, State::Running | State::ToBreakpoint) {
+            let before = self.tm.steps;
+            let event = self.tm.run_for(left);
+            left = left.saturating_sub(self.tm.steps - before);
+            match event {
+                None => break,
+                Some(MachineEvent::Output(byte)) => self.output.push(byte),
+                Some(MachineEvent::InputNeeded) => {
+                    self.state = State::WaitingForInput(Box::new(self.state.clone()));
+                }
+                Some(MachineEvent::Breakpoint(_)) | Some(MachineEvent::Cancelled) => {
+                    self.state = State::Paused;
+                }
+                Some(MachineEvent::Halted) => self.state = State::Ended("halted".to_string()),
+                Some(MachineEvent::Error(e)) => self.state = State::Ended(e.to_string()),
+            }
+        }
+    }
+    ///puts a breakpoint on the instruction that runs next, or takes it away if there is one
+    fn toggle_breakpoint(&mut self) {
+        let Some(position) = self.tm.position() else {
+            return;
+        };
+        match self.breakpoints.iter().position(|(at, _)| *at == position) {
+            Some(index) => {
+                let (_, id) = self.breakpoints.remove(index);
+                self.tm.remove_breakpoint(id);
+            }
+            None => {
+                if let Some(id) = self.tm.add_breakpoint(position) {
+                    self.breakpoints.push((position, id));
+                }
+            }
+        }
+    }
+    ///the first cell to show on a tape panel this many rows high
+    fn tape_start(&self, rows: usize) -> usize {
+        self.tape_offset
+            .unwrap_or_else(|| self.tm.pointer().saturating_sub(rows / 2))
+    }
+    fn draw(&self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(5),
+                Constraint::Length(8),
+                Constraint::Length(1),
+            ])
+            .split(frame.area());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+            .split(rows[0]);
+        self.draw_source(frame, columns[0]);
+        self.draw_tape(frame, columns[1]);
+        let output = String::from_utf8_lossy(&self.output);
+        let lines = output.lines().count() as u16;
+        let scroll = lines.saturating_sub(rows[1].height.saturating_sub(2));
+        frame.render_widget(
+            Paragraph::new(output.to_string())
+                .block(Block::default().borders(Borders::ALL).title("output"))
+                .wrap(Wrap { trim: false })
+                .scroll((scroll, 0)),
+            rows[1],
+        );
+        let state = match &self.state {
+            State::Paused => "paused".to_string(),
+            State::Running => "running".to_string(),
+            State::ToBreakpoint => "running to a breakpoint".to_string(),
+            State::WaitingForInput(_) => "type a key for \",\" (esc for end of input)".to_string(),
+            State::Ended(reason) => reason.clone(),
+        };
+        let status = format!(
+            " {} | {} steps | {:.0} steps/s | {} per frame | space step, r run, n next breakpoint, \
+             b breakpoint, +/- speed, arrows/f tape, q quit",
+            state, self.tm.steps, self.steps_per_second, self.speed
+        );
+        frame.render_widget(
+            Paragraph::new(status).style(Style::default().add_modifier(Modifier::REVERSED)),
+            rows[2],
+        );
+    }
+    ///the source, with the instruction that runs next lit up, breakpoints in red, and every
+    ///pair of brackets coloured by how deep it's nested
+    fn draw_source(&self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let current = self.tm.position();
+        let mut depth = 0;
+        let mut lines = vec![];
+        for (line_index, line) in self.source.lines().enumerate() {
+            let mut spans = vec![];
+            for (column_index, c) in line.chars().enumerate() {
+                let position = Position {
+                    line: line_index + 1,
+                    column: column_index + 1,
+                };
+                let mut style = Style::default();
+                match c {
+                    '[' => {
+                        style = style.fg(NESTING_COLORS[depth % NESTING_COLORS.len()]);
+                        depth += 1;
+                    }
+                    ']' => {
+                        depth = depth.saturating_sub(1);
+                        style = style.fg(NESTING_COLORS[depth % NESTING_COLORS.len()]);
+                    }
+                    _ => {}
+                }
+                if self.breakpoints.iter().any(|(at, _)| *at == position) {
+                    style = style.bg(Color::Red);
+                }
+                if current == Some(position) {
+                    style = style.bg(Color::Yellow).fg(Color::Black);
+                }
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            lines.push(Line::from(spans));
+        }
+        let middle = current.map_or(0, |position| position.line) as u16;
+        let scroll = middle.saturating_sub(area.height / 2);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title("source"))
+                .scroll((scroll, 0)),
+            area,
+        );
+    }
+    ///the cells around the pointer, one a row, with the pointer's cell lit up
+    fn draw_tape(&self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let rows = area.height.saturating_sub(2) as usize;
+        let start = self.tape_start(rows);
+        let tape = self.tm.tape();
+        let lines: Vec<Line> = (start..(start + rows).min(tape.len()))
+            .map(|index| {
+                let cell = tape[index];
+                let shown = match cell {
+                    32..=126 => (cell as char).to_string(),
+                    _ => String::new(),
+                };
+                let value = self.tm.cell_signedness.value(cell);
+                let text = format!("{:>5} {:>4} {}", index, value, shown);
+                match index == self.tm.pointer() {
+                    true => Line::styled(text, Style::default().bg(Color::Yellow).fg(Color::Black)),
+                    false => Line::raw(text),
+                }
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("tape")),
+            area,
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! warnings about what a program is doing while it runs, with "--runtime-warnings". some bugs
+//! don't make a program fail, it just goes on doing something pointless for millions of steps,
+//! and these notice the usual ones without stopping the run:
+//!
+//! - oscillation: a cell going back and forth between two values with nothing else on the tape
+//!   changing, like a loop that undoes what it does
+//! - ping-pong: the pointer only ever at the same two cells
+//! - repeated-output: the last few thousand bytes written being one short block over and over
+//!
+//! it's looked at on the same steps the cycle detector looks at the state, or every
+//! SAMPLE_EVERY steps when it isn't on, and a detector only speaks up once the last SAMPLES
+//! looks all agree. a hash of the tape is all that's kept of most of them, along with the tape
+//! of the last two that were different, to say which cells went back and forth. a loop whose
+//! length goes evenly into the steps between looks is seen at the same point every time, so
+//! with --detect-cycles every 1000 a loop of 4 instructions looks like it stands still. and
+//! it's the program as optimized that's looked at, where ">+<" doesn't move the pointer at all.
+//!
+//! every detector warns once when what it found starts, and not again until it has stopped and
+//! SAMPLES more looks have gone by, and at most LIMIT times in a run, so none of them can fill
+//! the screen. like the cycle detector, nothing is looked at while "%" runs a program of its
+//! own, or with big cells
+use crate::shared::{lock, shared, Shared};
+use crate::{Position, TuringMachine};
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+///how many steps go by between looks, when the cycle detector isn't deciding it. it's prime so
+///it doesn't line up with the loops of a program
+pub const SAMPLE_EVERY: u64 = 10_007;
+///how many looks in a row have to agree before a detector warns
+const SAMPLES: usize = 100;
+///how many of the last bytes written are looked at for a block that repeats
+const OUTPUT_WINDOW: usize = 4096;
+///how many times the block has to repeat in them
+const REPEATS: usize = 8;
+///how many times each detector warns in a run at most
+const LIMIT: u32 = 3;
+
+///what a warning can be about
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Detector {
+    Oscillation,
+    PingPong,
+    RepeatedOutput,
+}
+
+impl Detector {
+    pub const ALL: [Detector; 3] = [
+        Detector::Oscillation,
+        Detector::PingPong,
+        Detector::RepeatedOutput,
+    ];
+    ///the name used on the command line and in json
+    pub fn name(&self) -> &'static str {
+        match self {
+            Detector::Oscillation => "oscillation",
+            Detector::PingPong => "ping-pong",
+            Detector::RepeatedOutput => "repeated-output",
+        }
+    }
+    ///turns the name used on the command line into the matching detector
+    pub fn from_name(name: &str) -> Option<Self> {
+        Detector::ALL
+            .into_iter()
+            .find(|detector| detector.name() == name)
+    }
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+///something a detector noticed
+#[derive(PartialEq, Debug, Clone)]
+pub struct RuntimeWarning {
+    pub detector: Detector,
+    ///the step it was noticed at
+    pub step: u64,
+    ///where the program was then, or for repeated-output, the last "." that wrote something
+    pub position: Position,
+    pub message: String,
+}
+
+impl fmt::Display for RuntimeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "step {} at {}: {} ({})",
+            self.step,
+            self.position,
+            self.message,
+            self.detector.name()
+        )
+    }
+}
+
+///the warnings callback, shared between clones of a machine
+pub(crate) type Callback = Shared<dyn FnMut(&RuntimeWarning) + Send>;
+
+///one look at the machine
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    step: u64,
+    pointer: usize,
+    tape: u64,
+}
+
+///whether a detector has found something lately
+#[derive(Debug, Clone, Copy, Default)]
+struct Episode {
+    //whether what it found is still going on
+    going: bool,
+    //how many looks it has to wait before it can warn again
+    quiet: usize,
+    warned: u32,
+}
+
+///what the machine keeps for the detectors
+#[derive(Clone)]
+pub(crate) struct Watcher {
+    detectors: Vec<Detector>,
+    callback: Callback,
+    next_sample: u64,
+    //the last looks, oldest first
+    samples: VecDeque<Sample>,
+    //the last two tapes that were different, with their hashes, so the cells that went back and
+    //forth can be told apart
+    tapes: Vec<(u64, Vec<u8>)>,
+    //the last bytes written, whether any were written since the last look, and where the last
+    //one was written from
+    output: VecDeque<u8>,
+    wrote: bool,
+    wrote_at: Option<Position>,
+    episodes: [Episode; 3],
+}
+
+impl TuringMachine {
+    ///makes the machine call the callback whenever one of the detectors notices something, see
+    ///the top of this file, or stops that with no detectors. the run carries on either way
+    pub fn set_runtime_warnings(
+        &mut self,
+        detectors: &[Detector],
+        callback: impl FnMut(&RuntimeWarning) + Send + 'static,
+    ) {
+        self.warnings = match detectors.is_empty() {
+            true => None,
+            false => Some(Watcher {
+                detectors: detectors.to_vec(),
+                callback: shared(callback),
+                next_sample: self.steps,
+                samples: VecDeque::new(),
+                tapes: vec![],
+                output: VecDeque::new(),
+                wrote: false,
+                wrote_at: None,
+                episodes: Default::default(),
+            }),
+        };
+    }
+    ///keeps the byte for repeated-output
+    pub(crate) fn watch_output(&mut self, byte: u8) {
+        let position = self.position_or_last();
+        if let Some(watcher) = &mut self.warnings {
+            if watcher.output.len() == OUTPUT_WINDOW {
+                watcher.output.pop_front();
+            }
+            watcher.output.push_back(byte);
+            watcher.wrote = true;
+            watcher.wrote_at = Some(position);
+        }
+    }
+    ///looks at the machine when it's time to, and warns about whatever the detectors find
+    pub(crate) fn check_warnings(&mut self) {
+        let Some(watcher) = &self.warnings else {
+            return;
+        };
+        if self.steps < watcher.next_sample || self.eval_depth > 0 {
+            return;
+        }
+        #[cfg(feature = "bigint")]
+        if self.big_cells.is_some() {
+            return;
+        }
+        let every = self
+            .cycles
+            .as_ref()
+            .map_or(SAMPLE_EVERY, |cycles| cycles.every);
+        let sample = Sample {
+            step: self.steps,
+            pointer: self.pointer,
+            tape: crate::program::fnv1a_bytes(self.used_tape().iter().copied()),
+        };
+        let position = self.position_or_last();
+        let new_tape = (!watcher.tapes.iter().any(|(hash, _)| *hash == sample.tape))
+            .then(|| self.used_tape().to_vec());
+        let watcher = self.warnings.as_mut().expect("checked above");
+        watcher.next_sample = sample.step + every;
+        if let Some(tape) = new_tape {
+            if watcher.tapes.len() == 2 {
+                watcher.tapes.remove(0);
+            }
+            watcher.tapes.push((sample.tape, tape));
+        }
+        if watcher.samples.len() == SAMPLES {
+            watcher.samples.pop_front();
+        }
+        watcher.samples.push_back(sample);
+        let mut found = vec![];
+        for detector in watcher.detectors.clone() {
+            let message = match detector {
+                Detector::Oscillation => self.oscillation(),
+                Detector::PingPong => self.ping_pong(),
+                Detector::RepeatedOutput => self.repeated_output(),
+            };
+            let watcher = self.warnings.as_mut().expect("checked above");
+            let episode = &mut watcher.episodes[detector.index()];
+            episode.quiet = episode.quiet.saturating_sub(1);
+            let Some(message) = message else {
+                episode.going = false;
+                continue;
+            };
+            if episode.going || episode.quiet > 0 || episode.warned == LIMIT {
+                continue;
+            }
+            episode.going = true;
+            episode.quiet = SAMPLES;
+            episode.warned += 1;
+            let message = match episode.warned == LIMIT {
+                true => format!("{}, and it won't be warned about again", message),
+                false => message,
+            };
+            found.push(RuntimeWarning {
+                detector,
+                step: sample.step,
+                position: match detector {
+                    Detector::RepeatedOutput => watcher.wrote_at.unwrap_or(position),
+                    _ => position,
+                },
+                message,
+            });
+        }
+        let watcher = self.warnings.as_mut().expect("checked above");
+        watcher.wrote = false;
+        let callback = Shared::clone(&watcher.callback);
+        for warning in &found {
+            (lock(&callback))(warning);
+        }
+    }
+    ///the two values that show up among the last looks, each of them often, or nothing when
+    ///there isn't a full set of looks or there are more or fewer than two
+    fn two_of<T: PartialEq + Copy>(
+        samples: &VecDeque<Sample>,
+        value: fn(&Sample) -> T,
+    ) -> Option<(T, T)> {
+        if samples.len() < SAMPLES {
+            return None;
+        }
+        let first = value(&samples[0]);
+        let second = samples.iter().map(value).find(|other| *other != first)?;
+        let mut counts = (0, 0);
+        for sample in samples {
+            match value(sample) {
+                one if one == first => counts.0 += 1,
+                two if two == second => counts.1 += 1,
+                _ => return None,
+            }
+        }
+        (counts.0.min(counts.1) >= SAMPLES / 8).then_some((first, second))
+    }
+    fn oscillation(&self) -> Option<String> {
+        let watcher = self.warnings.as_ref()?;
+        let (first, second) = Self::two_of(&watcher.samples, |sample| sample.tape)?;
+        let tape = |hash| {
+            watcher
+                .tapes
+                .iter()
+                .find(|(other, _)| *other == hash)
+                .map(|(_, tape)| tape)
+        };
+        let (first, second) = (tape(first)?, tape(second)?);
+        let cell = |tape: &Vec<u8>, index| tape.get(index).copied().unwrap_or(0);
+        let cells = (0..first.len().max(second.len()))
+            .filter(|index| cell(first, *index) != cell(second, *index))
+            .collect::<Vec<_>>();
+        let since = watcher.samples[0].step;
+        Some(match cells.as_slice() {
+            [index] => format!(
+                "cell {} has been going back and forth between {} and {} since step {}, with \
+                 nothing else on the tape changing",
+                index,
+                self.cell_signedness.value(cell(first, *index)),
+                self.cell_signedness.value(cell(second, *index)),
+                since
+            ),
+            _ => format!(
+                "cells {} have been going back and forth between the same two values since step \
+                 {}, with nothing else on the tape changing",
+                list(&cells),
+                since
+            ),
+        })
+    }
+    fn ping_pong(&self) -> Option<String> {
+        let watcher = self.warnings.as_ref()?;
+        let (first, second) = Self::two_of(&watcher.samples, |sample| sample.pointer)?;
+        Some(format!(
+            "the pointer has only been at cells {} and {} since step {}",
+            first.min(second),
+            first.max(second),
+            watcher.samples[0].step
+        ))
+    }
+    fn repeated_output(&self) -> Option<String> {
+        let watcher = self.warnings.as_ref()?;
+        if !watcher.wrote || watcher.output.len() < OUTPUT_WINDOW {
+            return None;
+        }
+        let output = watcher.output.iter().copied().collect::<Vec<_>>();
+        let period = period(&output);
+        if period > OUTPUT_WINDOW / REPEATS {
+            return None;
+        }
+        let block = output[..period.min(32)]
+            .iter()
+            .flat_map(|byte| core::ascii::escape_default(*byte))
+            .map(char::from)
+            .collect::<String>();
+        Some(format!(
+            "the last {} bytes written are the same {} over and over: \"{}{}\"",
+            OUTPUT_WINDOW,
+            match period {
+                1 => String::from("byte"),
+                _ => format!("{} bytes", period),
+            },
+            block,
+            match period > 32 {
+                true => "...",
+                false => "",
+            }
+        ))
+    }
+}
+
+///the length of the shortest block the bytes are made of over and over, the last time maybe cut
+///short, found with the prefix function
+fn period(bytes: &[u8]) -> usize {
+    let mut prefix = vec![0; bytes.len()];
+    for index in 1..bytes.len() {
+        let mut length = prefix[index - 1];
+        while length > 0 && bytes[index] != bytes[length] {
+            length = prefix[length - 1];
+        }
+        if bytes[index] == bytes[length] {
+            length += 1;
+        }
+        prefix[index] = length;
+    }
+    bytes.len() - prefix.last().copied().unwrap_or(0)
+}
+
+///"1, 2 and 3"
+fn list(cells: &[usize]) -> String {
+    match cells {
+        [] => String::new(),
+        [only] => format!("{}", only),
+        [rest @ .., last] => format!(
+            "{} and {}",
+            rest.iter()
+                .map(|cell| format!("{}", cell))
+                .collect::<Vec<_>>()
+                .join(", "),
+            last
+        ),
+    }
+}
@@ -0,0 +1,99 @@
+//! the interpreter for the web, built with `--features wasm` for wasm32-unknown-unknown, as a
+//! shared library with `cargo rustc --crate-type cdylib`. nothing
+//! in here touches stdin or stdout, input is handed over as bytes and output is collected into
+//! bytes that get handed back to javascript
+use crate::lang::Lang;
+use crate::shared::lock;
+use crate::TuringMachine;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+
+///turns every panic into a javascript error carrying the panic message, instead of the wasm
+///module just trapping with "unreachable"
+#[wasm_bindgen(start)]
+pub fn start() {
+    std::panic::set_hook(Box::new(|info| wasm_bindgen::throw_str(&info.to_string())));
+}
+
+///makes a machine for the program that reads from "input" and writes into a buffer of its own
+fn machine(program: &str, input: &[u8]) -> Result<WasmMachine, String> {
+    let mut tm = TuringMachine::new(program, &Lang::Brainfuck).map_err(|e| e.to_string())?;
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    Ok(WasmMachine { tm, output })
+}
+
+///runs a whole program in one go. the result is an object with the bytes the program wrote in
+///"output", the number of instructions it ran in "steps", and "error", which is null unless
+///something went wrong
+#[wasm_bindgen]
+pub fn run(program: &str, input: &[u8], max_steps: u64) -> JsValue {
+    let (output, steps, error) = match machine(program, input) {
+        Ok(mut machine) => {
+            machine.tm.step_limit = Some(max_steps);
+            let error = machine.tm.run().err().map(|e| e.to_string());
+            (machine.take_output(), machine.tm.steps, error)
+        }
+        Err(error) => (vec![], 0, Some(error)),
+    };
+    let result = js_sys::Object::new();
+    let set = |key: &str, value: JsValue| {
+        js_sys::Reflect::set(&result, &key.into(), &value).expect("result is a plain object");
+    };
+    set("output", js_sys::Uint8Array::from(&output[..]).into());
+    set("steps", JsValue::from_f64(steps as f64));
+    set("error", error.map(JsValue::from).unwrap_or(JsValue::NULL));
+    result.into()
+}
+
+///a machine that javascript can run a few steps at a time, to show what it is doing
+#[wasm_bindgen]
+pub struct WasmMachine {
+    tm: TuringMachine,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+#[wasm_bindgen]
+impl WasmMachine {
+    ///loads a program, ready to run, with all the input it will ever get
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: &str, input: &[u8]) -> Result<WasmMachine, JsError> {
+        machine(program, input).map_err(|e| JsError::new(&e))
+    }
+    ///runs at most "n" instructions, stopping early when the program ends. gives back whether
+    ///the program still has instructions left to run
+    pub fn step(&mut self, n: u32) -> Result<bool, JsError> {
+        for _ in 0..n {
+            if !self.tm.has_instructions_left() {
+                break;
+            }
+            self.tm
+                .perform_next_instruction()
+                .map_err(|e| JsError::new(&e.to_string()))?;
+        }
+        if !self.tm.has_instructions_left() {
+            self.tm.finish().map_err(|e| JsError::new(&e.to_string()))?;
+        }
+        Ok(self.tm.has_instructions_left())
+    }
+    ///the cells from "start" on, at most "length" of them, for drawing part of the tape
+    pub fn tape_window(&self, start: usize, length: usize) -> Vec<u8> {
+        let start = start.min(self.tm.tape.len());
+        let end = start.saturating_add(length).min(self.tm.tape.len());
+        self.tm.tape[start..end].to_vec()
+    }
+    ///where the pointer is on the tape
+    pub fn pointer(&self) -> usize {
+        self.tm.pointer
+    }
+    ///how many instructions have been run so far
+    pub fn steps(&self) -> f64 {
+        self.tm.steps as f64
+    }
+    ///the bytes the program has written since the last time this was called
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut *lock(&self.output))
+    }
+}
@@ -0,0 +1,107 @@
+//! watching what a program writes for a pattern, to stop it the moment it writes something it
+//! shouldn't. bytes are fed in one at a time as the "."s run, and only the last few are kept, so
+//! watching costs the same however much the program prints
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+///how many bytes of output are kept to show around a match, besides the pattern itself
+const CONTEXT: usize = 32;
+
+///a pattern being looked for in the output, with the last bytes written
+#[derive(PartialEq, Debug, Clone)]
+pub struct OutputWatch {
+    pattern: Vec<u8>,
+    recent: VecDeque<u8>,
+}
+
+impl OutputWatch {
+    ///a watch for the pattern, written as text with escapes, see unescape. gives back what's
+    ///wrong with the text if it can't be read
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let pattern = unescape(text)?;
+        if pattern.is_empty() {
+            return Err("the pattern is empty".into());
+        }
+        Ok(OutputWatch {
+            recent: VecDeque::with_capacity(pattern.len() + CONTEXT),
+            pattern,
+        })
+    }
+    ///takes the next byte written, and says whether the pattern ends with it
+    pub fn feed(&mut self, byte: u8) -> bool {
+        if self.recent.len() == self.pattern.len() + CONTEXT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(byte);
+        self.matched()
+    }
+    ///whether the pattern is what was written last
+    pub fn matched(&self) -> bool {
+        self.recent.len() >= self.pattern.len()
+            && self
+                .recent
+                .iter()
+                .rev()
+                .zip(self.pattern.iter().rev())
+                .all(|(written, wanted)| written == wanted)
+    }
+    ///the pattern, escaped so it can be shown
+    pub fn pattern(&self) -> String {
+        escaped(self.pattern.iter().copied())
+    }
+    ///the last bytes written, up to and with the match, escaped so they can be shown
+    pub fn context(&self) -> String {
+        escaped(self.recent.iter().copied())
+    }
+    ///what to say once the pattern has been matched
+    pub fn describe_match(&self) -> String {
+        format!(
+            "the output matched \"{}\", it ended \"{}\"",
+            self.pattern(),
+            self.context()
+        )
+    }
+}
+
+///the bytes written as text, where `\xNN` is the byte with that hex value and `\n`, `\r`, `\t`,
+///`\0` and `\\` are what they are in rust. gives back what's wrong with the text if it can't
+///be read
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut unescaped = vec![];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut bytes = [0; 4];
+            unescaped.extend_from_slice(c.encode_utf8(&mut bytes).as_bytes());
+            continue;
+        }
+        let byte = match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('0') => 0,
+            Some('\\') => b'\\',
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 => byte,
+                    _ => return Err(format!("'\\x{}' isn't a byte like \\x0a", digits)),
+                }
+            }
+            Some(other) => return Err(format!("'\\{}' isn't an escape", other)),
+            None => return Err("the text ends in a lone '\\'".into()),
+        };
+        unescaped.push(byte);
+    }
+    Ok(unescaped)
+}
+
+fn escaped(bytes: impl Iterator<Item = u8>) -> String {
+    bytes
+        .flat_map(core::ascii::escape_default)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,89 @@
+//! who wrote every cell last, with "--track-writers". when a cell holds something surprising the
+//! question is always who put it there, and this keeps the answer: for every cell the program
+//! wrote, the step of the last write and the instruction that did it. it only takes up room for
+//! the cells up to the last one written, and nothing unless it's asked for.
+//!
+//! every instruction that gives the current cell a value counts as writing it, even when the
+//! value stays the same, like a "," at the end of input that leaves the cell as it was. blocks
+//! of moves, "+" and "-" are run an instruction at a time while the writers are kept, like they
+//! are with a history, and loops aren't tiered up, which would skip their writes
+use crate::{program, Instruction, Position};
+use alloc::vec::Vec;
+use core::fmt;
+
+///the last write to a cell
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Write {
+    ///which step it was, counting from 1
+    pub step: u64,
+    pub position: Position,
+    pub instruction: Instruction,
+}
+
+impl fmt::Display for Write {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match program::symbol(self.instruction) {
+            Some(symbol) => write!(
+                f,
+                "last written at step {} by '{}' at {}",
+                self.step, symbol, self.position
+            ),
+            None => write!(
+                f,
+                "last written at step {} by a custom instruction at {}",
+                self.step, self.position
+            ),
+        }
+    }
+}
+
+///the last write to every cell that has been written, by the index of the cell
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Writers {
+    cells: Vec<Option<Write>>,
+}
+
+impl Writers {
+    pub fn new() -> Self {
+        Writers::default()
+    }
+    pub(crate) fn record(&mut self, index: usize, write: Write) {
+        if index >= self.cells.len() {
+            self.cells.resize(index + 1, None);
+        }
+        self.cells[index] = Some(write);
+    }
+    ///the last write to the cell, nothing if it was never written
+    pub fn last(&self, index: usize) -> Option<&Write> {
+        self.cells.get(index)?.as_ref()
+    }
+    ///how many cells there's room for, up to the last one written
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+///whether running the instruction gives the current cell a value
+pub(crate) fn writes_cell(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Increment
+            | Instruction::Decrement
+            | Instruction::Replace
+            | Instruction::FlipBit
+            | Instruction::ReadBit
+            | Instruction::ReadFile
+            | Instruction::Random
+            | Instruction::Fork
+            | Instruction::Restore
+            | Instruction::ShiftRight
+            | Instruction::ShiftLeft
+            | Instruction::Not
+            | Instruction::Xor
+            | Instruction::And
+            | Instruction::Or
+    )
+}
@@ -0,0 +1,82 @@
+//! programs run with AsyncTuringMachine over tokio's in-memory duplex streams, on a task of their
+//! own, and runs dropped halfway that carry on where they left off
+#![cfg(feature = "async")]
+use bfint::async_io::AsyncTuringMachine;
+use bfint::control::RunOutcome;
+use bfint::{lang::Lang, TuringMachine};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+///writes "ab"
+const AB: &str = "++++++++[>++++++++++++<-]>+.+.";
+
+fn machine(
+    program: &str,
+    input: DuplexStream,
+    output: DuplexStream,
+) -> AsyncTuringMachine<DuplexStream, DuplexStream> {
+    let tm = TuringMachine::new(program, &Lang::Brainfuck).expect("it parses");
+    AsyncTuringMachine::new(tm, input, output)
+}
+
+///the run goes on a task of its own, which only works when its future can be sent
+#[tokio::test]
+async fn spawned_echo() {
+    let (mut to_program, input) = tokio::io::duplex(64);
+    let (output, mut from_program) = tokio::io::duplex(64);
+    let mut machine = machine(",[.,]", input, output);
+    let run = tokio::spawn(async move { machine.run().await });
+    to_program.write_all(b"echo").await.expect("it writes");
+    drop(to_program);
+    let mut echoed = vec![];
+    from_program
+        .read_to_end(&mut echoed)
+        .await
+        .expect("it reads");
+    assert_eq!(echoed, b"echo");
+    let outcome = run.await.expect("the task doesn't panic");
+    assert_eq!(outcome, Ok(RunOutcome::Halted));
+}
+
+///with room for one byte, the run waits to write the second until the first has been read. it's
+///dropped while it waits, and the byte still comes out when it's run again
+#[tokio::test]
+async fn dropped_while_writing() {
+    let (_to_program, input) = tokio::io::duplex(1);
+    let (output, mut from_program) = tokio::io::duplex(1);
+    let mut machine = machine(AB, input, output);
+    let dropped = tokio::time::timeout(Duration::from_millis(50), machine.run()).await;
+    assert!(dropped.is_err(), "the run didn't wait to write");
+    let mut byte = [0];
+    from_program.read_exact(&mut byte).await.expect("it reads");
+    assert_eq!(&byte, b"a");
+    let run = tokio::spawn(async move {
+        let outcome = machine.run().await;
+        drop(machine);
+        outcome
+    });
+    let mut rest = vec![];
+    from_program.read_to_end(&mut rest).await.expect("it reads");
+    assert_eq!(rest, b"b");
+    let outcome = run.await.expect("the task doesn't panic");
+    assert_eq!(outcome, Ok(RunOutcome::Halted));
+}
+
+///dropped while it waits for input, the run asks for it again when it's run again
+#[tokio::test]
+async fn dropped_while_reading() {
+    let (mut to_program, input) = tokio::io::duplex(1);
+    let (output, mut from_program) = tokio::io::duplex(64);
+    let mut machine = machine(",.", input, output);
+    let dropped = tokio::time::timeout(Duration::from_millis(50), machine.run()).await;
+    assert!(dropped.is_err(), "the run didn't wait to read");
+    to_program.write_all(b"x").await.expect("it writes");
+    assert_eq!(machine.run().await, Ok(RunOutcome::Halted));
+    drop(machine);
+    let mut written = vec![];
+    from_program
+        .read_to_end(&mut written)
+        .await
+        .expect("it reads");
+    assert_eq!(written, b"x");
+}
@@ -0,0 +1,145 @@
+//! brainfuck++'s file instructions in --lang bf-file, on files in a temporary directory: "#"
+//! opening the file named on the tape, ":" writing to it and ";" reading from it up to its end,
+//! and the errors when there's no file open or it can't be opened. and --sandbox refusing them
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::lang::Lang;
+use bfint::{Position, RuntimeError, TuringMachine};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-bf-file-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///puts the path on the tape from the current cell on, and goes back to its start, so that a "#"
+///after it opens it. the cell after the name is left 0, which ends it
+fn name(path: &Path) -> String {
+    let path = path.to_str().expect("the path is utf-8");
+    let mut source: String = path
+        .bytes()
+        .map(|byte| format!("{}>", "+".repeat(byte as usize)))
+        .collect();
+    source.push_str(&"<".repeat(path.len()));
+    source
+}
+
+///runs the program, giving back how it ended and what it printed
+fn run(source: &str) -> (Result<RunOutcome, RuntimeError>, Vec<u8>) {
+    let mut tm = TuringMachine::new(source, &Lang::BfFile).expect("it parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+    let outcome = tm.run();
+    let output = bfint::shared::lock(&output).clone();
+    (outcome, output)
+}
+
+///writes "hi" to a file that isn't there yet, which "#" makes
+#[test]
+fn writing() {
+    let directory = Directory::new("writing");
+    let path = directory.0.join("out.txt");
+    let far = ">".repeat(path.to_str().expect("the path is utf-8").len() + 1);
+    let source = format!("{}#{}{}:+:", name(&path), far, "+".repeat(b'h' as usize));
+    assert_eq!(run(&source).0, Ok(RunOutcome::Halted));
+    assert_eq!(std::fs::read(&path).expect("it was made"), b"hi");
+}
+
+///reads a file that's there byte by byte, and once it's at the end reads 0 like "," does
+#[test]
+fn reading() {
+    let directory = Directory::new("reading");
+    let path = directory.0.join("in.txt");
+    std::fs::write(&path, "xyz").expect("it can be written");
+    let (outcome, output) = run(&format!("{}#;.;.;.;.", name(&path)));
+    assert_eq!(outcome, Ok(RunOutcome::Halted));
+    assert_eq!(output, b"xyz\0");
+    //opening it doesn't change it
+    assert_eq!(std::fs::read(&path).expect("it's still there"), b"xyz");
+}
+
+#[test]
+fn no_file_open() {
+    assert_eq!(
+        run("+;").0,
+        Err(RuntimeError::NoOpenFile(Position { line: 1, column: 2 }))
+    );
+    assert_eq!(
+        run(":").0,
+        Err(RuntimeError::NoOpenFile(Position { line: 1, column: 1 }))
+    );
+}
+
+///a file in a directory that isn't there can't be made, and the error is at the "#"
+#[test]
+fn not_opened() {
+    let directory = Directory::new("not-opened");
+    let path = directory.0.join("missing").join("out.txt");
+    let source = name(&path) + "#";
+    match run(&source).0 {
+        Err(RuntimeError::FileError(message, position)) => {
+            assert!(message.starts_with("could not open '"), "{}", message);
+            assert_eq!(position.column, source.len());
+        }
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+///"#" is only an instruction in bf-file, it's a comment in brainfuck
+#[test]
+fn off_by_default() {
+    let mut tm = TuringMachine::new("+#;:", &Lang::Brainfuck).expect("it parses");
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+}
+
+///bfint --lang bf-file, which --sandbox refuses before the program opens anything
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let directory = Directory::new("sandbox");
+    let program = directory.0.join("program.b");
+    std::fs::write(&program, name(&directory.0.join("out.txt")) + "#+:").expect("it's written");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--lang", "bf-file"])
+            .args(args)
+            .arg(&program)
+            .output()
+            .expect("bfint starts")
+    };
+    if cfg!(feature = "sandbox") {
+        let refused = run(&["--sandbox"]);
+        assert_eq!(refused.status.code(), Some(2));
+        let said = String::from_utf8_lossy(&refused.stderr);
+        assert!(
+            said.contains("--sandbox can't run bf-file programs, they open files"),
+            "{}",
+            said
+        );
+        assert!(!directory.0.join("out.txt").exists());
+    }
+    assert!(run(&[]).status.success());
+    //the "+" is on the first byte of the name, where "#" left the pointer
+    let first = directory.0.to_str().expect("the path is utf-8").as_bytes()[0];
+    assert_eq!(
+        std::fs::read(directory.0.join("out.txt")).expect("it was made"),
+        [first + 1]
+    );
+}
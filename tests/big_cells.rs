@@ -0,0 +1,127 @@
+//! cells with no upper limit checked against bytes that wrap around. a program that never takes a
+//! cell past 255 or below 0 can't tell the two apart, so it halts the same way after the same
+//! steps with the same output and the same tape either way. proptest shrinks whatever it finds
+//! down to a small program
+#![cfg(all(feature = "std", feature = "bigint", not(target_arch = "wasm32")))]
+use bfint::big::Underflow;
+use bfint::control::RunOutcome;
+use bfint::{lang::Lang, RuntimeError, TuringMachine};
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+const STEP_LIMIT: u64 = 20_000;
+
+///how a run went, and the tape it left
+#[derive(PartialEq, Debug)]
+struct Ran {
+    outcome: Result<RunOutcome, RuntimeError>,
+    output: Vec<u8>,
+    tape: Vec<u8>,
+    steps: u64,
+}
+
+///runs the program with bytes, or with big cells that stop it when one would go below 0, giving
+///back how it went and, with big cells, the largest value one held
+fn run(source: &str, input: &[u8], big: bool) -> (Ran, Option<BigUint>) {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    if big {
+        tm.use_big_cells(Underflow::Error)
+            .expect("it's plain brainfuck");
+    }
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    tm.step_limit = Some(STEP_LIMIT);
+    let outcome = tm.run();
+    let output = bfint::shared::lock(&output).clone();
+    let largest = tm.big_cells().map(|big_cells| big_cells.largest().clone());
+    let ran = Ran {
+        outcome,
+        output,
+        tape: tm.tape().to_vec(),
+        steps: tm.steps,
+    };
+    (ran, largest)
+}
+
+///a program with its brackets balanced and its loops at most three deep, with the input read now
+///and then so cells start out at more than just 0
+fn program() -> impl Strategy<Value = String> {
+    let command = prop::sample::select(vec!["+", "-", "<", ">", ".", ",", "+", ">", "[-]"])
+        .prop_map(String::from);
+    let body = command.prop_recursive(3, 48, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(|parts| parts.concat()),
+            prop::collection::vec(inner, 0..8).prop_map(|parts| format!("[{}]", parts.concat())),
+        ]
+    });
+    (0..4usize, prop::collection::vec(body, 0..12))
+        .prop_map(|(moves, parts)| format!("{}{}", ">".repeat(moves), parts.concat()))
+}
+
+proptest! {
+    #[test]
+    fn bytes_and_big_cells_agree_below_256(
+        source in program(),
+        input in prop::collection::vec(any::<u8>(), 0..8),
+    ) {
+        let (big, largest) = run(&source, &input, true);
+        let largest = largest.expect("it has big cells");
+        //nothing went below 0, or it would have stopped there, and nothing past 255
+        if big.outcome.is_ok() && largest <= BigUint::from(255u8) {
+            let (bytes, _) = run(&source, &input, false);
+            prop_assert_eq!(bytes, big);
+        }
+    }
+}
+
+#[test]
+fn the_largest_value() {
+    let largest = |source: &str| run(source, &[], true).1.expect("it has big cells");
+    assert_eq!(largest(""), BigUint::ZERO);
+    assert_eq!(largest("++++[>++++<-]>"), BigUint::from(16u8));
+    //it's the largest there was, not the largest there is at the end
+    assert_eq!(largest("+++++[-]++"), BigUint::from(5u8));
+    assert_eq!(largest(&"+".repeat(300)), BigUint::from(300u16));
+    //where the byte it wraps around to is all the bytes have
+    let (bytes, none) = run(&"+".repeat(300), &[], false);
+    assert_eq!(none, None);
+    assert_eq!(bytes.tape[0], 44);
+    //100 times 100, carried past every byte boundary along the way
+    let squared = format!("{}[>{}<-]", "+".repeat(100), "+".repeat(100));
+    assert_eq!(largest(&squared), BigUint::from(10_000u16));
+}
+
+///going below 0 is where they part, the bytes wrap around and the big cells stop
+#[test]
+fn below_zero() {
+    let (bytes, _) = run("-.", &[], false);
+    assert_eq!(bytes.output, [255]);
+    let (big, largest) = run("-.", &[], true);
+    assert!(matches!(big.outcome, Err(RuntimeError::CellUnderflow(_))));
+    assert!(big.output.is_empty());
+    assert_eq!(largest, Some(BigUint::ZERO));
+}
+
+///--stats says the largest value next to the steps, after the line with them
+#[cfg(feature = "cli")]
+#[test]
+fn in_the_stats() {
+    let program = std::env::temp_dir().join(format!("bfint-big-{}.bf", std::process::id()));
+    std::fs::write(&program, format!("{}[-]", "+".repeat(300))).expect("it can be written");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["run", "--cell-width", "big", "--stats"])
+        .arg(&program)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&program);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let steps = stderr.find(" steps").expect("it says the steps");
+    let largest = stderr
+        .find("the largest cell held 300")
+        .unwrap_or_else(|| panic!("it says the largest value:\n{}", stderr));
+    assert!(steps < largest, "{}", stderr);
+}
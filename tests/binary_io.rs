@@ -0,0 +1,129 @@
+//! every byte from 0 to 255 going through bfint's stdin and stdout as it is, redirected to and
+//! from files, which on windows is where "\n" could have become "\r\n". and what's done for a
+//! windows console instead, which is tried out on its own here since there's no console to run
+//! it on: bytes that can't be utf-8 shown as "\u{FFFD}", and "\r\n" read as "\n"
+#![cfg(feature = "std")]
+use bfint::io::{ConsoleInput, ConsoleOutput};
+use std::io::{Read, Write};
+
+///every byte, once, in order
+fn every_byte() -> Vec<u8> {
+    (0..=255).collect()
+}
+
+#[test]
+fn to_a_console() {
+    let mut written = vec![];
+    let mut console = ConsoleOutput::new(&mut written);
+    for byte in every_byte() {
+        console.write_all(&[byte]).expect("it can be written");
+    }
+    drop(console);
+    let mut expected: Vec<u8> = (0..=127).collect();
+    expected.extend("\u{FFFD}".repeat(128).bytes());
+    assert_eq!(written, expected);
+    //utf-8 whose bytes come one at a time goes out as it is, and a character that's never
+    //finished is shown as one "\u{FFFD}"
+    let mut written = vec![];
+    let mut console = ConsoleOutput::new(&mut written);
+    for byte in "é€😀".bytes().chain([0xF0, 0x9F]) {
+        console.write_all(&[byte]).expect("it can be written");
+    }
+    drop(console);
+    assert_eq!(String::from_utf8(written), Ok("é€😀\u{FFFD}".to_string()));
+}
+
+#[test]
+fn from_a_console() {
+    let read = |typed: &[u8], chunk: usize| {
+        let mut console = ConsoleInput::new(typed);
+        let mut read = vec![];
+        let mut buffer = vec![0; chunk];
+        loop {
+            match console.read(&mut buffer).expect("it can be read") {
+                0 => return read,
+                length => read.extend_from_slice(&buffer[..length]),
+            }
+        }
+    };
+    for chunk in [1, 2, 4096] {
+        assert_eq!(read(b"one\r\ntwo\r\n", chunk), b"one\ntwo\n");
+        //a "\r" on its own, at the end too, is kept
+        assert_eq!(read(b"a\rb\r\r\nc\r", chunk), b"a\rb\r\nc\r");
+    }
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use super::every_byte;
+    use std::fs::File;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    ///a directory for the files of one test, which goes away with it
+    struct Directory(PathBuf);
+
+    impl Directory {
+        fn new(test: &str) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "bfint-binary-io-{}-{}",
+                std::process::id(),
+                test
+            ));
+            std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+            Directory(directory)
+        }
+    }
+
+    impl Drop for Directory {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    ///runs the program with stdin and stdout redirected to files, giving back what it wrote
+    fn redirected(test: &str, program: &str, input: &[u8]) -> Vec<u8> {
+        let directory = Directory::new(test);
+        let (path, input_path, output_path) = (
+            directory.0.join("program.b"),
+            directory.0.join("input"),
+            directory.0.join("output"),
+        );
+        std::fs::write(&path, program).expect("it can be written");
+        std::fs::write(&input_path, input).expect("it can be written");
+        let status = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .arg(&path)
+            .stdin(File::open(&input_path).expect("it was just written"))
+            .stdout(Stdio::from(
+                File::create(&output_path).expect("it can be made"),
+            ))
+            .status()
+            .expect("bfint starts");
+        assert!(status.success());
+        std::fs::read(&output_path).expect("bfint wrote it")
+    }
+
+    #[test]
+    fn every_byte_out() {
+        let program = format!(".{}", "+.".repeat(255));
+        assert_eq!(redirected("out", &program, b""), every_byte());
+    }
+
+    #[test]
+    fn every_byte_in() {
+        let program = ",.".repeat(256);
+        assert_eq!(redirected("in", &program, &every_byte()), every_byte());
+    }
+
+    ///the same on windows, which is where it could go wrong, with the "\r\n" that only a console
+    ///would turn into "\n" read from a file as it is
+    #[cfg(windows)]
+    #[test]
+    fn on_windows() {
+        let program = format!(".{}", "+.".repeat(255));
+        let written = redirected("windows", &program, b"");
+        assert_eq!(written.len(), 256);
+        assert_eq!(written, (0..=255).collect::<Vec<u8>>());
+        assert_eq!(redirected("windows-in", ",.,.,.", b"\r\n\n"), b"\r\n\n");
+    }
+}
@@ -0,0 +1,63 @@
+/* uses libbfint through include/bfint.h the way a C program would: runs a program with input,
+ * steps one, reads its tape and gets the errors back. prints "ok" if everything was as it
+ * should be, and what wasn't otherwise */
+#include <stdio.h>
+#include <string.h>
+
+#include "bfint.h"
+
+static int failed = 0;
+
+static void check(int ok, const char *what) {
+    if (!ok) {
+        printf("FAIL %s\n", what);
+        failed = 1;
+    }
+}
+
+int main(void) {
+    BfError err = BF_ERROR_OK;
+
+    /* echoes its input back */
+    BfMachine *machine = bf_machine_new(",[.,]", &err);
+    check(machine != NULL && err == BF_ERROR_OK, "an echo program parses");
+    bf_machine_set_input(machine, (const uint8_t *)"hello", 5, &err);
+    bf_machine_run(machine, &err);
+    check(err == BF_ERROR_OK, "the echo program runs");
+    uint8_t output[16];
+    size_t written = bf_machine_take_output(machine, output, 3, &err);
+    check(written == 3 && memcmp(output, "hel", 3) == 0, "the first of the output is taken");
+    written = bf_machine_take_output(machine, output, sizeof output, &err);
+    check(written == 2 && memcmp(output, "lo", 2) == 0, "what didn't fit is left for later");
+    bf_machine_free(machine);
+
+    /* a step at a time, until there's nothing left */
+    machine = bf_machine_new("+++>++", &err);
+    int steps = 0;
+    while (bf_machine_step(machine, &err)) {
+        steps++;
+    }
+    check(err == BF_ERROR_OK, "stepping works");
+    check(bf_machine_read_cell(machine, 0, &err) == 3, "cell 0 is 3");
+    check(bf_machine_read_cell(machine, 1, &err) == 2, "cell 1 is 2");
+    bf_machine_read_cell(machine, (uintptr_t)-1, &err);
+    check(err == BF_ERROR_OUT_OF_RANGE, "a cell past the end is out of range");
+    bf_machine_free(machine);
+
+    /* errors come back as codes, with a message to go with them */
+    machine = bf_machine_new("+[", &err);
+    check(machine == NULL && err == BF_ERROR_PARSE_ERROR, "an unmatched bracket doesn't parse");
+    check(strlen(bf_last_error_message()) > 0, "a parse error has a message");
+    machine = bf_machine_new("<", &err);
+    bf_machine_run(machine, &err);
+    check(err == BF_ERROR_RUNTIME_ERROR, "going off the tape fails");
+    bf_machine_free(machine);
+    bf_machine_run(NULL, &err);
+    check(err == BF_ERROR_NULL_POINTER, "a null machine is an error");
+    bf_machine_free(NULL);
+
+    if (!failed) {
+        printf("ok\n");
+    }
+    return failed;
+}
@@ -0,0 +1,176 @@
+//! the cache of compiled programs in a temporary directory: what it gives back for a program it
+//! has, one it hasn't, and files written by another version or broken since, and the command
+//! line only keeping anything in one when it's given --cache-dir
+#![cfg(feature = "std")]
+use bfint::bytecode::BytecodeError;
+use bfint::cache::{Cache, CacheError};
+use bfint::{lang::Lang, TuringMachine};
+use std::path::PathBuf;
+
+const SOURCE: &str = "++++++++[>++++++<-]>+.";
+
+///a directory for the cache of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-cache-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&directory);
+        Directory(directory)
+    }
+    ///the cached programs in it
+    fn files(&self) -> Vec<PathBuf> {
+        match std::fs::read_dir(&self.0) {
+            Ok(read) => read
+                .map(|entry| entry.expect("it can be read").path())
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///the cache in the directory with SOURCE kept in it, and the key it's under
+fn stored(directory: &Directory) -> (Cache, String) {
+    let cache = Cache::new(&directory.0);
+    let tm = TuringMachine::new(SOURCE, &Lang::Brainfuck).expect("it parses");
+    let key = Cache::key(SOURCE, &Lang::Brainfuck, &["fold"]);
+    cache.store(&key, tm.program(), 22).expect("it's kept");
+    (cache, key)
+}
+
+#[test]
+fn miss() {
+    let directory = Directory::new("miss");
+    let cache = Cache::new(&directory.0);
+    let key = Cache::key(SOURCE, &Lang::Brainfuck, &[]);
+    assert_eq!(cache.load(&key, SOURCE), Err(CacheError::Missing));
+    //another language or other passes are another program
+    let (cache, key) = stored(&directory);
+    for other in [
+        Cache::key(SOURCE, &Lang::Ook, &["fold"]),
+        Cache::key(SOURCE, &Lang::Brainfuck, &[]),
+        Cache::key("+.", &Lang::Brainfuck, &["fold"]),
+    ] {
+        assert_ne!(other, key);
+        assert_eq!(cache.load(&other, SOURCE), Err(CacheError::Missing));
+    }
+}
+
+#[test]
+fn hit() {
+    let directory = Directory::new("hit");
+    let (cache, key) = stored(&directory);
+    let cached = cache.load(&key, SOURCE).expect("it's in the cache");
+    let tm = TuringMachine::new(SOURCE, &Lang::Brainfuck).expect("it parses");
+    assert_eq!(&cached.program, tm.program().as_ref());
+    assert_eq!(cached.parsed, 22);
+    assert_eq!(cache.entries().expect("it can be listed").len(), 1);
+    //a file under the key that was compiled from something else isn't taken for the source
+    assert_eq!(
+        cache.load(&key, "+."),
+        Err(CacheError::Bytecode(BytecodeError::OtherSource))
+    );
+}
+
+///the version is written after the magic and the format, which a file written by another
+///version of bfint has another one of. the file is the bytecode, ending in an fnv-1a hash of the
+///rest of it, and then 8 bytes of how many instructions were parsed
+#[test]
+fn version_mismatch() {
+    let directory = Directory::new("version");
+    let (cache, key) = stored(&directory);
+    let path = directory.files().pop().expect("there's a file");
+    let mut bytes = std::fs::read(&path).expect("it can be read");
+    let version = env!("CARGO_PKG_VERSION").as_bytes();
+    let at = bytes
+        .windows(version.len())
+        .position(|window| window == version)
+        .expect("the version is in it");
+    bytes[at] = match bytes[at] {
+        b'9' => b'8',
+        _ => b'9',
+    };
+    //the hash made again, so it's only the version that's wrong
+    let hashed = bytes.len() - 16;
+    let hash = bytes[..hashed]
+        .iter()
+        .fold(0xcbf29ce484222325u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    bytes[hashed..hashed + 8].copy_from_slice(&hash.to_le_bytes());
+    std::fs::write(&path, &bytes).expect("it can be written");
+    assert!(matches!(
+        cache.load(&key, SOURCE),
+        Err(CacheError::Bytecode(BytecodeError::OtherVersion(_)))
+    ));
+}
+
+#[test]
+fn corrupted_entry() {
+    let directory = Directory::new("corrupted");
+    let (cache, key) = stored(&directory);
+    let path = directory.files().pop().expect("there's a file");
+    let bytes = std::fs::read(&path).expect("it can be read");
+    for (broken, error) in [
+        (vec![], BytecodeError::Malformed),
+        (b"not a program at all".to_vec(), BytecodeError::NotBytecode),
+        (bytes[..bytes.len() / 2].to_vec(), BytecodeError::Malformed),
+    ] {
+        std::fs::write(&path, &broken).expect("it can be written");
+        assert_eq!(
+            cache.load(&key, SOURCE),
+            Err(CacheError::Bytecode(error)),
+            "{:?}",
+            broken
+        );
+    }
+    //and keeping it again writes over it
+    let tm = TuringMachine::new(SOURCE, &Lang::Brainfuck).expect("it parses");
+    cache.store(&key, tm.program(), 22).expect("it's kept");
+    assert!(cache.load(&key, SOURCE).is_ok());
+}
+
+///runs "bfint run" on SOURCE with the options, with the places a cache could default to in the
+///directory, and checks it wrote "1"
+#[cfg(feature = "cli")]
+fn run(directory: &Directory, options: &[&str]) {
+    let program = std::env::temp_dir().join(format!("bfint-cache-{}.bf", std::process::id()));
+    std::fs::write(&program, SOURCE).expect("the program can be written");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("run")
+        .args(options)
+        .arg(&program)
+        .env("XDG_CACHE_HOME", &directory.0)
+        .env("HOME", &directory.0)
+        .output()
+        .expect("bfint starts");
+    assert_eq!(output.stdout, b"1");
+    assert!(output.status.success());
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn only_with_a_cache_dir() {
+    let home = Directory::new("home");
+    run(&home, &["-O2"]);
+    assert!(home.files().is_empty(), "{:?}", home.files());
+    let directory = Directory::new("cli");
+    let cache_dir = directory.0.to_str().expect("the path is utf-8");
+    run(&home, &["-O2", "--cache-dir", cache_dir, "--no-cache"]);
+    assert!(directory.files().is_empty());
+    run(&home, &["-O2", "--cache-dir", cache_dir]);
+    let files = directory.files();
+    assert_eq!(files.len(), 1);
+    //from the cache the second time, and again once it's been broken and written over
+    run(&home, &["-O2", "--cache-dir", cache_dir]);
+    std::fs::write(&files[0], b"broken").expect("it can be written");
+    run(&home, &["-O2", "--cache-dir", cache_dir]);
+    assert_ne!(std::fs::read(&files[0]).expect("it's there"), b"broken");
+}
@@ -0,0 +1,149 @@
+//! the one budget pbrain procedure calls and bf-eval's "%" share: a procedure calling itself, two
+//! calling each other and an eval that evals itself all stop at the limit with every call that
+//! got them there. and the calls a machine is in, in its backtrace, in the debugger's "bt" and in
+//! a checkpoint made in the middle of one
+#![cfg(feature = "std")]
+use bfint::debugger::Debugger;
+use bfint::lang::Lang;
+use bfint::{Frame, Position, RuntimeError, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+///runs the program with the limit, which it has to go past
+fn too_deep(source: &str, lang: Lang, limit: usize) -> RuntimeError {
+    let mut tm = TuringMachine::new(source, &lang).expect("it parses");
+    tm.call_depth_limit = limit;
+    tm.output = Arc::new(Mutex::new(vec![]));
+    tm.run().expect_err("it goes too deep")
+}
+
+///procedure 1 calls itself, from 1:3, after the call to it at 1:5
+#[test]
+fn a_procedure_calling_itself() {
+    let mut sites = vec![at(1, 5)];
+    sites.extend([at(1, 3); 5]);
+    assert_eq!(
+        too_deep("+(:):", Lang::Pbrain, 5),
+        RuntimeError::CallDepthExceeded(5, sites)
+    );
+    //none at all
+    assert_eq!(
+        too_deep("+(:):", Lang::Pbrain, 0),
+        RuntimeError::CallDepthExceeded(0, vec![at(1, 5)])
+    );
+    //the default
+    let RuntimeError::CallDepthExceeded(limit, sites) = too_deep("+(:):", Lang::Pbrain, 1024)
+    else {
+        panic!("it's the call depth that's exceeded");
+    };
+    assert_eq!((limit, sites.len()), (1024, 1025));
+}
+
+///procedure 1 adds one and calls 2, which takes one away and calls 1
+#[test]
+fn two_procedures_calling_each_other() {
+    let sites = vec![at(1, 12), at(1, 4), at(1, 9), at(1, 4), at(1, 9)];
+    assert_eq!(
+        too_deep("+(+:)+(-:)-:", Lang::Pbrain, 4),
+        RuntimeError::CallDepthExceeded(4, sites)
+    );
+}
+
+///the cell holds a "%", so evaling it evals it again
+#[test]
+fn an_eval_evaling_itself() {
+    let source = format!("{}%", "+".repeat(37));
+    //the "%" in the program, and then the one that's evaled, which is a program of its own
+    let sites = |evals: usize| {
+        let mut sites = vec![at(1, 38)];
+        sites.extend(vec![at(1, 1); evals]);
+        sites
+    };
+    //evals have a limit of their own, lower than the default for calls
+    assert_eq!(
+        too_deep(&source, Lang::BfEval, 1024),
+        RuntimeError::CallDepthExceeded(64, sites(64))
+    );
+    //and count towards the one for calls too
+    assert_eq!(
+        too_deep(&source, Lang::BfEval, 10),
+        RuntimeError::CallDepthExceeded(10, sites(10))
+    );
+}
+
+///deep recursion is said with the middle cut out
+#[test]
+fn what_it_says() {
+    assert_eq!(
+        too_deep("+(:):", Lang::Pbrain, 2).to_string(),
+        "the call at 1:3 goes past the limit of 2 nested calls, made from the calls at 1:5, 1:3"
+    );
+    assert_eq!(
+        too_deep("+(:):", Lang::Pbrain, 20).to_string(),
+        "the call at 1:3 goes past the limit of 20 nested calls, made from the calls at 1:5, \
+         1:3, 1:3, 1:3, (12 more), 1:3, 1:3, 1:3, 1:3"
+    );
+    assert_eq!(
+        too_deep("+(:):", Lang::Pbrain, 0).to_string(),
+        "the call at 1:5 goes past the limit of 0 nested calls"
+    );
+}
+
+///procedure 1 goes round a loop on the second cell, calling itself from inside it until the
+///cell is 0, which it is after two calls
+const NESTED: &str = "+(>[-<:>]<)>++<:";
+
+///runs the machine until it's the calls deep
+fn calls_deep(tm: &mut TuringMachine, depth: usize) {
+    while tm.call_stack().len() < depth {
+        tm.step().expect("it runs");
+    }
+}
+
+#[test]
+fn backtrace() {
+    let mut tm = TuringMachine::new(NESTED, &Lang::Pbrain).expect("it parses");
+    calls_deep(&mut tm, 3);
+    assert_eq!(tm.call_stack(), [at(1, 7), at(1, 7), at(1, 16)]);
+    //at the ">" the procedure starts with, outside its loop
+    assert_eq!(tm.position(), Some(at(1, 3)));
+    assert_eq!(
+        tm.backtrace(),
+        [
+            Frame::Call(at(1, 7)),
+            Frame::Loop(at(1, 4), at(1, 9)),
+            Frame::Call(at(1, 7)),
+            Frame::Loop(at(1, 4), at(1, 9)),
+            Frame::Call(at(1, 16)),
+        ]
+    );
+    let mut debugger = Debugger::new(tm, vec![]);
+    assert_eq!(
+        debugger.execute("bt").expect("it's a command"),
+        "#0  at 1:3\n#1  in the call at 1:7\n#2  in the loop 1:4-1:9\n#3  in the call at 1:7\n\
+         #4  in the loop 1:4-1:9\n#5  in the call at 1:16\n"
+    );
+}
+
+///a checkpoint made three calls deep carries on from there, and returns from every one of them
+#[test]
+fn resumed_in_the_middle() {
+    let source = format!("{}>>.", NESTED);
+    let mut tm = TuringMachine::new(&source, &Lang::Pbrain).expect("it parses");
+    calls_deep(&mut tm, 3);
+    let checkpoint = tm.checkpoint(&source);
+    let mut resumed = TuringMachine::new(&source, &Lang::Pbrain).expect("it parses");
+    resumed
+        .resume(&source, &checkpoint)
+        .expect("it's a checkpoint of it");
+    assert_eq!(resumed.backtrace(), tm.backtrace());
+    let output = Arc::new(Mutex::new(vec![]));
+    resumed.output = output.clone();
+    resumed.run().expect("it runs");
+    assert!(resumed.call_stack().is_empty());
+    assert_eq!(*bfint::shared::lock(&output), [0]);
+    assert_eq!(resumed.tape()[..3], [1, 0, 0]);
+}
@@ -0,0 +1,210 @@
+//! the capabilities report against what's really there: every feature this test was built with
+//! is in it and none that it wasn't, every feature Cargo.toml has is one it knows about, every name
+//! in it is one the settings take, and its json keeps the fields and the shape tools go by
+#![cfg(feature = "std")]
+use bfint::capabilities::{Capabilities, SCHEMA_VERSION};
+use bfint::config::InterpreterConfig;
+use bfint::optimize::Pipeline;
+use serde_json::Value;
+
+///every feature, and whether this test was built with it
+const FEATURES: [(&str, bool); 17] = [
+    ("cli", cfg!(feature = "cli")),
+    ("std", cfg!(feature = "std")),
+    ("wasm", cfg!(feature = "wasm")),
+    ("ffi", cfg!(feature = "ffi")),
+    ("python", cfg!(feature = "python")),
+    ("async", cfg!(feature = "async")),
+    ("bigint", cfg!(feature = "bigint")),
+    ("tui", cfg!(feature = "tui")),
+    ("serde", cfg!(feature = "serde")),
+    ("decompress", cfg!(feature = "decompress")),
+    ("png", cfg!(feature = "png")),
+    ("net", cfg!(feature = "net")),
+    ("server", cfg!(feature = "server")),
+    ("rpc", cfg!(feature = "rpc")),
+    ("sandbox", cfg!(feature = "sandbox")),
+    ("dap", cfg!(feature = "dap")),
+    ("lsp", cfg!(feature = "lsp")),
+];
+
+///the features in Cargo.toml's [features], leaving out default
+fn declared() -> Vec<String> {
+    include_str!("../Cargo.toml")
+        .lines()
+        .skip_while(|line| *line != "[features]")
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(name, _)| name.to_string())
+        .filter(|name| name != "default")
+        .collect()
+}
+
+#[test]
+fn features() {
+    let report = Capabilities::new();
+    for (name, on) in FEATURES {
+        assert_eq!(report.features.contains(&name), on, "{}", name);
+    }
+    let known: Vec<&str> = FEATURES.iter().map(|(name, _)| *name).collect();
+    assert_eq!(declared(), known);
+}
+
+///the names it gives are the ones the settings take, so it can't say something's there that isn't
+#[test]
+fn from_the_registries() {
+    let report = Capabilities::new();
+    let set = |name: &str, value: &str| InterpreterConfig::default().set(name, value);
+    for lang in &report.languages {
+        assert!(set("lang", lang).is_ok(), "{}", lang);
+    }
+    for (setting, _) in &report.extensions {
+        assert!(set(setting, "true").is_ok(), "{}", setting);
+    }
+    //a width this build doesn't have is still a name, it's only refused when it's used
+    for (width, available) in &report.cell_widths {
+        let mut config = InterpreterConfig::default();
+        config.set("cell-width", width).expect("it's a width");
+        assert_eq!(config.validate().is_ok(), *available, "{}", width);
+        assert_eq!(
+            report.tape_backends.contains(width),
+            *available,
+            "{}",
+            width
+        );
+    }
+    for underflow in &report.cell_underflow {
+        assert!(set("cell-underflow", underflow).is_ok(), "{}", underflow);
+    }
+    for signedness in &report.cell_signedness {
+        assert!(set("cell-signedness", signedness).is_ok(), "{}", signedness);
+    }
+    for eof in &report.eof {
+        assert!(set("eof", eof).is_ok(), "{}", eof);
+    }
+    for (level, passes) in &report.levels {
+        assert!(set("optimize", level).is_ok(), "{}", level);
+        assert!(passes.iter().all(|pass| report.passes.contains(pass)));
+    }
+    assert!(Pipeline::from_names(&report.passes).is_ok());
+    let names: Vec<&str> = report.settings.iter().map(|setting| setting.name).collect();
+    assert_eq!(names, bfint::config::SETTINGS);
+    //a default is written the way the setting takes it
+    for setting in &report.settings {
+        if let Some(default) = &setting.default {
+            assert!(set(setting.name, default).is_ok(), "{}", setting.name);
+        }
+    }
+}
+
+///the fields, and what each of them holds, which is what a tool reading schema 1 goes by
+#[test]
+fn json_schema() {
+    let json: Value = serde_json::from_str(&Capabilities::new().to_json()).expect("it's json");
+    assert_eq!(SCHEMA_VERSION, 1);
+    assert_eq!(json["schema"], 1);
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    let mut fields: Vec<&str> = json
+        .as_object()
+        .expect("it's an object")
+        .keys()
+        .map(String::as_str)
+        .collect();
+    fields.sort_unstable();
+    assert_eq!(
+        fields,
+        [
+            "cell_signedness",
+            "cell_underflow",
+            "cell_widths",
+            "engines",
+            "eof",
+            "extensions",
+            "features",
+            "languages",
+            "levels",
+            "limits",
+            "passes",
+            "runtime_warnings",
+            "schema",
+            "settings",
+            "tape",
+            "targets",
+            "version",
+        ]
+    );
+    let strings = |field: &Value| {
+        field
+            .as_array()
+            .expect("it's an array")
+            .iter()
+            .all(Value::is_string)
+    };
+    for field in [
+        "languages",
+        "cell_underflow",
+        "cell_signedness",
+        "eof",
+        "engines",
+        "targets",
+        "passes",
+        "runtime_warnings",
+        "features",
+    ] {
+        assert!(strings(&json[field]), "{}", field);
+    }
+    for extension in json["extensions"].as_array().expect("it's an array") {
+        assert!(extension["setting"].is_string() && extension["lang"].is_string());
+    }
+    for width in json["cell_widths"].as_array().expect("it's an array") {
+        assert!(width["name"].is_string() && width["available"].is_boolean());
+    }
+    for level in json["levels"].as_array().expect("it's an array") {
+        assert!(level["name"].is_string() && strings(&level["passes"]));
+    }
+    assert_eq!(json["tape"]["cells"], bfint::TAPE_LENGTH);
+    assert!(strings(&json["tape"]["backends"]));
+    assert_eq!(
+        json["limits"]["snippet_steps"],
+        bfint::snippet::SNIPPET_STEPS
+    );
+    assert_eq!(
+        json["limits"]["warning_sample_every"],
+        bfint::warnings::SAMPLE_EVERY
+    );
+    assert!(json["limits"]["tier_threshold"].is_u64());
+    for setting in json["settings"].as_array().expect("it's an array") {
+        assert!(setting["name"].is_string() && setting["takes"].is_string());
+        assert!(setting["default"].is_string() || setting["default"].is_null());
+    }
+}
+
+///bfint capabilities --json is the same report
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .arg("capabilities")
+            .args(args)
+            .output()
+            .expect("bfint starts");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).expect("it's utf-8")
+    };
+    let json: Value = serde_json::from_str(&run(&["--json"])).expect("it's json");
+    let expected: Value = serde_json::from_str(&Capabilities::new().to_json()).expect("it's json");
+    assert_eq!(json, expected);
+    let text = run(&[]);
+    assert!(
+        text.starts_with(&format!(
+            "bfint {}, capabilities schema 1\n",
+            env!("CARGO_PKG_VERSION")
+        )),
+        "{}",
+        text
+    );
+}
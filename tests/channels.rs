@@ -0,0 +1,190 @@
+//! bf-channels reading from two in-memory channels in turn, each running out on its own and with
+//! an eof behavior of its own, how much was read from each, and a checkpoint made between reads
+//! carrying on from where every channel was. and --channel and --ext-channels through bfint
+#![cfg(feature = "std")]
+use bfint::channels::{ChannelError, ChannelSpec, ChannelUse, Source};
+use bfint::lang::Lang;
+use bfint::{EofBehavior, Position, RuntimeError, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///reads a byte from channel 0 and prints it, then one from channel 1, then 0 again and 1 again.
+///the first cell says which channel, the second is what's read
+const INTERLEAVED: &str = ">,.<+'>,.<-'>,.<+'>,.";
+
+///a machine with the channels, and what it prints
+fn machine(
+    source: &str,
+    channels: &[(u8, &[u8], Option<EofBehavior>)],
+) -> (TuringMachine, Arc<Mutex<Vec<u8>>>) {
+    let mut tm = TuringMachine::new(source, &Lang::BfChannels).expect("it parses");
+    for (number, bytes, eof) in channels {
+        tm.add_channel(
+            *number,
+            Some(Arc::new(Mutex::new(Cursor::new(bytes.to_vec())))),
+            *eof,
+        );
+    }
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    (tm, output)
+}
+
+fn used(read: u64, ended: bool) -> ChannelUse {
+    ChannelUse { read, ended }
+}
+
+#[test]
+fn interleaved() {
+    let (mut tm, output) = machine(INTERLEAVED, &[(0, b"ab", None), (1, b"12", None)]);
+    tm.run().expect("it runs");
+    assert_eq!(*bfint::shared::lock(&output), b"a1b2");
+    assert_eq!(tm.channel(), 1);
+    assert_eq!(tm.channel_use(), [(0, used(2, false)), (1, used(2, false))]);
+}
+
+///channel 0 runs out after one byte and stays that way, while channel 1 carries on, and gives
+///255 once it's run out too
+#[test]
+fn running_out() {
+    let source = ">,.,.<+'>,.,.,.<-'>,.";
+    let (mut tm, output) = machine(
+        source,
+        &[(0, b"x", None), (1, b"yz", Some(EofBehavior::Max))],
+    );
+    tm.eof_behavior = EofBehavior::Zero;
+    tm.run().expect("it runs");
+    assert_eq!(*bfint::shared::lock(&output), b"x\0yz\xff\0");
+    assert_eq!(tm.channel_use(), [(0, used(1, true)), (1, used(2, true))]);
+}
+
+///channel 0 is the usual input unless it's given one, any other has to be given one
+#[test]
+fn channels_there_are() {
+    let (mut tm, output) = machine(",.>+',.", &[(1, b"b", None)]);
+    tm.input = Arc::new(Mutex::new(Cursor::new(b"a".to_vec())));
+    tm.run().expect("it runs");
+    assert_eq!(*bfint::shared::lock(&output), b"ab");
+    let (mut tm, _) = machine("++',", &[(1, b"b", None)]);
+    assert_eq!(
+        tm.run(),
+        Err(RuntimeError::NoSuchChannel(
+            2,
+            Position { line: 1, column: 4 }
+        ))
+    );
+}
+
+///a checkpoint made right after the second read, before what it read is printed, resumed with
+///the channels given from the start again, skips what was read from each
+#[test]
+fn resumed() {
+    let channels: [(u8, &[u8], Option<EofBehavior>); 2] = [(0, b"ab", None), (1, b"12", None)];
+    let (mut tm, output) = machine(INTERLEAVED, &channels);
+    while tm.channel_use() != [(0, used(1, false)), (1, used(1, false))] {
+        tm.step().expect("it runs");
+    }
+    let checkpoint = tm.checkpoint(INTERLEAVED);
+    assert_eq!(*bfint::shared::lock(&output), b"a");
+    let (mut resumed, output) = machine(INTERLEAVED, &channels);
+    resumed
+        .resume(INTERLEAVED, &checkpoint)
+        .expect("it's a checkpoint of it");
+    assert_eq!(resumed.channel(), 1);
+    resumed.run().expect("it runs");
+    assert_eq!(*bfint::shared::lock(&output), b"1b2");
+    assert_eq!(
+        resumed.channel_use(),
+        [(0, used(2, false)), (1, used(2, false))]
+    );
+}
+
+#[test]
+fn specs() {
+    assert_eq!(
+        ChannelSpec::parse("0=file:a.bin"),
+        Ok(ChannelSpec {
+            number: 0,
+            source: Source::File("a.bin".to_string()),
+            eof: None
+        })
+    );
+    assert_eq!(
+        ChannelSpec::parse("1=str:hello, world,eof=max"),
+        Ok(ChannelSpec {
+            number: 1,
+            source: Source::Text("hello, world".to_string()),
+            eof: Some(EofBehavior::Max)
+        })
+    );
+    assert_eq!(
+        ChannelSpec::parse("255=stdin").map(|spec| spec.source),
+        Ok(Source::Stdin)
+    );
+    assert_eq!(
+        ChannelSpec::parse("stdin"),
+        Err(ChannelError::Malformed("stdin".to_string()))
+    );
+    assert_eq!(
+        ChannelSpec::parse("256=stdin"),
+        Err(ChannelError::Number("256".to_string()))
+    );
+    assert_eq!(
+        ChannelSpec::parse("1=tcp:here"),
+        Err(ChannelError::Source("tcp:here".to_string()))
+    );
+    assert_eq!(
+        ChannelSpec::parse("1=stdin,eof=never"),
+        Err(ChannelError::Eof("never".to_string()))
+    );
+}
+
+///--ext-channels with channel 0 the usual input, 1 a file and 2 a text, and what --stats says
+///was read from each
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let directory = std::env::temp_dir().join(format!("bfint-channels-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    std::fs::write(directory.join("program.b"), ">,.<+'>,.<+'>,.,.<--'>,.")
+        .expect("it can be written");
+    std::fs::write(directory.join("sensor.bin"), [b'1']).expect("it can be written");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args([
+            "--ext-channels",
+            "--stats",
+            "--channel",
+            "1=file:sensor.bin",
+            "--channel",
+            "2=str:X",
+            "--channel",
+            "0=stdin",
+            "program.b",
+        ])
+        .current_dir(&directory)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("bfint starts");
+    child
+        .stdin
+        .take()
+        .expect("it's piped")
+        .write_all(b"ab")
+        .expect("it takes the input");
+    let output = child.wait_with_output().expect("it finishes");
+    let _ = std::fs::remove_dir_all(&directory);
+    let said = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", said);
+    assert_eq!(output.stdout, b"a1X\0b");
+    for line in [
+        "read 2 bytes from channel 0\n",
+        "read 1 bytes from channel 1\n",
+        "read 1 bytes from channel 2, and it ran out\n",
+    ] {
+        assert!(said.contains(line), "{}", said);
+    }
+}
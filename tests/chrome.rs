@@ -0,0 +1,175 @@
+//! traces in chrome's trace event format, loaded back as json and checked for what's in them:
+//! a begin and an end event for every time the program went into a loop and came out of it,
+//! nested the way the loops are, instant events for reading and writing, and the stats of the
+//! run in the metadata
+#![cfg(feature = "std")]
+use bfint::chrome::ChromeTrace;
+use bfint::lang::Lang;
+use bfint::TuringMachine;
+use serde_json::Value;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///reads a byte and goes round the outer loop that many times, each time going into the inner
+///loop and writing what it added up to
+const NESTED: &str = ",[>++[>+<-]>.<<-]";
+
+///runs the program with the input, tracing it, and gives back the trace loaded as json, and the
+///steps it took
+fn trace(program: &str, input: &[u8]) -> (Value, u64) {
+    let mut tm = TuringMachine::new(program, &Lang::Brainfuck).expect("it parses");
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    tm.output = Arc::new(Mutex::new(vec![]));
+    let trace = Arc::new(Mutex::new(ChromeTrace::new()));
+    tm.set_observer(trace.clone());
+    let _ = tm.run();
+    let json = bfint::shared::lock(&trace).to_json("nested.b", &tm);
+    (
+        serde_json::from_str(&json).expect("the trace is json"),
+        tm.steps,
+    )
+}
+
+///the events that aren't metadata or the stats counter, as what they are, like "B loop@1:2",
+///"E" or "output 4"
+fn events(trace: &Value) -> Vec<String> {
+    trace["traceEvents"]
+        .as_array()
+        .expect("there are events")
+        .iter()
+        .filter(|event| event["ph"] != "M" && event["ph"] != "C")
+        .map(
+            |event| match event["ph"].as_str().expect("every event has a phase") {
+                "B" => format!("B {}", event["name"].as_str().expect("it's named")),
+                "E" => "E".to_string(),
+                "i" => format!(
+                    "{} {}",
+                    event["name"].as_str().expect("it's named"),
+                    event["args"]["byte"]
+                ),
+                phase => panic!("an event of phase {}", phase),
+            },
+        )
+        .collect()
+}
+
+///every end event has a begin before it, and no begin is left without one
+fn balanced(events: &[String]) -> bool {
+    let mut depth = 0usize;
+    for event in events {
+        if event.starts_with('B') {
+            depth += 1;
+        } else if event == "E" {
+            match depth.checked_sub(1) {
+                Some(less) => depth = less,
+                None => return false,
+            }
+        }
+    }
+    depth == 0
+}
+
+#[test]
+fn loops_nest_the_way_they_ran() {
+    let (trace, steps) = trace(NESTED, &[2]);
+    let events = events(&trace);
+    assert_eq!(
+        events,
+        [
+            "input 2",
+            "B loop@1:2",
+            "B loop@1:6",
+            "E",
+            "output 2",
+            //the outer "]" going back isn't coming out of the loop, the inner "[" is going
+            //into its loop again
+            "B loop@1:6",
+            "E",
+            "output 4",
+            "E",
+        ]
+    );
+    assert!(balanced(&events));
+    //the times never go backwards
+    let times: Vec<f64> = trace["traceEvents"]
+        .as_array()
+        .expect("there are events")
+        .iter()
+        .filter_map(|event| event["ts"].as_f64())
+        .collect();
+    assert!(
+        times.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{:?}",
+        times
+    );
+    //the end of the inner loop says where its "]" is
+    let inner = trace["traceEvents"]
+        .as_array()
+        .expect("there are events")
+        .iter()
+        .find(|event| event["name"] == "loop@1:6")
+        .expect("it went into the inner loop");
+    assert_eq!(inner["args"]["end"], "1:11");
+    let metadata = &trace["otherData"];
+    assert_eq!(metadata["program"], "nested.b");
+    assert_eq!(metadata["steps"], steps);
+    assert_eq!(metadata["instructions"], NESTED.len());
+    assert_eq!(metadata["loops_entered"], 3);
+    assert_eq!(metadata["inputs"], 1);
+    assert_eq!(metadata["outputs"], 2);
+    assert_eq!(metadata["events_dropped"], 0);
+}
+
+///a loop that's skipped is never gone into
+#[test]
+fn a_skipped_loop() {
+    let (trace, _) = trace(NESTED, &[0]);
+    assert_eq!(events(&trace), ["input 0"]);
+    assert_eq!(trace["otherData"]["loops_entered"], 0);
+}
+
+///a run that fails inside loops comes out of all of them
+#[test]
+fn failing_inside_loops() {
+    let (trace, _) = trace("+[>+[<<]]", &[]);
+    let events = events(&trace);
+    assert_eq!(events, ["B loop@1:2", "B loop@1:5", "E", "E"]);
+}
+
+///written by bfint --trace-chrome
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    let directory = std::env::temp_dir().join(format!("bfint-chrome-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    let program = directory.join("nested.b");
+    let input = directory.join("input");
+    let out = directory.join("trace.json");
+    std::fs::write(&program, NESTED).expect("it can be written");
+    std::fs::write(&input, [3]).expect("it can be written");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("--trace-chrome")
+        .arg(&out)
+        .arg("--input")
+        .arg(&input)
+        .arg(&program)
+        .output()
+        .expect("bfint starts");
+    let written = std::fs::read_to_string(&out);
+    let _ = std::fs::remove_dir_all(&directory);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, [2, 4, 6]);
+    let trace: Value =
+        serde_json::from_str(&written.expect("it wrote the trace")).expect("the trace is json");
+    let events = events(&trace);
+    assert!(balanced(&events));
+    assert_eq!(
+        events.iter().filter(|event| *event == "B loop@1:6").count(),
+        3
+    );
+    assert_eq!(trace["otherData"]["loops_entered"], 4);
+}
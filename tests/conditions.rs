@@ -0,0 +1,69 @@
+//! conditions on breakpoints read from their text and worked out against a machine, including
+//! ones with as many operators and brackets as a condition can have and ones with far too many
+#![cfg(feature = "std")]
+use bfint::condition::{Condition, ConditionError, MAX_OPERATORS};
+use bfint::{lang::Lang, TuringMachine};
+
+///a machine that's run "+++>++", so cell 0 is 3, cell 1 is 2 and the pointer is on cell 1
+fn machine() -> TuringMachine {
+    let mut tm = TuringMachine::new("+++>++", &Lang::Brainfuck).expect("it parses");
+    tm.run().expect("it halts");
+    tm
+}
+
+fn holds(text: &str) -> bool {
+    Condition::parse(text)
+        .unwrap_or_else(|e| panic!("{:?} doesn't parse: {}", text, e))
+        .holds(&machine())
+}
+
+#[test]
+fn conditions_hold() {
+    assert!(holds("cell[0] == 3 && cell == 2"));
+    assert!(holds("ptr == 1 && !(cell[ptr-1] < 3)"));
+    assert!(holds("cell[0] == 4 || cell[cell[1] - 1] + 1 == 3"));
+    assert!(!holds("steps > 100"));
+}
+
+///as many as there can be, nested as deep as they go, are read and worked out without running
+///out of stack
+#[test]
+fn as_many_operators_as_there_can_be() {
+    let nots = "!".repeat(MAX_OPERATORS);
+    assert!(holds(&format!("{}cell == 2", nots)));
+    let depth = MAX_OPERATORS;
+    assert!(holds(&format!(
+        "{}cell == 2{}",
+        "(".repeat(depth),
+        ")".repeat(depth)
+    )));
+    //cell 0 is 3 and cell 3 is 0, so it goes back and forth between them
+    let index = format!("{}0{}", "cell[".repeat(depth), "]".repeat(depth));
+    assert!(holds(&format!("{} == 0", index)));
+    let chain = vec!["cell == 2"; MAX_OPERATORS + 1].join(" && ");
+    assert!(holds(&chain));
+    let sum = vec!["1"; MAX_OPERATORS + 1].join("+");
+    assert!(holds(&format!("{} > 0", sum)));
+}
+
+fn too_many(text: &str) -> ConditionError {
+    Condition::parse(text).expect_err("there are too many operators")
+}
+
+#[test]
+fn too_many_operators() {
+    let error = too_many(&format!("{}1 == 1", "!".repeat(200_000)));
+    assert_eq!(error.offset, MAX_OPERATORS);
+    assert_eq!(
+        error.message,
+        format!(
+            "there can only be {} operators and brackets in a condition",
+            MAX_OPERATORS
+        )
+    );
+    let depth = 100_000;
+    let error = too_many(&format!("{}1 == 1{}", "(".repeat(depth), ")".repeat(depth)));
+    assert_eq!(error.offset, MAX_OPERATORS);
+    too_many(&vec!["1 == 1"; 100_000].join(" || "));
+    too_many(&format!("{} == 1", vec!["ptr"; 100_000].join("-")));
+}
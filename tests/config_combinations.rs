@@ -0,0 +1,157 @@
+//! every combination of settings that's refused, each with its own error and a message that says
+//! how to fix it. rule has no "_", so an error that's added to ConfigError doesn't build here
+//! until it's said whether it's one of these, and RULES then fails until it has a case below
+#![cfg(feature = "std")]
+use bfint::checkpoint::CheckpointError;
+use bfint::config::{ConfigError, InterpreterConfig};
+use bfint::{lang::Lang, TuringMachine};
+
+///the combination the error refuses, or nothing for errors about one setting on its own
+fn rule(error: &ConfigError) -> Option<&'static str> {
+    match error {
+        ConfigError::BigCellsUnavailable => Some("big cells without bigint"),
+        ConfigError::TieredBigCells => Some("tiered with big cells"),
+        ConfigError::TieredWriters => Some("tiered with track-writers"),
+        ConfigError::UnderflowWithoutBigCells => Some("cell-underflow without big cells"),
+        ConfigError::BigBits => Some("big cells with boolfuck"),
+        ConfigError::NotPlain(_) => Some("big cells with instructions that aren't plain"),
+        ConfigError::ParseError(_)
+        | ConfigError::UnknownSetting(_)
+        | ConfigError::InvalidValue(_, _) => None,
+        #[cfg(feature = "serde")]
+        ConfigError::Profile(_) => None,
+    }
+}
+
+///every rule this build has
+#[cfg(feature = "bigint")]
+const RULES: &[&str] = &[
+    "tiered with big cells",
+    "tiered with track-writers",
+    "cell-underflow without big cells",
+    "big cells with boolfuck",
+    "big cells with instructions that aren't plain",
+];
+#[cfg(not(feature = "bigint"))]
+const RULES: &[&str] = &["big cells without bigint", "tiered with track-writers"];
+
+///some settings, the program they're for, and what the message refusing them has to say
+type Case = (
+    &'static [(&'static str, &'static str)],
+    &'static str,
+    &'static str,
+);
+
+///a case for every rule
+#[cfg(feature = "bigint")]
+const CASES: &[Case] = &[
+    (
+        &[("cell-width", "big"), ("tiered", "true")],
+        "+",
+        "leave one of them out",
+    ),
+    (
+        &[("tiered", "true"), ("track-writers", "true")],
+        "+",
+        "leave one of them out",
+    ),
+    (
+        &[("cell-underflow", "error")],
+        "+",
+        "set cell-width big too, or leave cell-underflow out",
+    ),
+    (
+        &[("lang", "boolfuck"), ("cell-width", "big")],
+        "+",
+        "leave cell-width out",
+    ),
+    (
+        &[("lang", "pbrain"), ("cell-width", "big")],
+        "+(-):",
+        "there's another one at 1:2",
+    ),
+];
+#[cfg(not(feature = "bigint"))]
+const CASES: &[Case] = &[
+    (
+        &[("cell-width", "big")],
+        "+",
+        "build it with the \"bigint\" feature",
+    ),
+    (
+        &[("tiered", "true"), ("track-writers", "true")],
+        "+",
+        "leave one of them out",
+    ),
+];
+
+#[test]
+fn every_combination_is_refused() {
+    let mut refused = vec![];
+    for (settings, source, fix) in CASES {
+        let mut config = InterpreterConfig::default();
+        for (name, value) in *settings {
+            config.set(name, value).expect("it's a setting");
+        }
+        let error = match TuringMachine::with_config(source, &config) {
+            Ok(_) => panic!("{:?} was let through", settings),
+            Err(error) => error,
+        };
+        assert!(error.to_string().contains(fix), "{:?}: {}", settings, error);
+        let rule = rule(&error).unwrap_or_else(|| panic!("{:?}: {:?}", settings, error));
+        //the ones the settings alone break are refused before there's a program
+        match rule {
+            "big cells with instructions that aren't plain" => {
+                assert_eq!(config.validate(), Ok(()))
+            }
+            _ => assert_eq!(config.validate(), Err(error)),
+        }
+        refused.push(rule);
+    }
+    assert_eq!(refused, RULES);
+}
+
+///one of each on its own is fine
+#[test]
+fn each_on_its_own() {
+    let mut settings = vec![("tiered", "true"), ("track-writers", "true")];
+    if cfg!(feature = "bigint") {
+        settings.extend([("cell-width", "big"), ("lang", "boolfuck")]);
+    }
+    for (name, value) in settings {
+        let mut config = InterpreterConfig::default();
+        config.set(name, value).expect("it's a setting");
+        assert_eq!(config.validate(), Ok(()), "{}", name);
+    }
+}
+
+///without bigint, cell-underflow can't even be set
+#[cfg(not(feature = "bigint"))]
+#[test]
+fn underflow_without_bigint() {
+    let mut config = InterpreterConfig::default();
+    assert_eq!(
+        config.set("cell-underflow", "error"),
+        Err(ConfigError::BigCellsUnavailable)
+    );
+}
+
+///a checkpoint only goes with the program it was made for
+#[test]
+fn a_checkpoint_for_another_program() {
+    let mut tm = TuringMachine::new("+++", &Lang::Brainfuck).expect("it parses");
+    tm.run().expect("it runs");
+    let checkpoint = tm.checkpoint("+++");
+    let mut other = TuringMachine::new("---", &Lang::Brainfuck).expect("it parses");
+    let error = other
+        .resume("---", &checkpoint)
+        .expect_err("it's another program");
+    assert_eq!(error, CheckpointError::WrongProgram);
+    assert!(error
+        .to_string()
+        .contains("resume it with the program it was made with"));
+    let mut same = TuringMachine::new("+++", &Lang::Brainfuck).expect("it parses");
+    same.resume("+++", &checkpoint)
+        .expect("it's the same program");
+    assert_eq!(same.tape()[0], 3);
+}
@@ -0,0 +1,50 @@
+//! machines made on one thread, run on another and handed back, with the handle that pauses and
+//! cancels them left on the thread that made them
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::{lang::Lang, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn send<T: Send>(_: &T) {}
+
+#[test]
+fn runs_on_another_thread() {
+    let mut tm = TuringMachine::new(",[.,]", &Lang::Brainfuck).expect("it parses");
+    send(&tm);
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(b"sent".to_vec())));
+    tm.output = output.clone();
+    let worker = thread::spawn(move || {
+        let outcome = tm.run();
+        (tm, outcome)
+    });
+    let (tm, outcome) = worker.join().expect("the worker doesn't panic");
+    assert_eq!(outcome, Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), b"sent");
+    assert!(tm.steps > 0);
+}
+
+///a program that never halts, cancelled from the thread that made it, comes back where it
+///stopped and can be run some more once it's reset
+#[test]
+fn cancelled_from_the_thread_that_made_it() {
+    let mut tm = TuringMachine::new("+[>+<]", &Lang::Brainfuck).expect("it parses");
+    let handle = tm.control_handle();
+    let worker = thread::spawn(move || {
+        let outcome = tm.run();
+        (tm, outcome)
+    });
+    thread::sleep(Duration::from_millis(50));
+    handle.pause();
+    handle.cancel();
+    let (mut tm, outcome) = worker.join().expect("the worker doesn't panic");
+    assert_eq!(outcome, Ok(RunOutcome::Cancelled));
+    let steps = tm.steps;
+    assert!(steps > 0);
+    handle.reset();
+    tm.step().expect("it steps");
+    assert_eq!(tm.steps, steps + 1);
+}
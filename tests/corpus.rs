@@ -0,0 +1,189 @@
+//! every program in tests/fixtures, run through the library with its input and output in
+//! memory. each one has to halt, print exactly what its ".out" file says, and take as many steps
+//! as tests/fixtures/steps.toml says, give or take its tolerance
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::fixtures::Baselines;
+use bfint::{lang::Lang, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///a fixture, built into the test so it doesn't depend on where it's run from
+struct Fixture {
+    name: &'static str,
+    source: &'static [u8],
+    input: &'static [u8],
+    expected: &'static [u8],
+}
+
+///the fixture with the name, and its input if it has one
+macro_rules! fixture {
+    ($name:literal, $stem:literal) => {
+        fixture!($name, $stem, b"")
+    };
+    ($name:literal, $stem:literal, input) => {
+        fixture!(
+            $name,
+            $stem,
+            include_bytes!(concat!("fixtures/", $stem, ".in"))
+        )
+    };
+    ($name:literal, $stem:literal, $input:expr) => {
+        Fixture {
+            name: $name,
+            source: include_bytes!(concat!("fixtures/", $name)),
+            input: $input,
+            expected: include_bytes!(concat!("fixtures/", $stem, ".out")),
+        }
+    };
+}
+
+const CORPUS: [Fixture; 10] = [
+    fixture!("busy.bf", "busy"),
+    fixture!("cat.bf", "cat", input),
+    fixture!("hello.bf", "hello"),
+    fixture!("latin1.b", "latin1"),
+    fixture!("mandelbrot.bf", "mandelbrot"),
+    fixture!("numbers.bf", "numbers"),
+    fixture!("rot13.bf", "rot13", input),
+    fixture!("script.bf", "script"),
+    fixture!("sierpinski.bf", "sierpinski"),
+    fixture!("squares.b", "squares"),
+];
+
+fn baselines() -> Baselines {
+    Baselines::parse(include_str!("fixtures/steps.toml")).expect("steps.toml parses")
+}
+
+///runs the fixture with its input, giving back how it ended, what it printed and how many steps
+///it took. it's stopped at twice its baseline, so one that doesn't halt fails instead of hanging
+fn run(fixture: &Fixture, step_limit: u64) -> (RunOutcome, Vec<u8>, u64) {
+    let mut tm = TuringMachine::from_bytes(fixture.source, &Lang::Brainfuck)
+        .unwrap_or_else(|e| panic!("{} doesn't parse: {}", fixture.name, e));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(fixture.input.to_vec())));
+    tm.output = output.clone();
+    tm.step_limit = Some(step_limit);
+    let outcome = tm
+        .run()
+        .unwrap_or_else(|e| panic!("{} failed: {}", fixture.name, e));
+    let output = bfint::shared::lock(&output).clone();
+    (outcome, output, tm.steps)
+}
+
+fn check(name: &str) {
+    let fixture = CORPUS
+        .iter()
+        .find(|fixture| fixture.name == name)
+        .expect("the fixture is in the corpus");
+    let baselines = baselines();
+    let baseline = *baselines
+        .steps
+        .get(name)
+        .unwrap_or_else(|| panic!("{} has no step count in steps.toml", name));
+    let (outcome, output, steps) = run(fixture, baseline * 2);
+    assert_eq!(outcome, RunOutcome::Halted, "{} didn't halt", name);
+    assert!(
+        output == fixture.expected,
+        "{} printed {:?}, not {:?}",
+        name,
+        String::from_utf8_lossy(&output),
+        String::from_utf8_lossy(fixture.expected)
+    );
+    assert_eq!(
+        baselines.check(name, steps),
+        None,
+        "{} took {} steps, the baseline is {} give or take {}%",
+        name,
+        steps,
+        baseline,
+        baselines.tolerance
+    );
+}
+
+#[test]
+fn busy() {
+    check("busy.bf");
+}
+
+#[test]
+fn cat() {
+    check("cat.bf");
+}
+
+#[test]
+fn hello() {
+    check("hello.bf");
+}
+
+#[test]
+fn latin1() {
+    check("latin1.b");
+}
+
+#[test]
+fn mandelbrot() {
+    check("mandelbrot.bf");
+}
+
+#[test]
+fn numbers() {
+    check("numbers.bf");
+}
+
+#[test]
+fn rot13() {
+    check("rot13.bf");
+}
+
+#[test]
+fn script() {
+    check("script.bf");
+}
+
+#[test]
+fn sierpinski() {
+    check("sierpinski.bf");
+}
+
+#[test]
+fn squares() {
+    check("squares.b");
+}
+
+///a fixture added to the directory without being added here would never be run by cargo test
+#[test]
+fn every_fixture_is_in_the_corpus() {
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    let fixtures = bfint::fixtures::discover(std::path::Path::new(directory))
+        .expect("tests/fixtures can be read");
+    let mut names: Vec<&str> = fixtures
+        .iter()
+        .map(|fixture| fixture.name.as_str())
+        .collect();
+    names.sort();
+    let corpus: Vec<&str> = CORPUS.iter().map(|fixture| fixture.name).collect();
+    assert_eq!(names, corpus);
+    assert_eq!(
+        baselines()
+            .steps
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>(),
+        corpus
+    );
+}
+
+///the step limit stops a program that would go on, instead of letting it hang
+#[test]
+fn a_program_past_its_step_limit_stops() {
+    let fixture = CORPUS
+        .iter()
+        .find(|fixture| fixture.name == "mandelbrot.bf")
+        .expect("the fixture is in the corpus");
+    let mut tm = TuringMachine::from_bytes(fixture.source, &Lang::Brainfuck).expect("it parses");
+    tm.output = Arc::new(Mutex::new(vec![]));
+    tm.step_limit = Some(1000);
+    assert!(tm.run().is_err());
+    assert_eq!(tm.steps, 1000);
+}
@@ -0,0 +1,149 @@
+//! the machine's state hash, and --detect-cycles built on it: a program going back and forth
+//! between the same few states forever is stopped with the steps the state repeated at, while
+//! one that prints in its loop, or one that's only slow, runs as it would have
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::random::Rng;
+use bfint::{Position, RuntimeError, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+fn machine(source: &str) -> TuringMachine {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    tm.output = Arc::new(Mutex::new(vec![]));
+    tm
+}
+
+///the machine after the steps
+fn after(source: &str, steps: usize) -> TuringMachine {
+    let mut tm = machine(source);
+    for _ in 0..steps {
+        tm.step().expect("it runs");
+    }
+    tm
+}
+
+#[test]
+fn state_hash() {
+    //the same state is the same hash, however it got there
+    assert_eq!(
+        after("+++>+<", 6).state_hash(),
+        after("+>+<++", 6).state_hash()
+    );
+    assert_eq!(after("+-", 2).state_hash(), after("><", 2).state_hash());
+    //and it's different for a different cell, pointer or place in the program
+    let hashes = [
+        after("+>+<", 4).state_hash(),
+        after("++><", 4).state_hash(),
+        after("+>+<", 3).state_hash(),
+        after("+>+<", 2).state_hash(),
+        machine("+>+<").state_hash(),
+    ];
+    for (index, hash) in hashes.iter().enumerate() {
+        assert!(!hashes[index + 1..].contains(hash), "{:?}", hashes);
+    }
+    //what it printed isn't part of it, nor are cells that are 0 past the pointer
+    assert_eq!(after("+.-", 3).state_hash(), after("+-.", 3).state_hash());
+    assert_eq!(after(">+-<", 4).state_hash(), after("><><", 4).state_hash());
+    //bf-rand's generator is part of it, but only for a program that uses it
+    let random = |seed| {
+        let mut tm = TuringMachine::new("?", &Lang::BfRand).expect("it parses");
+        tm.rng = Rng::from_seed(seed);
+        tm.state_hash()
+    };
+    assert_eq!(random(1), random(1));
+    assert_ne!(random(1), random(2));
+}
+
+///"[-+]" takes the cell from 1 to 0 and back to 1, going around three instructions forever
+#[test]
+fn oscillating() {
+    for every in [1, 2, 3, 7, 100] {
+        let mut tm = machine("+[-+]");
+        tm.detect_cycles(Some(every));
+        let Err(RuntimeError::InfiniteLoop(first, second, position)) = tm.run() else {
+            panic!("it wasn't caught looking every {} steps", every);
+        };
+        assert!(first < second, "every {}", every);
+        //the same place in the same cycle
+        assert_eq!((second - first) % 3, 0, "every {}", every);
+        assert_eq!(tm.steps, second, "every {}", every);
+        assert!(
+            (2..=5).contains(&position.column),
+            "every {}: {}",
+            every,
+            position
+        );
+        assert_eq!(position.line, 1);
+        //and found soon after it started
+        assert!(second < 10 * every + 10, "every {}: {}", every, second);
+    }
+}
+
+#[test]
+fn what_it_says() {
+    let mut tm = machine("+[]");
+    tm.detect_cycles(Some(1));
+    let error = tm.run().expect_err("it's caught");
+    assert_eq!(
+        error,
+        RuntimeError::InfiniteLoop(3, 4, Position { line: 1, column: 3 })
+    );
+    assert_eq!(
+        error.to_string(),
+        "the program is in an infinite loop at 1:3 (state repeated at steps 3 and 4)"
+    );
+}
+
+///the second cell wraps around, so the state repeats every 256 times around
+#[test]
+fn wrapping_around() {
+    let mut tm = machine("+[>+<]");
+    tm.detect_cycles(Some(7));
+    assert!(matches!(tm.run(), Err(RuntimeError::InfiniteLoop(..))));
+}
+
+///what isn't a cycle, or is one that can't be caught
+#[test]
+fn not_caught() {
+    //it halts, however long it takes
+    let mut tm = machine("++++++[>++++++++[>+++++<-]<-]>>.");
+    tm.detect_cycles(Some(1));
+    assert!(tm.run().is_ok());
+    //printing, which might be what it's for
+    let mut tm = machine("+[.]");
+    tm.detect_cycles(Some(1));
+    tm.step_limit = Some(1000);
+    assert!(matches!(tm.run(), Err(RuntimeError::StepLimitReached(_))));
+    //going somewhere else every time, until it's off the tape
+    let mut tm = machine("+[>+]");
+    tm.detect_cycles(Some(1000));
+    assert!(matches!(tm.run(), Err(RuntimeError::PointerOutOfBounds(_))));
+    //and turned off again
+    let mut tm = machine("+[]");
+    tm.detect_cycles(Some(1));
+    tm.detect_cycles(None);
+    tm.step_limit = Some(1000);
+    assert!(matches!(tm.run(), Err(RuntimeError::StepLimitReached(_))));
+}
+
+///bfint --detect-cycles
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let path = std::env::temp_dir().join(format!("bfint-cycles-{}.b", std::process::id()));
+    std::fs::write(&path, "+[-+]").expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--detect-cycles", "1000"])
+        .arg(&path)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&path);
+    assert!(!output.status.success());
+    let said = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        said.contains("the program is in an infinite loop at 1:"),
+        "{}",
+        said
+    );
+}
@@ -0,0 +1,236 @@
+//! the debug adapter driven the way an editor would drive it, every request framed and every
+//! answer read back through framing.rs: launching a program, a conditional breakpoint it runs on
+//! to, the loops it's in as frames, its cells as variables, stepping in, over and out, and on to
+//! the end. every message has a seq one more than the last one's
+#![cfg(feature = "dap")]
+use bfint::dap::Server;
+use bfint::framing;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-dap-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+    ///a file in it holding the text
+    fn file(&self, name: &str, text: &str) -> String {
+        let path = self.0.join(name);
+        std::fs::write(&path, text).expect("the temporary directory can be written to");
+        path.to_str().expect("the path is utf-8").to_string()
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///three times round the loop, adding one to the next cell and printing it each time, with the
+///first cell named
+const PROGRAM: &str = "+++ ;@0 count\n[\n>+.<-\n]>.";
+
+///the request with the seq
+fn request(seq: i64, command: &str, arguments: Value) -> Value {
+    json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments })
+}
+
+///what the adapter sends back for the requests
+fn session(requests: &[Value]) -> Vec<Value> {
+    let mut input = vec![];
+    for request in requests {
+        framing::send(&mut input, request).expect("a vec can be written to");
+    }
+    let mut output = vec![];
+    Server::new(&mut output)
+        .serve(&input[..])
+        .expect("every request is answered");
+    let mut sent = vec![];
+    let mut output = &output[..];
+    while let Some(message) = framing::read_message(&mut output).expect("the answers are framed") {
+        sent.push(message);
+    }
+    sent
+}
+
+///the response to the request with the seq
+fn response(sent: &[Value], seq: i64) -> &Value {
+    sent.iter()
+        .find(|message| message["type"] == "response" && message["request_seq"] == seq)
+        .unwrap_or_else(|| panic!("request {} was answered: {:?}", seq, sent))
+}
+
+///the events sent after the response to the request with the seq, up to the next response
+fn events_after(sent: &[Value], seq: i64) -> Vec<&Value> {
+    let at = sent
+        .iter()
+        .position(|message| message["type"] == "response" && message["request_seq"] == seq)
+        .unwrap_or_else(|| panic!("request {} was answered: {:?}", seq, sent));
+    sent[at + 1..]
+        .iter()
+        .take_while(|message| message["type"] == "event")
+        .collect()
+}
+
+///the variables a variables response gives, as name and value
+fn variables(response: &Value) -> Vec<(String, String)> {
+    response["body"]["variables"]
+        .as_array()
+        .expect("there are variables")
+        .iter()
+        .map(|variable| {
+            let text = |key: &str| variable[key].as_str().expect("it's text").to_string();
+            (text("name"), text("value"))
+        })
+        .collect()
+}
+
+#[test]
+fn a_breakpoint_round_trip() {
+    let directory = Directory::new("round-trip");
+    let program = directory.file("program.bf", PROGRAM);
+    let sent = session(&[
+        request(1, "initialize", json!({ "adapterID": "bfint" })),
+        request(2, "launch", json!({ "program": program })),
+        //the ">" in the loop the second time round, and a line with nothing on it
+        request(
+            3,
+            "setBreakpoints",
+            json!({
+                "source": { "path": program },
+                "breakpoints": [{ "line": 3, "condition": "cell == 2" }, { "line": 9 }],
+            }),
+        ),
+        request(4, "configurationDone", json!({})),
+        request(5, "threads", json!({})),
+        request(6, "stackTrace", json!({ "threadId": 1 })),
+        request(7, "scopes", json!({ "frameId": 0 })),
+        request(8, "variables", json!({ "variablesReference": 1 })),
+        request(9, "variables", json!({ "variablesReference": 2 })),
+        request(10, "variables", json!({ "variablesReference": 3 })),
+        request(11, "stepIn", json!({ "threadId": 1 })),
+        request(12, "stepOut", json!({ "threadId": 1 })),
+        request(13, "stackTrace", json!({ "threadId": 1 })),
+        request(14, "next", json!({ "threadId": 1 })),
+        request(15, "continue", json!({ "threadId": 1 })),
+        request(16, "pause", json!({ "threadId": 1 })),
+        request(17, "disconnect", json!({})),
+        //nothing after disconnect is read
+        request(18, "threads", json!({})),
+    ]);
+    for seq in 1..=15 {
+        assert_eq!(response(&sent, seq)["success"], true, "{}", seq);
+    }
+    assert_eq!(
+        response(&sent, 1)["body"]["supportsConditionalBreakpoints"],
+        true
+    );
+    assert_eq!(events_after(&sent, 2)[0]["event"], "initialized");
+    let breakpoints = &response(&sent, 3)["body"]["breakpoints"];
+    assert_eq!(breakpoints[0]["verified"], true);
+    assert_eq!(breakpoints[0]["line"], 3);
+    assert_eq!(breakpoints[0]["column"], 1);
+    assert_eq!(breakpoints[1]["verified"], false);
+    //it runs to the breakpoint, printing what the first time round printed on the way
+    let events = events_after(&sent, 4);
+    assert_eq!(events[0]["event"], "output");
+    assert_eq!(events[0]["body"]["output"], "\u{1}");
+    assert_eq!(events[1]["event"], "stopped");
+    assert_eq!(events[1]["body"]["reason"], "breakpoint");
+    assert_eq!(
+        events[1]["body"]["hitBreakpointIds"],
+        json!([breakpoints[0]["id"]])
+    );
+    assert_eq!(response(&sent, 5)["body"]["threads"][0]["id"], 1);
+    let frames = &response(&sent, 6)["body"]["stackFrames"];
+    assert_eq!(frames[0]["line"], 3);
+    assert_eq!(frames[0]["column"], 1);
+    assert_eq!(frames[0]["source"]["path"], program.as_str());
+    assert_eq!(frames[1]["name"], "loop at 2:1");
+    assert_eq!(frames[1]["line"], 2);
+    assert_eq!(response(&sent, 6)["body"]["totalFrames"], 2);
+    let scopes = &response(&sent, 7)["body"]["scopes"];
+    assert_eq!(scopes[2]["name"], "Named cells");
+    let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+    assert_eq!(
+        variables(response(&sent, 8)),
+        [pair("pointer", "0"), pair("cell", "2"), pair("steps", "10")]
+    );
+    let tape = variables(response(&sent, 9));
+    assert_eq!(tape.len(), 9);
+    assert_eq!(tape[..2], [pair("cell[0]", "2"), pair("cell[1]", "1")]);
+    assert_eq!(variables(response(&sent, 10)), [pair("count (0)", "2")]);
+    //one instruction, the ">"
+    let events = events_after(&sent, 11);
+    assert_eq!(events[0]["body"]["reason"], "step");
+    //out of the loop, printing the last two times round on the way
+    let events = events_after(&sent, 12);
+    assert_eq!(events[0]["body"]["output"], "\u{2}\u{3}");
+    assert_eq!(events[1]["body"]["reason"], "step");
+    let frames = &response(&sent, 13)["body"]["stackFrames"];
+    assert_eq!(response(&sent, 13)["body"]["totalFrames"], 1);
+    assert_eq!(frames[0]["line"], 4);
+    assert_eq!(frames[0]["column"], 2);
+    //the ">" isn't a "[", so next is one instruction too
+    assert_eq!(events_after(&sent, 14)[0]["body"]["reason"], "step");
+    let events = events_after(&sent, 15);
+    assert_eq!(events[0]["body"]["output"], "\u{3}");
+    assert_eq!(events[1]["event"], "exited");
+    assert_eq!(events[1]["body"]["exitCode"], 0);
+    assert_eq!(events[2]["event"], "terminated");
+    let refused = response(&sent, 16);
+    assert_eq!(refused["success"], false);
+    assert_eq!(refused["message"], "pause isn't supported");
+    assert_eq!(response(&sent, 17)["success"], true);
+    assert!(sent.iter().all(|message| message["request_seq"] != 18));
+    let seqs: Vec<u64> = sent
+        .iter()
+        .map(|message| message["seq"].as_u64().expect("every message has a seq"))
+        .collect();
+    assert_eq!(seqs, (1..=seqs.len() as u64).collect::<Vec<_>>());
+}
+
+///stopOnEntry stops before anything has run, and a stepLimit stops the program like an error
+#[test]
+fn on_entry_and_out_of_steps() {
+    let directory = Directory::new("entry");
+    let program = directory.file("endless.bf", "+[]");
+    let sent = session(&[
+        request(1, "initialize", json!({})),
+        request(
+            2,
+            "launch",
+            json!({ "program": program, "stopOnEntry": true, "stepLimit": 5 }),
+        ),
+        request(3, "configurationDone", json!({})),
+        request(4, "variables", json!({ "variablesReference": 1 })),
+        request(5, "continue", json!({})),
+    ]);
+    assert_eq!(events_after(&sent, 3)[0]["body"]["reason"], "entry");
+    assert_eq!(
+        variables(response(&sent, 4))[2],
+        ("steps".to_string(), "0".to_string())
+    );
+    let events = events_after(&sent, 5);
+    assert_eq!(events[0]["body"]["category"], "stderr");
+    assert_eq!(events[1]["body"]["reason"], "exception");
+    //without a program there's nothing to launch
+    let sent = session(&[
+        request(1, "launch", json!({})),
+        request(
+            2,
+            "launch",
+            json!({ "program": directory.0.join("missing.bf") }),
+        ),
+        request(3, "stackTrace", json!({})),
+    ]);
+    for seq in 1..=3 {
+        assert_eq!(response(&sent, seq)["success"], false, "{}", seq);
+    }
+}
@@ -0,0 +1,147 @@
+//! analyze --dead-writes on programs made up to have a write nothing reads, and on ones that look
+//! like they might but don't: the value is printed, the loop summed up reads it, or a loop the
+//! analysis can't follow is in the way so it doesn't guess
+#![cfg(feature = "std")]
+use bfint::analyze::writes::{dead_writes, DeadWrite};
+use bfint::lang::{self, Lang};
+use bfint::Position;
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+fn found(source: &str) -> Vec<DeadWrite> {
+    let tokens = lang::tokenize(source, &Lang::Brainfuck).expect("it parses");
+    dead_writes(&tokens)
+}
+
+///the first write, which cell, and what wrote over it, for a stretch starting at 1:1
+fn write(position: Position, offset: isize, overwritten_at: Position) -> DeadWrite {
+    DeadWrite {
+        position,
+        offset,
+        start: at(1, 1),
+        overwritten_at,
+    }
+}
+
+#[test]
+fn written_over() {
+    //cleared, whatever was added first
+    assert_eq!(found("+++[-]"), [write(at(1, 1), 0, at(1, 4))]);
+    //and read over, with the cell next to it left alone
+    assert_eq!(found("+>+<,."), [write(at(1, 1), 0, at(1, 5))]);
+    //a cell some way off, found again by going back and forth
+    assert_eq!(found("+>>--<<<>>>[-]<<."), [write(at(1, 4), 2, at(1, 12))]);
+}
+
+///the copy a copy loop leaves behind, only ever cleared again. the loop is what wrote it
+#[test]
+fn the_copy_left_over() {
+    let program = "+++[->+>+<<]\n>.\n>[-]";
+    assert_eq!(
+        found(program),
+        [DeadWrite {
+            position: at(1, 4),
+            offset: 2,
+            start: at(1, 1),
+            overwritten_at: at(3, 2),
+        }]
+    );
+    let said = found(program)[0].to_string();
+    assert_eq!(
+        said,
+        "1:4: probably never read, the cell +2 from where the pointer was at 1:1 is written over \
+         at 3:2 first"
+    );
+}
+
+#[test]
+fn read_first() {
+    for program in [
+        //printed
+        "+.[-]",
+        "+>+.<.,>,",
+        //what's added to the cell says how many times the loop goes round, so it's read
+        "+++[->++<]>.<[-]",
+        //the 0 a loop leaves isn't a write
+        "[-][-]",
+        "+[->+<][-]",
+        //a different cell is cleared
+        "+>[-]<.",
+        //and "," after "," throws input away, which is how it's skipped
+        ",,,.",
+    ] {
+        assert_eq!(found(program), [], "{}", program);
+    }
+}
+
+///what's left at the end isn't counted, the program or a loop going around again might look at it
+#[test]
+fn left_at_the_end() {
+    assert_eq!(found("+>++>+++"), []);
+    assert_eq!(found("+[>[-]+<-]"), []);
+}
+
+///a loop that moves, reads or prints can't be followed, so it counts as reading everything and
+///what comes after it starts again from nothing
+#[test]
+fn gives_up() {
+    for program in [
+        //moves
+        "+>+<[>]<[-]",
+        "+[<][-]",
+        //reads or prints
+        "+[,]",
+        "+>[<.>-]<[-]",
+        //a loop in a loop
+        "+[>[-]+<-][-]",
+    ] {
+        assert_eq!(found(program), [], "{}", program);
+    }
+    //after it, it's looked at again
+    assert_eq!(
+        found("+[<]+[-]"),
+        [DeadWrite {
+            position: at(1, 5),
+            offset: 0,
+            start: at(1, 5),
+            overwritten_at: at(1, 6),
+        }]
+    );
+    //inside a loop that can't be followed, its body is looked at on its own, and offsets count
+    //from the "["'s next instruction
+    assert_eq!(
+        found("+[>+,.<-]"),
+        [DeadWrite {
+            position: at(1, 4),
+            offset: 1,
+            start: at(1, 3),
+            overwritten_at: at(1, 5),
+        }]
+    );
+}
+
+///bfint analyze --dead-writes, with the path in front of every one
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let program = std::env::temp_dir().join(format!("bfint-dead-writes-{}.b", std::process::id()));
+    std::fs::write(&program, "+++[->+>+<<]\n>.\n>[-]").expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["analyze", "--dead-writes"])
+        .arg(&program)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&program);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "{}:1:4: probably never read, the cell +2 from where the pointer was at 1:1 is \
+             written over at 3:2 first\n",
+            program.display()
+        )
+    );
+}
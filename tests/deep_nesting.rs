@@ -0,0 +1,67 @@
+//! a program nested a hundred thousand loops deep, taken through everything the library does
+//! with a program: parsing it, checking it for every error, formatting it, linting it and running
+//! it at every level, formatted and not. it's all done on a thread spawned with the stack every
+//! thread starts out with, so nothing along the way can be using the call stack to keep its place
+#![cfg(feature = "std")]
+use bfint::format::{self, Comments};
+use bfint::optimize::Level;
+use bfint::program::{self, ParseOptions};
+use bfint::{lang, lang::Lang, ParseError, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+const DEEP: usize = 100_000;
+
+///what the program prints, at the level
+fn run(source: &str, level: Level) -> Vec<u8> {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    tm.optimize(&level.pipeline(), |_, _| {});
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.step_limit = Some(10_000_000);
+    tm.run().expect("it runs");
+    let output = bfint::shared::lock(&output).clone();
+    output
+}
+
+///every loop is entered, the innermost prints the cell, and the cell is 0 for every "]" after
+fn everything() {
+    let source = format!("+{}.-{}", "[".repeat(DEEP), "]".repeat(DEEP));
+    let options = ParseOptions::default();
+    let parsed = program::parse(&source, &options).expect("it has no errors");
+    assert_eq!(parsed.len(), 2 * DEEP + 3);
+    let tokens = lang::tokenize(&source, &Lang::Brainfuck).expect("it tokenizes");
+    let _ = bfint::lint::lint(&tokens);
+    let formatted =
+        format::format(&source, &Lang::Brainfuck, 80, Comments::Keep).expect("it can be formatted");
+    assert!(formatted.lines().count() > DEEP);
+    for level in Level::ALL {
+        assert_eq!(run(&source, level), [1], "at -O{}", level.name());
+        assert_eq!(
+            run(&formatted, level),
+            [1],
+            "formatted, at -O{}",
+            level.name()
+        );
+    }
+    //one deeper than it's allowed to go is an error at the first bracket past the limit
+    let options = ParseOptions {
+        max_nesting: DEEP - 1,
+        ..ParseOptions::default()
+    };
+    match program::parse(&source, &options) {
+        Err(errors) => assert!(
+            matches!(errors[..], [ParseError::TooDeep(position, max)]
+                if (position.column, max) == (DEEP + 1, DEEP - 1)),
+            "{:?}",
+            errors
+        ),
+        Ok(_) => panic!("it went {} deep", DEEP),
+    }
+}
+
+#[test]
+fn on_a_default_stack() {
+    std::thread::spawn(everything)
+        .join()
+        .expect("nothing overflows the stack");
+}
@@ -0,0 +1,118 @@
+//! which language a program is read as when it isn't given with --lang: every extension there
+//! is, and the contents of programs without one, like one piped in on stdin. bfint says at info
+//! level what it went with, and when the guess doesn't parse, that it was a guess
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+
+const OOK: &str = include_str!("dialects/hello.ook");
+const SPOON: &str = include_str!("dialects/hello.spoon");
+const OUT: &[u8] = include_bytes!("dialects/hello.out");
+
+#[test]
+fn by_extension() {
+    for (path, lang) in [
+        ("hello.b", Some(Lang::Brainfuck)),
+        ("hello.bf", Some(Lang::Brainfuck)),
+        ("hello.ook", Some(Lang::Ook)),
+        ("hello.spoon", Some(Lang::Spoon)),
+        ("hello.pb", Some(Lang::Pbrain)),
+        ("hello.pbrain", Some(Lang::Pbrain)),
+        ("dir.ook/hello.ook.gz", Some(Lang::Ook)),
+        ("https://example.com/hello.spoon?raw=1", Some(Lang::Spoon)),
+        ("hello.txt", None),
+        ("hello", None),
+        ("-", None),
+    ] {
+        assert_eq!(Lang::from_extension(path), lang, "{}", path);
+    }
+}
+
+#[test]
+fn by_contents() {
+    assert_eq!(Lang::from_content(OOK), Lang::Ook);
+    assert_eq!(Lang::from_content(SPOON), Lang::Spoon);
+    assert_eq!(Lang::from_content("++[>+<-] Ook. Ook."), Lang::Brainfuck);
+    assert_eq!(Lang::from_content(""), Lang::Brainfuck);
+    //nine in ten isn't more than nine in ten
+    let nine = format!("{} and", "Ook.".repeat(9).replace('.', ". "));
+    assert_eq!(Lang::from_content(&nine), Lang::Brainfuck);
+    let ten = format!("{} and", "Ook.".repeat(10).replace('.', ". "));
+    assert_eq!(Lang::from_content(&ten), Lang::Ook);
+}
+
+///the extension wins over the contents, and without one the contents decide
+#[test]
+fn detected() {
+    assert_eq!(Lang::detect("hello.b", OOK), Lang::Brainfuck);
+    assert_eq!(Lang::detect("hello", OOK), Lang::Ook);
+    assert_eq!(Lang::detect("-", SPOON), Lang::Spoon);
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    ///runs bfint with the program piped in, giving back what it printed and said
+    fn bfint(args: &[&str], stdin: &str) -> (Vec<u8>, String) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("bfint starts");
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(stdin.as_bytes())
+            .expect("it takes the program");
+        let output = child.wait_with_output().expect("bfint finishes");
+        (
+            output.stdout,
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    }
+
+    #[test]
+    fn from_stdin() {
+        for (program, lang) in [(super::OOK, "Ook"), (super::SPOON, "Spoon")] {
+            let (printed, said) = bfint(&["-v", "-"], program);
+            assert_eq!(printed, super::OUT, "{}", said);
+            assert!(
+                said.contains(&format!("-: reading it as {}", lang)),
+                "{}",
+                said
+            );
+        }
+    }
+
+    #[test]
+    fn a_wrong_guess() {
+        //mostly ook, but with a word that isn't one
+        let program = "Ook! Ook? Ook! Ook? Ook! Ook? Ook! Ook? Ook! Ook? Ook! Ook? Ook.";
+        let (_, said) = bfint(&["-"], program);
+        assert!(
+            said.contains("- was read as Ook, guessed from its contents; use --lang"),
+            "{}",
+            said
+        );
+        //given with --lang, it's not a guess
+        let (_, said) = bfint(&["--lang", "ook", "-"], program);
+        assert!(said.contains("error: "), "{}", said);
+        assert!(!said.contains("guessed"), "{}", said);
+    }
+
+    ///--lang wins over what the contents say, so every "." of the ook program prints a 0
+    #[test]
+    fn lang_wins() {
+        let (printed, said) = bfint(&["--lang", "bf", "-"], super::OOK);
+        assert_eq!(
+            printed,
+            vec![0; super::OOK.matches('.').count()],
+            "{}",
+            said
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! the sample programs for every language other than brainfuck, in tests/dialects, run through
+//! the library in the language they're written in with their input and output in memory. each
+//! one has to halt and print exactly what its ".out" file says
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::{lang::Lang, Position, RuntimeError, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///runs the program in the language with the input, giving back how it ended and what it printed.
+///brainfork goes through the scheduler, so the machines it forks into run too
+fn run(source: &str, lang: &Lang, input: &[u8]) -> (Result<RunOutcome, RuntimeError>, Vec<u8>) {
+    let mut tm = TuringMachine::from_bytes(source.as_bytes(), lang)
+        .unwrap_or_else(|e| panic!("the {:?} sample doesn't parse: {}", lang, e));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    tm.step_limit = Some(10_000_000);
+    let outcome = match lang {
+        Lang::Brainfork => bfint::fork::run(tm),
+        _ => tm.run(),
+    };
+    let output = bfint::shared::lock(&output).clone();
+    (outcome, output)
+}
+
+fn check(source: &str, lang: Lang, expected: &[u8]) {
+    let (outcome, output) = run(source, &lang, b"");
+    assert_eq!(
+        outcome,
+        Ok(RunOutcome::Halted),
+        "the {:?} sample didn't halt",
+        lang
+    );
+    assert!(
+        output == expected,
+        "the {:?} sample printed {:?}, not {:?}",
+        lang,
+        String::from_utf8_lossy(&output),
+        String::from_utf8_lossy(expected)
+    );
+}
+
+const HELLO: &[u8] = include_bytes!("dialects/hello.out");
+
+///the hello world from wikipedia's brainfuck page, word for word in Ook!
+#[test]
+fn ook_hello_world() {
+    check(include_str!("dialects/hello.ook"), Lang::Ook, HELLO);
+}
+
+///the same hello world, in Spoon's ones and zeroes
+#[test]
+fn spoon_hello_world() {
+    check(include_str!("dialects/hello.spoon"), Lang::Spoon, HELLO);
+}
+
+///the same hello world, one bit at a time, lowest bit of each byte first
+#[test]
+fn boolfuck_hello_world() {
+    check(
+        include_str!("dialects/hello.boolfuck"),
+        Lang::Boolfuck,
+        HELLO,
+    );
+}
+
+///a byte read a bit at a time and written back out a bit at a time is the same byte
+#[test]
+fn boolfuck_round_trips_a_byte() {
+    for byte in [0x00, 0x01, 0x80, 0xa5, 0xff] {
+        let (outcome, output) = run(",;,;,;,;,;,;,;,;", &Lang::Boolfuck, &[byte]);
+        assert_eq!(outcome, Ok(RunOutcome::Halted));
+        assert_eq!(output, [byte]);
+    }
+}
+
+///a procedure defined once and called in a loop, printing the cell to its left as a digit
+#[test]
+fn pbrain_calls_a_procedure() {
+    check(
+        include_str!("dialects/digits.pb"),
+        Lang::Pbrain,
+        include_bytes!("dialects/digits.out"),
+    );
+}
+
+///a procedure that calls itself until its counter runs out
+#[test]
+fn pbrain_procedures_recurse() {
+    check(
+        include_str!("dialects/recursion.pb"),
+        Lang::Pbrain,
+        include_bytes!("dialects/recursion.out"),
+    );
+}
+
+#[test]
+fn pbrain_calling_an_undefined_procedure_fails() {
+    let (outcome, output) = run("+++:", &Lang::Pbrain, b"");
+    assert_eq!(
+        outcome,
+        Err(RuntimeError::UndefinedProcedure(
+            3,
+            Position { line: 1, column: 4 }
+        ))
+    );
+    assert!(output.is_empty());
+}
+
+///storage, shifts and not, and "@" stopping the program before the last of it
+#[test]
+fn ebf1_sample() {
+    check(
+        include_str!("dialects/hi.ebf1"),
+        Lang::Ebf1,
+        include_bytes!("dialects/hi.out"),
+    );
+}
+
+///each of the opcodes on its own: the cell is set, copied to storage with "$" when the opcode
+///needs something in storage, set to the other number, and printed after the opcode
+#[test]
+fn ebf1_opcodes() {
+    let set = |number: usize| "+".repeat(number);
+    let with_storage = |stored: usize, cell: usize, opcode: char| {
+        format!("{}$[-]{}{}.", set(stored), set(cell), opcode)
+    };
+    for (source, expected) in [
+        //12 ^ 5, 12 & 10 and 12 | 3
+        (with_storage(12, 5, '^'), 9),
+        (with_storage(12, 10, '&'), 8),
+        (with_storage(12, 3, '|'), 15),
+        //"!" gives back what "$" put away
+        (format!("{}$[-]!.", set(7)), 7),
+        //5 >> 1, and 5 << 1
+        (format!("{}}}.", set(5)), 2),
+        (format!("{}{{.", set(5)), 10),
+        //a bit shifted off the top is gone
+        (format!("{}{{.", set(129)), 2),
+        (format!("{}~.", set(5)), 250),
+    ] {
+        let (outcome, output) = run(&source, &Lang::Ebf1, b"");
+        assert_eq!(outcome, Ok(RunOutcome::Halted), "{}", source);
+        assert_eq!(output, [expected], "{}", source);
+    }
+    //"@" ends the program there
+    let (outcome, output) = run("+.@+.", &Lang::Ebf1, b"");
+    assert_eq!(outcome, Ok(RunOutcome::Halted));
+    assert_eq!(output, [1]);
+}
+
+///hello world split between two machines: the parent prints "Hello, " while the one it forked
+///into waits, then prints "World!"
+#[test]
+fn brainfork_hello_world_in_parallel() {
+    check(
+        include_str!("dialects/hello.brainfork"),
+        Lang::Brainfork,
+        b"Hello, World!\n",
+    );
+}
@@ -0,0 +1,105 @@
+//! random programs run as they're written and at every optimization level, tiered too, with the
+//! same input and the same step limit. the passes only ever take steps out, so a program that
+//! halts as written halts at every level with the same output and the same tape, and one that
+//! fails does so the same way after the same output. one that runs out of steps as written has
+//! printed no more by then than the optimized one has by the time it stops, and when the
+//! optimized one halted, it halts the same way with the same tape given the steps it needs.
+//! proptest shrinks whatever it finds down to a small program
+#![cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use bfint::control::RunOutcome;
+use bfint::optimize::Level;
+use bfint::program::Program;
+use bfint::{lang::Lang, RuntimeError, TuringMachine};
+use proptest::prelude::*;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+const STEP_LIMIT: u64 = 20_000;
+///the steps a program that halted once it was optimized gets to halt as it's written. a loop the
+///optimizer runs in one go can take hundreds of steps as written for every one it takes
+const FINISHING_LIMIT: u64 = 1000 * STEP_LIMIT;
+
+///how a run went, and the tape it left
+#[derive(PartialEq, Debug)]
+struct Ran {
+    outcome: Result<RunOutcome, RuntimeError>,
+    output: Vec<u8>,
+    tape: Vec<u8>,
+}
+
+fn run(source: &str, input: &[u8], level: Level, tiered: bool, step_limit: u64) -> Ran {
+    let program = Program::compile(source, &Lang::Brainfuck, level).expect("it parses");
+    let mut tm = TuringMachine::new("", &Lang::Brainfuck).expect("nothing parses");
+    tm.load_program(Arc::new(program));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    tm.output = output.clone();
+    tm.step_limit = Some(step_limit);
+    if tiered {
+        tm.set_tiering(Some(2));
+    }
+    let outcome = tm.run();
+    let output = bfint::shared::lock(&output).clone();
+    Ran {
+        outcome,
+        output,
+        tape: tm.tape().to_vec(),
+    }
+}
+
+///a program with its brackets balanced and its loops at most four deep. the loops the optimizer
+///runs in one go are thrown in whole now and then, since they'd hardly ever come up otherwise
+fn program() -> impl Strategy<Value = String> {
+    let command = prop::sample::select(vec![
+        "+", "-", "<", ">", ".", ",", "+", ">", "[-]", "[>]", "[<]", "[->+<]", "[-<++>]",
+    ])
+    .prop_map(String::from);
+    let body = command.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(|parts| parts.concat()),
+            prop::collection::vec(inner, 0..8).prop_map(|parts| format!("[{}]", parts.concat())),
+        ]
+    });
+    //a few moves first, so not every "<" goes off the start of the tape
+    (0..4usize, prop::collection::vec(body, 0..12))
+        .prop_map(|(moves, parts)| format!("{}{}", ">".repeat(moves), parts.concat()))
+}
+
+proptest! {
+    #[test]
+    fn optimizing_never_changes_what_a_program_does(
+        source in program(),
+        input in prop::collection::vec(any::<u8>(), 0..8),
+    ) {
+        let written = run(&source, &input, Level::O0, false, STEP_LIMIT);
+        for (level, tiered) in [(Level::O1, false), (Level::O2, false), (Level::O2, true)] {
+            let optimized = run(&source, &input, level, tiered, STEP_LIMIT);
+            match &written.outcome {
+                Ok(_) => prop_assert_eq!(&optimized, &written, "at {}, tiered {}", level, tiered),
+                //the optimized one halted in the steps it had, so as written it halts too when
+                //it's given as many as it needs, and ends up with the same output and tape
+                Err(RuntimeError::StepLimitReached(_)) if optimized.outcome.is_ok() => {
+                    let finished = run(&source, &input, Level::O0, false, FINISHING_LIMIT);
+                    prop_assert_eq!(&optimized, &finished, "at {}, tiered {}", level, tiered)
+                }
+                Err(RuntimeError::StepLimitReached(_)) => prop_assert!(
+                    optimized.output.starts_with(&written.output),
+                    "at {}, tiered {}, it printed {:?}, which doesn't start with {:?}",
+                    level,
+                    tiered,
+                    optimized.output,
+                    written.output
+                ),
+                //the tape isn't compared, since a run of moves, "+" and "-" that goes off the
+                //tape can have changed its cells in another order by then
+                Err(_) => prop_assert_eq!(
+                    (&optimized.outcome, &optimized.output),
+                    (&written.outcome, &written.output),
+                    "at {}, tiered {}",
+                    level,
+                    tiered
+                ),
+            }
+        }
+    }
+}
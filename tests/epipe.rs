@@ -0,0 +1,75 @@
+//! bfint writing to a pipe that's closed while it's still running, like "bfint yes.b | head". it
+//! stops quietly, with 141 like SIGPIPE would give or 0 with --epipe ok, and --stats still says
+//! how far it got on stderr, which is still open
+#![cfg(all(feature = "cli", unix))]
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+
+///prints "y" forever
+const YES: &str = "+++++++++++[>+++++++++++<-]>.[.]";
+
+///runs bfint on YES with the arguments, and closes its stdout once it's read a few bytes of it
+fn closed_early(args: &[&str]) -> (Vec<u8>, Output) {
+    let path = std::env::temp_dir().join(format!(
+        "bfint-epipe-{}-{}.b",
+        std::process::id(),
+        args.join("")
+    ));
+    std::fs::write(&path, YES).expect("it can be written");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(args)
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("bfint starts");
+    let mut stdout = child.stdout.take().expect("it's piped");
+    let mut read = [0; 10];
+    stdout.read_exact(&mut read).expect("it prints");
+    drop(stdout);
+    let output = child.wait_with_output().expect("it finishes");
+    let _ = std::fs::remove_file(&path);
+    (read.to_vec(), output)
+}
+
+#[test]
+fn closed() {
+    let (read, output) = closed_early(&[]);
+    assert_eq!(read, b"yyyyyyyyyy");
+    assert_eq!(output.status.code(), Some(141));
+    //no panic, and nothing else either
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn ok() {
+    for epipe in [&["--epipe", "ok"][..], &["--epipe=ok"]] {
+        let (_, output) = closed_early(epipe);
+        assert_eq!(output.status.code(), Some(0), "{:?}", epipe);
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "", "{:?}", epipe);
+    }
+    let (_, output) = closed_early(&["--epipe=error"]);
+    assert_eq!(output.status.code(), Some(141));
+}
+
+#[test]
+fn stats() {
+    let (_, output) = closed_early(&["--stats"]);
+    assert_eq!(output.status.code(), Some(141));
+    let said = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        said.contains("instructions, 0 of them taken out"),
+        "{}",
+        said
+    );
+    assert!(!said.contains("panicked"), "{}", said);
+}
+
+#[test]
+fn not_a_choice() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--epipe", "sometimes", "x.b"])
+        .output()
+        .expect("bfint starts");
+    assert_eq!(output.status.code(), Some(2));
+}
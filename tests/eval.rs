@@ -0,0 +1,79 @@
+//! "%" in --lang bf-eval running the text on the tape: "+++." put in memory a byte at a time and
+//! evaluated on the same tape, the program carrying on after it, what it evaluates not parsing,
+//! and the steps it takes counting against the program's limit. how deep it goes is in
+//! call_depth.rs
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::lang::Lang;
+use bfint::{ParseError, Position, RuntimeError, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///the program that writes the text into the cells from the current one on, a byte to a cell,
+///and goes back to where it starts. the cell after it is left 0, which ends it
+fn write_text(text: &str) -> String {
+    let mut source: String = text
+        .bytes()
+        .map(|byte| format!("{}>", "+".repeat(byte as usize)))
+        .collect();
+    source.push_str(&"<".repeat(text.len()));
+    source
+}
+
+fn machine(source: &str) -> (TuringMachine, Arc<Mutex<Vec<u8>>>) {
+    let mut tm = TuringMachine::new(source, &Lang::BfEval).expect("it parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+    (tm, output)
+}
+
+///"+++." runs with the pointer on the "+" it starts with, so it makes that cell a "." and prints
+///it, and the "." after the "%" prints it again
+#[test]
+fn built_a_byte_at_a_time() {
+    let source = write_text("+++.") + "%.";
+    let (mut tm, output) = machine(&source);
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), b"..");
+    assert_eq!(tm.tape()[..5], *b".++.\0");
+    assert_eq!(tm.pointer(), 0);
+    //the outer program's steps, and the four of "+++."
+    let outer = source.chars().count() as u64;
+    assert_eq!(tm.steps, outer + 4);
+}
+
+///the pointer is shared too, so what it moves stays moved
+#[test]
+fn the_same_pointer() {
+    let (mut tm, output) = machine(&(write_text(">>+") + "%."));
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(tm.pointer(), 2);
+    assert_eq!(*bfint::shared::lock(&output), [b'+' + 1]);
+}
+
+#[test]
+fn not_parsing() {
+    let source = write_text("+[") + "%";
+    let (mut tm, _) = machine(&source);
+    assert_eq!(
+        tm.run(),
+        Err(RuntimeError::EvalParseError(
+            ParseError::UnmatchedOpen(Position { line: 1, column: 2 }),
+            Position {
+                line: 1,
+                column: source.len()
+            }
+        ))
+    );
+}
+
+///the steps what's evaluated takes count against the limit of the program, so a loop that never
+///ends in it is stopped like one in the program would be
+#[test]
+fn sharing_the_step_limit() {
+    let source = write_text("+[]") + "%";
+    let (mut tm, _) = machine(&source);
+    tm.step_limit = Some(1_000);
+    assert_eq!(tm.run(), Err(RuntimeError::StepLimitReached(1_000)));
+}
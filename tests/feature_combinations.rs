@@ -0,0 +1,171 @@
+//! every feature on its own, with the default cli feature left out, and the builds for the web
+//! and for small computers with no operating system, checked with cargo, along with the unit tests
+//! of the core without std run on this computer. a feature that only
+//! builds along with another one it doesn't ask for in Cargo.toml, or a cfg that's missing
+//! somewhere, shows up here and not in the usual build, which has them all on together.
+//!
+//! they take a while, so they only run when asked for:
+//!
+//! ```text
+//! cargo test --test feature_combinations -- --ignored
+//! ```
+#![cfg(feature = "std")]
+use std::path::Path;
+use std::process::Command;
+
+///runs the cargo command with the arguments, in a target directory of its own, since the one
+///these tests were built in is locked while they run, and panics with what it said if it fails
+fn cargo(subcommand: &str, args: &[&str]) {
+    let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("feature_combinations");
+    let mut command = Command::new(env!("CARGO"));
+    command
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args([subcommand, "--quiet", "--no-default-features"])
+        .args(args)
+        .arg("--target-dir")
+        .arg(&target);
+    let output = command
+        .output()
+        .unwrap_or_else(|e| panic!("{:?} didn't start: {}", command, e));
+    assert!(
+        output.status.success(),
+        "{:?} failed\n{}",
+        command,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+///"cargo check" with the arguments
+fn check(args: &[&str]) {
+    cargo("check", args);
+}
+
+///the library, its tests and its examples with only the feature, and what it asks for
+fn alone(feature: &str) {
+    check(&["--all-targets", "--features", feature]);
+}
+
+#[test]
+#[ignore]
+fn std() {
+    alone("std");
+}
+
+#[test]
+#[ignore]
+fn bigint() {
+    alone("bigint");
+}
+
+#[test]
+#[ignore]
+fn serde() {
+    alone("serde");
+}
+
+#[test]
+#[ignore]
+fn async_io() {
+    alone("async");
+}
+
+#[test]
+#[ignore]
+fn ffi() {
+    alone("ffi");
+}
+
+#[test]
+#[ignore]
+fn python() {
+    alone("python");
+}
+
+#[test]
+#[ignore]
+fn server() {
+    alone("server");
+}
+
+#[test]
+#[ignore]
+fn rpc() {
+    alone("rpc");
+}
+
+#[test]
+#[ignore]
+fn dap() {
+    alone("dap");
+}
+
+#[test]
+#[ignore]
+fn lsp() {
+    alone("lsp");
+}
+
+#[test]
+#[ignore]
+fn decompress() {
+    alone("decompress");
+}
+
+#[test]
+#[ignore]
+fn png() {
+    alone("png");
+}
+
+#[test]
+#[ignore]
+fn net() {
+    alone("net");
+}
+
+#[test]
+#[ignore]
+fn sandbox() {
+    alone("sandbox");
+}
+
+#[test]
+#[ignore]
+fn tui() {
+    alone("tui");
+}
+
+///the web interface, on the target it's for
+#[test]
+#[ignore]
+fn wasm32() {
+    check(&[
+        "--lib",
+        "--features",
+        "wasm",
+        "--target",
+        "wasm32-unknown-unknown",
+    ]);
+}
+
+///the core with no operating system, and big cells, which don't need one either
+#[test]
+#[ignore]
+fn thumbv7em() {
+    check(&["--lib", "--target", "thumbv7em-none-eabihf"]);
+    check(&[
+        "--lib",
+        "--features",
+        "bigint",
+        "--target",
+        "thumbv7em-none-eabihf",
+    ]);
+}
+
+///the unit tests that only build without std, like the ones of io.rs that hand a machine a queue
+///and a vector, on the computer running this. it's the same core thumbv7em checks, run
+#[test]
+#[ignore]
+fn alloc_only() {
+    cargo("test", &["--lib"]);
+}
@@ -0,0 +1,70 @@
+//! the C interface as C sees it: the header in include/bfint.h has to be the one build.rs makes
+//! from src/ffi.rs, and tests/c/ffi.c is compiled against it and linked to the library built as
+//! a shared library, the way the README says to
+#![cfg(feature = "ffi")]
+use std::path::Path;
+use std::process::Command;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+///the header is committed so C programs can use it without building bfint first, and has to
+///keep up with src/ffi.rs
+#[test]
+fn header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/bfint.h"));
+    let committed = include_str!("../include/bfint.h");
+    assert!(
+        generated == committed,
+        "include/bfint.h is out of date, copy {}/bfint.h over it",
+        env!("OUT_DIR")
+    );
+}
+
+///runs the command and panics with what it wrote if it fails
+fn run(command: &mut Command) -> String {
+    let output = command
+        .output()
+        .unwrap_or_else(|e| panic!("{:?} didn't start: {}", command, e));
+    assert!(
+        output.status.success(),
+        "{:?} failed\n{}{}",
+        command,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[cfg(unix)]
+#[test]
+fn c_program() {
+    //a target directory of its own, since the one these tests were built in is locked while
+    //they run
+    let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    run(Command::new(env!("CARGO"))
+        .current_dir(MANIFEST_DIR)
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "ffi",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--target-dir")
+        .arg(&target));
+    let library = target.join("debug");
+    let program = target.join("ffi-test");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    run(Command::new(compiler)
+        .arg(Path::new(MANIFEST_DIR).join("tests/c/ffi.c"))
+        .arg("-I")
+        .arg(Path::new(MANIFEST_DIR).join("include"))
+        .arg("-L")
+        .arg(&library)
+        .arg(format!("-Wl,-rpath,{}", library.display()))
+        .arg("-lbfint")
+        .arg("-o")
+        .arg(&program));
+    assert_eq!(run(&mut Command::new(&program)), "ok\n");
+}
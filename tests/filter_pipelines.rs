@@ -0,0 +1,321 @@
+//! runs the bfint binary as a filter in real pipelines, with its stdin and stdout connected to
+//! other processes, and checks it behaves the way a unix filter should: bytes go through as they
+//! are, the end of the input is what --eof says, a closed stdout is what --epipe says, every
+//! way a run can end has its own exit code, stdout only ever has what the program wrote and
+//! stderr only whole lines, none at all with -q and json ones with --message-format json
+#![cfg(feature = "cli")]
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+///how a run of bfint went
+struct Ran {
+    stdout: Vec<u8>,
+    stderr: String,
+    code: Option<i32>,
+}
+
+///a directory for the programs one test runs, which goes away with it
+struct Bfint {
+    directory: PathBuf,
+}
+
+impl Bfint {
+    ///a directory of its own for the test, so tests running side by side don't share files
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-pipelines-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Bfint { directory }
+    }
+    ///a file holding the program, named after it
+    fn program(&self, name: &str, source: &str) -> PathBuf {
+        let path = self.directory.join(name);
+        std::fs::write(&path, source).expect("the temporary directory can be written to");
+        path
+    }
+    ///"bfint run" with the options and the program, not started yet
+    fn command(&self, options: &[&str], program: &Path) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_bfint"));
+        command.arg("run").args(options).arg(program);
+        command
+    }
+    ///starts the command with its stdin and stdout as given, and stderr kept for later
+    fn spawn(&self, mut command: Command, stdin: Stdio, stdout: Stdio) -> Child {
+        command
+            .stdin(stdin)
+            .stdout(stdout)
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("bfint starts")
+    }
+    ///runs the program with the options, writing the input into a pipe on its stdin from
+    ///another thread, the way a generator in front of it would
+    fn run(&self, options: &[&str], program: &Path, input: &[u8]) -> Ran {
+        let mut child = self.spawn(
+            self.command(options, program),
+            Stdio::piped(),
+            Stdio::piped(),
+        );
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = input.to_vec();
+        //a program that stops reading early closes the pipe, which isn't the generator's problem
+        let writer = std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+        let output = child.wait_with_output().expect("bfint can be waited for");
+        let _ = writer.join();
+        Ran {
+            stdout: output.stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            code: output.status.code(),
+        }
+    }
+}
+
+impl Drop for Bfint {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+///checks the run exited with the code and wrote exactly the bytes to stdout. whatever it wrote
+///to stderr has to be whole lines, and nothing at all when quiet
+fn expect(ran: &Ran, code: i32, stdout: &[u8], quiet: bool) {
+    assert_eq!(ran.code, Some(code), "stderr: {:?}", ran.stderr);
+    assert_eq!(ran.stdout, stdout);
+    if quiet {
+        assert_eq!(ran.stderr, "");
+    }
+    assert!(
+        ran.stderr.is_empty() || ran.stderr.ends_with('\n'),
+        "left a line unfinished on stderr: {:?}",
+        ran.stderr
+    );
+}
+
+///every byte there is, and the ones a text mode would turn into something else
+fn every_byte() -> Vec<u8> {
+    let mut bytes: Vec<u8> = (0..=255).collect();
+    bytes.extend(b"\r\n\x1a\r");
+    bytes
+}
+
+#[test]
+fn every_byte_goes_through_unchanged() {
+    let bfint = Bfint::new("bytes");
+    let bytes = every_byte();
+    let copy = bfint.program("copy.bf", &",.".repeat(bytes.len()));
+    expect(&bfint.run(&[], &copy, &bytes), 0, &bytes, true);
+}
+
+#[test]
+fn the_end_of_the_input_is_what_eof_says() {
+    let bfint = Bfint::new("eof");
+    let reads = bfint.program("reads.bf", ",.,.,.,.");
+    for (eof, expected) in [
+        ("zero", &b"ab\0\0"[..]),
+        ("max", b"ab\xff\xff"),
+        ("unchanged", b"abbb"),
+    ] {
+        expect(
+            &bfint.run(&["--eof", eof], &reads, b"ab"),
+            0,
+            expected,
+            true,
+        );
+    }
+    //no input at all is the end of the input
+    let mut child = bfint.spawn(bfint.command(&[], &reads), Stdio::null(), Stdio::piped());
+    let mut stdout = vec![];
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut stdout)
+        .expect("the pipe can be read");
+    let ran = Ran {
+        stdout,
+        stderr: String::new(),
+        code: child.wait().expect("bfint can be waited for").code(),
+    };
+    expect(&ran, 0, b"\0\0\0\0", true);
+}
+
+///rot13 twice over, with bfint on both ends of the pipe between them
+#[test]
+fn bfint_into_bfint_gives_back_what_went_in() {
+    let bfint = Bfint::new("rot13");
+    let rot13 = bfint.program("rot13.bf", include_str!("fixtures/rot13.bf"));
+    let text = include_bytes!("fixtures/rot13.in");
+    let mut first = bfint.spawn(bfint.command(&[], &rot13), Stdio::piped(), Stdio::piped());
+    let between = first.stdout.take().expect("stdout is piped");
+    let second = bfint.spawn(
+        bfint.command(&[], &rot13),
+        Stdio::from(between),
+        Stdio::piped(),
+    );
+    let mut stdin = first.stdin.take().expect("stdin is piped");
+    stdin
+        .write_all(text)
+        .expect("the first bfint reads its input");
+    drop(stdin);
+    let output = second.wait_with_output().expect("bfint can be waited for");
+    let code = first.wait().expect("bfint can be waited for").code();
+    let ran = Ran {
+        stdout: output.stdout,
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        code: output.status.code(),
+    };
+    expect(&ran, 0, text, true);
+    assert_eq!(code, Some(0), "the first bfint failed");
+}
+
+///a program that never stops writing, into one that reads three bytes and stops
+#[test]
+fn a_closed_stdout_is_what_epipe_says() {
+    let bfint = Bfint::new("epipe");
+    let forever = bfint.program("forever.bf", "+[.]");
+    let three = bfint.program("three.bf", ",.,.,.");
+    for (epipe, expected) in [("error", 141), ("ok", 0)] {
+        let mut writer = bfint.spawn(
+            bfint.command(&["--epipe", epipe], &forever),
+            Stdio::null(),
+            Stdio::piped(),
+        );
+        let between = writer.stdout.take().expect("stdout is piped");
+        let reader = bfint.spawn(
+            bfint.command(&[], &three),
+            Stdio::from(between),
+            Stdio::piped(),
+        );
+        let read = reader.wait_with_output().expect("bfint can be waited for");
+        let mut stderr = String::new();
+        writer
+            .stderr
+            .take()
+            .expect("stderr is piped")
+            .read_to_string(&mut stderr)
+            .expect("the pipe can be read");
+        let ran = Ran {
+            stdout: vec![],
+            stderr,
+            code: writer.wait().expect("bfint can be waited for").code(),
+        };
+        expect(&ran, expected, b"", true);
+        assert_eq!(read.stdout, b"\x01\x01\x01");
+        assert!(read.status.success(), "the reader failed");
+    }
+}
+
+///what the program wrote before it stopped is all there is on stdout, whatever stopped it
+#[test]
+fn every_way_a_run_ends_has_its_own_exit_code() {
+    let bfint = Bfint::new("exits");
+    let exits: [(&str, &str, i32, &[u8]); 6] = [
+        ("", "++.", 0, b"\x02"),
+        ("", "++.<", 1, b"\x02"),
+        ("--no-such-option", "++.", 2, b""),
+        ("", "++.[", 3, b""),
+        ("--max-steps 1000", "++.[]", 4, b"\x02"),
+        ("--max-output 1", "++..", 4, b"\x02"),
+    ];
+    for (options, source, code, expected) in exits {
+        let program = bfint.program("exit.bf", source);
+        let options: Vec<&str> = options.split_whitespace().collect();
+        let ran = bfint.run(&options, &program, b"");
+        expect(&ran, code, expected, code == 0);
+        if code != 0 {
+            assert!(
+                !ran.stderr.is_empty(),
+                "{:?} didn't say what went wrong",
+                source
+            );
+        }
+    }
+}
+
+///asking bfint to say more still keeps it all off stdout
+#[test]
+fn stats_and_verbose_only_write_to_stderr() {
+    let bfint = Bfint::new("stats");
+    let bytes = every_byte();
+    let copy = bfint.program("copy.bf", &",.".repeat(bytes.len()));
+    let ran = bfint.run(&["--stats", "-vv"], &copy, &bytes);
+    expect(&ran, 0, &bytes, false);
+    assert!(
+        ran.stderr.contains("steps"),
+        "--stats didn't say anything: {:?}",
+        ran.stderr
+    );
+}
+
+///-q leaves nothing on stderr but errors, even where -v would have said something
+#[test]
+fn quiet_is_silent() {
+    let bfint = Bfint::new("quiet");
+    let bytes = every_byte();
+    let copy = bfint.program("copy.bf", &",.".repeat(bytes.len()));
+    let ran = bfint.run(&["-v"], &copy, &bytes);
+    expect(&ran, 0, &bytes, false);
+    assert!(!ran.stderr.is_empty(), "-v didn't say anything");
+    expect(&bfint.run(&["-q"], &copy, &bytes), 0, &bytes, true);
+    let fails = bfint.program("fails.bf", "++.<");
+    let ran = bfint.run(&["-q"], &fails, b"");
+    expect(&ran, 1, b"\x02", false);
+    assert_eq!(
+        ran.stderr.lines().next().map(|line| line.contains("error")),
+        Some(true)
+    );
+}
+
+///every line is a json object with the kind of message and the message, whatever it is
+#[test]
+fn json_messages_are_one_a_line() {
+    let bfint = Bfint::new("json");
+    let program = bfint.program("fails.bf", "++.>+.<<");
+    let ran = bfint.run(
+        &["--message-format", "json", "-vv", "--stats", "--progress"],
+        &program,
+        b"",
+    );
+    expect(&ran, 1, b"\x02\x01", false);
+    let mut kinds = vec![];
+    for line in ran.stderr.lines() {
+        let message: serde_json::Value =
+            serde_json::from_str(line).unwrap_or_else(|e| panic!("{:?} isn't json: {}", line, e));
+        assert!(message["message"].is_string(), "{:?}", line);
+        kinds.push(message["kind"].as_str().expect("it has a kind").to_string());
+    }
+    for kind in ["error", "stats"] {
+        assert!(
+            kinds.iter().any(|k| k == kind),
+            "no {} in {:?}",
+            kind,
+            kinds
+        );
+    }
+}
+
+///the progress line is drawn on stderr, over and over, and stdout still only has the output
+#[test]
+fn progress_stays_off_stdout() {
+    let bfint = Bfint::new("progress");
+    let bytes = every_byte();
+    let copy = bfint.program("copy.bf", &",.".repeat(bytes.len()));
+    expect(
+        &bfint.run(&["--progress", "--stats"], &copy, &bytes),
+        0,
+        &bytes,
+        false,
+    );
+    //and one that runs long enough to draw it, almost two million steps
+    let busy = bfint.program("busy.bf", "-[>-[>++++++++[-]<-]<-].");
+    let ran = bfint.run(&["--progress"], &busy, b"");
+    expect(&ran, 0, b"\0", false);
+    assert!(
+        ran.stderr.contains("steps"),
+        "no progress: {:?}",
+        ran.stderr
+    );
+}
@@ -0,0 +1,8 @@
+a busy loop that keeps counting down for a while and then stops
+
+-[                              two hundred and fifty five times
+    >-[-]                       count a second cell all the way down
+    <-
+]
+then says so
+++++++++++[>++++++++++<-]>.+++++++++++.-.---------.>++++++++++.
@@ -0,0 +1,29 @@
+the classic hello world with every line explained
+
+++++++++                set cell 0 to 8
+[
+    >++++               add 4 to cell 1 which will always set it to 4
+    [                   as the cell will be cleared by the loop
+        >++             add 2 to cell 2
+        >+++            add 3 to cell 3
+        >+++            add 3 to cell 4
+        >+              add 1 to cell 5
+        <<<<-           decrement the loop counter in cell 1
+    ]                   loop until cell 1 is zero; the number of iterations is 4
+    >+                  add 1 to cell 2
+    >+                  add 1 to cell 3
+    >-                  subtract 1 from cell 4
+    >>+                 add 1 to cell 6
+    [<]                 move back to the first zero cell found; this will be cell 1
+    <-                  decrement the loop counter in cell 0
+]                       loop until cell 0 is zero; the number of iterations is 8
+
+>>.                     cell 2 has 72 which is "H"
+>---.                   subtract 3 from cell 3 to get 101 which is "e"
++++++++..+++.           likewise for "llo" from cell 3
+>>.                     cell 5 is 32 for the space
+<-.                     subtract 1 from cell 4 for 87 to give a "W"
+<.                      cell 3 was set to "o" from the end of "Hello"
++++.------.--------.    cell 3 for "rl" and "d"
+>>+.                    add 1 to cell 5 gives us an exclamation point
+>++.                    and finally a newline from cell 6
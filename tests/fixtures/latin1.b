@@ -0,0 +1,2 @@
+caf crme; a comment in latin1 that is not utf8
+++++++++[>+++++++++<-]>.+.
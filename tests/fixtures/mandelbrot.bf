@@ -0,0 +1,61 @@
+a small mandelbrot set drawn in text
+
+the picture is 26 columns by 11 rows from minus 2 to 0 point 5 across
+and from 1 to minus 1 down with at most 8 rounds of z squared plus c
+for every point and a character for how many rounds it took to escape
+numbers are fixed point in tenths kept as a sign and a size in two cells
+so they fit in bytes and squares are looked up one step at a time
+it takes about five and a half million steps so it stays well inside
+the limit bfint test runs programs with
+
+>>>>>>>>>>>>>>>>>>>>>>>>>>>+<<<<<<<<<<++++++++++>>>>>>>>>>>>>>>>>+++++++
+++++[-<<<<<<<<<<<<<<<++++++++++++++++++++<<<<<<<<<<<<<<<<<<<++++++++++++
+++++++++++++++[->>>>>>>>+[->>>>>>>>>>>>>[->>>>>+<<<<+<]>[-<+>]>>>>[-[-[-
+[-<<+>>[-<<+>>[-<<+>>[-<<+>>[-<<++>>[-<<++>>[-<<++>>[-<<++>>[-<<++>>[-<<
+++>>[-<<+++>>[-<<+++>>[-<<+++>>[-<<+++>>[-<<++++>>[-<<++++>>[-<<++++>>>-
+]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>
+[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<+<[<
+<<<<<<<<<<<<<+>>>>>>>>>>>>>>[-]]<<<[->>>+<<<<+>]<[->+<]>>>>[-[-[-[-<<<<<
+<+>>>>>>[-<<<<<<+>>>>>>[-<<<<<<+>>>>>>[-<<<<<<+>>>>>>[-<<<<<<++>>>>>>[-<
+<<<<<++>>>>>>[-<<<<<<++>>>>>>[-<<<<<<++>>>>>>[-<<<<<<++>>>>>>[-<<<<<<++>
+>>>>>[-<<<<<<+++>>>>>>[-<<<<<<+++>>>>>>[-<<<<<<+++>>>>>>[-<<<<<<+++>>>>>
+>[-<<<<<<++++>>>>>>[-<<<<<<++++>>>>>>[-<<<<<<++++>>>>>>>-]>[->]<]>[->]<]
+>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[
+->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<+<[<<<<<<<<<<<<<<+>
+>>>>>>>>>>>>>[-]]<<<<<<<<<<<<<<[-<+>>+<]>[-<+>]<<[<<+>>[-]]<+<[->-<]>[->
+>>>>>>>>>>>>>[->>+<<<<+>>]<<[->>+<<]<<[->>>>>>+<<<<+<<]>>[-<<+>>]>>>>[-[
+-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[-[
+-[-[->-]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>
+[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[-
+>]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]
+<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<]>[->]<+
+<[<<<<<<<<<<<<<<+>>>>>>>>>>>>>>[-]]<<<<<<<<<<<<<<<<]>>[-<+>>+<]>[-<+>]<<
+[<<+>>[-]]>[-]<<+<[->->>>>>>>>>>>>>>[-]<<<<[-]<<<<<<<<<<<]>[->>>>>>>>>>>
+[->>>>>>>>>>+<<<<<<<<<+<]>[-<+>]>[->>>>>>+<<<<<<<+>]<[->+<]>>>>+++++>>>>
+>[-<<[->+<<<<-[>-]>[<+++++>>>>>>+<<<<<->]<+>>]>[-<+>]>]<<<<<[-]>>>[-]>>>
+[->+<]<<<<<<<<<<<<<<<<<<<<<<<<<<[-<+<+>>]<<[->>+<<]>>>[-<<+<+>>>]<<<[->>
+>+<<<]>[->>>>>>>>>+<<<<<<<<<[->>>>>>>>>-<<<<<<<<<]]>[-]>>>>>>>>>>>>>>>[-
+]<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>[-]>[->+<]<<<<[->>>>>>+<<<<<<]<<<<[->>
+>>>>>>>+<<<+<<<<<<]>>>>>>[-<<<<<<+>>>>>>]<<<[->>>>>>>+<<<<+<<<]>>>[-<<<+
+>>>]>>>+>+<[->-[>-]>[<<[-<<<<+>>>>]>>->]<+<<]>[[-<<<<<+>>>>>]<<<<<<<<<<<
+<<<<<<<<<+>>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<[->>>>>>>>+<<<<<<<+<]>[-<+>]+
+>>>>>>>[-<<<<<<<->>>>>>>>>>>>>>>>>>[-<<<<<<<+>>>>>>>]<<<<<<<<<<<]<<<<<<<
+[->>>>>>>>>>>>>>>>>>[-<<<<<<<<+>>>>>>>>]<<<<<<<<<<<<<<<<<<]<[-]>>>[->>>>
+>>>>+<<<+<<<<<]>>>>>[-<<<<<+>>>>>]<<<<[->>>>>>>>+<<<<+<<<<]>>>>[-<<<<+>>
+>>]>>>+>+<[->-[>-]>[<<[-<<+>>]>>->]<+<<]>[[-<<<+>>>]<<<<<<<<<<<<<<<<<<<+
+>>>>>>>>>>>>>>>>>>>]<<<<<<<<<<<<<<<<<<<<<<<+>>++++++++<<[->+<<+>]<[->+<]
+>>[->-<]>[>>>+<<<[-]]>>>>>]<<]<<<<<<<++++++++++++++++++++++++++++++++>>[
+->>+<+<]>[-<+>]>[-<<<<++++++++++++++>>>>[-<+>]]<[->+<]>[-<<<<-->>>>[-<+>
+]]<[->+<]>[-<<<<++++++++++++++>>>>[-<+>]]<[->+<]>[-<<<<+>>>>[-<+>]]<[->+
+<]>[-<<<<++>>>>[-<+>]]<[->+<]>[-<<<<------------------>>>>[-<+>]]<[->+<]
+>[-<<<<->>>>[-<+>]]<[->+<]>[-<<<<------->>>>[-<+>]]<[->+<]<<<.[-]>>>>[-]
+>[-]>>>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<[-]>>>>>>>>>>>>>>>>[-]<<<<<<<<<<<<<<
+<<<<<<[-]>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<<<+>>>>>>>>+>>>>>>]<<<<<<[->>>>>>
++<<<<<<]<<<<<<<<[>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<[-]]>>>>>>>>>>+>>>>>
+>>[-<<<<<<<->>>>->>>]<<<<<<<[->+<]<<<<<<<<<<<<<<<]>>>>>>>>>>>>>>>>[-]>>>
+[-]<<<<<<<<<<<<<<<<<<++++++++++.[-]>>>>>>>>>>>>>>>>[-<<<<<<<<<<<<+>>>>>>
+>>+>>>>]<<<<[->>>>+<<<<]<<<<<<<<[>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<<[-]]
+>>>>>>>>>>+>>>>>>>[-<<<<<<<->>->>>>>]<<<<<<<[->>>+<<<]>>[-<<<<<<<<<<<<+>
+>>>>>>>+>>>>]<<<<[->>>>+<<<<]<<<<<<<<[>>>>>>>>>>>>>>>>>+<<<<<<<<<<<<<<<<
+<[-]]>>>>>>>>>>+>>>>>>>[-<<<<<<<->>->>>>>]<<<<<<<[->>>+<<<]>>>>>>>>>>>>>
+>>>>>>]
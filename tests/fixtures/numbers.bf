@@ -0,0 +1,24 @@
+multiplies six by seven and prints the answer in decimal
+
+++++++[>+++++++<-]>             cell 1 holds the number
+
+each time around the number is divided by ten and the quotient
+becomes the next number four cells to the right; the remainder
+plus one is left behind as a digit
+[
+    >>++++++++++<<              the divisor
+    [->+>-[>+>>]>[+[-<+>]>+>>]<<<<<<]
+                                leaves 0 then n then (ten minus remainder) then remainder then quotient
+    >[-]                        throw away the copy of the number
+    >[-]                        and what is left of the divisor
+    >+                          the digit plus one so a zero digit is not a zero cell
+    >                           on to the quotient
+]
+
+walk back over the digits printing them in the order they were written down
+<[
+    -                           the digit itself
+    >++++++[<++++++++>-]<.      made into an ascii digit
+    [-]<<<<                     on to the one before it
+]
+++++++++++.                     and a newline
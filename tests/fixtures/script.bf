@@ -0,0 +1,3 @@
+#!/usr/bin/env bfint
+prints ok and a newline; the line above lets it run on its own
++++++++++++[>++++++++++<-]>+.----.>++++++++++.
@@ -0,0 +1,8 @@
+the sierpinski triangle in stars by daniel b cristofani
+from his collection of small brainfuck programs
+
+++++++++[>+>++++<<-]>++>>+<[-[>>+<<-]+>>]>+[
+    -<<<[
+        ->[+[-]+>++>>>-<<]<[<]>>++++++[<<+++++>>-]+<<++.[-]<<
+    ]>.>+[>>]>+
+]
@@ -0,0 +1,116 @@
+//! profiles written as folded stacks for flame graphs: a program with loops inside loops counted
+//! a step at a time, and the lines for it read back into the nesting of the loops and how many
+//! steps ran in each. optimized, what was taken out isn't counted, and what's left is still
+//! counted in the loops it came from, named by where they were in the source
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::listing::{folded, Profile};
+use bfint::observe::Observation;
+use bfint::TuringMachine;
+use std::collections::BTreeMap;
+
+///two loops inside the outer one, the second with one more inside it, each gone round three
+///times for each of the two times the outer one goes round
+const NESTED: &str = "++\n[>+++[>+<-]>[>++[-]<-]<<-]";
+
+///the folded stacks as the loops of every line and the count at the end of it
+fn stacks(folded: &str, name: &str) -> BTreeMap<Vec<String>, u64> {
+    folded
+        .lines()
+        .map(|line| {
+            let (stack, count) = line.rsplit_once(' ').expect("a line ends with a count");
+            let mut frames = stack.split(';');
+            assert_eq!(frames.next(), Some(name), "{}", line);
+            (
+                frames.map(str::to_string).collect(),
+                count.parse().expect("the count is a number"),
+            )
+        })
+        .collect()
+}
+
+fn stack(frames: &[&str]) -> Vec<String> {
+    frames.iter().map(|frame| frame.to_string()).collect()
+}
+
+///every step the machine takes, counted against its instruction
+fn profile(mut tm: TuringMachine) -> Profile {
+    let mut profile = Profile::new(tm.program());
+    for observation in tm.iter_steps() {
+        match observation {
+            Observation::Step(step) => profile.counts[step.pc] += 1,
+            Observation::Finished(outcome) => {
+                outcome.expect("it runs");
+            }
+        }
+    }
+    profile
+}
+
+///the stacks of NESTED and how many steps ran in each, with the count of the ones in no loop
+fn nested(outside: u64) -> BTreeMap<Vec<String>, u64> {
+    BTreeMap::from([
+        //"++" and the "[" going into the outer loop
+        (stack(&[]), outside),
+        //">+++", "[", ">", "[", "<<-" and "]", twice
+        (stack(&["loop@2:1"]), 22),
+        //">+<-" and "]"
+        (stack(&["loop@2:1", "loop@2:6"]), 30),
+        //">++", "[", "<-" and "]"
+        (stack(&["loop@2:1", "loop@2:13"]), 42),
+        //"-" and "]" twice, six times
+        (stack(&["loop@2:1", "loop@2:13", "loop@2:17"]), 24),
+    ])
+}
+
+#[test]
+fn the_hierarchy_and_the_counts() {
+    let tm = TuringMachine::new(NESTED, &Lang::Brainfuck).expect("it parses");
+    let program = tm.program().clone();
+    let profile = profile(tm);
+    let stacks = stacks(&folded(&program, &profile, "prog"), "prog");
+    assert_eq!(stacks, nested(3));
+    //every step is somewhere
+    assert_eq!(stacks.values().sum::<u64>(), profile.counts.iter().sum());
+}
+
+///a ";" or " " in the name would be read as the start of a frame or of the count
+#[test]
+fn the_name_is_one_frame() {
+    let tm = TuringMachine::new("+[-]", &Lang::Brainfuck).expect("it parses");
+    let program = tm.program().clone();
+    let profile = profile(tm);
+    let folded = folded(&program, &profile, "my prog;v2");
+    assert_eq!(folded, "my_prog_v2 2\nmy_prog_v2;loop@1:2 2\n");
+}
+
+///bfint --profile-folded, with "+--+" in front that -O2 takes out
+#[cfg(feature = "cli")]
+#[test]
+fn optimized() {
+    let directory = std::env::temp_dir().join(format!("bfint-folded-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    let program = directory.join("prog.b");
+    let out = directory.join("out.folded");
+    std::fs::write(&program, format!("+--+{}", NESTED)).expect("it can be written");
+    let run = |level: &str| {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .arg(level)
+            .arg("--profile-folded")
+            .arg(&out)
+            .arg(&program)
+            .output()
+            .expect("bfint starts");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        std::fs::read_to_string(&out).expect("it wrote the stacks")
+    };
+    let unoptimized = run("-O0");
+    let optimized = run("-O2");
+    let _ = std::fs::remove_dir_all(&directory);
+    assert_eq!(stacks(&unoptimized, "prog.b"), nested(7));
+    assert_eq!(stacks(&optimized, "prog.b"), nested(3));
+}
@@ -0,0 +1,131 @@
+//! programs read from files: a byte order mark is passed over, "\r\n" line endings don't move
+//! the positions errors are reported at, bytes that aren't utf-8 are one character each, and a
+//! file that isn't there is reported with its path, the same from the library and from bfint
+#![cfg(feature = "std")]
+use bfint::program::{LoadError, Program};
+use bfint::{lang::Lang, optimize::Level, ParseError, Position, TuringMachine};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-from-file-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+    ///a file in it holding the bytes
+    fn file(&self, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        std::fs::write(&path, bytes).expect("the temporary directory can be written to");
+        path
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///what the program in the file printed
+fn printed(path: &PathBuf) -> Vec<u8> {
+    let mut tm = TuringMachine::from_file(path, &Lang::Brainfuck).expect("it loads");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.run().expect("it runs");
+    let output = bfint::shared::lock(&output).clone();
+    output
+}
+
+///the parse error the program in the file has
+fn parse_error(path: &PathBuf) -> ParseError {
+    match TuringMachine::from_file(path, &Lang::Brainfuck) {
+        Err(LoadError::ParseError(e)) => e,
+        other => panic!("it doesn't parse: {:?}", other.err()),
+    }
+}
+
+///why the program in the file couldn't be loaded
+fn load_error(path: &PathBuf) -> LoadError {
+    TuringMachine::from_file(path, &Lang::Brainfuck)
+        .err()
+        .expect("it doesn't load")
+}
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn a_byte_order_mark() {
+    let directory = Directory::new("bom");
+    let path = directory.file("bom.bf", b"\xef\xbb\xbf+++.");
+    assert_eq!(printed(&path), [3]);
+    let tm = TuringMachine::from_file(&path, &Lang::Brainfuck).expect("it loads");
+    assert_eq!(tm.position(), Some(at(1, 1)));
+    //the mark isn't the first column
+    let path = directory.file("unmatched.bf", b"\xef\xbb\xbf[");
+    assert_eq!(parse_error(&path), ParseError::UnmatchedOpen(at(1, 1)));
+}
+
+#[test]
+fn crlf_line_endings() {
+    let directory = Directory::new("crlf");
+    let path = directory.file("crlf.bf", b"+\r\n +\r\n  ]");
+    assert_eq!(parse_error(&path), ParseError::UnmatchedClose(at(3, 3)));
+    let path = directory.file("runs.bf", b"++\r\n[>+<-]\r\n>.\r\n");
+    assert_eq!(printed(&path), [2]);
+    let tm = TuringMachine::from_file(&path, &Lang::Brainfuck).expect("it loads");
+    assert_eq!(tm.find(|_| true).last(), Some(&at(3, 2)));
+}
+
+///0xe9 is "é" in latin-1, and on its own it isn't utf-8
+#[test]
+fn bytes_that_arent_utf8() {
+    let directory = Directory::new("latin-1");
+    let path = directory.file("latin-1.bf", b"caf\xe9 +.\n\xe9\xe9]");
+    assert_eq!(parse_error(&path), ParseError::UnmatchedClose(at(2, 3)));
+    let path = directory.file("runs.bf", b"caf\xe9 +.");
+    assert_eq!(printed(&path), [1]);
+    let program = Program::from_file(&path, &Lang::Brainfuck, Level::O0).expect("it loads");
+    let tm = TuringMachine::with_program(Arc::new(program));
+    assert_eq!(tm.position(), Some(at(1, 6)));
+}
+
+#[test]
+fn a_missing_file() {
+    let directory = Directory::new("missing");
+    let path = directory.0.join("missing.bf");
+    let message = format!("no such file: {}", path.display());
+    let error = load_error(&path);
+    assert!(
+        matches!(&error, LoadError::Io(_, e) if e.kind() == std::io::ErrorKind::NotFound),
+        "{:?}",
+        error
+    );
+    assert_eq!(error.to_string(), message);
+    let error = Program::from_file(&path, &Lang::Brainfuck, Level::O1).expect_err("it isn't there");
+    assert_eq!(error.to_string(), message);
+    //a directory is there, but can't be read like a file
+    let error = load_error(&directory.0);
+    assert!(
+        error.to_string().starts_with("could not read "),
+        "{}",
+        error
+    );
+    #[cfg(feature = "cli")]
+    {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .arg("run")
+            .arg(&path)
+            .output()
+            .expect("bfint starts");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(&message), "{}", stderr);
+    }
+}
@@ -0,0 +1,201 @@
+//! one tank of fuel shared by three programs run as a pipeline, each reading what the one before
+//! it wrote: they stop at exactly the budget they were given between them, with the stage that
+//! ran out and where. and what optimized programs and evals burn, and bfint pipeline --max-steps
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::fuel::{Fuel, FuelCost};
+use bfint::lang::Lang;
+use bfint::optimize::Level;
+use bfint::TuringMachine;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///copies its input, adds one to every byte of it, and takes one away again
+const STAGES: [&str; 3] = [",[.,]", ",[+.,]", ",[-.,]"];
+const INPUT: &[u8] = b"abc";
+
+fn machine(source: &str, input: &[u8]) -> (TuringMachine, Arc<Mutex<Vec<u8>>>) {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    tm.input = Arc::new(Mutex::new(Cursor::new(input.to_vec())));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    (tm, output)
+}
+
+///runs the stages one after the other, each on what the one before wrote, burning the fuel if
+///there's any. gives back how every stage ended and how many steps it took, up to the one that
+///didn't halt
+fn pipeline(fuel: Option<&Fuel>) -> Vec<(RunOutcome, u64)> {
+    let mut input = INPUT.to_vec();
+    let mut ended = vec![];
+    for (index, source) in STAGES.iter().enumerate() {
+        let (mut tm, output) = machine(source, &input);
+        let outcome = match fuel {
+            Some(fuel) => tm.run_with_fuel(&fuel.for_stage(index + 1)),
+            None => tm.run(),
+        }
+        .expect("it runs");
+        ended.push((outcome, tm.steps));
+        if outcome != RunOutcome::Halted {
+            break;
+        }
+        input = bfint::shared::lock(&output).clone();
+    }
+    ended
+}
+
+#[test]
+fn a_pipeline_of_three() {
+    let steps: Vec<u64> = pipeline(None).iter().map(|(_, steps)| *steps).collect();
+    let total: u64 = steps.iter().sum();
+    //exactly enough
+    let fuel = Fuel::new(total);
+    assert!(pipeline(Some(&fuel))
+        .iter()
+        .all(|(outcome, _)| *outcome == RunOutcome::Halted));
+    assert_eq!(fuel.left(), 0);
+    //a few short, or one short, runs out in the third stage with the steps it had left
+    for short in [1, 5, steps[2]] {
+        let fuel = Fuel::new(total - short);
+        let ended = pipeline(Some(&fuel));
+        assert_eq!(ended.len(), 3);
+        let (outcome, third) = ended[2];
+        assert_eq!(third, steps[2] - short, "{} short", short);
+        let (run_for, _) = machine(STAGES[2], b"bcd");
+        let mut expected = run_for;
+        for _ in 0..third {
+            expected.step().expect("it runs");
+        }
+        assert_eq!(
+            outcome,
+            RunOutcome::FuelExhausted {
+                stage: Some(3),
+                position: expected.position().expect("it's not at the end")
+            }
+        );
+        let burned: u64 = ended.iter().map(|(_, steps)| steps).sum();
+        assert_eq!(burned, total - short);
+        assert_eq!(fuel.left(), 0);
+    }
+    //not even enough for the first
+    let ended = pipeline(Some(&Fuel::new(steps[0] - 1)));
+    assert!(matches!(
+        ended[..],
+        [(RunOutcome::FuelExhausted { stage: Some(1), .. }, _)]
+    ));
+}
+
+///a machine that ran out carries on where it stopped once the tank has more in it
+#[test]
+fn more_fuel() {
+    let (mut tm, output) = machine(STAGES[1], INPUT);
+    let fuel = Fuel::new(7);
+    assert!(matches!(
+        tm.run_with_fuel(&fuel),
+        Ok(RunOutcome::FuelExhausted { stage: None, .. })
+    ));
+    assert_eq!(tm.steps, 7);
+    fuel.add(1000);
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), b"bcd");
+    let (mut unlimited, _) = machine(STAGES[1], INPUT);
+    unlimited.run().expect("it runs");
+    assert_eq!(fuel.left(), 1007 - unlimited.steps);
+}
+
+///an optimized program gets exactly as far on the same fuel, unless what it put together is
+///counted as one
+#[test]
+fn optimized() {
+    let source = "++++++++[>++++++++<-]>+.[-]";
+    let (mut written, _) = machine(source, b"");
+    written.run().expect("it runs");
+    let optimized = |units: u64, cost: FuelCost| {
+        let (mut tm, _) = machine(source, b"");
+        tm.optimize(&Level::O2.pipeline(), |_, _| {});
+        let mut fuel = Fuel::new(units);
+        fuel.cost = cost;
+        let outcome = tm.run_with_fuel(&fuel).expect("it runs");
+        (outcome, fuel.left())
+    };
+    assert_eq!(
+        optimized(written.steps, FuelCost::Expanded),
+        (RunOutcome::Halted, 0)
+    );
+    assert!(matches!(
+        optimized(written.steps - 1, FuelCost::Expanded),
+        (RunOutcome::FuelExhausted { .. }, 0)
+    ));
+    let (outcome, left) = optimized(written.steps, FuelCost::Ops);
+    assert_eq!(outcome, RunOutcome::Halted);
+    assert!(left > written.steps / 2, "{} left", left);
+}
+
+///what "%" runs burns the fuel of the machine it runs in, and stops when it's gone
+#[test]
+fn evals() {
+    //"+++" written to the tape, and evaled
+    let source = format!(
+        "{}>{}>{}<<%",
+        "+".repeat(43),
+        "+".repeat(43),
+        "+".repeat(43)
+    );
+    let mut tm = TuringMachine::new(&source, &Lang::BfEval).expect("it parses");
+    let fuel = Fuel::new(1000);
+    assert_eq!(tm.run_with_fuel(&fuel), Ok(RunOutcome::Halted));
+    //134 in the program and 3 in what it evaled
+    assert_eq!(fuel.left(), 1000 - 134 - 3);
+    let mut tm = TuringMachine::new(&source, &Lang::BfEval).expect("it parses");
+    let fuel = Fuel::new(135);
+    let outcome = tm.run_with_fuel(&fuel).expect("it runs");
+    assert!(
+        matches!(outcome, RunOutcome::FuelExhausted { .. }),
+        "{:?}",
+        outcome
+    );
+    assert_eq!(tm.tape()[0], 43 + 1);
+}
+
+///bfint pipeline --max-steps is one tank for every stage
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let directory = std::env::temp_dir().join(format!("bfint-fuel-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    for (index, source) in STAGES.iter().enumerate() {
+        std::fs::write(directory.join(format!("{}.b", index + 1)), source)
+            .expect("it can be written");
+    }
+    let steps: Vec<u64> = pipeline(None).iter().map(|(_, steps)| *steps).collect();
+    let total: u64 = steps.iter().sum();
+    let pipeline = |fuel: u64| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["pipeline", "--max-steps", &fuel.to_string()])
+            .args(["1.b", "2.b", "3.b"])
+            .current_dir(&directory)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("bfint starts");
+        child
+            .stdin
+            .take()
+            .expect("it's piped")
+            .write_all(INPUT)
+            .expect("it takes the input");
+        child.wait_with_output().expect("it finishes")
+    };
+    let enough = pipeline(total);
+    let short = pipeline(total - 1);
+    let _ = std::fs::remove_dir_all(&directory);
+    assert!(enough.status.success());
+    assert_eq!(enough.stdout, INPUT);
+    assert!(!short.status.success());
+    let said = String::from_utf8_lossy(&short.stderr);
+    assert!(said.contains("ran out of fuel at 1:"), "{}", said);
+    assert!(said.contains("(in stage "), "{}", said);
+}
@@ -0,0 +1,161 @@
+//! going to any step of a run with the debugger, forwards and backwards, on a program that reads
+//! its input and runs long enough to have checkpoints to go back to. wherever it lands, at step 0,
+//! in the middle of a loop or at the last step, the machine is the same as one that was run up
+//! to that step from the start
+#![cfg(feature = "std")]
+use bfint::debugger::{Debugger, StopReason};
+use bfint::lang::Lang;
+use bfint::observe::Observation;
+use bfint::{Position, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///reads how many times to go round, adding three to the third cell every time, and prints it
+const PROGRAM: &str = ",[>+++[>+<-]<-]>>.";
+const INPUT: [u8; 1] = [200];
+///",", "[" and ">>." outside, and 200 times ">+++", "[", "<-" and "]" in the outer loop and
+///">+<-" and "]" three times in the inner one
+const LAST: u64 = 5 + 200 * (8 + 3 * 5);
+
+///what the machine was at the step, as far as goto can tell
+#[derive(PartialEq, Debug)]
+struct State {
+    steps: u64,
+    pointer: usize,
+    position: Option<Position>,
+    cells: Vec<u8>,
+}
+
+fn state(tm: &TuringMachine) -> State {
+    State {
+        steps: tm.steps,
+        pointer: tm.pointer(),
+        position: tm.position(),
+        cells: tm.tape()[..4].to_vec(),
+    }
+}
+
+fn machine() -> TuringMachine {
+    let mut tm = TuringMachine::new(PROGRAM, &Lang::Brainfuck).expect("it parses");
+    tm.input = Arc::new(Mutex::new(Cursor::new(INPUT.to_vec())));
+    tm.output = Arc::new(Mutex::new(vec![]));
+    tm
+}
+
+///a machine run a step at a time from the start, up to the step
+fn run_to(step: u64) -> State {
+    let mut tm = machine();
+    for observation in tm.iter_steps().take(step as usize) {
+        if let Observation::Finished(_) = observation {
+            panic!("the run ended before step {}", step);
+        }
+    }
+    state(&tm)
+}
+
+fn debugger() -> Debugger {
+    Debugger::new(
+        TuringMachine::new(PROGRAM, &Lang::Brainfuck).expect("it parses"),
+        INPUT.to_vec(),
+    )
+}
+
+#[test]
+fn the_run_is_as_long_as_it_should_be() {
+    let mut debugger = debugger();
+    let stop = debugger.resume(None);
+    assert_eq!(stop.reason, StopReason::Halted);
+    assert_eq!(stop.output, [88]);
+    assert_eq!(debugger.tm.steps, LAST);
+}
+
+///every one of them gone to from the end of the run, and then from each other
+#[test]
+fn back_and_forth() {
+    let mut debugger = debugger();
+    debugger.resume(None);
+    //the start, a ">" in the inner loop most of the way through, past a few checkpoints, and
+    //the "." at the end
+    let mid_loop = 3000;
+    for step in [0, mid_loop, LAST, mid_loop, 1, 0, LAST - 1] {
+        debugger.goto(step).expect("it can go there");
+        assert_eq!(state(&debugger.tm), run_to(step), "step {}", step);
+    }
+    debugger.goto(mid_loop).expect("it can go there");
+    assert_eq!(debugger.tm.enclosing_loops().len(), 2);
+    //carrying on from there runs the rest as it ran the first time, input and all
+    let stop = debugger.resume(None);
+    assert_eq!(stop.reason, StopReason::Halted);
+    assert_eq!(stop.output, [88]);
+    assert_eq!(debugger.tm.steps, LAST);
+}
+
+///going ahead gives back what was printed on the way, going back doesn't print it again
+#[test]
+fn output_on_the_way() {
+    let mut debugger = debugger();
+    let stop = debugger.goto(LAST).expect("it can go there");
+    assert_eq!(stop.output, [88]);
+    //at the last step, with nothing left to run, which carrying on finds out
+    assert_eq!(stop.reason, StopReason::Stepped);
+    assert!(!debugger.tm.has_instructions_left());
+    let stop = debugger.goto(LAST - 1).expect("it can go there");
+    assert!(stop.output.is_empty());
+    assert_eq!(state(&debugger.tm), run_to(LAST - 1));
+    //past the end is the end
+    let stop = debugger.goto(LAST + 100).expect("it can go there");
+    assert_eq!(stop.reason, StopReason::Halted);
+    assert_eq!(debugger.tm.steps, LAST);
+}
+
+#[test]
+fn the_command() {
+    let mut debugger = debugger();
+    debugger.execute("goto 2000").expect("it's a step");
+    assert_eq!(debugger.tm.steps, 2000);
+    debugger.execute("g -1500").expect("it's a step");
+    assert_eq!(state(&debugger.tm), run_to(500));
+    debugger.execute("goto +7").expect("it's a step");
+    assert_eq!(debugger.tm.steps, 507);
+    //back further than the start is the start
+    debugger.execute("goto -10000").expect("it's a step");
+    assert_eq!(state(&debugger.tm), run_to(0));
+    let error = debugger.execute("goto soon").expect_err("it isn't a step");
+    assert_eq!(error.to_string(), "'soon' isn't a step like 1234");
+}
+
+///bfint debug --goto-step starts the session at the step
+#[cfg(feature = "cli")]
+#[test]
+fn from_the_command_line() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let program = std::env::temp_dir().join(format!("bfint-goto-{}.b", std::process::id()));
+    std::fs::write(&program, "++[>+++<-]>.").expect("it can be written");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["debug", "--goto-step", "7"])
+        .arg(&program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("bfint starts");
+    child
+        .stdin
+        .take()
+        .expect("it's piped")
+        .write_all(b"goto 0\nquit\n")
+        .expect("it takes the commands");
+    let output = child.wait_with_output().expect("it finishes");
+    let _ = std::fs::remove_file(&program);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("at 1:8: pointer 1, 7 steps, cells 0: 2 [3] 0"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("(bfdb) at 1:1: pointer 0, 0 steps"),
+        "{}",
+        stdout
+    );
+}
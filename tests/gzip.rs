@@ -0,0 +1,136 @@
+//! decompressing the files in tests/compressed, made with zlib: sierpinski.bf compressed with
+//! each of the three kinds of deflate block, and files that are broken in the ways a compressed
+//! file can be
+#![cfg(feature = "decompress")]
+use bfint::gzip::{self, GzipError};
+
+const SOURCE: &[u8] = include_bytes!("fixtures/sierpinski.bf");
+///compressed with zlib's level 0, which keeps it as it is in stored blocks
+const STORED: &[u8] = include_bytes!("compressed/sierpinski-stored.bf.gz");
+///compressed with the codes every deflate decompressor knows, zlib's Z_FIXED
+const FIXED: &[u8] = include_bytes!("compressed/sierpinski-fixed.bf.gz");
+///compressed with codes of its own, which zlib picks for it at level 9
+const DYNAMIC: &[u8] = include_bytes!("compressed/sierpinski-dynamic.bf.gz");
+
+///the kind of the first block, from the two bits after the one that says whether it's the last.
+///none of the files have anything between the 10 bytes of the header and the first block
+fn block_kind(compressed: &[u8]) -> u8 {
+    (compressed[10] >> 1) & 3
+}
+
+#[test]
+fn every_kind_of_block() {
+    for (compressed, kind) in [(STORED, 0), (FIXED, 1), (DYNAMIC, 2)] {
+        assert_eq!(block_kind(compressed), kind);
+        assert_eq!(
+            gzip::decompress(compressed, gzip::MAX_SIZE).ok().as_deref(),
+            Some(SOURCE),
+            "the block of kind {} didn't decompress",
+            kind
+        );
+    }
+}
+
+///several files one after the other decompress to all of them, like gunzip does
+#[test]
+fn files_one_after_the_other() {
+    let both = [DYNAMIC, STORED].concat();
+    assert_eq!(
+        gzip::decompress(&both[..], gzip::MAX_SIZE).ok(),
+        Some([SOURCE, SOURCE].concat())
+    );
+}
+
+#[test]
+fn truncated() {
+    for compressed in [STORED, FIXED, DYNAMIC] {
+        for length in [4, 12, compressed.len() / 2, compressed.len() - 1] {
+            assert!(
+                matches!(
+                    gzip::decompress(&compressed[..length], gzip::MAX_SIZE),
+                    Err(GzipError::Truncated)
+                ),
+                "cut off after {} bytes",
+                length
+            );
+        }
+    }
+}
+
+///the checksum, and the length after it, are the last 8 bytes
+#[test]
+fn wrong_checksum() {
+    for compressed in [STORED, FIXED, DYNAMIC] {
+        for byte in [8, 4] {
+            let mut broken = compressed.to_vec();
+            let index = broken.len() - byte;
+            broken[index] ^= 1;
+            assert!(matches!(
+                gzip::decompress(&broken[..], gzip::MAX_SIZE),
+                Err(GzipError::Corrupt(_))
+            ));
+        }
+    }
+}
+
+#[test]
+fn corrupt() {
+    assert!(matches!(
+        gzip::decompress(
+            &include_bytes!("compressed/corrupt.bf.gz")[..],
+            gzip::MAX_SIZE
+        ),
+        Err(GzipError::Corrupt(_))
+    ));
+    //a stored block whose length doesn't match its check
+    let mut broken = STORED.to_vec();
+    broken[11] ^= 1;
+    assert!(matches!(
+        gzip::decompress(&broken[..], gzip::MAX_SIZE),
+        Err(GzipError::Corrupt(_))
+    ));
+    //a block of the kind that doesn't exist
+    let mut broken = FIXED.to_vec();
+    broken[10] |= 6;
+    assert!(matches!(
+        gzip::decompress(&broken[..], gzip::MAX_SIZE),
+        Err(GzipError::Corrupt(_))
+    ));
+    assert!(matches!(
+        gzip::decompress(SOURCE, gzip::MAX_SIZE),
+        Err(GzipError::Corrupt(_))
+    ));
+}
+
+///decompressing stops as soon as it would go past the limit, whichever kind of block it's in
+#[test]
+fn too_large() {
+    for compressed in [STORED, FIXED, DYNAMIC] {
+        assert_eq!(
+            gzip::decompress(compressed, SOURCE.len()).ok().as_deref(),
+            Some(SOURCE)
+        );
+        assert!(matches!(
+            gzip::decompress(compressed, SOURCE.len() - 1),
+            Err(GzipError::TooLarge(limit)) if limit == SOURCE.len() - 1
+        ));
+    }
+    let both = [FIXED, FIXED].concat();
+    assert!(matches!(
+        gzip::decompress(&both[..], SOURCE.len() + 1),
+        Err(GzipError::TooLarge(limit)) if limit == SOURCE.len() + 1
+    ));
+}
+
+///the loader decompresses a file as it reads it, whatever it's called
+#[test]
+fn loaded_from_a_file() {
+    use bfint::program::{read_source, LoadError};
+    let compressed = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compressed");
+    let source = read_source(format!("{}/sierpinski-dynamic.bf.gz", compressed));
+    assert_eq!(source.ok().as_deref().map(str::as_bytes), Some(SOURCE));
+    assert!(matches!(
+        read_source(format!("{}/corrupt.bf.gz", compressed)),
+        Err(LoadError::Decompress(_, GzipError::Corrupt(_)))
+    ));
+}
@@ -0,0 +1,78 @@
+//! "@" with --ext-halt stopping the program from three loops deep, without going back out
+//! through any of them: the run halts there with what was printed before it written out, and
+//! says where it stopped. without the extension "@" is a comment
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::lang::Lang;
+use bfint::{Position, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+///three loops that would never end, one inside the other, with the "@" in the innermost one
+///after a "." that prints what's in its cell. the "." after every loop is never reached
+const NESTED: &str = "+[>++[>+++[.@-].]<.]<.";
+
+fn machine(source: &str, lang: &Lang) -> (TuringMachine, Arc<Mutex<Vec<u8>>>) {
+    let mut tm = TuringMachine::new(source, lang).expect("it parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    (tm, output)
+}
+
+#[test]
+fn from_three_loops_deep() {
+    let (mut tm, output) = machine(NESTED, &Lang::BfHalt);
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), [3]);
+    assert_eq!(
+        tm.halted_at(),
+        Some(Position {
+            line: 1,
+            column: 13
+        })
+    );
+    assert!(!tm.has_instructions_left());
+    //the cells are as they were at the "@"
+    assert_eq!(tm.tape()[..3], [1, 2, 3]);
+    assert_eq!(tm.pointer(), 2);
+    //stepping it any further does nothing
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), [3]);
+}
+
+///a program that runs off its end has no "@" to say it stopped at
+#[test]
+fn off_the_end() {
+    let (mut tm, _) = machine("+.", &Lang::BfHalt);
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(tm.halted_at(), None);
+}
+
+///in brainfuck the "@" is skipped, so the innermost loop takes its cell down to 0, the middle one
+///goes around again with that cell 0 this time, and the outer one never ends
+#[test]
+fn a_comment_without_it() {
+    let (mut tm, output) = machine(NESTED, &Lang::Brainfuck);
+    tm.step_limit = Some(1_000);
+    assert!(tm.run().is_err());
+    assert!(bfint::shared::lock(&output).starts_with(&[3, 2, 1, 0]));
+    assert_eq!(tm.halted_at(), None);
+}
+
+///bfint --ext-halt, which says where it stopped when asked to say more
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let path = std::env::temp_dir().join(format!("bfint-halt-{}.b", std::process::id()));
+    std::fs::write(&path, NESTED).expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--ext-halt", "-v"])
+        .arg(&path)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&path);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, [3]);
+    let said = String::from_utf8_lossy(&output.stderr);
+    assert!(said.contains("stopped by the \"@\" at 1:13"), "{}", said);
+}
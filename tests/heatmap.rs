@@ -0,0 +1,76 @@
+//! --heatmap-image against a picture drawn once and checked by eye, and the ranges of cells it
+//! can't draw
+#![cfg(feature = "cli")]
+use bfint::heatmap::Heatmap;
+use std::process::Command;
+
+///the bfint binary in a directory of its own, which goes away with it
+struct Drawing {
+    directory: std::path::PathBuf,
+}
+
+impl Drawing {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-heatmap-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Drawing { directory }
+    }
+    ///runs tests/fixtures/heatmap.bf with the options, giving back the exit code
+    fn run(&self, options: &[&str]) -> Option<i32> {
+        let program = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/heatmap.bf");
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .current_dir(&self.directory)
+            .arg("run")
+            .args(options)
+            .arg(program)
+            .output()
+            .expect("bfint starts");
+        output.status.code()
+    }
+}
+
+impl Drop for Drawing {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+///three nested loops sweeping over four cells, a column every 4 steps
+#[test]
+fn the_same_picture_as_before() {
+    let drawing = Drawing::new("golden");
+    let code = drawing.run(&["--heatmap-image", "heatmap.ppm", "--heatmap-every", "4"]);
+    assert_eq!(code, Some(0));
+    let drawn = std::fs::read(drawing.directory.join("heatmap.ppm")).expect("it was drawn");
+    assert!(
+        drawn == include_bytes!("fixtures/heatmap.ppm"),
+        "the heat map isn't the one in tests/fixtures/heatmap.ppm"
+    );
+}
+
+///a range with no cells in it is refused before the run
+#[test]
+fn empty_ranges_are_refused() {
+    let drawing = Drawing::new("empty");
+    for cells in ["5..5", "5..=4", "0..0"] {
+        let code = drawing.run(&["--heatmap-image", "heatmap.ppm", "--heatmap-cells", cells]);
+        assert_eq!(code, Some(2), "{} was taken", cells);
+    }
+    assert!(!drawing.directory.join("heatmap.ppm").exists());
+}
+
+///the library has no say in what it's given, and draws an inverted range as no rows at all
+#[test]
+fn an_inverted_range_has_no_rows() {
+    let mut heatmap = Heatmap::new(None);
+    for step in 1..=10 {
+        heatmap.step(step);
+        heatmap.write(step as usize % 3);
+    }
+    let (start, end) = (5, 4);
+    let image = heatmap.render(Some(start..=end));
+    assert_eq!((image.width, image.height), (10, 0));
+    assert!(image.pixels.is_empty());
+    assert_eq!(image.ppm(), b"P6\n10 0\n255\n");
+}
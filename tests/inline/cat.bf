@@ -0,0 +1,8 @@
+copies its input to its output; bfint test runs it with the input below and checks that it
+prints what it should
+
+;<< hello, world\n
+;<< second line\n
+,[        ;=> hello, world\n
+    .,    ;=> second line\n
+]
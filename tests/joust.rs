@@ -0,0 +1,107 @@
+//! bf joust programs parsed with their repetitions written out, and the sample programs in
+//! tests/joust played against each other
+use bfint::joust::{self, JoustError, Winner};
+use bfint::{ParseError, Position};
+
+fn position(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn repetitions_are_written_out() {
+    let program = joust::parse("(+)*3").expect("it parses");
+    assert_eq!(program.to_string(), "+++");
+    let program = joust::parse("(+{-}>)%2").expect("it parses");
+    assert_eq!(program.to_string(), "++->>");
+    let program = joust::parse("((+)*2>)*2").expect("it parses");
+    assert_eq!(program.to_string(), "++>++>");
+}
+
+///brackets only have to match once everything has been written out
+#[test]
+fn loops_can_span_repetitions() {
+    let program = joust::parse("(+[)*3 (])*3").expect("it parses");
+    assert_eq!(program.to_string(), "+[+[+[]]]");
+}
+
+///repetitions inside each other are read off a stack, not by recursing, so a lot of them don't
+///run out of it
+#[test]
+fn repetitions_nest_deep() {
+    let depth = 100_000;
+    let source = format!("{}+{}", "(".repeat(depth), ")*1".repeat(depth));
+    let program = joust::parse(&source).expect("it parses");
+    assert_eq!(program.to_string(), "+");
+}
+
+#[test]
+fn repetitions_nested_too_deep() {
+    let source = format!("{}+{}", "(".repeat(4), ")*1".repeat(4));
+    assert!(joust::parse_with(&source, 4).is_ok());
+    assert_eq!(
+        joust::parse_with(&format!("({})*1", source), 4),
+        Err(JoustError::ParseError(ParseError::TooDeep(
+            position(1, 5),
+            4
+        )))
+    );
+}
+
+///the loops a repetition writes out count towards how deep the program goes too
+#[test]
+fn written_out_loops_nested_too_deep() {
+    assert!(joust::parse_with("([)*2 (])*2", 2).is_ok());
+    assert_eq!(
+        joust::parse_with("([)*3 (])*3", 2),
+        Err(JoustError::ParseError(ParseError::TooDeep(
+            position(1, 2),
+            2
+        )))
+    );
+}
+
+#[test]
+fn malformed_repetitions() {
+    assert_eq!(
+        joust::parse("+(+"),
+        Err(JoustError::UnmatchedOpen(position(1, 2)))
+    );
+    assert_eq!(
+        joust::parse("(+{-)%2"),
+        Err(JoustError::UnmatchedOpen(position(1, 1)))
+    );
+    assert_eq!(
+        joust::parse("+)"),
+        Err(JoustError::Unexpected(')', position(1, 2)))
+    );
+    assert_eq!(
+        joust::parse("(+)+"),
+        Err(JoustError::MissingCount(position(1, 1)))
+    );
+    assert_eq!(
+        joust::parse("(+{-}+)*2"),
+        Err(JoustError::WrongRepeat(position(1, 1)))
+    );
+    assert_eq!(
+        joust::parse("(+)%2"),
+        Err(JoustError::WrongRepeat(position(1, 1)))
+    );
+    assert_eq!(
+        joust::parse("((+)*100000)*1000"),
+        Err(JoustError::TooLong(position(1, 1)))
+    );
+}
+
+#[test]
+fn samples() {
+    let parse = |source: &str| joust::parse(source).expect("the sample parses");
+    let clear = parse(include_str!("joust/clear.bfjoust"));
+    let sitter = parse(include_str!("joust/sitter.bfjoust"));
+    let suicide = parse(include_str!("joust/suicide.bfjoust"));
+    let duels = joust::TAPE_LENGTHS.count() * 2;
+    //the clear gets to the sitter's flag on every tape length
+    assert_eq!(joust::play(&clear, &sitter).wins(Winner::Left), duels);
+    //walking off the tape loses straight away
+    assert_eq!(joust::play(&sitter, &suicide).wins(Winner::Left), duels);
+    assert_eq!(joust::play(&suicide, &clear).wins(Winner::Right), duels);
+}
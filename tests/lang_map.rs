@@ -0,0 +1,126 @@
+//! the mapping files in dialects, each used to write hello world in its dialect and run it, which
+//! has to print what the brainfuck one does. and where a bracket that isn't matched is found in a
+//! dialect whose tokens are more than one character, mappings that can't be told apart, and
+//! bfint --lang-map
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::lang::{Lang, Mapping};
+use bfint::{ParseError, Position, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+const HELLO: &str = include_str!("fixtures/hello.bf");
+const EXPECTED: &[u8] = include_bytes!("fixtures/hello.out");
+
+const DIALECTS: [(&str, &str); 3] = [
+    ("alphuck", include_str!("../dialects/alphuck.toml")),
+    ("blub", include_str!("../dialects/blub.toml")),
+    ("reversefuck", include_str!("../dialects/reversefuck.toml")),
+];
+
+///the brainfuck program written with the mapping's tokens, a space between each of them. the
+///tokens are read straight from the lines of the file, so this doesn't go by what's being tested
+fn translate(source: &str, mapping: &str) -> String {
+    let tokens: Vec<(char, &str)> = mapping
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(command, token)| {
+            let command = command.trim_matches('"').chars().next();
+            (command.expect("it's a command"), token.trim_matches('"'))
+        })
+        .collect();
+    assert_eq!(tokens.len(), 8);
+    let translated: Vec<&str> = source
+        .chars()
+        .filter_map(|c| tokens.iter().find(|(command, _)| *command == c))
+        .map(|(_, token)| *token)
+        .collect();
+    translated.join(" ")
+}
+
+fn machine(source: &str, mapping: &str) -> Result<TuringMachine, ParseError> {
+    let mapping = Mapping::parse(mapping).expect("the mapping is right");
+    TuringMachine::new(source, &Lang::Mapped(mapping))
+}
+
+#[test]
+fn hello_world() {
+    for (name, mapping) in DIALECTS {
+        let source = translate(HELLO, mapping);
+        let mut tm = machine(&source, mapping).expect("it parses");
+        let output = Arc::new(Mutex::new(vec![]));
+        tm.output = output.clone();
+        tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+        assert_eq!(tm.run(), Ok(RunOutcome::Halted), "{}", name);
+        assert_eq!(*bfint::shared::lock(&output), EXPECTED, "{}", name);
+    }
+}
+
+///the position of a token is where it starts, in lines and columns of the dialect's source
+#[test]
+fn positions() {
+    let blub = DIALECTS[1].1;
+    let source = "Blub. Blub.\n  Blub! Blub? Blub. Blub.";
+    assert!(matches!(
+        machine(source, blub),
+        Err(ParseError::UnmatchedOpen(Position { line: 2, column: 3 }))
+    ));
+    //in reversefuck "]" opens a loop
+    let reversefuck = DIALECTS[2].1;
+    assert!(matches!(
+        machine("-]", reversefuck),
+        Err(ParseError::UnmatchedOpen(Position { line: 1, column: 2 }))
+    ));
+}
+
+#[test]
+fn ambiguous() {
+    let mapping = DIALECTS[0].1.replace("\"s\"", "\"ab\"");
+    assert_eq!(
+        Mapping::parse(&mapping)
+            .expect_err("a is the start of ab")
+            .to_string(),
+        "the token 'a' is the start of the token 'ab', so they can't be told apart"
+    );
+    let missing: String = DIALECTS[0]
+        .1
+        .lines()
+        .filter(|line| !line.starts_with("\".\""))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    assert_eq!(
+        Mapping::parse(&missing)
+            .expect_err("'.' is left out")
+            .to_string(),
+        "'.' has no token"
+    );
+}
+
+///bfint --lang-map with the mapping file, and one that's wrong
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let mapping = concat!(env!("CARGO_MANIFEST_DIR"), "/dialects/alphuck.toml");
+    let directory = std::env::temp_dir().join(format!("bfint-lang-map-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    std::fs::write(directory.join("hello.a"), translate(HELLO, DIALECTS[0].1))
+        .expect("it can be written");
+    std::fs::write(directory.join("wrong.toml"), "\">\" = \"a\"\n").expect("it can be written");
+    let run = |mapping: &str| {
+        Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--lang-map", mapping, "hello.a"])
+            .current_dir(&directory)
+            .output()
+            .expect("bfint starts")
+    };
+    let right = run(mapping);
+    let wrong = run("wrong.toml");
+    let _ = std::fs::remove_dir_all(&directory);
+    assert!(right.status.success());
+    assert_eq!(right.stdout, EXPECTED);
+    assert!(!wrong.status.success());
+    let said = String::from_utf8_lossy(&wrong.stderr);
+    assert!(said.contains("wrong.toml: '<' has no token"), "{}", said);
+}
@@ -0,0 +1,97 @@
+//! tests/fixtures/latin1.b, whose comment has 0xe9 and 0xe8 in it, "é" and "è" in latin-1,
+//! which on their own aren't utf-8. it loads from its file and from its bytes and runs, every
+//! byte of it one character, so the positions of what comes after a byte like that are where
+//! they are in the file, and a diagnostic shows the line with it in it
+#![cfg(feature = "std")]
+use bfint::{lang::Lang, ParseError, Position, Program, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+const FIXTURE: &[u8] = include_bytes!("fixtures/latin1.b");
+
+///the fixture where it is in the repository, to be loaded the way a file is
+fn path() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/latin1.b")
+}
+
+///what the machine printed after running to the end
+fn printed(mut tm: TuringMachine) -> Vec<u8> {
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.run().expect("it runs");
+    let output = bfint::shared::lock(&output).clone();
+    output
+}
+
+#[test]
+fn the_fixture_has_bytes_that_arent_utf8() {
+    assert!(FIXTURE.contains(&0xe9));
+    assert!(String::from_utf8(FIXTURE.to_vec()).is_err());
+}
+
+#[test]
+fn from_its_file() {
+    let tm = TuringMachine::from_file(path(), &Lang::Brainfuck).expect("it loads");
+    assert_eq!(printed(tm), b"HI");
+}
+
+#[test]
+fn from_its_bytes() {
+    let tm = TuringMachine::from_bytes(FIXTURE, &Lang::Brainfuck).expect("it loads");
+    assert_eq!(printed(tm), b"HI");
+    let program = Program::try_from(FIXTURE).expect("it parses");
+    //the "+" that starts the second line, and the "." that ends the program
+    assert_eq!(program.tokens()[0].1, Position { line: 2, column: 1 });
+    let (_, last) = *program.tokens().last().expect("it has instructions");
+    assert_eq!(
+        last,
+        Position {
+            line: 2,
+            column: 26
+        }
+    );
+    assert_eq!(
+        program.source_excerpt(
+            Position { line: 1, column: 1 },
+            Position { line: 1, column: 8 }
+        ),
+        "café crè"
+    );
+}
+
+///a byte that isn't utf-8 is one column, like any other
+#[test]
+fn positions_after_it() {
+    let error = TuringMachine::from_bytes(b"caf\xe9 +[\n", &Lang::Brainfuck)
+        .err()
+        .expect("it's unmatched");
+    assert_eq!(
+        error,
+        ParseError::UnmatchedOpen(Position { line: 1, column: 7 })
+    );
+}
+
+///the line is shown with the "é" in it, and the caret under the "[" after it
+#[cfg(feature = "cli")]
+#[test]
+fn in_a_diagnostic() {
+    let program = std::env::temp_dir().join(format!("bfint-latin1-{}.b", std::process::id()));
+    std::fs::write(&program, b"caf\xe9 +[\n").expect("it can be written");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("check")
+        .arg(&program)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&program);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unmatched '[' at 1:7\n    café +[\n          ^\n"),
+        "{}",
+        stderr
+    );
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg(path())
+        .output()
+        .expect("bfint starts");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"HI");
+}
@@ -0,0 +1,165 @@
+//! what tab finishes at the repl's prompt: the names of ":" commands, then files for the ones
+//! that take a path, looked for in a temporary directory, and cell names for the ones that take
+//! a cell, and nothing at all for a line of brainfuck. and the editor putting what was finished
+//! into the line, as far as every candidate agrees
+#![cfg(feature = "std")]
+use bfint::lineedit::{complete, Action, Argument, Completion, Editor, History, Key};
+use std::path::PathBuf;
+
+///the commands the way the repl has them, see REPL_COMMANDS in main.rs
+const COMMANDS: [(&str, Argument); 6] = [
+    (":tape", Argument::Cell),
+    (":set", Argument::Cell),
+    (":load", Argument::Path),
+    (":save", Argument::Path),
+    (":reset", Argument::None),
+    (":help", Argument::None),
+];
+
+fn names() -> Vec<String> {
+    ["count", "carry", "total"].map(String::from).to_vec()
+}
+
+fn completion(start: usize, candidates: &[&str]) -> Completion {
+    Completion {
+        start,
+        candidates: candidates
+            .iter()
+            .map(|candidate| candidate.to_string())
+            .collect(),
+    }
+}
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-lineedit-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+    ///the path of the directory, with a "/" at the end
+    fn path(&self) -> String {
+        format!("{}/", self.0.to_str().expect("the path is utf-8"))
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn commands() {
+    let complete = |line: &str| complete(line, &COMMANDS, &names());
+    assert_eq!(
+        complete(":"),
+        completion(0, &[":tape", ":set", ":load", ":save", ":reset", ":help"])
+    );
+    assert_eq!(complete(":s"), completion(0, &[":set", ":save"]));
+    assert_eq!(complete("  :re"), completion(2, &[":reset"]));
+    assert_eq!(complete(":x"), completion(0, &[]));
+}
+
+#[test]
+fn cells() {
+    let complete = |line: &str| complete(line, &COMMANDS, &names());
+    assert_eq!(
+        complete(":tape "),
+        completion(6, &["count", "carry", "total"])
+    );
+    assert_eq!(complete(":set c"), completion(5, &["count", "carry"]));
+    assert_eq!(complete(":set  ca"), completion(6, &["carry"]));
+    //only the first argument
+    assert_eq!(complete(":set count c"), completion(11, &[]));
+    //and not for a command that takes none, or one there isn't
+    assert_eq!(complete(":reset c"), completion(7, &[]));
+    assert_eq!(complete(":nope c"), completion(6, &[]));
+}
+
+#[test]
+fn paths() {
+    let directory = Directory::new("paths");
+    for file in ["hello.b", "help.b", ".hidden.b"] {
+        std::fs::write(directory.0.join(file), "").expect("it can be written");
+    }
+    std::fs::create_dir(directory.0.join("programs")).expect("it can be made");
+    let path = directory.path();
+    let complete = |line: &str| complete(line, &COMMANDS, &names()).candidates;
+    let full = |names: &[&str]| -> Vec<String> {
+        names
+            .iter()
+            .map(|name| format!("{}{}", path, name))
+            .collect()
+    };
+    //directories end in "/", and files starting with "." only show up for a "."
+    assert_eq!(
+        complete(&format!(":load {}", path)),
+        full(&["hello.b", "help.b", "programs/"])
+    );
+    assert_eq!(
+        complete(&format!(":save {}hel", path)),
+        full(&["hello.b", "help.b"])
+    );
+    assert_eq!(complete(&format!(":load {}.", path)), full(&[".hidden.b"]));
+    assert_eq!(complete(&format!(":load {}p", path)), full(&["programs/"]));
+    assert!(complete(&format!(":load {}nothing/", path)).is_empty());
+    //a path is never a cell
+    assert!(complete(&format!(":tape {}", path)).is_empty());
+}
+
+///a line of brainfuck, or of input for the program, gets nothing, even with a ":" in it
+#[test]
+fn not_a_command() {
+    for line in ["", "+++", "+[:", "ab :load ", "t"] {
+        assert_eq!(
+            complete(line, &COMMANDS, &names()),
+            Completion::default(),
+            "{}",
+            line
+        );
+    }
+}
+
+///tab in the editor puts in as much as every candidate agrees on, and lists them when that's
+///nothing more than what's typed
+#[test]
+fn in_the_editor() {
+    let history = History::default();
+    let complete = |line: &str| complete(line, &COMMANDS, &names());
+    let mut editor = Editor::new(&history);
+    let type_in = |editor: &mut Editor, text: &str| {
+        for c in text.chars() {
+            editor.key(Key::Char(c), &history, &complete);
+        }
+    };
+    type_in(&mut editor, ":ta");
+    assert_eq!(editor.key(Key::Tab, &history, &complete), Action::Redraw);
+    //finished, with a space for the argument
+    assert_eq!(editor.line(), ":tape ");
+    type_in(&mut editor, "c");
+    //"count" and "carry" agree on nothing more than "c"
+    assert_eq!(
+        editor.key(Key::Tab, &history, &complete),
+        Action::Show(vec!["count".to_string(), "carry".to_string()])
+    );
+    type_in(&mut editor, "o");
+    editor.key(Key::Tab, &history, &complete);
+    assert_eq!(editor.line(), ":tape count ");
+    //":s" goes as far as ":s", which is what's there
+    let mut editor = Editor::new(&history);
+    type_in(&mut editor, ":s");
+    assert!(matches!(
+        editor.key(Key::Tab, &history, &complete),
+        Action::Show(_)
+    ));
+    assert_eq!(editor.line(), ":s");
+    //brainfuck is left as it is
+    let mut editor = Editor::new(&history);
+    type_in(&mut editor, "++");
+    assert_eq!(editor.key(Key::Tab, &history, &complete), Action::Redraw);
+    assert_eq!(editor.line(), "++");
+}
@@ -0,0 +1,184 @@
+//! running a program as written and optimized in lockstep. the passes there are agree with the
+//! program as written, and a pass that's broken on purpose, taking the last "+" off every run of
+//! three or more, is caught at the first sync point where a cell comes out different
+#![cfg(feature = "std")]
+use bfint::lang::{self, Lang};
+use bfint::lockstep::{describe, run, Lockstep, Point};
+use bfint::optimize::{Ir, Level, Pass, PassStats};
+use bfint::snapshot::Change;
+use bfint::{Instruction, Position, TuringMachine};
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+///takes the last "+" off every run of three or more
+struct OneShort;
+
+impl Pass for OneShort {
+    fn name(&self) -> &'static str {
+        "one-short"
+    }
+    fn run(&self, ir: &mut Ir) -> PassStats {
+        let before = ir.tokens.len();
+        let mut kept: Vec<(Instruction, Position)> = vec![];
+        let mut run = 0;
+        for (index, &(instruction, position)) in ir.tokens.iter().enumerate() {
+            run = match instruction {
+                Instruction::Increment => run + 1,
+                _ => 0,
+            };
+            let next = ir.tokens.get(index + 1).map(|(next, _)| *next);
+            if run < 3 || next == Some(Instruction::Increment) {
+                kept.push((instruction, position));
+            }
+        }
+        ir.tokens = kept;
+        PassStats {
+            removed: before - ir.tokens.len(),
+            fused: 0,
+        }
+    }
+}
+
+///the source with the pass run over it, each instruction left where it was and the ones it took
+///out made into spaces, so the brackets are at the positions they had
+fn broken(source: &str) -> String {
+    let tokens = lang::tokenize(source, &Lang::Brainfuck).expect("it parses");
+    let mut ir = Ir::new(tokens, true);
+    OneShort.run(&mut ir);
+    let mut lines: Vec<Vec<char>> = source.lines().map(|line| vec![' '; line.len()]).collect();
+    for (instruction, position) in ir.tokens {
+        lines[position.line - 1][position.column - 1] = match instruction {
+            Instruction::Increment => '+',
+            Instruction::Decrement => '-',
+            Instruction::MoveRight => '>',
+            Instruction::MoveLeft => '<',
+            Instruction::Output => '.',
+            Instruction::Replace => ',',
+            Instruction::JumpToClose => '[',
+            Instruction::JumpToOpen => ']',
+            _ => unreachable!("it's only brainfuck"),
+        };
+    }
+    let lines: Vec<String> = lines.into_iter().map(String::from_iter).collect();
+    lines.join("\n")
+}
+
+fn machine(source: &str) -> TuringMachine {
+    TuringMachine::new(source, &Lang::Brainfuck).expect("it parses")
+}
+
+///the program as written and at the level
+fn optimized(source: &str, level: Level) -> [TuringMachine; 2] {
+    let mut optimized = machine(source);
+    optimized.optimize(&level.pipeline(), |_, _| {});
+    [machine(source), optimized]
+}
+
+#[test]
+fn the_passes_agree() {
+    let hello = include_str!("fixtures/hello.bf");
+    for level in Level::ALL {
+        let lockstep = run(optimized(hello, level), b"", 1_000_000);
+        let Lockstep::Agreed { points, end } = lockstep else {
+            panic!("{} diverged: {:?}", level, lockstep);
+        };
+        assert_eq!(end, Point::Halted);
+        assert!(points > 13, "{} only had {} sync points", level, points);
+    }
+    //reading too, and the loops copy-loop and clear-loop run in one go are run through
+    let lockstep = run(optimized(",[->++<]>.,[-].", Level::O2), b"\x05\x09", 10_000);
+    assert_eq!(
+        lockstep,
+        Lockstep::Agreed {
+            points: 4,
+            end: Point::Halted
+        }
+    );
+}
+
+#[test]
+fn the_toy_pass_is_broken() {
+    assert_eq!(broken("++[>+++<-]>."), "++[>++ <-]>.");
+    //a run goes on over the end of a line
+    assert_eq!(broken("+++\n++++."), "+++\n+++ .");
+}
+
+///the first "[" agrees, with two in the first cell either way, and at the "]" the second cell
+///has two where it should have three
+#[test]
+fn caught() {
+    let source = "++[>+++<-]>.";
+    let lockstep = run([machine(source), machine(&broken(source))], b"", 10_000);
+    let Lockstep::Diverged {
+        points,
+        sides,
+        changes,
+    } = &lockstep
+    else {
+        panic!("the broken pass wasn't caught");
+    };
+    assert_eq!(*points, 1);
+    for side in sides {
+        assert_eq!(side.point, Point::Bracket(at(1, 10)));
+        assert_eq!(side.loops, [(at(1, 3), at(1, 10))]);
+        assert_eq!(side.pointer, 0);
+    }
+    assert_eq!(sides[0].steps, 9);
+    assert_eq!(sides[1].steps, 8);
+    assert_eq!(
+        changes.changes().collect::<Vec<_>>(),
+        [Change {
+            index: 1,
+            old: 3,
+            new: 2
+        }]
+    );
+    assert_eq!(
+        describe(&lockstep, ["-O0", "one-short"]),
+        format!(
+            "-O0 and one-short agreed at 1 sync points, and then:\n\
+             -O0 at the bracket at 1:10, after 9 steps\n  in the loops 1:3-1:10\n  {}\n\
+             one-short at the bracket at 1:10, after 8 steps\n  in the loops 1:3-1:10\n  {}\n\
+             cell 1: 3 for -O0, 2 for one-short\n",
+            sides[0].state, sides[1].state
+        )
+    );
+}
+
+///the run is as long as hello world, and it's caught a few sync points in
+#[test]
+fn caught_in_hello_world() {
+    let hello = include_str!("fixtures/hello.bf");
+    let lockstep = run([machine(hello), machine(&broken(hello))], b"", 1_000_000);
+    let Lockstep::Diverged { points, sides, .. } = lockstep else {
+        panic!("the broken pass wasn't caught");
+    };
+    assert!(points < 5, "{}", points);
+    assert_eq!(sides[0].point, sides[1].point);
+    //nothing was printed yet
+    assert!(matches!(sides[0].point, Point::Bracket(_)));
+}
+
+///bfint debug --lockstep, where -O2 agrees and there's nothing to debug. the copy loop runs in
+///one go, so the "." is the only sync point
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::{Command, Stdio};
+    let path = std::env::temp_dir().join(format!("bfint-lockstep-{}.b", std::process::id()));
+    std::fs::write(&path, "++[>+++<-]>.").expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["debug", "--lockstep", "-O2"])
+        .arg(&path)
+        .stdin(Stdio::null())
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&path);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "-O0 and -O2 agreed at all 1 sync points and then both halted\n"
+    );
+}
@@ -0,0 +1,231 @@
+//! --log-state-every with a step interval, on a program that takes 153 steps: a line every 50
+//! steps and one more at the end with how the run ended, every line the same "key=value" pairs
+//! in the same order. and the intervals it takes, and the log on its own with made up progress
+#![cfg(feature = "std")]
+use bfint::progress::{Every, Progress, StateLog};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+///a hundred in the second cell, printed, in 153 steps
+const PROGRAM: &str = "++++++++++[>++++++++++<-]>.";
+
+///the pairs of a line, checking the keys are the ones every line has, in order. the outcome is
+///last, and in quotes since it can have spaces in it
+fn fields(line: &str) -> Vec<(String, String)> {
+    let (pairs, outcome) = match line.split_once(" outcome=") {
+        Some((pairs, outcome)) => (pairs, Some(("outcome".to_string(), outcome.to_string()))),
+        None => (line, None),
+    };
+    let fields: Vec<(String, String)> = pairs
+        .split(' ')
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').expect("every pair has a \"=\"");
+            (key.to_string(), value.to_string())
+        })
+        .chain(outcome)
+        .collect();
+    let keys: Vec<&str> = fields.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(
+        keys[..6],
+        [
+            "time",
+            "steps",
+            "steps_per_sec",
+            "pointer",
+            "cell",
+            "output_bytes"
+        ],
+        "{}",
+        line
+    );
+    //like 2024-05-01T12:00:00Z
+    let time = &fields[0].1;
+    assert_eq!(time.len(), 20, "{}", time);
+    assert!(
+        time.ends_with('Z') && time.as_bytes()[10] == b'T',
+        "{}",
+        time
+    );
+    fields[2].1.parse::<u64>().expect("the speed is a number");
+    fields
+}
+
+///the value of the key in the line
+fn value<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+#[test]
+fn intervals() {
+    assert_eq!(Every::from_name("1000"), Some(Every::Steps(1000)));
+    assert_eq!(
+        Every::from_name("10s"),
+        Some(Every::Time(Duration::from_secs(10)))
+    );
+    assert_eq!(
+        Every::from_name("500ms"),
+        Some(Every::Time(Duration::from_millis(500)))
+    );
+    assert_eq!(
+        Every::from_name("1.5m"),
+        Some(Every::Time(Duration::from_secs(90)))
+    );
+    for name in ["0", "0s", "", "soon", "-5"] {
+        assert_eq!(Every::from_name(name), None, "{}", name);
+    }
+    //time is looked at every so many steps, steps exactly as often as they're asked for
+    assert_eq!(Every::Steps(50).check_every(), 50);
+    assert_eq!(
+        Every::from_name("10s").map(|every| every.check_every()),
+        Some(100_000)
+    );
+}
+
+///a writer whose bytes can be looked at after it's handed over
+#[derive(Clone, Default)]
+struct Written(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Written {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("nothing panics holding it").write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_line_when_its_due() {
+    let written = Written::default();
+    let mut log = StateLog::new(Every::Steps(100), Box::new(written.clone()));
+    let progress = |steps: u64, seconds: u64| Progress {
+        steps,
+        elapsed: Duration::from_secs(seconds),
+        output_bytes: steps / 100,
+        pointer: 3,
+        cell: 7,
+    };
+    log.update(&progress(50, 1), || Some(1024));
+    log.update(&progress(100, 2), || Some(1024));
+    log.update(&progress(150, 3), || {
+        panic!("nothing's due, so it isn't asked")
+    });
+    log.update(&progress(300, 4), || None);
+    log.finish(&progress(310, 4), Some(2048), "error: off the tape");
+    let written =
+        String::from_utf8(written.0.lock().expect("it's done").clone()).expect("the log is utf-8");
+    let lines: Vec<_> = written.lines().map(fields).collect();
+    assert_eq!(lines.len(), 3, "{}", written);
+    assert_eq!(value(&lines[0], "steps"), Some("100"));
+    //a hundred steps in two seconds
+    assert_eq!(value(&lines[0], "steps_per_sec"), Some("50"));
+    assert_eq!(value(&lines[0], "peak_memory_kb"), Some("1024"));
+    assert_eq!(value(&lines[1], "steps"), Some("300"));
+    assert_eq!(value(&lines[1], "steps_per_sec"), Some("100"));
+    assert_eq!(value(&lines[1], "peak_memory_kb"), None);
+    //the last line is written however little happened, and no time passed
+    assert_eq!(value(&lines[2], "steps"), Some("310"));
+    assert_eq!(value(&lines[2], "steps_per_sec"), Some("0"));
+    assert_eq!(value(&lines[2], "pointer"), Some("3"));
+    assert_eq!(value(&lines[2], "cell"), Some("7"));
+    assert_eq!(value(&lines[2], "output_bytes"), Some("3"));
+    assert_eq!(value(&lines[2], "outcome"), Some("\"error: off the tape\""));
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use super::{fields, value, PROGRAM};
+    use std::process::Command;
+
+    ///the program in a file of the test's own, and a log next to it, which go away with it
+    struct Files(std::path::PathBuf);
+
+    impl Files {
+        fn new(test: &str, program: &str) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "bfint-log-state-{}-{}",
+                std::process::id(),
+                test
+            ));
+            std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+            std::fs::write(directory.join("program.b"), program).expect("it can be written");
+            Files(directory)
+        }
+        fn path(&self, name: &str) -> String {
+            self.0
+                .join(name)
+                .to_str()
+                .expect("the path is utf-8")
+                .to_string()
+        }
+    }
+
+    impl Drop for Files {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn every_fifty_steps() {
+        let files = Files::new("fifty", PROGRAM);
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--log-state-every", "50"])
+            .arg(files.path("program.b"))
+            .output()
+            .expect("bfint starts");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, [100]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<_> = stderr.lines().map(fields).collect();
+        let steps: Vec<_> = lines.iter().map(|line| value(line, "steps")).collect();
+        assert_eq!(
+            steps,
+            [Some("50"), Some("100"), Some("150"), Some("153")],
+            "{}",
+            stderr
+        );
+        //only the last one has an outcome
+        let outcomes: Vec<_> = lines.iter().map(|line| value(line, "outcome")).collect();
+        assert_eq!(outcomes, [None, None, None, Some("\"halted\"")]);
+        let last = &lines[3];
+        assert_eq!(value(last, "pointer"), Some("1"));
+        assert_eq!(value(last, "cell"), Some("100"));
+        assert_eq!(value(last, "output_bytes"), Some("1"));
+    }
+
+    ///appended to a file, with the run failing on its step limit
+    #[test]
+    fn to_a_file() {
+        let files = Files::new("file", "+[]");
+        let log = files.path("state.log");
+        std::fs::write(&log, "from before\n").expect("it can be written");
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args([
+                "--log-state-every",
+                "40",
+                "--max-steps",
+                "100",
+                "--log-state-file",
+            ])
+            .arg(&log)
+            .arg(files.path("program.b"))
+            .output()
+            .expect("bfint starts");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stderr.contains("steps="), "{}", stderr);
+        let log = std::fs::read_to_string(&log).expect("it wrote the log");
+        let mut lines = log.lines();
+        assert_eq!(lines.next(), Some("from before"));
+        let lines: Vec<_> = lines.map(fields).collect();
+        let steps: Vec<_> = lines.iter().map(|line| value(line, "steps")).collect();
+        assert_eq!(steps, [Some("40"), Some("80"), Some("100")], "{}", log);
+        let outcome = value(&lines[2], "outcome").expect("the last line has one");
+        assert!(outcome.starts_with("\"error: "), "{}", outcome);
+    }
+}
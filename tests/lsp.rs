@@ -0,0 +1,234 @@
+//! the language server driven the way an editor would drive it: every message framed and read
+//! back through framing.rs, from initialize to exit. positions go back and forth in utf-16 code
+//! units, so a line with characters that take two of them, or four bytes, still gets its
+//! diagnostics, hovers and edits in the right place. and the framing itself, with a length
+//! that's missing, cut short or far too long
+#![cfg(feature = "lsp")]
+use bfint::framing;
+use bfint::lsp::Server;
+use serde_json::{json, Value};
+use std::io;
+
+///what the server answers the messages with, and whether it was asked to shut down before it
+///was told to exit
+fn session(messages: &[Value]) -> (Vec<Value>, bool) {
+    let mut input = vec![];
+    for message in messages {
+        framing::send(&mut input, message).expect("a vec can be written to");
+    }
+    let mut output = vec![];
+    let shut_down = Server::new(&mut output)
+        .serve(&input[..])
+        .expect("every message is answered");
+    let mut answers = vec![];
+    let mut output = &output[..];
+    while let Some(answer) = framing::read_message(&mut output).expect("the answers are framed") {
+        answers.push(answer);
+    }
+    (answers, shut_down)
+}
+
+fn request(id: i64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+const URI: &str = "file:///program.bf";
+
+fn open(text: &str) -> Value {
+    notification(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": { "uri": URI, "languageId": "brainfuck", "version": 1, "text": text },
+        }),
+    )
+}
+
+///a change that puts the text in at the position
+fn insert(line: u64, character: u64, text: &str) -> Value {
+    let at = json!({ "line": line, "character": character });
+    notification(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "range": { "start": at, "end": at }, "text": text }],
+        }),
+    )
+}
+
+///the answer to the request with the id
+fn answer(answers: &[Value], id: i64) -> &Value {
+    answers
+        .iter()
+        .find(|answer| answer["id"] == id)
+        .unwrap_or_else(|| panic!("request {} was answered: {:?}", id, answers))
+}
+
+///the diagnostics in every publishDiagnostics, in order
+fn published(answers: &[Value]) -> Vec<&Value> {
+    answers
+        .iter()
+        .filter(|answer| answer["method"] == "textDocument/publishDiagnostics")
+        .map(|answer| &answer["params"]["diagnostics"])
+        .collect()
+}
+
+#[test]
+fn a_whole_session() {
+    let document = json!({ "textDocument": { "uri": URI } });
+    let (answers, shut_down) = session(&[
+        request(
+            1,
+            "initialize",
+            json!({ "initializationOptions": { "width": 20 } }),
+        ),
+        notification("initialized", json!({})),
+        open("+[>+[-]<-"),
+        //the "]" that was missing goes on the end
+        insert(0, 9, "]"),
+        request(2, "textDocument/documentSymbol", document.clone()),
+        request(
+            3,
+            "textDocument/hover",
+            json!({ "textDocument": { "uri": URI }, "position": { "line": 0, "character": 4 } }),
+        ),
+        request(4, "textDocument/formatting", document.clone()),
+        request(5, "no/suchMethod", json!({})),
+        notification("textDocument/didClose", document),
+        request(6, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+        //nothing after exit is read
+        request(7, "shutdown", Value::Null),
+    ]);
+    assert!(shut_down);
+    let capabilities = &answer(&answers, 1)["result"]["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert!(capabilities["experimental"]["bfint"].is_object());
+    let diagnostics = published(&answers);
+    assert_eq!(diagnostics.len(), 3, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0][0]["severity"], 1, "{:?}", diagnostics[0]);
+    assert_eq!(
+        diagnostics[0][0]["range"]["start"],
+        json!({ "line": 0, "character": 1 })
+    );
+    //fixed, and then closed
+    assert!(diagnostics[1]
+        .as_array()
+        .expect("it's a list")
+        .iter()
+        .all(|diagnostic| diagnostic["severity"] != 1));
+    assert_eq!(diagnostics[2], &json!([]));
+    let symbols = &answer(&answers, 2)["result"];
+    assert_eq!(symbols[0]["name"], "loop@1:2");
+    assert_eq!(symbols[0]["children"][0]["name"], "loop@1:5");
+    let hover = &answer(&answers, 3)["result"];
+    assert_eq!(
+        hover["contents"]["value"],
+        "this bracket opens the loop from 1:5 to 1:7, which is 2 loops deep"
+    );
+    let edits = &answer(&answers, 4)["result"];
+    assert!(edits[0]["newText"].as_str().is_some(), "{:?}", edits);
+    assert_eq!(answer(&answers, 5)["error"]["code"], -32601);
+    assert!(answers.iter().all(|answer| answer["id"] != 7));
+}
+
+///exiting without a shutdown is what the protocol wants a failing exit code for
+#[test]
+fn exit_without_shutdown() {
+    let (answers, shut_down) = session(&[
+        request(1, "initialize", json!({})),
+        notification("exit", Value::Null),
+    ]);
+    assert!(!shut_down);
+    assert_eq!(answers.len(), 1);
+    //and the input running out is the same as exiting
+    let (_, shut_down) = session(&[request(1, "initialize", json!({}))]);
+    assert!(!shut_down);
+}
+
+///"😀" is one char, four bytes and two utf-16 code units, and "é" one char, two bytes and one
+///code unit, so a bracket after them is at a different column in every one of those
+#[test]
+fn utf16_positions() {
+    let text = "😀é [\n😀😀[-]";
+    let hover = |line: u64, character: u64| {
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+        })
+    };
+    let (answers, _) = session(&[
+        request(1, "initialize", json!({})),
+        open(text),
+        //the "[" at the end of the first line is what's wrong, so the fix goes before the "😀"s
+        //of the second
+        insert(1, 0, "]"),
+        request(2, "textDocument/hover", hover(0, 4)),
+        request(3, "textDocument/hover", hover(1, 5)),
+        request(4, "textDocument/hover", hover(1, 7)),
+        //the second half of a "😀" is the "😀", which isn't a bracket
+        request(5, "textDocument/hover", hover(1, 2)),
+    ]);
+    let diagnostics = published(&answers);
+    assert_eq!(
+        diagnostics[0][0]["range"],
+        json!({ "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 5 } })
+    );
+    let hovered = |id| answer(&answers, id)["result"]["contents"]["value"].clone();
+    assert_eq!(
+        hovered(2),
+        "this bracket opens the loop from 1:4 to 2:1, which is 1 loop deep"
+    );
+    assert_eq!(
+        answer(&answers, 2)["result"]["range"]["start"],
+        json!({ "line": 0, "character": 4 })
+    );
+    //"]😀😀[-]" after the fix
+    assert_eq!(
+        hovered(3),
+        "this bracket opens the loop from 2:4 to 2:6, which is 1 loop deep"
+    );
+    assert_eq!(
+        hovered(4),
+        "this bracket closes the loop from 2:4 to 2:6, which is 1 loop deep"
+    );
+    assert_eq!(answer(&answers, 5)["result"], Value::Null);
+}
+
+#[test]
+fn framing() {
+    let read = |bytes: &[u8]| framing::read_message(&mut &bytes[..]);
+    assert_eq!(
+        read(b"content-length: 2\r\n\r\n{}").expect("it's framed"),
+        Some(json!({}))
+    );
+    //other headers are passed over
+    assert_eq!(
+        read(b"Content-Type: application/json\r\nContent-Length: 4\r\n\r\n[1]\n")
+            .expect("it's framed"),
+        Some(json!([1]))
+    );
+    assert_eq!(read(b"").expect("it's the end"), None);
+    let kind = |bytes: &[u8]| read(bytes).expect_err("it's refused").kind();
+    assert_eq!(kind(b"\r\n{}"), io::ErrorKind::InvalidData);
+    assert_eq!(
+        kind(b"Content-Length: many\r\n\r\n{}"),
+        io::ErrorKind::InvalidData
+    );
+    assert_eq!(
+        kind(b"Content-Length: 10\r\n\r\n{}"),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert_eq!(
+        kind(b"Content-Length: 2\r\n\r\n{{"),
+        io::ErrorKind::InvalidData
+    );
+    //refused before anything is set aside for it
+    let huge = format!("Content-Length: {}\r\n\r\n{{}}", usize::MAX);
+    assert_eq!(kind(huge.as_bytes()), io::ErrorKind::InvalidData);
+    let past = format!("Content-Length: {}\r\n\r\n{{}}", framing::MAX_LENGTH + 1);
+    assert_eq!(kind(past.as_bytes()), io::ErrorKind::InvalidData);
+}
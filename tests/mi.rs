@@ -0,0 +1,136 @@
+//! a whole session of the debugger for programs, driven through its json lines the way an
+//! editor plugin would: a breakpoint, steps, running on to it and to the end, reading cells,
+//! values and conditions, lines it has to reject, and quitting. every event has a seq one more
+//! than the last one's, over the whole session
+#![cfg(feature = "std")]
+use bfint::debugger::Debugger;
+use bfint::mi::Session;
+use bfint::{lang::Lang, TuringMachine};
+use serde_json::{json, Value};
+
+///three times round the loop, adding one to the next cell and printing it each time
+const PROGRAM: &str = "+++\n[>+.<-]\n>.";
+
+///a session on PROGRAM
+fn session() -> Session {
+    let tm = TuringMachine::new(PROGRAM, &Lang::Brainfuck).expect("it parses");
+    Session::new(Debugger::new(tm, vec![]))
+}
+
+///the events a line gives back, parsed
+fn send(session: &mut Session, line: &str) -> Vec<Value> {
+    session
+        .handle(line)
+        .iter()
+        .map(|event| serde_json::from_str(event).expect("every event is json"))
+        .collect()
+}
+
+///the one event a line gives back
+fn one(session: &mut Session, line: &str) -> Value {
+    let events = send(session, line);
+    assert_eq!(events.len(), 1, "{}: {:?}", line, events);
+    events.into_iter().next().expect("there's one")
+}
+
+#[test]
+fn a_whole_session() {
+    let mut session = session();
+    let mut events = vec![];
+    let mut send = |line: &str| {
+        let sent = send(&mut session, line);
+        events.extend(sent.clone());
+        sent
+    };
+    //the "." in the loop
+    let event = &send(r#"{"cmd": "break", "loc": "2:4"}"#)[0];
+    assert_eq!(event["event"], "breakpoint");
+    assert_eq!(event["loc"], "2:4");
+    let breakpoint = event["id"].clone();
+    let event = &send(r#"{"cmd": "step", "count": 3}"#)[0];
+    assert_eq!(event["event"], "stopped");
+    assert_eq!(event["reason"], "step");
+    assert_eq!(event["loc"], "2:1");
+    assert_eq!(event["steps"], 3);
+    //on to the breakpoint, before the "." has run
+    let event = &send(r#"{"cmd": "continue"}"#)[0];
+    assert_eq!(event["reason"], "breakpoint");
+    assert_eq!(event["breakpoint"], breakpoint);
+    assert_eq!(event["loc"], "2:4");
+    assert_eq!(event["pointer"], 1);
+    let event = &send(r#"{"cmd": "read-cells", "from": 0, "count": 3}"#)[0];
+    assert_eq!(event["event"], "cells");
+    assert_eq!(event["values"], json!([3, 1, 0]));
+    assert_eq!(
+        send(r#"{"cmd": "eval", "value": "cell[0] + cell[1]"}"#)[0]["value"],
+        4
+    );
+    let event = &send(r#"{"cmd": "eval-condition", "condition": "ptr == 1 && cell == 1"}"#)[0];
+    assert_eq!(event["holds"], true);
+    //the "." runs and writes, and the next time round stops at the breakpoint again
+    let sent = send(r#"{"cmd": "continue"}"#);
+    assert_eq!(sent[0]["event"], "output");
+    assert_eq!(sent[0]["bytes"], json!([1]));
+    assert_eq!(sent[1]["reason"], "breakpoint");
+    assert_eq!(
+        send(r#"{"cmd": "read-cells", "count": 2}"#)[0]["values"],
+        json!([2, 2])
+    );
+    //the "." and on to the "]"
+    let sent = send(r#"{"cmd": "step", "count": 3}"#);
+    assert_eq!(sent[0]["bytes"], json!([2]));
+    assert_eq!(sent[1]["loc"], "2:7");
+    //the last time round, and then the ">." after the loop
+    assert_eq!(send(r#"{"cmd": "continue"}"#)[0]["reason"], "breakpoint");
+    let sent = send(r#"{"cmd": "continue"}"#);
+    assert_eq!(sent[0]["text"], "\u{3}\u{3}");
+    assert_eq!(sent[1]["event"], "halted");
+    assert_eq!(sent[1]["steps"], 24);
+    assert_eq!(
+        send(r#"{"cmd": "read-cells", "count": 2}"#)[0]["values"],
+        json!([0, 3])
+    );
+    let sent = send(r#"{"cmd": "quit"}"#);
+    assert!(sent.is_empty(), "{:?}", sent);
+    assert!(session.done());
+    let seqs: Vec<u64> = events
+        .iter()
+        .map(|event| event["seq"].as_u64().expect("every event has a seq"))
+        .collect();
+    assert_eq!(seqs, (1..=seqs.len() as u64).collect::<Vec<_>>());
+}
+
+///lines that aren't commands that can be carried out are rejected, and nothing is done
+#[test]
+fn rejected_lines() {
+    let mut session = session();
+    for line in [
+        "",
+        "not json",
+        r#"{"cmd": "fly"}"#,
+        r#"{"loc": "1:1"}"#,
+        r#"{"cmd": "break"}"#,
+        r#"{"cmd": "break", "loc": "one"}"#,
+        r#"{"cmd": "step", "count": "many"}"#,
+        r#"{"cmd": "eval", "value": "cell["}"#,
+        r#"{"cmd": "eval-condition", "condition": "ptr =="}"#,
+    ] {
+        let event = one(&mut session, line);
+        assert_eq!(event["event"], "rejected", "{}", line);
+        assert!(event["message"].is_string(), "{}", line);
+    }
+    let event = one(&mut session, r#"{"cmd": "read-cells", "count": 1}"#);
+    assert_eq!(event["values"], json!([0]));
+    assert!(!session.done());
+}
+
+///a program that fails says where
+#[test]
+fn an_error() {
+    let tm = TuringMachine::new("+\n<", &Lang::Brainfuck).expect("it parses");
+    let mut session = Session::new(Debugger::new(tm, vec![]));
+    let event = one(&mut session, r#"{"cmd": "continue"}"#);
+    assert_eq!(event["event"], "error");
+    assert_eq!(event["loc"], "2:1");
+    assert!(event["message"].is_string());
+}
@@ -0,0 +1,123 @@
+//! fetching programs from a server on localhost that answers every connection with the next of
+//! the responses it was given, over plain http
+#![cfg(feature = "net")]
+use bfint::net::{self, FetchError};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+///starts a server that answers a request with each of the responses in turn, and gives back its
+///url. a response that's text can say where the server is with "{url}"
+fn serve(responses: Vec<Vec<u8>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("it binds");
+    let url = format!(
+        "http://{}",
+        listener.local_addr().expect("it has an address")
+    );
+    let base = url.clone();
+    thread::spawn(move || {
+        for response in responses {
+            let (stream, _) = listener.accept().expect("it accepts");
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                line.clear();
+            }
+            let response = match String::from_utf8(response) {
+                Ok(text) => text.replace("{url}", &base).into_bytes(),
+                Err(e) => e.into_bytes(),
+            };
+            let mut writer = &stream;
+            //the client can hang up before it's all been written, when it's too large
+            let _ = writer.write_all(&response);
+        }
+    });
+    url
+}
+
+fn ok(body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend(body);
+    response
+}
+
+fn redirect(to: &str) -> Vec<u8> {
+    format!(
+        "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        to
+    )
+    .into_bytes()
+}
+
+#[test]
+fn fetches_a_program() {
+    let url = serve(vec![ok(b"+++.")]);
+    assert_eq!(
+        net::fetch(&format!("{}/a.b", url)).expect("it fetches"),
+        b"+++."
+    );
+}
+
+#[test]
+fn follows_redirects() {
+    let url = serve(vec![redirect("/b.b"), redirect("{url}/c.b"), ok(b"+.")]);
+    assert_eq!(
+        net::fetch(&format!("{}/a.b", url)).expect("it fetches"),
+        b"+."
+    );
+    let url = serve(vec![redirect("/a.b"); 6]);
+    assert!(matches!(
+        net::fetch(&format!("{}/a.b", url)),
+        Err(FetchError::TooManyRedirects)
+    ));
+}
+
+#[test]
+fn other_statuses_are_errors() {
+    let url = serve(vec![
+        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+    ]);
+    match net::fetch(&format!("{}/a.b", url)) {
+        Err(FetchError::Status(404, reason)) => assert_eq!(reason, "Not Found"),
+        other => panic!("a 404 was {:?}", other),
+    }
+}
+
+#[test]
+fn too_large() {
+    let url = serve(vec![ok(&vec![b'+'; net::MAX_SIZE + 1])]);
+    assert!(matches!(
+        net::fetch(&format!("{}/a.b", url)),
+        Err(FetchError::TooLarge)
+    ));
+}
+
+#[test]
+fn not_a_url() {
+    assert!(matches!(
+        net::fetch("ftp://example.com/a.b"),
+        Err(FetchError::BadUrl)
+    ));
+}
+
+///a gzip file that's small to send but decompresses to more than MAX_SIZE is as much of an
+///error as sending that much would have been
+#[cfg(feature = "decompress")]
+#[test]
+fn too_large_once_decompressed() {
+    use bfint::gzip::GzipError;
+    use bfint::program::{read_source, LoadError};
+    let compressed = include_bytes!("compressed/too-large.bf.gz");
+    assert!(compressed.len() < net::MAX_SIZE);
+    let url = serve(vec![ok(compressed)]);
+    match read_source(format!("{}/a.bf.gz", url)) {
+        Err(LoadError::Decompress(_, GzipError::TooLarge(limit))) => {
+            assert_eq!(limit, net::MAX_SIZE)
+        }
+        other => panic!("it was {:?}", other.map(|source| source.len())),
+    }
+}
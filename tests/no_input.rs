@@ -0,0 +1,87 @@
+//! --no-input refusing a program that reads, before it runs, with every "," it has, and running
+//! one that doesn't read the same as without it. bf-rand's "?" is refused too unless there's a
+//! seed, since it makes the output different every run as well
+#![cfg(feature = "cli")]
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-no-input-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+    ///runs bfint in the directory on a program of its own with the arguments, with input piped
+    ///in that --no-input should never get to
+    fn run(&self, source: &str, args: &[&str]) -> Output {
+        std::fs::write(self.0.join("program.b"), source).expect("it can be written");
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(args)
+            .arg("program.b")
+            .current_dir(&self.0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("bfint starts");
+        let _ = child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(b"xyz");
+        child.wait_with_output().expect("bfint ends")
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///every "," is named with where it is, and nothing runs
+#[test]
+fn refused() {
+    let directory = Directory::new("refused");
+    let output = directory.run("+.\n,.>,\n", &["--no-input"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "program.b:2:1: error: this reads input, which --no-input doesn't allow\n    ,.>,\n    ^\n\
+         program.b:2:4: error: this reads input, which --no-input doesn't allow\n    ,.>,\n       \
+         ^\n2 errors\n"
+    );
+}
+
+///a program that doesn't read runs the same as it would without --no-input
+#[test]
+fn a_clean_run() {
+    let directory = Directory::new("clean");
+    let program = "++++++++[>++++++++<-]>+.+.+.";
+    let output = directory.run(program, &["--no-input"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"ABC");
+    assert_eq!(directory.run(program, &[]).stdout, b"ABC");
+}
+
+#[test]
+fn random_bytes() {
+    let directory = Directory::new("random");
+    let unseeded = directory.run("?.", &["--no-input", "--lang", "bf-rand"]);
+    assert_eq!(unseeded.status.code(), Some(1));
+    let said = String::from_utf8_lossy(&unseeded.stderr);
+    assert!(
+        said.contains("program.b:1:1: error: this gives different bytes every run"),
+        "{}",
+        said
+    );
+    let seeded = directory.run("?.", &["--no-input", "--lang", "bf-rand", "--seed", "0"]);
+    assert!(seeded.status.success());
+    assert_eq!(seeded.stdout, [0xaf]);
+}
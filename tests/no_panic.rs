@@ -0,0 +1,108 @@
+//! the library given what it's least likely to expect: nothing at all, brackets that don't
+//! match, nesting far deeper than any real program, every program in every language cut off
+//! after every character, no steps to run at all and a range of cells that ends before it
+//! starts. whatever it makes of them, it gives back an error or a result and doesn't panic
+#![cfg(feature = "std")]
+use bfint::heatmap::Heatmap;
+use bfint::optimize::Level;
+use bfint::{lang::Lang, RuntimeError, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///how deep the deep programs nest
+const DEEP: usize = 100_000;
+
+///parses the program in the language and runs it at every level with a few steps to go on,
+///giving back whether it parsed
+fn run_everywhere(source: &str, lang: &Lang) -> bool {
+    for level in Level::ALL {
+        let Ok(mut tm) = TuringMachine::new(source, lang) else {
+            return false;
+        };
+        tm.optimize(&level.pipeline(), |_, _| {});
+        tm.input = Arc::new(Mutex::new(Cursor::new(b"input".to_vec())));
+        tm.output = Arc::new(Mutex::new(vec![]));
+        tm.step_limit = Some(10_000);
+        let _ = match lang {
+            Lang::Brainfork => bfint::fork::run(tm),
+            _ => tm.run(),
+        };
+    }
+    true
+}
+
+#[test]
+fn the_empty_program() {
+    for lang in Lang::ALL {
+        assert!(run_everywhere("", &lang), "{:?} has no empty program", lang);
+    }
+}
+
+#[test]
+fn a_lone_closing_bracket() {
+    assert!(!run_everywhere("]", &Lang::Brainfuck));
+    assert!(!run_everywhere("[", &Lang::Brainfuck));
+    assert!(!run_everywhere("+]+[", &Lang::Brainfuck));
+}
+
+///the loops are all entered, and all left, every one of them once
+#[test]
+fn nested_a_hundred_thousand_deep() {
+    let deep = format!("+{}-{}", "[".repeat(DEEP), "]".repeat(DEEP));
+    assert!(run_everywhere(&deep, &Lang::Brainfuck));
+    let skipped = format!("{}{}", "[".repeat(DEEP), "]".repeat(DEEP));
+    assert!(run_everywhere(&skipped, &Lang::Brainfuck));
+    assert!(!run_everywhere(&"[".repeat(DEEP), &Lang::Brainfuck));
+    assert!(!run_everywhere(&"]".repeat(DEEP), &Lang::Brainfuck));
+}
+
+///a program in every language, cut off after every character
+#[test]
+fn truncated_in_every_dialect() {
+    let programs = [
+        (Lang::Brainfuck, include_str!("fixtures/hello.bf")),
+        (Lang::Ook, include_str!("dialects/hello.ook")),
+        (Lang::Spoon, include_str!("dialects/hello.spoon")),
+        (Lang::Pbrain, include_str!("dialects/recursion.pb")),
+        (Lang::Ebf1, include_str!("dialects/hi.ebf1")),
+        (Lang::Brainfork, include_str!("dialects/hello.brainfork")),
+        (Lang::Boolfuck, include_str!("dialects/hello.boolfuck")),
+        //without "#", so no file is opened
+        (Lang::BfFile, "++[>+;:<-]>."),
+        (Lang::BfEval, "++++[>++++++++<-]>+++%."),
+        (Lang::BfRand, "+++[>?.<-]"),
+        (Lang::BfHalt, "+[>+.<@]"),
+        (Lang::BfChannels, "++[>'+.'<-]"),
+    ];
+    assert_eq!(programs.len(), Lang::ALL.len());
+    for (lang, source) in programs {
+        for (end, _) in source.char_indices() {
+            run_everywhere(&source[..end], &lang);
+        }
+        assert!(run_everywhere(source, &lang), "the {:?} program", lang);
+    }
+}
+
+#[test]
+fn no_steps_at_all() {
+    let mut tm = TuringMachine::new("+[>+<]", &Lang::Brainfuck).expect("it parses");
+    tm.step_limit = Some(0);
+    assert_eq!(tm.run(), Err(RuntimeError::StepLimitReached(0)));
+    assert_eq!(tm.steps, 0);
+    let mut tm = TuringMachine::new("", &Lang::Brainfuck).expect("it parses");
+    tm.step_limit = Some(0);
+    assert!(tm.run().is_ok());
+}
+
+#[test]
+fn an_inverted_range_of_cells() {
+    let mut heatmap = Heatmap::new(None);
+    let (start, end) = (10, 3);
+    assert_eq!(heatmap.render(Some(start..=end)).height, 0);
+    heatmap.step(1);
+    heatmap.write(5);
+    assert!(heatmap.render(Some(start..=end)).pixels.is_empty());
+    let (start, end) = (usize::MAX, 0);
+    assert_eq!(heatmap.render(Some(start..=end)).height, 0);
+    assert!(heatmap.render(Some(end..=start)).height > 0);
+}
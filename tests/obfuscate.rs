@@ -0,0 +1,108 @@
+//! obfuscating tests/fixtures/hello.bf: it still prints hello world, for every seed tried, it's
+//! as much bigger as the factor says, and the same seed gives the same program
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::obfuscate::{obfuscate, ObfuscateError};
+use bfint::{minify, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+const HELLO: &str = include_str!("fixtures/hello.bf");
+const OUT: &[u8] = include_bytes!("fixtures/hello.out");
+
+fn printed(source: &str) -> Vec<u8> {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.run().expect("it runs");
+    let printed = bfint::shared::lock(&output).clone();
+    printed
+}
+
+#[test]
+fn hello_still_prints() {
+    for seed in 0..8 {
+        let obfuscated = obfuscate(HELLO, &Lang::Brainfuck, seed, 3.0).expect("it obfuscates");
+        assert_eq!(printed(&obfuscated), OUT, "seed {}:\n{}", seed, obfuscated);
+    }
+}
+
+///bigger by the factor than the program it came from, comments and all, and by a lot more than
+///the program without its comments
+#[test]
+fn bigger() {
+    let minified = minify::minify(HELLO, &Lang::Brainfuck).expect("it minifies");
+    for factor in [1.0, 2.0, 3.0, 5.5] {
+        let obfuscated = obfuscate(HELLO, &Lang::Brainfuck, 1, factor).expect("it obfuscates");
+        let grown = obfuscated.len() as f64 / HELLO.len() as f64;
+        assert!(
+            (factor - 0.01..factor + 0.1).contains(&grown),
+            "{} times as long for {}",
+            grown,
+            factor
+        );
+        assert!(obfuscated.len() > minified.len());
+    }
+    //even a program that's all instructions grows
+    let obfuscated = obfuscate(&minified, &Lang::Brainfuck, 1, 3.0).expect("it obfuscates");
+    assert!(obfuscated.len() >= 3 * minified.len());
+    assert_eq!(printed(&obfuscated), OUT);
+}
+
+#[test]
+fn the_same_seed_is_the_same_program() {
+    let obfuscate = |seed| obfuscate(HELLO, &Lang::Brainfuck, seed, 3.0).expect("it obfuscates");
+    assert_eq!(obfuscate(42), obfuscate(42));
+    assert_ne!(obfuscate(42), obfuscate(43));
+}
+
+#[test]
+fn what_cant_be_obfuscated() {
+    assert_eq!(
+        obfuscate("Ook. Ook?", &Lang::Ook, 1, 3.0),
+        Err(ObfuscateError::UnsupportedLang)
+    );
+    assert!(matches!(
+        obfuscate("+[", &Lang::Brainfuck, 1, 3.0),
+        Err(ObfuscateError::ParseError(_))
+    ));
+}
+
+///bfint obfuscate --verify, with the program it writes run afterwards
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let out = std::env::temp_dir().join(format!("bfint-obfuscate-{}.bf", std::process::id()));
+    let hello = format!("{}/tests/fixtures/hello.bf", env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args([
+            "obfuscate",
+            "--seed",
+            "7",
+            "--factor",
+            "2",
+            "--verify",
+            "-o",
+        ])
+        .arg(&out)
+        .arg(&hello)
+        .output()
+        .expect("bfint starts");
+    let written = std::fs::read_to_string(&out);
+    let ran = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg(&out)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&out);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let written = written.expect("it wrote the program");
+    assert_eq!(
+        written.trim_end(),
+        obfuscate(HELLO, &Lang::Brainfuck, 7, 2.0).expect("it obfuscates")
+    );
+    assert_eq!(ran.stdout, OUT);
+}
@@ -0,0 +1,87 @@
+//! hello world run through iter_steps: the first ten observations exactly, the bytes the whole
+//! run wrote, the run's outcome as the last observation, and the machine carrying on with run
+//! from wherever the iterator was dropped
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::observe::{Observation, StepObservation};
+use bfint::shared::shared;
+use bfint::{lang::Lang, Instruction, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+const HELLO: &str =
+    "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.\
+    +++.------.--------.>>+.>++.";
+
+///a machine running hello world, with what it writes kept in memory
+fn machine() -> TuringMachine {
+    let mut tm = TuringMachine::new(HELLO, &Lang::Brainfuck).expect("the program parses");
+    tm.output = shared(vec![]);
+    tm
+}
+
+fn step(step: u64, op: Instruction, pointer: usize, cell_after: u8) -> Observation {
+    Observation::Step(StepObservation {
+        step,
+        pc: step as usize - 1,
+        op,
+        pointer,
+        cell_after,
+        output: None,
+    })
+}
+
+#[test]
+fn the_first_ten() {
+    let mut tm = machine();
+    let first: Vec<Observation> = tm.iter_steps().take(10).collect();
+    let mut expected: Vec<Observation> = (1..=8)
+        .map(|n| step(n, Instruction::Increment, 0, n as u8))
+        .collect();
+    expected.push(step(9, Instruction::JumpToClose, 0, 8));
+    expected.push(step(10, Instruction::MoveRight, 1, 0));
+    assert_eq!(first, expected);
+    //only as far as they were taken
+    assert_eq!(tm.steps, 10);
+    assert_eq!(tm.pointer(), 1);
+}
+
+#[test]
+fn the_whole_run() {
+    let mut tm = machine();
+    let observations: Vec<Observation> = tm.iter_steps().collect();
+    assert_eq!(
+        observations.last(),
+        Some(&Observation::Finished(Ok(RunOutcome::Halted)))
+    );
+    let steps: Vec<&StepObservation> = observations
+        .iter()
+        .filter_map(|observation| match observation {
+            Observation::Step(step) => Some(step),
+            Observation::Finished(_) => None,
+        })
+        .collect();
+    assert_eq!(steps.len() as u64, tm.steps);
+    assert!(steps
+        .windows(2)
+        .all(|pair| pair[1].step == pair[0].step + 1));
+    let written: Vec<u8> = steps.iter().filter_map(|step| step.output).collect();
+    assert_eq!(written, b"Hello World!\n");
+    //every output comes from a "."
+    assert!(steps
+        .iter()
+        .all(|step| step.output.is_none() || step.op == Instruction::Output));
+    //nothing after the end
+    assert_eq!(tm.iter_steps().count(), 1);
+}
+
+#[test]
+fn carrying_on_with_run() {
+    let mut tm = TuringMachine::new(HELLO, &Lang::Brainfuck).expect("the program parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    let sampled = tm.iter_steps().step_by(100).take(3).count();
+    assert_eq!(sampled, 3);
+    assert_eq!(tm.steps, 201);
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), b"Hello World!\n");
+}
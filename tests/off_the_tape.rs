@@ -0,0 +1,73 @@
+//! programs that go off one end of the tape or the other, run at every optimization level. the
+//! runs of moves, "+" and "-" in an optimized program are made shorter and run in one go, but
+//! going off the tape has to fail at the same move, after the same output, as it does at -O0
+#![cfg(feature = "std")]
+use bfint::optimize::Level;
+use bfint::program::Program;
+use bfint::{lang::Lang, RuntimeError, TuringMachine, TAPE_LENGTH};
+use std::sync::{Arc, Mutex};
+
+///what the program printed and how it failed, compiled at the level
+fn run(source: &str, level: Level) -> (Vec<u8>, RuntimeError) {
+    let program = Program::compile(source, &Lang::Brainfuck, level).expect("it parses");
+    let mut tm = TuringMachine::new("", &Lang::Brainfuck).expect("nothing parses");
+    tm.load_program(Arc::new(program));
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    let error = tm
+        .run()
+        .expect_err(&format!("{:?} stays on the tape at {}", source, level));
+    let output = bfint::shared::lock(&output).clone();
+    (output, error)
+}
+
+fn check(source: &str) {
+    let unoptimized = run(source, Level::O0);
+    assert!(
+        matches!(unoptimized.1, RuntimeError::PointerOutOfBounds(_)),
+        "{:?} failed with {:?}",
+        source,
+        unoptimized.1
+    );
+    for level in [Level::O1, Level::O2] {
+        assert_eq!(
+            run(source, level),
+            unoptimized,
+            "{:?} went off the tape differently at {}",
+            source,
+            level
+        );
+    }
+}
+
+///a move there and back is no move at all, except when there is off the tape
+#[test]
+fn there_and_back_off_the_start() {
+    check("+.<>.");
+    check("+.<<>>.");
+    check("+.>+<<<>>>-.");
+}
+
+#[test]
+fn there_and_back_off_the_end() {
+    let end = ">".repeat(TAPE_LENGTH - 1);
+    check(&format!("{}+.><.", end));
+    check(&format!("{}+.<+>>+<<.", end));
+}
+
+///the run goes off in the middle, after cells it has changed and before ones it would have
+#[test]
+fn off_the_start_partway_through_a_run() {
+    check("++.>+>-<<<<+>>>+.");
+    check("+[>+<<<<]");
+}
+
+///a loop that would run in one go but goes off the tape goes around an instruction at a time
+///instead, up to the move that goes off
+#[test]
+fn loops_run_in_one_go_off_the_tape() {
+    check("+[<]");
+    check("+>+>+[<<]");
+    check("+[-<+>]");
+    check(&format!("{}+>+>+[>]", ">".repeat(TAPE_LENGTH - 3)));
+}
@@ -0,0 +1,136 @@
+//! parsing a program on its own, without a machine: the options for strict characters, input
+//! after a "!" and cell names, every error there is found at once, and TuringMachine::new
+//! running exactly what parse gives back. none of it needs std
+use bfint::lang::Lang;
+use bfint::{parse, Instruction, ParseError, ParseOptions, Position};
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn with_the_default_options() {
+    let program = parse("+ comment\n[>.]", &ParseOptions::default()).expect("it parses");
+    assert_eq!(
+        program.tokens(),
+        [
+            (Instruction::Increment, at(1, 1)),
+            (Instruction::JumpToClose, at(2, 1)),
+            (Instruction::MoveRight, at(2, 2)),
+            (Instruction::Output, at(2, 3)),
+            (Instruction::JumpToOpen, at(2, 4)),
+        ]
+    );
+    assert_eq!(program.embedded_input(), None);
+    assert!(program.cell_names().is_empty());
+}
+
+#[test]
+fn every_error_at_once() {
+    let errors = parse("]+[[\n]]]\n[", &ParseOptions::default()).expect_err("it doesn't parse");
+    assert_eq!(
+        errors,
+        [
+            ParseError::UnmatchedClose(at(1, 1)),
+            ParseError::UnmatchedClose(at(2, 3)),
+            ParseError::UnmatchedOpen(at(3, 1)),
+        ]
+    );
+    //the first of them is what a machine is refused with
+    let first = bfint::TuringMachine::new("]+[[\n]]]\n[", &Lang::Brainfuck)
+        .err()
+        .expect("it doesn't parse");
+    assert_eq!(first, errors[0]);
+}
+
+#[test]
+fn strict_characters() {
+    let strict = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse("+ x\n-y]", &strict).expect_err("it has comments"),
+        [
+            ParseError::StrayCharacter('x', at(1, 3)),
+            ParseError::StrayCharacter('y', at(2, 2)),
+            ParseError::UnmatchedClose(at(2, 3)),
+        ]
+    );
+    //whitespace isn't stray
+    assert!(parse("+ \t\n-\r\n", &strict).is_ok());
+    //ook's words are made of characters that would be stray anywhere else
+    let ook = ParseOptions {
+        lang: Lang::Ook,
+        ..strict
+    };
+    assert_eq!(parse("Ook. Ook.", &ook).expect("it's ook").len(), 1);
+}
+
+#[test]
+fn input_after_a_separator() {
+    let separated = ParseOptions {
+        input_separator: true,
+        ..ParseOptions::default()
+    };
+    let program = parse(",.,.!hi!", &separated).expect("it parses");
+    assert_eq!(program.len(), 4);
+    assert_eq!(program.embedded_input(), Some(&b"hi!"[..]));
+    //without the option the "!" is a comment, and so is everything after it
+    let program = parse(",.,.!hi!", &ParseOptions::default()).expect("it parses");
+    assert_eq!(program.embedded_input(), None);
+    //a "#!" line's "!" isn't the separator
+    let program = parse("#!/usr/bin/env bfint\n,.!x", &separated).expect("it parses");
+    assert_eq!(program.embedded_input(), Some(&b"x"[..]));
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn cell_names() {
+    let named = ParseOptions {
+        cell_names: true,
+        ..ParseOptions::default()
+    };
+    let program = parse(";@3 counter\n>>++ ;@ptr total", &named).expect("it parses");
+    let names: Vec<(usize, &str)> = program
+        .cell_names()
+        .iter()
+        .map(|(cell, name)| (*cell, name.as_str()))
+        .collect();
+    assert_eq!(names, [(2, "total"), (3, "counter")]);
+    //a name that can't be read is one more error
+    let errors = parse("<;@ptr lost\n[", &named).expect_err("it doesn't parse");
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(matches!(errors[0], ParseError::Annotation(_)));
+    assert_eq!(errors[1], ParseError::UnmatchedOpen(at(2, 1)));
+}
+
+#[test]
+fn nested_too_deep() {
+    let shallow = ParseOptions {
+        max_nesting: 2,
+        ..ParseOptions::default()
+    };
+    assert!(parse("[[]][[]]", &shallow).is_ok());
+    assert_eq!(
+        parse("[[[]]]", &shallow).expect_err("it's too deep"),
+        [ParseError::TooDeep(at(1, 3), 2)]
+    );
+}
+
+///a machine made from the source runs what parse gives back, instruction for instruction
+#[test]
+fn the_machine_runs_what_parse_gives() {
+    let source = "++[>+++<-] comment >.";
+    let program = parse(source, &ParseOptions::default()).expect("it parses");
+    let tm = bfint::TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    let found = tm.find(|_| true);
+    assert_eq!(
+        found,
+        program
+            .tokens()
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,191 @@
+//! what a parsed program can say about itself: its instructions with where they are, the
+//! brackets that match, its loops with how deep they are, the text of part of its source, the
+//! input it came with and the hash checkpoints are checked with. and parsing one with parse and
+//! try_from, and writing it back out with to_string
+use bfint::lang::Lang;
+use bfint::optimize::Level;
+use bfint::{Instruction, LoopInfo, ParseError, ParseOptions, Position, Program};
+
+fn program(source: &str) -> Program {
+    source.parse().expect("it parses")
+}
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+fn loop_info(open: usize, close: usize, depth: usize) -> LoopInfo {
+    LoopInfo {
+        open,
+        close,
+        depth,
+        body: open + 1..close,
+    }
+}
+
+#[test]
+fn instructions() {
+    let program = program("+ [\n>-]");
+    assert_eq!(program.len(), 5);
+    assert!(!program.is_empty());
+    assert!(self::program("no instructions").is_empty());
+    assert_eq!(
+        program.instruction_at(0),
+        Some((Instruction::Increment, at(1, 1)))
+    );
+    assert_eq!(
+        program.instruction_at(3),
+        Some((Instruction::Decrement, at(2, 2)))
+    );
+    assert_eq!(program.instruction(4), Some(Instruction::JumpToOpen));
+    assert_eq!(program.instruction_at(5), None);
+    assert_eq!(program.instructions().count(), program.len());
+}
+
+#[test]
+fn matching_brackets() {
+    let program = program("[[-]>]");
+    for (bracket, other) in [(0, 5), (1, 3)] {
+        assert_eq!(program.matching_bracket(bracket), Some(other));
+        assert_eq!(program.matching_bracket(other), Some(bracket));
+    }
+    //not a bracket, and past the end
+    assert_eq!(program.matching_bracket(2), None);
+    assert_eq!(program.matching_bracket(6), None);
+}
+
+#[test]
+fn adjacent_loops() {
+    let program = program("[][-]+[>]");
+    assert_eq!(
+        program.loops().collect::<Vec<_>>(),
+        [loop_info(0, 1, 0), loop_info(2, 4, 0), loop_info(6, 8, 0)]
+    );
+    //an empty loop's body is empty
+    assert!(program.loops().next().expect("there's one").body.is_empty());
+}
+
+#[test]
+fn deeply_nested_loops() {
+    let depth = 1000;
+    let source = format!("{}+{}", "[".repeat(depth), "]".repeat(depth));
+    let program = program(&source);
+    let loops: Vec<LoopInfo> = program.loops().collect();
+    assert_eq!(loops.len(), depth);
+    for (index, info) in loops.iter().enumerate() {
+        assert_eq!(*info, loop_info(index, 2 * depth - index, index));
+    }
+    //the innermost one holds only the "+"
+    assert_eq!(loops[depth - 1].body, depth..depth + 1);
+}
+
+///loops side by side inside others, with the depths going up and down again
+#[test]
+fn nested_and_adjacent() {
+    let program = program("[[][[]]]>[]");
+    assert_eq!(
+        program.loops().collect::<Vec<_>>(),
+        [
+            loop_info(0, 7, 0),
+            loop_info(1, 2, 1),
+            loop_info(3, 6, 1),
+            loop_info(4, 5, 2),
+            loop_info(9, 10, 0),
+        ]
+    );
+    //every loop's brackets match
+    for info in program.loops() {
+        assert_eq!(program.matching_bracket(info.open), Some(info.close));
+    }
+}
+
+#[test]
+fn source_excerpts() {
+    let program = program("+ [>é<]\n[-]");
+    //a loop from its brackets, with the "é" in it taking two bytes
+    let info = program.loops().next().expect("there's a loop");
+    let (_, open) = program.instruction_at(info.open).expect("it's there");
+    let (_, close) = program.instruction_at(info.close).expect("it's there");
+    assert_eq!(program.source_excerpt(open, close), "[>é<]");
+    assert_eq!(program.source_excerpt(at(1, 6), at(2, 3)), "<]\n[-]");
+    assert_eq!(program.source_excerpt(at(2, 1), at(9, 1)), "[-]");
+}
+
+#[test]
+fn embedded_input() {
+    let options = ParseOptions {
+        input_separator: true,
+        ..ParseOptions::default()
+    };
+    let program = bfint::parse(",[.,]!abc", &options).expect("it parses");
+    assert_eq!(program.embedded_input(), Some(&b"abc"[..]));
+    assert_eq!(self::program(",[.,]").embedded_input(), None);
+}
+
+///the hash is of the source, comments and all, and doesn't change from one build to the next
+#[test]
+fn hashes() {
+    assert_eq!(program("").hash(), 0xcbf29ce484222325);
+    assert_eq!(program("+").hash(), 0xaf63a64c860190ca);
+    assert_eq!(program("+").hash(), program("+").hash());
+    assert_ne!(program("+").hash(), program("+ a comment").hash());
+    #[cfg(feature = "std")]
+    assert_eq!(
+        program("[-]").hash(),
+        bfint::checkpoint::program_hash("[-]")
+    );
+    let ook = Program::compile("Ook. Ook.", &Lang::Ook, Level::O0).expect("it's ook");
+    assert_eq!(ook.hash(), program("Ook. Ook.").hash());
+}
+
+#[test]
+fn from_str() {
+    let program: Program = "+[->+<] moves it".parse().expect("it parses");
+    assert_eq!(program.len(), 7);
+    assert_eq!(
+        "+[".parse::<Program>().expect_err("it's unmatched"),
+        ParseError::UnmatchedOpen(at(1, 2))
+    );
+    //the first error, the same one a machine is refused with
+    assert_eq!(
+        "]]".parse::<Program>().expect_err("it's unmatched"),
+        ParseError::UnmatchedClose(at(1, 1))
+    );
+    let tried = Program::try_from("+[->+<]").expect("it parses");
+    assert_eq!(tried.tokens(), program.tokens());
+    assert_eq!(
+        Program::try_from("]").expect_err("it's unmatched"),
+        ParseError::UnmatchedClose(at(1, 1))
+    );
+}
+
+///bytes are read like a file is: a byte order mark is passed over, and bytes that aren't utf-8
+///are a character each
+#[test]
+fn try_from_bytes() {
+    let program = Program::try_from(&b"\xef\xbb\xbf+\xe9["[..]).expect_err("it's unmatched");
+    assert_eq!(program, ParseError::UnmatchedOpen(at(1, 3)));
+    let program = Program::try_from(&b"caf\xe9 +."[..]).expect("it parses");
+    assert_eq!(
+        program.instruction_at(0),
+        Some((Instruction::Increment, at(1, 6)))
+    );
+    assert_eq!(program.to_string(), "+.");
+}
+
+///a program written out parses back into the same instructions
+#[test]
+fn display_round_trips() {
+    let program = program("hello\n++[>+<-]  world.");
+    assert_eq!(program.to_string(), "++[>+<-].");
+    let again = program
+        .to_string()
+        .parse::<Program>()
+        .expect("it parses back");
+    let instructions = |program: &Program| program.instructions().collect::<Vec<_>>();
+    assert_eq!(instructions(&again), instructions(&program));
+    assert_eq!(again.to_string(), program.to_string());
+    //and an optimized one as the instructions it was made from
+    let optimized = Program::compile("+++[-]>", &Lang::Brainfuck, Level::O2).expect("it parses");
+    assert_eq!(optimized.to_string(), "+++[-]>");
+}
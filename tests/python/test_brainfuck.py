@@ -0,0 +1,60 @@
+"""the brainfuck module as python sees it, once "maturin develop" has built and installed it.
+run with "pytest" from the top of the repository"""
+import pytest
+
+import brainfuck
+
+HELLO = (
+    "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<."
+    "+++.------.--------.>>+.>++."
+)
+
+
+def test_hello_world():
+    assert brainfuck.run(HELLO) == b"Hello World!\n"
+
+
+def test_input():
+    assert brainfuck.run(",[.,]", b"echo") == b"echo"
+
+
+def test_stepping():
+    machine = brainfuck.Machine("+++>++")
+    while machine.step():
+        pass
+    assert machine.steps == 6
+    assert machine.pointer == 1
+    assert machine.cell(0) == 3
+    assert machine.tape(0, 3) == b"\x03\x02\x00"
+
+
+def test_breakpoints():
+    machine = brainfuck.Machine("+.+.+.")
+    machine.add_breakpoint(1, 4)
+    assert machine.breakpoints == [(1, 4)]
+    assert machine.run()
+    assert machine.take_output() == b"\x01"
+    assert not machine.run()
+    assert machine.take_output() == b"\x02\x03"
+
+
+def test_parse_error():
+    with pytest.raises(brainfuck.ParseError):
+        brainfuck.run("+[")
+    with pytest.raises(brainfuck.ParseError):
+        brainfuck.Machine("]")
+
+
+def test_runtime_error():
+    with pytest.raises(brainfuck.RuntimeError):
+        brainfuck.run("<")
+
+
+def test_step_limit():
+    with pytest.raises(brainfuck.RuntimeError):
+        brainfuck.run("+[]", max_steps=1000)
+
+
+def test_cell_past_the_end():
+    with pytest.raises(IndexError):
+        brainfuck.Machine("+").cell(1_000_000)
@@ -0,0 +1,73 @@
+//! bf-rand's "?" with a fixed seed: a program printing several random bytes prints exactly the
+//! same ones every time, which are splitmix64's for that seed, whether the seed is given to the
+//! machine, through the seed setting or with bfint --seed
+#![cfg(feature = "std")]
+use bfint::config::InterpreterConfig;
+use bfint::control::RunOutcome;
+use bfint::lang::Lang;
+use bfint::random::Rng;
+use bfint::TuringMachine;
+use std::sync::{Arc, Mutex};
+
+///prints four random bytes, the last two from the same cell
+const PROGRAM: &str = "?.>?.?.?.";
+
+///the lowest bytes of the first four numbers splitmix64 gives for seed 0, which are
+///0xe220a8397b1dcdaf, 0x6e789e6aa1b965f4, 0x06c45d188009454f and 0xf88bb8a8724c81ec
+const SEED_0: [u8; 4] = [0xaf, 0xf4, 0x4f, 0xec];
+
+fn run(tm: &mut TuringMachine) -> Vec<u8> {
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    assert_eq!(tm.run(), Ok(RunOutcome::Halted));
+    let printed = bfint::shared::lock(&output).clone();
+    printed
+}
+
+#[test]
+fn seeded() {
+    let mut tm = TuringMachine::new(PROGRAM, &Lang::BfRand).expect("it parses");
+    tm.rng = Rng::from_seed(0);
+    assert_eq!(run(&mut tm), SEED_0);
+    //a different seed, which is just as fixed
+    let mut tm = TuringMachine::new(PROGRAM, &Lang::BfRand).expect("it parses");
+    tm.rng = Rng::from_seed(42);
+    let printed = run(&mut tm);
+    assert_ne!(printed, SEED_0);
+    let mut again = TuringMachine::new(PROGRAM, &Lang::BfRand).expect("it parses");
+    again.rng = Rng::from_seed(42);
+    assert_eq!(run(&mut again), printed);
+}
+
+#[test]
+fn seed_setting() {
+    let mut config = InterpreterConfig::default();
+    config.set("lang", "bf-rand").expect("it's a language");
+    config.set("seed", "0").expect("it's a seed");
+    let mut tm = TuringMachine::new(PROGRAM, &Lang::BfRand).expect("it parses");
+    config.apply(&mut tm).expect("it's applied");
+    assert_eq!(run(&mut tm), SEED_0);
+}
+
+///"?" is only an instruction in bf-rand
+#[test]
+fn off_by_default() {
+    let mut tm = TuringMachine::new(PROGRAM, &Lang::Brainfuck).expect("it parses");
+    assert_eq!(run(&mut tm), [0; 4]);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let path = std::env::temp_dir().join(format!("bfint-random-{}.b", std::process::id()));
+    std::fs::write(&path, PROGRAM).expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--lang", "bf-rand", "--seed", "0"])
+        .arg(&path)
+        .output()
+        .expect("bfint starts");
+    let _ = std::fs::remove_file(&path);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, SEED_0);
+}
@@ -0,0 +1,165 @@
+//! --input random:<seed>[:<length>]: the bytes a seed gives, pinned so a change to the generator
+//! can't go unnoticed, since it would make every run someone saved the seed of different. the
+//! input ending after its length, what "random:" takes, and the seed --stats says, which gives
+//! the same run again even when it was made up with "auto"
+#![cfg(feature = "std")]
+use bfint::io::RandomInput;
+use std::io::Read;
+
+///the first eight bytes of seed 42 and of seed 0, the lowest byte of each number splitmix64
+///gives. seed 0's first number is the well known 0xe220a8397b1dcdaf
+const FORTY_TWO: [u8; 8] = [149, 3, 82, 148, 242, 6, 93, 164];
+const ZERO: [u8; 8] = [175, 244, 79, 236, 155, 234, 225, 60];
+
+fn bytes(mut input: RandomInput) -> Vec<u8> {
+    let mut bytes = vec![];
+    input.read_to_end(&mut bytes).expect("it can't fail");
+    bytes
+}
+
+#[test]
+fn pinned_bytes() {
+    assert_eq!(bytes(RandomInput::new(42, Some(8))), FORTY_TWO);
+    assert_eq!(bytes(RandomInput::new(0, Some(8))), ZERO);
+    //a shorter length is the start of the same bytes
+    assert_eq!(bytes(RandomInput::new(42, Some(3))), FORTY_TWO[..3]);
+    assert_eq!(bytes(RandomInput::new(42, Some(0))), []);
+}
+
+#[test]
+fn without_a_length_it_never_ends() {
+    let mut input = RandomInput::new(42, None);
+    let mut buffer = [0; 4096];
+    input.read_exact(&mut buffer).expect("there's always more");
+    assert_eq!(buffer[..8], FORTY_TWO);
+    input.read_exact(&mut buffer).expect("there's always more");
+}
+
+#[test]
+fn from_its_name() {
+    let input = RandomInput::from_name("42:8").expect("it's a seed and a length");
+    assert_eq!(input.seed, 42);
+    assert_eq!(bytes(input), FORTY_TWO);
+    let input = RandomInput::from_name("0").expect("it's a seed");
+    assert_eq!(input, RandomInput::new(0, None));
+    //auto makes a seed up, and says which
+    let input = RandomInput::from_name("auto:8").expect("it's auto and a length");
+    let seed = input.seed;
+    assert_eq!(bytes(input), bytes(RandomInput::new(seed, Some(8))));
+    for name in ["", "forty-two", "42:", "42:many", "-1", "auto:-1"] {
+        assert_eq!(RandomInput::from_name(name), None, "{}", name);
+    }
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use std::path::PathBuf;
+    use std::process::{Command, Output};
+
+    ///prints four bytes of input, and the cell as it is after the input ended
+    const PROGRAM: &str = ",.,.,.,.,.";
+
+    ///PROGRAM in a file of the test's own, which goes away with it
+    struct Program(PathBuf);
+
+    impl Program {
+        fn new(test: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "bfint-random-{}-{}.b",
+                std::process::id(),
+                test
+            ));
+            std::fs::write(&path, PROGRAM).expect("it can be written");
+            Program(path)
+        }
+        fn path(&self) -> &str {
+            self.0.to_str().expect("the path is utf-8")
+        }
+    }
+
+    impl Drop for Program {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    ///runs the program, which has to succeed
+    fn bfint(program: &Program, args: &[&str]) -> Output {
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(args)
+            .arg(program.path())
+            .output()
+            .expect("bfint starts");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    }
+
+    #[test]
+    fn a_seed() {
+        let program = Program::new("seed");
+        let output = bfint(&program, &["--input", "random:42:4", "--stats"]);
+        assert_eq!(output.stdout, [149, 3, 82, 148, 0]);
+        let said = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            said.contains("the input was random bytes from seed 42\n"),
+            "{}",
+            said
+        );
+        //--eof says what "," does once it's ended
+        let output = bfint(&program, &["--input", "random:42:4", "--eof", "unchanged"]);
+        assert_eq!(output.stdout, [149, 3, 82, 148, 148]);
+    }
+
+    ///the seed auto made up gives the same run again
+    #[test]
+    fn auto() {
+        let program = Program::new("auto");
+        let output = bfint(&program, &["--input", "random:auto:4", "--stats"]);
+        let said = String::from_utf8_lossy(&output.stderr);
+        let seed = said
+            .lines()
+            .find_map(|line| line.strip_prefix("the input was random bytes from seed "))
+            .expect("--stats says the seed");
+        let again = bfint(&program, &["--input", &format!("random:{}:4", seed)]);
+        assert_eq!(again.stdout, output.stdout);
+    }
+
+    ///a recorded run on random input replays as it was
+    #[test]
+    fn recorded() {
+        let program = Program::new("recorded");
+        let transcript =
+            std::env::temp_dir().join(format!("bfint-random-{}.transcript", std::process::id()));
+        let transcript = transcript.to_str().expect("the path is utf-8").to_string();
+        let recorded = bfint(
+            &program,
+            &["--input", "random:7:4", "--record", &transcript],
+        );
+        let replayed = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--replay", &transcript, program.path()])
+            .output()
+            .expect("bfint starts");
+        let _ = std::fs::remove_file(&transcript);
+        assert!(
+            replayed.status.success(),
+            "{}",
+            String::from_utf8_lossy(&replayed.stderr)
+        );
+        assert_eq!(replayed.stdout, recorded.stdout);
+    }
+
+    #[test]
+    fn not_a_seed() {
+        let program = Program::new("not-a-seed");
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--input", "random:lots", program.path()])
+            .output()
+            .expect("bfint starts");
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("like random:42"));
+    }
+}
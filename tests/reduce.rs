@@ -0,0 +1,140 @@
+//! the reducer shrinking programs full of things that have nothing to do with the "bug" down to
+//! the few instructions that show it, every program it tries on the way parsing, and the same
+//! program always ending up at the same place. and bfint reduce --check with a command
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::reduce::{reduce, ReduceError, Reduction};
+use bfint::{ParseError, TuringMachine};
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///what the program prints with nothing to read, as far as it gets in a few thousand steps
+fn prints(source: &str) -> Vec<u8> {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("every candidate parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+    tm.step_limit = Some(5_000);
+    let _ = tm.run();
+    let printed = bfint::shared::lock(&output).clone();
+    printed
+}
+
+///reduces the program for as long as it prints the byte, checking every candidate is only tried
+///once
+fn reduced(source: &str, byte: u8) -> Reduction {
+    let mut tried = HashSet::new();
+    reduce(source, &Lang::Brainfuck, |candidate| {
+        assert!(tried.insert(candidate.to_string()), "{} twice", candidate);
+        prints(candidate).contains(&byte)
+    })
+    .expect("it reduces")
+}
+
+///"++." is the only program of three instructions or less that prints a 2
+#[test]
+fn down_to_the_core() {
+    let noisy = "this prints 2\n+++[-]++.\n>>+++[>++++<-]>.[-]<<,[.,]";
+    let reduction = reduced(noisy, 2);
+    assert_eq!(reduction.source, "++.");
+    assert_eq!((reduction.before, reduction.after), (35, 3));
+    assert!(reduction.checks > 3);
+    //the same every time
+    assert_eq!(reduced(noisy, 2), reduction);
+}
+
+///a loop that's needed stays, whole, with as little in it as still does what it did
+#[test]
+fn loops() {
+    //fourteen, made with a loop, and the noise around it
+    let noisy = ">>+[-]<<++>+++[<++++>-]<.[-]>>[>]";
+    let reduction = reduced(noisy, 14);
+    let core = prints(&reduction.source);
+    assert!(core.contains(&14), "{}", reduction.source);
+    assert!(reduction.source.contains('['), "{}", reduction.source);
+    assert!(reduction.after < 20, "{}", reduction.source);
+    //nothing more can come out, one instruction or one loop at a time
+    let source: Vec<char> = reduction.source.chars().collect();
+    for index in 0..source.len() {
+        if source[index] == '[' || source[index] == ']' {
+            continue;
+        }
+        let mut smaller = source.clone();
+        smaller.remove(index);
+        let smaller: String = smaller.into_iter().collect();
+        assert!(!prints(&smaller).contains(&14), "{} is smaller", smaller);
+    }
+}
+
+///every program it tries has its brackets matched, however deep the loops are
+#[test]
+fn matched_brackets() {
+    let nested = "+[>+[>+[>+.<-]<-]<-]>>>[[-]<]";
+    let mut tried = 0;
+    reduce(nested, &Lang::Brainfuck, |candidate| {
+        tried += 1;
+        assert!(
+            TuringMachine::new(candidate, &Lang::Brainfuck).is_ok(),
+            "{}",
+            candidate
+        );
+        prints(candidate).contains(&1)
+    })
+    .expect("it reduces");
+    assert!(tried > 10);
+}
+
+#[test]
+fn what_cant_be_reduced() {
+    assert_eq!(
+        reduce("Ook. Ook.", &Lang::Ook, |_| true),
+        Err(ReduceError::UnsupportedLang)
+    );
+    assert!(matches!(
+        reduce("+[", &Lang::Brainfuck, |_| true),
+        Err(ReduceError::ParseError(ParseError::UnmatchedOpen(_)))
+    ));
+    assert_eq!(
+        reduce("+.", &Lang::Brainfuck, |_| false),
+        Err(ReduceError::NotReproduced)
+    );
+    //with what the comments were taking up gone, it's gone too
+    assert_eq!(
+        reduce("a comment +.", &Lang::Brainfuck, |source| source
+            .contains('a')),
+        Err(ReduceError::NotReproduced)
+    );
+}
+
+///bfint reduce with a check that says whether the program still has two "+" in a row
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let directory = std::env::temp_dir().join(format!("bfint-reduce-test-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    std::fs::write(directory.join("bug.b"), "+>++[-<+>]<.>>+++.").expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args([
+            "reduce",
+            "bug.b",
+            "--check",
+            "grep -q '++' {}",
+            "-o",
+            "core.b",
+        ])
+        .current_dir(&directory)
+        .output()
+        .expect("bfint starts");
+    let core = std::fs::read_to_string(directory.join("core.b"));
+    let _ = std::fs::remove_dir_all(&directory);
+    let said = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", said);
+    assert_eq!(core.expect("it was written"), "++\n");
+    assert!(
+        said.contains("bug.b: 18 instructions reduced to 2"),
+        "{}",
+        said
+    );
+}
@@ -0,0 +1,241 @@
+//! the json-rpc service driven a line at a time, the way a script on the other end of a pipe
+//! would: parsing, running, formatting and analyzing programs, two sessions stepped through side
+//! by side, batches and notifications, and the error objects for everything that can be wrong
+#![cfg(feature = "rpc")]
+use bfint::rpc::{self, Service};
+use serde_json::{json, Value};
+
+///the answer to every line, parsed, with the lines that weren't answered left out
+fn session(lines: &[Value]) -> Vec<Value> {
+    let mut input = String::new();
+    for line in lines {
+        input.push_str(&line.to_string());
+        input.push('\n');
+    }
+    let mut output = vec![];
+    Service::new()
+        .serve(input.as_bytes(), &mut output)
+        .expect("every line is answered");
+    String::from_utf8(output)
+        .expect("the answers are utf-8")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every answer is json"))
+        .collect()
+}
+
+fn request(id: i64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+///the answer to the request with the id
+fn answer(answers: &[Value], id: i64) -> &Value {
+    answers
+        .iter()
+        .find(|answer| answer["id"] == id)
+        .unwrap_or_else(|| panic!("request {} was answered: {:?}", id, answers))
+}
+
+///the code of the error the request with the id was answered with
+fn code(answers: &[Value], id: i64) -> i64 {
+    answer(answers, id)["error"]["code"]
+        .as_i64()
+        .unwrap_or_else(|| panic!("request {} failed: {:?}", id, answers))
+}
+
+///prints "A"
+const HELLO: &str = "++++++++[>++++++++<-]>+.";
+
+#[test]
+fn programs() {
+    let answers = session(&[
+        request(1, "parse", json!({ "program": "+[\n" })),
+        request(2, "parse", json!({ "program": "[-][-]" })),
+        request(3, "run", json!({ "program": HELLO })),
+        request(
+            4,
+            "run",
+            json!({ "program": ",[.,]", "input": "hi", "config": { "eof": "0" } }),
+        ),
+        request(
+            5,
+            "run",
+            json!({ "program": "+.[]", "config": { "max-steps": 10 } }),
+        ),
+        request(6, "format", json!({ "program": "+[->+<]", "width": 4 })),
+        request(7, "analyze", json!({ "program": HELLO })),
+    ]);
+    assert_eq!(answers.len(), 7);
+    let diagnostics = &answer(&answers, 1)["result"]["diagnostics"];
+    assert_eq!(diagnostics[0]["severity"], "error");
+    assert_eq!(diagnostics[0]["line"], 1);
+    assert_eq!(diagnostics[0]["column"], 2);
+    //it parses, and the linter has something to say about it
+    let diagnostics = &answer(&answers, 2)["result"]["diagnostics"];
+    assert!(!diagnostics.as_array().expect("it's a list").is_empty());
+    let ran = &answer(&answers, 3)["result"];
+    assert_eq!(ran["output"], "A");
+    assert_eq!(ran["bytes"], json!([65]));
+    assert_eq!(ran["stats"]["instructions"], 24);
+    assert_eq!(answer(&answers, 4)["result"]["output"], "hi");
+    //what it wrote before the limit goes along with the error
+    assert_eq!(code(&answers, 5), rpc::RUNTIME_ERROR);
+    let data = &answer(&answers, 5)["error"]["data"];
+    assert_eq!(data["bytes"], json!([1]));
+    assert_eq!(data["stats"]["steps"], 10);
+    assert!(answer(&answers, 6)["result"]["formatted"].is_string());
+    assert!(answer(&answers, 7)["result"].is_object());
+}
+
+///two sessions at once, each stepping through its own program
+#[test]
+fn sessions() {
+    let one = json!({ "session": 1 });
+    let two = json!({ "session": 2 });
+    let answers = session(&[
+        request(
+            1,
+            "session.create",
+            json!({ "program": "+++\n[>+.<-]\n>." }),
+        ),
+        request(2, "session.create", json!({ "program": HELLO })),
+        request(
+            3,
+            "session.setBreakpoint",
+            json!({ "session": 1, "loc": "2:4", "if": "cell == 2" }),
+        ),
+        request(4, "session.step", json!({ "session": 1, "count": 3 })),
+        request(5, "session.continue", one.clone()),
+        request(6, "session.readCells", json!({ "session": 1, "count": 2 })),
+        request(7, "session.next", two.clone()),
+        request(8, "session.step", two.clone()),
+        request(9, "session.finish", two.clone()),
+        request(
+            10,
+            "session.readCells",
+            json!({ "session": 2, "from": 1, "count": 1 }),
+        ),
+        request(11, "session.continue", one.clone()),
+        request(12, "session.continue", one.clone()),
+        request(13, "session.close", one.clone()),
+        request(14, "session.step", one),
+        request(15, "session.continue", two),
+    ]);
+    assert_eq!(answer(&answers, 1)["result"]["session"], 1);
+    assert_eq!(answer(&answers, 2)["result"]["session"], 2);
+    let breakpoint = &answer(&answers, 3)["result"];
+    assert_eq!(breakpoint["loc"], "2:4");
+    let stepped = &answer(&answers, 4)["result"];
+    assert_eq!(stepped["reason"], "step");
+    assert_eq!(stepped["loc"], "2:1");
+    assert_eq!(stepped["steps"], 3);
+    //the second time round, after printing 1
+    let stopped = &answer(&answers, 5)["result"];
+    assert_eq!(stopped["reason"], "breakpoint");
+    assert_eq!(stopped["breakpoint"], breakpoint["id"]);
+    assert_eq!(stopped["bytes"], json!([1]));
+    assert_eq!(answer(&answers, 6)["result"]["values"], json!([2, 2]));
+    //over the eight "+"s one at a time, then over the whole loop
+    assert_eq!(answer(&answers, 7)["result"]["loc"], "1:2");
+    assert_eq!(answer(&answers, 8)["result"]["steps"], 2);
+    let finished = &answer(&answers, 9)["result"];
+    assert_eq!(finished["reason"], "halted");
+    assert_eq!(finished["output"], "A");
+    assert_eq!(answer(&answers, 10)["result"]["values"], json!([65]));
+    let ended = &answer(&answers, 11)["result"];
+    assert_eq!(ended["reason"], "halted");
+    assert_eq!(ended["bytes"], json!([2, 3, 3]));
+    assert_eq!(code(&answers, 12), rpc::FINISHED);
+    assert_eq!(answer(&answers, 13)["result"], json!({}));
+    assert_eq!(code(&answers, 14), rpc::UNKNOWN_SESSION);
+    assert_eq!(code(&answers, 15), rpc::FINISHED);
+}
+
+#[test]
+fn batches_and_notifications() {
+    let notification =
+        json!({ "jsonrpc": "2.0", "method": "session.create", "params": { "program": "+" } });
+    let answers = session(&[
+        json!([
+            request(1, "run", json!({ "program": "+." })),
+            notification.clone()
+        ]),
+        //only notifications, so nothing is answered
+        json!([notification.clone()]),
+        notification,
+        json!([]),
+        //the notifications created sessions all the same
+        request(2, "session.step", json!({ "session": 3 })),
+    ]);
+    assert_eq!(answers.len(), 3, "{:?}", answers);
+    assert_eq!(answers[0][0]["result"]["bytes"], json!([1]));
+    assert_eq!(answers[0].as_array().expect("it's a batch").len(), 1);
+    assert_eq!(answers[1]["error"]["code"], rpc::INVALID_REQUEST);
+    assert_eq!(answer(&answers, 2)["result"]["reason"], "step");
+}
+
+#[test]
+fn errors() {
+    let mut service = Service::new();
+    let mut code = |line: &str| {
+        let answer = service.handle_line(line).expect("it's answered");
+        answer["error"]["code"].as_i64().expect("it's an error")
+    };
+    assert_eq!(code("not json"), rpc::PARSE_ERROR);
+    assert_eq!(code(r#"{"id": 1, "method": "run"}"#), rpc::INVALID_REQUEST);
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "fly"}"#),
+        rpc::METHOD_NOT_FOUND
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "run", "params": [1]}"#),
+        rpc::INVALID_PARAMS
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "run", "params": {}}"#),
+        rpc::INVALID_PARAMS
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "run", "params": {"program": "]"}}"#),
+        rpc::PROGRAM_PARSE_ERROR
+    );
+    assert_eq!(
+        code(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "run", "params": {"program": "+", "config": {"speed": 1}}}"#
+        ),
+        rpc::CONFIG_ERROR
+    );
+    assert_eq!(
+        code(r#"{"jsonrpc": "2.0", "id": 1, "method": "session.step", "params": {"session": 9}}"#),
+        rpc::UNKNOWN_SESSION
+    );
+}
+
+///through a real pipe, to bfint rpc
+#[cfg(feature = "cli")]
+#[test]
+fn over_a_pipe() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Command, Stdio};
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("rpc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("bfint starts");
+    let mut stdin = child.stdin.take().expect("it's piped");
+    let mut stdout = BufReader::new(child.stdout.take().expect("it's piped"));
+    //each answer comes back before the next request is sent
+    let mut ask = |request: Value| {
+        writeln!(stdin, "{}", request).expect("it takes the request");
+        stdin.flush().expect("it takes the request");
+        let mut line = String::new();
+        stdout.read_line(&mut line).expect("it answers");
+        serde_json::from_str::<Value>(&line).expect("the answer is json")
+    };
+    let created = ask(request(1, "session.create", json!({ "program": HELLO })));
+    assert_eq!(created["result"]["session"], 1);
+    let ran = ask(request(2, "session.continue", json!({ "session": 1 })));
+    assert_eq!(ran["result"]["output"], "A");
+    drop(stdin);
+    assert!(child.wait().expect("it finishes").success());
+}
@@ -0,0 +1,163 @@
+//! --runtime-warnings on programs made to set off exactly one detector each: a cell going back and
+//! forth, the pointer going back and forth, and the same byte printed forever. the run carries on
+//! either way, a detector that's left out says nothing, and one that keeps finding the same thing
+//! only says so once
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::warnings::{Detector, RuntimeWarning, SAMPLE_EVERY};
+use bfint::{Position, RuntimeError, TuringMachine};
+use std::sync::{Arc, Mutex};
+
+///takes the first cell from 1 to 0 and back, forever
+const OSCILLATING: &str = "+[-+]";
+///goes from the first cell to the second and back, forever, with both of them 1 so the part of
+///the tape that's used stays the same
+const PING_PONG: &str = "+>+<[><]";
+///prints a 1, forever
+const REPEATING: &str = "+[.]";
+
+///long enough for a full set of looks, a hundred of them SAMPLE_EVERY steps apart
+const STEPS: u64 = 101 * SAMPLE_EVERY;
+
+///runs the program up to the step limit with the detectors, and gives back what they said
+fn warnings(source: &str, detectors: &[Detector], steps: u64) -> Vec<RuntimeWarning> {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    tm.output = Arc::new(Mutex::new(std::io::sink()));
+    tm.step_limit = Some(steps);
+    let found = Arc::new(Mutex::new(vec![]));
+    let kept = Arc::clone(&found);
+    tm.set_runtime_warnings(detectors, move |warning| {
+        bfint::shared::lock(&kept).push(warning.clone())
+    });
+    //none of them stop it
+    assert_eq!(tm.run(), Err(RuntimeError::StepLimitReached(steps)));
+    let found = bfint::shared::lock(&found).clone();
+    found
+}
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn oscillation() {
+    let found = warnings(OSCILLATING, &Detector::ALL, STEPS);
+    let [warning] = &found[..] else {
+        panic!("{:?}", found);
+    };
+    assert_eq!(warning.detector, Detector::Oscillation);
+    assert_eq!(warning.step, 99 * SAMPLE_EVERY);
+    assert!(
+        warning
+            .message
+            .starts_with("cell 0 has been going back and forth between "),
+        "{}",
+        warning.message
+    );
+    assert!(
+        warning
+            .message
+            .ends_with(" since step 0, with nothing else on the tape changing"),
+        "{}",
+        warning.message
+    );
+    assert_eq!(warning.position.line, 1);
+}
+
+#[test]
+fn ping_pong() {
+    let found = warnings(PING_PONG, &Detector::ALL, STEPS);
+    let [warning] = &found[..] else {
+        panic!("{:?}", found);
+    };
+    assert_eq!(warning.detector, Detector::PingPong);
+    assert_eq!(
+        warning.message,
+        "the pointer has only been at cells 0 and 1 since step 0"
+    );
+    assert_eq!(warning.step, 99 * SAMPLE_EVERY);
+}
+
+#[test]
+fn repeated_output() {
+    let found = warnings(REPEATING, &Detector::ALL, STEPS);
+    let [warning] = &found[..] else {
+        panic!("{:?}", found);
+    };
+    assert_eq!(warning.detector, Detector::RepeatedOutput);
+    assert_eq!(
+        warning.message,
+        "the last 4096 bytes written are the same byte over and over: \"\\x01\""
+    );
+    //the "." that wrote it
+    assert_eq!(warning.position, at(1, 3));
+    //as soon as 4096 bytes were written, two steps each, and looked at
+    assert_eq!(warning.step, SAMPLE_EVERY);
+    assert_eq!(
+        warning.to_string(),
+        format!(
+            "step {} at 1:3: {} (repeated-output)",
+            SAMPLE_EVERY, warning.message
+        )
+    );
+}
+
+///leaving the detector out, or all of them, and each program says nothing
+#[test]
+fn left_out() {
+    for (source, detector) in [
+        (OSCILLATING, Detector::Oscillation),
+        (PING_PONG, Detector::PingPong),
+        (REPEATING, Detector::RepeatedOutput),
+    ] {
+        let others: Vec<Detector> = Detector::ALL
+            .into_iter()
+            .filter(|other| *other != detector)
+            .collect();
+        assert_eq!(warnings(source, &others, STEPS), [], "{}", source);
+        assert_eq!(warnings(source, &[], STEPS), [], "{}", source);
+    }
+}
+
+///the same thing going on for three times as long is still one warning
+#[test]
+fn once() {
+    let found = warnings(REPEATING, &[Detector::RepeatedOutput], 3 * STEPS);
+    assert_eq!(found.len(), 1, "{:?}", found);
+}
+
+#[test]
+fn names() {
+    for detector in Detector::ALL {
+        assert_eq!(Detector::from_name(detector.name()), Some(detector));
+    }
+    assert_eq!(Detector::from_name("ping_pong"), None);
+}
+
+///--runtime-warnings, and --no-warn leaving one out
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let path = std::env::temp_dir().join(format!("bfint-warnings-{}.b", std::process::id()));
+    std::fs::write(&path, PING_PONG).expect("it can be written");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--runtime-warnings", "--max-steps", &STEPS.to_string()])
+            .args(args)
+            .arg(&path)
+            .output()
+            .expect("bfint starts");
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+    let warned = run(&[]);
+    let not_warned = run(&["--no-warn", "ping-pong,oscillation"]);
+    let _ = std::fs::remove_file(&path);
+    assert!(warned.contains("[warn] step 990693 at 1:"), "{}", warned);
+    assert!(
+        warned.contains("the pointer has only been at cells 0 and 1 since step 0 (ping-pong)"),
+        "{}",
+        warned
+    );
+    assert!(!not_warned.contains("[warn]"), "{}", not_warned);
+}
@@ -0,0 +1,120 @@
+//! the sampling profiler on its step clock, which takes the same samples every run: which
+//! instructions they found, the report of the places and loops with their shares, and the
+//! samples as folded stacks, the same as the counts would be but thinned out
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::listing::folded;
+use bfint::observe::Observation;
+use bfint::sampler::{Clock, Sampler};
+use bfint::TuringMachine;
+use std::sync::{Arc, Mutex};
+
+///17 steps, 14 of them in the loop
+const PROGRAM: &str = "++[>+++<-]";
+
+///the program run with a sampler on the clock, which is given back once it's done
+fn sampled(clock: Clock) -> (TuringMachine, Arc<Mutex<Sampler>>) {
+    let mut tm = TuringMachine::new(PROGRAM, &Lang::Brainfuck).expect("it parses");
+    let sampler = Arc::new(Mutex::new(Sampler::new(clock)));
+    tm.set_observer(sampler.clone());
+    tm.run().expect("it runs");
+    (tm, sampler)
+}
+
+#[test]
+fn every_other_step() {
+    let (tm, sampler) = sampled(Clock::Steps(2));
+    let sampler = bfint::shared::lock(&sampler);
+    //the instruction of every second step, counted from the steps themselves
+    let mut expected = vec![0; tm.program().len()];
+    let mut reference = TuringMachine::new(PROGRAM, &Lang::Brainfuck).expect("it parses");
+    for observation in reference.iter_steps() {
+        if let Observation::Step(step) = observation {
+            if step.step % 2 == 0 {
+                expected[step.pc] += 1;
+            }
+        }
+    }
+    assert_eq!(sampler.profile(tm.program()).counts, expected);
+    //steps 2, 4 and so on land on every instruction in the loop once, and the second "+"
+    assert_eq!(
+        sampler.report(tm.program()),
+        "8 samples, one every 2 steps\n\
+         place  samples   share\n\
+         1:10         1   12.5%\n\
+         1:2          1   12.5%\n\
+         1:4          1   12.5%\n\
+         1:5          1   12.5%\n\
+         1:6          1   12.5%\n\
+         1:7          1   12.5%\n\
+         1:8          1   12.5%\n\
+         1:9          1   12.5%\n\
+         loop      samples   share\n\
+         loop@1:3        7   87.5%\n"
+    );
+}
+
+///sampling every step is counting every step
+#[test]
+fn every_step() {
+    let (tm, sampler) = sampled(Clock::Steps(1));
+    let sampler = bfint::shared::lock(&sampler);
+    let profile = sampler.profile(tm.program());
+    assert_eq!(profile.counts.iter().sum::<u64>(), tm.steps);
+    assert_eq!(
+        folded(tm.program(), &profile, "prog"),
+        "prog 3\nprog;loop@1:3 14\n"
+    );
+    assert!(sampler
+        .report(tm.program())
+        .ends_with("loop@1:3       14   82.4%\n"));
+}
+
+///more steps between samples than the run has takes none, and says so
+#[test]
+fn no_samples() {
+    let (tm, sampler) = sampled(Clock::Steps(100));
+    let sampler = bfint::shared::lock(&sampler);
+    assert_eq!(
+        sampler.report(tm.program()),
+        "0 samples, one every 100 steps\n"
+    );
+    assert_eq!(
+        folded(tm.program(), &sampler.profile(tm.program()), "prog"),
+        ""
+    );
+}
+
+///the timer thread is stopped when the run ends, however few samples it managed
+#[test]
+fn on_a_timer() {
+    let (tm, sampler) = sampled(Clock::Hertz(1000));
+    let report = bfint::shared::lock(&sampler).report(tm.program());
+    assert!(report.contains(" samples at 1000 Hz\n"), "{}", report);
+}
+
+///bfint --profile-sample-steps, writing the samples as folded stacks
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    let directory = std::env::temp_dir().join(format!("bfint-sampler-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    let program = directory.join("prog.b");
+    let out = directory.join("out.folded");
+    std::fs::write(&program, PROGRAM).expect("it can be written");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--profile-sample-steps", "2", "--profile-folded"])
+        .arg(&out)
+        .arg(&program)
+        .output()
+        .expect("bfint starts");
+    let written = std::fs::read_to_string(&out);
+    let _ = std::fs::remove_dir_all(&directory);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("loop@1:3        7   87.5%"), "{}", stderr);
+    assert_eq!(
+        written.expect("it wrote the stacks"),
+        "prog.b 1\nprog.b;loop@1:3 7\n"
+    );
+}
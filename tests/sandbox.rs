@@ -0,0 +1,146 @@
+//! the bfint binary run with --sandbox: what would open a file once the filter is in place is
+//! refused before anything runs, and the rest runs like it does without it, files opened before
+//! the filter included
+#![cfg(all(feature = "cli", feature = "sandbox", target_os = "linux"))]
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+///writes "1"
+const ONE: &str = "++++++++[>++++++<-]>+.";
+
+///a directory for the files one test uses, which goes away with it
+struct Sandboxed {
+    directory: PathBuf,
+}
+
+impl Sandboxed {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-sandbox-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Sandboxed { directory }
+    }
+    fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(name)
+    }
+    ///runs "bfint run --sandbox" with the options on the program, with nothing on stdin
+    fn run(&self, options: &[&str], source: &str) -> Output {
+        let program = self.path("program.bf");
+        std::fs::write(&program, source).expect("the temporary directory can be written to");
+        Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .current_dir(&self.directory)
+            .args(["run", "--sandbox"])
+            .args(options)
+            .arg(&program)
+            .stdin(Stdio::null())
+            .output()
+            .expect("bfint starts")
+    }
+}
+
+impl Drop for Sandboxed {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn a_plain_program_runs() {
+    let sandboxed = Sandboxed::new("plain");
+    let output = sandboxed.run(&[], ONE);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(output.stdout, b"1");
+}
+
+///bf-file's "#" opens a file while the program runs, so it's refused before it starts, like the
+///options that open files after it
+#[test]
+fn what_opens_files_while_it_runs_is_refused() {
+    let sandboxed = Sandboxed::new("refused");
+    for options in [
+        &["--lang", "bf-file"][..],
+        &["--record", "run.json"],
+        &["--profile", "profile.txt"],
+    ] {
+        let output = sandboxed.run(options, ONE);
+        assert_eq!(
+            output.status.code(),
+            Some(2),
+            "{:?}: {}",
+            options,
+            stderr(&output)
+        );
+        assert_eq!(output.stdout, b"", "{:?} ran anyway", options);
+        assert!(
+            stderr(&output).contains("--sandbox"),
+            "{:?}: {}",
+            options,
+            stderr(&output)
+        );
+    }
+    assert!(!sandboxed.path("run.json").exists());
+    assert!(!sandboxed.path("profile.txt").exists());
+}
+
+///the files these write are opened before the filter goes in, and only written and closed after
+#[test]
+fn files_opened_before_it_are_written() {
+    let sandboxed = Sandboxed::new("files");
+    let output = sandboxed.run(&["--capture-input", "captured.bin"], ",.");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(output.stdout, b"\0");
+    assert!(sandboxed.path("captured.bin").exists());
+    let output = sandboxed.run(
+        &["--log-state-every", "10", "--log-state-file", "state.log"],
+        ONE,
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(output.stdout, b"1");
+    let log = std::fs::read_to_string(sandboxed.path("state.log")).expect("the log was written");
+    assert!(!log.is_empty());
+}
+
+///sleeping between instructions gives the thread waiting for ctrl+c time to start after the
+///filter is in place, which it has to be able to do, and ending on an error still says so
+#[test]
+fn a_delayed_run_that_runs_out_of_fuel() {
+    let sandboxed = Sandboxed::new("delay");
+    for _ in 0..20 {
+        let output = sandboxed.run(&["--delay", "1ms", "--max-steps", "5"], ONE);
+        assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+        assert!(
+            stderr(&output).contains("ran out of fuel"),
+            "{}",
+            stderr(&output)
+        );
+    }
+}
+
+///--output files are made before the filter goes in, and the output goes to all of them
+#[test]
+fn output_goes_to_files_and_stdout() {
+    let sandboxed = Sandboxed::new("output");
+    let output = sandboxed.run(
+        &[
+            "--output",
+            "first.txt",
+            "--output",
+            "second.txt",
+            "--output",
+            "-",
+        ],
+        ONE,
+    );
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(output.stdout, b"1");
+    for file in ["first.txt", "second.txt"] {
+        assert_eq!(
+            std::fs::read(sandboxed.path(file)).expect("it was written"),
+            b"1"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! "bfint self-test" at every level, tiered and not, which runs the programs built into it on
+//! the engine those options pick, and refuses options it doesn't know
+#![cfg(feature = "cli")]
+use std::process::Command;
+
+///runs "bfint self-test" with the options, giving back its last line and whether it passed
+fn self_test(options: &[&str]) -> (String, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("self-test")
+        .args(options)
+        .output()
+        .expect("bfint starts");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last = stdout.lines().last().unwrap_or_default().to_string();
+    (last, output.status.success())
+}
+
+///all at once, since every one of them takes a while
+#[test]
+fn on_every_engine() {
+    let engines = [
+        &[][..],
+        &["-O1"],
+        &["-O2"],
+        &["--tiered"],
+        &["-O2", "--tiered"],
+    ];
+    std::thread::scope(|scope| {
+        for options in engines {
+            scope.spawn(move || {
+                let (last, passed) = self_test(options);
+                assert!(passed, "{:?}: {}", options, last);
+                assert!(
+                    last.ends_with(" passed, 0 failed"),
+                    "{:?}: {}",
+                    options,
+                    last
+                );
+            });
+        }
+    });
+}
+
+#[test]
+fn unknown_options() {
+    for options in [&["-O3"][..], &["--lang", "ook"], &["program.bf"]] {
+        let (_, passed) = self_test(options);
+        assert!(!passed, "{:?}", options);
+    }
+}
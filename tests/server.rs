@@ -0,0 +1,117 @@
+//! the playground served on localhost, with requests that are fine and ones that go past its
+//! limits on the headers and on how long the request takes to send
+#![cfg(feature = "server")]
+use bfint::server::{Limits, Server};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LIMITS: Limits = Limits {
+    max_steps: 10_000,
+    max_time: Duration::from_secs(5),
+    max_output: 1024,
+    max_request: 1024,
+    max_headers: 8,
+    max_header_bytes: 1024,
+    max_request_time: Duration::from_millis(500),
+    max_connections: 4,
+};
+
+///a server with the limits, going until the flag is set
+fn serve() -> (SocketAddr, Arc<AtomicBool>) {
+    let server = Server::bind("127.0.0.1:0", LIMITS).expect("it binds");
+    let address = server.local_addr().expect("it has an address");
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = Arc::clone(&stop);
+    thread::spawn(move || server.serve(&stopped));
+    (address, stop)
+}
+
+///sends the request all at once and gives back the whole answer
+fn send(request: &[u8]) -> String {
+    let (address, stop) = serve();
+    let mut stream = TcpStream::connect(address).expect("it connects");
+    stream.write_all(request).expect("it sends");
+    let mut answer = String::new();
+    stream.read_to_string(&mut answer).expect("it answers");
+    stop.store(true, Ordering::Relaxed);
+    answer
+}
+
+fn status(answer: &str) -> &str {
+    answer.split("\r\n").next().unwrap_or("")
+}
+
+#[test]
+fn runs_a_program() {
+    let body = r#"{"program": "++++++++[>++++++<-]>+."}"#;
+    let answer = send(
+        format!(
+            "POST /run HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .as_bytes(),
+    );
+    assert_eq!(status(&answer), "HTTP/1.1 200 OK");
+    assert!(answer.contains(r#""output_base64":"MQ==""#), "{}", answer);
+    assert!(answer.contains(r#""status":"halted""#), "{}", answer);
+}
+
+#[test]
+fn too_many_headers() {
+    let headers = "X-Header: 1\r\n".repeat(LIMITS.max_headers);
+    let answer = send(format!("GET / HTTP/1.1\r\n{}\r\n", headers).as_bytes());
+    assert_eq!(status(&answer), "HTTP/1.1 200 OK");
+    let headers = "X-Header: 1\r\n".repeat(LIMITS.max_headers + 1);
+    let answer = send(format!("GET / HTTP/1.1\r\n{}\r\n", headers).as_bytes());
+    assert_eq!(
+        status(&answer),
+        "HTTP/1.1 431 Request Header Fields Too Large"
+    );
+}
+
+///the limit is on all the headers together, not on each of them
+#[test]
+fn headers_too_large() {
+    let header = format!("X-Header: {}\r\n", "a".repeat(LIMITS.max_header_bytes / 4));
+    let answer = send(format!("GET / HTTP/1.1\r\n{}\r\n", header.repeat(2)).as_bytes());
+    assert_eq!(status(&answer), "HTTP/1.1 200 OK");
+    let answer = send(format!("GET / HTTP/1.1\r\n{}\r\n", header.repeat(4)).as_bytes());
+    assert_eq!(
+        status(&answer),
+        "HTTP/1.1 431 Request Header Fields Too Large"
+    );
+}
+
+///a client sending a byte at a time, never waiting long between them, is still cut off once the
+///request as a whole has taken too long
+#[test]
+fn sending_too_slowly() {
+    let (address, stop) = serve();
+    let mut stream = TcpStream::connect(address).expect("it connects");
+    let start = Instant::now();
+    let mut cut_off = false;
+    for byte in b"GET / HTTP/1.1\r\n".iter().cycle().take(100) {
+        if stream.write_all(&[*byte]).is_err() {
+            cut_off = true;
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+        //the server closing the connection shows up as the end of what it sent
+        stream
+            .set_read_timeout(Some(Duration::from_millis(1)))
+            .expect("it sets the timeout");
+        if let Ok(0) = stream.read(&mut [0]) {
+            cut_off = true;
+            break;
+        }
+    }
+    stop.store(true, Ordering::Relaxed);
+    assert!(cut_off, "the connection was never closed");
+    assert!(start.elapsed() >= LIMITS.max_request_time);
+    assert!(start.elapsed() < LIMITS.max_request_time * 4);
+}
@@ -0,0 +1,184 @@
+//! the settings run takes, given to debug and repl instead: they go through the same
+//! InterpreterConfig, so limits stop the program being debugged or typed in, combinations that
+//! don't go together are refused with the same message, and a profile can hold any of them
+#![cfg(feature = "cli")]
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-settings-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+    ///a file in it holding the text
+    fn file(&self, name: &str, text: &str) -> String {
+        let path = self.0.join(name);
+        std::fs::write(&path, text).expect("the temporary directory can be written to");
+        path.to_str().expect("the path is utf-8").to_string()
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///how a run of bfint went
+struct Ran {
+    stdout: String,
+    stderr: String,
+    code: Option<i32>,
+}
+
+///runs bfint with the arguments and what's piped into it
+fn bfint(args: &[&str], stdin: &str) -> Ran {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("bfint starts");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(stdin.as_bytes())
+        .expect("it takes its input");
+    let output = child.wait_with_output().expect("bfint finishes");
+    Ran {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        code: output.status.code(),
+    }
+}
+
+///a program that never ends, and a script that runs it
+fn endless(directory: &Directory) -> [String; 2] {
+    [
+        directory.file("script", "run\n"),
+        directory.file("endless.bf", "+[]"),
+    ]
+}
+
+#[test]
+fn limits_stop_the_debugger() {
+    let directory = Directory::new("debug-limits");
+    let [script, program] = endless(&directory);
+    let ran = bfint(
+        &["debug", "--max-steps", "5", "--script", &script, &program],
+        "",
+    );
+    assert!(
+        ran.stdout.contains("stopped after running 5 steps"),
+        "{}",
+        ran.stdout
+    );
+    //the same limit is how long the machines run in lockstep for
+    let ran = bfint(&["debug", "--lockstep", "--max-steps", "50", &program], "");
+    assert!(
+        ran.stdout.contains("agreed at all 50 sync points"),
+        "{}",
+        ran.stdout
+    );
+}
+
+#[test]
+fn limits_stop_the_repl() {
+    let ran = bfint(&["repl", "--no-history", "--max-steps", "5"], "+[]\n+\n");
+    assert!(
+        ran.stdout.contains("error: stopped after running 5 steps"),
+        "{}",
+        ran.stdout
+    );
+    //the line that failed is taken back, and the next one runs
+    assert!(ran.stdout.contains("pointer 0, cell 1\n"), "{}", ran.stdout);
+    assert_eq!(ran.code, Some(0));
+}
+
+#[test]
+fn combinations_are_refused() {
+    let directory = Directory::new("combinations");
+    let [script, program] = endless(&directory);
+    for args in [
+        &[
+            "debug",
+            "--tiered",
+            "--track-writers",
+            "--script",
+            &script,
+            &program,
+        ][..],
+        &["repl", "--no-history", "--tiered", "--track-writers"],
+    ] {
+        let ran = bfint(args, "");
+        assert_eq!(ran.code, Some(2), "{:?}", args);
+        assert!(
+            ran.stderr
+                .contains("tiered can't be used with track-writers"),
+            "{}",
+            ran.stderr
+        );
+    }
+    let ran = bfint(&["repl", "--no-history", "--eof", "sometimes"], "");
+    assert_eq!(ran.code, Some(2));
+    assert!(ran
+        .stderr
+        .contains("'sometimes' isn't something eof can be"));
+    //big cells only go with programs that are all plain brainfuck, which lines that haven't
+    //been typed yet can't be said to be
+    let ran = bfint(&["repl", "--no-history", "--cell-width", "big"], "");
+    assert_eq!(ran.code, Some(2));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn from_a_profile() {
+    let directory = Directory::new("profile");
+    let [script, program] = endless(&directory);
+    let profile = directory.file("profile.toml", "max-steps = 5\n");
+    let ran = bfint(
+        &["debug", "--config", &profile, "--script", &script, &program],
+        "",
+    );
+    assert!(
+        ran.stdout.contains("stopped after running 5 steps"),
+        "{}",
+        ran.stdout
+    );
+    //what's given along with the profile wins
+    let ran = bfint(
+        &[
+            "debug",
+            "--max-steps",
+            "7",
+            "--config",
+            &profile,
+            "--script",
+            &script,
+            &program,
+        ],
+        "",
+    );
+    assert!(
+        ran.stdout.contains("stopped after running 7 steps"),
+        "{}",
+        ran.stdout
+    );
+    let misspelled = directory.file("misspelled.toml", "max-stepz = 5\n");
+    let ran = bfint(&["repl", "--no-history", "--config", &misspelled], "");
+    assert_eq!(ran.code, Some(2));
+    assert!(
+        ran.stderr
+            .contains("there is no setting called 'max-stepz'"),
+        "{}",
+        ran.stderr
+    );
+}
@@ -0,0 +1,120 @@
+//! programs run one after the other on the same tape: hello world split in two where no loop is
+//! cut in half, and each half loaded after the other onto one machine, prints what the whole
+//! program does and leaves the same tape. and bfint --shared-tape with the two halves, naming the
+//! stage that fails and stopping one that runs too long
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::lang::Lang;
+use bfint::TuringMachine;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+const HELLO: &str = include_str!("fixtures/hello.bf");
+const EXPECTED: &[u8] = include_bytes!("fixtures/hello.out");
+
+///the program in two, after the "." that prints the middle of what it prints, which isn't inside
+///a loop
+fn split(source: &str) -> (&str, &str) {
+    let middle = source.matches('.').count() / 2;
+    let mut depth = 0;
+    let mut printed = 0;
+    for (index, c) in source.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '.' => printed += 1,
+            _ => {}
+        }
+        if depth == 0 && printed == middle {
+            return source.split_at(index + 1);
+        }
+    }
+    panic!("there's nowhere to split it");
+}
+
+fn machine(source: &str) -> (TuringMachine, Arc<Mutex<Vec<u8>>>) {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    let output = Arc::new(Mutex::new(vec![]));
+    tm.output = output.clone();
+    tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+    (tm, output)
+}
+
+#[test]
+fn split_in_two() {
+    let (first, second) = split(HELLO);
+    let (mut whole, whole_output) = machine(HELLO);
+    assert_eq!(whole.run(), Ok(RunOutcome::Halted));
+    let (mut stages, output) = machine(first);
+    assert_eq!(stages.run(), Ok(RunOutcome::Halted));
+    //the first half prints some of it, and leaves the tape for the second
+    let printed = bfint::shared::lock(&output).len();
+    assert!(printed > 0 && printed < EXPECTED.len(), "{}", printed);
+    stages.load(second, &Lang::Brainfuck).expect("it parses");
+    assert_eq!(stages.run(), Ok(RunOutcome::Halted));
+    assert_eq!(*bfint::shared::lock(&output), EXPECTED);
+    assert_eq!(
+        *bfint::shared::lock(&output),
+        *bfint::shared::lock(&whole_output)
+    );
+    assert_eq!(stages.tape(), whole.tape());
+    assert_eq!(stages.pointer(), whole.pointer());
+    assert_eq!(stages.steps, whole.steps);
+}
+
+///bfint --shared-tape with the halves in two files
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let directory = std::env::temp_dir().join(format!("bfint-shared-tape-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+    let (first, second) = split(HELLO);
+    for (name, source) in [
+        ("first.b", first),
+        ("second.b", second),
+        ("whole.b", HELLO),
+        ("forever.b", "+[]"),
+        //off the start of the tape from wherever the first half leaves the pointer
+        ("off.b", "<<<<<<<<<<<<<<<<<<<<"),
+    ] {
+        std::fs::write(directory.join(name), source).expect("it can be written");
+    }
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(args)
+            .current_dir(&directory)
+            .output()
+            .expect("bfint starts")
+    };
+    let split = run(&["--shared-tape", "first.b", "second.b"]);
+    let whole = run(&["whole.b"]);
+    let failed = run(&["--shared-tape", "first.b", "off.b"]);
+    let too_long = run(&[
+        "--shared-tape",
+        "first.b",
+        "forever.b",
+        "--stage-max-steps",
+        "1000",
+    ]);
+    let not_shared = run(&["first.b", "second.b"]);
+    let _ = std::fs::remove_dir_all(&directory);
+    assert!(split.status.success());
+    assert_eq!(split.stdout, EXPECTED);
+    assert_eq!(split.stdout, whole.stdout);
+    //the first stage still prints what it prints
+    assert!(!failed.status.success());
+    assert!(EXPECTED.starts_with(&failed.stdout) && !failed.stdout.is_empty());
+    let said = String::from_utf8_lossy(&failed.stderr);
+    assert!(said.starts_with("off.b:1:"), "{}", said);
+    assert!(said.contains("(in stage 2 of 2)"), "{}", said);
+    let said = String::from_utf8_lossy(&too_long.stderr);
+    assert!(
+        said.starts_with("forever.b:1:3: error: stopped after "),
+        "{}",
+        said
+    );
+    assert!(said.contains("(in stage 2 of 2)"), "{}", said);
+    //more than one program is only for --shared-tape
+    assert_eq!(not_shared.status.code(), Some(2));
+}
@@ -0,0 +1,102 @@
+//! programs that start with a "#!" line, so they can be made executable and run as scripts. the
+//! line is passed over before anything else, so its characters aren't instructions, its "!" isn't
+//! the one input comes after and it isn't stray in a strict parse, and everything after it is
+//! still on the line of the file it's on
+use bfint::lang::Lang;
+use bfint::{parse, Instruction, ParseError, ParseOptions, Position};
+
+///a line full of characters that would be instructions anywhere else
+const SHEBANG: &str = "#!/usr/bin/env -S bfint --eof=-1 -O2,[.]\n";
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+#[test]
+fn its_characters_arent_instructions() {
+    let program = parse(&format!("{}+.", SHEBANG), &ParseOptions::default()).expect("it parses");
+    assert_eq!(
+        program.tokens(),
+        [
+            (Instruction::Increment, at(2, 1)),
+            (Instruction::Output, at(2, 2)),
+        ]
+    );
+    //only at the very start
+    let program = parse(&format!("\n{}", SHEBANG), &ParseOptions::default()).expect("it parses");
+    assert!(program.len() > 5);
+}
+
+#[test]
+fn with_the_separator() {
+    let separated = ParseOptions {
+        input_separator: true,
+        ..ParseOptions::default()
+    };
+    let program = parse(&format!("{},.,.!ab", SHEBANG), &separated).expect("it parses");
+    assert_eq!(program.embedded_input(), Some(&b"ab"[..]));
+    assert_eq!(program.len(), 4);
+    //a "#!" line with nothing after it
+    let program = parse("#!bfint", &separated).expect("it parses");
+    assert_eq!(program.embedded_input(), None);
+    assert!(program.is_empty());
+}
+
+#[test]
+fn in_a_strict_parse() {
+    let strict = ParseOptions {
+        strict: true,
+        ..ParseOptions::default()
+    };
+    assert!(parse(&format!("{}+.", SHEBANG), &strict).is_ok());
+    //what comes after it is still checked, on the line it's on in the file
+    assert_eq!(
+        parse(&format!("{}+\n x]", SHEBANG), &strict).expect_err("it has a comment"),
+        [
+            ParseError::StrayCharacter('x', at(3, 2)),
+            ParseError::UnmatchedClose(at(3, 3)),
+        ]
+    );
+    //ook doesn't care about strictness, but skips the line too
+    let ook = ParseOptions {
+        lang: Lang::Ook,
+        ..strict
+    };
+    let program = parse("#!/usr/bin/env bfint\nOok. Ook?", &ook).expect("it's ook");
+    assert_eq!(
+        program.instruction_at(0),
+        Some((Instruction::MoveRight, at(2, 1)))
+    );
+}
+
+#[test]
+fn errors_are_on_the_line_in_the_file() {
+    let error = bfint::TuringMachine::new(&format!("{}+\n[", SHEBANG), &Lang::Brainfuck)
+        .err()
+        .expect("it's unmatched");
+    assert_eq!(error, ParseError::UnmatchedOpen(at(3, 1)));
+}
+
+///run as a script, with bfint as its interpreter
+#[cfg(all(feature = "cli", unix))]
+#[test]
+fn as_a_script() {
+    use std::os::unix::fs::PermissionsExt;
+    let script = std::env::temp_dir().join(format!("bfint-shebang-{}.bf", std::process::id()));
+    let text = format!(
+        "#!{}\n++++++++[>++++++++<-]>+.+.\n",
+        env!("CARGO_BIN_EXE_bfint")
+    );
+    std::fs::write(&script, text).expect("it can be written");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+        .expect("it can be made executable");
+    let output = std::process::Command::new(&script).output();
+    let _ = std::fs::remove_file(&script);
+    let output = output.expect("the script starts");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, b"AB");
+}
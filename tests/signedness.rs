@@ -0,0 +1,216 @@
+//! signed cells around where they wrap, from -128 to 127, and unsigned ones from 0 to 255: what
+//! a byte stands for and which byte stands for a number, what the machine shows and compares in
+//! breakpoint conditions, and the debugger and the repl taking --cell-signedness, --eof and
+//! --config the same way run does
+#![cfg(feature = "std")]
+use bfint::condition::Condition;
+use bfint::config::InterpreterConfig;
+use bfint::Signedness;
+
+#[test]
+fn values_at_the_boundaries() {
+    for (byte, signed, unsigned) in [
+        (0, 0, 0),
+        (1, 1, 1),
+        (127, 127, 127),
+        (128, -128, 128),
+        (129, -127, 129),
+        (255, -1, 255),
+    ] {
+        assert_eq!(Signedness::Signed.value(byte), signed);
+        assert_eq!(Signedness::Unsigned.value(byte), unsigned);
+        assert_eq!(Signedness::Signed.byte(signed.into()), Some(byte));
+        assert_eq!(Signedness::Unsigned.byte(unsigned.into()), Some(byte));
+    }
+    for (signedness, outside) in [
+        (Signedness::Signed, [-129, 128]),
+        (Signedness::Unsigned, [-1, 256]),
+    ] {
+        for value in outside {
+            assert_eq!(signedness.byte(value), None, "{:?} {}", signedness, value);
+        }
+    }
+}
+
+///a machine with signed cells that ran the program
+fn signed(source: &str) -> bfint::TuringMachine {
+    let mut config = InterpreterConfig::default();
+    config
+        .set("cell-signedness", "signed")
+        .expect("it's a signedness");
+    let mut tm = bfint::TuringMachine::with_config(source, &config).expect("it parses");
+    tm.run().expect("it runs");
+    tm
+}
+
+///"+" and "-" wrap the same way either way, it's only what the cells are shown as that changes
+#[test]
+fn wrapping_around() {
+    let tm = signed("-");
+    assert_eq!(tm.describe_cell(0), "cell 0 = -1");
+    assert_eq!(tm.tape()[0], 255);
+    let tm = signed(&"+".repeat(127));
+    assert_eq!(tm.describe_cell(0), "cell 0 = 127");
+    let tm = signed(&"+".repeat(128));
+    assert_eq!(tm.describe_cell(0), "cell 0 = -128");
+    assert_eq!(tm.tape()[0], 128);
+    //and back up again
+    let tm = signed(&format!("{}-", "+".repeat(128)));
+    assert_eq!(tm.describe_cell(0), "cell 0 = 127");
+    let tm = signed(&"+".repeat(256));
+    assert_eq!(tm.describe_cell(0), "cell 0 = 0");
+    let tm = signed(&format!("{}>-<", "+".repeat(128)));
+    assert_eq!(tm.show_cells(0..=1), "[-128] -1");
+}
+
+#[test]
+fn conditions_compare_signed_values() {
+    let holds = |tm: &bfint::TuringMachine, condition: &str| {
+        Condition::parse(condition).expect("it parses").holds(tm)
+    };
+    let tm = signed(&"+".repeat(128));
+    assert!(holds(&tm, "cell + 128 == 0"));
+    assert!(holds(&tm, "cell < 0"));
+    assert!(!holds(&tm, "cell > 127"));
+    let tm = signed(&"+".repeat(127));
+    assert!(holds(&tm, "cell == 127"));
+    assert!(holds(&tm, "cell > 0"));
+    let mut tm = signed("-");
+    assert!(holds(&tm, "cell + 1 == 0"));
+    tm.cell_signedness = Signedness::Unsigned;
+    assert!(holds(&tm, "cell == 255"));
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::{Command, Stdio};
+
+    ///a directory for the files of one test, which goes away with it
+    struct Directory(PathBuf);
+
+    impl Directory {
+        fn new(test: &str) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "bfint-signedness-{}-{}",
+                std::process::id(),
+                test
+            ));
+            std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+            Directory(directory)
+        }
+        ///a file in it holding the text
+        fn file(&self, name: &str, text: &str) -> String {
+            let path = self.0.join(name);
+            std::fs::write(&path, text).expect("the temporary directory can be written to");
+            path.to_str().expect("the path is utf-8").to_string()
+        }
+    }
+
+    impl Drop for Directory {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    ///runs bfint with the arguments and what's piped into it, giving back what it printed
+    fn bfint(args: &[&str], stdin: &str) -> String {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("bfint starts");
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(stdin.as_bytes())
+            .expect("it takes its input");
+        let output = child.wait_with_output().expect("bfint finishes");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).expect("it's utf-8")
+    }
+
+    ///reads the end of input, which is 255 with --eof max and -1 signed, and goes one past 127
+    const PROGRAM: &str = ",>++++++++++++++++[>++++++++<-]>";
+
+    ///the debugger's dump and print after running PROGRAM with the options
+    fn debugged(directory: &Directory, options: &[&str]) -> String {
+        let program = directory.file("program.bf", PROGRAM);
+        let script = directory.file("script", "run\nprint cell[0]\nprint cell[2]\ndump\n");
+        let mut args = vec!["debug"];
+        args.extend(options);
+        args.extend(["--script", &script, &program]);
+        bfint(&args, "")
+    }
+
+    #[test]
+    fn debug_takes_the_settings() {
+        let directory = Directory::new("debug");
+        let shown = debugged(&directory, &["--cell-signedness", "signed", "--eof", "max"]);
+        assert!(shown.contains("print cell[0]\n-1\n"), "{}", shown);
+        assert!(shown.contains("print cell[2]\n-128\n"), "{}", shown);
+        assert!(shown.contains("cells 0: -1 0 [-128]"), "{}", shown);
+        let shown = debugged(&directory, &["--eof", "max"]);
+        assert!(shown.contains("cells 0: 255 0 [128]"), "{}", shown);
+        let shown = debugged(&directory, &[]);
+        assert!(shown.contains("cells 0: 0 0 [128]"), "{}", shown);
+    }
+
+    #[test]
+    fn the_repl_takes_the_settings() {
+        let lines =
+            "-\n:set 0 127\n+\n:tape 0 2\n:set 1 128\n:set 1 -129\n:set 1 -128\n:tape 0 2\n";
+        let shown = bfint(
+            &["repl", "--no-history", "--cell-signedness", "signed"],
+            lines,
+        );
+        assert!(shown.contains("pointer 0, cell -1\n"), "{}", shown);
+        assert!(shown.contains("pointer 0, cell -128\n"), "{}", shown);
+        assert!(shown.contains("0: [-128] 0\n"), "{}", shown);
+        assert_eq!(
+            shown.matches("cells hold -128 to 127").count(),
+            2,
+            "{}",
+            shown
+        );
+        assert!(shown.contains("0: [-128] -128\n"), "{}", shown);
+        let shown = bfint(
+            &["repl", "--no-history"],
+            "-\n:set 0 -1\n:set 0 255\n:tape 0 1\n",
+        );
+        assert!(shown.contains("pointer 0, cell 255\n"), "{}", shown);
+        assert!(shown.contains("cells hold 0 to 255"), "{}", shown);
+        assert!(shown.contains("0: [255]\n"), "{}", shown);
+        //",", with --eof max at the end of what's piped in
+        let shown = bfint(&["repl", "--no-history", "--eof", "max"], ",\n");
+        assert!(shown.contains("pointer 0, cell 255\n"), "{}", shown);
+    }
+
+    ///a profile, with what's given along with it winning over it
+    #[cfg(feature = "serde")]
+    #[test]
+    fn from_a_profile() {
+        let directory = Directory::new("profile");
+        let profile = directory.file(
+            "profile.toml",
+            "cell-signedness = \"signed\"\neof = \"max\"\n",
+        );
+        let shown = debugged(&directory, &["--config", &profile]);
+        assert!(shown.contains("cells 0: -1 0 [-128]"), "{}", shown);
+        let shown = debugged(
+            &directory,
+            &["--config", &profile, "--cell-signedness", "unsigned"],
+        );
+        assert!(shown.contains("cells 0: 255 0 [128]"), "{}", shown);
+        let shown = bfint(&["repl", "--no-history", "--config", &profile], ",\n");
+        assert!(shown.contains("pointer 0, cell -1\n"), "{}", shown);
+    }
+}
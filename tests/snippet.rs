@@ -0,0 +1,145 @@
+//! running a bit of program against a machine without disturbing it: what it printed, read and
+//! changed, the machine left as it was unless it's kept, and a snippet that never ends running
+//! out of its own steps. and the debugger's eval, which rolls back, and eval!, which keeps
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::debugger::{DebugError, Debugger};
+use bfint::lang::Lang;
+use bfint::snapshot::Change;
+use bfint::snippet::SNIPPET_STEPS;
+use bfint::{ParseError, RuntimeError, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+///a machine that's run "++>+++" and is stopped with the pointer at the second cell
+fn machine() -> TuringMachine {
+    let mut tm = TuringMachine::new("++>+++", &Lang::Brainfuck).expect("it parses");
+    tm.output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+    tm.run().expect("it runs");
+    tm
+}
+
+#[test]
+fn rolled_back() {
+    let tm = machine();
+    let snippet = tm
+        .run_snippet("[-<+>]<.,", &Lang::Brainfuck, b"xy", 1_000)
+        .expect("it parses");
+    assert_eq!(snippet.outcome, Ok(RunOutcome::Halted));
+    assert_eq!(snippet.output, [5]);
+    assert_eq!(snippet.read, 1);
+    //the "[", three times around five instructions, and "<.,"
+    assert_eq!(snippet.steps, 1 + 3 * 5 + 3);
+    assert_eq!(snippet.machine.tape()[..2], *b"x\0");
+    assert_eq!(snippet.machine.pointer(), 0);
+    assert_eq!(
+        snippet.diff.changes().collect::<Vec<_>>(),
+        [
+            Change {
+                index: 0,
+                old: 2,
+                new: b'x'
+            },
+            Change {
+                index: 1,
+                old: 3,
+                new: 0
+            },
+        ]
+    );
+    //the machine itself is as it was
+    assert_eq!(tm.tape()[..2], [2, 3]);
+    assert_eq!(tm.pointer(), 1);
+    assert_eq!(tm.steps, 6);
+}
+
+#[test]
+fn kept() {
+    let mut tm = machine();
+    let snippet = tm
+        .run_snippet("[-<+>]<", &Lang::Brainfuck, b"", 1_000)
+        .expect("it parses");
+    tm.keep_snippet(&snippet);
+    assert_eq!(tm.tape()[..2], [5, 0]);
+    assert_eq!(tm.pointer(), 0);
+    //only the tape and the pointer, the program's steps are its own
+    assert_eq!(tm.steps, 6);
+}
+
+///a snippet that never ends runs out of the steps it was given, counted on from the machine's,
+///and one that fails says so in its outcome
+#[test]
+fn out_of_steps() {
+    let tm = machine();
+    let snippet = tm
+        .run_snippet("[]", &Lang::Brainfuck, b"", 50)
+        .expect("it parses");
+    assert_eq!(snippet.outcome, Err(RuntimeError::StepLimitReached(6 + 50)));
+    assert_eq!(snippet.steps, 50);
+    assert!(snippet.diff.is_empty());
+    let snippet = tm
+        .run_snippet("<<", &Lang::Brainfuck, b"", 50)
+        .expect("it parses");
+    assert!(snippet.outcome.is_err());
+    assert!(matches!(
+        tm.run_snippet("[", &Lang::Brainfuck, b"", 50),
+        Err(ParseError::UnmatchedOpen(_))
+    ));
+}
+
+#[test]
+fn eval_rolls_back() {
+    let mut debugger = Debugger::new(machine(), vec![]);
+    assert_eq!(
+        debugger.execute("eval +++").expect("it's a command"),
+        "cell 1: 3 -> 6\n1 cell changed\nran 3 steps and left the pointer at 1, the machine is as \
+         it was\n"
+    );
+    assert_eq!(debugger.tm.tape()[..2], [2, 3]);
+}
+
+#[test]
+fn eval_commits() {
+    let mut debugger = Debugger::new(machine(), b"ab".to_vec());
+    assert_eq!(
+        debugger.execute("eval! +++").expect("it's a command"),
+        "cell 1: 3 -> 6\n1 cell changed\nran 3 steps and left the pointer at 1, which was kept\n"
+    );
+    assert_eq!(debugger.tm.tape()[..2], [2, 6]);
+    //what it reads is gone from the program's input, so the next one reads on from there
+    debugger.execute("eval! ,").expect("it's a command");
+    debugger.execute("eval! <,").expect("it's a command");
+    assert_eq!(debugger.tm.tape()[..2], *b"ba");
+}
+
+///eval! doesn't keep a snippet that didn't halt
+#[test]
+fn eval_not_kept() {
+    let mut debugger = Debugger::new(machine(), vec![]);
+    assert_eq!(
+        debugger.execute("eval! +[]").expect("it's a command"),
+        format!(
+            "cell 1: 3 -> 4\n1 cell changed\ngave up after {} steps, the machine is as it was\n",
+            SNIPPET_STEPS
+        )
+    );
+    let failed = debugger.execute("eval! +<<<").expect("it's a command");
+    assert!(
+        failed.contains("\nerror: ") && failed.ends_with(", the machine is as it was\n"),
+        "{}",
+        failed
+    );
+    assert_eq!(debugger.tm.tape()[..2], [2, 3]);
+    assert_eq!(debugger.tm.pointer(), 1);
+    assert_eq!(
+        debugger.execute("eval"),
+        Err(DebugError::BadArgument(
+            "eval needs some code to run".to_string()
+        ))
+    );
+    assert!(matches!(
+        debugger.execute("eval! ]"),
+        Err(DebugError::BadArgument(_))
+    ));
+}
@@ -0,0 +1,246 @@
+//! specs for "bfint verify": tests/specs/rot13.toml read and run against the program it's for,
+//! the same spec as json, a case that fails every way a case can, what's wrong with a spec that
+//! isn't one, on the line it's wrong on, and the command with its exit codes
+#![cfg(feature = "std")]
+use bfint::fixtures::Outcome;
+use bfint::lang::Lang;
+use bfint::spec::{Case, Spec, SpecError};
+use bfint::TuringMachine;
+
+const ROT13: &str = include_str!("fixtures/rot13.bf");
+const SPEC: &str = include_str!("specs/rot13.toml");
+
+///how every case of the spec went with rot13, on two threads
+fn outcomes(spec: &Spec) -> Vec<Outcome> {
+    let tm = TuringMachine::new(ROT13, &Lang::Brainfuck).expect("it parses");
+    spec.run(tm.program(), 2, |_| {})
+        .into_iter()
+        .map(|(outcome, _)| outcome)
+        .collect()
+}
+
+fn case(name: &str, input: &[u8], expected_output: &[u8], max_steps: Option<u64>) -> Case {
+    Case {
+        name: name.to_string(),
+        input: input.to_vec(),
+        expected_output: expected_output.to_vec(),
+        max_steps,
+    }
+}
+
+#[test]
+fn the_rot13_spec() {
+    let spec = Spec::from_toml(SPEC).expect("it's a spec");
+    assert_eq!(spec.max_steps, Some(1_000_000));
+    assert_eq!(spec.cases.len(), 6);
+    assert_eq!(spec.cases[0], case("nothing", b"", b"", None));
+    assert_eq!(
+        spec.cases[3],
+        case("only letters change", b"1, 2\n", b"1, 2\n", None)
+    );
+    //base64 for the input, and escapes for the output
+    assert_eq!(
+        spec.cases[5],
+        case("other bytes", b"\x7f\x80\xff", b"\x7f\x80\xff", None)
+    );
+    assert!(outcomes(&spec)
+        .iter()
+        .all(|outcome| *outcome == Outcome::Passed));
+}
+
+#[test]
+fn as_json() {
+    let json = r#"{
+        "max_steps": 1000000,
+        "cases": [
+            {"name": "lowercase", "input": "hello", "expected_output": "uryyb"},
+            {"input_base64": "f4D/", "expected_output_base64": "f4D/", "max_steps": 50000},
+            {"input": "é\n", "expected_output": "\xc3\xa9\n"}
+        ]
+    }"#;
+    let spec = Spec::from_json(json).expect("it's a spec");
+    assert_eq!(
+        spec.cases,
+        [
+            case("lowercase", b"hello", b"uryyb", None),
+            case("case 2", b"\x7f\x80\xff", b"\x7f\x80\xff", Some(50000)),
+            case("case 3", "é\n".as_bytes(), "é\n".as_bytes(), None),
+        ]
+    );
+    assert_eq!(
+        outcomes(&spec),
+        [Outcome::Passed, Outcome::Passed, Outcome::Passed]
+    );
+}
+
+///the wrong output is given back, and running out of steps is failing
+#[test]
+fn cases_that_fail() {
+    let spec = Spec::from_toml(
+        "[[cases]]\n\
+         input = \"abc\"\n\
+         expected_output = \"abc\"\n\
+         [[cases]]\n\
+         input = \"hello\"\n\
+         expected_output = \"uryyb\"\n\
+         max_steps = 10\n",
+    )
+    .expect("it's a spec");
+    let outcomes = outcomes(&spec);
+    assert_eq!(outcomes[0], Outcome::WrongOutput(b"nop".to_vec()));
+    assert!(
+        matches!(&outcomes[1], Outcome::Failed(reason) if reason.contains("10")),
+        "{:?}",
+        outcomes[1]
+    );
+}
+
+#[test]
+fn whats_wrong_with_it() {
+    let toml = |text: &str| Spec::from_toml(text).expect_err("it isn't a spec");
+    assert!(matches!(
+        toml("[[cases]]\nexpected_output = \"\"\n[cases]\n"),
+        SpecError::Syntax { line: 3, .. }
+    ));
+    assert!(matches!(
+        toml("[[cases]]\nexpected_output \"\"\n"),
+        SpecError::Syntax { line: 2, .. }
+    ));
+    assert!(matches!(
+        toml("[[cases]]\nexpected_output = \"\" x\n"),
+        SpecError::Syntax { line: 2, .. }
+    ));
+    assert_eq!(
+        toml("# a spec\n\n[[cases]]\nexpected = \"\"\n"),
+        SpecError::UnknownKey {
+            line: 4,
+            key: "expected".to_string()
+        }
+    );
+    assert_eq!(
+        toml("[[cases]]\ninput = \"a\"\ninput = \"b\"\nexpected_output = \"\"\n"),
+        SpecError::DuplicateKey {
+            line: 3,
+            key: "input".to_string()
+        }
+    );
+    assert!(matches!(
+        toml("max_steps = \"lots\"\n[[cases]]\nexpected_output = \"\"\n"),
+        SpecError::BadValue { line: 1, key, .. } if key == "max_steps"
+    ));
+    assert!(matches!(
+        toml("[[cases]]\nexpected_output_base64 = \"!!\"\n"),
+        SpecError::BadValue { line: 2, key, .. } if key == "expected_output_base64"
+    ));
+    //the case starts on the line of its [[cases]]
+    assert!(matches!(
+        toml("[[cases]]\nexpected_output = \"\"\n\n[[cases]]\ninput = \"a\"\n"),
+        SpecError::BadCase {
+            case: 2,
+            line: 4,
+            ..
+        }
+    ));
+    assert!(matches!(
+        toml("[[cases]]\nexpected_output = \"\"\nexpected_output_base64 = \"\"\n"),
+        SpecError::BadCase {
+            case: 1,
+            line: 1,
+            ..
+        }
+    ));
+    assert_eq!(toml("max_steps = 5\n"), SpecError::NoCases);
+    assert_eq!(
+        Spec::from_json(r#"{"cases": []}"#).expect_err("it has no cases"),
+        SpecError::NoCases
+    );
+    assert!(matches!(
+        Spec::from_json("{\n\"cases\": [\n{\"expected_output\": \"\", \"speed\": 1}]}")
+            .expect_err("it isn't a spec"),
+        SpecError::UnknownKey { line: 3, key } if key == "speed"
+    ));
+    assert!(matches!(
+        Spec::from_json("{\"cases\": [").expect_err("it isn't json"),
+        SpecError::Syntax { line: 1, .. }
+    ));
+    assert_eq!(
+        SpecError::UnknownKey {
+            line: 4,
+            key: "expected".to_string()
+        }
+        .to_string(),
+        "line 4: \"expected\" isn't something a spec has"
+    );
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn verify(program: &str, spec: &str) -> (Option<i32>, String, String) {
+        let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["verify", "--jobs", "2", program, spec])
+            .output()
+            .expect("bfint starts");
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    }
+
+    fn repository(path: &str) -> String {
+        format!("{}/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    #[test]
+    fn every_case_passes() {
+        let (code, stdout, stderr) = verify(
+            &repository("tests/fixtures/rot13.bf"),
+            &repository("tests/specs/rot13.toml"),
+        );
+        assert_eq!(code, Some(0), "{}", stderr);
+        assert!(
+            stdout.starts_with("ok   nothing\nok   lowercase\n"),
+            "{}",
+            stdout
+        );
+        assert!(stdout.ends_with("6 passed, 0 failed\n"), "{}", stdout);
+    }
+
+    #[test]
+    fn a_case_fails() {
+        let spec: PathBuf =
+            std::env::temp_dir().join(format!("bfint-spec-{}.json", std::process::id()));
+        std::fs::write(
+            &spec,
+            r#"{"cases": [{"name": "wrong", "input": "ab\ncd", "expected_output": "no\nqp"},
+                          {"name": "right", "input": "ab", "expected_output": "no"}]}"#,
+        )
+        .expect("it can be written");
+        let (code, stdout, _) = verify(
+            &repository("tests/fixtures/rot13.bf"),
+            spec.to_str().expect("the path is utf-8"),
+        );
+        //a spec that isn't one
+        std::fs::write(&spec, "{\"cases\": []}").expect("it can be written");
+        let (bad_code, _, stderr) = verify(
+            &repository("tests/fixtures/rot13.bf"),
+            spec.to_str().expect("the path is utf-8"),
+        );
+        let _ = std::fs::remove_file(&spec);
+        assert_eq!(code, Some(1));
+        assert_eq!(
+            stdout,
+            "FAIL wrong\n     \
+             the output is different:\n     \
+             -\"qp\"\n     \
+             +\"pq\"\n\
+             ok   right\n\
+             1 passed, 1 failed\n"
+        );
+        assert_eq!(bad_code, Some(1));
+        assert!(stderr.contains("there are no cases"), "{}", stderr);
+    }
+}
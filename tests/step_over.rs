@@ -0,0 +1,125 @@
+//! stepping over a loop as one thing and out of the loop the machine is in: it lands just past
+//! the "]" with the cells the whole loop left behind, and a breakpoint, the step limit or an error
+//! inside the loop stops it there instead
+#![cfg(feature = "std")]
+use bfint::debugger::{Debugger, StopReason};
+use bfint::{lang::Lang, Position, RuntimeError, TuringMachine};
+
+///counts ten into the second cell, then prints it
+const COUNTING: &str = "++++++++++[>+<-]>.";
+
+fn debugger(source: &str) -> Debugger {
+    let tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    Debugger::new(tm, vec![])
+}
+
+fn at(line: usize, column: usize) -> Option<Position> {
+    Some(Position { line, column })
+}
+
+#[test]
+fn over_a_counting_loop() {
+    let mut debugger = debugger(COUNTING);
+    assert_eq!(debugger.resume(Some(10)).reason, StopReason::Stepped);
+    assert_eq!(debugger.tm.position(), at(1, 11));
+    let stop = debugger.step_over();
+    assert_eq!(stop.reason, StopReason::Stepped);
+    assert!(stop.output.is_empty());
+    //the ">" just past the "]"
+    assert_eq!(debugger.tm.position(), at(1, 17));
+    assert_eq!(debugger.tm.last_position(), at(1, 16));
+    assert_eq!(debugger.tm.pointer(), 0);
+    assert_eq!(debugger.tm.tape()[..2], [0, 10]);
+    //the "[" once, and ">+<-]" ten times
+    assert_eq!(debugger.tm.steps, 61);
+    //anything but a "[" is one instruction
+    assert_eq!(debugger.step_over().reason, StopReason::Stepped);
+    assert_eq!(debugger.tm.position(), at(1, 18));
+    let stop = debugger.step_over();
+    assert_eq!(stop.output, [10]);
+    assert_eq!(debugger.step_over().reason, StopReason::Halted);
+}
+
+///a loop that's passed over at its "[" lands in the same place
+#[test]
+fn over_a_skipped_loop() {
+    let mut debugger = debugger("[-]>+");
+    assert_eq!(debugger.step_over().reason, StopReason::Stepped);
+    assert_eq!(debugger.tm.position(), at(1, 4));
+    assert_eq!(debugger.tm.steps, 1);
+}
+
+#[test]
+fn a_breakpoint_inside_stops_it() {
+    let mut debugger = debugger(COUNTING);
+    debugger.resume(Some(10));
+    //the "+" in the loop, when it's about to make the second cell 4
+    let id = debugger
+        .add_breakpoint(
+            Position {
+                line: 1,
+                column: 13,
+            },
+            Some("cell[1] == 3"),
+        )
+        .expect("there's an instruction there");
+    assert_eq!(debugger.step_over().reason, StopReason::Breakpoint(id));
+    assert_eq!(debugger.tm.position(), at(1, 13));
+    assert_eq!(debugger.tm.tape()[..2], [7, 3]);
+    //out of the loop from there, which the breakpoint doesn't stop again
+    debugger.tm.remove_breakpoint(id);
+    assert_eq!(debugger.step_out().reason, StopReason::Stepped);
+    assert_eq!(debugger.tm.position(), at(1, 17));
+    assert_eq!(debugger.tm.tape()[..2], [0, 10]);
+}
+
+#[test]
+fn the_step_limit_stops_it() {
+    let mut debugger = debugger(COUNTING);
+    debugger.tm.step_limit = Some(20);
+    debugger.resume(Some(10));
+    assert_eq!(
+        debugger.step_over().reason,
+        StopReason::Error(RuntimeError::StepLimitReached(20))
+    );
+    assert_eq!(debugger.tm.steps, 20);
+    assert_eq!(debugger.tm.tape()[..2], [8, 2]);
+}
+
+///the machine stays at the instruction that failed, inside the loop
+#[test]
+fn an_error_inside_stops_it() {
+    let mut debugger = debugger("+[<]>");
+    debugger.resume(Some(1));
+    let stop = debugger.step_over();
+    assert!(
+        matches!(
+            stop.reason,
+            StopReason::Error(RuntimeError::PointerOutOfBounds(_))
+        ),
+        "{:?}",
+        stop.reason
+    );
+    assert_eq!(debugger.tm.position(), at(1, 3));
+}
+
+///out of the inner loop only, with the outer one still going
+#[test]
+fn out_of_the_innermost_loop() {
+    let mut debugger = debugger("++[>+++[>+<-]<-]>>.");
+    //into the inner loop, at its first ">"
+    debugger.resume(Some(8));
+    assert_eq!(debugger.tm.position(), at(1, 9));
+    assert_eq!(debugger.tm.enclosing_loops().len(), 2);
+    assert_eq!(debugger.step_out().reason, StopReason::Stepped);
+    assert_eq!(debugger.tm.position(), at(1, 14));
+    assert_eq!(debugger.tm.enclosing_loops().len(), 1);
+    assert_eq!(debugger.tm.tape()[..3], [2, 0, 3]);
+    assert_eq!(debugger.step_out().reason, StopReason::Stepped);
+    assert_eq!(debugger.tm.position(), at(1, 17));
+    assert_eq!(debugger.tm.tape()[..3], [0, 0, 6]);
+    //outside of loops it runs to the end
+    let stop = debugger.step_out();
+    assert_eq!(stop.output, [6]);
+    assert_eq!(stop.reason, StopReason::Halted);
+}
@@ -0,0 +1,53 @@
+//! how many steps small programs take, counted by hand: every instruction that runs is a step,
+//! a "[" on 0 jumps past its "]" in one, a "]" on anything else back past its "[" in one, and
+//! the end of the program isn't a step at all. the tiered runner counts the loops it runs in one
+//! go the same way
+#![cfg(feature = "std")]
+use bfint::control::{RunOutcome, StepResult};
+use bfint::{lang::Lang, TuringMachine};
+
+///the program's step count once it's run, stepped through, and run tiered
+fn steps(source: &str) -> [u64; 3] {
+    let machine = || TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    let mut run = machine();
+    assert_eq!(run.run(), Ok(RunOutcome::Halted));
+    let mut stepped = machine();
+    while stepped.step().expect("it steps") == StepResult::Ran {}
+    let mut tiered = machine();
+    tiered.set_tiering(Some(1));
+    assert_eq!(tiered.run(), Ok(RunOutcome::Halted));
+    [run.steps, stepped.steps, tiered.steps]
+}
+
+#[test]
+fn a_loop_on_zero() {
+    assert_eq!(steps("[]"), [1; 3]);
+    //and nothing in it runs
+    assert_eq!(steps("[+++]"), [1; 3]);
+}
+
+///"+" twice, "[" once going in, then "-" and "]" twice around
+#[test]
+fn clearing_a_cell() {
+    assert_eq!(steps("++[-]"), [7; 3]);
+    assert_eq!(steps("+++[-]"), [10; 3]);
+}
+
+///3 "+" and the outer "[", then 3 times around the outer loop: ">", 2 "+", the inner "[", the
+///inner loop twice around, "<", "-" and the outer "]", which is 11
+#[test]
+fn nested_loops() {
+    assert_eq!(steps("+++[>++[-]<-]"), [37; 3]);
+}
+
+///stepping past the end doesn't count, however many times it's done
+#[test]
+fn the_end_is_not_a_step() {
+    assert_eq!(steps(""), [0; 3]);
+    let mut tm = TuringMachine::new("+", &Lang::Brainfuck).expect("it parses");
+    assert_eq!(tm.step(), Ok(StepResult::Ran));
+    for _ in 0..3 {
+        assert_eq!(tm.step(), Ok(StepResult::Halted));
+    }
+    assert_eq!(tm.steps, 1);
+}
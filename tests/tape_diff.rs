@@ -0,0 +1,159 @@
+//! TapeDiff between random tapes, used up to different places: applying it to the old tape makes
+//! the new one, its inverse goes back again, and its runs are every cell that's different and
+//! nothing else. and what it says when it can't be applied, its text and its json
+#![cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use bfint::snapshot::{Change, DiffError, DiffRun, TapeDiff};
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+
+///a tape of a few different values, so that cells next to each other are often the same on both
+///and runs start and stop, with as many 0s as were left at the end of it
+fn tape() -> impl Strategy<Value = Vec<u8>> {
+    let cell = prop_oneof![Just(0u8), Just(1), Just(2), Just(255), any::<u8>()];
+    (prop::collection::vec(cell, 0..40), 0..4usize).prop_map(|(mut cells, zeros)| {
+        cells.extend(vec![0; zeros]);
+        cells
+    })
+}
+
+///the tape made as long as the length with 0s, which is what it holds past its end
+fn padded(tape: &[u8], length: usize) -> Vec<u8> {
+    let mut tape = tape.to_vec();
+    tape.resize(length, 0);
+    tape
+}
+
+proptest! {
+    #[test]
+    fn round_trips(old in tape(), new in tape()) {
+        let length = old.len().max(new.len());
+        let diff = TapeDiff::compute(&old, &new);
+        let mut tape = padded(&old, length);
+        diff.apply(&mut tape).expect("it's the tape it was made from");
+        prop_assert_eq!(&tape, &padded(&new, length));
+        diff.invert().apply(&mut tape).expect("it's the tape it was made to");
+        prop_assert_eq!(&tape, &padded(&old, length));
+        prop_assert_eq!(diff.invert().invert(), diff.clone());
+        prop_assert_eq!(diff.invert(), TapeDiff::compute(&new, &old));
+    }
+
+    #[test]
+    fn every_cell_that_is_different(old in tape(), new in tape()) {
+        let length = old.len().max(new.len());
+        let (before, after) = (padded(&old, length), padded(&new, length));
+        let diff = TapeDiff::compute(&old, &new);
+        let expected: Vec<Change> = (0..length)
+            .filter(|&index| before[index] != after[index])
+            .map(|index| Change { index, old: before[index], new: after[index] })
+            .collect();
+        prop_assert_eq!(diff.changes().collect::<Vec<_>>(), expected.clone());
+        prop_assert_eq!(diff.is_empty(), expected.is_empty());
+        //runs are as long as they can be, with a cell that's the same between them
+        for run in &diff.runs {
+            prop_assert!(!run.old.is_empty());
+            prop_assert_eq!(run.old.len(), run.new.len());
+        }
+        for pair in diff.runs.windows(2) {
+            prop_assert!(pair[0].start + pair[0].old.len() < pair[1].start);
+        }
+    }
+
+    ///the 0s at the end of a tape make no difference, it's all 0 past there anyway
+    #[test]
+    fn used_up_to_different_places(old in tape(), zeros in 0..10usize) {
+        prop_assert!(TapeDiff::compute(&old, &old).is_empty());
+        prop_assert!(TapeDiff::compute(&old, &padded(&old, old.len() + zeros)).is_empty());
+        let mut shorter = old.clone();
+        while shorter.last() == Some(&0) {
+            shorter.pop();
+        }
+        prop_assert!(TapeDiff::compute(&shorter, &old).is_empty());
+    }
+
+    #[test]
+    fn json(old in tape(), new in tape()) {
+        let diff = TapeDiff::compute(&old, &new);
+        let parsed: serde_json::Value =
+            serde_json::from_str(&diff.to_json()).expect("it's json");
+        let runs = parsed["runs"].as_array().expect("it has runs");
+        prop_assert_eq!(runs.len(), diff.runs.len());
+        for (json, run) in runs.iter().zip(&diff.runs) {
+            prop_assert_eq!(json["start"].as_u64(), Some(run.start as u64));
+            prop_assert_eq!(json["old"].to_string(), format!("{:?}", run.old).replace(' ', ""));
+            prop_assert_eq!(json["new"].to_string(), format!("{:?}", run.new).replace(' ', ""));
+        }
+    }
+}
+
+fn run(start: usize, old: &[u8], new: &[u8]) -> DiffRun {
+    DiffRun {
+        start,
+        old: old.to_vec(),
+        new: new.to_vec(),
+    }
+}
+
+#[test]
+fn runs() {
+    assert_eq!(
+        TapeDiff::compute(&[1, 2, 3, 4, 5], &[1, 0, 0, 4, 6, 7]).runs,
+        [run(1, &[2, 3], &[0, 0]), run(4, &[5, 0], &[6, 7])]
+    );
+    assert_eq!(TapeDiff::compute(&[], &[]), TapeDiff::default());
+}
+
+///a tape that isn't the one the diff was made from is left as it was
+#[test]
+fn not_from_this_tape() {
+    let diff = TapeDiff::compute(&[1, 2, 3], &[1, 5, 3, 0, 9]);
+    let mut tape = vec![1, 2, 4, 0, 0];
+    assert_eq!(diff.apply(&mut tape), Ok(()));
+    assert_eq!(tape, [1, 5, 4, 0, 9]);
+    let mut tape = vec![1, 7, 3, 0, 0];
+    assert_eq!(diff.apply(&mut tape), Err(DiffError::Mismatch(1, 2, 7)));
+    assert_eq!(tape, [1, 7, 3, 0, 0]);
+    //cell 1 would have been fine, but it's all checked first
+    let mut tape = vec![1, 2, 3, 0];
+    assert_eq!(diff.apply(&mut tape), Err(DiffError::OffTape(4)));
+    assert_eq!(tape, [1, 2, 3, 0]);
+    assert_eq!(
+        DiffError::Mismatch(1, 2, 7).to_string(),
+        "cell 1 holds 7, the diff was made from a tape where it holds 2"
+    );
+    assert_eq!(
+        DiffError::OffTape(4).to_string(),
+        "the diff changes cell 4, past the end of the tape"
+    );
+}
+
+#[test]
+fn text_and_json() {
+    let diff = TapeDiff::compute(&[0, 0, 0, 0, 1, 2, 3], &[0, 0, 0, 5, 5, 5, 5, 0, 4]);
+    assert_eq!(
+        diff.to_string(),
+        "cells 3-6: 0 1 2 3 -> 5 5 5 5\ncell 8: 0 -> 4\n"
+    );
+    let names = BTreeMap::from([(5, "x".to_string())]);
+    assert_eq!(
+        diff.render(&names),
+        "cells 3-4: 0 1 -> 5 5\nx(5): 2 -> 5\ncell 6: 3 -> 5\ncell 8: 0 -> 4\n"
+    );
+    assert_eq!(
+        diff.to_json(),
+        r#"{"runs":[{"start":3,"old":[0,1,2,3],"new":[5,5,5,5]},{"start":8,"old":[0],"new":[4]}]}"#
+    );
+    assert_eq!(TapeDiff::default().to_json(), r#"{"runs":[]}"#);
+}
+
+///what serde makes of it is the same json
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let diff = TapeDiff::compute(&[9, 0, 1], &[9, 1, 0, 0, 255]);
+    assert_eq!(
+        serde_json::to_string(&diff).expect("it serializes"),
+        diff.to_json()
+    );
+    let back: TapeDiff = serde_json::from_str(&diff.to_json()).expect("it deserializes");
+    assert_eq!(back, diff);
+}
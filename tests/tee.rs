@@ -0,0 +1,233 @@
+//! a Tee sending a program's output to two files and an in-memory comparator at once, each
+//! getting every byte and all of them flushed together, an output that fails naming itself and
+//! stopping the run, and one that's closed stopping it quietly. and --output given twice
+#![cfg(feature = "std")]
+use bfint::control::RunOutcome;
+use bfint::io::{OutputByte, Tee, OUTPUT_CLOSED};
+use bfint::lang::Lang;
+use bfint::{RuntimeError, TuringMachine};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const HELLO: &str = include_str!("fixtures/hello.bf");
+const EXPECTED: &[u8] = include_bytes!("fixtures/hello.out");
+
+///files in the temporary directory, which are removed again at the end of the test
+struct Files(Vec<PathBuf>);
+
+impl Files {
+    fn new(test: &str, names: &[&str]) -> Self {
+        Files(
+            names
+                .iter()
+                .map(|name| {
+                    std::env::temp_dir().join(format!(
+                        "bfint-tee-{}-{}-{}",
+                        std::process::id(),
+                        test,
+                        name
+                    ))
+                })
+                .collect(),
+        )
+    }
+    fn create(&self, index: usize) -> BufWriter<std::fs::File> {
+        BufWriter::new(std::fs::File::create(&self.0[index]).expect("it can be created"))
+    }
+    fn read(&self, index: usize) -> Vec<u8> {
+        std::fs::read(&self.0[index]).expect("it was written")
+    }
+}
+
+impl Drop for Files {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+///compares the output with what it should be as it goes, failing at the first byte that isn't
+struct Comparator {
+    expected: &'static [u8],
+    seen: Arc<Mutex<usize>>,
+}
+
+impl OutputByte for Comparator {
+    fn write_byte(&mut self, byte: u8) -> Result<(), String> {
+        let mut seen = bfint::shared::lock(&self.seen);
+        match self.expected.get(*seen) {
+            Some(expected) if *expected == byte => {
+                *seen += 1;
+                Ok(())
+            }
+            _ => Err(format!("byte {} isn't what was expected", *seen)),
+        }
+    }
+}
+
+///takes the bytes until it's had the room for, like a disk that fills up, or is closed then
+struct Filling {
+    room: usize,
+    error: &'static str,
+}
+
+impl OutputByte for Filling {
+    fn write_byte(&mut self, _: u8) -> Result<(), String> {
+        match self.room {
+            0 => Err(self.error.to_string()),
+            _ => {
+                self.room -= 1;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn machine(source: &str, tee: Tee) -> TuringMachine {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    tm.output = Arc::new(Mutex::new(tee));
+    tm
+}
+
+#[test]
+fn two_files_and_a_comparator() {
+    let files = Files::new("two", &["first.bin", "second.bin"]);
+    let seen = Arc::new(Mutex::new(0));
+    let mut tee = Tee::new();
+    tee.add("first.bin", files.create(0));
+    tee.add_unnamed(Comparator {
+        expected: EXPECTED,
+        seen: Arc::clone(&seen),
+    });
+    tee.add("second.bin", files.create(1));
+    assert_eq!(tee.len(), 3);
+    let mut tm = machine(HELLO, tee);
+    while tm.has_instructions_left() {
+        tm.step().expect("it runs");
+    }
+    assert_eq!(*bfint::shared::lock(&seen), EXPECTED.len());
+    //the files hold on to what they're given until they're flushed, and finishing the run
+    //flushes them all at once
+    assert_eq!(files.read(0), b"");
+    assert_eq!(files.read(1), b"");
+    tm.finish().expect("they're flushed");
+    assert_eq!(files.read(0), EXPECTED);
+    assert_eq!(files.read(1), EXPECTED);
+}
+
+///the comparator failing stops the run there, with its own error, and the file after it never
+///gets the byte
+#[test]
+fn the_comparator_failing() {
+    let files = Files::new("comparator", &["out.bin"]);
+    let mut tee = Tee::new();
+    tee.add_unnamed(Comparator {
+        expected: b"Hello, World!\n",
+        seen: Arc::new(Mutex::new(0)),
+    });
+    tee.add("out.bin", files.create(0));
+    let mut tm = machine(HELLO, tee);
+    assert_eq!(
+        tm.run(),
+        Err(RuntimeError::IoError(
+            "could not write output: byte 5 isn't what was expected".to_string()
+        ))
+    );
+    bfint::shared::lock(&tm.output)
+        .flush_bytes()
+        .expect("they're flushed");
+    assert_eq!(files.read(0), b"Hello");
+}
+
+///a full disk is an error that says which output it was, and flushing still gets every other
+///output's bytes out
+#[test]
+fn a_full_disk() {
+    let files = Files::new("full", &["kept.bin"]);
+    let mut tee = Tee::new();
+    tee.add("kept.bin", files.create(0));
+    tee.add(
+        "disk.bin",
+        Filling {
+            room: 3,
+            error: "No space left on device",
+        },
+    );
+    let mut tm = machine(HELLO, tee);
+    let error = tm.run().expect_err("the disk fills up");
+    assert_eq!(
+        error.to_string(),
+        "could not write output: could not write disk.bin: No space left on device"
+    );
+    bfint::shared::lock(&tm.output)
+        .flush_bytes()
+        .expect("they're flushed");
+    //the fourth byte went to the first output before the second failed on it
+    assert_eq!(files.read(0), b"Hell");
+}
+
+///whatever was reading one of them going away stops the run quietly, like it does with one
+#[test]
+fn closed() {
+    let mut tee = Tee::new();
+    tee.add("-", Vec::new());
+    tee.add(
+        "stdout",
+        Filling {
+            room: 2,
+            error: OUTPUT_CLOSED,
+        },
+    );
+    let mut tm = machine(HELLO, tee);
+    assert_eq!(tm.run(), Ok(RunOutcome::OutputClosed));
+}
+
+///--output twice, and - for stdout among them
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let files = Files::new("cli", &["hello.bf", "a.bin", "b.bin"]);
+    std::fs::write(&files.0[0], HELLO).expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("--output")
+        .arg(&files.0[1])
+        .args(["--output", "-", "--output"])
+        .arg(&files.0[2])
+        .arg(&files.0[0])
+        .output()
+        .expect("bfint starts");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, EXPECTED);
+    assert_eq!(files.read(1), EXPECTED);
+    assert_eq!(files.read(2), EXPECTED);
+    //without -, stdout doesn't get it
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .arg("--output")
+        .arg(&files.0[1])
+        .arg(&files.0[0])
+        .output()
+        .expect("bfint starts");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"");
+    assert_eq!(files.read(1), EXPECTED);
+}
+
+///a disk that's full, named in the error
+#[cfg(all(feature = "cli", target_os = "linux"))]
+#[test]
+fn to_a_full_disk() {
+    use std::process::Command;
+    let files = Files::new("dev-full", &["hello.bf"]);
+    std::fs::write(&files.0[0], HELLO).expect("it can be written");
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--output", "-", "--output", "/dev/full"])
+        .arg(&files.0[0])
+        .output()
+        .expect("bfint starts");
+    assert!(!output.status.success());
+    let said = String::from_utf8_lossy(&output.stderr);
+    assert!(said.contains("could not write /dev/full"), "{}", said);
+}
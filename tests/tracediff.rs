@@ -0,0 +1,206 @@
+//! tracediff on pairs of programs: ones written differently that read and write the same compare
+//! the same, and for ones that don't, the first read or write they part ways on, with where each
+//! of them was, the loops it was in and its cells
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::tracediff::{compare, describe, Comparison, Io, Side};
+use bfint::{Position, TuringMachine};
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+fn machine(source: &str) -> TuringMachine {
+    TuringMachine::new(source, &Lang::Brainfuck).expect("it parses")
+}
+
+fn diff(first: &str, second: &str, input: &[u8]) -> Comparison {
+    compare([machine(first), machine(second)], input, 10_000)
+}
+
+fn sides(comparison: Comparison) -> (usize, [Side; 2]) {
+    match comparison {
+        Comparison::Different { events, sides } => (events, sides),
+        Comparison::Same { .. } => panic!("they compared the same"),
+    }
+}
+
+#[test]
+fn the_same_however_its_written() {
+    //an "A" made in a loop and made by adding one at a time
+    assert_eq!(
+        diff(
+            "++++++++[>++++++++<-]>+.",
+            &format!("{}.", "+".repeat(65)),
+            b""
+        ),
+        Comparison::Same {
+            events: 1,
+            end: Io::Halted
+        }
+    );
+    //reading both bytes into different cells, and printing them in the same order
+    assert_eq!(
+        diff(",.,.", ",.>,.<", b"hi"),
+        Comparison::Same {
+            events: 4,
+            end: Io::Halted
+        }
+    );
+    //reading past the end, which both of them do
+    assert_eq!(
+        diff(",,.", ",>,.", b"x"),
+        Comparison::Same {
+            events: 3,
+            end: Io::Halted
+        }
+    );
+    //failing, wherever it happens
+    assert!(matches!(
+        diff("<", "+>>+[<<]", b""),
+        Comparison::Same {
+            events: 0,
+            end: Io::Failed(_)
+        }
+    ));
+    assert_eq!(
+        diff("+[]", "+[>+<]", b""),
+        Comparison::Same {
+            events: 0,
+            end: Io::OutOfSteps
+        }
+    );
+}
+
+///the second program prints before it reads the second byte
+#[test]
+fn read_or_write() {
+    let (events, [first, second]) = sides(diff(",>,<.>.", ",.>,.", b"hi"));
+    assert_eq!(events, 1);
+    assert_eq!(first.io, Io::Read(Some(b'i')));
+    assert_eq!(first.position, Some(at(1, 3)));
+    assert_eq!(second.io, Io::Output(b'h'));
+    assert_eq!(second.position, Some(at(1, 2)));
+    assert_eq!(second.steps, 2);
+    assert!(first.loops.is_empty() && second.loops.is_empty());
+}
+
+///they part ways inside a loop, the second time round it
+#[test]
+fn in_a_loop() {
+    let comparison = diff("+++[>+.<-]", "+++[>+.+<-]", b"");
+    let (events, [first, second]) = sides(comparison.clone());
+    assert_eq!(events, 1);
+    assert_eq!((first.io, second.io), (Io::Output(2), Io::Output(3)));
+    assert_eq!(first.position, Some(at(1, 7)));
+    assert_eq!(second.position, Some(at(1, 7)));
+    assert_eq!(first.loops, [(at(1, 4), at(1, 10))]);
+    assert_eq!(second.loops, [(at(1, 4), at(1, 11))]);
+    //the second one's "+" runs the first time round
+    assert_eq!((first.steps, second.steps), (13, 14));
+    assert!(
+        first.state.starts_with("at 1:8: pointer 1, "),
+        "{}",
+        first.state
+    );
+    assert!(
+        first.state.ends_with("cells 0: 2 [2] 0 0 0 0 0 0 0 0"),
+        "{}",
+        first.state
+    );
+    assert_eq!(
+        describe(&comparison, ["a.bf", "b.bf"]),
+        format!(
+            "a.bf and b.bf did the same 1 read or write, and then:\n\
+             a.bf wrote 2 at 1:7, after 13 steps\n  in the loops 1:4-1:10\n  {}\n\
+             b.bf wrote 3 at 1:7, after 14 steps\n  in the loops 1:4-1:11\n  {}\n",
+            first.state, second.state
+        )
+    );
+}
+
+///one of them ends and the other carries on
+#[test]
+fn ending() {
+    let (events, [first, second]) = sides(diff("+.", "+.+[]", b""));
+    assert_eq!(events, 1);
+    assert_eq!(first.io, Io::Halted);
+    assert_eq!(first.position, None);
+    assert_eq!(second.io, Io::OutOfSteps);
+    let (_, [first, second]) = sides(diff(".", "<.", b""));
+    assert_eq!(first.io, Io::Output(0));
+    assert!(matches!(second.io, Io::Failed(_)));
+    assert_eq!(second.position, Some(at(1, 1)));
+}
+
+#[cfg(feature = "cli")]
+mod cli {
+    use std::path::PathBuf;
+    use std::process::{Command, Output};
+
+    ///a directory for the programs of one test, which goes away with it
+    struct Directory(PathBuf);
+
+    impl Directory {
+        fn new(test: &str, files: &[(&str, &str)]) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "bfint-tracediff-{}-{}",
+                std::process::id(),
+                test
+            ));
+            std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+            for (name, contents) in files {
+                std::fs::write(directory.join(name), contents).expect("it can be written");
+            }
+            Directory(directory)
+        }
+        fn tracediff(&self, args: &[&str]) -> Output {
+            Command::new(env!("CARGO_BIN_EXE_bfint"))
+                .arg("tracediff")
+                .args(args)
+                .current_dir(&self.0)
+                .output()
+                .expect("bfint starts")
+        }
+    }
+
+    impl Drop for Directory {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn the_same() {
+        let directory = Directory::new(
+            "same",
+            &[("a.bf", ",[.,]"), ("b.bf", ",[.>,]"), ("in", "abc")],
+        );
+        let output = directory.tracediff(&["--input", "in", "a.bf", "b.bf"]);
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "a.bf and b.bf did the same 7 reads and writes and then both halted\n"
+        );
+    }
+
+    #[test]
+    fn different() {
+        let directory = Directory::new(
+            "different",
+            &[("a.bf", ",[.,]"), ("b.bf", ",[+.,]"), ("in", "abc")],
+        );
+        let output = directory.tracediff(&["--input", "in", "a.bf", "b.bf"]);
+        assert_eq!(output.status.code(), Some(1));
+        let said = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = said.lines().collect();
+        assert_eq!(
+            lines[0],
+            "a.bf and b.bf did the same 1 read or write, and then:"
+        );
+        assert_eq!(lines[1], "a.bf wrote 97 'a' at 1:3, after 3 steps");
+        assert_eq!(lines[2], "  in the loops 1:2-1:5");
+        assert_eq!(lines[4], "b.bf wrote 98 'b' at 1:4, after 4 steps");
+        assert_eq!(lines.len(), 7, "{}", said);
+    }
+}
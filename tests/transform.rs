@@ -0,0 +1,142 @@
+//! BfTransform as a piece of a std::io chain: a file put through rot13.bf with io::copy comes out
+//! as rot13.out, a lot of text comes out as rot13 would make it, and two of them one after the
+//! other give back what went in. and programs that halt before reading everything, or fail, or
+//! are written to after their input was closed
+#![cfg(feature = "std")]
+use bfint::lang::Lang;
+use bfint::transform::BfTransform;
+use bfint::TuringMachine;
+use std::io::{self, Read, Write};
+
+const ROT13: &str = include_str!("fixtures/rot13.bf");
+
+fn transform(source: &str) -> BfTransform {
+    BfTransform::new(TuringMachine::new(source, &Lang::Brainfuck).expect("it parses"))
+}
+
+///what rot13 makes of the bytes, done here instead
+fn rotated(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .map(|byte| match byte {
+            b'a'..=b'z' => (byte - b'a' + 13) % 26 + b'a',
+            b'A'..=b'Z' => (byte - b'A' + 13) % 26 + b'A',
+            _ => *byte,
+        })
+        .collect()
+}
+
+#[test]
+fn a_file_through_rot13() {
+    let path = std::env::temp_dir().join(format!("bfint-transform-{}.in", std::process::id()));
+    std::fs::write(&path, include_bytes!("fixtures/rot13.in")).expect("it can be written");
+    let mut file = std::fs::File::open(&path).expect("it can be opened");
+    let mut rot13 = transform(ROT13);
+    let copied = io::copy(&mut file, &mut rot13);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(
+        copied.expect("it's all copied") as usize,
+        include_bytes!("fixtures/rot13.in").len()
+    );
+    rot13.close_input().expect("it runs to the end");
+    assert!(rot13.is_done());
+    let mut output = vec![];
+    rot13.read_to_end(&mut output).expect("it's read");
+    assert_eq!(output, include_bytes!("fixtures/rot13.out"));
+    assert_eq!(rot13.unread(), 0);
+}
+
+///reading closes the input once everything written is read, so io::copy can take it out too
+#[test]
+fn copied_out() {
+    let text: Vec<u8> = (0..2_000u32)
+        .map(|index| b"The quick brown fox jumps over the lazy dog.\n"[index as usize % 45])
+        .collect();
+    let mut rot13 = transform(ROT13);
+    io::copy(&mut &text[..], &mut rot13).expect("it's all copied");
+    //everything written has gone through already, without being read
+    assert_eq!(rot13.available(), text.len());
+    let mut output = vec![];
+    io::copy(&mut rot13, &mut output).expect("it's all copied");
+    assert_eq!(output, rotated(&text));
+}
+
+///rot13 twice over is where it started
+#[test]
+fn one_after_the_other() {
+    let text = b"Hello, World! abcxyz NOPQRS\n".repeat(10);
+    let mut first = transform(ROT13);
+    let mut second = transform(ROT13);
+    io::copy(&mut &text[..], &mut first).expect("it's all copied");
+    io::copy(&mut first, &mut second).expect("it's all copied");
+    let mut output = vec![];
+    second.read_to_end(&mut output).expect("it's read");
+    assert_eq!(output, text);
+}
+
+///written a byte at a time, what's available is what the input so far has made, and reading it
+///doesn't close the input
+#[test]
+fn a_byte_at_a_time() {
+    let mut rot13 = transform(ROT13);
+    let mut output = vec![];
+    for byte in b"Uryyb" {
+        rot13.write_all(&[*byte]).expect("it's written");
+        let mut read = vec![0; rot13.available()];
+        rot13.read_exact(&mut read).expect("it's there");
+        output.extend(read);
+    }
+    assert_eq!(output, b"Hello");
+    rot13.write_all(b"!").expect("the input is still open");
+    assert!(!rot13.is_done());
+}
+
+///a program that only wants the first byte is done after it, and doesn't mind what comes after
+#[test]
+fn halting_first() {
+    let mut first = transform(",.");
+    first.write_all(b"abc").expect("it's written");
+    assert!(first.is_done());
+    assert_eq!(first.unread(), 2);
+    first.write_all(b"def").expect("it's thrown away");
+    assert_eq!(first.unread(), 5);
+    let mut output = vec![];
+    first.read_to_end(&mut output).expect("it's read");
+    assert_eq!(output, b"a");
+}
+
+#[test]
+fn failing() {
+    //prints what it read, then goes off the tape
+    let mut failing = transform(",.<");
+    let error = failing.write_all(b"x").expect_err("it fails");
+    assert!(error.to_string().contains("1:3"), "{}", error);
+    //the output it had written still comes out, and then the error
+    let mut byte = [0];
+    assert_eq!(failing.read(&mut byte).expect("it's there"), 1);
+    assert_eq!(byte, *b"x");
+    assert!(failing.read(&mut byte).is_err());
+    assert!(failing.write_all(b"y").is_err());
+}
+
+///reading what a program that's waiting for input makes closes its input, and writing after that
+///is an error while it's still going
+#[test]
+fn closed() {
+    //reads one byte, and prints one more than it forever
+    let mut forever = transform(",+[.]");
+    let mut output = [0; 4];
+    forever.read_exact(&mut output).expect("it's there");
+    assert_eq!(output, [1; 4]);
+    assert!(!forever.is_done());
+    let error = forever
+        .write_all(b"late")
+        .expect_err("the input was closed");
+    assert_eq!(error.to_string(), "the input was already closed");
+    //once a program's halted, late input is thrown away instead
+    let mut rot13 = transform(ROT13);
+    rot13.write_all(b"a").expect("it's written");
+    rot13.close_input().expect("it runs to the end");
+    assert!(rot13.write_all(b"b").is_ok());
+    assert_eq!(rot13.unread(), 1);
+}
@@ -0,0 +1,65 @@
+//! the web interface as javascript sees it, run in node. it needs the wasm target and
+//! wasm-bindgen-test-runner, from "cargo install wasm-bindgen-cli" at the version of wasm-bindgen
+//! in Cargo.lock:
+//! "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner cargo test
+//! --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm"
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+use bfint::wasm::{run, WasmMachine};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const HELLO: &str =
+    "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.\
+    +++.------.--------.>>+.>++.";
+
+///a field of the object run gives back
+fn field(result: &JsValue, key: &str) -> JsValue {
+    js_sys::Reflect::get(result, &key.into()).expect("the result is an object")
+}
+
+fn output(result: &JsValue) -> Vec<u8> {
+    field(result, "output")
+        .dyn_into::<js_sys::Uint8Array>()
+        .expect("the output is bytes")
+        .to_vec()
+}
+
+#[wasm_bindgen_test]
+fn hello_world() {
+    let result = run(HELLO, &[], 1_000_000);
+    assert_eq!(output(&result), b"Hello World!\n");
+    assert!(field(&result, "error").is_null());
+    assert!(field(&result, "steps")
+        .as_f64()
+        .is_some_and(|steps| steps > 0.0));
+}
+
+///a run that goes past its steps stops there with an error, and keeps what it wrote before
+#[wasm_bindgen_test]
+fn step_limit() {
+    let result = run("+.+[>+<]", &[], 1000);
+    assert_eq!(output(&result), b"\x01");
+    assert_eq!(
+        field(&result, "error").as_string().as_deref(),
+        Some("stopped after running 1000 steps")
+    );
+    assert_eq!(field(&result, "steps").as_f64(), Some(1000.0));
+}
+
+#[wasm_bindgen_test]
+fn parse_error() {
+    let result = run("+[", &[], 1000);
+    assert!(field(&result, "error").as_string().is_some());
+    assert!(WasmMachine::new("]", &[]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn stepping() {
+    let mut machine = WasmMachine::new(",.>++", b"x").expect("it parses");
+    assert!(machine.step(2).expect("it steps"));
+    assert_eq!(machine.take_output(), b"x");
+    assert!(!machine.step(100).expect("it steps"));
+    assert_eq!(machine.pointer(), 1);
+    assert_eq!(machine.tape_window(0, 2), b"x\x02");
+    assert_eq!(machine.steps(), 5.0);
+}
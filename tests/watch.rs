@@ -0,0 +1,171 @@
+//! --watch on a program in a temporary directory, changed underneath it the way an editor would:
+//! a run that hasn't finished is stopped, a few saves in a row make one run, --input is read
+//! again every time, and a program that doesn't parse has its errors shown while it carries on
+//! watching
+#![cfg(all(feature = "cli", unix))]
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+///a directory for the files of one test, which goes away with it
+struct Directory(PathBuf);
+
+impl Directory {
+    fn new(test: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("bfint-watch-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&directory).expect("the temporary directory can be made");
+        Directory(directory)
+    }
+    ///writes the text to the file in it, giving back its path
+    fn write(&self, name: &str, text: &str) -> String {
+        let path = self.0.join(name);
+        std::fs::write(&path, text).expect("the temporary directory can be written to");
+        path.to_str().expect("the path is utf-8").to_string()
+    }
+}
+
+impl Drop for Directory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+///bfint watching, with everything it prints and says collected as it comes. it's killed when
+///it's dropped
+struct Watching {
+    child: Child,
+    stdout: Arc<Mutex<Vec<u8>>>,
+    stderr: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Watching {
+    fn start(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("bfint starts");
+        let collect = |mut from: Box<dyn Read + Send>| {
+            let collected = Arc::new(Mutex::new(vec![]));
+            let to = collected.clone();
+            std::thread::spawn(move || {
+                let mut buffer = [0; 256];
+                while let Ok(read @ 1..) = from.read(&mut buffer) {
+                    to.lock()
+                        .expect("nothing panics holding it")
+                        .extend_from_slice(&buffer[..read]);
+                }
+            });
+            collected
+        };
+        let stdout = collect(Box::new(child.stdout.take().expect("it's piped")));
+        let stderr = collect(Box::new(child.stderr.take().expect("it's piped")));
+        Watching {
+            child,
+            stdout,
+            stderr,
+        }
+    }
+    fn stdout(&self) -> String {
+        String::from_utf8_lossy(&self.stdout.lock().expect("nothing panics holding it")).into()
+    }
+    fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.stderr.lock().expect("nothing panics holding it")).into()
+    }
+    ///waits for what's been printed so far to be the text
+    fn printed(&self, text: &str) {
+        self.wait(|| self.stdout() == text, text);
+    }
+    ///waits for it to have said the text as many times
+    fn said(&self, text: &str, times: usize) {
+        self.wait(|| self.stderr().matches(text).count() == times, text);
+    }
+    fn wait(&self, done: impl Fn() -> bool, what: &str) {
+        let started = Instant::now();
+        while !done() {
+            assert!(
+                started.elapsed() < Duration::from_secs(20),
+                "waited for {:?}, it printed {:?} and said:\n{}",
+                what,
+                self.stdout(),
+                self.stderr()
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+impl Drop for Watching {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+///long enough for a change to be noticed and waited out, see WATCH_INTERVAL in main.rs
+const SETTLE: Duration = Duration::from_millis(600);
+
+const CHANGED: &str = "--- the program changed, running it again ---";
+
+#[test]
+fn reruns_when_the_program_changes() {
+    let directory = Directory::new("reruns");
+    let program = directory.write("program.bf", ",.");
+    let input = directory.write("input", "ab");
+    let watching = Watching::start(&["--watch", &program, "--input", &input]);
+    watching.printed("a");
+    //three saves in a row are one change, and the input is read again for it
+    directory.write("input", "xy");
+    directory.write("program.bf", ",");
+    std::thread::sleep(Duration::from_millis(50));
+    directory.write("program.bf", ",.,");
+    std::thread::sleep(Duration::from_millis(50));
+    directory.write("program.bf", ",.,.");
+    watching.printed("axy");
+    std::thread::sleep(SETTLE);
+    watching.said(CHANGED, 1);
+    assert_eq!(watching.stdout(), "axy");
+}
+
+#[test]
+fn a_run_that_hasnt_finished_is_stopped() {
+    let directory = Directory::new("stopped");
+    let program = directory.write("program.bf", "+[]");
+    let watching = Watching::start(&["--watch", &program]);
+    std::thread::sleep(SETTLE);
+    directory.write("program.bf", "++++++++[>++++++++<-]>+.");
+    watching.printed("A");
+    watching.said(CHANGED, 1);
+}
+
+#[test]
+fn a_program_that_doesnt_parse() {
+    let directory = Directory::new("parse");
+    let program = directory.write("program.bf", "+[");
+    let watching = Watching::start(&["--watch", &program]);
+    watching.said("unmatched '['", 1);
+    directory.write("program.bf", "++++++++[>++++++++<-]>++.");
+    watching.printed("B");
+    directory.write("program.bf", "]");
+    watching.said("unmatched ']'", 1);
+    //still watching
+    directory.write("program.bf", "++++++++[>++++++++<-]>+++.");
+    watching.printed("BC");
+    watching.said(CHANGED, 3);
+}
+
+#[test]
+fn it_needs_a_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bfint"))
+        .args(["--watch", "-"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("bfint starts");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--watch needs a program in a file"));
+}
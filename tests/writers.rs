@@ -0,0 +1,159 @@
+//! who wrote every cell last, through a copy loop and a clear loop, run as written and optimized
+//! into ones that run in one go, which give the same writers since they go around an instruction
+//! at a time while they're kept. and where they're shown: the debugger's "p" and its stops, and
+//! the error bfint --track-writers reports
+#![cfg(feature = "std")]
+use bfint::debugger::Debugger;
+use bfint::lang::Lang;
+use bfint::optimize::Level;
+use bfint::writers::Write;
+use bfint::{Instruction, Position, TuringMachine};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+fn at(line: usize, column: usize) -> Position {
+    Position { line, column }
+}
+
+///copies the first cell, three, to the next with one "+" and twice over to the one after that
+///with two. every time around is nine steps, from step 5 on
+const COPY: &str = "+++[>+>++<<-]";
+
+fn machine(source: &str, level: Level) -> TuringMachine {
+    let mut tm = TuringMachine::new(source, &Lang::Brainfuck).expect("it parses");
+    tm.optimize(&level.pipeline(), |_, _| {});
+    tm.output = Arc::new(Mutex::new(vec![]));
+    tm.input = Arc::new(Mutex::new(Cursor::new(vec![])));
+    tm.track_writers();
+    tm
+}
+
+fn written(step: u64, position: Position, instruction: Instruction) -> Option<Write> {
+    Some(Write {
+        step,
+        position,
+        instruction,
+    })
+}
+
+#[test]
+fn through_a_copy_loop() {
+    for level in Level::ALL {
+        let mut tm = machine(COPY, level);
+        tm.run().expect("it runs");
+        let writers = tm.writers().expect("they're kept");
+        //the last time around starts at step 23
+        assert_eq!(
+            writers.last(0).copied(),
+            written(30, at(1, 12), Instruction::Decrement),
+            "{}",
+            level
+        );
+        assert_eq!(
+            writers.last(1).copied(),
+            written(24, at(1, 6), Instruction::Increment),
+            "{}",
+            level
+        );
+        assert_eq!(
+            writers.last(2).copied(),
+            written(27, at(1, 9), Instruction::Increment),
+            "{}",
+            level
+        );
+        assert_eq!(writers.last(3), None);
+        assert_eq!(writers.len(), 3);
+        assert_eq!(
+            tm.describe_cell(2),
+            "cell 2 = 6 (last written at step 27 by '+' at 1:9)"
+        );
+        assert_eq!(tm.describe_cell(3), "cell 3 = 0 (never written)");
+        assert_eq!(tm.steps, 31);
+    }
+}
+
+///"[-]", which -O2 sets to 0 in one go
+#[test]
+fn through_a_clear_loop() {
+    for level in Level::ALL {
+        let mut tm = machine("+++++[-]>,", level);
+        tm.run().expect("it runs");
+        assert_eq!(
+            tm.describe_cell(0),
+            "cell 0 = 0 (last written at step 15 by '-' at 1:7)",
+            "{}",
+            level
+        );
+        //a "," at the end of input writes the cell, even though it leaves it 0
+        assert_eq!(
+            tm.describe_cell(1),
+            "cell 1 = 0 (last written at step 18 by ',' at 1:10)",
+            "{}",
+            level
+        );
+    }
+}
+
+///a machine that isn't keeping track says nothing about it
+#[test]
+fn not_kept() {
+    let mut tm = TuringMachine::new(COPY, &Lang::Brainfuck).expect("it parses");
+    tm.run().expect("it runs");
+    assert_eq!(tm.writers(), None);
+    assert_eq!(tm.describe_cell(1), "cell 1 = 3");
+}
+
+///"p" on a cell says who wrote it, and stopping says who wrote the current one
+#[test]
+fn in_the_debugger() {
+    let mut debugger = Debugger::new(machine(COPY, Level::O0), vec![]);
+    debugger.execute("b 1:12").expect("it's a place");
+    let stopped = debugger.execute("r").expect("it's a command");
+    assert!(
+        stopped.ends_with("cell 0 = 3 (last written at step 3 by '+' at 1:3)\n"),
+        "{}",
+        stopped
+    );
+    assert_eq!(
+        debugger.execute("p").expect("it's a command"),
+        "cell 0 = 3 (last written at step 3 by '+' at 1:3)\n"
+    );
+    assert_eq!(
+        debugger.execute("p cell[2]").expect("it's a cell"),
+        "cell 2 = 2 (last written at step 9 by '+' at 1:9)\n"
+    );
+    assert_eq!(
+        debugger.execute("p cell[5]").expect("it's a cell"),
+        "cell 5 = 0 (never written)\n"
+    );
+    //the pointer isn't a cell, and has no writer
+    assert_eq!(debugger.execute("p ptr").expect("it's a value"), "0\n");
+}
+
+///an error says who wrote the cell it happened at. the loop takes the first cell down to 0 for
+///the last time at step 12, and the "<" after it goes off the tape
+#[cfg(feature = "cli")]
+#[test]
+fn from_bfint() {
+    use std::process::Command;
+    let path = std::env::temp_dir().join(format!("bfint-writers-{}.b", std::process::id()));
+    std::fs::write(&path, "++[>+<-]<").expect("it can be written");
+    let run = |level: &str| {
+        Command::new(env!("CARGO_BIN_EXE_bfint"))
+            .args(["--track-writers", level])
+            .arg(&path)
+            .output()
+            .expect("bfint starts")
+    };
+    let outputs = [run("-O0"), run("-O2")];
+    let _ = std::fs::remove_file(&path);
+    for output in outputs {
+        assert!(!output.status.success());
+        let said = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            said.contains("cell 0 = 0 (last written at step 12 by '-' at 1:7)\n"),
+            "{}",
+            said
+        );
+    }
+}